@@ -0,0 +1,38 @@
+/*
+ * C interface to the hashline-tools edit engine.
+ *
+ * Build with `cargo build --release --features ffi`; the shared library is
+ * written to target/release (libhashline_tools.so / .dylib / hashline_tools.dll).
+ *
+ * All strings are NUL-terminated UTF-8. Functions returning `char *` return
+ * JSON owned by the library, to be released with hashline_free(), or NULL on
+ * failure; hashline_last_error() then describes the failure as a JSON object
+ * with "error" and "message" fields (plus "mismatches" for stale anchors).
+ */
+#ifndef HASHLINE_H
+#define HASHLINE_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* {"file", "total_lines", "total_bytes", "lines": [{"anchor": "1#AB", "byte_offset": 0, "text": "..."}]} */
+char *hashline_read(const char *path);
+
+/*
+ * Apply a JSON array of edits to `content` in memory.
+ * `options_json` is a JSON object of apply options, or NULL for defaults.
+ * {"content", "first_changed_line", "relocations", "statuses", "bypassed"}
+ */
+char *hashline_apply_edits(const char *content, const char *edits_json, const char *options_json);
+
+/* Error of the last failed call on this thread; valid until the next call. Do not free. */
+const char *hashline_last_error(void);
+
+void hashline_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* HASHLINE_H */
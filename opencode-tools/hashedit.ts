@@ -80,9 +80,9 @@ function validateLines(lines: unknown, field: string): string[] {
   return lines as string[];
 }
 
-type ReplaceOp = { op: "replace"; pos: HashAnchor; end?: HashAnchor; lines: string[] };
-type AppendOp  = { op: "append";  pos: PosAnchor; lines: string[] };
-type PrependOp = { op: "prepend"; pos: PosAnchor; lines: string[] };
+type ReplaceOp = { op: "replace"; pos: HashAnchor; end?: HashAnchor; lines: string[]; expected_lines?: string[] };
+type AppendOp  = { op: "append";  pos: PosAnchor; lines: string[]; expected_lines?: string[] };
+type PrependOp = { op: "prepend"; pos: PosAnchor; lines: string[]; expected_lines?: string[] };
 type DeleteOp  = { op: "delete";  pos: HashAnchor; end?: HashAnchor };
 type WriteOp   = { op: "write";   content: string };
 type EditOp    = ReplaceOp | AppendOp | PrependOp | DeleteOp | WriteOp;
@@ -91,8 +91,13 @@ type InternalOp = {
   pos?: string;
   end?: string;
   lines: string[];
+  expected_lines?: string[];
 };
 
+function optionalLines(value: unknown, field: string): string[] | undefined {
+  return value === undefined ? undefined : validateLines(value, field);
+}
+
 function validateOp(raw: unknown, index: number): EditOp {
   const ctx = `edits[${index}]`;
   if (!raw || typeof raw !== "object") throw new Error(`${ctx}: must be an object`);
@@ -116,17 +121,20 @@ function validateOp(raw: unknown, index: number): EditOp {
         end = validateAnchor(rawObj.end, `${ctx}.end`, { allowEof: false, allowBof: false });
       }
       const lines = validateLines(rawObj.lines, `${ctx}.lines`);
-      return { op: "replace", pos, end, lines };
+      const expected_lines = optionalLines(rawObj.expected_lines, `${ctx}.expected_lines`);
+      return { op: "replace", pos, end, lines, expected_lines };
     }
     case "append": {
       const pos = validateAnchor(rawObj.pos, `${ctx}.pos`, { allowEof: true, allowBof: false });
       const lines = validateLines(rawObj.lines, `${ctx}.lines`);
-      return { op: "append", pos, lines };
+      const expected_lines = optionalLines(rawObj.expected_lines, `${ctx}.expected_lines`);
+      return { op: "append", pos, lines, expected_lines };
     }
     case "prepend": {
       const pos = validateAnchor(rawObj.pos, `${ctx}.pos`, { allowEof: false, allowBof: true });
       const lines = validateLines(rawObj.lines, `${ctx}.lines`);
-      return { op: "prepend", pos, lines };
+      const expected_lines = optionalLines(rawObj.expected_lines, `${ctx}.expected_lines`);
+      return { op: "prepend", pos, lines, expected_lines };
     }
     case "delete": {
       const pos = validateAnchor(rawObj.pos, `${ctx}.pos`, { allowEof: false, allowBof: false });
@@ -144,15 +152,18 @@ function validateOp(raw: unknown, index: number): EditOp {
 function toInternalOp(edit: Exclude<EditOp, WriteOp>): InternalOp {
   switch (edit.op) {
     case "replace":
-      return { op: "replace", pos: edit.pos, ...(edit.end ? { end: edit.end } : {}), lines: edit.lines };
+      return {
+        op: "replace", pos: edit.pos, ...(edit.end ? { end: edit.end } : {}), lines: edit.lines,
+        ...(edit.expected_lines ? { expected_lines: edit.expected_lines } : {}),
+      };
     case "append":
       return edit.pos === "EOF"
         ? { op: "append", lines: edit.lines }
-        : { op: "append", pos: edit.pos, lines: edit.lines };
+        : { op: "append", pos: edit.pos, lines: edit.lines, ...(edit.expected_lines ? { expected_lines: edit.expected_lines } : {}) };
     case "prepend":
       return edit.pos === "BOF"
         ? { op: "prepend", lines: edit.lines }
-        : { op: "prepend", pos: edit.pos, lines: edit.lines };
+        : { op: "prepend", pos: edit.pos, lines: edit.lines, ...(edit.expected_lines ? { expected_lines: edit.expected_lines } : {}) };
     case "delete":
       return { op: "replace", pos: edit.pos, ...(edit.end ? { end: edit.end } : {}), lines: [] };
   }
@@ -193,6 +204,9 @@ Rules:
         lines: tool.schema
           .optional(tool.schema.array(tool.schema.string()))
           .describe('Lines to insert or replace. Required for "replace", "append", "prepend".'),
+        expected_lines: tool.schema
+          .optional(tool.schema.array(tool.schema.string()))
+          .describe('Optional current content at pos (the whole pos..end range for "replace"). Verified before editing; lets the edit survive a stale hash if the content is unchanged.'),
         content: tool.schema
           .optional(tool.schema.string())
           .describe('Full file content. Required for "write" only.'),
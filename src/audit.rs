@@ -0,0 +1,136 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{content_fingerprint, render_error, BypassedCheck, EditOptions, OutputFormat};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Audit Log
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Environment variable naming the audit log when `--audit-log` is not given.
+pub const AUDIT_LOG_ENV_VAR: &str = "HASHLINE_AUDIT_LOG";
+
+/// The audit log to use: `flag` if given, else the one `HASHLINE_AUDIT_LOG`
+/// names.
+pub fn audit_log_path(flag: Option<PathBuf>) -> Option<PathBuf> {
+    flag.or_else(|| std::env::var_os(AUDIT_LOG_ENV_VAR).filter(|v| !v.is_empty()).map(PathBuf::from))
+}
+
+/// Append `record` to the audit log at `log_path` as one JSON line, creating
+/// the log if needed. The line is written with a single append, so records
+/// from concurrent runs never interleave.
+fn append_record(log_path: &Path, mut record: serde_json::Value) -> std::io::Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    record["timestamp"] = timestamp.into();
+    let mut line = record.to_string();
+    line.push('\n');
+    let mut log = OpenOptions::new().create(true).append(true).open(log_path)?;
+    log.write_all(line.as_bytes())
+}
+
+/// Append one JSON line describing a forced edit of `file` to the audit log
+/// at `log_path`, creating the log if needed.
+pub fn append_audit_record(log_path: &Path, file: &str, bypassed: &[BypassedCheck]) -> std::io::Result<()> {
+    append_record(log_path, serde_json::json!({
+        "event": "force",
+        "file": file,
+        "bypassed": bypassed,
+    }))
+}
+
+/// The audit log to record a forced run in. Forcing without an audit log is
+/// refused so that every bypassed check leaves a trace.
+pub(crate) fn forced_audit_log(options: &EditOptions) -> Result<Option<&Path>, String> {
+    if !options.apply.force {
+        return Ok(None);
+    }
+    match &options.audit_log {
+        Some(path) => Ok(Some(path.as_path())),
+        None => Err(render_error(
+            options.format,
+            "audit_log_required",
+            "--force requires --audit-log so that bypassed checks are recorded".to_string(),
+        )),
+    }
+}
+
+/// Record a forced run before its changes are written.
+pub(crate) fn audit_forced_edit(
+    log_path: Option<&Path>,
+    file: &str,
+    bypassed: &[BypassedCheck],
+    options: &EditOptions,
+) -> Result<(), String> {
+    let Some(log_path) = log_path else {
+        return Ok(());
+    };
+    append_audit_record(log_path, file, bypassed).map_err(|e| render_error(
+        options.format,
+        "io",
+        format!("Failed to write audit log {}: {}; no changes were made", log_path.display(), e),
+    ))
+}
+
+/// One command run, recorded once its result is known.
+pub(crate) struct AuditedCommand<'a> {
+    /// `read`, `search`, `edit`, `plan`, `commit` or `multi_edit`.
+    pub event: &'a str,
+    pub files: Vec<&'a str>,
+    /// The edit payload or plan token, recorded as its SHA-256.
+    pub payload: Option<&'a str>,
+}
+
+impl AuditedCommand<'_> {
+    /// Record the command and its `result` in the audit log, if there is
+    /// one, passing the result through.
+    pub(crate) fn audit<T>(self, log_path: Option<&Path>, format: OutputFormat, result: Result<T, String>) -> Result<T, String> {
+        let mut record = serde_json::json!({
+            "event": self.event,
+            "files": self.files,
+            "status": if result.is_ok() { "ok" } else { "error" },
+        });
+        if let Some(payload) = self.payload {
+            record["payload_sha256"] = content_fingerprint(payload).into();
+        }
+        if let Err(rendered) = &result {
+            let (kind, message) = error_summary(rendered);
+            record["error"] = kind.into();
+            record["message"] = message.into();
+        }
+        let Some(log_path) = log_path else {
+            return result;
+        };
+        match append_record(log_path, record) {
+            Ok(()) => result,
+            Err(e) => {
+                let outcome = if result.is_ok() { "the command itself succeeded" } else { "the command failed too" };
+                let message = format!("Failed to write audit log {}: {}; {}", log_path.display(), e, outcome);
+                Err(render_error(format, "io", message))
+            }
+        }
+    }
+}
+
+/// Append `record` to the audit log, if there is one.
+pub(crate) fn audit_event(log_path: Option<&Path>, record: serde_json::Value) -> Result<(), String> {
+    match log_path {
+        Some(log_path) => append_record(log_path, record)
+            .map_err(|e| format!("Failed to write audit log {}: {}", log_path.display(), e)),
+        None => Ok(()),
+    }
+}
+
+/// The kind and first line of a rendered command error. Text errors carry
+/// no kind, so theirs is `error`.
+fn error_summary(rendered: &str) -> (String, String) {
+    let json: Option<serde_json::Value> = serde_json::from_str(rendered).ok();
+    let field = |name: &str| json.as_ref().and_then(|v| v[name].as_str()).map(str::to_string);
+    let kind = field("error").unwrap_or_else(|| "error".to_string());
+    let message = field("message").unwrap_or_else(|| rendered.to_string());
+    (kind, message.lines().next().unwrap_or_default().to_string())
+}
@@ -0,0 +1,275 @@
+use std::collections::BTreeSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::patch_series::civil_date;
+use crate::{content_fingerprint, render_error, write_atomic, EditOptions, HashlineError, Operation, OutputFormat, Policy};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Backup Store
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Where `--backup` without a value keeps its store, relative to the
+/// working directory.
+pub const DEFAULT_BACKUP_DIR: &str = ".hashline/backups";
+
+/// The list of recorded versions inside a backup store, one JSON line each.
+const BACKUP_INDEX_FILE: &str = "index.jsonl";
+
+/// The file locked while a backup store is changed. The index itself is
+/// replaced on a prune, so it cannot carry the lock.
+const BACKUP_LOCK_FILE: &str = "lock";
+
+/// The fewest leading characters of a fingerprint that name a version, as
+/// with git's abbreviated object names.
+pub const MIN_BACKUP_VERSION_PREFIX: usize = 4;
+
+/// One pre-edit version of a file in the backup store.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupEntry {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// Absolute path of the edited file.
+    pub path: PathBuf,
+    /// SHA-256 of the content, which is also the name it is stored under.
+    pub fingerprint: String,
+    pub bytes: u64,
+}
+
+/// What [`prune_backups`] removed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PrunedBackups {
+    /// Versions dropped from the index.
+    pub entries: usize,
+    /// Stored contents no remaining version refers to, now deleted.
+    pub objects: usize,
+    pub bytes: u64,
+}
+
+fn object_path(dir: &Path, fingerprint: &str) -> PathBuf {
+    dir.join("objects").join(fingerprint)
+}
+
+/// Lock the store in `dir` until the returned file is dropped. Recording
+/// and pruning both hold it, so a prune neither drops versions recorded
+/// while it rewrites the index nor deletes contents they have just reused.
+fn lock_store(dir: &Path) -> io::Result<File> {
+    let lock = OpenOptions::new().create(true).truncate(false).write(true).open(dir.join(BACKUP_LOCK_FILE))?;
+    lock.lock()?;
+    Ok(lock)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Save the content each `(path, content)` had before a write. A content
+/// already in the store is kept once however many versions share it.
+pub fn record_backup(dir: &Path, files: &[(&Path, &str)]) -> io::Result<Vec<BackupEntry>> {
+    fs::create_dir_all(dir.join("objects"))?;
+    let _lock = lock_store(dir)?;
+    let timestamp = now();
+    let mut entries = Vec::new();
+    let mut lines = String::new();
+    for (path, content) in files {
+        let fingerprint = content_fingerprint(content);
+        let object = object_path(dir, &fingerprint);
+        if !object.exists() {
+            // Written under a temporary name so a crash never leaves a
+            // truncated object behind the name of a whole one
+            let partial = object.with_extension("partial");
+            fs::write(&partial, content)?;
+            fs::rename(&partial, &object)?;
+        }
+        let entry = BackupEntry { timestamp, path: std::path::absolute(path)?, fingerprint, bytes: content.len() as u64 };
+        lines.push_str(&serde_json::to_string(&entry).map_err(io::Error::other)?);
+        lines.push('\n');
+        entries.push(entry);
+    }
+    OpenOptions::new().create(true).append(true).open(dir.join(BACKUP_INDEX_FILE))?.write_all(lines.as_bytes())?;
+    Ok(entries)
+}
+
+/// The versions in the store, oldest first, only those of `file` when given.
+/// A store that does not exist yet has none.
+pub fn list_backups(dir: &Path, file: Option<&Path>) -> Result<Vec<BackupEntry>, String> {
+    let index = dir.join(BACKUP_INDEX_FILE);
+    let text = match fs::read_to_string(&index) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", index.display(), e)),
+    };
+    let file = file.map(std::path::absolute).transpose()
+        .map_err(|e| format!("Failed to resolve path: {}", e))?;
+    let mut entries = Vec::new();
+    for (i, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let entry: BackupEntry = serde_json::from_str(line)
+            .map_err(|e| format!("Corrupt backup index {} at line {}: {}", index.display(), i + 1, e))?;
+        if file.as_ref().is_none_or(|file| *file == entry.path) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Put `file` back to a stored version: the one whose fingerprint starts
+/// with `version`, or the latest when `None`. The content it had is backed
+/// up first, so a restore can be restored away. `version` must have at
+/// least [`MIN_BACKUP_VERSION_PREFIX`] characters, and `policy` must allow
+/// writing `file`.
+pub fn restore_backup(dir: &Path, file: &Path, version: Option<&str>, policy: &Policy) -> Result<BackupEntry, HashlineError> {
+    policy.check(Operation::Write, file, false)?;
+    restore_version(dir, file, version).map_err(HashlineError::BackupRestore)
+}
+
+fn restore_version(dir: &Path, file: &Path, version: Option<&str>) -> Result<BackupEntry, String> {
+    if let Some(version) = version.filter(|version| version.len() < MIN_BACKUP_VERSION_PREFIX) {
+        return Err(format!(
+            "Backup version '{}' is too short; give at least {} characters of its fingerprint",
+            version, MIN_BACKUP_VERSION_PREFIX
+        ));
+    }
+    let entries = list_backups(dir, Some(file))?;
+    let entry = match version {
+        None => entries.last().ok_or_else(|| format!("No backups of {}", file.display()))?,
+        Some(version) => {
+            let matching: BTreeSet<&str> = entries.iter()
+                .map(|e| e.fingerprint.as_str())
+                .filter(|fingerprint| fingerprint.starts_with(version))
+                .collect();
+            if matching.len() > 1 {
+                return Err(format!("{} matches more than one backup of {}; give more of it", version, file.display()));
+            }
+            entries.iter().rev()
+                .find(|e| e.fingerprint.starts_with(version))
+                .ok_or_else(|| format!("No backup of {} starts with {}", file.display(), version))?
+        }
+    };
+    let object = object_path(dir, &entry.fingerprint);
+    let content = fs::read_to_string(&object)
+        .map_err(|e| format!("Failed to read backup {}: {}", object.display(), e))?;
+    if let Ok(current) = fs::read_to_string(file) {
+        if current == content {
+            return Ok(entry.clone());
+        }
+        record_backup(dir, &[(file, &current)]).map_err(|e| format!("Failed to back up {}: {}", file.display(), e))?;
+    }
+    write_atomic(file, &content).map_err(|e| format!("Failed to restore {}: {}", file.display(), e))?;
+    Ok(entry.clone())
+}
+
+/// Drop the versions recorded before `cutoff`, then delete the stored
+/// contents no remaining version refers to.
+pub fn prune_backups(dir: &Path, cutoff: SystemTime) -> Result<PrunedBackups, String> {
+    let cutoff = cutoff.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let _lock = match lock_store(dir) {
+        Ok(lock) => lock,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(PrunedBackups::default()),
+        Err(e) => return Err(format!("Failed to lock {}: {}", dir.display(), e)),
+    };
+    let entries = list_backups(dir, None)?;
+    let (kept, dropped): (Vec<_>, Vec<_>) = entries.into_iter().partition(|e| e.timestamp >= cutoff);
+    let mut pruned = PrunedBackups { entries: dropped.len(), ..PrunedBackups::default() };
+    if dropped.is_empty() {
+        return Ok(pruned);
+    }
+
+    let index = dir.join(BACKUP_INDEX_FILE);
+    let mut text = String::new();
+    for entry in &kept {
+        text.push_str(&serde_json::to_string(entry).expect("backup entry serializes"));
+        text.push('\n');
+    }
+    let partial = index.with_extension("partial");
+    fs::write(&partial, text)
+        .and_then(|_| fs::rename(&partial, &index))
+        .map_err(|e| format!("Failed to rewrite {}: {}", index.display(), e))?;
+
+    let referenced: BTreeSet<&str> = kept.iter().map(|e| e.fingerprint.as_str()).collect();
+    let unreferenced: BTreeSet<&str> = dropped.iter().map(|e| e.fingerprint.as_str()).collect();
+    for fingerprint in unreferenced.difference(&referenced) {
+        let object = object_path(dir, fingerprint);
+        let bytes = fs::metadata(&object).map(|m| m.len()).unwrap_or(0);
+        if fs::remove_file(&object).is_ok() {
+            pruned.objects += 1;
+            pruned.bytes += bytes;
+        }
+    }
+    Ok(pruned)
+}
+
+/// `timestamp` as a UTC date and time.
+fn format_timestamp(timestamp: u64) -> String {
+    let (year, month, day) = civil_date(timestamp / 86_400);
+    let seconds = timestamp % 86_400;
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC", year, month, day, seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+/// List the stored versions, of `file` or of every file.
+pub fn cmd_backups_list(dir: &Path, file: Option<&Path>, format: OutputFormat) -> Result<String, String> {
+    let entries = list_backups(dir, file).map_err(|e| render_error(format, "io", e))?;
+    if format == OutputFormat::Json {
+        return Ok(serde_json::json!({ "status": "ok", "backups": entries }).to_string());
+    }
+    if entries.is_empty() {
+        return Ok(format!("No backups in {}", dir.display()));
+    }
+    let mut output = format!("{} backup{} in {}:", entries.len(), if entries.len() == 1 { "" } else { "s" }, dir.display());
+    for entry in entries.iter().rev() {
+        output.push_str(&format!(
+            "\n  {}  {}  {} ({} bytes)",
+            &entry.fingerprint[..12.min(entry.fingerprint.len())],
+            format_timestamp(entry.timestamp),
+            entry.path.display(),
+            entry.bytes,
+        ));
+    }
+    Ok(output)
+}
+
+/// Restore `file` from the store and report the version it got.
+pub fn cmd_backups_restore(dir: &Path, file: &Path, version: Option<&str>, policy: &Policy, format: OutputFormat) -> Result<String, String> {
+    let entry = restore_backup(dir, file, version, policy).map_err(|e| render_error(format, e.kind(), e.to_string()))?;
+    Ok(match format {
+        OutputFormat::Json => serde_json::json!({ "status": "restored", "backup": entry }).to_string(),
+        OutputFormat::Text => format!(
+            "Restored {} to the version backed up at {} ({})",
+            file.display(), format_timestamp(entry.timestamp), &entry.fingerprint[..12.min(entry.fingerprint.len())],
+        ),
+    })
+}
+
+/// Remove the versions older than `keep_days` days.
+pub fn cmd_backups_prune(dir: &Path, keep_days: u64, format: OutputFormat) -> Result<String, String> {
+    let cutoff = SystemTime::now()
+        .checked_sub(Duration::from_secs(keep_days.saturating_mul(86_400)))
+        .unwrap_or(UNIX_EPOCH);
+    let pruned = prune_backups(dir, cutoff).map_err(|e| render_error(format, "io", e))?;
+    Ok(match format {
+        OutputFormat::Json => serde_json::json!({ "status": "pruned", "pruned": pruned }).to_string(),
+        OutputFormat::Text => format!(
+            "Pruned {} backup{} older than {} day{}, freeing {} bytes",
+            pruned.entries, if pruned.entries == 1 { "" } else { "s" },
+            keep_days, if keep_days == 1 { "" } else { "s" },
+            pruned.bytes,
+        ),
+    })
+}
+
+impl EditOptions {
+    /// Save the pre-edit content of each `(path, old_content, new_content)`
+    /// about to be written, if a backup store is configured.
+    pub(crate) fn record_backup(&self, files: &[(&Path, &str, &str)]) -> Result<(), String> {
+        let Some(dir) = &self.backup_dir else {
+            return Ok(());
+        };
+        let files: Vec<(&Path, &str)> = files.iter().map(|(path, old, _)| (*path, *old)).collect();
+        record_backup(dir, &files)
+            .map(|_| ())
+            .map_err(|e| render_error(self.format, "io", format!("Failed to record backup: {}", e)))
+    }
+}
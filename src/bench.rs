@@ -0,0 +1,184 @@
+use std::fs;
+use std::io;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::{
+    apply_and_diff_hashed_file, cmd_read_to, hashes_in_parallel, render_error, validate_hashed_file, EditOptions,
+    HashedFile, HashlineEdit, LineHash, OutputFormat,
+};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Benchmark
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Timings of one phase of [`run_bench`] over all iterations.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PhaseTiming {
+    /// `hash`, `read`, `validate` or `apply`.
+    pub phase: &'static str,
+    pub total_ms: f64,
+    pub mean_ms: f64,
+    pub min_ms: f64,
+    /// File lines processed per second, at the mean time.
+    pub lines_per_sec: f64,
+    /// File megabytes (10^6 bytes) processed per second, at the mean time.
+    pub mb_per_sec: f64,
+}
+
+/// What `bench` measured on one file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BenchReport {
+    pub file: String,
+    pub lines: usize,
+    pub bytes: usize,
+    pub iterations: usize,
+    /// Edits in the synthetic batch validated and applied each iteration.
+    pub edits: usize,
+    pub hash_cache: bool,
+    /// Whether the file is hashed in parallel: built with the `rayon`
+    /// feature, at least `PARALLEL_HASH_MIN_LINES` long, and more than one
+    /// thread in the pool. The hash cache hashes on one thread regardless.
+    pub parallel_hashing: bool,
+    pub phases: Vec<PhaseTiming>,
+}
+
+/// Time each phase of working on `file_path` `iterations` times: loading and
+/// hashing it (through `options.hash_cache` when set), reading it whole,
+/// validating a synthetic batch of `edit_count` single-line replacements, and
+/// applying that batch in memory with its diff. Nothing is written.
+pub fn run_bench(
+    file_path: &str,
+    iterations: usize,
+    edit_count: usize,
+    options: &EditOptions,
+) -> io::Result<BenchReport> {
+    let iterations = iterations.max(1);
+    let (content, hashes) = load(file_path, options)?;
+    let file = hashed_file(&content, hashes);
+    let edits = synthetic_edits(&file, edit_count);
+    let lines = file.len();
+    let bytes = content.len();
+
+    let mut hash = Vec::with_capacity(iterations);
+    let mut read = Vec::with_capacity(iterations);
+    let mut validate = Vec::with_capacity(iterations);
+    let mut apply = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        hash.push(time(|| load(file_path, options).map(|(content, hashes)| drop(hashed_file(&content, hashes))))?);
+        read.push(time(|| {
+            cmd_read_to(&mut io::sink(), file_path, None, Some(lines), options.hash_cache.as_ref())
+                .map_err(io::Error::other)
+        })?);
+        validate.push(time(|| {
+            validate_hashed_file(&content, &file, &edits, &options.apply);
+            Ok(())
+        })?);
+        apply.push(time(|| {
+            apply_and_diff_hashed_file(&content, file.clone(), &edits, &options.apply)
+                .map(|_| ())
+                .map_err(|e| io::Error::other(e.to_string()))
+        })?);
+    }
+
+    let phase = |phase, samples: &[Duration]| PhaseTiming::new(phase, samples, lines, bytes);
+    Ok(BenchReport {
+        file: file_path.to_string(),
+        lines,
+        bytes,
+        iterations,
+        edits: edits.len(),
+        hash_cache: options.hash_cache.is_some(),
+        parallel_hashing: options.hash_cache.is_none() && hashes_in_parallel(lines),
+        phases: vec![
+            phase("hash", &hash),
+            phase("read", &read),
+            phase("validate", &validate),
+            phase("apply", &apply),
+        ],
+    })
+}
+
+pub fn cmd_bench(file_path: &str, iterations: usize, edit_count: usize, options: &EditOptions) -> Result<String, String> {
+    let report = run_bench(file_path, iterations, edit_count, options)
+        .map_err(|e| render_error(options.format, "io", format!("Failed to benchmark file: {}", e)))?;
+
+    if options.format == OutputFormat::Json {
+        return Ok(serde_json::to_string(&report).expect("bench report serializes to JSON"));
+    }
+    let on_off = |on: bool| if on { "on" } else { "off" };
+    let mut output = format!(
+        "{}: {} lines, {} bytes; {} iterations, {} edits per batch (hash cache {}, parallel hashing {})",
+        report.file,
+        report.lines,
+        report.bytes,
+        report.iterations,
+        report.edits,
+        on_off(report.hash_cache),
+        on_off(report.parallel_hashing),
+    );
+    for p in &report.phases {
+        output.push_str(&format!(
+            "\n  {:<9} mean {:>10.3} ms  min {:>10.3} ms  {:>14.0} lines/s  {:>10.1} MB/s",
+            p.phase, p.mean_ms, p.min_ms, p.lines_per_sec, p.mb_per_sec
+        ));
+    }
+    Ok(output)
+}
+
+impl PhaseTiming {
+    fn new(phase: &'static str, samples: &[Duration], lines: usize, bytes: usize) -> PhaseTiming {
+        let total: Duration = samples.iter().sum();
+        let mean = total / samples.len() as u32;
+        let min = samples.iter().min().copied().unwrap_or_default();
+        // A phase too fast for the clock still gets a finite rate
+        let mean_secs = mean.as_secs_f64().max(1e-9);
+        PhaseTiming {
+            phase,
+            total_ms: total.as_secs_f64() * 1e3,
+            mean_ms: mean.as_secs_f64() * 1e3,
+            min_ms: min.as_secs_f64() * 1e3,
+            lines_per_sec: lines as f64 / mean_secs,
+            mb_per_sec: bytes as f64 / 1e6 / mean_secs,
+        }
+    }
+}
+
+/// Read `file_path`, with its hashes when the cache has them.
+fn load(file_path: &str, options: &EditOptions) -> io::Result<(String, Option<Vec<LineHash>>)> {
+    match &options.hash_cache {
+        Some(cache) => cache.load_hashes(file_path).map(|(content, hashes)| (content, Some(hashes))),
+        None => Ok((fs::read_to_string(file_path)?, None)),
+    }
+}
+
+fn hashed_file(content: &str, hashes: Option<Vec<LineHash>>) -> HashedFile<'_> {
+    match hashes {
+        Some(hashes) => HashedFile::from_parts(content, hashes),
+        None => HashedFile::from_content(content),
+    }
+}
+
+fn time(run: impl FnOnce() -> io::Result<()>) -> io::Result<Duration> {
+    let start = Instant::now();
+    run()?;
+    Ok(start.elapsed())
+}
+
+/// Up to `count` single-line replacements spread evenly over the file, each
+/// appending a marker to its line.
+fn synthetic_edits(file: &HashedFile, count: usize) -> Vec<HashlineEdit> {
+    let count = count.min(file.len());
+    (0..count)
+        .map(|i| {
+            let line = i * file.len() / count + 1;
+            HashlineEdit::Replace {
+                pos: file.anchor(line).expect("line lies within the file"),
+                end: None,
+                lines: vec![format!("{} // bench", file.lines()[line - 1])],
+                expected_lines: None,
+            }
+        })
+        .collect()
+}
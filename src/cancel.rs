@@ -0,0 +1,64 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Cancellation
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// The error a cancelled read or search fails with.
+pub const CANCELLED_MESSAGE: &str = "Cancelled";
+
+/// Stops a long-running read or search from another thread, or on Ctrl-C
+/// with [`CancelToken::on_interrupt`]. Clones share one flag. The default
+/// token is never cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+/// The token SIGINT cancels, once [`CancelToken::on_interrupt`] installed
+/// the handler.
+static INTERRUPT: OnceLock<CancelToken> = OnceLock::new();
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// The token cancelled by the first SIGINT (Ctrl-C) the process gets,
+    /// instead of being killed by it. A second SIGINT kills the process as
+    /// usual. Elsewhere than on Unix the token is never cancelled.
+    pub fn on_interrupt() -> CancelToken {
+        INTERRUPT.get_or_init(|| {
+            #[cfg(unix)]
+            // SAFETY: the handler only stores to an atomic and resets the
+            // disposition, both async-signal-safe
+            unsafe {
+                libc::signal(libc::SIGINT, on_sigint as *const () as libc::sighandler_t);
+            }
+            CancelToken::new()
+        }).clone()
+    }
+
+    /// Fail with [`CANCELLED_MESSAGE`] once cancelled.
+    pub(crate) fn check(&self) -> Result<(), String> {
+        if self.is_cancelled() { Err(CANCELLED_MESSAGE.to_string()) } else { Ok(()) }
+    }
+}
+
+#[cfg(unix)]
+extern "C" fn on_sigint(_: libc::c_int) {
+    if let Some(token) = INTERRUPT.get() {
+        token.cancel();
+    }
+    // SAFETY: signal() is async-signal-safe
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_DFL);
+    }
+}
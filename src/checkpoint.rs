@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::journal::{check_private_dir, create_private_dir, write_private};
+use crate::{content_fingerprint, default_journal_dir, render_error, write_atomic, EditOptions, HashlineError, Operation, OutputFormat, Policy};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Checkpoints and Undo
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Checkpoints kept before the oldest are pruned.
+pub const CHECKPOINT_LIMIT: usize = 100;
+
+/// Where checkpoints are kept inside a journal directory.
+pub fn checkpoint_dir(journal_dir: &Path) -> PathBuf {
+    journal_dir.join("checkpoints")
+}
+
+/// One successfully written edit batch, with enough saved to revert it.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Checkpoint {
+    pub id: String,
+    pub files: Vec<CheckpointFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct CheckpointFile {
+    /// Absolute path of the edited file.
+    pub path: PathBuf,
+    /// Copy of the content before the batch.
+    pub backup: PathBuf,
+    /// Fingerprint of the content the batch wrote, to detect later changes.
+    pub after_fingerprint: String,
+}
+
+static CHECKPOINT_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Checkpoint IDs sort in creation order, and start with the creation time
+/// in nanoseconds as 16 hex digits.
+fn new_checkpoint_id() -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("{:016x}{:02x}", nanos as u64, CHECKPOINT_COUNTER.fetch_add(1, Ordering::Relaxed) % 256)
+}
+
+/// Save the pre-edit content of each `(path, old_content, new_content)` and
+/// return the new checkpoint's ID. `dir` is created for the current user
+/// alone, and refused if another user could write to it.
+pub fn record_checkpoint(dir: &Path, files: &[(&Path, &str, &str)]) -> std::io::Result<String> {
+    create_private_dir(dir)?;
+    let id = new_checkpoint_id();
+    let mut checkpoint = Checkpoint { id: id.clone(), files: Vec::new() };
+    for (i, (path, old_content, new_content)) in files.iter().enumerate() {
+        let backup = dir.join(format!("{}.{}.bak", id, i));
+        write_private(&backup, old_content.as_bytes())?;
+        checkpoint.files.push(CheckpointFile {
+            path: std::path::absolute(path)?,
+            backup,
+            after_fingerprint: content_fingerprint(new_content),
+        });
+    }
+    write_private(&dir.join(format!("{}.json", id)), &serde_json::to_vec(&checkpoint).expect("checkpoint serializes"))?;
+    prune_checkpoints(dir);
+    Ok(id)
+}
+
+/// Forget a checkpoint, e.g. because the write it covered failed.
+fn discard_checkpoint(dir: &Path, id: &str) {
+    if let Ok(checkpoint) = load_checkpoint(&dir.join(format!("{}.json", id))) {
+        remove_checkpoint(dir, &checkpoint);
+    }
+}
+
+fn load_checkpoint(path: &Path) -> Result<Checkpoint, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read checkpoint {}: {}", path.display(), e))?;
+    serde_json::from_str(&text).map_err(|e| format!("Corrupt checkpoint {}: {}", path.display(), e))
+}
+
+fn remove_checkpoint(dir: &Path, checkpoint: &Checkpoint) {
+    for file in &checkpoint.files {
+        let _ = fs::remove_file(&file.backup);
+    }
+    let _ = fs::remove_file(dir.join(format!("{}.json", checkpoint.id)));
+}
+
+/// IDs of the stored checkpoints, oldest first.
+fn checkpoint_ids(dir: &Path) -> Vec<String> {
+    let mut ids: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| e.file_name().to_str()?.strip_suffix(".json").map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    ids.sort();
+    ids
+}
+
+/// Refuse a checkpoint directory that exists but is not private, whose
+/// checkpoints another user may have planted.
+fn check_checkpoint_dir(dir: &Path) -> Result<(), String> {
+    match check_private_dir(dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!("Refusing checkpoints in {}: {}", dir.display(), e)),
+        _ => Ok(()),
+    }
+}
+
+/// The stored checkpoints from `from` on (all of them when `None`), oldest
+/// first.
+pub(crate) fn load_checkpoints(dir: &Path, from: Option<&str>) -> Result<Vec<Checkpoint>, String> {
+    check_checkpoint_dir(dir)?;
+    let ids = checkpoint_ids(dir);
+    let start = match from {
+        Some(id) => ids.iter().position(|c| c == id).ok_or_else(|| format!("Unknown checkpoint {}", id))?,
+        None => 0,
+    };
+    ids[start..].iter().map(|id| load_checkpoint(&dir.join(format!("{}.json", id)))).collect()
+}
+
+impl Checkpoint {
+    /// When the checkpoint was recorded, in seconds since the Unix epoch.
+    pub(crate) fn created(&self) -> u64 {
+        self.id.get(..16).and_then(|nanos| u64::from_str_radix(nanos, 16).ok()).unwrap_or(0) / 1_000_000_000
+    }
+}
+
+fn prune_checkpoints(dir: &Path) {
+    let ids = checkpoint_ids(dir);
+    for id in &ids[..ids.len().saturating_sub(CHECKPOINT_LIMIT)] {
+        discard_checkpoint(dir, id);
+    }
+}
+
+/// A checkpoint reverted by [`undo_checkpoints`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UndoneCheckpoint {
+    pub id: String,
+    pub files: Vec<PathBuf>,
+}
+
+/// Revert checkpoint `to` and every checkpoint after it, or only the latest
+/// checkpoint when `to` is `None`.
+///
+/// Nothing is touched unless every affected file still has exactly the
+/// content the reverted batches left it with, nor when `dir` is not private
+/// to the current user, nor when `policy` forbids writing any of the files.
+pub fn undo_checkpoints(dir: &Path, to: Option<&str>, policy: &Policy) -> Result<Vec<UndoneCheckpoint>, HashlineError> {
+    check_checkpoint_dir(dir).map_err(HashlineError::Undo)?;
+    let ids = checkpoint_ids(dir);
+    let start = match to {
+        Some(id) => ids.iter().position(|c| c == id)
+            .ok_or_else(|| HashlineError::Undo(format!("Unknown checkpoint {}", id)))?,
+        None if ids.is_empty() => return Err(HashlineError::Undo("No checkpoints to undo".to_string())),
+        None => ids.len() - 1,
+    };
+    let checkpoints: Vec<Checkpoint> = ids[start..].iter()
+        .map(|id| load_checkpoint(&dir.join(format!("{}.json", id))))
+        .collect::<Result<_, _>>()
+        .map_err(HashlineError::Undo)?;
+
+    // Walk back from the newest batch, checking each file is as that batch
+    // left it, and work out what every file must be restored to
+    let mut restore: BTreeMap<PathBuf, String> = BTreeMap::new();
+    for checkpoint in checkpoints.iter().rev() {
+        for file in &checkpoint.files {
+            let current = match restore.get(&file.path) {
+                Some(content) => content.clone(),
+                None => fs::read_to_string(&file.path)
+                    .map_err(|e| HashlineError::Undo(format!("Failed to read {}: {}", file.path.display(), e)))?,
+            };
+            if content_fingerprint(&current) != file.after_fingerprint {
+                return Err(HashlineError::Undo(format!(
+                    "{} has changed since checkpoint {}; refusing to undo",
+                    file.path.display(), checkpoint.id
+                )));
+            }
+            let before = fs::read_to_string(&file.backup)
+                .map_err(|e| HashlineError::Undo(format!("Failed to read backup for {}: {}", file.path.display(), e)))?;
+            restore.insert(file.path.clone(), before);
+        }
+    }
+
+    for path in restore.keys() {
+        policy.check(Operation::Write, path, false)?;
+    }
+    for (path, content) in &restore {
+        write_atomic(path, content)
+            .map_err(|e| HashlineError::Undo(format!("Failed to restore {}: {}", path.display(), e)))?;
+    }
+    let undone = checkpoints.iter().rev()
+        .map(|c| UndoneCheckpoint { id: c.id.clone(), files: c.files.iter().map(|f| f.path.clone()).collect() })
+        .collect();
+    for checkpoint in &checkpoints {
+        remove_checkpoint(dir, checkpoint);
+    }
+    Ok(undone)
+}
+
+/// Revert edit batches and report which checkpoints were undone.
+pub fn cmd_undo(journal_dir: &Path, to: Option<&str>, policy: &Policy, format: OutputFormat) -> Result<String, String> {
+    let undone = undo_checkpoints(&checkpoint_dir(journal_dir), to, policy)
+        .map_err(|e| render_error(format, e.kind(), e.to_string()))?;
+
+    if format == OutputFormat::Json {
+        return Ok(serde_json::json!({ "status": "undone", "checkpoints": undone }).to_string());
+    }
+    let mut output = format!(
+        "Undid {} checkpoint{}:",
+        undone.len(),
+        if undone.len() == 1 { "" } else { "s" }
+    );
+    for checkpoint in &undone {
+        let files: Vec<String> = checkpoint.files.iter().map(|f| f.display().to_string()).collect();
+        output.push_str(&format!("\n  - {}: {}", checkpoint.id, files.join(", ")));
+    }
+    Ok(output)
+}
+
+impl EditOptions {
+    fn checkpoint_dir(&self) -> PathBuf {
+        checkpoint_dir(&self.journal_dir.clone().unwrap_or_else(default_journal_dir))
+    }
+
+    /// Record a checkpoint for a write about to happen, if checkpoints are on.
+    pub(crate) fn record_checkpoint(&self, files: &[(&Path, &str, &str)]) -> Result<Option<String>, String> {
+        if !self.checkpoints {
+            return Ok(None);
+        }
+        record_checkpoint(&self.checkpoint_dir(), files)
+            .map(Some)
+            .map_err(|e| render_error(self.format, "io", format!("Failed to record checkpoint: {}", e)))
+    }
+
+    /// Drop a checkpoint whose write did not happen.
+    pub(crate) fn discard_checkpoint(&self, id: Option<&str>) {
+        if let Some(id) = id {
+            discard_checkpoint(&self.checkpoint_dir(), id);
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use clap::{Args, Parser, Subcommand};
+use serde_json::{json, Value};
+
+use crate::{parse_byte_size, AnchorMode, AnchorRef, DiffFormat, EndOfLine, FenceSelector, LogLevel, ReadToken, OutputFormat, DEFAULT_BACKUP_DIR, DEFAULT_FUZZY_THRESHOLD, DEFAULT_HASH_CACHE_DIR, DEFAULT_MISMATCH_CONTEXT, DEFAULT_STATS_FILE, EDITS_FORMAT_VERSION};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// CLI
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[derive(Parser)]
+#[command(name = "hashline-tools")]
+#[command(about = "Hashline tools for opencode")]
+pub struct Cli {
+    /// Apply edits by line number even when anchors are stale (requires --audit-log)
+    #[arg(long, global = true)]
+    pub force: bool,
+    /// Append a JSON line for every command run, and for every check skipped by --force (default: $HASHLINE_AUDIT_LOG)
+    #[arg(long, global = true)]
+    pub audit_log: Option<std::path::PathBuf>,
+    /// Directory for transaction journals and undo checkpoints (default: .hashline/journal)
+    #[arg(long, global = true)]
+    pub journal_dir: Option<std::path::PathBuf>,
+    /// Keep every pre-edit version of written files in the backup store DIR, for `backups` (default: .hashline/backups)
+    #[arg(long, global = true, value_name = "DIR", num_args = 0..=1, require_equals = true, default_missing_value = DEFAULT_BACKUP_DIR)]
+    pub backup: Option<std::path::PathBuf>,
+    /// Reuse line hashes of unchanged files across runs, cached in DIR (default: .hashline/cache)
+    #[arg(long, global = true, value_name = "DIR", num_args = 0..=1, require_equals = true, default_missing_value = DEFAULT_HASH_CACHE_DIR)]
+    pub hash_cache: Option<std::path::PathBuf>,
+    /// Stream reads of files larger than SIZE and refuse edits that would need more (e.g. 512M, 2G)
+    #[arg(long, global = true, value_name = "SIZE", value_parser = parse_byte_size)]
+    pub memory_budget: Option<u64>,
+    /// Refuse --edits-stdin and --token-stdin payloads larger than SIZE (e.g. 256M)
+    #[arg(long, global = true, value_name = "SIZE", value_parser = parse_byte_size, default_value = "64M")]
+    pub max_payload: u64,
+    /// Refuse to edit files larger than SIZE (e.g. 10M)
+    #[arg(long, global = true, value_name = "SIZE", value_parser = parse_byte_size)]
+    pub max_file_size: Option<u64>,
+    /// Refuse edit batches of more than N edits
+    #[arg(long, global = true, value_name = "N")]
+    pub max_edits: Option<usize>,
+    /// Refuse edit batches whose edits carry more than N lines in all
+    #[arg(long, global = true, value_name = "N")]
+    pub max_payload_lines: Option<usize>,
+    /// Ignore .editorconfig files instead of following their indent style, line endings and final newline
+    #[arg(long, global = true)]
+    pub no_editorconfig: bool,
+    /// Line ending for inserted lines, instead of the one most of the file uses (overrides .editorconfig)
+    #[arg(long, global = true, value_enum)]
+    pub line_ending: Option<EndOfLine>,
+    /// How line hashes are derived; edits must use the mode their anchors were read in
+    #[arg(long, global = true, value_enum, default_value_t = AnchorMode::Chained)]
+    pub anchors: AnchorMode,
+    /// Run CMD through the shell after each write, and roll the write back if it fails (e.g. 'cargo check')
+    #[arg(long, global = true, value_name = "CMD")]
+    pub verify_cmd: Option<String>,
+    /// Append the stats of each edit batch to FILE, for `stats` (default: $HASHLINE_STATS; .hashline/stats.jsonl without FILE)
+    #[arg(long, global = true, value_name = "FILE", num_args = 0..=1, require_equals = true, default_missing_value = DEFAULT_STATS_FILE)]
+    pub record_stats: Option<std::path::PathBuf>,
+    /// Minimum similarity, 0.0 to 1.0, for a nearby line's content to count as where a stale anchor moved
+    #[arg(long, global = true, value_name = "SIMILARITY", value_parser = parse_similarity, default_value_t = DEFAULT_FUZZY_THRESHOLD)]
+    pub fuzzy_threshold: f64,
+    /// List at most N content matches per stale anchor in mismatch errors
+    #[arg(long, global = true, value_name = "N")]
+    pub fuzzy_max_candidates: Option<usize>,
+    /// Log diagnostics to stderr at this level (default: $HASHLINE_LOG, else off)
+    #[arg(long, global = true, value_enum)]
+    pub log_level: Option<LogLevel>,
+    #[command(subcommand)]
+    pub command: Commands,
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    Read { 
+        file_path: String, 
+        #[arg(long)] offset: Option<usize>, 
+        #[arg(long)] limit: Option<usize>,
+        /// Resume after the last line of a truncated read, from the token it printed
+        #[arg(long = "continue", value_name = "TOKEN", conflicts_with_all = ["offset", "fence", "columns", "git_changed"])]
+        continue_from: Option<ReadToken>,
+        /// Start after this LINE#HASH anchor, failing with nearby candidates if it is stale
+        #[arg(long, value_name = "ANCHOR", conflicts_with_all = ["offset", "continue_from", "fence", "columns", "git_changed"])]
+        after: Option<AnchorRef>,
+        /// Prefix each line with the byte offset it starts at, and give the file's size
+        #[arg(long, conflicts_with_all = ["fence", "columns", "git_changed"])] byte_offsets: bool,
+        /// Read only this Markdown code fence, by index or by language or attribute value
+        #[arg(long)] fence: Option<FenceSelector>,
+        /// Label each field of a CSV or TSV row with its column name
+        #[arg(long)] columns: bool,
+        /// Show only the lines changed since git's HEAD, with a little context
+        #[arg(long, conflicts_with_all = ["offset", "limit", "fence", "columns"])] git_changed: bool,
+    },
+    /// Print every line matching a regex as `path:LINE#HASH:text`, skipping files ignored by .gitignore or .ignore
+    Search {
+        dir: String,
+        pattern: String,
+        /// Search subdirectories too
+        #[arg(long, short)] recursive: bool,
+        /// Stop after this many matching lines
+        #[arg(long)] max_results: Option<usize>,
+    },
+    Edit { 
+        file_path: String, 
+        #[arg(long)] edits: Option<String>, 
+        #[arg(long, conflicts_with = "edits")] edits_stdin: bool,
+        #[command(flatten)] shorthand: EditShorthand,
+        /// For a notebook, the 0-based cell a plain edit list applies to
+        #[arg(long)] cell: Option<usize>,
+        /// Edit only this Markdown code fence, with anchors from `read --fence`
+        #[arg(long)] fence: Option<FenceSelector>,
+        /// Relocate stale anchors to a nearby matching line instead of failing
+        #[arg(long)] auto_rebase: bool,
+        /// Retarget anchors whose hash belongs to the adjacent line
+        #[arg(long)] fix_off_by_one: bool,
+        /// Lines of surrounding code shown around each stale anchor
+        #[arg(long, default_value_t = DEFAULT_MISMATCH_CONTEXT)] mismatch_context: usize,
+        /// On hash mismatch, write conflict markers for the stale edits instead of failing
+        #[arg(long)] conflict_markers: bool,
+        /// Like --conflict-markers, but print the marked-up file instead of writing it
+        #[arg(long)] print_conflicts: bool,
+        /// Apply the edits that validate and report the rest as skipped
+        #[arg(long)] allow_partial: bool,
+        /// Treat repeated identical edits as overlapping instead of collapsing them
+        #[arg(long)] no_dedupe: bool,
+        /// Combine overlapping edits when one already makes the other's change
+        #[arg(long)] merge_overlaps: bool,
+        /// Apply edits in order, each anchored against the result of the ones before
+        #[arg(long)] sequential: bool,
+        /// Check the edits and report per-edit results without writing
+        #[arg(long)] validate_only: bool,
+        /// Show the diff and the anchors changed lines would get, without writing
+        #[arg(long)] dry_run: bool,
+        /// Print the edit as GitHub ```suggestion review comments instead of writing it
+        #[arg(long, conflicts_with_all = ["validate_only", "dry_run", "print_conflicts"])] suggest: bool,
+        /// Show each edit's diff and ask y/n/all/quit on the terminal before writing
+        #[arg(long, conflicts_with_all = ["edits_stdin", "sequential", "validate_only", "dry_run", "print_conflicts", "suggest"])]
+        interactive: bool,
+        /// How the diff of the change is shown: hash-aware, a standard unified diff, or not at all
+        #[arg(long, value_enum, default_value_t = DiffFormat::Hashline)] diff_format: DiffFormat,
+        /// Output format for results and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)] format: OutputFormat,
+    },
+    /// Validate edits without writing and print a token that `commit` applies later
+    Plan {
+        file_path: String,
+        #[arg(long)] edits: Option<String>,
+        #[arg(long, conflicts_with = "edits")] edits_stdin: bool,
+        /// Relocate stale anchors to a nearby matching line instead of failing
+        #[arg(long)] auto_rebase: bool,
+        /// Apply the edits that validate and report the rest as skipped
+        #[arg(long)] allow_partial: bool,
+        /// Apply edits in order, each anchored against the result of the ones before
+        #[arg(long)] sequential: bool,
+        /// Output format for results and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)] format: OutputFormat,
+    },
+    /// Apply a token from `plan`, if the file has not changed since
+    Commit {
+        #[arg(long)] token: Option<String>,
+        #[arg(long, conflicts_with = "token")] token_stdin: bool,
+        /// Output format for results and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)] format: OutputFormat,
+    },
+    /// Apply edits to several files all-or-nothing; payload is `{"path": [edits], ...}`
+    MultiEdit {
+        #[arg(long)] edits: Option<String>,
+        #[arg(long, conflicts_with = "edits")] edits_stdin: bool,
+        /// Relocate stale anchors to a nearby matching line instead of failing
+        #[arg(long)] auto_rebase: bool,
+        /// Lines of surrounding code shown around each stale anchor
+        #[arg(long, default_value_t = DEFAULT_MISMATCH_CONTEXT)] mismatch_context: usize,
+        /// Output format for results and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)] format: OutputFormat,
+    },
+    /// Finish or undo multi-edit transactions interrupted by a crash
+    Recover {
+        /// Restore the original files even where the transaction could be completed
+        #[arg(long)] rollback: bool,
+        /// Output format for results and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)] format: OutputFormat,
+    },
+    /// Revert the latest edit, or every edit back to and including a checkpoint
+    Undo {
+        /// Checkpoint to revert to, as printed by the edit that created it
+        #[arg(long)] to: Option<String>,
+        /// Output format for results and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)] format: OutputFormat,
+    },
+    /// Write the checkpointed edit batches as a `git format-patch` series, one patch per batch
+    ExportSeries {
+        /// First checkpoint to export; every stored one when omitted
+        #[arg(long)] from: Option<String>,
+        /// Directory the patch paths are relative to, usually the repository root
+        #[arg(long, default_value = ".")] root: std::path::PathBuf,
+        /// Directory the patches are written to
+        #[arg(long, short, default_value = ".")] output_dir: std::path::PathBuf,
+        /// Output format for results and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)] format: OutputFormat,
+    },
+    /// Save the working tree of the current git repository as a commit on a hidden ref, to restore later (runs git)
+    Checkpoint {
+        /// Describe the checkpoint
+        #[arg(long, short)] message: Option<String>,
+        /// List the saved checkpoints instead
+        #[arg(long, conflicts_with = "message")] list: bool,
+        /// Output format for results and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)] format: OutputFormat,
+    },
+    /// Put the working tree back as a git checkpoint saved it, saving the current one first
+    RestoreCheckpoint {
+        /// Checkpoint to restore; the latest when omitted
+        id: Option<u64>,
+        /// Output format for results and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)] format: OutputFormat,
+    },
+    /// Time hashing, reading, validating and applying a synthetic edit batch on a file, without writing it
+    Bench {
+        file_path: String,
+        /// How many times to run each phase
+        #[arg(long, default_value_t = 10)] iterations: usize,
+        /// Single-line replacements in the synthetic batch, spread evenly over the file
+        #[arg(long, default_value_t = 100)] edits: usize,
+        /// Relocate stale anchors to a nearby matching line instead of failing
+        #[arg(long)] auto_rebase: bool,
+        /// Apply edits in order, each anchored against the result of the ones before
+        #[arg(long)] sequential: bool,
+        /// Output format for results and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)] format: OutputFormat,
+    },
+    /// Write the anchors of a file, or of every file under a directory, to a `.hashline` manifest
+    Manifest {
+        path: String,
+        /// Where to write the manifest, in PATH's directory or above it (default: PATH.hashline, or DIR/manifest.hashline)
+        #[arg(long, short)] output: Option<std::path::PathBuf>,
+        /// Output format for results and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)] format: OutputFormat,
+    },
+    /// Check files against a `.hashline` manifest, failing with the ones that were added, removed or changed since
+    VerifyManifest {
+        manifest: std::path::PathBuf,
+        /// Output format for results and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)] format: OutputFormat,
+    },
+    /// Report the files added, removed and changed since a `.hashline` manifest, with the changed lines' current anchors
+    Drift {
+        manifest: std::path::PathBuf,
+        /// Output format for results and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)] format: OutputFormat,
+    },
+    /// Summarize the edit batches recorded with --record-stats: edits applied, mismatch and auto-rebase rates
+    Stats {
+        /// One row per session instead of the totals
+        #[arg(long)] sessions: bool,
+        /// Output format for results and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)] format: OutputFormat,
+    },
+    /// List, restore or prune the pre-edit versions kept with --backup
+    Backups {
+        #[command(subcommand)] action: BackupsAction,
+    },
+}
+
+/// What `backups` does with the backup store.
+#[derive(Subcommand)]
+pub enum BackupsAction {
+    /// List the stored versions, newest first
+    List {
+        /// Only the versions of this file
+        file: Option<std::path::PathBuf>,
+        /// Output format for results and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)] format: OutputFormat,
+    },
+    /// Put a file back to a stored version, backing up its current content first
+    Restore {
+        file: std::path::PathBuf,
+        /// Fingerprint, or its first 4 or more characters, of the version to restore; the latest when omitted
+        #[arg(long)] version: Option<String>,
+        /// Output format for results and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)] format: OutputFormat,
+    },
+    /// Remove the versions older than N days, and the contents only they kept
+    Prune {
+        #[arg(long, value_name = "N")] keep_days: u64,
+        /// Output format for results and errors
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)] format: OutputFormat,
+    },
+}
+
+/// One-edit flags for `edit`, so the common case needs no JSON. Each flag
+/// may be repeated; TEXT is split into lines on `\n`, `@PATH` reads it
+/// from a file and a leading `@@` stands for a literal `@`.
+#[derive(Args, Debug, Clone, Default)]
+pub struct EditShorthand {
+    /// Replace the line at ANCHOR, or an ANCHOR..ANCHOR range, with TEXT
+    #[arg(long, num_args = 2, value_names = ["ANCHOR", "TEXT"], conflicts_with_all = ["edits", "edits_stdin"])]
+    pub replace: Vec<String>,
+    /// Delete the line at ANCHOR, or an ANCHOR..ANCHOR range
+    #[arg(long, value_name = "ANCHOR", conflicts_with_all = ["edits", "edits_stdin"])]
+    pub delete: Vec<String>,
+    /// Insert TEXT after the line at ANCHOR
+    #[arg(long, num_args = 2, value_names = ["ANCHOR", "TEXT"], conflicts_with_all = ["edits", "edits_stdin"])]
+    pub insert_after: Vec<String>,
+    /// Insert TEXT before the line at ANCHOR
+    #[arg(long, num_args = 2, value_names = ["ANCHOR", "TEXT"], conflicts_with_all = ["edits", "edits_stdin"])]
+    pub insert_before: Vec<String>,
+}
+
+impl EditShorthand {
+    pub fn is_empty(&self) -> bool {
+        self.replace.is_empty() && self.delete.is_empty() && self.insert_after.is_empty() && self.insert_before.is_empty()
+    }
+
+    /// The flags as a version 2 edits document, or `None` when none were given.
+    /// Anchors are checked when the document is parsed, like any other payload.
+    pub fn to_edits_json(&self) -> Result<Option<String>, String> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let mut edits = Vec::new();
+        for pair in self.replace.chunks(2) {
+            let mut edit = anchor_range(&pair[0]);
+            edit["op"] = "replace".into();
+            edit["lines"] = shorthand_lines(&pair[1])?.into();
+            edits.push(edit);
+        }
+        for range in &self.delete {
+            let mut edit = anchor_range(range);
+            edit["op"] = "delete".into();
+            edits.push(edit);
+        }
+        for (op, pairs) in [("append", &self.insert_after), ("prepend", &self.insert_before)] {
+            for pair in pairs.chunks(2) {
+                edits.push(json!({ "op": op, "pos": pair[0], "lines": shorthand_lines(&pair[1])? }));
+            }
+        }
+        Ok(Some(json!({ "version": EDITS_FORMAT_VERSION, "edits": edits }).to_string()))
+    }
+}
+
+/// A similarity between 0.0 and 1.0, for `--fuzzy-threshold`.
+fn parse_similarity(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(similarity) if (0.0..=1.0).contains(&similarity) => Ok(similarity),
+        _ => Err(format!("expected a number from 0.0 to 1.0, got '{}'", text)),
+    }
+}
+
+/// `{"pos"}` for `8#RT`, `{"pos", "end"}` for `6#ZT..10#NV`.
+fn anchor_range(range: &str) -> Value {
+    match range.split_once("..") {
+        Some((pos, end)) => json!({ "pos": pos, "end": end }),
+        None => json!({ "pos": range }),
+    }
+}
+
+fn shorthand_lines(text: &str) -> Result<Vec<String>, String> {
+    let text = match text.strip_prefix('@') {
+        Some(literal) if literal.starts_with('@') => literal.to_string(),
+        Some(path) => {
+            let content = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            content.strip_suffix('\n').map(str::to_string).unwrap_or(content)
+        }
+        None => text.to_string(),
+    };
+    Ok(text.split('\n').map(str::to_string).collect())
+}
@@ -0,0 +1,853 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::time::Instant;
+
+use crate::audit::{audit_forced_edit, forced_audit_log, AuditedCommand};
+use crate::config::value_name;
+use crate::git_changed::read_git_changed;
+use crate::markdown::read_fence;
+use crate::memory_budget::exceeds_budget;
+use crate::notebook::{edit_notebook, is_notebook, read_notebook};
+use crate::read_token::ReadToken;
+use crate::suggestion::render_suggestions;
+use crate::table::read_table;
+use crate::{
+    annotate_lines_with_mode, apply_and_diff_hashed_file, parse_edits, read_lines, render_hash_aware_diff, unified_diff,
+    markdown_fences, validate_hashed_file, write_atomic, AnchorMode, AnchorRef, AnchorState, AnnotatedLine, AppliedEdits, ApplyOptions,
+    CancelToken, EditHooks, EditReviewer, EditStatus, FenceSelector, HashCache, HashMismatch, HashedFile, HashlineEdit, HashlineError,
+    HashlineMismatchError, LineHash, LineHasher, MarkdownFence, Operation, PendingEdit, Policy, ReviewDecision, SkipReason,
+    DEFAULT_MISMATCH_CONTEXT, DEFAULT_REBASE_WINDOW,
+};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Commands
+// ═══════════════════════════════════════════════════════════════════════════
+
+pub fn cmd_read(file_path: &str, offset: Option<usize>, limit: Option<usize>) -> Result<String, String> {
+    cmd_read_with_cache(file_path, offset, limit, None)
+}
+
+/// [`cmd_read`], taking the hashes from `cache` when the file is unchanged.
+pub fn cmd_read_with_cache(
+    file_path: &str,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    cache: Option<&HashCache>,
+) -> Result<String, String> {
+    let mut output = Vec::new();
+    cmd_read_to(&mut output, file_path, offset, limit, cache)?;
+    Ok(String::from_utf8(output).expect("annotated lines are UTF-8"))
+}
+
+/// [`cmd_read_with_cache`], writing each annotated line to `out` as it is
+/// produced instead of building the whole window in memory. Writes exactly
+/// what [`cmd_read_with_cache`] returns.
+pub fn cmd_read_to(
+    out: &mut impl Write,
+    file_path: &str,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    cache: Option<&HashCache>,
+) -> Result<(), String> {
+    let options = ReadOptions { offset, limit, hash_cache: cache.cloned(), ..ReadOptions::default() };
+    cmd_read_with_options(out, file_path, &options)
+}
+
+/// Options for the `read` command.
+#[derive(Debug, Clone, Default)]
+pub struct ReadOptions {
+    /// 0-based line to start at.
+    pub offset: Option<usize>,
+    /// Lines to show; 2000 when unset.
+    pub limit: Option<usize>,
+    /// Take the window's hashes from this cache's line index.
+    pub hash_cache: Option<HashCache>,
+    /// Files larger than this many bytes are streamed line by line instead
+    /// of read whole, and bypass the cache.
+    pub memory_budget: Option<u64>,
+    /// How the printed hashes are derived, taken for the file's path. The
+    /// cache only holds chained hashes, so other reads bypass it.
+    pub anchor_mode: AnchorMode,
+    /// Read only this fenced code block, as a document of its own.
+    pub fence: Option<FenceSelector>,
+    /// Read a CSV or TSV file with each field labeled by its column.
+    pub columns: bool,
+    /// Read only the regions changed since git's `HEAD`.
+    pub git_changed: bool,
+    /// Resume where the read that printed this token ended, instead of at
+    /// `offset`. Fails if the file changed above that point.
+    pub continue_from: Option<ReadToken>,
+    /// Start right after this line, once its anchor is verified. A stale
+    /// anchor fails the read with the lines it may have moved to.
+    pub after: Option<AnchorRef>,
+    /// Prefix each line with the byte offset it starts at, as `@OFFSET `,
+    /// and give the file's length in bytes in the footer.
+    pub byte_offsets: bool,
+    /// Record the read in this audit log.
+    pub audit_log: Option<std::path::PathBuf>,
+    /// Stop streaming a file over the memory budget once cancelled. The read
+    /// then fails with [`CANCELLED_MESSAGE`](crate::CANCELLED_MESSAGE).
+    pub cancel: CancelToken,
+}
+
+/// [`cmd_read_to`] with every read option.
+pub fn cmd_read_with_options(out: &mut impl Write, file_path: &str, options: &ReadOptions) -> Result<(), String> {
+    let result = read_with_options(out, file_path, options);
+    let read = AuditedCommand { event: "read", files: vec![file_path], payload: None };
+    read.audit(options.audit_log.as_deref(), OutputFormat::Text, result)
+}
+
+fn read_with_options(out: &mut impl Write, file_path: &str, options: &ReadOptions) -> Result<(), String> {
+    let read_error = |e: std::io::Error| format!("Failed to read file: {}", e);
+    let write_error = |e: std::io::Error| format!("Failed to write output: {}", e);
+    if let Some(selector) = &options.fence {
+        return read_fence(out, file_path, selector, options);
+    }
+    if is_notebook(file_path) {
+        return read_notebook(out, file_path, options);
+    }
+    if options.columns {
+        return read_table(out, file_path, options);
+    }
+    if options.git_changed {
+        return read_git_changed(out, file_path, options);
+    }
+    let start = match (&options.continue_from, &options.after) {
+        (Some(token), _) => {
+            token.check(std::path::Path::new(file_path), options.anchor_mode)?;
+            token.anchor.line
+        }
+        (None, Some(anchor)) => {
+            verify_read_anchor(file_path, anchor, options)?;
+            anchor.line
+        }
+        (None, None) => options.offset.unwrap_or(0),
+    };
+    let count = options.limit.unwrap_or(2000);
+    let mode = options.anchor_mode.for_path(file_path);
+    let size = fs::metadata(file_path).map_err(read_error)?.len();
+    if exceeds_budget(size, options.memory_budget) {
+        let (window, total_lines) = stream_read_window(file_path, start, count, mode, &options.cancel)?;
+        options.cancel.check()?;
+        let lines = window.iter().enumerate()
+            .map(|(i, (text, hash))| AnnotatedLine { line: start + i + 1, hash: *hash, text });
+        return write_read_window(out, lines, start, total_lines, count, file_path, options).map_err(write_error);
+    }
+    match options.hash_cache.as_ref().filter(|_| options.anchor_mode == AnchorMode::Chained) {
+        // Only the window is hashed, from the nearest cached chain seed
+        Some(cache) => {
+            let window = cache.load_window(file_path, start..start.saturating_add(count)).map_err(read_error)?;
+            write_read_window(out, window.annotated(), start, window.total_lines, count, file_path, options).map_err(write_error)
+        }
+        None => {
+            let content = fs::read_to_string(file_path).map_err(read_error)?;
+            let total_lines = content.lines().count();
+            let lines = annotate_lines_with_mode(&content, mode).skip(start).take(count);
+            write_read_window(out, lines, start, total_lines, count, file_path, options).map_err(write_error)
+        }
+    }
+}
+
+/// Fail unless `anchor` is current in the file. A stale anchor is reported
+/// like a stale edit anchor, with the nearby lines that carry its hash. Over
+/// the memory budget only the anchored line is streamed, so no such lines
+/// are looked for.
+fn verify_read_anchor(file_path: &str, anchor: &AnchorRef, options: &ReadOptions) -> Result<(), String> {
+    let read_error = |e: std::io::Error| format!("Failed to read file: {}", e);
+    let mode = options.anchor_mode.for_path(file_path);
+    let size = fs::metadata(file_path).map_err(read_error)?.len();
+    if exceeds_budget(size, options.memory_budget) {
+        let start = anchor.line.saturating_sub(1);
+        let (window, total_lines) = stream_read_window(file_path, start, 1, mode, &options.cancel)?;
+        options.cancel.check()?;
+        return match window.first() {
+            Some((_, hash)) if hash.as_str() == anchor.hash => Ok(()),
+            Some((_, hash)) => Err(format!(
+                "Hash mismatch error: line {} has changed since last read; it is now {}#{}", anchor.line, anchor.line, hash,
+            )),
+            None => Err(format!("Line {} does not exist (file has {} lines)", anchor.line, total_lines)),
+        };
+    }
+    let content = fs::read_to_string(file_path).map_err(read_error)?;
+    let file = HashedFile::from_content_with_mode(&content, mode);
+    match file.revalidate(anchor) {
+        AnchorState::Valid => Ok(()),
+        AnchorState::OutOfRange { len } => Err(format!("Line {} does not exist (file has {} lines)", anchor.line, len)),
+        AnchorState::Stale { .. } => {
+            let mismatch = HashMismatch::new(anchor, None, &file, DEFAULT_REBASE_WINDOW);
+            let error = HashlineMismatchError::new(vec![mismatch], &file, DEFAULT_MISMATCH_CONTEXT);
+            Err(render_apply_error(&HashlineError::Mismatch(error), OutputFormat::Text))
+        }
+    }
+}
+
+/// Hash `file_path` line by line without holding it in memory, keeping only
+/// the `count` lines from `start`. Returns them with the file's line count.
+/// Syntax anchors need the whole file parsed, so they cannot be streamed.
+fn stream_read_window(
+    file_path: &str,
+    start: usize,
+    count: usize,
+    mode: AnchorMode,
+    cancel: &CancelToken,
+) -> Result<(Vec<(String, LineHash)>, usize), String> {
+    if mode.hashes_whole_file() {
+        return Err(format!(
+            "{} is over the memory budget, and {} anchors parse the whole file; read it with --anchors scoped or a larger --memory-budget",
+            file_path, mode,
+        ));
+    }
+    let read_error = |e: std::io::Error| format!("Failed to read file: {}", e);
+    let mut window = Vec::new();
+    let mut hasher = LineHasher::new(mode);
+    let mut total_lines = 0;
+    for (i, text) in read_lines(BufReader::new(fs::File::open(file_path).map_err(read_error)?)).enumerate() {
+        // The caller reports the cancellation
+        if cancel.is_cancelled() {
+            break;
+        }
+        total_lines += 1;
+        if i >= start.saturating_add(count) {
+            continue;
+        }
+        let text = text.map_err(read_error)?;
+        let hash = hasher.hash(i + 1, &text);
+        if i >= start {
+            window.push((text, hash));
+        }
+    }
+    Ok((window, total_lines))
+}
+
+fn write_read_window<'a>(
+    out: &mut impl Write,
+    lines: impl Iterator<Item = AnnotatedLine<'a>>,
+    start: usize,
+    total_lines: usize,
+    count: usize,
+    file_path: &str,
+    options: &ReadOptions,
+) -> std::io::Result<()> {
+    if start >= total_lines {
+        return write!(out, "<file>\n(End of file - 0 lines)\n</file>");
+    }
+    let end = start.saturating_add(count).min(total_lines);
+    let (offsets, total_bytes) = if options.byte_offsets {
+        (window_byte_offsets(file_path, start, count)?, Some(fs::metadata(file_path)?.len()))
+    } else {
+        (Vec::new(), None)
+    };
+    writeln!(out, "<file>")?;
+    let mut last = None;
+    for (i, line) in lines.enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        if let Some(offset) = offsets.get(i) {
+            write!(out, "@{} ", offset)?;
+        }
+        write!(out, "{}", line)?;
+        last = Some(line.anchor());
+    }
+    let size = |lead: &str| total_bytes.map(|bytes| format!("{}{} bytes", lead, bytes)).unwrap_or_default();
+    
+    if end < total_lines {
+        write!(out, "\n\n(File has more lines. Use 'offset' parameter to read beyond line {}", end)?;
+        if let Some(last) = last {
+            let token = ReadToken::issue(std::path::Path::new(file_path), last.clone(), options.anchor_mode)?;
+            write!(out, ". Last line shown: {}{})", last, size(". File is "))?;
+            write!(out, "\nNext: {}", next_read_command(file_path, format!("--continue {}", token), options))?;
+            write!(out, "\n  or: {}", next_read_command(file_path, format!("--offset {}", end), options))?;
+        } else {
+            write!(out, ")")?;
+        }
+    } else {
+        write!(out, "\n\n(End of file - {} total lines{})", total_lines, size(", "))?;
+    }
+    write!(out, "\n</file>")
+}
+
+/// The byte offsets the `count` lines from `start` of `file_path` start at,
+/// found by streaming the file up to them.
+fn window_byte_offsets(file_path: &str, start: usize, count: usize) -> std::io::Result<Vec<u64>> {
+    let mut reader = BufReader::new(fs::File::open(file_path)?);
+    let mut offsets = Vec::new();
+    let mut offset = 0u64;
+    let mut line = Vec::new();
+    for i in 0..start.saturating_add(count) {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 {
+            break;
+        }
+        if i >= start {
+            offsets.push(offset);
+        }
+        offset += read as u64;
+    }
+    Ok(offsets)
+}
+
+/// The command that reads on from where a read with `options` stopped:
+/// `resume` (`--offset N` or `--continue TOKEN`) plus every option that
+/// shaped the window, e.g. `hashline-tools read src/lib.rs --offset 2000`.
+pub(crate) fn next_read_command(file_path: &str, resume: String, options: &ReadOptions) -> String {
+    let mut command = String::from("hashline-tools");
+    if options.anchor_mode != AnchorMode::default() {
+        command += &format!(" --anchors {}", value_name(options.anchor_mode));
+    }
+    command += &format!(" read {}", shell_word(file_path));
+    if let Some(fence) = &options.fence {
+        command += &format!(" --fence {}", shell_word(&fence.to_string()));
+    }
+    if options.columns {
+        command += " --columns";
+    }
+    if options.byte_offsets {
+        command += " --byte-offsets";
+    }
+    command += &format!(" {}", resume);
+    if let Some(limit) = options.limit {
+        command += &format!(" --limit {}", limit);
+    }
+    command
+}
+
+/// `word` as one POSIX shell word, single-quoted unless it is plain.
+fn shell_word(word: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "_-./:@%+=,".contains(c);
+    if !word.is_empty() && word.chars().all(plain) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+/// How command results and errors are rendered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    /// Human- and LLM-readable text (default)
+    #[default]
+    Text,
+    /// Machine-readable JSON objects
+    Json,
+}
+
+/// How `edit` shows the change it made.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffFormat {
+    /// Changed lines and their context with fresh `LINE#HASH` anchors (default)
+    #[default]
+    Hashline,
+    /// A standard unified diff of the whole file, for `patch` and review tools
+    Unified,
+    /// No diff
+    None,
+}
+
+impl DiffFormat {
+    /// The diff of `applied`, which turned `old` into `new` in `file_path`,
+    /// or `None` when diffs are off. Unified diffs cover the whole file, so
+    /// their line numbers hold for a fence edit too.
+    fn render(self, file_path: &str, old: &str, new: &str, applied: &AppliedEdits) -> Option<String> {
+        match self {
+            DiffFormat::Hashline => {
+                let first_changed_line = applied.outcome.first_changed_line.unwrap_or(1);
+                Some(render_hash_aware_diff(&applied.diff, first_changed_line))
+            }
+            DiffFormat::Unified => Some(unified_diff(old, new, file_path, file_path)),
+            DiffFormat::None => None,
+        }
+    }
+
+    /// `diff` as the `<diff>` block closing text output.
+    fn block(self, file_path: &str, diff: Option<&str>) -> String {
+        match (self, diff) {
+            (DiffFormat::Hashline, Some(diff)) => format!("\n\n<diff>\n--- {}\n+++ {}\n{}\n</diff>", file_path, file_path, diff),
+            (_, Some(diff)) => format!("\n\n<diff>\n{}</diff>", diff),
+            (_, None) => String::new(),
+        }
+    }
+}
+
+/// Options for the `edit` command.
+#[derive(Debug, Clone, Default)]
+pub struct EditOptions {
+    pub apply: ApplyOptions,
+    pub format: OutputFormat,
+    /// Report per-edit validation results without writing anything.
+    pub validate_only: bool,
+    /// In conflict-marker mode, print the marked-up file instead of writing it.
+    pub print_conflicts: bool,
+    /// Validate and report the diff and post-edit anchors without writing.
+    pub dry_run: bool,
+    /// Render the edit as GitHub suggested changes without writing.
+    pub suggest: bool,
+    /// Where multi-file transactions keep their crash-recovery journal, and
+    /// checkpoints are kept. Defaults to [`crate::default_journal_dir`].
+    pub journal_dir: Option<std::path::PathBuf>,
+    /// Record an undo checkpoint for every successful write.
+    pub checkpoints: bool,
+    /// Keep every pre-edit version of the files written in this backup
+    /// store, until pruned.
+    pub backup_dir: Option<std::path::PathBuf>,
+    /// Where forced runs are recorded. Required when `apply.force` is set.
+    pub audit_log: Option<std::path::PathBuf>,
+    /// Operator guardrails checked before any file is touched.
+    pub policy: Policy,
+    /// Callbacks run before a batch is validated for writing and after it
+    /// is written. Validation-only and dry runs skip them.
+    pub hooks: EditHooks,
+    /// Take the file's hashes from this cache when it is unchanged.
+    pub hash_cache: Option<HashCache>,
+    /// Refuse edits whose files would need more than this many bytes in memory.
+    pub memory_budget: Option<u64>,
+    /// Follow the `.editorconfig` files that apply to each edited file.
+    pub editorconfig: bool,
+    /// For notebooks, the cell a plain edit list applies to.
+    pub cell: Option<usize>,
+    /// Edit only this fenced code block, with anchors read from it alone.
+    pub fence: Option<FenceSelector>,
+    /// Shell command run after every write. If it fails, the written files
+    /// get their previous content back and the edit is reported as failed.
+    pub verify_cmd: Option<String>,
+    /// Append the stats of each batch to this file, for `stats`.
+    pub stats_file: Option<std::path::PathBuf>,
+    /// How the diff of a written or dry-run edit is shown.
+    pub diff_format: DiffFormat,
+    /// Ask this reviewer about each edit before writing, and write only the
+    /// accepted ones. Dry runs and suggestions skip the review.
+    pub review: Option<EditReviewer>,
+}
+
+pub fn cmd_edit(file_path: &str, edits_json: &str) -> Result<String, String> {
+    cmd_edit_with_options(file_path, edits_json, &EditOptions::default())
+}
+
+pub fn cmd_edit_with_options(file_path: &str, edits_json: &str, options: &EditOptions) -> Result<String, String> {
+    let result = edit_with_options(file_path, edits_json, options);
+    let edit = AuditedCommand { event: "edit", files: vec![file_path], payload: Some(edits_json) };
+    edit.audit(options.audit_log.as_deref(), options.format, result)
+}
+
+fn edit_with_options(file_path: &str, edits_json: &str, options: &EditOptions) -> Result<String, String> {
+    options.policy.check(Operation::Edit, std::path::Path::new(file_path), options.apply.force)
+        .map_err(|e| render_error(options.format, "policy_violation", e.to_string()))?;
+    options.check_verify_policy()?;
+    options.check_memory_budget([std::path::Path::new(file_path)])?;
+    options.check_file_limits([std::path::Path::new(file_path)])?;
+    if is_notebook(file_path) && options.fence.is_none() {
+        if options.suggest {
+            return Err(render_error(options.format, "not_suggestable", "Notebook edits cannot be written as suggestions".to_string()));
+        }
+        return edit_notebook(file_path, edits_json, options);
+    }
+    
+    let read_error = |e: std::io::Error| render_error(options.format, "io", format!("Failed to read file: {}", e));
+    let anchor_mode = options.apply.anchor_mode.for_path(file_path);
+    let started = Instant::now();
+    let (content, hashes) = match options.hash_cache.as_ref().filter(|_| anchor_mode == AnchorMode::Chained) {
+        Some(cache) => cache.load_hashes(file_path).map(|(content, hashes)| (content, Some(hashes))).map_err(read_error)?,
+        None => (fs::read_to_string(file_path).map_err(read_error)?, None),
+    };
+    let file = match hashes {
+        Some(hashes) => HashedFile::from_parts(&content, hashes),
+        None => HashedFile::from_content_with_mode(&content, anchor_mode),
+    };
+    log::debug!("read and hashed {} ({} lines) in {:.1?}", file_path, file.len(), started.elapsed());
+    
+    let hashline_edits = parse_edits(edits_json)
+        .map_err(|e| render_error(options.format, e.kind(), e.to_string()))?;
+    options.check_batch_limits(std::path::Path::new(file_path), &hashline_edits)?;
+    
+    if let Some(selector) = &options.fence {
+        let fences = markdown_fences(&content);
+        let fence = selector.select(&fences).map_err(|e| render_error(options.format, "invalid_fence", e))?;
+        let body = &content[fence.content.clone()];
+        let file = HashedFile::from_content_with_mode(body, anchor_mode);
+        if options.validate_only {
+            return validate_hashline_cmd(body, &file, &hashline_edits, options);
+        }
+        let target = EditTarget::Fence { file: &content, fence };
+        return apply_hashed_file_cmd(body, file, file_path, &hashline_edits, options, target);
+    }
+    if options.validate_only {
+        return validate_hashline_cmd(&content, &file, &hashline_edits, options);
+    }
+    apply_hashed_file_cmd(&content, file, file_path, &hashline_edits, options, EditTarget::File)
+}
+
+/// Where the edited text lives in the file that is written.
+enum EditTarget<'a> {
+    /// The edited text is the whole file.
+    File,
+    /// The edited text is the content of `fence` in `file`.
+    Fence { file: &'a str, fence: &'a MarkdownFence },
+}
+
+impl EditTarget<'_> {
+    /// The file's content, given the edited text's.
+    fn file_content<'a>(&'a self, edited: &'a str) -> &'a str {
+        match self {
+            EditTarget::File => edited,
+            EditTarget::Fence { file, .. } => file,
+        }
+    }
+
+    /// The file's new content, given the edited text's.
+    fn written(&self, edited: String) -> String {
+        let EditTarget::Fence { file, fence } = self else {
+            return edited;
+        };
+        let (before, after) = (&file[..fence.content.start], &file[fence.content.end..]);
+        // The closing marker needs a line of its own
+        let eol = if before.ends_with("\r\n") { "\r\n" } else { "\n" };
+        let eol = if fence.closed && !edited.is_empty() && !edited.ends_with('\n') { eol } else { "" };
+        format!("{}{}{}{}", before, edited, eol, after)
+    }
+
+    /// Where line numbers in the output count from, for text output.
+    fn location(&self) -> String {
+        match self {
+            EditTarget::File => String::new(),
+            EditTarget::Fence { fence, .. } => format!(" of fence {}", fence.index),
+        }
+    }
+
+    /// Tag a JSON result with the fence it applies to.
+    fn tag(&self, mut value: serde_json::Value) -> String {
+        if let EditTarget::Fence { fence, .. } = self {
+            value["fence"] = fence.index.into();
+        }
+        value.to_string()
+    }
+}
+
+fn validate_hashline_cmd(content: &str, file: &HashedFile, edits: &[HashlineEdit], options: &EditOptions) -> Result<String, String> {
+    let statuses = validate_hashed_file(content, file, edits, &options.apply);
+    let failed = statuses.iter().filter(|s| !s.is_ok()).count();
+    
+    let output = match options.format {
+        OutputFormat::Json => serde_json::json!({
+            "status": if failed == 0 { "valid" } else { "invalid" },
+            "statuses": statuses,
+        }).to_string(),
+        OutputFormat::Text if failed == 0 => format!(
+            "Validation passed: {} edit{} would apply cleanly.",
+            statuses.len(),
+            if statuses.len() == 1 { "" } else { "s" }
+        ),
+        OutputFormat::Text => format!(
+            "Validation failed: {} of {} edits would be skipped.{}",
+            failed, statuses.len(), format_statuses(&statuses)
+        ),
+    };
+    if failed == 0 { Ok(output) } else { Err(output) }
+}
+
+/// Render a command error as plain text or as a JSON error object.
+pub(crate) fn render_error(format: OutputFormat, kind: &str, message: String) -> String {
+    match format {
+        OutputFormat::Text => message,
+        OutputFormat::Json => serde_json::json!({
+            "status": "error",
+            "error": kind,
+            "message": message,
+        }).to_string(),
+    }
+}
+
+pub(crate) fn apply_hashline_cmd(content: &str, file_path: &str, edits: &[HashlineEdit], options: &EditOptions) -> Result<String, String> {
+    let file = HashedFile::from_content_with_mode(content, options.apply.anchor_mode.for_path(file_path));
+    apply_hashed_file_cmd(content, file, file_path, edits, options, EditTarget::File)
+}
+
+/// [`apply_hashline_cmd`] for `content` already hashed as `file`.
+fn apply_hashed_file_cmd(
+    content: &str,
+    file: HashedFile,
+    file_path: &str,
+    edits: &[HashlineEdit],
+    options: &EditOptions,
+    target: EditTarget,
+) -> Result<String, String> {
+    let format = options.format;
+    let audit_log = forced_audit_log(options)?;
+    let path = std::path::Path::new(file_path);
+    // Hooks see whole files, so they are not run for a fence
+    let run_hooks = !options.dry_run && !options.suggest && matches!(target, EditTarget::File);
+    if run_hooks {
+        options.hooks.run_before(path, edits).map_err(|e| render_error(format, e.kind(), e.to_string()))?;
+    }
+    let apply = options.apply_options_for(path).map_err(|e| render_error(format, "io", e))?;
+    let started = Instant::now();
+    let reviewer = options.review.as_ref().filter(|_| !options.dry_run && !options.suggest && !options.print_conflicts);
+    let original = reviewer.map(|_| file.clone());
+    let mut applied = apply_and_diff_hashed_file(content, file, edits, &apply);
+    log::debug!("applied {} edit(s) to {} in {:.1?}", edits.len(), file_path, started.elapsed());
+    let mut rejected = Vec::new();
+    let kept;
+    let mut edits = edits;
+    if let (Some(reviewer), Some(original), Ok(batch)) = (reviewer, original, &applied) {
+        rejected = review_edits(content, &original, file_path, edits, &batch.outcome.statuses, &apply, reviewer);
+        if !rejected.is_empty() {
+            let mut is_rejected = vec![false; edits.len()];
+            for &index in &rejected {
+                is_rejected[index] = true;
+            }
+            let kept_indexes: Vec<usize> = (0..edits.len()).filter(|&i| !is_rejected[i]).collect();
+            if kept_indexes.is_empty() {
+                return Ok(match format {
+                    OutputFormat::Text => format!("No changes made: all {} edits were rejected", rejected.len()),
+                    OutputFormat::Json => serde_json::json!({
+                        "status": "unchanged",
+                        "rejected": rejected,
+                    }).to_string(),
+                });
+            }
+            let total = edits.len();
+            kept = kept_indexes.iter().map(|&i| edits[i].clone()).collect::<Vec<_>>();
+            edits = &kept;
+            applied = apply_and_diff_hashed_file(content, original, edits, &apply);
+            if let Ok(applied) = &mut applied {
+                applied.outcome.statuses = restore_status_indexes(&applied.outcome.statuses, &kept_indexes, total);
+            }
+        }
+    }
+    options.record_stats(file_path, edits.len(), &applied);
+    match applied {
+        Ok(applied) => {
+            let outcome = &applied.outcome;
+            let new_content = outcome.content.clone();
+            let conflicts = outcome.statuses.iter().filter(|s| s.is_conflicted()).count();
+            
+            if conflicts > 0 && options.print_conflicts {
+                return Ok(match format {
+                    OutputFormat::Text => new_content,
+                    OutputFormat::Json => serde_json::json!({
+                        "status": "conflicted",
+                        "file": file_path,
+                        "content": new_content,
+                        "statuses": outcome.statuses,
+                    }).to_string(),
+                });
+            }
+            
+            // Per-edit statuses only carry information when edits may be skipped,
+            // conflicted or collapsed
+            let duplicates = outcome.statuses.iter()
+                .any(|s| matches!(s, EditStatus::Duplicate { .. } | EditStatus::Merged { .. }));
+            let mut status_msg = if options.apply.allow_partial || conflicts > 0 || duplicates {
+                format_statuses(&outcome.statuses)
+            } else {
+                String::new()
+            };
+            if !rejected.is_empty() {
+                let names: Vec<_> = rejected.iter().map(|i| format!("edits[{}]", i)).collect();
+                status_msg.push_str(&format!("\n\nRejected on review: {}", names.join(", ")));
+            }
+            
+            if new_content == content {
+                return Ok(match format {
+                    OutputFormat::Text => format!("No changes made{}", status_msg),
+                    OutputFormat::Json => serde_json::json!({
+                        "status": "unchanged",
+                        "statuses": outcome.statuses,
+                    }).to_string(),
+                });
+            }
+            
+            if options.suggest {
+                let file_content = target.file_content(content);
+                return render_suggestions(file_path, file_content, &target.written(new_content), format);
+            }
+            if options.dry_run {
+                return Ok(render_dry_run(&applied, content, file_path, &status_msg, options, &target));
+            }
+            
+            audit_forced_edit(audit_log, file_path, &outcome.bypassed, options)?;
+            let new_file_content = target.written(new_content);
+            let started = Instant::now();
+            options.record_backup(&[(path, target.file_content(content), &new_file_content)])?;
+            let checkpoint = options.record_checkpoint(&[(path, target.file_content(content), &new_file_content)])?;
+            if let Err(e) = write_atomic(path, &new_file_content) {
+                options.discard_checkpoint(checkpoint.as_deref());
+                return Err(render_error(format, "io", format!("Failed to write file: {}", e)));
+            }
+            options.verify_written(&[(path, target.file_content(content))], checkpoint.as_deref())?;
+            log::debug!("wrote {} in {:.1?}", file_path, started.elapsed());
+            if run_hooks {
+                options.hooks.run_after(path, edits, content, outcome, &applied.file);
+            }
+            
+            let first_changed_line = outcome.first_changed_line.unwrap_or(1);
+            let diff_output = options.diff_format.render(file_path, target.file_content(content), &new_file_content, &applied);
+            
+            if format == OutputFormat::Json {
+                return Ok(target.tag(serde_json::json!({
+                    "status": if conflicts > 0 { "conflicted" } else { "applied" },
+                    "file": file_path,
+                    "first_changed_line": first_changed_line,
+                    "relocations": outcome.relocations,
+                    "bypassed": outcome.bypassed,
+                    "statuses": outcome.statuses,
+                    "checkpoint": checkpoint,
+                    "rejected": rejected,
+                    "diff": diff_output,
+                })));
+            }
+            
+            let first_line_msg = format!(" (first change at line {}{})", first_changed_line, target.location());
+            
+            let mut anchor_msg = String::new();
+            if !outcome.relocations.is_empty() {
+                anchor_msg.push_str(&format!("\n\nRebased {} stale anchor{}:",
+                    outcome.relocations.len(),
+                    if outcome.relocations.len() > 1 { "s" } else { "" }
+                ));
+                for relocation in &outcome.relocations {
+                    anchor_msg.push_str(&format!("\n  - {}", relocation));
+                }
+            }
+            if !outcome.bypassed.is_empty() {
+                anchor_msg.push_str(&format!("\n\nForced past {} failed check{}:",
+                    outcome.bypassed.len(),
+                    if outcome.bypassed.len() > 1 { "s" } else { "" }
+                ));
+                for check in &outcome.bypassed {
+                    anchor_msg.push_str(&format!("\n  - {}", check));
+                }
+            }
+            
+            let summary = if conflicts > 0 {
+                format!("Edit applied with {} conflict{}{}. Resolve the <<<<<<< blocks before editing further",
+                    conflicts, if conflicts > 1 { "s" } else { "" }, first_line_msg)
+            } else {
+                format!("Edit applied successfully{}", first_line_msg)
+            };
+            
+            let checkpoint_msg = checkpoint.map(|id| format!("\nCheckpoint: {}", id)).unwrap_or_default();
+            
+            let diff_block = options.diff_format.block(file_path, diff_output.as_deref());
+            Ok(format!("{}.{}{}{}{}", summary, checkpoint_msg, anchor_msg, status_msg, diff_block))
+        }
+        Err(e) => Err(render_apply_error(&e, format)),
+    }
+}
+
+/// Show `reviewer` each edit of a batch that applied, on its own, and return
+/// the indexes of the edits it turned down. Skipped edits are not shown.
+fn review_edits(
+    content: &str,
+    file: &HashedFile,
+    file_path: &str,
+    edits: &[HashlineEdit],
+    statuses: &[EditStatus],
+    apply: &ApplyOptions,
+    reviewer: &EditReviewer,
+) -> Vec<usize> {
+    let mut rejected = Vec::new();
+    let mut remaining = None;
+    for (index, edit) in edits.iter().enumerate() {
+        if !statuses.get(index).is_some_and(|s| s.is_applied() || s.is_conflicted()) {
+            continue;
+        }
+        let decision = match remaining {
+            Some(decision) => decision,
+            None => {
+                let diff = match apply_and_diff_hashed_file(content, file.clone(), std::slice::from_ref(edit), apply) {
+                    Ok(applied) => render_hash_aware_diff(&applied.diff, applied.outcome.first_changed_line.unwrap_or(1)),
+                    Err(e) => render_apply_error(&e, OutputFormat::Text),
+                };
+                let pending = PendingEdit { file: file_path, index, total: edits.len(), diff: &diff };
+                reviewer.review(&pending)
+            }
+        };
+        match decision {
+            ReviewDecision::Accept => {}
+            ReviewDecision::Reject => rejected.push(index),
+            ReviewDecision::AcceptAll => remaining = Some(ReviewDecision::Accept),
+            ReviewDecision::Quit => {
+                rejected.push(index);
+                remaining = Some(ReviewDecision::Reject);
+            }
+        }
+    }
+    rejected
+}
+
+/// Spread the statuses of a re-applied subset of a batch back over the
+/// original edit indexes, so `statuses[i]` is always about `edits[i]`.
+/// Edits left out of the subset were rejected on review.
+fn restore_status_indexes(statuses: &[EditStatus], kept: &[usize], total: usize) -> Vec<EditStatus> {
+    let rejected = EditStatus::Skipped { reason: SkipReason::Rejected, message: "rejected on review".to_string() };
+    let mut restored = vec![rejected; total];
+    for (status, &index) in statuses.iter().zip(kept) {
+        restored[index] = match status {
+            EditStatus::Duplicate { of } => EditStatus::Duplicate { of: kept[*of] },
+            EditStatus::Merged { into } => EditStatus::Merged { into: kept[*into] },
+            status => status.clone(),
+        };
+    }
+    restored
+}
+
+/// Render a failed batch, with full mismatch details for stale anchors.
+pub(crate) fn render_apply_error(e: &HashlineError, format: OutputFormat) -> String {
+    match (e, format) {
+        (HashlineError::Mismatch(mismatch_err), OutputFormat::Text) => format!("Hash mismatch error:\n{}", mismatch_err),
+        (HashlineError::LimitExceeded(exceeded), OutputFormat::Text) => exceeded.to_string(),
+        (HashlineError::Mismatch(_) | HashlineError::LimitExceeded(_), OutputFormat::Json) => {
+            let mut value = e.to_json();
+            value["status"] = "error".into();
+            value.to_string()
+        }
+        _ => render_error(format, "edit_failed", format!("Edit failed: {}", e)),
+    }
+}
+
+fn render_dry_run(
+    applied: &AppliedEdits,
+    content: &str,
+    file_path: &str,
+    status_msg: &str,
+    options: &EditOptions,
+    target: &EditTarget,
+) -> String {
+    let (format, diff_format) = (options.format, options.diff_format);
+    let outcome = &applied.outcome;
+    let first_changed_line = outcome.first_changed_line.unwrap_or(1);
+    let new_file_content = target.written(outcome.content.clone());
+    let diff_output = diff_format.render(file_path, target.file_content(content), &new_file_content, applied);
+    let anchors = &applied.changed;
+    
+    if format == OutputFormat::Json {
+        return target.tag(serde_json::json!({
+            "status": "dry_run",
+            "file": file_path,
+            "first_changed_line": first_changed_line,
+            "relocations": outcome.relocations,
+            "statuses": outcome.statuses,
+            "anchors": anchors,
+            "diff": diff_output,
+        }));
+    }
+    
+    let mut anchor_msg = String::from("\n\nAnchors after the edit:");
+    for line in anchors {
+        anchor_msg.push_str(&format!("\n  {}:{}", line.anchor, line.text));
+    }
+    format!("Dry run: edit validated, nothing written (first change at line {}{}).{}{}{}",
+        first_changed_line, target.location(), status_msg, anchor_msg, diff_format.block(file_path, diff_output.as_deref()))
+}
+
+/// Render per-edit statuses as an indented list for text output.
+fn format_statuses(statuses: &[EditStatus]) -> String {
+    let mut out = String::from("\n\nEdit statuses:");
+    for (i, status) in statuses.iter().enumerate() {
+        out.push_str(&format!("\n  - edits[{}]: {}", i, status));
+    }
+    out
+}
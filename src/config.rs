@@ -0,0 +1,197 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Deserializer};
+
+use crate::{parse_byte_size, AnchorMode, DiffFormat, OutputFormat};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Configuration File
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Environment variable naming the configuration file, instead of the one
+/// [`default_config_path`] gives.
+pub const CONFIG_ENV_VAR: &str = "HASHLINE_CONFIG";
+
+/// `$XDG_CONFIG_HOME/hashline-tools/config.toml`, or
+/// `~/.config/hashline-tools/config.toml` when `XDG_CONFIG_HOME` is unset.
+pub fn default_config_path() -> Option<PathBuf> {
+    let non_empty = |name| std::env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+    let config_home = non_empty("XDG_CONFIG_HOME").or_else(|| non_empty("HOME").map(|home| home.join(".config")))?;
+    Some(config_home.join("hashline-tools").join("config.toml"))
+}
+
+/// Defaults for command-line options, from a TOML file:
+///
+/// ```toml
+/// anchors = "scoped"
+/// format = "json"
+/// mismatch_context = 4
+/// memory_budget = "512M"
+/// ```
+///
+/// A flag given on the command line wins over the file. Unknown keys are
+/// rejected, so a typo is not silently ignored.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// `--anchors`
+    pub anchors: Option<AnchorMode>,
+    /// `--format`, for every command that has it.
+    pub format: Option<OutputFormat>,
+    /// `--diff-format`
+    pub diff_format: Option<DiffFormat>,
+    /// `--mismatch-context`
+    pub mismatch_context: Option<usize>,
+    /// `--fuzzy-threshold`
+    pub fuzzy_threshold: Option<f64>,
+    /// `--fuzzy-max-candidates`
+    pub fuzzy_max_candidates: Option<usize>,
+    /// `false` for `--no-editorconfig`.
+    pub editorconfig: Option<bool>,
+    /// `false` to stop recording undo checkpoints for writes.
+    pub checkpoints: Option<bool>,
+    /// `--journal-dir`
+    pub journal_dir: Option<PathBuf>,
+    /// `--backup=DIR`
+    pub backup_dir: Option<PathBuf>,
+    /// `--hash-cache=DIR`
+    pub hash_cache: Option<PathBuf>,
+    /// `--memory-budget`, as bytes or a size such as `"512M"`.
+    #[serde(deserialize_with = "byte_size")]
+    pub memory_budget: Option<u64>,
+    /// `--max-payload`, as bytes or a size such as `"256M"`.
+    #[serde(deserialize_with = "byte_size")]
+    pub max_payload: Option<u64>,
+    /// `--max-file-size`, as bytes or a size such as `"10M"`.
+    #[serde(deserialize_with = "byte_size")]
+    pub max_file_size: Option<u64>,
+    /// `--max-edits`
+    pub max_edits: Option<usize>,
+    /// `--max-payload-lines`
+    pub max_payload_lines: Option<usize>,
+    /// `--audit-log`, used when neither the flag nor `HASHLINE_AUDIT_LOG`
+    /// names one.
+    pub audit_log: Option<PathBuf>,
+    /// `--verify-cmd`
+    pub verify_cmd: Option<String>,
+    /// `--record-stats=FILE`, used when neither the flag nor
+    /// `HASHLINE_STATS` names one.
+    pub stats_file: Option<PathBuf>,
+}
+
+fn byte_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Size {
+        Bytes(u64),
+        Text(String),
+    }
+    match Option::<Size>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Size::Bytes(bytes)) => Ok(Some(bytes)),
+        Some(Size::Text(text)) => parse_byte_size(&text).map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+impl Config {
+    /// Read the configuration file at `path`.
+    pub fn load(path: &Path) -> Result<Config, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Failed to read config {}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| format!("Invalid config {}: {}", path.display(), e))
+    }
+
+    /// The configuration in the file `HASHLINE_CONFIG` names, which must
+    /// exist, or else in the file at [`default_config_path`] if there is
+    /// one. Without either, nothing is configured.
+    pub fn from_env() -> Result<Config, String> {
+        if let Some(path) = std::env::var_os(CONFIG_ENV_VAR).filter(|v| !v.is_empty()) {
+            return Config::load(Path::new(&path));
+        }
+        match default_config_path() {
+            Some(path) if path.is_file() => Config::load(&path),
+            _ => Ok(Config::default()),
+        }
+    }
+
+    /// Make the configured values the defaults of `command`'s arguments,
+    /// so flags given on the command line still win.
+    pub fn apply_defaults(&self, mut command: clap::Command) -> clap::Command {
+        let mut globals: Vec<(&'static str, String)> = Vec::new();
+        let mut local: Vec<(&'static str, String)> = Vec::new();
+        if let Some(anchors) = self.anchors {
+            globals.push(("anchors", value_name(anchors)));
+        }
+        if self.editorconfig == Some(false) {
+            globals.push(("no_editorconfig", true.to_string()));
+        }
+        if let Some(dir) = &self.journal_dir {
+            globals.push(("journal_dir", dir.display().to_string()));
+        }
+        if let Some(dir) = &self.hash_cache {
+            globals.push(("hash_cache", dir.display().to_string()));
+        }
+        if let Some(dir) = &self.backup_dir {
+            globals.push(("backup", dir.display().to_string()));
+        }
+        if let Some(budget) = self.memory_budget {
+            globals.push(("memory_budget", budget.to_string()));
+        }
+        if let Some(max) = self.max_payload {
+            globals.push(("max_payload", max.to_string()));
+        }
+        if let Some(max) = self.max_file_size {
+            globals.push(("max_file_size", max.to_string()));
+        }
+        if let Some(max) = self.max_edits {
+            globals.push(("max_edits", max.to_string()));
+        }
+        if let Some(max) = self.max_payload_lines {
+            globals.push(("max_payload_lines", max.to_string()));
+        }
+        if let Some(threshold) = self.fuzzy_threshold {
+            globals.push(("fuzzy_threshold", threshold.to_string()));
+        }
+        if let Some(max) = self.fuzzy_max_candidates {
+            globals.push(("fuzzy_max_candidates", max.to_string()));
+        }
+        if let Some(verify_cmd) = &self.verify_cmd {
+            globals.push(("verify_cmd", verify_cmd.clone()));
+        }
+        if let Some(format) = self.format {
+            local.push(("format", value_name(format)));
+        }
+        if let Some(context) = self.mismatch_context {
+            local.push(("mismatch_context", context.to_string()));
+        }
+        if let Some(diff_format) = self.diff_format {
+            local.push(("diff_format", value_name(diff_format)));
+        }
+        // clap only takes defaults borrowed for the life of the program,
+        // which is how long the command lives anyway
+        let leak = |(id, default): (&'static str, String)| (id, &*default.leak());
+        let globals: Vec<(&str, &'static str)> = globals.into_iter().map(leak).collect();
+        let local: Vec<(&str, &'static str)> = local.into_iter().map(leak).collect();
+        for (id, default) in globals {
+            command = command.mut_arg(id, |arg| arg.default_value(default));
+        }
+        let subcommands: Vec<String> = command.get_subcommands().map(|s| s.get_name().to_string()).collect();
+        for name in subcommands {
+            command = command.mut_subcommand(name, |mut subcommand| {
+                for (id, default) in &local {
+                    if subcommand.get_arguments().any(|arg| arg.get_id() == id) {
+                        subcommand = subcommand.mut_arg(id, |arg| arg.default_value(*default));
+                    }
+                }
+                subcommand
+            });
+        }
+        command
+    }
+}
+
+/// The name `value` is given as on the command line.
+pub(crate) fn value_name(value: impl ValueEnum) -> String {
+    value.to_possible_value().expect("no values are skipped").get_name().to_string()
+}
@@ -0,0 +1,191 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::{ApplyOptions, EditOptions, EditorConfig, EndOfLine, IndentStyle};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// EditorConfig Files
+// ═══════════════════════════════════════════════════════════════════════════
+
+pub const EDITORCONFIG_FILE_NAME: &str = ".editorconfig";
+
+impl EditorConfig {
+    /// The properties `.editorconfig` files give `path`. Files are read from
+    /// the path's directory upwards until one declares `root = true`; nearer
+    /// files and later sections override earlier ones.
+    pub fn for_file(path: impl AsRef<Path>) -> io::Result<EditorConfig> {
+        let path = std::path::absolute(path)?;
+        let mut found = Vec::new();
+        for dir in path.ancestors().skip(1) {
+            match fs::read_to_string(dir.join(EDITORCONFIG_FILE_NAME)) {
+                Ok(text) => {
+                    let file = ConfigFile::parse(&text);
+                    let root = file.root;
+                    found.push((dir, file));
+                    if root {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut properties: HashMap<String, String> = HashMap::new();
+        for (dir, file) in found.iter().rev() {
+            for section in file.sections.iter().filter(|s| glob_matches(&s.glob, dir, &path)) {
+                for (key, value) in &section.properties {
+                    if value == "unset" {
+                        properties.remove(key);
+                    } else {
+                        properties.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        Ok(EditorConfig::from_properties(&properties))
+    }
+
+    fn from_properties(properties: &HashMap<String, String>) -> EditorConfig {
+        let get = |key: &str| properties.get(key).map(String::as_str);
+        let tab_width = get("tab_width").and_then(|v| v.parse().ok());
+        EditorConfig {
+            indent_style: match get("indent_style") {
+                Some("tab") => Some(IndentStyle::Tab),
+                Some("space") => Some(IndentStyle::Space),
+                _ => None,
+            },
+            indent_size: match get("indent_size") {
+                Some("tab") => tab_width,
+                Some(size) => size.parse().ok().or(tab_width),
+                None => tab_width,
+            },
+            end_of_line: match get("end_of_line") {
+                Some("lf") => Some(EndOfLine::Lf),
+                Some("crlf") => Some(EndOfLine::Crlf),
+                Some("cr") => Some(EndOfLine::Cr),
+                _ => None,
+            },
+            insert_final_newline: match get("insert_final_newline") {
+                Some("true") => Some(true),
+                Some("false") => Some(false),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl EditOptions {
+    /// The apply options for editing `path`: the anchor mode for its
+    /// extension and, with `editorconfig` set, the properties its
+    /// `.editorconfig` files give it filling in whatever `apply.editorconfig`
+    /// leaves unset. Errors are plain messages, for the caller to render.
+    pub(crate) fn apply_options_for(&self, path: &Path) -> Result<Cow<'_, ApplyOptions>, String> {
+        let anchor_mode = self.apply.anchor_mode.for_path(path);
+        if !self.editorconfig {
+            if anchor_mode == self.apply.anchor_mode {
+                return Ok(Cow::Borrowed(&self.apply));
+            }
+            return Ok(Cow::Owned(ApplyOptions { anchor_mode, ..self.apply.clone() }));
+        }
+        let found = EditorConfig::for_file(path)
+            .map_err(|e| format!("Failed to read {}: {}", EDITORCONFIG_FILE_NAME, e))?;
+        let set = &self.apply.editorconfig;
+        let editorconfig = EditorConfig {
+            indent_style: set.indent_style.or(found.indent_style),
+            indent_size: set.indent_size.or(found.indent_size),
+            end_of_line: set.end_of_line.or(found.end_of_line),
+            insert_final_newline: set.insert_final_newline.or(found.insert_final_newline),
+        };
+        Ok(Cow::Owned(ApplyOptions { anchor_mode, editorconfig, ..self.apply.clone() }))
+    }
+}
+
+/// One parsed `.editorconfig` file.
+struct ConfigFile {
+    root: bool,
+    sections: Vec<Section>,
+}
+
+struct Section {
+    glob: String,
+    /// Lowercased keys and values, in file order.
+    properties: Vec<(String, String)>,
+}
+
+impl ConfigFile {
+    fn parse(text: &str) -> ConfigFile {
+        let mut file = ConfigFile { root: false, sections: Vec::new() };
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with(['#', ';']) {
+                continue;
+            }
+            if let Some(glob) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                file.sections.push(Section { glob: glob.to_string(), properties: Vec::new() });
+            } else if let Some((key, value)) = line.split_once('=') {
+                let key = key.trim().to_ascii_lowercase();
+                let value = value.trim().to_ascii_lowercase();
+                match file.sections.last_mut() {
+                    Some(section) => section.properties.push((key, value)),
+                    None if key == "root" => file.root = value == "true",
+                    None => {}
+                }
+            }
+        }
+        file
+    }
+}
+
+/// Whether a section glob from the `.editorconfig` in `dir` covers `path`.
+/// Globs without a `/` match file names at any depth; the others are
+/// relative to `dir`.
+fn glob_matches(glob: &str, dir: &Path, path: &Path) -> bool {
+    let relative = match glob.strip_prefix('/') {
+        Some(anchored) => anchored.to_string(),
+        None if glob.contains('/') => glob.to_string(),
+        None => format!("**/{}", glob),
+    };
+    // `**` inside a component crosses directories here, but not in `glob`
+    let relative: Vec<String> = relative.split('/')
+        .map(|component| match component {
+            "**" => component.to_string(),
+            _ if component.starts_with("**") => format!("**/*{}", &component[2..]),
+            _ if component.ends_with("**") => format!("{}*/**", &component[..component.len() - 2]),
+            _ => component.replace("**", "*"),
+        })
+        .collect();
+    let relative = relative.join("/");
+    let prefix = glob::Pattern::escape(&dir.to_string_lossy());
+    let options = glob::MatchOptions { require_literal_separator: true, ..glob::MatchOptions::new() };
+    expand_braces(&relative).iter().any(|alternative| {
+        glob::Pattern::new(&format!("{}/{}", prefix, alternative))
+            .is_ok_and(|pattern| pattern.matches_path_with(path, options))
+    })
+}
+
+/// Expand `{a,b}` alternatives and `{1..3}` number ranges into separate globs.
+fn expand_braces(glob: &str) -> Vec<String> {
+    let Some(open) = glob.find('{') else {
+        return vec![glob.to_string()];
+    };
+    let Some(close) = glob[open..].find('}').map(|i| open + i) else {
+        return vec![glob.to_string()];
+    };
+    let (head, inner, tail) = (&glob[..open], &glob[open + 1..close], &glob[close + 1..]);
+    let choices: Vec<String> = match inner.split_once("..").map(|(a, b)| (a.parse::<i64>(), b.parse::<i64>())) {
+        Some((Ok(from), Ok(to))) => (from.min(to)..=from.max(to)).map(|n| n.to_string()).collect(),
+        _ if inner.contains(',') => inner.split(',').map(str::to_string).collect(),
+        // A single word in braces is matched literally
+        _ => {
+            return expand_braces(tail).into_iter()
+                .map(|rest| format!("{}[{{]{}[}}]{}", head, inner, rest))
+                .collect();
+        }
+    };
+    choices.iter()
+        .flat_map(|choice| expand_braces(&format!("{}{}{}", head, choice, tail)))
+        .collect()
+}
@@ -0,0 +1,92 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Atomic File Writes
+// ═══════════════════════════════════════════════════════════════════════════
+//
+// A file is never written in place: the new content goes to a temp file in
+// the same directory, which is synced and then renamed over the target. A
+// crash leaves either the old content or the new, never a truncated mix,
+// at worst with a stray `.NAME.hashline-*.tmp` file beside it.
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Replace the content of `path` with `content` atomically, keeping its
+/// permissions and, where the process may set them, its owner and group.
+/// A symlink is followed, so the file it points to is replaced rather than
+/// the link. Hard links to the old file keep the old content.
+pub fn write_atomic(path: &Path, content: impl AsRef<[u8]>) -> io::Result<()> {
+    let target = resolve_symlink(path)?;
+    let temp = stage_file(&target, content.as_ref())?;
+    if let Err(e) = fs::rename(&temp, &target) {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+    sync_dir(&target);
+    Ok(())
+}
+
+/// Write `content` to a synced temp file next to `target`, with the
+/// target's permissions and owner, ready to be renamed over it.
+pub(crate) fn stage_file(target: &Path, content: &[u8]) -> io::Result<PathBuf> {
+    let file_name = target.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    let temp = target.with_file_name(format!(
+        ".{}.hashline-{}-{}.tmp",
+        file_name,
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut file = OpenOptions::new().write(true).create_new(true).open(&temp)?;
+    let staged = file.write_all(content)
+        .and_then(|_| copy_metadata(target, &file))
+        .and_then(|_| file.sync_all());
+    if let Err(e) = staged {
+        drop(file);
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+    Ok(temp)
+}
+
+/// The file writing to `path` should replace: the one a symlink points to,
+/// or `path` itself.
+pub(crate) fn resolve_symlink(path: &Path) -> io::Result<PathBuf> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => fs::canonicalize(path),
+        _ => Ok(path.to_path_buf()),
+    }
+}
+
+/// Give `temp` the permissions and owner of `target`, if it exists. Only
+/// root may give a file away, so a failed change of owner is not an error:
+/// the file then belongs to whoever wrote it, as after any rewrite.
+fn copy_metadata(target: &Path, temp: &File) -> io::Result<()> {
+    let Ok(metadata) = fs::metadata(target) else {
+        return Ok(());
+    };
+    // Owner first: changing it clears setuid and setgid bits
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let _ = std::os::unix::fs::fchown(temp, Some(metadata.uid()), Some(metadata.gid()));
+    }
+    temp.set_permissions(metadata.permissions())
+}
+
+/// Sync the directory holding `path`, so the rename survives a crash too.
+/// Not every platform or filesystem supports it; the content is already
+/// synced either way.
+pub(crate) fn sync_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
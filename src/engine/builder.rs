@@ -0,0 +1,340 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::format;
+
+use crate::{AnchorRef, Column, EditOp, HashlineEdit, HashlineError};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Edit Builder
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Start building an edit batch. See [`EditBuilder`].
+pub fn edits() -> EditBuilder {
+    EditBuilder::default()
+}
+
+/// Anything usable as an anchor: an [`AnchorRef`] or a `"LINE#HASH"` string.
+pub trait IntoAnchor {
+    fn into_anchor(self) -> Result<AnchorRef, HashlineError>;
+}
+
+impl IntoAnchor for AnchorRef {
+    fn into_anchor(self) -> Result<AnchorRef, HashlineError> {
+        Ok(self)
+    }
+}
+
+impl IntoAnchor for &AnchorRef {
+    fn into_anchor(self) -> Result<AnchorRef, HashlineError> {
+        Ok(self.clone())
+    }
+}
+
+impl IntoAnchor for &str {
+    fn into_anchor(self) -> Result<AnchorRef, HashlineError> {
+        self.parse()
+    }
+}
+
+impl IntoAnchor for String {
+    fn into_anchor(self) -> Result<AnchorRef, HashlineError> {
+        self.parse()
+    }
+}
+
+/// Fluent construction of a `Vec<HashlineEdit>`:
+///
+/// ```
+/// # use hashline_tools::edits;
+/// let batch = edits()
+///     .replace("2#TJ", ["fn main() {"])
+///     .append_after("5#QX", ["    run();"])
+///     .delete_range("8#ZP", "9#MV")
+///     .build()?;
+/// assert_eq!(batch.len(), 3);
+/// # Ok::<(), hashline_tools::HashlineError>(())
+/// ```
+///
+/// Malformed anchors and inverted ranges are reported by [`EditBuilder::build`],
+/// together with the index of the edit they belong to. Whether the anchors
+/// match a file is only checked when the batch is applied.
+#[derive(Debug, Clone, Default)]
+pub struct EditBuilder {
+    edits: Vec<HashlineEdit>,
+    /// Edits requested so far, including ones rejected with an error.
+    requested: usize,
+    /// Whether the most recent request was rejected.
+    last_rejected: bool,
+    errors: Vec<String>,
+}
+
+impl EditBuilder {
+    /// Replace the anchored line.
+    pub fn replace<S: Into<String>>(self, pos: impl IntoAnchor, lines: impl IntoIterator<Item = S>) -> Self {
+        self.push_replace(pos.into_anchor(), None, lines)
+    }
+
+    /// Replace the lines from `pos` through `end`, inclusive.
+    pub fn replace_range<S: Into<String>>(
+        self,
+        pos: impl IntoAnchor,
+        end: impl IntoAnchor,
+        lines: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.push_replace(pos.into_anchor(), Some(end.into_anchor()), lines)
+    }
+
+    /// Delete the anchored line.
+    pub fn delete(self, pos: impl IntoAnchor) -> Self {
+        self.push_range(pos.into_anchor(), None, |pos, end| HashlineEdit::Delete { pos, end, expected_lines: None })
+    }
+
+    /// Delete the lines from `pos` through `end`, inclusive.
+    pub fn delete_range(self, pos: impl IntoAnchor, end: impl IntoAnchor) -> Self {
+        self.push_range(pos.into_anchor(), Some(end.into_anchor()), |pos, end| {
+            HashlineEdit::Delete { pos, end, expected_lines: None }
+        })
+    }
+
+    /// Move the anchored line to just below `after`.
+    pub fn move_line(self, pos: impl IntoAnchor, after: impl IntoAnchor) -> Self {
+        self.push_move(pos.into_anchor(), None, after.into_anchor())
+    }
+
+    /// Move the lines from `pos` through `end`, inclusive, to just below
+    /// `after`.
+    pub fn move_range(self, pos: impl IntoAnchor, end: impl IntoAnchor, after: impl IntoAnchor) -> Self {
+        self.push_move(pos.into_anchor(), Some(end.into_anchor()), after.into_anchor())
+    }
+
+    /// Insert lines after the anchored line.
+    pub fn append_after<S: Into<String>>(mut self, pos: impl IntoAnchor, lines: impl IntoIterator<Item = S>) -> Self {
+        let index = self.next_index();
+        if let Some(pos) = self.anchor(index, pos.into_anchor()) {
+            self.push(HashlineEdit::Append { pos: Some(pos), lines: collect(lines), expected_lines: None });
+        }
+        self
+    }
+
+    /// Insert lines at the end of the file.
+    pub fn append<S: Into<String>>(mut self, lines: impl IntoIterator<Item = S>) -> Self {
+        self.next_index();
+        self.push(HashlineEdit::Append { pos: None, lines: collect(lines), expected_lines: None });
+        self
+    }
+
+    /// Insert lines before the anchored line.
+    pub fn prepend_before<S: Into<String>>(mut self, pos: impl IntoAnchor, lines: impl IntoIterator<Item = S>) -> Self {
+        let index = self.next_index();
+        if let Some(pos) = self.anchor(index, pos.into_anchor()) {
+            self.push(HashlineEdit::Prepend { pos: Some(pos), lines: collect(lines), expected_lines: None });
+        }
+        self
+    }
+
+    /// Insert lines at the start of the file.
+    pub fn prepend<S: Into<String>>(mut self, lines: impl IntoIterator<Item = S>) -> Self {
+        self.next_index();
+        self.push(HashlineEdit::Prepend { pos: None, lines: collect(lines), expected_lines: None });
+        self
+    }
+
+    /// Set the value at `path` in a JSON, YAML or TOML file. See
+    /// [`HashlineEdit::SetPath`].
+    pub fn set_path(mut self, path: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+        self.next_index();
+        self.push(HashlineEdit::SetPath { path: path.into(), value: value.into(), pos: None, format: None });
+        self
+    }
+
+    /// Set one field of the anchored CSV or TSV row. See [`HashlineEdit::SetCell`].
+    pub fn set_cell(mut self, pos: impl IntoAnchor, column: impl Into<Column>, value: impl Into<String>) -> Self {
+        let index = self.next_index();
+        if let Some(pos) = self.anchor(index, pos.into_anchor()) {
+            self.push(HashlineEdit::SetCell { pos, column: column.into(), value: value.into(), delimiter: None });
+        }
+        self
+    }
+
+    /// Set one column of the CSV or TSV rows from `pos` through `end`, with
+    /// one value per row or a single value for them all.
+    pub fn set_column<S: Into<String>>(
+        mut self,
+        pos: impl IntoAnchor,
+        end: impl IntoAnchor,
+        column: impl Into<Column>,
+        values: impl IntoIterator<Item = S>,
+    ) -> Self {
+        let index = self.next_index();
+        let (Some(pos), Some(end)) = (self.anchor(index, pos.into_anchor()), self.anchor(index, end.into_anchor())) else {
+            return self;
+        };
+        if pos.line > end.line {
+            self.errors.push(format!(
+                "edits[{}]: range start line {} must be <= end line {}",
+                index, pos.line, end.line
+            ));
+            return self;
+        }
+        let column = column.into();
+        self.push(HashlineEdit::SetColumn { pos, end: Some(end), column, values: collect(values), delimiter: None });
+        self
+    }
+
+    /// Add a custom [`EditOp`] over the anchors its `range()` names.
+    pub fn custom(mut self, op: impl EditOp + 'static) -> Self {
+        let index = self.next_index();
+        let (pos, end) = op.range();
+        let (Some(pos), Some(end)) = (self.anchor(index, Ok(pos)), self.anchor(index, Ok(end))) else {
+            return self;
+        };
+        if pos.line > end.line {
+            self.errors.push(format!(
+                "edits[{}]: range start line {} must be <= end line {}",
+                index, pos.line, end.line
+            ));
+            return self;
+        }
+        self.push(HashlineEdit::custom(op));
+        self
+    }
+
+    /// Set `expected_lines` on the edit added last.
+    pub fn expecting<S: Into<String>>(mut self, expected: impl IntoIterator<Item = S>) -> Self {
+        // The edit before was rejected; its error is already recorded
+        if self.last_rejected {
+            return self;
+        }
+        let expected = Some(collect(expected));
+        match self.edits.last_mut() {
+            Some(HashlineEdit::Replace { expected_lines, .. })
+            | Some(HashlineEdit::Delete { expected_lines, .. })
+            | Some(HashlineEdit::Append { pos: Some(_), expected_lines, .. })
+            | Some(HashlineEdit::Prepend { pos: Some(_), expected_lines, .. }) => *expected_lines = expected,
+            _ => self.errors.push(format!(
+                "edits[{}]: expected_lines needs an anchored edit",
+                self.requested.saturating_sub(1)
+            )),
+        }
+        self
+    }
+
+    /// The finished batch, or every problem found while building it.
+    pub fn build(self) -> Result<Vec<HashlineEdit>, HashlineError> {
+        if self.errors.is_empty() {
+            Ok(self.edits)
+        } else {
+            Err(HashlineError::Validation(self.errors))
+        }
+    }
+
+    fn push_replace<S: Into<String>>(
+        self,
+        pos: Result<AnchorRef, HashlineError>,
+        end: Option<Result<AnchorRef, HashlineError>>,
+        lines: impl IntoIterator<Item = S>,
+    ) -> Self {
+        let lines = collect(lines);
+        self.push_range(pos, end, |pos, end| HashlineEdit::Replace { pos, end, lines, expected_lines: None })
+    }
+
+    /// Add the edit `make` builds over `pos..=end`, once both anchors check out.
+    fn push_range(
+        mut self,
+        pos: Result<AnchorRef, HashlineError>,
+        end: Option<Result<AnchorRef, HashlineError>>,
+        make: impl FnOnce(AnchorRef, Option<AnchorRef>) -> HashlineEdit,
+    ) -> Self {
+        let index = self.next_index();
+        let pos = self.anchor(index, pos);
+        let end = match end {
+            Some(end) => match self.anchor(index, end) {
+                Some(end) => Some(end),
+                None => return self,
+            },
+            None => None,
+        };
+        let Some(pos) = pos else {
+            return self;
+        };
+        if let Some(end) = &end {
+            if pos.line > end.line {
+                self.errors.push(format!(
+                    "edits[{}]: range start line {} must be <= end line {}",
+                    index, pos.line, end.line
+                ));
+                return self;
+            }
+        }
+        self.push(make(pos, end));
+        self
+    }
+
+    /// Index of the edit being requested. It counts as rejected until pushed.
+    fn next_index(&mut self) -> usize {
+        self.requested += 1;
+        self.last_rejected = true;
+        self.requested - 1
+    }
+
+    fn push(&mut self, edit: HashlineEdit) {
+        self.edits.push(edit);
+        self.last_rejected = false;
+    }
+
+    /// Unwrap a parsed anchor, recording the error against edit `index`.
+    fn push_move(
+        mut self,
+        pos: Result<AnchorRef, HashlineError>,
+        end: Option<Result<AnchorRef, HashlineError>>,
+        after: Result<AnchorRef, HashlineError>,
+    ) -> Self {
+        let index = self.next_index();
+        let pos = self.anchor(index, pos);
+        let end = match end {
+            Some(end) => match self.anchor(index, end) {
+                Some(end) => Some(end),
+                None => return self,
+            },
+            None => None,
+        };
+        let (Some(pos), Some(after)) = (pos, self.anchor(index, after)) else {
+            return self;
+        };
+        let end_line = end.as_ref().map_or(pos.line, |e| e.line);
+        if pos.line > end_line {
+            self.errors.push(format!(
+                "edits[{}]: range start line {} must be <= end line {}",
+                index, pos.line, end_line
+            ));
+            return self;
+        }
+        if (pos.line..=end_line).contains(&after.line) {
+            self.errors.push(format!(
+                "edits[{}]: move target line {} is inside the moved lines {}-{}",
+                index, after.line, pos.line, end_line
+            ));
+            return self;
+        }
+        self.push(HashlineEdit::Move { pos, end, after });
+        self
+    }
+
+    fn anchor(&mut self, index: usize, anchor: Result<AnchorRef, HashlineError>) -> Option<AnchorRef> {
+        match anchor {
+            Ok(anchor) if anchor.line == 0 => {
+                self.errors.push(format!("edits[{}]: Line 0 must be >= 1", index));
+                None
+            }
+            Ok(anchor) => Some(anchor),
+            Err(e) => {
+                self.errors.push(format!("edits[{}]: {}", index, e));
+                None
+            }
+        }
+    }
+}
+
+fn collect<S: Into<String>>(lines: impl IntoIterator<Item = S>) -> Vec<String> {
+    lines.into_iter().map(Into::into).collect()
+}
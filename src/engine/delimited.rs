@@ -0,0 +1,247 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::format;
+use core::fmt;
+use core::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{AnchorRef, EditOp, HashedFile, HashlineEdit};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Delimited Files
+// ═══════════════════════════════════════════════════════════════════════════
+//
+// `set_cell` and `set_column` edit single fields of CSV and TSV rows, so a
+// wide row need not be retyped whole to change one value. The column is
+// resolved against the header row when the batch is planned, and the edit
+// becomes a custom op over its rows that rewrites only the field's text:
+// the delimiter and the other fields, quoted or not, are left as they are.
+
+/// A column of a delimited file: its 0-based index, or its name in the
+/// header row (line 1).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+pub enum Column {
+    Index(usize),
+    Name(String),
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Column::Index(index) => write!(f, "{}", index),
+            Column::Name(name) => write!(f, "`{}`", name),
+        }
+    }
+}
+
+impl From<usize> for Column {
+    fn from(index: usize) -> Self {
+        Column::Index(index)
+    }
+}
+
+impl From<&str> for Column {
+    fn from(name: &str) -> Self {
+        Column::Name(name.to_string())
+    }
+}
+
+impl From<String> for Column {
+    fn from(name: String) -> Self {
+        Column::Name(name)
+    }
+}
+
+/// One field of a delimited row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelimitedField {
+    /// Byte range of the field's text in the row, quotes included.
+    pub span: Range<usize>,
+    /// The field's value, without quotes and with `""` unescaped.
+    pub value: String,
+    /// Whether the field is written in double quotes.
+    pub quoted: bool,
+}
+
+/// Delimiters [`detect_delimiter`] chooses from, preferred in this order on a tie.
+const DELIMITERS: [char; 4] = [',', '\t', ';', '|'];
+
+/// Guess the delimiter from the first line that is not blank: whichever of
+/// comma, tab, semicolon and pipe it has most of outside quotes. A comma
+/// when it has none of them.
+pub fn detect_delimiter(content: &str) -> char {
+    let first = content.lines().find(|line| !line.trim().is_empty()).unwrap_or("");
+    let mut counts = [0usize; DELIMITERS.len()];
+    let mut quoted = false;
+    for c in first.chars() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if let Some(i) = DELIMITERS.iter().position(|&d| d == c).filter(|_| !quoted) {
+            counts[i] += 1;
+        }
+    }
+    // `max_by_key` keeps the last maximum, so search from the end
+    let best = (0..DELIMITERS.len()).rev().max_by_key(|&i| counts[i]).unwrap_or(0);
+    if counts[best] == 0 { ',' } else { DELIMITERS[best] }
+}
+
+/// Split one row into its fields. A quoted field must close on the same
+/// line; rows with values spanning lines cannot be split.
+pub fn split_fields(row: &str, delimiter: char) -> Result<Vec<DelimitedField>, String> {
+    let mut fields = Vec::new();
+    let mut start = 0;
+    loop {
+        let rest = &row[start..];
+        let (field, next) = if let Some(quoted) = rest.strip_prefix('"') {
+            let mut value = String::new();
+            let mut chars = quoted.char_indices().peekable();
+            let close = loop {
+                match chars.next() {
+                    Some((_, '"')) if chars.peek().map(|&(_, c)| c) == Some('"') => {
+                        chars.next();
+                        value.push('"');
+                    }
+                    Some((i, '"')) => break start + 1 + i + 1,
+                    Some((_, c)) => value.push(c),
+                    None => return Err(format!("field {} opens a quote that does not close on this line", fields.len())),
+                }
+            };
+            match row[close..].chars().next() {
+                None => (DelimitedField { span: start..close, value, quoted: true }, None),
+                Some(c) if c == delimiter => {
+                    (DelimitedField { span: start..close, value, quoted: true }, Some(close + c.len_utf8()))
+                }
+                Some(_) => return Err(format!("field {} has text after its closing quote", fields.len())),
+            }
+        } else {
+            match rest.find(delimiter) {
+                Some(i) => (
+                    DelimitedField { span: start..start + i, value: rest[..i].to_string(), quoted: false },
+                    Some(start + i + delimiter.len_utf8()),
+                ),
+                None => (DelimitedField { span: start..row.len(), value: rest.to_string(), quoted: false }, None),
+            }
+        };
+        fields.push(field);
+        match next {
+            Some(next) => start = next,
+            None => return Ok(fields),
+        }
+    }
+}
+
+/// `value` written as a field in place of `original`: quoted if the
+/// original was, or if it holds the delimiter or a quote.
+fn render_field(value: &str, original: &DelimitedField, delimiter: char) -> Result<String, String> {
+    if value.contains(['\n', '\r']) {
+        return Err(format!("{:?} spans lines; values must fit on their row", value));
+    }
+    if original.quoted || value.contains(delimiter) || value.contains('"') {
+        Ok(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+/// Turn a `set_cell` or `set_column` edit into a custom op over rows
+/// `pos..=end`. `values` holds one value per row, or one for them all.
+pub(crate) fn resolve_set_cells(
+    file: &HashedFile,
+    name: &'static str,
+    pos: &AnchorRef,
+    end: Option<&AnchorRef>,
+    column: &Column,
+    values: &[String],
+    delimiter: Option<char>,
+) -> Result<HashlineEdit, String> {
+    let lines = file.lines();
+    let delimiter = delimiter.unwrap_or_else(|| {
+        detect_delimiter(lines.iter().find(|line| !line.trim().is_empty()).map_or("", |line| line))
+    });
+    let index = match column {
+        Column::Index(index) => *index,
+        Column::Name(name) => {
+            let header = lines.first().ok_or("the file has no header row")?;
+            let header = split_fields(header, delimiter).map_err(|e| format!("header row: {}", e))?;
+            let mut matching = header.iter().enumerate().filter(|(_, field)| field.value == *name);
+            match (matching.next(), matching.next()) {
+                (Some((index, _)), None) => index,
+                (Some(_), Some(_)) => return Err(format!("several columns are named `{}`; use an index", name)),
+                (None, _) => {
+                    let names: Vec<&str> = header.iter().map(|field| field.value.as_str()).collect();
+                    return Err(format!("no column `{}` in the header row ({})", name, names.join(", ")));
+                }
+            }
+        }
+    };
+    let end = end.unwrap_or(pos);
+    let rows = end.line.saturating_sub(pos.line) + 1;
+    if values.len() != 1 && values.len() != rows {
+        return Err(format!(
+            "{} values for {} rows; give one per row, or one for them all",
+            values.len(),
+            rows
+        ));
+    }
+    Ok(HashlineEdit::custom(SetCellsOp {
+        name,
+        pos: pos.clone(),
+        end: end.clone(),
+        column: column.clone(),
+        index,
+        values: values.to_vec(),
+        delimiter,
+    }))
+}
+
+#[derive(Debug)]
+struct SetCellsOp {
+    name: &'static str,
+    pos: AnchorRef,
+    end: AnchorRef,
+    column: Column,
+    /// The column's 0-based index.
+    index: usize,
+    values: Vec<String>,
+    delimiter: char,
+}
+
+impl SetCellsOp {
+    fn value(&self, row: usize) -> &str {
+        self.values.get(row).unwrap_or(&self.values[0])
+    }
+
+    /// `line` with the column's field replaced by the value for `row`.
+    fn rewrite(&self, row: usize, line: &str) -> Result<String, String> {
+        let fields = split_fields(line, self.delimiter).map_err(|e| format!("row {}: {}", self.pos.line + row, e))?;
+        let field = fields.get(self.index).ok_or_else(|| {
+            format!("row {} has {} fields, so no column {}", self.pos.line + row, fields.len(), self.column)
+        })?;
+        let text = render_field(self.value(row), field, self.delimiter)?;
+        Ok(format!("{}{}{}", &line[..field.span.start], text, &line[field.span.end..]))
+    }
+}
+
+impl EditOp for SetCellsOp {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn range(&self) -> (AnchorRef, AnchorRef) {
+        (self.pos.clone(), self.end.clone())
+    }
+
+    fn validate(&self, lines: &[String]) -> Result<(), String> {
+        lines.iter().enumerate().try_for_each(|(row, line)| self.rewrite(row, line).map(drop))
+    }
+
+    fn apply(&self, lines: &[String]) -> Vec<String> {
+        lines.iter()
+            .enumerate()
+            .map(|(row, line)| self.rewrite(row, line).unwrap_or_else(|_| line.clone()))
+            .collect()
+    }
+}
@@ -0,0 +1,316 @@
+use alloc::borrow::Cow;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::format;
+use core::fmt::Write;
+use core::ops::Range;
+
+use serde::Serialize;
+
+use super::line_diff::{diff_lines, LineRun};
+use crate::{AnchorRef, HashedFile};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Hash-aware Diff
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Lines of context shown around each change.
+pub const DIFF_CONTEXT_LINES: usize = 5;
+
+/// What a [`DiffLine`] represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DiffLineKind {
+    Context,
+    Insert,
+    Delete,
+}
+
+/// One line of a hash-aware diff.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    /// 1-indexed line number in the new content.
+    pub line: usize,
+    /// Fresh hash in the new content; `None` for deleted lines.
+    pub hash: Option<String>,
+    pub text: String,
+}
+
+/// A contiguous run of changed lines and their context.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DiffHunk {
+    pub lines: Vec<DiffLine>,
+}
+
+impl core::fmt::Display for DiffLine {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let sign = match self.kind {
+            DiffLineKind::Context => ' ',
+            DiffLineKind::Insert => '+',
+            DiffLineKind::Delete => '-',
+        };
+        let hash = self.hash.as_deref().unwrap_or("  ");
+        write!(f, "{}{}#{}:{}", sign, self.line, hash, self.text)
+    }
+}
+
+/// Diff `old_content` against `new_content`, giving every new line its fresh
+/// hash so the anchors can be reused without re-reading the file. When
+/// nothing changed, the hunk shows the context around `first_changed_line`.
+pub fn hash_aware_diff(old_content: &str, new_content: &str, first_changed_line: usize) -> Vec<DiffHunk> {
+    hash_aware_diff_with(old_content, &HashedFile::from_content(new_content), first_changed_line)
+}
+
+/// [`hash_aware_diff`] against new content that is already hashed, e.g. by
+/// [`HashedFile::rehash_from`].
+pub fn hash_aware_diff_with(old_content: &str, new_file: &HashedFile, first_changed_line: usize) -> Vec<DiffHunk> {
+    let old_lines: Vec<&str> = old_content.lines().collect();
+    let total_new_lines = new_file.len();
+
+    // Collect all changed line numbers (in new file)
+    let changes = ChangedLines::between(&old_lines, new_file.lines());
+    let changed_new_lines: BTreeSet<usize> = changes.inserted.iter().map(|i| i + 1).collect(); // 1-indexed
+    // Deleted old lines, keyed by the new line they were just above (1-indexed)
+    let mut deleted_before: BTreeMap<usize, Vec<&str>> = BTreeMap::new();
+    for &(old, new) in &changes.deleted {
+        deleted_before.entry(new + 1).or_default().push(old_lines[old]);
+    }
+
+    // Display range around each change, merging overlapping ranges. A
+    // deletion is shown with the context of the line that took its place
+    let mut display_ranges: Vec<(usize, usize)> = changed_new_lines.iter()
+        .map(|&line| {
+            let start = line.saturating_sub(DIFF_CONTEXT_LINES).max(1);
+            (start, (line + DIFF_CONTEXT_LINES).min(total_new_lines))
+        })
+        .chain(deleted_before.keys().map(|&line| {
+            let start = line.saturating_sub(DIFF_CONTEXT_LINES).max(1);
+            (start, (line - 1 + DIFF_CONTEXT_LINES).min(total_new_lines))
+        }))
+        .collect();
+    display_ranges.sort_by_key(|r| r.0);
+    let mut merged_ranges: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in display_ranges {
+        match merged_ranges.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+            _ => merged_ranges.push((start, end)),
+        }
+    }
+    if merged_ranges.is_empty() {
+        let start = first_changed_line.saturating_sub(DIFF_CONTEXT_LINES).max(1);
+        merged_ranges.push((start, (first_changed_line + DIFF_CONTEXT_LINES).min(total_new_lines)));
+    }
+
+    merged_ranges.into_iter()
+        .map(|(range_start, range_end)| {
+            let mut lines = Vec::new();
+            // Lines deleted from the end of the file sit below the last line
+            let last = if range_end == total_new_lines { range_end + 1 } else { range_end };
+            for line_num in range_start..=last {
+                for text in deleted_before.get(&line_num).into_iter().flatten() {
+                    lines.push(DiffLine { kind: DiffLineKind::Delete, line: line_num, hash: None, text: text.to_string() });
+                }
+                if line_num > range_end {
+                    break;
+                }
+                let was_inserted = changed_new_lines.contains(&line_num);
+                lines.push(DiffLine {
+                    kind: if was_inserted { DiffLineKind::Insert } else { DiffLineKind::Context },
+                    line: line_num,
+                    hash: new_file.hash_at(line_num).map(str::to_string),
+                    text: new_file.lines()[line_num - 1].to_string(),
+                });
+            }
+            DiffHunk { lines }
+        })
+        .collect()
+}
+
+/// Render [`hash_aware_diff`] as text: `LINE#HASH:content` lines, `...`
+/// between hunks, and a closing note on the anchors not shown.
+pub fn generate_hash_aware_diff(old_content: &str, new_content: &str, first_changed_line: usize) -> String {
+    render_hash_aware_diff(&hash_aware_diff(old_content, new_content, first_changed_line), first_changed_line)
+}
+
+/// Render diff hunks as [`generate_hash_aware_diff`] does.
+pub fn render_hash_aware_diff(hunks: &[DiffHunk], first_changed_line: usize) -> String {
+    let mut output_lines: Vec<String> = Vec::new();
+    for (i, hunk) in hunks.iter().enumerate() {
+        if i > 0 {
+            output_lines.push("...".to_string());
+        }
+        output_lines.extend(hunk.lines.iter().map(|l| l.to_string()));
+    }
+
+    // Hashes chain, so every line from the first change on has a new anchor
+    output_lines.push("".to_string());
+    output_lines.push(format!(
+        "Note: Hashes shown are current. Lines not shown from line {} on have new hashes too; re-read them before anchoring to them.",
+        first_changed_line
+    ));
+    output_lines.join("\n")
+}
+
+/// Lines of context around each hunk of a [`unified_diff`], as `diff -u` shows.
+pub const UNIFIED_DIFF_CONTEXT_LINES: usize = 3;
+
+/// Diff `old_content` against `new_content` as a standard unified diff under
+/// `--- old_name` and `+++ new_name` headers, for `patch`, `git apply` and
+/// review tools. The lines carry no hashes. Empty when nothing changed.
+pub fn unified_diff(old_content: &str, new_content: &str, old_name: &str, new_name: &str) -> String {
+    let old_lines = split_lines_inclusive(old_content);
+    let new_lines = split_lines_inclusive(new_content);
+    let mut out = String::new();
+    for hunk in group_runs(diff_lines(&old_lines, &new_lines), UNIFIED_DIFF_CONTEXT_LINES) {
+        if out.is_empty() {
+            let _ = writeln!(out, "--- {}\n+++ {}", old_name, new_name);
+        }
+        let (first, last) = (&hunk[0], &hunk[hunk.len() - 1]);
+        let _ = writeln!(
+            out,
+            "@@ -{} +{} @@",
+            hunk_range(first.old.start..last.old.end),
+            hunk_range(first.new.start..last.new.end),
+        );
+        for run in &hunk {
+            let lines = if run.equal {
+                old_lines[run.old.clone()].iter().map(|line| (' ', *line)).collect::<Vec<_>>()
+            } else {
+                let deleted = old_lines[run.old.clone()].iter().map(|line| ('-', *line));
+                deleted.chain(new_lines[run.new.clone()].iter().map(|line| ('+', *line))).collect()
+            };
+            for (sign, line) in lines {
+                out.push(sign);
+                out.push_str(line);
+                if !line.ends_with(['\r', '\n']) {
+                    out.push_str("\n\\ No newline at end of file\n");
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Lines with their endings, which may be `\n`, `\r\n` or a lone `\r`.
+fn split_lines_inclusive(content: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let bytes = content.as_bytes();
+    for (i, &byte) in bytes.iter().enumerate() {
+        let ends = byte == b'\n' || (byte == b'\r' && bytes.get(i + 1) != Some(&b'\n'));
+        if ends {
+            lines.push(&content[start..=i]);
+            start = i + 1;
+        }
+    }
+    if start < content.len() {
+        lines.push(&content[start..]);
+    }
+    lines
+}
+
+/// `start,len` as in a hunk header, 1-based; `len` is left out when it is
+/// 1, and an empty range starts at the line before it.
+fn hunk_range(range: Range<usize>) -> String {
+    match range.len() {
+        1 => format!("{}", range.start + 1),
+        0 => format!("{},0", range.start),
+        len => format!("{},{}", range.start + 1, len),
+    }
+}
+
+/// The changed runs with up to `context` equal lines on each side, split
+/// wherever more than twice that many lines separate two changes.
+fn group_runs(mut runs: Vec<LineRun>, context: usize) -> Vec<Vec<LineRun>> {
+    if let Some(first) = runs.first_mut().filter(|run| run.equal) {
+        let skip = first.old.len().saturating_sub(context);
+        first.old.start += skip;
+        first.new.start += skip;
+    }
+    if let Some(last) = runs.last_mut().filter(|run| run.equal) {
+        let skip = last.old.len().saturating_sub(context);
+        last.old.end -= skip;
+        last.new.end -= skip;
+    }
+
+    let mut hunks = Vec::new();
+    let mut hunk = Vec::new();
+    for run in runs {
+        if run.equal && run.old.len() > context * 2 {
+            hunk.push(LineRun { equal: true, old: run.old.start..run.old.start + context, new: run.new.start..run.new.start + context });
+            hunks.push(core::mem::take(&mut hunk));
+            hunk.push(LineRun { equal: true, old: run.old.end - context..run.old.end, new: run.new.end - context..run.new.end });
+            continue;
+        }
+        hunk.push(run);
+    }
+    if hunk.iter().any(|run| !run.equal) {
+        hunks.push(hunk);
+    }
+    hunks
+}
+
+/// A line the edit batch inserted or rewrote, with the anchor it will have
+/// once the batch is applied.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PostEditAnchor {
+    pub anchor: AnchorRef,
+    pub text: String,
+}
+
+/// Anchors of the lines in `new_content` that differ from `old_content`.
+pub fn changed_line_anchors(old_content: &str, new_content: &str) -> Vec<PostEditAnchor> {
+    changed_line_anchors_with(old_content, &HashedFile::from_content(new_content))
+}
+
+/// [`changed_line_anchors`] against new content that is already hashed.
+pub fn changed_line_anchors_with(old_content: &str, file: &HashedFile) -> Vec<PostEditAnchor> {
+    let old_lines: Vec<&str> = old_content.lines().collect();
+    ChangedLines::between(&old_lines, file.lines())
+        .inserted
+        .into_iter()
+        .map(|i| PostEditAnchor {
+            anchor: file.anchor(i + 1).expect("inserted line lies within the new content"),
+            text: file.lines()[i].to_string(),
+        })
+        .collect()
+}
+
+/// The lines an edit deleted and inserted, as 0-based indexes into the old
+/// and new lines respectively. Each deleted line also carries the index of
+/// the new line it was just above.
+struct ChangedLines {
+    deleted: Vec<(usize, usize)>,
+    inserted: Vec<usize>,
+}
+
+impl ChangedLines {
+    /// Diff only the window between the longest common prefix and suffix,
+    /// so a local edit costs a scan for equal lines rather than a diff of
+    /// the whole file. Edits spread across the file widen the window until
+    /// it is the full diff.
+    fn between(old_lines: &[&str], new_lines: &[Cow<'_, str>]) -> ChangedLines {
+        let prefix = old_lines.iter().zip(new_lines)
+            .take_while(|(a, b)| **a == *b)
+            .count();
+        let suffix = old_lines[prefix..].iter().rev()
+            .zip(new_lines[prefix..].iter().rev())
+            .take_while(|(a, b)| **a == *b)
+            .count();
+        let old_window = &old_lines[prefix..old_lines.len() - suffix];
+        let new_window: Vec<&str> = new_lines[prefix..new_lines.len() - suffix].iter().map(|line| &**line).collect();
+
+        let mut changes = ChangedLines { deleted: Vec::new(), inserted: Vec::new() };
+        for run in diff_lines(old_window, &new_window).into_iter().filter(|run| !run.equal) {
+            changes.deleted.extend(run.old.map(|i| (prefix + i, prefix + run.new.start)));
+            changes.inserted.extend(run.new.map(|i| prefix + i));
+        }
+        changes
+    }
+}
@@ -0,0 +1,90 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{AnchorRef, HashlineEdit};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Custom Edit Operations
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// An edit operation defined outside this crate.
+///
+/// Wrapped with [`HashlineEdit::custom`], it goes through the same pipeline
+/// as the built-in ops: its anchors are hash-checked (and relocated by
+/// auto-rebase), it is checked for overlaps against the rest of the batch,
+/// applied bottom-up with the others, and shows up in the diff.
+///
+/// ```
+/// # use hashline_tools::{AnchorRef, EditOp};
+/// /// Sort the lines of a block, e.g. an import list.
+/// #[derive(Debug)]
+/// struct SortLines { first: AnchorRef, last: AnchorRef }
+///
+/// impl EditOp for SortLines {
+///     fn name(&self) -> &str { "sort_lines" }
+///     fn range(&self) -> (AnchorRef, AnchorRef) { (self.first.clone(), self.last.clone()) }
+///     fn apply(&self, lines: &[String]) -> Vec<String> {
+///         let mut sorted = lines.to_vec();
+///         sorted.sort();
+///         sorted
+///     }
+/// }
+/// ```
+pub trait EditOp: fmt::Debug + Send + Sync {
+    /// Name used in statuses and overlap errors, e.g. `sort_lines`.
+    fn name(&self) -> &str;
+
+    /// Anchors of the first and last line the op rewrites.
+    fn range(&self) -> (AnchorRef, AnchorRef);
+
+    /// Reject the op for reasons the hashes cannot show, given the current
+    /// lines of its range (after any relocation). Accepts everything by default.
+    fn validate(&self, lines: &[String]) -> Result<(), String> {
+        let _ = lines;
+        Ok(())
+    }
+
+    /// The lines that replace the range.
+    fn apply(&self, lines: &[String]) -> Vec<String>;
+}
+
+/// A shared [`EditOp`] held by [`HashlineEdit::Custom`]. Two are equal only
+/// when they are the same op.
+#[derive(Clone)]
+pub struct CustomOp(Arc<dyn EditOp>);
+
+impl CustomOp {
+    pub fn new(op: impl EditOp + 'static) -> CustomOp {
+        CustomOp(Arc::new(op))
+    }
+}
+
+impl core::ops::Deref for CustomOp {
+    type Target = dyn EditOp;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.0
+    }
+}
+
+impl fmt::Debug for CustomOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl PartialEq for CustomOp {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl HashlineEdit {
+    /// Wrap a custom op, starting from the anchors its [`EditOp::range`] names.
+    pub fn custom(op: impl EditOp + 'static) -> HashlineEdit {
+        let (pos, end) = op.range();
+        HashlineEdit::Custom { pos, end, op: CustomOp::new(op) }
+    }
+}
@@ -0,0 +1,154 @@
+#[cfg(feature = "fs")]
+use std::fs;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "fs")]
+use super::atomic_write::write_atomic;
+
+use crate::{apply_to_hashed_file, AnchorRef, ApplyOptions, ApplyOutcome, EditHooks, HashedFile, HashlineEdit, HashlineError};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// In-memory Editor
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// A file loaded once and edited in memory.
+///
+/// The chained line hashes are cached and, after each batch, recomputed only
+/// from the first changed line until they line up with the old chain again.
+/// Nothing reaches disk until `save`, which like `open` needs the `fs`
+/// feature and replaces the file atomically. `open_async` and `save_async`
+/// (feature `tokio`) do the same I/O off the async threads, for use inside
+/// async servers.
+///
+#[cfg_attr(feature = "fs", doc = "```no_run")]
+#[cfg_attr(not(feature = "fs"), doc = "```ignore")]
+/// # use hashline_tools::{Editor, HashlineEdit};
+/// let mut editor = Editor::open("src/main.rs")?;
+/// let anchor = editor.anchor(2).unwrap();
+/// let edits = vec![HashlineEdit::Replace { pos: anchor, end: None, lines: vec!["// new".into()], expected_lines: None }];
+/// editor.apply(&edits)?;
+/// editor.save()?;
+/// # Ok::<(), hashline_tools::HashlineError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Editor {
+    path: PathBuf,
+    content: String,
+    file: HashedFile<'static>,
+    options: ApplyOptions,
+    hooks: EditHooks,
+    dirty: bool,
+}
+
+impl Editor {
+    /// Read `path` and hash every line.
+    #[cfg(feature = "fs")]
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Editor> {
+        let content = fs::read_to_string(path.as_ref())?;
+        Ok(Editor::from_content(path, content))
+    }
+
+    /// [`Editor::open`] without blocking the async runtime.
+    #[cfg(feature = "tokio")]
+    pub async fn open_async(path: impl AsRef<Path>) -> std::io::Result<Editor> {
+        let content = tokio::fs::read_to_string(path.as_ref()).await?;
+        Ok(Editor::from_content(path, content))
+    }
+
+    /// Start from content already in memory; `path` is where `save` writes.
+    pub fn from_content(path: impl AsRef<Path>, content: impl Into<String>) -> Editor {
+        let content = content.into();
+        Editor {
+            path: path.as_ref().to_path_buf(),
+            file: HashedFile::from_content(&content).into_owned(),
+            content,
+            options: ApplyOptions::default(),
+            hooks: EditHooks::default(),
+            dirty: false,
+        }
+    }
+
+    /// Options used for every batch passed to [`Editor::apply`]. The
+    /// buffer is rehashed if they change its [`crate::AnchorMode`], taken
+    /// [`for_path`](crate::AnchorMode::for_path) this editor's path.
+    pub fn with_options(mut self, options: ApplyOptions) -> Editor {
+        let anchor_mode = options.anchor_mode.for_path(&self.path);
+        if anchor_mode != self.file.mode() {
+            self.file = HashedFile::from_content_with_mode(&self.content, anchor_mode).into_owned();
+        }
+        self.options = ApplyOptions { anchor_mode, ..options };
+        self
+    }
+
+    /// Callbacks run around every [`Editor::apply`], with this editor's path.
+    pub fn with_hooks(mut self, hooks: EditHooks) -> Editor {
+        self.hooks = hooks;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    pub fn lines(&self) -> &[Cow<'static, str>] {
+        self.file.lines()
+    }
+
+    /// The buffer's lines and their cached hashes.
+    pub fn file(&self) -> &HashedFile<'static> {
+        &self.file
+    }
+
+    /// Hash of 1-indexed `line`, or `None` past the end of the file.
+    pub fn hash_at(&self, line: usize) -> Option<&str> {
+        self.file.hash_at(line)
+    }
+
+    /// Current `LINE#HASH` anchor for 1-indexed `line`.
+    pub fn anchor(&self, line: usize) -> Option<AnchorRef> {
+        self.file.anchor(line)
+    }
+
+    /// Whether there are applied edits not yet saved.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Apply a batch against the current in-memory content. On error,
+    /// including a rejection by a before-apply hook, the buffer is left
+    /// unchanged.
+    pub fn apply(&mut self, edits: &[HashlineEdit]) -> Result<ApplyOutcome, HashlineError> {
+        self.hooks.run_before(&self.path, edits)?;
+        let outcome = apply_to_hashed_file(&self.content, &self.file, edits, &self.options)?;
+        if outcome.content != self.content {
+            self.file.update(outcome.content.lines().map(|s| s.to_string()).collect());
+            self.hooks.run_after(&self.path, edits, &self.content, &outcome, &self.file);
+            self.content = outcome.content.clone();
+            self.dirty = true;
+        }
+        Ok(outcome)
+    }
+
+    /// Write the buffer back to its path, atomically. See [`write_atomic`].
+    #[cfg(feature = "fs")]
+    pub fn save(&mut self) -> std::io::Result<()> {
+        write_atomic(&self.path, &self.content)?;
+        self.dirty = false;
+        Ok(())
+    }
+    /// [`Editor::save`] without blocking the async runtime.
+    #[cfg(feature = "tokio")]
+    pub async fn save_async(&mut self) -> std::io::Result<()> {
+        let (path, content) = (self.path.clone(), self.content.clone());
+        tokio::task::spawn_blocking(move || write_atomic(&path, content))
+            .await
+            .map_err(std::io::Error::other)??;
+        self.dirty = false;
+        Ok(())
+    }
+}
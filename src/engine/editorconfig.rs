@@ -0,0 +1,141 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::format;
+
+use serde::{Deserialize, Serialize};
+
+use super::Line;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// EditorConfig
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum IndentStyle {
+    Tab,
+    Space,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum EndOfLine {
+    Lf,
+    Crlf,
+    Cr,
+}
+
+impl EndOfLine {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EndOfLine::Lf => "\n",
+            EndOfLine::Crlf => "\r\n",
+            EndOfLine::Cr => "\r",
+        }
+    }
+
+    /// The line ending `content` uses: `\r\n` or `\n`, whichever ends more
+    /// of its lines when it mixes them, with ties going to `\n`. `None` for
+    /// content without line breaks. Edits give the lines they insert this
+    /// ending; the others keep their own.
+    pub fn detect(content: &str) -> Option<EndOfLine> {
+        let newlines = content.bytes().filter(|&b| b == b'\n').count();
+        let crlf = content.matches("\r\n").count();
+        match newlines {
+            0 => None,
+            _ if crlf * 2 > newlines => Some(EndOfLine::Crlf),
+            _ => Some(EndOfLine::Lf),
+        }
+    }
+}
+
+/// The `.editorconfig` properties edits are made to follow. Unset
+/// properties leave the file as the edit batch produces it.
+///
+/// Indentation is only rewritten on the lines the edits insert; lines the
+/// batch leaves alone, and the output of custom ops, keep theirs. The line
+/// ending and final newline apply to the whole file; without an
+/// `end_of_line`, every line keeps its own ending and inserted lines get
+/// the one [`EndOfLine::detect`] finds.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct EditorConfig {
+    pub indent_style: Option<IndentStyle>,
+    /// Columns per indentation level, and the width of a tab.
+    pub indent_size: Option<usize>,
+    pub end_of_line: Option<EndOfLine>,
+    /// End the file with a line ending (`true`) or without one (`false`).
+    /// Unset keeps whatever the file had.
+    pub insert_final_newline: Option<bool>,
+}
+
+impl EditorConfig {
+    /// `line` with its leading indentation converted to the configured
+    /// style. Needs both `indent_style` and `indent_size`; leftover columns
+    /// that do not fill a tab stay spaces.
+    pub fn reindent<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let (Some(style), Some(size)) = (self.indent_style, self.indent_size.filter(|&s| s > 0)) else {
+            return Cow::Borrowed(line);
+        };
+        let body = line.trim_start_matches([' ', '\t']);
+        let indent = &line[..line.len() - body.len()];
+        let wanted = match style {
+            IndentStyle::Space if !indent.contains('\t') => return Cow::Borrowed(line),
+            IndentStyle::Tab if !indent.contains(' ') => return Cow::Borrowed(line),
+            IndentStyle::Space => " ".repeat(indent_columns(indent, size)),
+            IndentStyle::Tab => {
+                let columns = indent_columns(indent, size);
+                format!("{}{}", "\t".repeat(columns / size), " ".repeat(columns % size))
+            }
+        };
+        if wanted == indent {
+            Cow::Borrowed(line)
+        } else {
+            Cow::Owned(format!("{}{}", wanted, body))
+        }
+    }
+
+    /// Join edited lines into file content, keeping the line endings and
+    /// final newline of the `original` content unless configured otherwise.
+    /// `line_ending` is the ending for inserted lines, over any other.
+    pub(crate) fn join(&self, lines: &[Line<'_>], original: &str, line_ending: Option<EndOfLine>) -> String {
+        // A configured end_of_line rewrites every line, unless overridden
+        let normalize = line_ending.is_none() && self.end_of_line.is_some();
+        let default_eol = line_ending
+            .or(self.end_of_line)
+            .or_else(|| EndOfLine::detect(original))
+            .map_or("\n", EndOfLine::as_str);
+        let eol = |line: &Line<'_>| if normalize { default_eol } else { line.ending.unwrap_or(default_eol) };
+
+        let mut result = String::with_capacity(original.len());
+        for (i, line) in lines.iter().enumerate() {
+            result.push_str(&line.text);
+            if i + 1 < lines.len() {
+                result.push_str(eol(line));
+            }
+        }
+        let Some(last) = lines.last() else {
+            return result;
+        };
+        match self.insert_final_newline {
+            // An empty last line is the final newline itself
+            Some(false) if last.text.is_empty() && lines.len() > 1 => {
+                let ending = eol(&lines[lines.len() - 2]);
+                result.truncate(result.len() - ending.len());
+            }
+            Some(true) if !result.is_empty() => result.push_str(eol(last)),
+            None if original.ends_with('\n') => result.push_str(eol(last)),
+            _ => {}
+        }
+        result
+    }
+}
+
+/// Width of leading whitespace, with tabs advancing to the next tab stop.
+fn indent_columns(indent: &str, tab_width: usize) -> usize {
+    indent.chars().fold(0, |col, c| if c == '\t' { col + tab_width - col % tab_width } else { col + 1 })
+}
@@ -0,0 +1,123 @@
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{HashlineMismatchError, LimitExceeded};
+#[cfg(feature = "cli")]
+use crate::{PolicyViolation, TransactionError};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Errors
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Everything the library can fail with.
+///
+/// The `cmd_*` functions render these into the text or JSON the CLI prints
+/// and return that as their `Err(String)`.
+#[derive(Debug, thiserror::Error)]
+pub enum HashlineError {
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// Anchors whose hashes no longer match the file.
+    #[error(transparent)]
+    Mismatch(#[from] HashlineMismatchError),
+    /// Edits touching the same lines; one description per overlapping pair.
+    #[error("Overlapping edits detected. Combine overlapping edits into a single operation:\n{}", .0.join("\n"))]
+    Overlap(Vec<String>),
+    /// An anchor string that is not `LINE#HASH`.
+    #[error("{0}")]
+    InvalidAnchor(String),
+    /// An edits payload that is not valid JSON or not a supported version.
+    #[error("Failed to parse edits: {0}")]
+    InvalidEdits(String),
+    /// Edits that can never apply: lines out of range, inverted ranges,
+    /// `expected_lines` that do not match.
+    #[error("{}", .0.join("\n"))]
+    Validation(Vec<String>),
+    /// A sequential batch failed at one edit.
+    #[error("edits[{edit_index}] (applied in sequence): {source}")]
+    Sequence { edit_index: usize, source: Box<HashlineError> },
+    #[cfg(feature = "cli")]
+    #[error(transparent)]
+    Transaction(#[from] TransactionError),
+    #[cfg(feature = "cli")]
+    #[error(transparent)]
+    Policy(#[from] PolicyViolation),
+    #[error("{0}")]
+    InvalidPolicy(String),
+    /// A plan token that is malformed or whose signature does not match.
+    #[error("{0}")]
+    InvalidToken(String),
+    /// Checkpoints that cannot be undone.
+    #[error("{0}")]
+    Undo(String),
+    /// Interrupted transactions that cannot be recovered.
+    #[error("{0}")]
+    Recovery(String),
+    /// A backed-up version that cannot be restored.
+    #[error("{0}")]
+    BackupRestore(String),
+    /// A git checkpoint that cannot be restored.
+    #[error("{0}")]
+    GitRestore(String),
+    /// Checkpoints that cannot be exported as a patch series.
+    #[error("{0}")]
+    Export(String),
+    /// Content or a batch over one of the caller's [`crate::Limits`].
+    #[error(transparent)]
+    LimitExceeded(#[from] LimitExceeded),
+    /// A before-apply hook refused the batch.
+    #[error("Edit rejected: {0}")]
+    Rejected(String),
+}
+
+impl HashlineError {
+    /// Stable machine-readable name of the failure, used as the `error`
+    /// field of JSON error objects.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "std")]
+            HashlineError::Io(_) => "io",
+            HashlineError::Mismatch(_) => "hash_mismatch",
+            HashlineError::Overlap(_) => "overlap",
+            HashlineError::InvalidAnchor(_) => "invalid_anchor",
+            HashlineError::InvalidEdits(_) => "invalid_edits",
+            HashlineError::Validation(_) => "validation",
+            HashlineError::Sequence { .. } => "sequence",
+            #[cfg(feature = "cli")]
+            HashlineError::Transaction(_) => "transaction_failed",
+            #[cfg(feature = "cli")]
+            HashlineError::Policy(_) => "policy_violation",
+            HashlineError::InvalidPolicy(_) => "invalid_policy",
+            HashlineError::InvalidToken(_) => "invalid_token",
+            HashlineError::Undo(_) => "undo_failed",
+            HashlineError::Recovery(_) => "recovery_failed",
+            HashlineError::BackupRestore(_) => "restore_failed",
+            HashlineError::GitRestore(_) => "git",
+            HashlineError::Export(_) => "export_failed",
+            HashlineError::LimitExceeded(_) => "limit_exceeded",
+            HashlineError::Rejected(_) => "rejected",
+        }
+    }
+
+    /// The error as a JSON object: `{"error": kind, "message": ...}`, plus
+    /// the limit and sizes for a limit exceeded, or the full mismatch report
+    /// for stale anchors.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            HashlineError::Mismatch(mismatch) => mismatch.to_json(),
+            HashlineError::LimitExceeded(exceeded) => serde_json::json!({
+                "error": self.kind(),
+                "message": self.to_string(),
+                "limit": exceeded.limit,
+                "max": exceeded.max,
+                "actual": exceeded.actual,
+            }),
+            _ => serde_json::json!({
+                "error": self.kind(),
+                "message": self.to_string(),
+            }),
+        }
+    }
+}
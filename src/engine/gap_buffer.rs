@@ -0,0 +1,107 @@
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::ops::Range;
+// ═══════════════════════════════════════════════════════════════════════════
+// Gap Buffer
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// The lines of a file being edited, split at a movable gap.
+///
+/// Lines before the gap sit in `head` in order; lines after it sit in `tail`
+/// in reverse, so the line right after the gap is `tail.last()`. A splice
+/// moves the gap to the end of its range and only touches the lines between
+/// the old and new gap position plus the range itself. Batches are applied
+/// bottom-up, so the gap only ever moves towards the top of the file and
+/// every original line is moved at most once: a batch of many edits costs
+/// about one pass over the file instead of one `Vec::splice` memmove per edit.
+///
+/// Lines are borrowed from the original file and the edits wherever
+/// possible, so only what a custom op produces is ever allocated; untouched
+/// lines are never copied.
+#[derive(Debug, Default)]
+pub(crate) struct GapBuffer<'a> {
+    head: Vec<Line<'a>>,
+    tail: Vec<Line<'a>>,
+}
+
+/// A line of the buffer and the line ending it had in the original file.
+#[derive(Debug, Clone)]
+pub(crate) struct Line<'a> {
+    pub(crate) text: Cow<'a, str>,
+    /// `None` for lines the edits inserted, and for a last line that had
+    /// no line ending.
+    pub(crate) ending: Option<&'static str>,
+}
+
+impl<'a> GapBuffer<'a> {
+    /// The `lines` of `content`, each with the line ending it has there.
+    pub(crate) fn new(lines: &'a [Cow<'_, str>], content: &str) -> GapBuffer<'a> {
+        let mut endings = content.split_inclusive('\n').map(|line| {
+            if line.ends_with("\r\n") {
+                Some("\r\n")
+            } else if line.ends_with('\n') {
+                Some("\n")
+            } else {
+                None
+            }
+        });
+        let head = lines.iter()
+            .map(|line| Line { text: Cow::Borrowed(line), ending: endings.next().flatten() })
+            .collect();
+        GapBuffer { head, tail: Vec::new() }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.head.len() + self.tail.len()
+    }
+
+    /// Whether the buffer holds exactly one line and it is empty, i.e. the
+    /// content was a lone newline.
+    pub(crate) fn is_single_empty_line(&self) -> bool {
+        self.len() == 1 && self.head.first().or(self.tail.first()).is_some_and(|line| line.text.is_empty())
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.head.clear();
+        self.tail.clear();
+    }
+
+    /// The lines in `range`, contiguous.
+    pub(crate) fn slice(&mut self, range: Range<usize>) -> &[Line<'a>] {
+        self.move_gap(range.end);
+        &self.head[range]
+    }
+
+    /// Replace the lines in `range` with `lines`, like [`Vec::splice`]. The
+    /// new lines have no line ending of their own.
+    pub(crate) fn splice<I>(&mut self, range: Range<usize>, lines: I)
+    where
+        I: IntoIterator<Item = Cow<'a, str>>,
+        I::IntoIter: DoubleEndedIterator,
+    {
+        self.move_gap(range.end);
+        self.head.truncate(range.start);
+        self.tail.extend(lines.into_iter().rev().map(|text| Line { text, ending: None }));
+    }
+
+    /// Rotate the lines in `range` left by `mid`, like [`slice::rotate_left`].
+    /// The lines keep their line endings.
+    pub(crate) fn rotate_left(&mut self, range: Range<usize>, mid: usize) {
+        self.move_gap(range.end);
+        self.head[range].rotate_left(mid);
+    }
+
+    pub(crate) fn into_lines(mut self) -> Vec<Line<'a>> {
+        self.head.extend(self.tail.drain(..).rev());
+        self.head
+    }
+
+    fn move_gap(&mut self, at: usize) {
+        while self.head.len() > at {
+            self.tail.push(self.head.pop().expect("head is longer than the gap position"));
+        }
+        while self.head.len() < at {
+            self.head.push(self.tail.pop().expect("gap position lies within the buffer"));
+        }
+    }
+}
@@ -0,0 +1,322 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh32::xxh32;
+
+use crate::{line_hash, AnnotatedLine, HashedFile, LineHash};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Sidecar Hash Cache
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Where the cache lives when `--hash-cache` is given without a directory.
+pub const DEFAULT_HASH_CACHE_DIR: &str = ".hashline/cache";
+
+/// Lines per block of a file's line index: windowed reads keep the byte
+/// offset and chain seed at every block boundary, so a read deep in the file
+/// seeks to the nearest boundary and hashes at most this many lines early.
+pub const SEED_BLOCK_LINES: usize = 4096;
+
+/// Files modified this close to when their entry would be written are not
+/// cached: on filesystems with coarse timestamps a second write in the same
+/// tick keeps size and mtime, and the entry would silently go stale.
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+/// On-disk cache of hash chains, so repeated reads and validations of an
+/// unchanged large file skip hashing.
+///
+/// Entries are keyed by the file's canonical path and checked against its
+/// size and modification time; any change to either is a miss, and the entry
+/// is rewritten the next time the file is loaded. Windowed reads keep only a
+/// line index (a byte offset and chain seed every [`SEED_BLOCK_LINES`]
+/// lines) under the same key and checks.
+///
+/// ```no_run
+/// # use hashline_tools::HashCache;
+/// let cache = HashCache::new(".hashline/cache");
+/// let (content, file) = cache.load("src/main.rs")?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashCache {
+    dir: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    path: PathBuf,
+    size: u64,
+    mtime_nanos: u128,
+    /// Every line's hash, concatenated.
+    hashes: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexEntry {
+    path: PathBuf,
+    size: u64,
+    mtime_nanos: u128,
+    #[serde(flatten)]
+    index: LineIndex,
+}
+
+/// Where each block of a file starts, and the chain seed it starts from.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct LineIndex {
+    total_lines: usize,
+    /// `offsets[k]` is the byte offset of line `(k + 1) * SEED_BLOCK_LINES + 1`.
+    offsets: Vec<u64>,
+    /// `seeds[k]` is the hash of line `(k + 1) * SEED_BLOCK_LINES`, as far as
+    /// any read has hashed.
+    seeds: Vec<LineHash>,
+}
+
+impl HashCache {
+    pub fn new(dir: impl Into<PathBuf>) -> HashCache {
+        HashCache { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Read `path` and its hashed lines, taking the hashes from the cache when
+    /// the file is unchanged and caching them otherwise. Failing to write the
+    /// cache is not an error; the file is simply hashed again next time.
+    pub fn load(&self, path: impl AsRef<Path>) -> io::Result<(String, HashedFile<'static>)> {
+        let (content, hashes) = self.load_hashes(path)?;
+        let file = HashedFile::from_parts(&content, hashes).into_owned();
+        Ok((content, file))
+    }
+
+    /// [`HashCache::load`] returning only the hashes, so that the caller's
+    /// [`HashedFile`] can borrow its lines from the content.
+    pub(crate) fn load_hashes(&self, path: impl AsRef<Path>) -> io::Result<(String, Vec<LineHash>)> {
+        let (path, content, stamp) = read_stamped(path.as_ref())?;
+        if let Some(hashes) = stamp.and_then(|stamp| self.lookup(&path, stamp, &content)) {
+            return Ok((content, hashes));
+        }
+        let file = HashedFile::from_content(&content);
+        if let Some(stamp) = stamp {
+            let _ = self.store(&path, stamp, &file);
+        }
+        let hashes = file.hashes().to_vec();
+        Ok((content, hashes))
+    }
+
+    /// Hash only the lines of `path` in `lines` (0-based, clamped to the
+    /// file). The first read scans the file once for its line index; later
+    /// reads of the unchanged file seek straight to the block the window
+    /// starts in and pick the chain up from that block's seed. Seeds passed on
+    /// the way are added to the index for the next read.
+    pub fn load_window(&self, path: impl AsRef<Path>, lines: Range<usize>) -> io::Result<HashedWindow> {
+        let path = fs::canonicalize(path.as_ref())?;
+        let stamp = FileStamp::of(&fs::metadata(&path)?);
+        if let Some((stamp, mut index)) = stamp.and_then(|stamp| Some((stamp, self.lookup_index(&path, stamp)?))) {
+            let known_seeds = index.seeds.len();
+            let window = index.seek_window(&path, lines.clone())?;
+            // A write while seeking leaves lines the index does not describe
+            if FileStamp::of(&fs::metadata(&path)?) == Some(stamp) {
+                if index.seeds.len() > known_seeds {
+                    let _ = self.store_index(&path, stamp, index);
+                }
+                return Ok(window);
+            }
+        }
+
+        let (path, content, stamp) = read_stamped(&path)?;
+        let mut index = LineIndex::scan(&content);
+        let end = lines.end.min(index.total_lines);
+        let start = lines.start.min(end);
+        let (lines, hashes) = index.hash_lines(content.lines().map(Ok), 0, start..end)?;
+        if let Some(stamp) = stamp {
+            let _ = self.store_index(&path, stamp, index.clone());
+        }
+        Ok(HashedWindow { lines, total_lines: index.total_lines, start, hashes })
+    }
+
+    fn entry_path(&self, path: &Path, suffix: &str) -> PathBuf {
+        let key = path.to_string_lossy();
+        let name = format!("{:08x}{:08x}{}.json", xxh32(key.as_bytes(), 0), xxh32(key.as_bytes(), 1), suffix);
+        self.dir.join(name)
+    }
+
+    fn lookup(&self, path: &Path, stamp: FileStamp, content: &str) -> Option<Vec<LineHash>> {
+        let raw = fs::read(self.entry_path(path, "")).ok()?;
+        let entry: CacheEntry = serde_json::from_slice(&raw).ok()?;
+        if entry.path != path || entry.size != stamp.size || entry.mtime_nanos != stamp.mtime_nanos {
+            return None;
+        }
+        let lines = content.lines().count();
+        if entry.hashes.len() != lines * 2 || !entry.hashes.is_ascii() {
+            return None;
+        }
+        (0..lines).map(|i| entry.hashes[i * 2..i * 2 + 2].parse().ok()).collect()
+    }
+
+    fn store(&self, path: &Path, stamp: FileStamp, file: &HashedFile) -> io::Result<()> {
+        let entry = CacheEntry {
+            path: path.to_path_buf(),
+            size: stamp.size,
+            mtime_nanos: stamp.mtime_nanos,
+            hashes: file.hashes().iter().map(LineHash::as_str).collect(),
+        };
+        self.write_entry(path, "", stamp, &entry)
+    }
+
+    fn lookup_index(&self, path: &Path, stamp: FileStamp) -> Option<LineIndex> {
+        let raw = fs::read(self.entry_path(path, ".index")).ok()?;
+        let entry: IndexEntry = serde_json::from_slice(&raw).ok()?;
+        if entry.path != path || entry.size != stamp.size || entry.mtime_nanos != stamp.mtime_nanos {
+            return None;
+        }
+        Some(entry.index)
+    }
+
+    fn store_index(&self, path: &Path, stamp: FileStamp, index: LineIndex) -> io::Result<()> {
+        let entry = IndexEntry { path: path.to_path_buf(), size: stamp.size, mtime_nanos: stamp.mtime_nanos, index };
+        self.write_entry(path, ".index", stamp, &entry)
+    }
+
+    fn write_entry(&self, path: &Path, suffix: &str, stamp: FileStamp, entry: &impl Serialize) -> io::Result<()> {
+        if stamp.is_racy() {
+            return Ok(());
+        }
+        fs::create_dir_all(&self.dir)?;
+        let target = self.entry_path(path, suffix);
+        let temp = target.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&temp, serde_json::to_vec(entry).expect("cache entry serializes to JSON"))?;
+        fs::rename(&temp, &target)
+    }
+}
+
+/// A window of a file's lines with their hashes, from [`HashCache::load_window`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashedWindow {
+    /// The window's lines, without line endings.
+    pub lines: Vec<String>,
+    pub total_lines: usize,
+    /// 0-based index of the first line in `lines`.
+    pub start: usize,
+    pub hashes: Vec<LineHash>,
+}
+
+impl HashedWindow {
+    /// The window's lines with their hashes.
+    pub fn annotated(&self) -> impl Iterator<Item = AnnotatedLine<'_>> {
+        self.lines.iter()
+            .zip(&self.hashes)
+            .enumerate()
+            .map(|(i, (text, &hash))| AnnotatedLine { line: self.start + i + 1, hash, text })
+    }
+}
+
+impl LineIndex {
+    fn scan(content: &str) -> LineIndex {
+        let mut index = LineIndex::default();
+        let mut offset = 0;
+        // Split as `str::lines` does, so line numbers agree
+        for line in content.split_inclusive('\n') {
+            if index.total_lines > 0 && index.total_lines.is_multiple_of(SEED_BLOCK_LINES) {
+                index.offsets.push(offset as u64);
+            }
+            index.total_lines += 1;
+            offset += line.len();
+        }
+        index
+    }
+
+    /// Read the lines in `window` from `path`, starting at the last block
+    /// boundary before it that has a seed.
+    fn seek_window(&mut self, path: &Path, window: Range<usize>) -> io::Result<HashedWindow> {
+        let end = window.end.min(self.total_lines);
+        let start = window.start.min(end);
+        let block = self.seeds.len().min(self.offsets.len()).min(start / SEED_BLOCK_LINES);
+        let mut reader = BufReader::new(File::open(path)?);
+        reader.seek(SeekFrom::Start(block.checked_sub(1).map_or(0, |k| self.offsets[k])))?;
+        let (lines, hashes) = self.hash_lines(read_lines(reader), block * SEED_BLOCK_LINES, start..end)?;
+        Ok(HashedWindow { lines, total_lines: self.total_lines, start, hashes })
+    }
+
+    /// Hash `lines`, which start at 0-based line `from` on a block boundary,
+    /// up to the end of `window`, recording the seeds of blocks passed. Only
+    /// the window's lines are kept.
+    fn hash_lines<S: AsRef<str> + Into<String>>(
+        &mut self,
+        lines: impl Iterator<Item = io::Result<S>>,
+        from: usize,
+        window: Range<usize>,
+    ) -> io::Result<(Vec<String>, Vec<LineHash>)> {
+        let mut prev = (from / SEED_BLOCK_LINES).checked_sub(1).map(|k| self.seeds[k]);
+        let mut texts = Vec::with_capacity(window.len());
+        let mut hashes = Vec::with_capacity(window.len());
+        for (i, text) in (from..window.end).zip(lines) {
+            let text = text?;
+            let hash = line_hash(i + 1, text.as_ref(), prev);
+            if (i + 1).is_multiple_of(SEED_BLOCK_LINES) && (i + 1) / SEED_BLOCK_LINES > self.seeds.len() {
+                self.seeds.push(hash);
+            }
+            if i >= window.start {
+                texts.push(text.into());
+                hashes.push(hash);
+            }
+            prev = Some(hash);
+        }
+        Ok((texts, hashes))
+    }
+}
+
+/// Lines of `reader`, split as `str::lines` splits them.
+pub(crate) fn read_lines(mut reader: impl BufRead) -> impl Iterator<Item = io::Result<String>> {
+    std::iter::from_fn(move || {
+        let mut line = String::new();
+        match reader.read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) => {
+                if line.ends_with('\n') {
+                    line.pop();
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                }
+                Some(Ok(line))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    })
+}
+
+/// Canonicalize and read `path`, with its stamp if the file did not change
+/// while being read. A write between the metadata call and the read would
+/// leave a stamp that does not describe the content; such reads are uncacheable.
+fn read_stamped(path: &Path) -> io::Result<(PathBuf, String, Option<FileStamp>)> {
+    let path = fs::canonicalize(path)?;
+    let before = fs::metadata(&path)?;
+    let content = fs::read_to_string(&path)?;
+    let stamp = FileStamp::of(&before);
+    let stable = fs::metadata(&path).map(|after| FileStamp::of(&after) == stamp).unwrap_or(false);
+    Ok((path, content, stamp.filter(|_| stable)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    size: u64,
+    mtime_nanos: u128,
+}
+
+impl FileStamp {
+    /// `None` where the platform reports no modification time.
+    fn of(metadata: &fs::Metadata) -> Option<FileStamp> {
+        let mtime = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(FileStamp { size: metadata.len(), mtime_nanos: mtime.as_nanos() })
+    }
+
+    fn is_racy(&self) -> bool {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        now.as_nanos().saturating_sub(self.mtime_nanos) < RACY_WINDOW.as_nanos()
+    }
+}
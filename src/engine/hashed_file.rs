@@ -0,0 +1,370 @@
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::format;
+use core::ops::RangeInclusive;
+
+use super::scoped::line_seeds;
+use super::{hash_normalized, LineHasher};
+use crate::{line_hash, AnchorMode, AnchorRef, LineHash};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Hashed File
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// A file's lines together with their hashes, computed once. Hashes are
+/// chained unless the file was hashed in another [`AnchorMode`]. Lines
+/// hashed from content borrow it; [`HashedFile::into_owned`] detaches them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HashedFile<'a> {
+    lines: Vec<Cow<'a, str>>,
+    hashes: Vec<LineHash>,
+    mode: AnchorMode,
+}
+
+/// Whether an anchor still names the line it was taken from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnchorState {
+    Valid,
+    /// The line exists but its hash has changed.
+    Stale { current: AnchorRef },
+    /// The file no longer has that many lines.
+    OutOfRange { len: usize },
+}
+
+/// One line of [`annotate_lines`], borrowing its text from the content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnotatedLine<'a> {
+    /// 1-indexed line number.
+    pub line: usize,
+    pub hash: LineHash,
+    pub text: &'a str,
+}
+
+impl AnnotatedLine<'_> {
+    pub fn anchor(&self) -> AnchorRef {
+        AnchorRef { line: self.line, hash: self.hash.into() }
+    }
+}
+
+/// Renders as `LINE#HASH:content`, the form `read` prints.
+impl core::fmt::Display for AnnotatedLine<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}#{}:{}", self.line, self.hash, self.text)
+    }
+}
+
+/// Annotate `content` lazily, hashing each line only as it is reached.
+/// The hashes chain, so lines are always visited in order; skipping ahead
+/// still hashes the lines skipped.
+pub fn annotate_lines(content: &str) -> impl Iterator<Item = AnnotatedLine<'_>> {
+    annotate_lines_with_mode(content, AnchorMode::Chained)
+}
+
+/// [`annotate_lines`] with hashes derived in `mode`.
+pub fn annotate_lines_with_mode(content: &str, mode: AnchorMode) -> impl Iterator<Item = AnnotatedLine<'_>> {
+    let mut hasher = LineHasher::for_content(mode, content);
+    content.lines().enumerate().map(move |(i, text)| {
+        AnnotatedLine { line: i + 1, hash: hasher.hash(i + 1, text), text }
+    })
+}
+
+/// The byte offset each line of `content` starts at, one per line of
+/// [`annotate_lines`].
+pub fn line_byte_offsets(content: &str) -> impl Iterator<Item = usize> + '_ {
+    content.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some(start)
+    })
+}
+
+impl HashedFile<'static> {
+    pub fn new(lines: Vec<String>) -> HashedFile<'static> {
+        HashedFile::with_mode(lines, AnchorMode::Chained)
+    }
+
+    /// Hash `lines` in `mode`.
+    pub fn with_mode(lines: Vec<String>, mode: AnchorMode) -> HashedFile<'static> {
+        HashedFile::from_lines(lines.into_iter().map(Cow::Owned).collect(), mode)
+    }
+}
+
+impl<'a> HashedFile<'a> {
+    fn from_lines(lines: Vec<Cow<'a, str>>, mode: AnchorMode) -> HashedFile<'a> {
+        let hashes = match mode {
+            AnchorMode::Chained => {
+                let mut hashes = Vec::with_capacity(lines.len());
+                extend_hash_chain(&mut hashes, &lines);
+                hashes
+            }
+            _ => hash_seeded(&lines, line_seeds(mode, &lines)),
+        };
+        HashedFile { lines, hashes, mode }
+    }
+
+    /// The chained lines of `content` with hashes computed earlier, e.g.
+    /// loaded from a [`crate::HashCache`].
+    #[cfg(feature = "fs")]
+    pub(crate) fn from_parts(content: &'a str, hashes: Vec<LineHash>) -> HashedFile<'a> {
+        let lines: Vec<_> = content.lines().map(Cow::Borrowed).collect();
+        debug_assert_eq!(lines.len(), hashes.len());
+        HashedFile { lines, hashes, mode: AnchorMode::Chained }
+    }
+
+    pub fn from_content(content: &'a str) -> HashedFile<'a> {
+        HashedFile::from_content_with_mode(content, AnchorMode::Chained)
+    }
+
+    /// [`HashedFile::from_content`] with hashes derived in `mode`.
+    pub fn from_content_with_mode(content: &'a str, mode: AnchorMode) -> HashedFile<'a> {
+        HashedFile::from_lines(content.lines().map(Cow::Borrowed).collect(), mode)
+    }
+
+    /// This file with its lines copied out of the content they borrow.
+    pub fn into_owned(self) -> HashedFile<'static> {
+        let lines = self.lines.into_iter().map(|line| Cow::Owned(line.into_owned())).collect();
+        HashedFile { lines, hashes: self.hashes, mode: self.mode }
+    }
+
+    /// How this file's hashes were derived.
+    pub fn mode(&self) -> AnchorMode {
+        self.mode
+    }
+
+    pub fn lines(&self) -> &[Cow<'a, str>] {
+        &self.lines
+    }
+
+    pub fn into_lines(self) -> Vec<String> {
+        self.lines.into_iter().map(Cow::into_owned).collect()
+    }
+
+    /// Every line's hash, in order.
+    pub fn hashes(&self) -> &[LineHash] {
+        &self.hashes
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Hash of 1-indexed `line`, or `None` outside the file.
+    pub fn hash_at(&self, line: usize) -> Option<&str> {
+        self.hashes.get(line.checked_sub(1)?).map(LineHash::as_str)
+    }
+
+    /// Current `LINE#HASH` anchor for 1-indexed `line`.
+    pub fn anchor(&self, line: usize) -> Option<AnchorRef> {
+        self.hash_at(line).map(|hash| AnchorRef { line, hash: hash.to_string() })
+    }
+
+    /// `LINE#HASH:content` for each line in `range`, clamped to the file.
+    pub fn annotate(&self, range: RangeInclusive<usize>) -> Vec<String> {
+        let start = (*range.start()).max(1);
+        let end = (*range.end()).min(self.len());
+        (start..=end)
+            .map(|line| format!("{}#{}:{}", line, self.hashes[line - 1], self.lines[line - 1]))
+            .collect()
+    }
+
+    /// Check an anchor against the current content.
+    pub fn revalidate(&self, anchor: &AnchorRef) -> AnchorState {
+        match self.anchor(anchor.line) {
+            Some(current) if current == *anchor => AnchorState::Valid,
+            Some(current) => AnchorState::Stale { current },
+            None => AnchorState::OutOfRange { len: self.len() },
+        }
+    }
+
+    /// Hash `new_content`, an edited version of this file whose first change
+    /// is at or after 1-indexed `first_changed_line`. The hashes of the lines
+    /// before it are reused; the chain is recomputed only from there on.
+    /// Scoped and syntax hashes are all recomputed. Lines this file shares with
+    /// `new_content` at either end are taken from this file, so only the
+    /// changed lines are copied.
+    pub fn rehash_from(&self, new_content: &str, first_changed_line: usize) -> HashedFile<'a> {
+        let new_lines: Vec<&str> = new_content.lines().collect();
+        let prefix = self.lines.iter().zip(&new_lines)
+            .take(first_changed_line.saturating_sub(1))
+            .take_while(|(a, b)| a == *b)
+            .count();
+        let suffix = self.lines[prefix..].iter().rev()
+            .zip(new_lines[prefix..].iter().rev())
+            .take_while(|(a, b)| a == *b)
+            .count();
+        let mut lines = Vec::with_capacity(new_lines.len());
+        lines.extend_from_slice(&self.lines[..prefix]);
+        lines.extend(new_lines[prefix..new_lines.len() - suffix].iter().map(|line| Cow::Owned(line.to_string())));
+        lines.extend_from_slice(&self.lines[self.lines.len() - suffix..]);
+        if self.mode != AnchorMode::Chained {
+            return HashedFile::from_lines(lines, self.mode);
+        }
+        let mut hashes = Vec::with_capacity(lines.len());
+        hashes.extend_from_slice(&self.hashes[..prefix]);
+        extend_hash_chain(&mut hashes, &lines[prefix..]);
+        HashedFile { lines, hashes, mode: self.mode }
+    }
+
+    /// Replace the lines, keeping the hashes of the unchanged prefix, and of
+    /// the unchanged suffix once the chain lines up with the old one again.
+    /// Scoped and syntax hashes are all recomputed.
+    pub fn update(&mut self, new_lines: Vec<String>) {
+        let new_lines = new_lines.into_iter().map(Cow::Owned).collect();
+        if self.mode != AnchorMode::Chained {
+            *self = HashedFile::from_lines(new_lines, self.mode);
+            return;
+        }
+        let old_lines = core::mem::replace(&mut self.lines, new_lines);
+        let old_hashes = core::mem::take(&mut self.hashes);
+        let prefix = old_lines.iter().zip(&self.lines).take_while(|(a, b)| a == b).count();
+        let suffix = old_lines[prefix..].iter().rev()
+            .zip(self.lines[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let suffix_start = self.lines.len() - suffix;
+        let offset = old_lines.len() - suffix;
+
+        let mut hashes = old_hashes[..prefix].to_vec();
+        for i in prefix..self.lines.len() {
+            let hash = line_hash(i + 1, &self.lines[i], last_hash(&hashes));
+            // Identical lines chained from an identical hash hash identically
+            // from here on, so the rest of the old chain still holds
+            if i >= suffix_start && i > 0 && old_hashes[offset + i - suffix_start] == hash {
+                hashes.extend_from_slice(&old_hashes[offset + i - suffix_start..]);
+                break;
+            }
+            hashes.push(hash);
+        }
+        self.hashes = hashes;
+    }
+}
+
+/// Hash `lines`, which follow the lines `hashes` already covers, onto the
+/// end of the chain.
+fn extend_hash_chain(hashes: &mut Vec<LineHash>, lines: &[Cow<'_, str>]) {
+    #[cfg(feature = "rayon")]
+    if hashes_in_parallel(lines.len()) {
+        return extend_hash_chain_parallel(hashes, lines);
+    }
+    let mut prev = last_hash(hashes);
+    for line in lines {
+        let hash = line_hash(hashes.len() + 1, line, prev);
+        hashes.push(hash);
+        prev = Some(hash);
+    }
+}
+
+/// The end of the chain so far, to seed the next line.
+fn last_hash(hashes: &[LineHash]) -> Option<LineHash> {
+    hashes.last().copied()
+}
+
+// ─── Parallel Hashing ───────────────────────────────────────────────────────
+//
+// A chained hash has only 256 values, so a line maps the 256 hashes the line
+// before it can have onto fewer, and hashed from all of them at once the
+// chains soon merge into one. From that line on a chunk's hashes are the same
+// whatever the chunk starts from, so chunks are hashed that way in parallel,
+// and then only each chunk's lines up to the merge are chained in order.
+// Scoped and syntax hashes depend on no other hash, only on a seed, so once
+// the seeds are found the lines are simply hashed in parallel.
+
+/// Files with at least this many lines are hashed in parallel, in every
+/// [`AnchorMode`], when the `rayon` feature is on and the pool has more
+/// than one thread. Hashes are the same either way.
+#[cfg(feature = "rayon")]
+pub const PARALLEL_HASH_MIN_LINES: usize = 65_536;
+
+/// Whether `lines` lines are hashed on the rayon pool.
+#[cfg(feature = "rayon")]
+pub(crate) fn hashes_in_parallel(lines: usize) -> bool {
+    lines >= PARALLEL_HASH_MIN_LINES && rayon::current_num_threads() > 1
+}
+
+#[cfg(all(feature = "cli", not(feature = "rayon")))]
+pub(crate) fn hashes_in_parallel(_lines: usize) -> bool {
+    false
+}
+
+/// The fewest lines a chunk hashed in parallel gets, so that the lines before
+/// its chains merge, a few hundred as a rule, stay a small part of it.
+#[cfg(feature = "rayon")]
+const PARALLEL_HASH_MIN_CHUNK: usize = 16_384;
+
+#[cfg(feature = "rayon")]
+fn extend_hash_chain_parallel(hashes: &mut Vec<LineHash>, lines: &[Cow<'_, str>]) {
+    use rayon::prelude::*;
+
+    let chunk_len = lines.len().div_ceil(rayon::current_num_threads()).max(PARALLEL_HASH_MIN_CHUNK);
+    let tails: Vec<Option<MergedChain>> = lines.par_chunks(chunk_len)
+        .enumerate()
+        .map(|(i, chunk)| if i == 0 { None } else { MergedChain::find(chunk) })
+        .collect();
+    for (chunk, tail) in lines.chunks(chunk_len).zip(tails) {
+        let ordered = tail.as_ref().map_or(chunk.len(), |tail| tail.merged_at + 1);
+        let mut prev = last_hash(hashes);
+        for line in &chunk[..ordered] {
+            let hash = line_hash(hashes.len() + 1, line, prev);
+            hashes.push(hash);
+            prev = Some(hash);
+        }
+        if let Some(tail) = tail {
+            debug_assert_eq!(prev, Some(tail.merged));
+            hashes.extend(tail.hashes);
+        }
+    }
+}
+
+/// Hash each of `lines` with its seed, in parallel for a large file.
+fn hash_seeded(lines: &[Cow<'_, str>], seeds: Vec<u32>) -> Vec<LineHash> {
+    #[cfg(feature = "rayon")]
+    if hashes_in_parallel(lines.len()) {
+        use rayon::prelude::*;
+        return lines.par_iter().zip(seeds).map(|(line, seed)| hash_normalized(line, seed)).collect();
+    }
+    lines.iter().zip(seeds).map(|(line, seed)| hash_normalized(line, seed)).collect()
+}
+
+/// Where the chains through a chunk, one from each hash the line before it
+/// may have, have all merged.
+#[cfg(feature = "rayon")]
+struct MergedChain {
+    /// Index in the chunk of the first line every chain gives the same hash.
+    merged_at: usize,
+    /// That hash.
+    merged: LineHash,
+    /// The hashes of the chunk's lines after it.
+    hashes: Vec<LineHash>,
+}
+
+#[cfg(feature = "rayon")]
+impl MergedChain {
+    /// Hash `chunk` from every possible previous hash at once, or `None`
+    /// when the chains never all merge, as a run of identical lines can
+    /// keep them apart.
+    fn find(chunk: &[Cow<'_, str>]) -> Option<MergedChain> {
+        let mut states: Vec<LineHash> = (0..=0xff).map(LineHash::from_digest).collect();
+        let merged_at = chunk.iter().position(|line| {
+            for state in &mut states {
+                *state = line_hash(0, line, Some(*state));
+            }
+            states.sort_unstable_by_key(|state| state.seed());
+            states.dedup();
+            states.len() == 1
+        })?;
+        let merged = states[0];
+        let mut prev = merged;
+        let hashes = chunk[merged_at + 1..].iter()
+            .map(|line| {
+                prev = line_hash(0, line, Some(prev));
+                prev
+            })
+            .collect();
+        Some(MergedChain { merged_at, merged, hashes })
+    }
+}
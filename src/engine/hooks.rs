@@ -0,0 +1,102 @@
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::{hash_aware_diff_with, ApplyOutcome, DiffHunk, HashedFile, HashlineEdit, HashlineError};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Edit Hooks
+// ═══════════════════════════════════════════════════════════════════════════
+
+type BeforeApply = dyn Fn(&Path, &[HashlineEdit]) -> Result<(), String> + Send + Sync;
+type AfterApply = dyn Fn(&AppliedBatch) + Send + Sync;
+
+/// A batch that changed a file, as seen by [`EditHooks::after_apply`] callbacks.
+#[derive(Debug)]
+pub struct AppliedBatch<'a> {
+    pub path: &'a Path,
+    pub edits: &'a [HashlineEdit],
+    pub outcome: &'a ApplyOutcome,
+    pub diff: &'a [DiffHunk],
+}
+
+/// Callbacks run around every edit batch, for approval gates, logging or
+/// notifications. Used by [`crate::Editor`] and by the `edit` command.
+///
+/// ```
+/// # use hashline_tools::EditHooks;
+/// let hooks = EditHooks::default()
+///     .before_apply(|path, edits| {
+///         if path.starts_with("vendor") {
+///             return Err(format!("{} edits to vendored code need review", edits.len()));
+///         }
+///         Ok(())
+///     })
+///     .after_apply(|batch| eprintln!("{}: {} hunk(s)", batch.path.display(), batch.diff.len()));
+/// ```
+#[derive(Clone, Default)]
+pub struct EditHooks {
+    before: Vec<Arc<BeforeApply>>,
+    after: Vec<Arc<AfterApply>>,
+}
+
+impl EditHooks {
+    /// Run `hook` before a batch is validated. Returning `Err(reason)`
+    /// rejects the batch with [`HashlineError::Rejected`] and nothing is applied.
+    pub fn before_apply(
+        mut self,
+        hook: impl Fn(&Path, &[HashlineEdit]) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.before.push(Arc::new(hook));
+        self
+    }
+
+    /// Run `hook` after a batch has changed the content.
+    pub fn after_apply(mut self, hook: impl Fn(&AppliedBatch) + Send + Sync + 'static) -> Self {
+        self.after.push(Arc::new(hook));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.before.is_empty() && self.after.is_empty()
+    }
+
+    /// Run the before-apply hooks in registration order, stopping at the
+    /// first rejection.
+    pub fn run_before(&self, path: &Path, edits: &[HashlineEdit]) -> Result<(), HashlineError> {
+        for hook in &self.before {
+            hook(path, edits).map_err(HashlineError::Rejected)?;
+        }
+        Ok(())
+    }
+
+    /// Run the after-apply hooks for a batch that turned `old_content` into
+    /// `outcome.content`, hashed as `new_file`. Batches that changed nothing
+    /// are not reported.
+    pub fn run_after(
+        &self,
+        path: &Path,
+        edits: &[HashlineEdit],
+        old_content: &str,
+        outcome: &ApplyOutcome,
+        new_file: &HashedFile,
+    ) {
+        if self.after.is_empty() || outcome.content == old_content {
+            return;
+        }
+        let diff = hash_aware_diff_with(old_content, new_file, outcome.first_changed_line.unwrap_or(1));
+        let batch = AppliedBatch { path, edits, outcome, diff: &diff };
+        for hook in &self.after {
+            hook(&batch);
+        }
+    }
+}
+
+impl fmt::Debug for EditHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EditHooks")
+            .field("before", &self.before.len())
+            .field("after", &self.after.len())
+            .finish()
+    }
+}
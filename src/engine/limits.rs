@@ -0,0 +1,100 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::HashlineEdit;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Per-Call Limits
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Caps on a single batch, so one pathological request cannot tie up the
+/// process that applies it. Each cap is off when `None`, as by default.
+///
+/// Checked before a batch is planned, by every apply path: the library
+/// functions, `Editor`, the CLI and the ffi, node and wasm bindings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct Limits {
+    /// Largest content, in bytes, a batch may be applied to.
+    pub max_file_bytes: Option<u64>,
+    /// Most edits in one batch.
+    pub max_edits: Option<usize>,
+    /// Most lines the edits of one batch may carry, counting `lines`,
+    /// `expected_lines` and `values` over all of them.
+    pub max_payload_lines: Option<usize>,
+}
+
+/// Which of the [`Limits`] a call went over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Limit {
+    FileBytes,
+    Edits,
+    PayloadLines,
+}
+
+/// A call refused for going over one of its [`Limits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, thiserror::Error)]
+pub struct LimitExceeded {
+    pub limit: Limit,
+    pub max: u64,
+    pub actual: u64,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.limit {
+            Limit::FileBytes => write!(f, "File is {} bytes, over the {}-byte limit", self.actual, self.max),
+            Limit::Edits => write!(f, "Batch has {} edits, over the limit of {}", self.actual, self.max),
+            Limit::PayloadLines => write!(f, "Edits carry {} lines, over the limit of {}", self.actual, self.max),
+        }
+    }
+}
+
+impl Limits {
+    /// Refuse a batch of `edits` on `content` that goes over any cap.
+    pub fn check(&self, content: &str, edits: &[HashlineEdit]) -> Result<(), LimitExceeded> {
+        self.check_file_bytes(content.len() as u64)?;
+        self.check_batch(edits)
+    }
+
+    /// Refuse a batch that has too many edits or carries too many lines,
+    /// whatever it applies to.
+    pub fn check_batch(&self, edits: &[HashlineEdit]) -> Result<(), LimitExceeded> {
+        exceeds(Limit::Edits, self.max_edits, edits.len())?;
+        if self.max_payload_lines.is_some() {
+            exceeds(Limit::PayloadLines, self.max_payload_lines, edits.iter().map(payload_lines).sum())?;
+        }
+        Ok(())
+    }
+
+    /// Refuse a file of `bytes` bytes, before it is read.
+    pub fn check_file_bytes(&self, bytes: u64) -> Result<(), LimitExceeded> {
+        match self.max_file_bytes {
+            Some(max) if bytes > max => Err(LimitExceeded { limit: Limit::FileBytes, max, actual: bytes }),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn exceeds(limit: Limit, max: Option<usize>, actual: usize) -> Result<(), LimitExceeded> {
+    match max {
+        Some(max) if actual > max => Err(LimitExceeded { limit, max: max as u64, actual: actual as u64 }),
+        _ => Ok(()),
+    }
+}
+
+fn payload_lines(edit: &HashlineEdit) -> usize {
+    let (lines, expected_lines) = match edit {
+        HashlineEdit::Replace { lines, expected_lines, .. }
+        | HashlineEdit::Append { lines, expected_lines, .. }
+        | HashlineEdit::Prepend { lines, expected_lines, .. } => (lines.len(), expected_lines.as_ref()),
+        HashlineEdit::Delete { expected_lines, .. } => (0, expected_lines.as_ref()),
+        HashlineEdit::SetColumn { values, .. } => (values.len(), None),
+        _ => (0, None),
+    };
+    lines + expected_lines.map_or(0, Vec::len)
+}
@@ -0,0 +1,314 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::{Index, IndexMut, Range};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Line Diff
+// ═══════════════════════════════════════════════════════════════════════════
+//
+// The Myers diff behind the hash-aware and unified diffs and the GitHub
+// suggestions, needing only `alloc` so the engine builds without `std`. It
+// is the linear-space form of the algorithm, which splits each window at
+// its middle snake, followed by a pass that slides every insertion and
+// deletion as far as it goes to merge it with its neighbours. The result
+// matches the `similar` crate's Myers diff, which the engine used before.
+
+/// A run of lines in a [`diff_lines`] result: the same on both sides, or
+/// `old` lines replaced by `new` ones, where either side may be empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LineRun {
+    pub equal: bool,
+    pub old: Range<usize>,
+    pub new: Range<usize>,
+}
+
+/// Diff `old` against `new`, as alternating equal and changed runs that
+/// cover both in order.
+pub(crate) fn diff_lines<T: PartialEq>(old: &[T], new: &[T]) -> Vec<LineRun> {
+    let max_d = max_d(old.len(), new.len());
+    let mut myers = Myers { old, new, forward: V::new(max_d), backward: V::new(max_d), ops: Vec::new() };
+    myers.conquer(0..old.len(), 0..new.len());
+    let mut ops = myers.ops;
+    compact(old, new, &mut ops);
+
+    let mut runs: Vec<LineRun> = Vec::new();
+    for op in ops {
+        let equal = op.tag == Tag::Equal;
+        match runs.last_mut() {
+            Some(last) if last.equal && equal => {
+                last.old.end += op.len;
+                last.new.end += op.len;
+            }
+            // Deletions and insertions between two equal runs make one change
+            Some(last) if !last.equal && !equal => {
+                let (side, range) = match op.tag {
+                    Tag::Delete => (&mut last.old, op.old_range()),
+                    _ => (&mut last.new, op.new_range()),
+                };
+                if side.start == side.end {
+                    *side = range;
+                } else {
+                    side.end = range.end;
+                }
+            }
+            _ => runs.push(LineRun { equal, old: op.old_range(), new: op.new_range() }),
+        }
+    }
+    runs
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tag {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// One step of the raw diff. `len` counts the lines on both sides of an
+/// equal run, the old lines of a deletion and the new lines of an insertion.
+#[derive(Debug, Clone, Copy)]
+struct Op {
+    tag: Tag,
+    old: usize,
+    new: usize,
+    len: usize,
+}
+
+impl Op {
+    fn equal(old: usize, new: usize, len: usize) -> Op {
+        Op { tag: Tag::Equal, old, new, len }
+    }
+
+    fn old_range(&self) -> Range<usize> {
+        self.old..self.old + if self.tag == Tag::Insert { 0 } else { self.len }
+    }
+
+    fn new_range(&self) -> Range<usize> {
+        self.new..self.new + if self.tag == Tag::Delete { 0 } else { self.len }
+    }
+
+    fn shift(&mut self, up: usize, down: usize) {
+        self.old = self.old + down - up;
+        self.new = self.new + down - up;
+    }
+}
+
+fn common_prefix<T: PartialEq>(old: &[T], old_range: Range<usize>, new: &[T], new_range: Range<usize>) -> usize {
+    old[old_range].iter().zip(&new[new_range]).take_while(|(a, b)| a == b).count()
+}
+
+fn common_suffix<T: PartialEq>(old: &[T], old_range: Range<usize>, new: &[T], new_range: Range<usize>) -> usize {
+    old[old_range].iter().rev().zip(new[new_range].iter().rev()).take_while(|(a, b)| a == b).count()
+}
+
+// ───────────────────────────────────────────────────────────────────────────
+// Myers
+// ───────────────────────────────────────────────────────────────────────────
+
+/// The most edits a middle snake can be apart from either end.
+fn max_d(old_len: usize, new_len: usize) -> usize {
+    (old_len + new_len).div_ceil(2) + 1
+}
+
+/// The furthest point reached on each diagonal `k`, for `k` from `-max_d`.
+struct V {
+    offset: isize,
+    v: Vec<usize>,
+}
+
+impl V {
+    fn new(max_d: usize) -> V {
+        V { offset: max_d as isize, v: vec![0; 2 * max_d] }
+    }
+}
+
+impl Index<isize> for V {
+    type Output = usize;
+
+    fn index(&self, k: isize) -> &usize {
+        &self.v[(k + self.offset) as usize]
+    }
+}
+
+impl IndexMut<isize> for V {
+    fn index_mut(&mut self, k: isize) -> &mut usize {
+        &mut self.v[(k + self.offset) as usize]
+    }
+}
+
+struct Myers<'a, T> {
+    old: &'a [T],
+    new: &'a [T],
+    forward: V,
+    backward: V,
+    ops: Vec<Op>,
+}
+
+impl<T: PartialEq> Myers<'_, T> {
+    /// Diff a window: strip what it starts and ends with, then split the
+    /// rest at its middle snake and diff both halves.
+    fn conquer(&mut self, mut old: Range<usize>, mut new: Range<usize>) {
+        let prefix = common_prefix(self.old, old.clone(), self.new, new.clone());
+        if prefix > 0 {
+            self.ops.push(Op::equal(old.start, new.start, prefix));
+        }
+        old.start += prefix;
+        new.start += prefix;
+        let suffix = common_suffix(self.old, old.clone(), self.new, new.clone());
+        old.end -= suffix;
+        new.end -= suffix;
+
+        if old.is_empty() && new.is_empty() {
+        } else if new.is_empty() {
+            self.ops.push(Op { tag: Tag::Delete, old: old.start, new: new.start, len: old.len() });
+        } else if old.is_empty() {
+            self.ops.push(Op { tag: Tag::Insert, old: old.start, new: new.start, len: new.len() });
+        } else if let Some((x, y)) = self.middle_snake(old.clone(), new.clone()) {
+            self.conquer(old.start..x, new.start..y);
+            self.conquer(x..old.end, y..new.end);
+        } else {
+            self.ops.push(Op { tag: Tag::Delete, old: old.start, new: new.start, len: old.len() });
+            self.ops.push(Op { tag: Tag::Insert, old: old.start, new: new.start, len: new.len() });
+        }
+
+        if suffix > 0 {
+            self.ops.push(Op::equal(old.end, new.end, suffix));
+        }
+    }
+
+    /// Where the forward and backward searches for the shortest edit
+    /// script of a window first overlap.
+    fn middle_snake(&mut self, old: Range<usize>, new: Range<usize>) -> Option<(usize, usize)> {
+        let (n, m) = (old.len(), new.len());
+        let delta = n as isize - m as isize;
+        let odd = delta & 1 == 1;
+        self.forward[1] = 0;
+        self.backward[1] = 0;
+
+        for d in 0..max_d(n, m) as isize {
+            for k in (-d..=d).rev().step_by(2) {
+                let vf = &self.forward;
+                let mut x = if k == -d || (k != d && vf[k - 1] < vf[k + 1]) { vf[k + 1] } else { vf[k - 1] + 1 };
+                let y = (x as isize - k) as usize;
+                let (x0, y0) = (x, y);
+                if x < n && y < m {
+                    x += common_prefix(self.old, old.start + x..old.end, self.new, new.start + y..new.end);
+                }
+                self.forward[k] = x;
+                if odd && (k - delta).abs() < d && x + self.backward[-(k - delta)] >= n {
+                    return Some((old.start + x0, new.start + y0));
+                }
+            }
+
+            for k in (-d..=d).rev().step_by(2) {
+                let vb = &self.backward;
+                let mut x = if k == -d || (k != d && vb[k - 1] < vb[k + 1]) { vb[k + 1] } else { vb[k - 1] + 1 };
+                let mut y = (x as isize - k) as usize;
+                if x < n && y < m {
+                    let advance = common_suffix(self.old, old.start..old.start + n - x, self.new, new.start..new.start + m - y);
+                    x += advance;
+                    y += advance;
+                }
+                self.backward[k] = x;
+                if !odd && (k - delta).abs() <= d && x + self.forward[-(k - delta)] >= n {
+                    return Some((old.start + n - x, new.start + m - y));
+                }
+            }
+        }
+        None
+    }
+}
+
+// ───────────────────────────────────────────────────────────────────────────
+// Compaction
+// ───────────────────────────────────────────────────────────────────────────
+
+/// Slide each deletion, then each insertion, up and then down as far as
+/// the lines around it allow, merging it into any change it runs into.
+fn compact<T: PartialEq>(old: &[T], new: &[T], ops: &mut Vec<Op>) {
+    for tag in [Tag::Delete, Tag::Insert] {
+        let mut i = 0;
+        while i < ops.len() {
+            if ops[i].tag == tag {
+                i = shift_up(old, new, ops, i);
+                i = shift_down(old, new, ops, i);
+            }
+            i += 1;
+        }
+    }
+}
+
+fn shift_up<T: PartialEq>(old: &[T], new: &[T], ops: &mut Vec<Op>, mut i: usize) -> usize {
+    while i > 0 {
+        let (prev, this) = (ops[i - 1], ops[i]);
+        match (prev.tag, this.tag) {
+            (Tag::Equal, _) => {
+                // Only an insertion has lines to compare; a deletion stays
+                let slide = common_suffix(old, prev.old_range(), new, this.new_range());
+                if slide > 0 {
+                    if ops.get(i + 1).is_some_and(|next| next.tag == Tag::Equal) {
+                        ops[i + 1].shift(slide, 0);
+                        ops[i + 1].len += slide;
+                    } else {
+                        ops.insert(i + 1, Op::equal(prev.old_range().end - slide, this.new_range().end - slide, slide));
+                    }
+                    ops[i].shift(slide, 0);
+                    ops[i - 1].len -= slide;
+                } else if prev.len != 0 {
+                    break;
+                }
+                if ops[i - 1].len == 0 {
+                    ops.remove(i - 1);
+                    i -= 1;
+                }
+            }
+            (a, b) if a == b => {
+                ops[i - 1].len += this.len;
+                ops.remove(i);
+                i -= 1;
+            }
+            _ => {
+                ops.swap(i - 1, i);
+                i -= 1;
+            }
+        }
+    }
+    i
+}
+
+fn shift_down<T: PartialEq>(old: &[T], new: &[T], ops: &mut Vec<Op>, mut i: usize) -> usize {
+    while i + 1 < ops.len() {
+        let (this, next) = (ops[i], ops[i + 1]);
+        match (this.tag, next.tag) {
+            (_, Tag::Equal) => {
+                let slide = common_prefix(old, next.old_range(), new, this.new_range());
+                if slide > 0 {
+                    if i > 0 && ops[i - 1].tag == Tag::Equal {
+                        ops[i - 1].len += slide;
+                    } else {
+                        ops.insert(i, Op::equal(next.old, this.new, slide));
+                        i += 1;
+                    }
+                    ops[i].shift(0, slide);
+                    ops[i + 1].shift(0, slide);
+                    ops[i + 1].len -= slide;
+                } else if next.len != 0 {
+                    break;
+                }
+                if ops[i + 1].len == 0 {
+                    ops.remove(i + 1);
+                }
+            }
+            (a, b) if a == b => {
+                ops[i].len += next.len;
+                ops.remove(i + 1);
+            }
+            _ => {
+                ops.swap(i, i + 1);
+                i += 1;
+            }
+        }
+    }
+    i
+}
+
@@ -0,0 +1,2206 @@
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh32::Xxh32;
+
+#[cfg(feature = "fs")]
+mod atomic_write;
+mod builder;
+mod delimited;
+mod diff;
+mod edit_op;
+mod editorconfig;
+#[cfg(feature = "std")]
+mod editor;
+mod error;
+mod gap_buffer;
+#[cfg(feature = "fs")]
+mod hash_cache;
+mod hashed_file;
+#[cfg(feature = "std")]
+mod hooks;
+mod limits;
+mod line_diff;
+mod payload;
+#[cfg(feature = "schemars")]
+mod schema;
+mod scoped;
+mod structured;
+#[cfg(feature = "tree-sitter")]
+mod syntax;
+mod trigram_index;
+
+#[cfg(feature = "fs")]
+pub use atomic_write::write_atomic;
+#[cfg(feature = "cli")]
+pub(crate) use atomic_write::{resolve_symlink, stage_file, sync_dir};
+pub use builder::{edits, EditBuilder, IntoAnchor};
+pub use delimited::{detect_delimiter, split_fields, Column, DelimitedField};
+pub use diff::{
+    changed_line_anchors, changed_line_anchors_with, generate_hash_aware_diff, hash_aware_diff,
+    hash_aware_diff_with, render_hash_aware_diff, unified_diff, DiffHunk, DiffLine, DiffLineKind, PostEditAnchor,
+    DIFF_CONTEXT_LINES, UNIFIED_DIFF_CONTEXT_LINES,
+};
+pub use edit_op::{CustomOp, EditOp};
+pub use editorconfig::{EditorConfig, EndOfLine, IndentStyle};
+#[cfg(feature = "std")]
+pub use editor::Editor;
+pub use error::HashlineError;
+#[cfg(feature = "fs")]
+pub use hash_cache::{HashCache, HashedWindow, DEFAULT_HASH_CACHE_DIR, SEED_BLOCK_LINES};
+#[cfg(feature = "cli")]
+pub(crate) use hash_cache::read_lines;
+use gap_buffer::{GapBuffer, Line};
+pub use hashed_file::{annotate_lines, annotate_lines_with_mode, line_byte_offsets, AnchorState, AnnotatedLine, HashedFile};
+#[cfg(feature = "rayon")]
+pub use hashed_file::PARALLEL_HASH_MIN_LINES;
+#[cfg(feature = "cli")]
+pub(crate) use hashed_file::hashes_in_parallel;
+#[cfg(feature = "std")]
+pub use hooks::{AppliedBatch, EditHooks};
+pub use limits::{Limit, LimitExceeded, Limits};
+#[cfg(feature = "cli")]
+pub(crate) use line_diff::diff_lines;
+pub use payload::{edits_from_value, migrate_edits, parse_edits, EDITS_FORMAT_VERSION};
+#[cfg(feature = "schemars")]
+pub use schema::{schema_for_edits, schema_for_options, schema_for_outcome};
+pub use scoped::AnchorMode;
+pub use structured::StructuredFormat;
+pub(crate) use scoped::LineHasher;
+#[cfg(feature = "tree-sitter")]
+pub use syntax::Grammar;
+pub use trigram_index::TrigramIndex;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Constants
+// ═══════════════════════════════════════════════════════════════════════════
+
+const NIBBLE_STR: &str = "ZPMQVRWSNKTXJBYH";
+/// `NIBBLE_STR` as a lookup table, indexed by nibble value.
+const NIBBLES: &[u8] = NIBBLE_STR.as_bytes();
+const HASH_SEED: u32 = 0;
+
+/// Lines searched above and below a stale anchor when auto-rebasing.
+pub const DEFAULT_REBASE_WINDOW: usize = 20;
+/// Lines shown above and below each stale anchor in a mismatch error.
+pub const DEFAULT_MISMATCH_CONTEXT: usize = 2;
+/// Minimum similarity (0.0-1.0) for a fuzzy content match to count as a
+/// candidate, unless [`ApplyOptions::fuzzy_threshold`] sets another.
+pub const DEFAULT_FUZZY_THRESHOLD: f64 = 0.7;
+/// The best fuzzy candidate must beat the runner-up by this much to be unambiguous.
+const FUZZY_AMBIGUITY_MARGIN: f64 = 0.1;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Hash Computation
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Compute a short 2-character hash of a single line using xxHash32.
+/// Uses whitespace-normalized line. Creates a hash chain where each line's hash
+/// depends on the previous line's hash, ensuring that any change invalidates
+/// all subsequent line hashes.
+pub fn compute_line_hash(line_num: usize, line: &str, prev_hash: Option<&str>) -> String {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let seed = match prev_hash {
+        Some(prev) => str_seed(prev),
+        None => first_line_seed(line_num, line),
+    };
+    hash_normalized(line, seed).into()
+}
+
+/// [`compute_line_hash`] without allocating: the chain is threaded through
+/// [`LineHash`] values and the line is hashed in place.
+pub fn line_hash(line_num: usize, line: &str, prev_hash: Option<LineHash>) -> LineHash {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let seed = match prev_hash {
+        Some(prev) => prev.seed(),
+        None => first_line_seed(line_num, line),
+    };
+    hash_normalized(line, seed)
+}
+
+/// A line's 2-character hash, e.g. `RT`, stored inline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct LineHash([u8; 2]);
+
+impl LineHash {
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.0).expect("hash characters are ASCII")
+    }
+
+    /// The lower 8 bits of the digest, one nibble per character.
+    pub(crate) fn from_digest(digest: u32) -> LineHash {
+        let hash = digest & 0xff;
+        LineHash([NIBBLES[(hash >> 4) as usize], NIBBLES[(hash & 0x0f) as usize]])
+    }
+
+    /// The seed the next line in the chain is hashed with.
+    pub(crate) fn seed(self) -> u32 {
+        (u32::from(self.0[0]) << 8) | u32::from(self.0[1])
+    }
+}
+
+impl core::fmt::Display for LineHash {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<LineHash> for String {
+    fn from(hash: LineHash) -> String {
+        hash.as_str().to_string()
+    }
+}
+
+impl TryFrom<String> for LineHash {
+    type Error = HashlineError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl PartialEq<str> for LineHash {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<String> for LineHash {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl core::str::FromStr for LineHash {
+    type Err = HashlineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.as_bytes() {
+            &[high, low] if NIBBLES.contains(&high) && NIBBLES.contains(&low) => Ok(LineHash([high, low])),
+            _ => Err(HashlineError::InvalidAnchor(format!("Invalid line hash '{}'", s))),
+        }
+    }
+}
+
+/// Seed from a previous hash given as text: its characters as base-256 digits.
+fn str_seed(prev: &str) -> u32 {
+    prev.chars().fold(0u32, |seed, c| seed.wrapping_mul(256).wrapping_add(c as u32))
+}
+
+/// The first line has no previous hash; lines without alphanumerics are
+/// seeded with their line number so blank and punctuation-only lines differ.
+fn first_line_seed(line_num: usize, line: &str) -> u32 {
+    // Whitespace is never alphanumeric, so checking the raw line is the same
+    // as checking the normalized one
+    if line.chars().any(|c| c.is_alphanumeric()) {
+        HASH_SEED
+    } else {
+        line_num as u32
+    }
+}
+
+/// xxHash32 of `line` with all whitespace removed, fed run by run instead
+/// of building the normalized string.
+fn hash_normalized(line: &str, seed: u32) -> LineHash {
+    let mut hasher = Xxh32::new(seed);
+    for run in line.split(char::is_whitespace) {
+        hasher.update(run.as_bytes());
+    }
+    LineHash::from_digest(hasher.digest())
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Anchor Parsing
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Parse a line reference like "5#ab" into structured form.
+/// Also accepts "5:abc" (old format) for backward compatibility.
+pub fn parse_anchor(anchor: &str) -> Option<(usize, String)> {
+    // Try new format: "LINE#HASH" (e.g., "5#ab")
+    let parts: Vec<&str> = anchor.splitn(2, '#').collect();
+    if parts.len() == 2 {
+        let line_num = parts[0].parse::<usize>().ok()?;
+        let hash = parts[1].to_string();
+        return Some((line_num, hash));
+    }
+    
+    // Try old format: "LINE:HASH" (e.g., "5:abc1")
+    let parts: Vec<&str> = anchor.splitn(2, ':').collect();
+    if parts.len() == 2 {
+        let line_num = parts[0].parse::<usize>().ok()?;
+        let hash = parts[1].to_string();
+        return Some((line_num, hash));
+    }
+    
+    None
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Hashline Edit Types
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AnchorRef {
+    pub line: usize,
+    pub hash: String,
+}
+
+impl core::fmt::Display for AnchorRef {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}#{}", self.line, self.hash)
+    }
+}
+
+impl Serialize for AnchorRef {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        // Serialize in the same "LINE#HASH" form accepted on input
+        serializer.collect_str(self)
+    }
+}
+
+/// Anchors appear in JSON as `"LINE#HASH"` strings.
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for AnchorRef {
+    fn schema_name() -> alloc::borrow::Cow<'static, str> {
+        "AnchorRef".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "pattern": format!("^[0-9]+#[{}]{{2}}$", NIBBLE_STR),
+            "description": "LINE#HASH anchor copied from read output, e.g. \"8#RT\"",
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for AnchorRef {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl core::str::FromStr for AnchorRef {
+    type Err = HashlineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Parse format: "LINE#HASH" (e.g., "8#RT")
+        let parts: Vec<&str> = s.splitn(2, '#').collect();
+        if parts.len() != 2 {
+            return Err(HashlineError::InvalidAnchor(
+                format!("Invalid anchor format '{}', expected format 'LINE#HASH' (e.g., '8#RT')", s)
+            ));
+        }
+        
+        let line = parts[0].parse::<usize>()
+            .map_err(|_| HashlineError::InvalidAnchor(
+                format!("Invalid line number '{}' in anchor '{}', expected format 'LINE#HASH' (e.g., '8#RT')", parts[0], s)
+            ))?;
+        
+        let hash = parts[1].to_string();
+        
+        Ok(AnchorRef { line, hash })
+    }
+}
+
+/// A single hashline edit operation.
+///
+/// Every anchored op accepts an optional `expected_lines`: the content the
+/// caller expects at the anchor (the whole `pos..=end` range for replace,
+/// starting at `pos` for append/prepend). When present, the content is
+/// verified even if the hash matches, and a stale hash is tolerated if the
+/// content still matches exactly, either in place or on a nearby line.
+///
+/// Non-exhaustive, so new ops can be added without a breaking release.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(tag = "op")]
+#[non_exhaustive]
+pub enum HashlineEdit {
+    #[serde(rename = "replace")]
+    Replace {
+        pos: AnchorRef,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        end: Option<AnchorRef>,
+        lines: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expected_lines: Option<Vec<String>>,
+    },
+    #[serde(rename = "append")]
+    Append {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pos: Option<AnchorRef>,
+        lines: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expected_lines: Option<Vec<String>>,
+    },
+    #[serde(rename = "prepend")]
+    Prepend {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pos: Option<AnchorRef>,
+        lines: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expected_lines: Option<Vec<String>>,
+    },
+    /// Remove the lines `pos..=end`, or just `pos`. The same change as a
+    /// replace with no lines, reported as a delete.
+    #[serde(rename = "delete")]
+    Delete {
+        pos: AnchorRef,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        end: Option<AnchorRef>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expected_lines: Option<Vec<String>>,
+    },
+    /// Cut the lines `pos..=end`, or just `pos`, and insert them below the
+    /// line `after`, which must be outside them. All three anchors are
+    /// checked, so the moved lines are the ones the caller read.
+    #[serde(rename = "move")]
+    Move {
+        pos: AnchorRef,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        end: Option<AnchorRef>,
+        after: AnchorRef,
+    },
+    /// Set the value at `path` (e.g. `dependencies.serde.version`) in a
+    /// JSON, YAML or TOML file, rewriting only the value's text so that the
+    /// key, comments and layout around it are kept. The value is located
+    /// when the batch is planned; `pos`, if given, must anchor the line it
+    /// starts on, and is checked like any other anchor. `format` is guessed
+    /// from the content when unset.
+    #[serde(rename = "set_path")]
+    SetPath {
+        path: String,
+        value: serde_json::Value,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pos: Option<AnchorRef>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        format: Option<StructuredFormat>,
+    },
+    /// Set one field of the CSV or TSV row at `pos`, leaving the rest of the
+    /// row as it is. `column` is a 0-based index or a name from the header
+    /// row. The value is quoted if the field was, or if it has to be.
+    /// `delimiter` is guessed from the content when unset.
+    #[serde(rename = "set_cell")]
+    SetCell {
+        pos: AnchorRef,
+        column: Column,
+        value: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        delimiter: Option<char>,
+    },
+    /// Set one column of the CSV or TSV rows `pos..=end`, like
+    /// [`HashlineEdit::SetCell`] on each row. `values` holds one value per
+    /// row, or a single value for them all.
+    #[serde(rename = "set_column")]
+    SetColumn {
+        pos: AnchorRef,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        end: Option<AnchorRef>,
+        column: Column,
+        values: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        delimiter: Option<char>,
+    },
+    /// An [`EditOp`] rewriting `pos..=end`, built with [`HashlineEdit::custom`].
+    /// Custom ops exist only in code and are never read from or written to JSON.
+    #[serde(skip)]
+    Custom {
+        pos: AnchorRef,
+        end: AnchorRef,
+        op: CustomOp,
+    },
+}
+
+/// Options controlling how a batch of hashline edits is validated and applied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct ApplyOptions {
+    /// Try to relocate stale anchors to a nearby line before failing.
+    pub auto_rebase: bool,
+    /// Lines above and below a stale anchor searched when auto-rebasing.
+    pub rebase_window: usize,
+    /// Apply the edits that validate and skip the rest instead of failing the batch.
+    pub allow_partial: bool,
+    /// Move an anchor to the adjacent line when only that line carries its hash.
+    pub fix_off_by_one: bool,
+    /// Lines of surrounding code shown around each stale anchor in mismatch errors.
+    pub mismatch_context: usize,
+    /// On hash mismatch, write the stale edits as `<<<<<<<`/`>>>>>>>` conflict
+    /// blocks at their anchored line numbers instead of failing.
+    pub conflict_markers: bool,
+    /// Apply by line number even when anchors are stale, recording every
+    /// check that was skipped in [`ApplyOutcome::bypassed`].
+    pub force: bool,
+    /// Collapse edits that repeat an earlier edit's target and content into
+    /// one, reporting the repeats as [`EditStatus::Duplicate`].
+    pub dedupe: bool,
+    /// Resolve overlapping edits when one already does what the other asks
+    /// for, reporting the absorbed edit as [`EditStatus::Merged`].
+    pub merge_overlaps: bool,
+    /// Apply edits one at a time in submission order, validating each edit's
+    /// anchors against the content left by the edits before it.
+    pub sequential: bool,
+    /// Indentation, line ending and final newline the result follows.
+    pub editorconfig: EditorConfig,
+    /// Line ending for the lines the edits insert, instead of the one most
+    /// of the file uses. Lines the batch leaves alone keep theirs. Wins over
+    /// `editorconfig.end_of_line`.
+    pub line_ending: Option<EndOfLine>,
+    /// How the anchors in the batch were hashed; they are checked against
+    /// the content hashed the same way.
+    pub anchor_mode: AnchorMode,
+    /// Minimum similarity (0.0-1.0) for a nearby line's content to make it
+    /// a candidate for a stale anchor, when auto-rebasing or in mismatch
+    /// errors. Lower finds more moved lines, at more risk of a wrong one.
+    pub fuzzy_threshold: f64,
+    /// Most content-match candidates listed for each stale anchor in
+    /// mismatch errors, best first; all of them when `None`. Auto-rebase
+    /// still weighs every candidate.
+    pub fuzzy_max_candidates: Option<usize>,
+    /// Caps on the size of the content and of the batch; none by default.
+    pub limits: Limits,
+}
+
+impl Default for ApplyOptions {
+    fn default() -> Self {
+        ApplyOptions {
+            auto_rebase: false,
+            rebase_window: DEFAULT_REBASE_WINDOW,
+            allow_partial: false,
+            fix_off_by_one: false,
+            mismatch_context: DEFAULT_MISMATCH_CONTEXT,
+            conflict_markers: false,
+            force: false,
+            dedupe: true,
+            merge_overlaps: false,
+            sequential: false,
+            editorconfig: EditorConfig::default(),
+            line_ending: None,
+            anchor_mode: AnchorMode::Chained,
+            fuzzy_threshold: DEFAULT_FUZZY_THRESHOLD,
+            fuzzy_max_candidates: None,
+            limits: Limits::default(),
+        }
+    }
+}
+
+/// Result of successfully applying a batch of hashline edits.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ApplyOutcome {
+    pub content: String,
+    pub first_changed_line: Option<usize>,
+    /// Stale anchors that were moved to a new line by auto-rebase.
+    pub relocations: Vec<Relocation>,
+    /// Per-edit outcome, in submission order.
+    pub statuses: Vec<EditStatus>,
+    /// Failed checks that `force` ignored.
+    pub bypassed: Vec<BypassedCheck>,
+}
+
+/// A validation that failed but was ignored because of `force`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BypassedCheck {
+    pub edit_index: usize,
+    /// The part of the edit that failed: "pos", "end" or "expected_lines".
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl core::fmt::Display for BypassedCheck {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "edits[{}].{}: {}", self.edit_index, self.field, self.message)
+    }
+}
+
+/// Why an edit was left out of a partially applied batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum SkipReason {
+    /// An anchor's hash did not match the file.
+    Mismatch,
+    /// The edit overlaps another edit in the batch.
+    Overlap,
+    /// The edit is malformed (line out of range, inverted range, ...).
+    Invalid,
+    /// The edit was rejected on interactive review.
+    Rejected,
+}
+
+impl core::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            SkipReason::Mismatch => "mismatch",
+            SkipReason::Overlap => "overlap",
+            SkipReason::Invalid => "invalid",
+            SkipReason::Rejected => "rejected",
+        })
+    }
+}
+
+/// Outcome of a single submitted edit.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(tag = "status", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum EditStatus {
+    /// Passed validation (reported by validation-only runs).
+    Valid,
+    Applied,
+    /// Written as a conflict block because its anchors were stale.
+    Conflicted { message: String },
+    /// Identical to an earlier edit and collapsed into it.
+    Duplicate { of: usize },
+    /// Overlapped an edit that already makes the same change, and was absorbed by it.
+    Merged { into: usize },
+    Skipped { reason: SkipReason, message: String },
+}
+
+impl EditStatus {
+    pub fn is_applied(&self) -> bool {
+        matches!(self, EditStatus::Applied)
+    }
+    
+    pub fn is_conflicted(&self) -> bool {
+        matches!(self, EditStatus::Conflicted { .. })
+    }
+    
+    /// True unless the edit was (or would be) skipped.
+    pub fn is_ok(&self) -> bool {
+        !matches!(self, EditStatus::Skipped { .. })
+    }
+}
+
+impl core::fmt::Display for EditStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            EditStatus::Valid => write!(f, "valid"),
+            EditStatus::Applied => write!(f, "applied"),
+            EditStatus::Conflicted { message } => write!(f, "conflicted: {}", message),
+            EditStatus::Duplicate { of } => write!(f, "duplicate of edits[{}]", of),
+            EditStatus::Merged { into } => write!(f, "merged into edits[{}]", into),
+            EditStatus::Skipped { reason, message } => write!(f, "skipped ({}): {}", reason, message),
+        }
+    }
+}
+
+/// A hash mismatch found during validation
+#[derive(Debug, Serialize)]
+pub struct HashMismatch {
+    pub line: usize,
+    pub expected: String,
+    pub actual: String,
+    /// Current text of the anchored line.
+    pub text: String,
+    /// Nearby lines the stale anchor may now refer to, best first.
+    pub candidates: Vec<RelocationCandidate>,
+}
+
+/// Error thrown when hashline references have stale hashes
+#[derive(Debug)]
+pub struct HashlineMismatchError {
+    pub mismatches: Vec<HashMismatch>,
+    /// The mismatched lines and their surrounding context, in line order,
+    /// with the hashes they had when the error was raised.
+    pub context_lines: Vec<MismatchContextLine>,
+}
+
+/// A line shown in a mismatch report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MismatchContextLine {
+    pub line: usize,
+    pub hash: String,
+    pub text: String,
+}
+
+impl core::fmt::Display for MismatchContextLine {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}#{}:{}", self.line, self.hash, self.text)
+    }
+}
+
+impl HashMismatch {
+    /// The mismatch for `anchor`, which is stale in `file`, with the lines
+    /// within `window` lines of it that it may now refer to. `hint` is
+    /// content the anchored line is expected to resemble, if known.
+    pub fn new(anchor: &AnchorRef, hint: Option<&str>, file: &HashedFile, window: usize) -> HashMismatch {
+        HashMismatch::with_options(anchor, hint, file, &ApplyOptions { rebase_window: window, ..ApplyOptions::default() })
+    }
+
+    /// [`HashMismatch::new`] with the rebase window and fuzzy matching
+    /// settings of `options`.
+    pub fn with_options(anchor: &AnchorRef, hint: Option<&str>, file: &HashedFile, options: &ApplyOptions) -> HashMismatch {
+        let mut candidates = relocation_candidates(anchor, hint, file, options.rebase_window, options.fuzzy_threshold);
+        if let Some(max) = options.fuzzy_max_candidates {
+            let hash_hits = candidates.iter().filter(|c| c.method == RelocationMethod::Hash).count();
+            candidates.truncate(hash_hits + max);
+        }
+        HashMismatch {
+            line: anchor.line,
+            expected: anchor.hash.clone(),
+            actual: file.hashes()[anchor.line - 1].to_string(),
+            text: file.lines()[anchor.line - 1].to_string(),
+            candidates,
+        }
+    }
+
+    /// The nearby lines that carry the stale anchor's hash and whose content
+    /// matches the edit's too. A hash alone recurs nearby by chance too often
+    /// to say the content moved there.
+    fn moved_to(&self) -> Vec<&AnchorRef> {
+        let content_matches = |line: usize| self.candidates.iter()
+            .any(|c| matches!(c.method, RelocationMethod::Fuzzy { .. }) && c.anchor.line == line);
+        self.candidates.iter()
+            .filter(|c| c.method == RelocationMethod::Hash && content_matches(c.anchor.line))
+            .map(|c| &c.anchor)
+            .collect()
+    }
+
+    /// The adjacent line's anchor, when the stale anchor's hash belongs to
+    /// exactly one of the lines directly above or below it. Only a
+    /// suggestion: the hash may sit there by chance.
+    pub fn off_by_one(&self) -> Option<&AnchorRef> {
+        let adjacent: Vec<&AnchorRef> = self.candidates.iter()
+            .filter(|c| c.method == RelocationMethod::Hash && c.anchor.line.abs_diff(self.line) == 1)
+            .map(|c| &c.anchor)
+            .collect();
+        match adjacent.as_slice() {
+            [only] => Some(only),
+            _ => None,
+        }
+    }
+}
+
+impl core::fmt::Display for HashlineMismatchError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mismatch_set: alloc::collections::BTreeSet<usize> = 
+            self.mismatches.iter().map(|m| m.line).collect();
+        
+        writeln!(f, "{} line{} have changed since last read. Use the updated LINE#ID references shown below (>>> marks changed lines).",
+            self.mismatches.len(),
+            if self.mismatches.len() > 1 { "s" } else { "" }
+        )?;
+        writeln!(f)?;
+        
+        let mut prev_line = 0usize;
+        for context_line in &self.context_lines {
+            if prev_line != 0 && context_line.line > prev_line + 1 {
+                writeln!(f, "    ...")?;
+            }
+            prev_line = context_line.line;
+            
+            let marker = if mismatch_set.contains(&context_line.line) { ">>>" } else { "   " };
+            writeln!(f, "{} {}", marker, context_line)?;
+        }
+        
+        // Point out anchors that are likely off by one, or whose content
+        // now sits on a nearby line: the line shifted rather than changed.
+        for m in &self.mismatches {
+            if let Some(adjacent) = m.off_by_one() {
+                writeln!(f, "\n{}#{}: off by one? Did you mean line {} ({})", m.line, m.expected, adjacent.line, adjacent)?;
+                continue;
+            }
+            let moved = m.moved_to();
+            match moved.as_slice() {
+                [] => {}
+                [only] => writeln!(f, "\n{}#{}: content appears to have moved to line {} (use {})",
+                    m.line, m.expected, only.line, only)?,
+                _ => {
+                    let lines: Vec<String> = moved.iter().map(|a| a.line.to_string()).collect();
+                    writeln!(f, "\n{}#{}: content may have moved to one of lines {}",
+                        m.line, m.expected, lines.join(", "))?;
+                }
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+impl core::error::Error for HashlineMismatchError {}
+
+impl HashlineMismatchError {
+    /// Capture the stale anchors together with `context` lines of `file`
+    /// above and below each, so rendering never re-reads the file.
+    pub fn new(mismatches: Vec<HashMismatch>, file: &HashedFile, context: usize) -> Self {
+        let mut display_lines: Vec<usize> = Vec::new();
+        for m in &mismatches {
+            let lo = m.line.saturating_sub(context).max(1);
+            let hi = (m.line + context).min(file.len());
+            display_lines.extend(lo..=hi);
+        }
+        display_lines.sort();
+        display_lines.dedup();
+        
+        let context_lines = display_lines.into_iter()
+            .map(|line| MismatchContextLine {
+                line,
+                hash: file.hashes()[line - 1].to_string(),
+                text: file.lines()[line - 1].to_string(),
+            })
+            .collect();
+        HashlineMismatchError { mismatches, context_lines }
+    }
+    
+    /// Structured form of the error for JSON output, so callers can repair
+    /// stale anchors programmatically instead of parsing the text rendering.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mismatches: Vec<serde_json::Value> = self.mismatches.iter()
+            .map(|m| serde_json::json!({
+                "anchor": format!("{}#{}", m.line, m.expected),
+                "line": m.line,
+                "expected_hash": m.expected,
+                "current_hash": m.actual,
+                "current_anchor": format!("{}#{}", m.line, m.actual),
+                "current_text": m.text,
+                "did_you_mean": m.off_by_one(),
+                "candidates": m.candidates,
+            }))
+            .collect();
+        serde_json::json!({
+            "error": "hash_mismatch",
+            "message": self.to_string(),
+            "mismatches": mismatches,
+        })
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Anchor Relocation
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// How a stale anchor was matched to its new line.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum RelocationMethod {
+    /// The edit's content matched a nearby line that also carries the
+    /// anchor's hash.
+    Hash,
+    /// The edit's content closely matched exactly one nearby line.
+    #[serde(rename = "content")]
+    Fuzzy { similarity: f64 },
+    /// The edit's `expected_lines` matched the file exactly.
+    ExpectedLines,
+    /// The anchor's hash belongs to the line directly above or below.
+    OffByOne,
+}
+
+/// A line a stale anchor may have moved to.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RelocationCandidate {
+    pub anchor: AnchorRef,
+    #[serde(flatten)]
+    pub method: RelocationMethod,
+}
+
+/// A stale anchor that auto-rebase moved to a different line.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Relocation {
+    /// Index of the edit in the submitted array.
+    pub edit_index: usize,
+    /// Which anchor of the edit was moved ("pos", "end" or "after").
+    pub field: &'static str,
+    pub from: AnchorRef,
+    pub to: AnchorRef,
+    pub method: RelocationMethod,
+}
+
+impl core::fmt::Display for Relocation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "edits[{}].{}: {}#{} -> {}#{} ",
+            self.edit_index, self.field, self.from.line, self.from.hash, self.to.line, self.to.hash)?;
+        match self.method {
+            RelocationMethod::Hash => write!(f, "(hash match)"),
+            RelocationMethod::Fuzzy { similarity } => {
+                write!(f, "(content match, {:.0}% similar)", similarity * 100.0)
+            }
+            RelocationMethod::ExpectedLines => write!(f, "(matched expected_lines)"),
+            RelocationMethod::OffByOne => write!(f, "(off by one)"),
+        }
+    }
+}
+
+/// Edit distance between two strings, counted in characters. Keeps two rows
+/// of the distance matrix, over the shorter string, rather than all of it.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut prev: Vec<usize> = (0..=short.len()).collect();
+    let mut curr = vec![0usize; short.len() + 1];
+    for (i, &long_char) in long.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, &short_char) in short.iter().enumerate() {
+            let cost = usize::from(long_char != short_char);
+            curr[j + 1] = (prev[j + 1] + 1).min(curr[j] + 1).min(prev[j] + cost);
+        }
+        core::mem::swap(&mut prev, &mut curr);
+    }
+    prev[short.len()]
+}
+
+/// [`levenshtein`], or `None` as soon as the distance is known to exceed
+/// `max_distance`. Only the diagonal band of width `2 * max_distance + 1` is
+/// computed, so this costs O(len · max_distance) instead of O(len²).
+pub fn levenshtein_bounded(a: &str, b: &str, max_distance: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max_distance {
+        return None;
+    }
+    // A shared prefix or suffix never adds to the distance
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(x, y)| x == y).count();
+    let a = &a[prefix..a.len() - suffix];
+    let b = &b[prefix..b.len() - suffix];
+
+    // Cells outside the band hold `too_far`, which is already over budget
+    let too_far = max_distance.saturating_add(1);
+    let mut prev: Vec<usize> = (0..=b.len()).map(|j| j.min(too_far)).collect();
+    let mut curr = vec![too_far; b.len() + 1];
+    for i in 1..=a.len() {
+        let lo = i.saturating_sub(max_distance).max(1);
+        let hi = i.saturating_add(max_distance).min(b.len());
+        curr[lo - 1] = if lo == 1 { i.min(too_far) } else { too_far };
+        let mut row_min = curr[lo - 1];
+        for j in lo..=hi {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let cell = (prev[j] + 1).min(curr[j - 1] + 1).min(prev[j - 1] + cost).min(too_far);
+            curr[j] = cell;
+            row_min = row_min.min(cell);
+        }
+        if hi < b.len() {
+            curr[hi + 1] = too_far;
+        }
+        // Row minimums never decrease, so the distance is already too large
+        if row_min > max_distance {
+            return None;
+        }
+        core::mem::swap(&mut prev, &mut curr);
+    }
+    Some(prev[b.len()]).filter(|&distance| distance <= max_distance)
+}
+
+/// Similarity of two lines in the range 0.0-1.0, ignoring surrounding
+/// whitespace: one minus their edit distance over the longer line's length.
+/// Lines that are both empty are identical.
+pub fn line_similarity(a: &str, b: &str) -> f64 {
+    let a = a.trim();
+    let b = b.trim();
+    let max_len = a.chars().count().max(b.chars().count());
+    if max_len == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / max_len as f64
+}
+
+/// [`line_similarity`], if it is at least `min_similarity`. Lines whose
+/// lengths alone rule that out are skipped without computing a distance, and
+/// the distance computation gives up once it exceeds what the threshold allows.
+pub fn line_similarity_at_least(a: &str, b: &str, min_similarity: f64) -> Option<f64> {
+    let a = a.trim();
+    let b = b.trim();
+    let max_len = a.chars().count().max(b.chars().count());
+    if max_len == 0 {
+        return (1.0 >= min_similarity).then_some(1.0);
+    }
+    let max_distance = max_edit_distance(min_similarity, max_len);
+    let distance = levenshtein_bounded(a, b, max_distance)?;
+    let similarity = 1.0 - distance as f64 / max_len as f64;
+    (similarity >= min_similarity).then_some(similarity)
+}
+
+/// The most edits two lines at most `max_len` characters long can be apart
+/// and still be `min_similarity` alike. Rounded up so float error can only
+/// let through a distance the exact similarity check then rejects.
+pub(crate) fn max_edit_distance(min_similarity: f64, max_len: usize) -> usize {
+    // `f64::ceil` needs `std`; the clamp leaves a non-negative value, which the cast rounds down
+    let distance = ((1.0 - min_similarity) * max_len as f64).clamp(0.0, max_len as f64);
+    let truncated = distance as usize;
+    if (truncated as f64) < distance { truncated + 1 } else { truncated }
+}
+
+/// Find lines whose content is similar to `needle`.
+/// Returns (1-indexed line, similarity) pairs, best match first.
+/// Use a [`TrigramIndex`] instead to search the same lines repeatedly.
+pub fn find_fuzzy_match(file_lines: &[impl AsRef<str>], needle: &str, min_similarity: f64) -> Vec<(usize, f64)> {
+    TrigramIndex::new(file_lines).find_fuzzy_match(needle, min_similarity)
+}
+
+/// The line directly above or below a stale anchor, if exactly one of them
+/// carries the anchor's hash. Getting the line number wrong by one while
+/// copying the hash correctly is the most common anchor mistake.
+fn adjacent_hash_match(anchor: &AnchorRef, file: &HashedFile) -> Option<usize> {
+    let hits: Vec<usize> = [anchor.line.checked_sub(1), Some(anchor.line + 1)]
+        .into_iter()
+        .flatten()
+        .filter(|&n| file.hash_at(n) == Some(anchor.hash.as_str()))
+        .collect();
+    match hits.as_slice() {
+        [only] => Some(*only),
+        _ => None,
+    }
+}
+
+/// Whether `hint`, content an edit is expected to resemble, matches line
+/// `line` of `file` at least `min_similarity` and clearly better than line
+/// `over`. A neighbour carries a stale anchor's hash by chance about once in
+/// 128 anchors, so a hash hit is only acted on when the content agrees.
+fn content_prefers(file: &HashedFile, line: usize, over: usize, hint: Option<&str>, min_similarity: f64) -> bool {
+    let Some(hint) = hint.filter(|h| !h.trim().is_empty()) else {
+        return false;
+    };
+    let similarity = |n: usize| line_similarity(&file.lines()[n - 1], hint);
+    let target = similarity(line);
+    target >= min_similarity && target - similarity(over) >= FUZZY_AMBIGUITY_MARGIN
+}
+
+/// Collect the lines a stale anchor may now refer to within `window` lines.
+/// Lines carrying the anchor's hash come first; lines whose content resembles
+/// `hint` (content the edit is expected to resemble) follow, best match first.
+/// The anchored line itself is never a candidate, nor a line whose content
+/// is less than `min_similarity` like `hint`.
+fn relocation_candidates(
+    anchor: &AnchorRef,
+    hint: Option<&str>,
+    file: &HashedFile,
+    window: usize,
+    min_similarity: f64,
+) -> Vec<RelocationCandidate> {
+    if file.is_empty() {
+        return Vec::new();
+    }
+    let lo = anchor.line.saturating_sub(window).max(1);
+    let hi = (anchor.line + window).min(file.len());
+    let candidate_at = |line: usize, method| RelocationCandidate {
+        anchor: file.anchor(line).expect("candidate lies within the file"),
+        method,
+    };
+    
+    let mut candidates: Vec<RelocationCandidate> = (lo..=hi)
+        .filter(|&n| n != anchor.line && file.hash_at(n) == Some(anchor.hash.as_str()))
+        .map(|n| candidate_at(n, RelocationMethod::Hash))
+        .collect();
+    
+    if let Some(hint) = hint.filter(|h| !h.trim().is_empty()) {
+        candidates.extend(
+            find_fuzzy_match(&file.lines()[lo - 1..hi], hint, min_similarity)
+                .into_iter()
+                .map(|(n, similarity)| (n + lo - 1, similarity))
+                .filter(|(n, _)| *n != anchor.line)
+                .map(|(n, similarity)| candidate_at(n, RelocationMethod::Fuzzy { similarity })),
+        );
+    }
+    candidates
+}
+
+/// Try to find the line a stale anchor now refers to, from the content the
+/// edit is expected to resemble: the best content match is used if it
+/// clearly beats the runner-up. Only unambiguous matches are returned.
+///
+/// The anchor's hash alone never moves an edit. There are only 256 hashes,
+/// so once a file has changed some nearby line carries the stale one by
+/// chance more often than not. A hash hit only counts on a line the content
+/// matches too, where it can settle a tie between equally close matches.
+fn relocate_anchor(
+    anchor: &AnchorRef,
+    hint: Option<&str>,
+    file: &HashedFile,
+    window: usize,
+    min_similarity: f64,
+) -> Option<(usize, RelocationMethod)> {
+    let candidates = relocation_candidates(anchor, hint, file, window, min_similarity);
+    log::trace!("relocation candidates for {}: {:?}", anchor, candidates);
+    
+    // The anchored line competes too: if it matches best, it was edited in
+    // place, which is a real conflict rather than a shift.
+    let own_similarity = hint.map(|h| line_similarity(&file.lines()[anchor.line - 1], h)).unwrap_or(0.0);
+    let mut fuzzy: Vec<(usize, f64)> = candidates.iter()
+        .filter_map(|c| match c.method {
+            RelocationMethod::Fuzzy { similarity } => Some((c.anchor.line, similarity)),
+            _ => None,
+        })
+        .collect();
+    if own_similarity >= min_similarity {
+        fuzzy.push((anchor.line, own_similarity));
+        fuzzy.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    }
+    
+    // The lines matching about as well as the best one. When the anchored
+    // line is among them, it may have been edited in place.
+    let (_, best_similarity) = *fuzzy.first()?;
+    let close: Vec<(usize, f64)> = fuzzy.into_iter()
+        .take_while(|(_, similarity)| best_similarity - similarity < FUZZY_AMBIGUITY_MARGIN)
+        .collect();
+    if close.iter().any(|(n, _)| *n == anchor.line) {
+        return None;
+    }
+    let has_hash = |line: usize| candidates.iter().any(|c| c.method == RelocationMethod::Hash && c.anchor.line == line);
+    match close.as_slice() {
+        [(line, _)] if has_hash(*line) => Some((*line, RelocationMethod::Hash)),
+        [(line, similarity)] => Some((*line, RelocationMethod::Fuzzy { similarity: *similarity })),
+        // The hash may settle a tie between lines the content matches alike
+        _ => match close.iter().filter(|(n, _)| has_hash(*n)).collect::<Vec<_>>().as_slice() {
+            [(line, _)] => Some((*line, RelocationMethod::Hash)),
+            _ => None,
+        },
+    }
+}
+
+/// Validates the anchors of an edit batch, rebasing stale ones when enabled.
+struct AnchorValidator<'a> {
+    file: &'a HashedFile<'a>,
+    options: &'a ApplyOptions,
+    mismatches: Vec<HashMismatch>,
+    validation_errors: Vec<String>,
+    relocations: Vec<Relocation>,
+    bypassed: Vec<BypassedCheck>,
+}
+
+impl<'a> AnchorValidator<'a> {
+    fn new(file: &'a HashedFile<'a>, options: &'a ApplyOptions) -> Self {
+        AnchorValidator {
+            file,
+            options,
+            mismatches: Vec::new(),
+            validation_errors: Vec::new(),
+            relocations: Vec::new(),
+            bypassed: Vec::new(),
+        }
+    }
+    
+    /// Check one anchor, returning where it was rebased to, if it was.
+    fn check(&mut self, anchor: &AnchorRef, edit_index: usize, field: &'static str, hint: Option<&str>) -> Option<AnchorRef> {
+        if anchor.line < 1 {
+            self.validation_errors.push(format!("Line {} must be >= 1", anchor.line));
+            return None;
+        }
+        let current = match self.file.revalidate(anchor) {
+            AnchorState::Valid => return None,
+            AnchorState::OutOfRange { len } => {
+                self.validation_errors.push(format!(
+                    "Line {} does not exist (file has {} lines)",
+                    anchor.line, len
+                ));
+                return None;
+            }
+            AnchorState::Stale { current } => current,
+        };
+        log::debug!("edits[{}].{}: anchor {} is stale, line is now {}", edit_index, field, anchor, current);
+        
+        if self.options.fix_off_by_one || self.options.auto_rebase {
+            let confirmed = |&line: &usize| content_prefers(self.file, line, anchor.line, hint, self.options.fuzzy_threshold);
+            if let Some(line) = adjacent_hash_match(anchor, self.file).filter(confirmed) {
+                return Some(self.relocate(anchor, line, RelocationMethod::OffByOne, edit_index, field));
+            }
+        }
+        
+        if self.options.auto_rebase {
+            if let Some((line, method)) = relocate_anchor(anchor, hint, self.file, self.options.rebase_window, self.options.fuzzy_threshold) {
+                return Some(self.relocate(anchor, line, method, edit_index, field));
+            }
+            log::debug!("edits[{}].{}: no unambiguous line to relocate {} to", edit_index, field, anchor);
+        }
+        
+        if self.options.force {
+            let message = format!("expected {}, line is now {}", anchor, current);
+            self.bypass(edit_index, field, message);
+            return None;
+        }
+        
+        self.push_mismatch(anchor, hint);
+        None
+    }
+    
+    fn bypass(&mut self, edit_index: usize, field: &'static str, message: String) {
+        log::info!("edits[{}].{}: forced past failed check: {}", edit_index, field, message);
+        self.bypassed.push(BypassedCheck { edit_index, field, message });
+    }
+    
+    fn relocate(&mut self, anchor: &AnchorRef, line: usize, method: RelocationMethod, edit_index: usize, field: &'static str) -> AnchorRef {
+        let to = self.file.anchor(line).expect("relocation target lies within the file");
+        self.relocations.push(Relocation {
+            edit_index,
+            field,
+            from: anchor.clone(),
+            to: to.clone(),
+            method,
+        });
+        log::debug!("relocated {}", self.relocations.last().expect("just pushed"));
+        to
+    }
+    
+    fn push_mismatch(&mut self, anchor: &AnchorRef, hint: Option<&str>) {
+        self.mismatches.push(HashMismatch::with_options(anchor, hint, self.file, self.options));
+    }
+    
+    fn hash_matches(&self, anchor: &AnchorRef) -> bool {
+        self.file.revalidate(anchor) == AnchorState::Valid
+    }
+    
+    fn block_matches(&self, start: usize, expected: &[String]) -> bool {
+        start >= 1
+            && start + expected.len() - 1 <= self.file.len()
+            && self.file.lines()[start - 1..start - 1 + expected.len()] == *expected
+    }
+    
+    /// Validate the anchors of a replace, delete or move of `pos..=end` whose new
+    /// content is `lines`.
+    fn check_range(
+        &mut self,
+        pos: &AnchorRef,
+        end: Option<&AnchorRef>,
+        edit_index: usize,
+        expected: Option<&[String]>,
+        lines: &[String],
+    ) -> Rebased {
+        let rebased = match self.check_expected(pos, end, edit_index, expected.unwrap_or(&[])) {
+            Some(rebased) => rebased,
+            None => Rebased {
+                pos: self.check(pos, edit_index, "pos", lines.first().map(|l| l.as_str())),
+                end: end.and_then(|end_ref| self.check(end_ref, edit_index, "end", lines.last().map(|l| l.as_str()))),
+                after: None,
+            },
+        };
+        // Check if start line > end line
+        if let Some(end_ref) = rebased.end.as_ref().or(end) {
+            let pos = rebased.pos.as_ref().unwrap_or(pos);
+            if pos.line > end_ref.line {
+                self.validation_errors.push(format!(
+                    "Range start line {} must be <= end line {}",
+                    pos.line, end_ref.line
+                ));
+            }
+        }
+        rebased
+    }
+
+    /// Validate an edit's anchors against its `expected_lines`.
+    /// Returns `None` when the content cannot vouch for the anchors, in which
+    /// case the regular hash checks should run.
+    fn check_expected(
+        &mut self,
+        pos: &AnchorRef,
+        end: Option<&AnchorRef>,
+        edit_index: usize,
+        expected: &[String],
+    ) -> Option<Rebased> {
+        if expected.is_empty() {
+            return None;
+        }
+        if let Some(end_ref) = end {
+            if end_ref.line < pos.line || end_ref.line - pos.line + 1 != expected.len() {
+                self.validation_errors.push(format!(
+                    "edits[{}]: expected_lines has {} lines but the range {}-{} covers {}",
+                    edit_index, expected.len(), pos.line, end_ref.line,
+                    (end_ref.line + 1).saturating_sub(pos.line)
+                ));
+                return Some(Rebased::default());
+            }
+        }
+        if pos.line < 1 || pos.line > self.file.len() {
+            return None;
+        }
+        
+        let hashes_match = self.hash_matches(pos) && end.is_none_or(|e| self.hash_matches(e));
+        if hashes_match {
+            if !self.block_matches(pos.line, expected) && self.options.force {
+                let message = format!("content at line {} does not match", pos.line);
+                self.bypass(edit_index, "expected_lines", message);
+            } else if !self.block_matches(pos.line, expected) {
+                self.validation_errors.push(format!(
+                    "edits[{}]: content at line {} does not match expected_lines",
+                    edit_index, pos.line
+                ));
+            }
+            return Some(Rebased::default());
+        }
+        
+        // Hash is stale: trust the content if it matches in place, or on
+        // exactly one nearby line.
+        let start = if self.block_matches(pos.line, expected) {
+            pos.line
+        } else {
+            let window = self.options.rebase_window;
+            let lo = pos.line.saturating_sub(window).max(1);
+            let hi = (pos.line + window).min(self.file.len());
+            let hits: Vec<usize> = (lo..=hi).filter(|&n| self.block_matches(n, expected)).collect();
+            match hits.as_slice() {
+                [only] => *only,
+                _ => {
+                    if self.options.force {
+                        let message = format!("content at line {} does not match", pos.line);
+                        self.bypass(edit_index, "expected_lines", message);
+                    }
+                    return None;
+                }
+            }
+        };
+        
+        let delta = start as isize - pos.line as isize;
+        Some(Rebased {
+            pos: self.tolerate(pos, start, edit_index, "pos"),
+            end: end.and_then(|end_ref| {
+                let end_line = (end_ref.line as isize + delta) as usize;
+                self.tolerate(end_ref, end_line, edit_index, "end")
+            }),
+            after: None,
+        })
+    }
+    
+    /// Move an anchor to `line` on the strength of its expected content,
+    /// recording the relocation when anything changed.
+    fn tolerate(&mut self, anchor: &AnchorRef, line: usize, edit_index: usize, field: &'static str) -> Option<AnchorRef> {
+        let to = self.file.anchor(line).expect("tolerated line lies within the file");
+        if *anchor == to {
+            return None;
+        }
+        self.relocations.push(Relocation {
+            edit_index,
+            field,
+            from: anchor.clone(),
+            to: to.clone(),
+            method: RelocationMethod::ExpectedLines,
+        });
+        log::debug!("relocated {}", self.relocations.last().expect("just pushed"));
+        Some(to)
+    }
+}
+
+/// The anchors of one edit that validation rebased, `None` where they hold.
+#[derive(Debug, Default)]
+struct Rebased {
+    pos: Option<AnchorRef>,
+    end: Option<AnchorRef>,
+    after: Option<AnchorRef>,
+}
+
+impl Rebased {
+    /// Point `edit`'s anchors at their rebased lines. The edit is copied
+    /// only when an anchor moved.
+    fn apply_to(self, edit: &mut Cow<'_, HashlineEdit>) {
+        fn set(anchor: &mut AnchorRef, to: Option<AnchorRef>) {
+            if let Some(to) = to {
+                *anchor = to;
+            }
+        }
+        let Rebased { pos: new_pos, end: new_end, after: new_after } = self;
+        if new_pos.is_none() && new_end.is_none() && new_after.is_none() {
+            return;
+        }
+        match edit.to_mut() {
+            HashlineEdit::Replace { pos, end, .. } | HashlineEdit::Delete { pos, end, .. } => {
+                set(pos, new_pos);
+                if new_end.is_some() {
+                    *end = new_end;
+                }
+            }
+            HashlineEdit::Move { pos, end, after } => {
+                set(pos, new_pos);
+                if new_end.is_some() {
+                    *end = new_end;
+                }
+                set(after, new_after);
+            }
+            HashlineEdit::Append { pos: Some(pos), .. } | HashlineEdit::Prepend { pos: Some(pos), .. } => set(pos, new_pos),
+            HashlineEdit::Custom { pos, end, .. } => {
+                set(pos, new_pos);
+                set(end, new_end);
+            }
+            _ => {}
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Hashline Edit Application
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Apply an array of hashline edits to file content.
+/// Edits are sorted bottom-up and validated before application.
+pub fn apply_hashline_edits(
+    content: &str,
+    edits: &[HashlineEdit],
+) -> Result<(String, Option<usize>), HashlineError> {
+    let outcome = apply_hashline_edits_with_options(content, edits, &ApplyOptions::default())?;
+    Ok((outcome.content, outcome.first_changed_line))
+}
+
+/// Check which edits would apply cleanly, without applying anything.
+/// Returns one status per submitted edit, in submission order.
+pub fn validate_hashline_edits(content: &str, edits: &[HashlineEdit]) -> Vec<EditStatus> {
+    validate_hashline_edits_with_options(content, edits, &ApplyOptions::default())
+}
+
+/// Like [`validate_hashline_edits`], honoring auto-rebase and related options.
+pub fn validate_hashline_edits_with_options(
+    content: &str,
+    edits: &[HashlineEdit],
+    options: &ApplyOptions,
+) -> Vec<EditStatus> {
+    if options.sequential {
+        let options = ApplyOptions { allow_partial: true, ..options.clone() };
+        return match apply_sequentially(content, edits, &options) {
+            Ok(outcome) => outcome.statuses.into_iter()
+                .map(|s| if s.is_applied() { EditStatus::Valid } else { s })
+                .collect(),
+            Err(e) => vec![EditStatus::Skipped { reason: SkipReason::Invalid, message: e.to_string() }; edits.len()],
+        };
+    }
+    validate_hashed_file(content, &HashedFile::from_content_with_mode(content, options.anchor_mode), edits, options)
+}
+
+/// [`validate_hashline_edits_with_options`] for `content` already hashed as `file`.
+pub(crate) fn validate_hashed_file(
+    content: &str,
+    file: &HashedFile,
+    edits: &[HashlineEdit],
+    options: &ApplyOptions,
+) -> Vec<EditStatus> {
+    if options.sequential {
+        return validate_hashline_edits_with_options(content, edits, options);
+    }
+    plan_edits(file, edits, options).statuses
+}
+
+/// A validated edit batch: anchors rebased, each edit's status decided, and
+/// the edits that should actually be applied identified.
+struct EditPlan<'e> {
+    /// The submitted edits, copied only where anchors were rebased or an
+    /// edit was resolved into a custom op.
+    edits: Vec<Cow<'e, HashlineEdit>>,
+    /// Indices of the edits to apply (valid, not duplicated, not overlapping)
+    active: Vec<usize>,
+    statuses: Vec<EditStatus>,
+    relocations: Vec<Relocation>,
+    bypassed: Vec<BypassedCheck>,
+    mismatches: Vec<HashMismatch>,
+    validation_errors: Vec<String>,
+    overlaps: Vec<Overlap>,
+}
+
+fn plan_edits<'e>(file: &HashedFile, edits: &'e [HashlineEdit], options: &ApplyOptions) -> EditPlan<'e> {
+    let mut statuses: Vec<EditStatus> = vec![EditStatus::Valid; edits.len()];
+    
+    // Pre-validate: collect all hash mismatches and check for invalid ranges.
+    // An edit is copied only when auto-rebase relocates one of its anchors.
+    let mut edits: Vec<Cow<'e, HashlineEdit>> = edits.iter().map(Cow::Borrowed).collect();
+    let mut validator = AnchorValidator::new(file, options);
+    
+    for (edit_index, edit) in edits.iter_mut().enumerate() {
+        let mismatches_before = validator.mismatches.len();
+        let errors_before = validator.validation_errors.len();
+        
+        // Path and cell edits become custom ops over the lines they rewrite
+        let resolved = match &**edit {
+            HashlineEdit::SetPath { path, value, pos, format } => {
+                Some(structured::resolve_set_path(file, path, value, pos.as_ref(), *format))
+            }
+            HashlineEdit::SetCell { pos, column, value, delimiter } => Some(delimited::resolve_set_cells(
+                file, "set_cell", pos, None, column, core::slice::from_ref(value), *delimiter,
+            )),
+            HashlineEdit::SetColumn { pos, end, column, values, delimiter } => Some(delimited::resolve_set_cells(
+                file, "set_column", pos, end.as_ref(), column, values, *delimiter,
+            )),
+            _ => None,
+        };
+        match resolved {
+            Some(Ok(resolved)) => *edit = Cow::Owned(resolved),
+            Some(Err(message)) => validator.validation_errors.push(format!("{}: {}", op_name(edit), message)),
+            None => {}
+        }
+        
+        let rebased = match &**edit {
+            HashlineEdit::Replace { pos, end, lines, expected_lines } => {
+                validator.check_range(pos, end.as_ref(), edit_index, expected_lines.as_deref(), lines)
+            }
+            HashlineEdit::Delete { pos, end, expected_lines } => {
+                validator.check_range(pos, end.as_ref(), edit_index, expected_lines.as_deref(), &[])
+            }
+            HashlineEdit::Move { pos, end, after } => {
+                let range = validator.check_range(pos, end.as_ref(), edit_index, None, &[]);
+                Rebased { after: validator.check(after, edit_index, "after", None), ..range }
+            }
+            HashlineEdit::Append { pos: Some(ref_pos), expected_lines, .. }
+            | HashlineEdit::Prepend { pos: Some(ref_pos), expected_lines, .. } => {
+                let expected = expected_lines.as_deref().unwrap_or(&[]);
+                let pos = match validator.check_expected(ref_pos, None, edit_index, expected) {
+                    Some(rebased) => rebased.pos,
+                    None => validator.check(ref_pos, edit_index, "pos", None),
+                };
+                Rebased { pos, ..Rebased::default() }
+            }
+            HashlineEdit::Custom { pos, end, .. } => Rebased {
+                pos: validator.check(pos, edit_index, "pos", None),
+                end: validator.check(end, edit_index, "end", None),
+                after: None,
+            },
+            HashlineEdit::Append { pos: None, .. } | HashlineEdit::Prepend { pos: None, .. } => Rebased::default(),
+            // Left unresolved only when locating the value failed
+            HashlineEdit::SetPath { .. } | HashlineEdit::SetCell { .. } | HashlineEdit::SetColumn { .. } => Rebased::default(),
+        };
+        rebased.apply_to(edit);
+        
+        // Checks on the rebased anchors
+        match &**edit {
+            HashlineEdit::Move { pos, end, after } => {
+                let end_line = end.as_ref().map_or(pos.line, |e| e.line);
+                if (pos.line..=end_line).contains(&after.line) {
+                    validator.validation_errors.push(format!(
+                        "Move target line {} is inside the moved lines {}-{}",
+                        after.line, pos.line, end_line
+                    ));
+                }
+            }
+            HashlineEdit::Custom { pos, end, op } => {
+                if pos.line > end.line {
+                    validator.validation_errors.push(format!(
+                        "Range start line {} must be <= end line {}",
+                        pos.line, end.line
+                    ));
+                } else if validator.validation_errors.len() == errors_before
+                    && validator.mismatches.len() == mismatches_before
+                {
+                    if let Err(message) = op.validate(&owned_lines(&file.lines()[pos.line - 1..end.line])) {
+                        validator.validation_errors.push(format!("{}: {}", op.name(), message));
+                    }
+                }
+            }
+            _ => {}
+        }
+        
+        if validator.validation_errors.len() > errors_before {
+            statuses[edit_index] = EditStatus::Skipped {
+                reason: SkipReason::Invalid,
+                message: validator.validation_errors[errors_before..].join("; "),
+            };
+        } else if validator.mismatches.len() > mismatches_before {
+            let message = validator.mismatches[mismatches_before..].iter()
+                .map(|m| format!("line {} is now {}#{} (expected {}#{})", m.line, m.line, m.actual, m.line, m.expected))
+                .collect::<Vec<_>>()
+                .join("; ");
+            statuses[edit_index] = EditStatus::Skipped { reason: SkipReason::Mismatch, message };
+        }
+    }
+    
+    let AnchorValidator { mismatches, validation_errors, mut relocations, mut bypassed, .. } = validator;
+    
+    // Indices of the edits still in play
+    let active: Vec<usize> = (0..edits.len()).filter(|&i| statuses[i].is_ok()).collect();
+    
+    // Collapse edits targeting same location with same content
+    let (mut active, duplicates) = if options.dedupe {
+        deduplicate_edits(&edits, &active)
+    } else {
+        (active, Vec::new())
+    };
+    for (index, of) in duplicates {
+        log::debug!("edits[{}]: duplicate of edits[{}]", index, of);
+        statuses[index] = EditStatus::Duplicate { of };
+    }
+    
+    // Check for overlapping edits. Neither side of an overlap is clearly
+    // the intended one, so both are skipped.
+    let mut overlaps = find_overlaps(&edits, &active, file.len());
+    if options.merge_overlaps {
+        while let Some((absorbed, into)) = overlaps.iter().find_map(|o| mergeable(&edits, o.first, o.second)) {
+            log::debug!("edits[{}]: merged into edits[{}]", absorbed, into);
+            statuses[absorbed] = EditStatus::Merged { into };
+            active.retain(|&i| i != absorbed);
+            overlaps = find_overlaps(&edits, &active, file.len());
+        }
+    }
+    for overlap in &overlaps {
+        log::debug!("edits[{}] and edits[{}] overlap, skipping both", overlap.first, overlap.second);
+        for (index, other) in [(overlap.first, overlap.second), (overlap.second, overlap.first)] {
+            statuses[index] = EditStatus::Skipped {
+                reason: SkipReason::Overlap,
+                message: format!("overlaps with edits[{}]", other),
+            };
+        }
+    }
+    active.retain(|&i| statuses[i].is_ok());
+    // A duplicate or merged edit shares the fate of the edit that absorbed it
+    for index in 0..statuses.len() {
+        let (of, relation) = match statuses[index] {
+            EditStatus::Duplicate { of } => (of, "duplicate of"),
+            EditStatus::Merged { into } => (into, "merged into"),
+            _ => continue,
+        };
+        if let EditStatus::Skipped { reason, .. } = statuses[of] {
+            statuses[index] = EditStatus::Skipped {
+                reason,
+                message: format!("{} edits[{}], which was skipped", relation, of),
+            };
+        }
+    }
+    relocations.retain(|r| active.contains(&r.edit_index));
+    bypassed.retain(|b| active.contains(&b.edit_index));
+    
+    EditPlan {
+        edits,
+        active,
+        statuses,
+        relocations,
+        bypassed,
+        mismatches,
+        validation_errors,
+        overlaps,
+    }
+}
+
+/// Apply an array of hashline edits to file content using the given options.
+pub fn apply_hashline_edits_with_options(
+    content: &str,
+    edits: &[HashlineEdit],
+    options: &ApplyOptions,
+) -> Result<ApplyOutcome, HashlineError> {
+    apply_to_hashed_file(content, &HashedFile::from_content_with_mode(content, options.anchor_mode), edits, options)
+}
+
+/// A batch applied in memory, with what a caller holding the content (an
+/// editor buffer, an LSP document) needs to show the change and keep editing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedEdits<'a> {
+    /// The new content plus relocations and per-edit statuses.
+    pub outcome: ApplyOutcome,
+    /// Structured hash-aware diff; empty when nothing changed.
+    pub diff: Vec<DiffHunk>,
+    /// Every line of the new content with its fresh hash, for the next batch.
+    pub file: HashedFile<'a>,
+    /// Anchors of the lines the batch inserted or rewrote.
+    pub changed: Vec<PostEditAnchor>,
+}
+
+/// Apply edits to `content` and diff the result, without touching disk.
+pub fn apply_and_diff<'a>(
+    content: &'a str,
+    edits: &[HashlineEdit],
+    options: &ApplyOptions,
+) -> Result<AppliedEdits<'a>, HashlineError> {
+    apply_and_diff_hashed_file(content, HashedFile::from_content_with_mode(content, options.anchor_mode), edits, options)
+}
+
+/// [`apply_and_diff`] for `content` already hashed as `old_file`.
+pub(crate) fn apply_and_diff_hashed_file<'a>(
+    content: &str,
+    old_file: HashedFile<'a>,
+    edits: &[HashlineEdit],
+    options: &ApplyOptions,
+) -> Result<AppliedEdits<'a>, HashlineError> {
+    let outcome = apply_to_hashed_file(content, &old_file, edits, options)?;
+    if outcome.content == content {
+        return Ok(AppliedEdits { outcome, diff: Vec::new(), file: old_file, changed: Vec::new() });
+    }
+    let first_changed_line = outcome.first_changed_line.unwrap_or(1);
+    let file = old_file.rehash_from(&outcome.content, first_changed_line);
+    let diff = hash_aware_diff_with(content, &file, first_changed_line);
+    let changed = changed_line_anchors_with(content, &file);
+    Ok(AppliedEdits { outcome, diff, file, changed })
+}
+
+/// Apply a batch to `content`, whose lines and hashes `file` already holds.
+pub(crate) fn apply_to_hashed_file(
+    content: &str,
+    file: &HashedFile,
+    edits: &[HashlineEdit],
+    options: &ApplyOptions,
+) -> Result<ApplyOutcome, HashlineError> {
+    options.limits.check(content, edits)?;
+    if options.sequential {
+        return apply_sequentially(content, edits, options);
+    }
+    if edits.is_empty() {
+        return Ok(ApplyOutcome {
+            content: content.to_string(),
+            first_changed_line: None,
+            relocations: Vec::new(),
+            statuses: Vec::new(),
+            bypassed: Vec::new(),
+        });
+    }
+    
+    let mut first_changed_line: Option<usize> = None;
+    
+    let EditPlan {
+        edits,
+        active,
+        mut statuses,
+        relocations,
+        bypassed,
+        mismatches,
+        validation_errors,
+        overlaps,
+    } = plan_edits(file, edits, options);
+    
+    if options.conflict_markers && !mismatches.is_empty()
+        && (options.allow_partial || validation_errors.is_empty())
+    {
+        return apply_with_conflicts(content, file, edits, statuses, relocations, options);
+    }
+    
+    if !options.allow_partial {
+        if !validation_errors.is_empty() {
+            return Err(HashlineError::Validation(validation_errors));
+        }
+        
+        if !mismatches.is_empty() {
+            return Err(HashlineError::Mismatch(HashlineMismatchError::new(mismatches, file, options.mismatch_context)));
+        }
+        
+        if !overlaps.is_empty() {
+            return Err(HashlineError::Overlap(overlaps.iter().map(|o| o.to_string()).collect()));
+        }
+    }
+    
+    for status in statuses.iter_mut().filter(|s| **s == EditStatus::Valid) {
+        *status = EditStatus::Applied;
+    }
+    let file_len = file.len();
+    let mut file_lines = GapBuffer::new(file.lines(), content);
+    
+    // Sort edits bottom-up (highest line first), later submissions first on a tie
+    let mut order = active;
+    order.sort_unstable_by_key(|&idx| core::cmp::Reverse((edit_sort_line(&edits[idx], file_len), idx)));
+    
+    // Apply edits
+    for edit in order.into_iter().map(|idx| &*edits[idx]) {
+        match edit {
+            HashlineEdit::Replace { pos, end, lines, .. } => {
+                if let Some(end_ref) = end {
+                    // Replace range
+                    let count = end_ref.line - pos.line + 1;
+                    file_lines.splice(pos.line - 1..pos.line - 1 + count, inserted(lines, &options.editorconfig));
+                } else {
+                    // Replace single line
+                    file_lines.splice(pos.line - 1..pos.line, inserted(lines, &options.editorconfig));
+                }
+                track_first_changed(&mut first_changed_line, pos.line);
+            }
+            HashlineEdit::Delete { pos, end, .. } => {
+                let end_line = end.as_ref().map_or(pos.line, |e| e.line);
+                file_lines.splice(pos.line - 1..end_line, core::iter::empty());
+                track_first_changed(&mut first_changed_line, pos.line);
+            }
+            HashlineEdit::Move { pos, end, after } => {
+                let (start, stop, shift) = move_region(pos, end.as_ref(), after);
+                file_lines.rotate_left(start - 1..stop, shift);
+                track_first_changed(&mut first_changed_line, start);
+            }
+            HashlineEdit::Append { pos, lines, .. } => {
+                if lines.is_empty() {
+                    continue;
+                }
+                if let Some(ref_pos) = pos {
+                    // Insert after specified line
+                    file_lines.splice(ref_pos.line..ref_pos.line, inserted(lines, &options.editorconfig));
+                    track_first_changed(&mut first_changed_line, ref_pos.line + 1);
+                } else {
+                    // Append at end of file
+                    if file_lines.is_single_empty_line() {
+                        file_lines.clear();
+                    }
+                    let start_idx = file_lines.len();
+                    file_lines.splice(start_idx..start_idx, inserted(lines, &options.editorconfig));
+                    track_first_changed(&mut first_changed_line, start_idx + 1);
+                }
+            }
+            HashlineEdit::Prepend { pos, lines, .. } => {
+                if lines.is_empty() {
+                    continue;
+                }
+                if let Some(ref_pos) = pos {
+                    // Insert before specified line
+                    file_lines.splice(ref_pos.line - 1..ref_pos.line - 1, inserted(lines, &options.editorconfig));
+                    track_first_changed(&mut first_changed_line, ref_pos.line);
+                } else {
+                    // Prepend at start of file
+                    if file_lines.is_single_empty_line() {
+                        file_lines.clear();
+                    }
+                    file_lines.splice(0..0, inserted(lines, &options.editorconfig));
+                    track_first_changed(&mut first_changed_line, 1);
+                }
+            }
+            HashlineEdit::Custom { pos, end, op } => {
+                let region: Vec<String> = file_lines.slice(pos.line - 1..end.line).iter().map(|l| l.text.to_string()).collect();
+                file_lines.splice(pos.line - 1..end.line, op.apply(&region).into_iter().map(Cow::Owned));
+                track_first_changed(&mut first_changed_line, pos.line);
+            }
+            HashlineEdit::SetPath { .. } | HashlineEdit::SetCell { .. } | HashlineEdit::SetColumn { .. } => {
+                unreachable!("{} edits are resolved when planned", op_name(edit))
+            }
+        }
+    }
+    
+    // Restore the original line endings and trailing newline, unless the
+    // editorconfig says otherwise
+    let result = options.editorconfig.join(&file_lines.into_lines(), content, options.line_ending);
+    Ok(ApplyOutcome {
+        content: result,
+        first_changed_line,
+        relocations,
+        statuses,
+        bypassed,
+    })
+}
+
+/// The lines `start..=stop` a move rewrites, from the moved lines through
+/// the target line, and how far it rotates them left.
+fn move_region(pos: &AnchorRef, end: Option<&AnchorRef>, after: &AnchorRef) -> (usize, usize, usize) {
+    let end_line = end.map_or(pos.line, |e| e.line);
+    let moved = end_line - pos.line + 1;
+    if after.line > end_line {
+        (pos.line, after.line, moved)
+    } else {
+        (after.line + 1, end_line, end_line - after.line - moved)
+    }
+}
+
+/// Lines of an edit for a [`GapBuffer`] splice, borrowed unless the
+/// editorconfig's indent style rewrites them.
+fn inserted<'a>(lines: &'a [String], editorconfig: &'a EditorConfig) -> impl DoubleEndedIterator<Item = Cow<'a, str>> {
+    lines.iter().map(|line| editorconfig.reindent(line))
+}
+
+/// Apply a batch in conflict-marker mode. Edits whose anchors are stale are
+/// replaced by a conflict block over the region their line numbers name,
+/// showing the current lines against what the edit wanted there. Everything
+/// else is applied as usual.
+fn apply_with_conflicts(
+    content: &str,
+    file: &HashedFile,
+    planned: Vec<Cow<'_, HashlineEdit>>,
+    statuses: Vec<EditStatus>,
+    relocations: Vec<Relocation>,
+    options: &ApplyOptions,
+) -> Result<ApplyOutcome, HashlineError> {
+    let file_lines = file.lines();
+    let current = |line: usize| file.anchor(line).expect("stale anchors were range-checked");
+    
+    let mut conflicts: Vec<(usize, String)> = Vec::new();
+    let edits: Vec<HashlineEdit> = planned.into_iter().enumerate()
+        .map(|(index, edit)| {
+            let EditStatus::Skipped { reason: SkipReason::Mismatch, message } = &statuses[index] else {
+                return edit.into_owned();
+            };
+            conflicts.push((index, message.clone()));
+            let (start, stop, incoming, stale) = match &*edit {
+                HashlineEdit::Replace { pos, end, lines, .. } => {
+                    let stop = end.as_ref().map_or(pos.line, |e| e.line);
+                    (pos.line, stop, lines.clone(), pos)
+                }
+                HashlineEdit::Delete { pos, end, .. } => {
+                    let stop = end.as_ref().map_or(pos.line, |e| e.line);
+                    (pos.line, stop, Vec::new(), pos)
+                }
+                HashlineEdit::Move { pos, end, after } => {
+                    let (start, stop, shift) = move_region(pos, end.as_ref(), after);
+                    let mut incoming = owned_lines(&file_lines[start - 1..stop]);
+                    incoming.rotate_left(shift);
+                    (start, stop, incoming, pos)
+                }
+                HashlineEdit::Append { pos: Some(pos), lines, .. } => {
+                    let mut incoming = vec![file_lines[pos.line - 1].to_string()];
+                    incoming.extend(lines.iter().cloned());
+                    (pos.line, pos.line, incoming, pos)
+                }
+                HashlineEdit::Prepend { pos: Some(pos), lines, .. } => {
+                    let mut incoming = lines.clone();
+                    incoming.push(file_lines[pos.line - 1].to_string());
+                    (pos.line, pos.line, incoming, pos)
+                }
+                // What the op would make of the region as it is now
+                HashlineEdit::Custom { pos, end, op } => {
+                    (pos.line, end.line, op.apply(&owned_lines(&file_lines[pos.line - 1..end.line])), pos)
+                }
+                // Unanchored inserts cannot go stale
+                _ => return edit.into_owned(),
+            };
+            HashlineEdit::Replace {
+                pos: current(start),
+                end: (stop > start).then(|| current(stop)),
+                lines: conflict_block(&file_lines[start - 1..stop], start, stop, &incoming, index, stale),
+                expected_lines: None,
+            }
+        })
+        .collect();
+    
+    // Every anchor now carries its current hash
+    let options = ApplyOptions {
+        auto_rebase: false,
+        fix_off_by_one: false,
+        conflict_markers: false,
+        ..options.clone()
+    };
+    let mut outcome = apply_hashline_edits_with_options(content, &edits, &options)?;
+    for (index, message) in conflicts {
+        if outcome.statuses[index].is_applied() {
+            outcome.statuses[index] = EditStatus::Conflicted { message };
+        }
+    }
+    outcome.relocations = relocations;
+    Ok(outcome)
+}
+
+/// Copies of `lines`, for the [`EditOp`] methods that take `&[String]`.
+fn owned_lines(lines: &[Cow<'_, str>]) -> Vec<String> {
+    lines.iter().map(|line| line.to_string()).collect()
+}
+
+/// Lines of a conflict block: the current region above, the edit's content below.
+fn conflict_block(
+    current: &[Cow<'_, str>],
+    start: usize,
+    stop: usize,
+    incoming: &[String],
+    edit_index: usize,
+    stale: &AnchorRef,
+) -> Vec<String> {
+    let region = if start == stop {
+        format!("line {}", start)
+    } else {
+        format!("lines {}-{}", start, stop)
+    };
+    let mut block = vec![format!("<<<<<<< current ({})", region)];
+    block.extend(current.iter().map(|line| line.to_string()));
+    block.push("=======".to_string());
+    block.extend(incoming.iter().cloned());
+    block.push(format!(">>>>>>> edits[{}] (stale anchor {})", edit_index, stale));
+    block
+}
+
+/// Apply edits one at a time in submission order. Each edit is validated
+/// against the content produced by the edits before it, so its anchors must
+/// use the hashes of that intermediate state.
+fn apply_sequentially(
+    content: &str,
+    edits: &[HashlineEdit],
+    options: &ApplyOptions,
+) -> Result<ApplyOutcome, HashlineError> {
+    // Conflict blocks would be labelled with the wrong edit index, and
+    // dedupe/overlap handling has nothing to compare against one edit at a
+    // time. The limits were checked against the whole batch.
+    let step_options = ApplyOptions {
+        sequential: false,
+        conflict_markers: false,
+        limits: Limits::default(),
+        ..options.clone()
+    };
+    let mut current = content.to_string();
+    let mut statuses = Vec::with_capacity(edits.len());
+    let mut relocations = Vec::new();
+    let mut bypassed = Vec::new();
+    
+    for (edit_index, edit) in edits.iter().enumerate() {
+        let step = match apply_hashline_edits_with_options(&current, core::slice::from_ref(edit), &step_options) {
+            Ok(step) => step,
+            Err(e @ HashlineError::Mismatch(_)) => return Err(e),
+            Err(e) => return Err(HashlineError::Sequence { edit_index, source: Box::new(e) }),
+        };
+        statuses.extend(step.statuses);
+        relocations.extend(step.relocations.into_iter().map(|r| Relocation { edit_index, ..r }));
+        bypassed.extend(step.bypassed.into_iter().map(|b| BypassedCheck { edit_index, ..b }));
+        current = step.content;
+    }
+    
+    let old_lines: Vec<&str> = content.lines().collect();
+    let new_lines: Vec<&str> = current.lines().collect();
+    let first_changed_line = (0..old_lines.len().max(new_lines.len()))
+        .find(|&i| old_lines.get(i) != new_lines.get(i))
+        .map(|i| i + 1);
+    Ok(ApplyOutcome {
+        content: current,
+        first_changed_line,
+        relocations,
+        statuses,
+        bypassed,
+    })
+}
+
+/// Split `active` edit indices into the edits to keep and the `(duplicate,
+/// original)` pairs of edits that repeat an earlier edit's target and content.
+fn deduplicate_edits(edits: &[Cow<'_, HashlineEdit>], active: &[usize]) -> (Vec<usize>, Vec<(usize, usize)>) {
+    // Keyed on the edit's position and its borrowed lines, so the map
+    // compares the content in place without copying it
+    let mut seen: alloc::collections::BTreeMap<(DedupePosition, &[String]), usize> = alloc::collections::BTreeMap::new();
+    let mut result = Vec::new();
+    let mut duplicates = Vec::new();
+    
+    for &i in active {
+        let key = match &*edits[i] {
+            HashlineEdit::Replace { pos, end, lines, .. } => {
+                (DedupePosition::Replace(pos.line, end.as_ref().map(|e| e.line)), lines.as_slice())
+            }
+            // The same change as a replace with no lines
+            HashlineEdit::Delete { pos, end, .. } => {
+                (DedupePosition::Replace(pos.line, end.as_ref().map(|e| e.line)), &[][..])
+            }
+            HashlineEdit::Move { pos, end, after } => {
+                (DedupePosition::Move(pos.line, end.as_ref().map(|e| e.line), after.line), &[][..])
+            }
+            HashlineEdit::Append { pos, lines, .. } => {
+                (DedupePosition::Append(pos.as_ref().map(|p| p.line)), lines.as_slice())
+            }
+            HashlineEdit::Prepend { pos, lines, .. } => {
+                (DedupePosition::Prepend(pos.as_ref().map(|p| p.line)), lines.as_slice())
+            }
+            // Custom ops cannot be compared, so none is a duplicate
+            HashlineEdit::Custom { .. }
+            | HashlineEdit::SetPath { .. }
+            | HashlineEdit::SetCell { .. }
+            | HashlineEdit::SetColumn { .. } => {
+                result.push(i);
+                continue;
+            }
+        };
+        
+        match seen.entry(key) {
+            alloc::collections::btree_map::Entry::Vacant(e) => {
+                e.insert(i);
+                result.push(i);
+            }
+            alloc::collections::btree_map::Entry::Occupied(e) => duplicates.push((i, *e.get())),
+        }
+    }
+    
+    (result, duplicates)
+}
+
+/// Where an edit applies, for [`deduplicate_edits`]; `None` is the end (append)
+/// or start (prepend) of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum DedupePosition {
+    Replace(usize, Option<usize>),
+    Append(Option<usize>),
+    Prepend(Option<usize>),
+    Move(usize, Option<usize>, usize),
+}
+
+/// The line an edit is ordered by when a batch is applied bottom-up.
+fn edit_sort_line(edit: &HashlineEdit, file_len: usize) -> usize {
+    match edit {
+        HashlineEdit::Replace { pos, end, .. } | HashlineEdit::Delete { pos, end, .. } => {
+            end.as_ref().map(|e| e.line).unwrap_or(pos.line)
+        }
+        HashlineEdit::Append { pos, .. } => pos.as_ref().map(|p| p.line).unwrap_or(file_len),
+        HashlineEdit::Prepend { pos, .. } => pos.as_ref().map(|p| p.line).unwrap_or(0),
+        HashlineEdit::Move { pos, end, after } => move_region(pos, end.as_ref(), after).1,
+        HashlineEdit::Custom { end, .. } => end.line,
+        HashlineEdit::SetPath { pos, .. } => pos.as_ref().map_or(0, |p| p.line),
+        HashlineEdit::SetCell { pos, .. } => pos.line,
+        HashlineEdit::SetColumn { pos, end, .. } => end.as_ref().unwrap_or(pos).line,
+    }
+}
+
+/// Two edits in a batch whose affected line ranges collide.
+struct Overlap {
+    first: usize,
+    second: usize,
+    first_range: (usize, usize),
+    second_range: (usize, usize),
+    first_desc: String,
+    second_desc: String,
+}
+
+impl core::fmt::Display for Overlap {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "  - edits[{}] {} at lines {}-{} overlaps with edits[{}] {} at lines {}-{}",
+            self.first, self.first_desc, self.first_range.0, self.first_range.1,
+            self.second, self.second_desc, self.second_range.0, self.second_range.1)
+    }
+}
+
+fn op_name(edit: &HashlineEdit) -> &str {
+    match edit {
+        HashlineEdit::Replace { .. } => "replace",
+        HashlineEdit::Append { .. } => "append",
+        HashlineEdit::Prepend { .. } => "prepend",
+        HashlineEdit::Delete { .. } => "delete",
+        HashlineEdit::Move { .. } => "move",
+        HashlineEdit::Custom { op, .. } => op.name(),
+        HashlineEdit::SetPath { .. } => "set_path",
+        HashlineEdit::SetCell { .. } => "set_cell",
+        HashlineEdit::SetColumn { .. } => "set_column",
+    }
+}
+
+/// Maximum characters of an edit's first line quoted in error messages.
+const SNIPPET_MAX_CHARS: usize = 40;
+
+/// Describe an edit as its op plus a short quote of its new lines,
+/// e.g. `replace ["fn main() {" +2 lines]`.
+fn describe_edit(edit: &HashlineEdit) -> String {
+    let lines = match edit {
+        HashlineEdit::Replace { lines, .. }
+        | HashlineEdit::Append { lines, .. }
+        | HashlineEdit::Prepend { lines, .. } => lines,
+        HashlineEdit::Delete { .. } | HashlineEdit::Custom { .. } => return op_name(edit).to_string(),
+        HashlineEdit::Move { after, .. } => return format!("move below line {}", after.line),
+        HashlineEdit::SetPath { path, .. } => return format!("set_path `{}`", path),
+        HashlineEdit::SetCell { column, .. } | HashlineEdit::SetColumn { column, .. } => {
+            return format!("{} {}", op_name(edit), column)
+        }
+    };
+    let snippet = match lines.first() {
+        None => "[]".to_string(),
+        Some(first) => {
+            let mut quoted: String = first.chars().take(SNIPPET_MAX_CHARS).collect();
+            if first.chars().count() > SNIPPET_MAX_CHARS {
+                quoted.push_str("...");
+            }
+            let more = if lines.len() > 1 { format!(" +{} lines", lines.len() - 1) } else { String::new() };
+            format!("[{:?}{}]", quoted, more)
+        }
+    };
+    format!("{} {}", op_name(edit), snippet)
+}
+
+/// Get the line range affected by an edit
+fn get_edit_range(edit: &HashlineEdit, file_len: usize) -> Option<(usize, usize)> {
+    match edit {
+        HashlineEdit::Replace { pos, end, .. } | HashlineEdit::Delete { pos, end, .. } => {
+            let end_line = end.as_ref().map(|e| e.line).unwrap_or(pos.line);
+            Some((pos.line, end_line))
+        }
+        HashlineEdit::Append { pos, lines, .. } => {
+            if lines.is_empty() { return None; }
+            let ref_line = pos.as_ref().map(|p| p.line).unwrap_or(file_len);
+            // Append inserts after ref_line, so range is [ref_line+1, ref_line+lines.len()]
+            Some((ref_line + 1, ref_line + lines.len()))
+        }
+        HashlineEdit::Prepend { pos, lines, .. } => {
+            if lines.is_empty() { return None; }
+            let ref_line = pos.as_ref().map(|p| p.line).unwrap_or(1);
+            // Prepend inserts before ref_line, so range is [ref_line, ref_line+lines.len()-1]
+            Some((ref_line, ref_line + lines.len() - 1))
+        }
+        HashlineEdit::Custom { pos, end, .. } => Some((pos.line, end.line)),
+        HashlineEdit::Move { pos, end, after } => {
+            let (start, stop, _) = move_region(pos, end.as_ref(), after);
+            Some((start, stop))
+        }
+        HashlineEdit::SetPath { .. } | HashlineEdit::SetCell { .. } | HashlineEdit::SetColumn { .. } => None,
+    }
+}
+
+/// Find every pair of `active` edits whose ranges overlap, in one sweep over
+/// the ranges sorted by start: O(n log n) plus the number of overlaps.
+fn find_overlaps(edits: &[Cow<'_, HashlineEdit>], active: &[usize], file_len: usize) -> Vec<Overlap> {
+    // Edits are named by their position in `active` until the pairs are sorted
+    let ranges: Vec<Option<(usize, usize)>> = active.iter().map(|&i| get_edit_range(&edits[i], file_len)).collect();
+    let mut by_start: Vec<(usize, usize)> = ranges.iter()
+        .enumerate()
+        .filter_map(|(n, range)| range.map(|(start, _)| (start, n)))
+        .collect();
+    by_start.sort_unstable();
+    
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    // Ranges already started, keyed by the line they end on
+    let mut open: alloc::collections::BTreeSet<(usize, usize)> = alloc::collections::BTreeSet::new();
+    for (start, n) in by_start {
+        while open.first().is_some_and(|&(end, _)| end < start) {
+            open.pop_first();
+        }
+        pairs.extend(open.iter().map(|&(_, m)| (m.min(n), m.max(n))));
+        let (_, end) = ranges[n].expect("only edits with ranges are swept");
+        open.insert((end, n));
+    }
+    
+    // Special case: Append and Prepend at same ref line are conceptually at the same position
+    // even if their intervals don't overlap (prepend inserts before, append inserts after)
+    let mut inserts_at: alloc::collections::BTreeMap<usize, (Vec<usize>, Vec<usize>)> = alloc::collections::BTreeMap::new();
+    for (n, &i) in active.iter().enumerate().filter(|(n, _)| ranges[*n].is_some()) {
+        match &*edits[i] {
+            HashlineEdit::Append { pos: Some(pos), .. } => inserts_at.entry(pos.line).or_default().0.push(n),
+            HashlineEdit::Prepend { pos: Some(pos), .. } => inserts_at.entry(pos.line).or_default().1.push(n),
+            _ => {}
+        }
+    }
+    for (appends, prepends) in inserts_at.values() {
+        for &a in appends {
+            pairs.extend(prepends.iter().map(|&p| (a.min(p), a.max(p))));
+        }
+    }
+    
+    pairs.sort_unstable();
+    pairs.dedup();
+    pairs.into_iter()
+        .map(|(m, n)| {
+            let (i, j) = (active[m], active[n]);
+            Overlap {
+                first: i,
+                second: j,
+                first_range: ranges[m].expect("overlapping edits have ranges"),
+                second_range: ranges[n].expect("overlapping edits have ranges"),
+                first_desc: describe_edit(&edits[i]),
+                second_desc: describe_edit(&edits[j]),
+            }
+        })
+        .collect()
+}
+
+/// For two overlapping edits, the `(absorbed, into)` pair when one of them
+/// is redundant given the other: replaces of the same range with the same
+/// content, or an insert just above a replace whose new lines start with the
+/// inserted ones.
+fn mergeable(edits: &[Cow<'_, HashlineEdit>], i: usize, j: usize) -> Option<(usize, usize)> {
+    if absorbs(&edits[i], &edits[j]) {
+        Some((j, i))
+    } else if absorbs(&edits[j], &edits[i]) {
+        Some((i, j))
+    } else {
+        None
+    }
+}
+
+/// Whether applying `outer` alone already makes the change `inner` asks for.
+fn absorbs(outer: &HashlineEdit, inner: &HashlineEdit) -> bool {
+    let Some((start, end_line, lines)) = replacement(outer) else {
+        return false;
+    };
+    match inner {
+        HashlineEdit::Prepend { pos: Some(at), lines: inserted, .. } => {
+            at.line == start && lines.starts_with(inserted)
+        }
+        HashlineEdit::Append { pos: Some(at), lines: inserted, .. } => {
+            at.line + 1 == start && lines.starts_with(inserted)
+        }
+        _ => replacement(inner) == Some((start, end_line, lines)),
+    }
+}
+
+/// The first and last line a replace or delete rewrites, and the lines it
+/// puts there.
+fn replacement(edit: &HashlineEdit) -> Option<(usize, usize, &[String])> {
+    match edit {
+        HashlineEdit::Replace { pos, end, lines, .. } => Some((pos.line, end.as_ref().map_or(pos.line, |e| e.line), lines)),
+        HashlineEdit::Delete { pos, end, .. } => Some((pos.line, end.as_ref().map_or(pos.line, |e| e.line), &[])),
+        _ => None,
+    }
+}
+
+fn track_first_changed(first: &mut Option<usize>, line: usize) {
+    if first.is_none() || line < first.unwrap() {
+        *first = Some(line);
+    }
+}
@@ -0,0 +1,158 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::format;
+
+use serde_json::{json, Value};
+
+use crate::{parse_anchor, HashlineEdit, HashlineError};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Edit Payload Versions
+// ═══════════════════════════════════════════════════════════════════════════
+//
+// Version 1 is a bare array of edits. Besides replace/append/prepend it
+// carries the shapes older agents send: `delete`, the single-key
+// `set_line`/`replace_lines`/`insert_after` objects, and anchors written as
+// `"LINE:HASH"` or `{"line": 8, "hash": "RT"}`.
+//
+// Version 2 is `{"version": 2, "edits": [...]}` with exactly the ops
+// replace, append, prepend, delete, move, set_path, set_cell and set_column, and
+// `"LINE#HASH"` anchors.
+//
+// Every payload is migrated to version 2 and then lowered to `HashlineEdit`s.
+
+/// Newest edits document version this build understands.
+pub const EDITS_FORMAT_VERSION: u64 = 2;
+
+/// Parse an edits document of any supported version.
+pub fn parse_edits(json: &str) -> Result<Vec<HashlineEdit>, HashlineError> {
+    let value = serde_json::from_str(json).map_err(|e| invalid(e.to_string()))?;
+    edits_from_value(value)
+}
+
+/// [`parse_edits`] for a document that is already parsed.
+pub fn edits_from_value(value: Value) -> Result<Vec<HashlineEdit>, HashlineError> {
+    let Value::Object(mut document) = migrate_edits(value)? else {
+        unreachable!("migrate_edits returns a version 2 document");
+    };
+    let Some(Value::Array(items)) = document.remove("edits") else {
+        unreachable!("migrate_edits returns a version 2 document");
+    };
+    items.into_iter().enumerate()
+        .map(|(i, item)| {
+            let item = check_item(item).map_err(|e| invalid(format!("edits[{}]: {}", i, e)))?;
+            serde_json::from_value(item).map_err(|e| invalid(format!("edits[{}]: {}", i, e)))
+        })
+        .collect()
+}
+
+/// Rewrite a document of any supported version as a current-version one,
+/// `{"version": 2, "edits": [...]}`.
+pub fn migrate_edits(value: Value) -> Result<Value, HashlineError> {
+    let (version, items) = match value {
+        Value::Array(items) => (1, items),
+        Value::Object(mut document) => {
+            let version = match document.get("version") {
+                None => 1,
+                Some(v) => v.as_u64().ok_or_else(|| invalid(format!("version must be a number, got {}", v)))?,
+            };
+            match document.remove("edits") {
+                Some(Value::Array(items)) => (version, items),
+                _ => return Err(invalid("expected an \"edits\" array".to_string())),
+            }
+        }
+        other => return Err(invalid(format!("expected an array of edits or an edits document, got {}", other))),
+    };
+    let items = match version {
+        1 => items.into_iter().enumerate()
+            .map(|(i, item)| migrate_v1(item).map_err(|e| invalid(format!("edits[{}]: {}", i, e))))
+            .collect::<Result<Vec<_>, _>>()?,
+        EDITS_FORMAT_VERSION => items,
+        _ => return Err(invalid(format!(
+            "unsupported edits version {} (this build understands versions 1 to {})",
+            version, EDITS_FORMAT_VERSION
+        ))),
+    };
+    Ok(json!({ "version": EDITS_FORMAT_VERSION, "edits": items }))
+}
+
+fn invalid(message: String) -> HashlineError {
+    HashlineError::InvalidEdits(message)
+}
+
+/// Rewrite one version 1 edit in its version 2 form.
+fn migrate_v1(item: Value) -> Result<Value, String> {
+    let Value::Object(mut edit) = item else {
+        return Ok(item);
+    };
+    if edit.len() == 1 && !edit.contains_key("op") {
+        let (shape, body) = edit.into_iter().next().expect("edit has one key");
+        return migrate_legacy_shape(&shape, body);
+    }
+    for field in ["pos", "end", "after"] {
+        if let Some(anchor) = edit.get_mut(field) {
+            *anchor = migrate_anchor(anchor)?;
+        }
+    }
+    Ok(Value::Object(edit))
+}
+
+/// `{"set_line": {...}}`, `{"replace_lines": {...}}` and `{"insert_after": {...}}`.
+fn migrate_legacy_shape(shape: &str, body: Value) -> Result<Value, String> {
+    let Value::Object(body) = body else {
+        return Err(format!("{} must be an object", shape));
+    };
+    let field = |name: &str| body.get(name).ok_or_else(|| format!("{} is missing \"{}\"", shape, name));
+    match shape {
+        "set_line" => Ok(json!({
+            "op": "replace",
+            "pos": migrate_anchor(field("anchor")?)?,
+            "lines": split_text(field("new_text")?, shape)?,
+        })),
+        "replace_lines" => Ok(json!({
+            "op": "replace",
+            "pos": migrate_anchor(field("start_anchor")?)?,
+            "end": migrate_anchor(field("end_anchor")?)?,
+            "lines": split_text(field("new_text")?, shape)?,
+        })),
+        "insert_after" => Ok(json!({
+            "op": "append",
+            "pos": migrate_anchor(field("anchor")?)?,
+            "lines": split_text(field("text")?, shape)?,
+        })),
+        _ => Err(format!("unknown edit shape \"{}\"", shape)),
+    }
+}
+
+/// `"LINE:HASH"` and `{"line": n, "hash": h}` become `"LINE#HASH"`.
+fn migrate_anchor(anchor: &Value) -> Result<Value, String> {
+    match anchor {
+        Value::String(s) if !s.contains('#') => match parse_anchor(s) {
+            Some((line, hash)) => Ok(format!("{}#{}", line, hash).into()),
+            None => Ok(anchor.clone()),
+        },
+        Value::Object(fields) => match (fields.get("line").and_then(Value::as_u64), fields.get("hash").and_then(Value::as_str)) {
+            (Some(line), Some(hash)) => Ok(format!("{}#{}", line, hash).into()),
+            _ => Err(format!("anchor object needs \"line\" and \"hash\", got {}", anchor)),
+        },
+        _ => Ok(anchor.clone()),
+    }
+}
+
+/// Legacy shapes carry their content as one newline-joined string.
+fn split_text(text: &Value, shape: &str) -> Result<Vec<String>, String> {
+    match text {
+        Value::String(s) => Ok(s.split('\n').map(|l| l.to_string()).collect()),
+        Value::Array(_) => serde_json::from_value(text.clone()).map_err(|e| format!("{}: {}", shape, e)),
+        _ => Err(format!("{} text must be a string", shape)),
+    }
+}
+
+/// Reject a version 2 edit that deserializing would silently misread:
+/// `lines` on a `delete` would be dropped.
+fn check_item(item: Value) -> Result<Value, String> {
+    if item.get("op").and_then(Value::as_str) == Some("delete") && item.get("lines").is_some() {
+        return Err("delete takes no \"lines\"; use replace".to_string());
+    }
+    Ok(item)
+}
@@ -0,0 +1,23 @@
+use schemars::{schema_for, Schema};
+
+use crate::{ApplyOptions, ApplyOutcome, HashlineEdit};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// JSON Schemas
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Schema of the edit array accepted by `edit`, `plan` and the bindings, so
+/// clients can check a payload before sending it.
+pub fn schema_for_edits() -> Schema {
+    schema_for!(Vec<HashlineEdit>)
+}
+
+/// Schema of the [`ApplyOptions`] object taken by the C, Node and WASM bindings.
+pub fn schema_for_options() -> Schema {
+    schema_for!(ApplyOptions)
+}
+
+/// Schema of a serialized [`ApplyOutcome`].
+pub fn schema_for_outcome() -> Schema {
+    schema_for!(ApplyOutcome)
+}
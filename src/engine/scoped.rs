@@ -0,0 +1,440 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::format;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh32::xxh32;
+
+#[cfg(feature = "tree-sitter")]
+use super::syntax::{self, Grammar};
+use super::{hash_normalized, HASH_SEED};
+use crate::{line_hash, LineHash};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Scoped Anchors
+// ═══════════════════════════════════════════════════════════════════════════
+//
+// Scopes are found by a heuristic, not a parse: definition keywords on a
+// line open one, and the matching brace or a return to the header's
+// indentation closes it. Braces in strings, character literals and
+// comments are skipped, but the lexer knows no particular language, so
+// raw strings, single-quoted strings of more than one character and
+// languages that mix braces and indentation can still close a scope early
+// or late. A wrong scope only changes which lines share a seed; anchors
+// are still checked against the hashes the same heuristic gives.
+
+/// How line hashes are derived.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AnchorMode {
+    /// Each hash is chained to the previous line's, so any change above a
+    /// line gives it a new hash (default)
+    #[default]
+    Chained,
+    /// Each hash is seeded by the definitions enclosing the line instead,
+    /// found from braces and indentation, so edits, reformatting and reflow
+    /// outside them leave it unchanged
+    Scoped,
+    /// Each hash is seeded by the definitions enclosing the line in the
+    /// file's syntax tree, parsed with this grammar or, for `None`, the one
+    /// [`AnchorMode::for_path`] picks. Lines no grammar covers get scoped
+    /// hashes.
+    #[cfg(feature = "tree-sitter")]
+    Syntax(Option<Grammar>),
+}
+
+/// Every mode, in the order `--help` lists them.
+const ANCHOR_MODES: &[AnchorMode] = &[
+    AnchorMode::Chained,
+    AnchorMode::Scoped,
+    #[cfg(feature = "tree-sitter")]
+    AnchorMode::Syntax(None),
+    #[cfg(feature = "tree-sitter")]
+    AnchorMode::Syntax(Some(Grammar::Rust)),
+    #[cfg(feature = "tree-sitter")]
+    AnchorMode::Syntax(Some(Grammar::Python)),
+];
+
+impl AnchorMode {
+    /// The mode's name on the command line, in config files and in JSON.
+    pub fn name(self) -> &'static str {
+        match self {
+            AnchorMode::Chained => "chained",
+            AnchorMode::Scoped => "scoped",
+            #[cfg(feature = "tree-sitter")]
+            AnchorMode::Syntax(None) => "syntax",
+            #[cfg(feature = "tree-sitter")]
+            AnchorMode::Syntax(Some(Grammar::Rust)) => "syntax:rust",
+            #[cfg(feature = "tree-sitter")]
+            AnchorMode::Syntax(Some(Grammar::Python)) => "syntax:python",
+        }
+    }
+
+    /// This mode for the file at `path`. Syntax anchors without a grammar
+    /// take the one for the file's extension; other modes are unchanged.
+    #[cfg(feature = "std")]
+    #[cfg_attr(not(feature = "tree-sitter"), allow(unused_variables))]
+    pub fn for_path(self, path: impl AsRef<Path>) -> AnchorMode {
+        #[cfg(feature = "tree-sitter")]
+        if self == AnchorMode::Syntax(None) {
+            return AnchorMode::Syntax(Grammar::for_path(path));
+        }
+        self
+    }
+
+    /// Whether hashing any line needs the whole file parsed first, so lines
+    /// cannot be hashed as they are read.
+    pub(crate) fn hashes_whole_file(self) -> bool {
+        #[cfg(feature = "tree-sitter")]
+        if let AnchorMode::Syntax(Some(_)) = self {
+            return true;
+        }
+        false
+    }
+}
+
+impl core::fmt::Display for AnchorMode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl core::str::FromStr for AnchorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // `semantic` is the scoped mode's former name
+        if s == "semantic" {
+            return Ok(AnchorMode::Scoped);
+        }
+        ANCHOR_MODES.iter().copied().find(|mode| mode.name() == s).ok_or_else(|| {
+            let names: Vec<&str> = ANCHOR_MODES.iter().map(|mode| mode.name()).collect();
+            format!("Unknown anchor mode '{}', expected one of: {}", s, names.join(", "))
+        })
+    }
+}
+
+impl Serialize for AnchorMode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for AnchorMode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Modes appear in JSON by name.
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for AnchorMode {
+    fn schema_name() -> Cow<'static, str> {
+        "AnchorMode".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        let names: Vec<&str> = ANCHOR_MODES.iter().map(|mode| mode.name()).collect();
+        schemars::json_schema!({
+            "type": "string",
+            "enum": names,
+            "description": "How line hashes are derived; anchors only validate in the mode they were read in",
+        })
+    }
+}
+
+#[cfg(feature = "cli")]
+impl clap::ValueEnum for AnchorMode {
+    fn value_variants<'a>() -> &'a [Self] {
+        ANCHOR_MODES
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        let help = match self {
+            AnchorMode::Chained => {
+                "Each hash is chained to the previous line's, so any change above a line gives it a new hash (default)"
+            }
+            AnchorMode::Scoped => {
+                "Each hash is seeded by the definitions enclosing the line instead, found from braces and \
+                 indentation, so edits, reformatting and reflow outside them leave it unchanged"
+            }
+            #[cfg(feature = "tree-sitter")]
+            AnchorMode::Syntax(None) => {
+                "Like scoped, with the definitions taken from a syntax tree of .rs and .py files; other files \
+                 get scoped hashes"
+            }
+            #[cfg(feature = "tree-sitter")]
+            AnchorMode::Syntax(Some(_)) => "Syntax anchors with this grammar, whatever the file's extension",
+        };
+        let value = clap::builder::PossibleValue::new(self.name()).help(help);
+        Some(if *self == AnchorMode::Scoped { value.alias("semantic") } else { value })
+    }
+}
+
+/// Hashes lines one after another in any [`AnchorMode`].
+#[derive(Debug, Clone)]
+pub(crate) enum LineHasher {
+    Chained(Option<LineHash>),
+    Scoped(ScopeTracker),
+    /// The seed of every line, found from the whole file up front.
+    #[cfg(feature = "tree-sitter")]
+    Seeded(Vec<u32>),
+}
+
+impl LineHasher {
+    /// A hasher for lines read one at a time, in a mode that can hash them
+    /// that way.
+    pub(crate) fn new(mode: AnchorMode) -> LineHasher {
+        assert!(!mode.hashes_whole_file(), "{} anchors need the whole file", mode);
+        match mode {
+            AnchorMode::Chained => LineHasher::Chained(None),
+            _ => LineHasher::Scoped(ScopeTracker::default()),
+        }
+    }
+
+    /// A hasher for the lines of `content`, in any mode.
+    #[cfg_attr(not(feature = "tree-sitter"), allow(unused_variables))]
+    pub(crate) fn for_content(mode: AnchorMode, content: &str) -> LineHasher {
+        #[cfg(feature = "tree-sitter")]
+        if let AnchorMode::Syntax(Some(grammar)) = mode {
+            return LineHasher::Seeded(syntax::line_seeds(content, content.lines().count(), grammar));
+        }
+        LineHasher::new(mode)
+    }
+
+    /// Hash the next line, 1-indexed `line_num`.
+    pub(crate) fn hash(&mut self, line_num: usize, line: &str) -> LineHash {
+        match self {
+            LineHasher::Chained(prev) => {
+                let hash = line_hash(line_num, line, *prev);
+                *prev = Some(hash);
+                hash
+            }
+            LineHasher::Scoped(scopes) => scopes.hash(line),
+            #[cfg(feature = "tree-sitter")]
+            LineHasher::Seeded(seeds) => hash_normalized(line, seeds.get(line_num - 1).copied().unwrap_or(HASH_SEED)),
+        }
+    }
+}
+
+/// The seed of each of `lines`, which make up a whole file, in `mode`,
+/// which is not [`AnchorMode::Chained`].
+pub(crate) fn line_seeds(mode: AnchorMode, lines: &[Cow<'_, str>]) -> Vec<u32> {
+    #[cfg(feature = "tree-sitter")]
+    if let AnchorMode::Syntax(Some(grammar)) = mode {
+        return syntax::line_seeds(&lines.join("\n"), lines.len(), grammar);
+    }
+    debug_assert_ne!(mode, AnchorMode::Chained);
+    let mut scopes = ScopeTracker::default();
+    lines.iter().map(|line| scopes.line_seed(line)).collect()
+}
+
+/// Follows the definitions (`fn`, `class`, `def`, `impl`, ...) open at each
+/// line, from their header lines and the braces or indentation that close
+/// them. A line's scoped hash is its whitespace-normalized text seeded by
+/// the names of those definitions, so it depends on no line outside the
+/// header chain, and never on a line after it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ScopeTracker {
+    scopes: Vec<Scope>,
+    /// Brace nesting before the next line.
+    depth: usize,
+    lexer: Lexer,
+}
+
+#[derive(Debug, Clone)]
+struct Scope {
+    seed: u32,
+    end: ScopeEnd,
+}
+
+#[derive(Debug, Clone)]
+enum ScopeEnd {
+    /// Closed by the brace that returns nesting to this depth, once its
+    /// body has opened. Until then a line ending in `:`, as a Python
+    /// signature wrapped over several lines does, makes it [`ScopeEnd::Indent`].
+    Brace { depth: usize, opened: bool, indent: usize },
+    /// Closed by the next non-blank line indented this much or less.
+    Indent(usize),
+}
+
+impl ScopeTracker {
+    fn seed(&self) -> u32 {
+        self.scopes.last().map_or(HASH_SEED, |scope| scope.seed)
+    }
+
+    fn hash(&mut self, line: &str) -> LineHash {
+        hash_normalized(line, self.line_seed(line))
+    }
+
+    /// Follow the scopes through `line` and return the seed it is hashed
+    /// with, which depends on the lines before it alone, so the hashing
+    /// itself can be done apart.
+    fn line_seed(&mut self, line: &str) -> u32 {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let body = line.trim_start();
+        let indent = line.len() - body.len();
+        // A line that starts inside a comment or string neither opens nor
+        // closes a definition by its indentation
+        let in_code = self.lexer.in_code();
+        if in_code && !body.is_empty() {
+            while matches!(self.scopes.last(), Some(Scope { end: ScopeEnd::Indent(header), .. }) if indent <= *header) {
+                self.scopes.pop();
+            }
+        }
+
+        if let Some(identity) = definition_identity(body).filter(|_| in_code) {
+            let end = if body.trim_end().ends_with(':') {
+                ScopeEnd::Indent(indent)
+            } else {
+                ScopeEnd::Brace { depth: self.depth, opened: false, indent }
+            };
+            if !body.trim_end().ends_with(';') {
+                let seed = xxh32(identity.as_bytes(), self.seed());
+                self.scopes.push(Scope { seed, end });
+            }
+        }
+        let seed = self.seed();
+        if let Some(Scope { end: end @ ScopeEnd::Brace { opened: false, .. }, .. }) = self.scopes.last_mut() {
+            if let ScopeEnd::Brace { indent: header, .. } = *end {
+                if body.trim_end().ends_with(':') {
+                    *end = ScopeEnd::Indent(header);
+                }
+            }
+        }
+
+        for brace in self.lexer.braces(body) {
+            match brace {
+                '{' => self.depth += 1,
+                _ => self.depth = self.depth.saturating_sub(1),
+            }
+        }
+        for scope in &mut self.scopes {
+            if let ScopeEnd::Brace { depth, opened, .. } = &mut scope.end {
+                *opened |= self.depth > *depth;
+            }
+        }
+        while matches!(self.scopes.last(), Some(Scope { end: ScopeEnd::Brace { depth, opened: true, .. }, .. }) if self.depth <= *depth) {
+            self.scopes.pop();
+        }
+        seed
+    }
+}
+
+/// Keywords that open a named definition.
+const DEFINITION_KEYWORDS: &[&str] = &[
+    "fn", "def", "class", "struct", "enum", "trait", "impl", "mod", "module", "namespace", "interface",
+    "function", "func", "union",
+];
+
+/// Words that may precede a definition keyword.
+const DEFINITION_MODIFIERS: &[&str] = &[
+    "pub", "async", "const", "unsafe", "extern", "export", "default", "static", "public", "private",
+    "protected", "internal", "abstract", "final", "override", "virtual", "sealed", "open", "data",
+];
+
+/// `keyword name` for a definition header line, e.g. `fn parse` or
+/// `impl Display for Foo`; `None` for any other line.
+fn definition_identity(body: &str) -> Option<String> {
+    let mut rest = body;
+    loop {
+        let word_len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        let (word, after) = rest.split_at(word_len);
+        if DEFINITION_MODIFIERS.contains(&word) {
+            // `pub(crate)`, `extern "C"`
+            rest = match after.strip_prefix('(') {
+                Some(group) => group.split_once(')')?.1,
+                None => after,
+            };
+            rest = rest.trim_start();
+            if let Some(abi) = rest.strip_prefix('"') {
+                rest = abi.split_once('"')?.1.trim_start();
+            }
+            continue;
+        }
+        if !DEFINITION_KEYWORDS.contains(&word) || !after.starts_with(|c: char| c.is_whitespace() || c == '<') {
+            return None;
+        }
+        let name = if word == "impl" {
+            let signature = after.split('{').next().unwrap_or(after);
+            signature.split_whitespace().collect::<Vec<_>>().join(" ")
+        } else {
+            after.trim_start().chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect()
+        };
+        return (!name.is_empty()).then(|| format!("{} {}", word, name));
+    }
+}
+
+/// Where the next line starts: in code, or inside a block comment or a
+/// string left open by the lines above.
+#[derive(Debug, Clone, Copy, Default)]
+struct Lexer {
+    block_comment: bool,
+    /// The quote, `"` or `` ` ``, of a string that spans lines.
+    quote: Option<char>,
+}
+
+impl Lexer {
+    fn in_code(&self) -> bool {
+        !self.block_comment && self.quote.is_none()
+    }
+
+    /// The braces of `line` outside strings, character literals and
+    /// comments, in order.
+    fn braces(&mut self, line: &str) -> Vec<char> {
+        let chars: Vec<char> = line.chars().collect();
+        let mut braces = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).copied();
+            if self.block_comment {
+                if c == '*' && next == Some('/') {
+                    self.block_comment = false;
+                    i += 1;
+                }
+            } else if let Some(quote) = self.quote {
+                if c == '\\' {
+                    i += 1;
+                } else if c == quote {
+                    self.quote = None;
+                }
+            } else {
+                match c {
+                    '/' if next == Some('/') => break,
+                    '#' if i == 0 || chars[i - 1].is_whitespace() => break,
+                    '/' if next == Some('*') => {
+                        self.block_comment = true;
+                        i += 1;
+                    }
+                    '"' | '`' => self.quote = Some(c),
+                    '\'' => i += char_literal_len(&chars[i..]).unwrap_or(1) - 1,
+                    '{' | '}' => braces.push(c),
+                    _ => {}
+                }
+            }
+            i += 1;
+        }
+        braces
+    }
+}
+
+/// Length of the character literal `chars` starts with, such as `'{'` or
+/// `'\u{7d}'`; `None` for a lone quote, such as a Rust lifetime's.
+fn char_literal_len(chars: &[char]) -> Option<usize> {
+    match chars {
+        ['\'', '\\', _, rest @ ..] => rest.iter().take(8).position(|&c| c == '\'').map(|end| end + 4),
+        ['\'', c, '\'', ..] if *c != '\'' => Some(3),
+        _ => None,
+    }
+}
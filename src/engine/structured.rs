@@ -0,0 +1,873 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{AnchorRef, EditOp, HashedFile, HashlineEdit};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Structured Path Edits
+// ═══════════════════════════════════════════════════════════════════════════
+//
+// A `set_path` edit names a value by its path in a JSON, YAML or TOML
+// document instead of by line. When the batch is planned the value's text
+// is located in the file and the edit becomes a custom op over the lines it
+// spans, rewriting only the value's bytes: keys, comments, indentation and
+// the rest of the line are left as they are.
+
+/// The syntax of a document edited with [`HashlineEdit::SetPath`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum StructuredFormat {
+    /// JSON, also with `//` and `/* */` comments and trailing commas
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl StructuredFormat {
+    /// Guess the format from the first line that is not blank or a comment:
+    /// `{`, or a `[` that does not open a TOML table, is JSON; a `[table]`
+    /// header or `key = value` line is TOML; anything else is YAML.
+    pub fn detect(content: &str) -> StructuredFormat {
+        let first = content.lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with("//"));
+        match first {
+            Some(line) if line.starts_with('{') => StructuredFormat::Json,
+            Some(line) if line.starts_with('[') && !is_toml_header(line) => StructuredFormat::Json,
+            Some(line) if line.starts_with('[') => StructuredFormat::Toml,
+            Some(line) if is_toml_key_value(line) => StructuredFormat::Toml,
+            _ => StructuredFormat::Yaml,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            StructuredFormat::Json => "JSON",
+            StructuredFormat::Yaml => "YAML",
+            StructuredFormat::Toml => "TOML",
+        }
+    }
+}
+
+fn is_toml_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '"' | '\'' | ' ' | '\t')
+}
+
+fn is_toml_header(line: &str) -> bool {
+    let line = line.split(" #").next().unwrap_or(line).trim_end();
+    let inner = line.trim_start_matches('[').trim_end_matches(']');
+    line.ends_with(']') && !inner.trim().is_empty() && inner.chars().all(is_toml_key_char)
+}
+
+fn is_toml_key_value(line: &str) -> bool {
+    line.split_once('=').is_some_and(|(key, _)| !key.trim().is_empty() && key.chars().all(is_toml_key_char))
+}
+
+/// One step of a located value's path.
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+impl Step {
+    fn matches(&self, segment: &str) -> bool {
+        match self {
+            Step::Key(key) => key == segment,
+            Step::Index(index) => segment.parse() == Ok(*index),
+        }
+    }
+}
+
+/// Split `a.b."c.d"[0]` into `a`, `b`, `c.d` and `0`.
+fn parse_path(path: &str) -> Result<Vec<String>, String> {
+    let mut segments = Vec::new();
+    let mut chars = path.chars().peekable();
+    while chars.peek().is_some() {
+        let segment: String = match chars.peek() {
+            Some('"') => {
+                chars.next();
+                let mut quoted = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => quoted.extend(chars.next()),
+                        Some('"') => break,
+                        Some(c) => quoted.push(c),
+                        None => return Err(format!("unterminated quote in path `{}`", path)),
+                    }
+                }
+                quoted
+            }
+            Some('[') => {
+                chars.next();
+                let index: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                if index.parse::<usize>().is_err() {
+                    return Err(format!("`[{}]` in path `{}` is not an index", index, path));
+                }
+                index
+            }
+            _ => {
+                let mut key = String::new();
+                while let Some(&c) = chars.peek().filter(|&&c| c != '.' && c != '[') {
+                    key.push(c);
+                    chars.next();
+                }
+                key
+            }
+        };
+        if segment.is_empty() {
+            return Err(format!("empty key in path `{}`", path));
+        }
+        segments.push(segment);
+        if chars.peek() == Some(&'.') {
+            chars.next();
+            if chars.peek().is_none() {
+                return Err(format!("path `{}` ends with `.`", path));
+            }
+        }
+    }
+    if segments.is_empty() {
+        return Err("empty path".to_string());
+    }
+    Ok(segments)
+}
+
+/// A value found in a document: its path and the byte range of its text.
+#[derive(Debug)]
+struct Located {
+    path: Vec<Step>,
+    start: usize,
+    end: usize,
+}
+
+/// Resolve a `set_path` edit against `file` into the custom op that
+/// rewrites the value's text.
+pub(crate) fn resolve_set_path(
+    file: &HashedFile,
+    path: &str,
+    value: &Value,
+    pos: Option<&AnchorRef>,
+    format: Option<StructuredFormat>,
+) -> Result<HashlineEdit, String> {
+    let text = file.lines().join("\n");
+    let format = format.unwrap_or_else(|| StructuredFormat::detect(&text));
+    let target = parse_path(path)?;
+    let values = match format {
+        StructuredFormat::Json => JsonScan::values(&text),
+        StructuredFormat::Toml => TomlScan::values(&text),
+        StructuredFormat::Yaml => yaml_values(&text),
+    }
+    .map_err(|e| format!("the file does not parse as {}: {}", format.name(), e))?;
+
+    let is_at = |steps: &[Step]| steps.len() == target.len() && steps.iter().zip(&target).all(|(s, t)| s.matches(t));
+    let Some(found) = values.iter().find(|v| is_at(&v.path)) else {
+        return Err(if values.iter().any(|v| v.path.len() > target.len() && is_at(&v.path[..target.len()])) {
+            format!("`{}` holds nested values; set them one by one", path)
+        } else {
+            format!("no value at `{}`; set_path only changes values that exist", path)
+        });
+    };
+    let replacement = render(format, value, &text[found.start..found.end])?;
+
+    let (first, start_col) = line_col(&text, found.start);
+    let (last, end_col) = line_col(&text, found.end);
+    let anchor = |line: usize| file.anchor(line + 1).expect("located values are within the file");
+    let pos = match pos {
+        Some(pos) if pos.line != first + 1 => {
+            return Err(format!("`{}` is on line {}, not line {}", path, first + 1, pos.line));
+        }
+        Some(pos) => pos.clone(),
+        None => anchor(first),
+    };
+    Ok(HashlineEdit::custom(SetPathOp {
+        path: path.to_string(),
+        pos,
+        end: anchor(last),
+        original: file.lines()[first..=last].iter().map(|line| line.to_string()).collect(),
+        start_col,
+        end_col,
+        replacement,
+    }))
+}
+
+/// 0-based line and byte column of `offset` in `text`.
+fn line_col(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    (before.matches('\n').count(), offset - before.rfind('\n').map_or(0, |i| i + 1))
+}
+
+/// The resolved form of a `set_path` edit.
+#[derive(Debug)]
+struct SetPathOp {
+    path: String,
+    pos: AnchorRef,
+    end: AnchorRef,
+    /// The lines the value spanned when it was located.
+    original: Vec<String>,
+    start_col: usize,
+    end_col: usize,
+    replacement: String,
+}
+
+impl EditOp for SetPathOp {
+    fn name(&self) -> &str {
+        "set_path"
+    }
+
+    fn range(&self) -> (AnchorRef, AnchorRef) {
+        (self.pos.clone(), self.end.clone())
+    }
+
+    fn validate(&self, lines: &[String]) -> Result<(), String> {
+        if lines == self.original {
+            Ok(())
+        } else {
+            Err(format!("`{}` is no longer on lines {}-{}", self.path, self.pos.line, self.end.line))
+        }
+    }
+
+    fn apply(&self, lines: &[String]) -> Vec<String> {
+        let first = &lines[0][..self.start_col];
+        let last = &lines[lines.len() - 1][self.end_col..];
+        vec![format!("{}{}{}", first, self.replacement, last)]
+    }
+}
+
+// ─── Rendering ──────────────────────────────────────────────────────────────
+
+/// `value` written as `format` source, on one line. Strings keep the
+/// quoting style of the `original` text where they can.
+fn render(format: StructuredFormat, value: &Value, original: &str) -> Result<String, String> {
+    match (format, value) {
+        (StructuredFormat::Json, _) => Ok(value.to_string()),
+        (StructuredFormat::Toml, Value::Null) => Err("TOML has no null".to_string()),
+        (StructuredFormat::Toml, Value::String(s))
+            if original.starts_with('\'') && !original.starts_with("'''") && !s.contains(['\'', '\n', '\r']) =>
+        {
+            Ok(format!("'{}'", s))
+        }
+        (StructuredFormat::Toml, Value::Array(items)) => {
+            let items = items.iter().map(|item| render(format, item, "")).collect::<Result<Vec<_>, _>>()?;
+            Ok(format!("[{}]", items.join(", ")))
+        }
+        (StructuredFormat::Toml, Value::Object(entries)) if entries.is_empty() => Ok("{}".to_string()),
+        (StructuredFormat::Toml, Value::Object(entries)) => {
+            let entries = entries.iter()
+                .map(|(key, item)| Ok(format!("{} = {}", toml_key(key), render(format, item, "")?)))
+                .collect::<Result<Vec<_>, String>>()?;
+            Ok(format!("{{ {} }}", entries.join(", ")))
+        }
+        (StructuredFormat::Yaml, Value::String(s)) if original.starts_with('\'') && !s.contains(['\n', '\r']) => {
+            Ok(format!("'{}'", s.replace('\'', "''")))
+        }
+        (StructuredFormat::Yaml, Value::String(s)) if !original.starts_with('"') && is_plain_yaml(s) => Ok(s.clone()),
+        // JSON scalars are valid TOML and YAML values, and JSON collections valid YAML
+        _ => Ok(value.to_string()),
+    }
+}
+
+fn toml_key(key: &str) -> String {
+    if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        key.to_string()
+    } else {
+        Value::from(key).to_string()
+    }
+}
+
+/// Whether `s` reads back as the same string when written unquoted in YAML.
+fn is_plain_yaml(s: &str) -> bool {
+    const RESERVED: &[&str] = &["true", "false", "yes", "no", "on", "off", "null", "~", "y", "n"];
+    !s.is_empty()
+        && s.trim() == s
+        && !s.starts_with(|c: char| "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        && !s.contains(": ")
+        && !s.contains(" #")
+        && !s.ends_with(':')
+        && !s.chars().any(char::is_control)
+        && !RESERVED.contains(&s.to_ascii_lowercase().as_str())
+        && s.parse::<f64>().is_err()
+}
+
+// ─── JSON ───────────────────────────────────────────────────────────────────
+
+struct JsonScan<'a> {
+    text: &'a str,
+    pos: usize,
+    found: Vec<Located>,
+}
+
+impl<'a> JsonScan<'a> {
+    fn values(text: &'a str) -> Result<Vec<Located>, String> {
+        let mut scan = JsonScan { text, pos: 0, found: Vec::new() };
+        scan.value(&mut Vec::new())?;
+        scan.skip_blank();
+        if scan.pos < text.len() {
+            return Err(scan.error("unexpected text after the document"));
+        }
+        Ok(scan.found)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("line {}: {}", line_col(self.text, self.pos).0 + 1, message)
+    }
+
+    /// Skip whitespace and comments.
+    fn skip_blank(&mut self) {
+        loop {
+            let rest = &self.text[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.starts_with("//") {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if let Some(comment) = trimmed.strip_prefix("/*") {
+                self.pos += comment.find("*/").map_or(trimmed.len(), |i| i + 4);
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip_blank();
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let start = self.pos;
+        self.pos += 1;
+        loop {
+            match self.peek() {
+                Some(b'\\') => self.pos += 2,
+                Some(b'"') => break,
+                Some(_) => self.pos += 1,
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+        self.pos += 1;
+        serde_json::from_str(&self.text[start..self.pos]).map_err(|e| self.error(&e.to_string()))
+    }
+
+    fn value(&mut self, path: &mut Vec<Step>) -> Result<(), String> {
+        self.skip_blank();
+        let start = self.pos;
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                while !self.eat(b'}') {
+                    if self.peek() != Some(b'"') {
+                        return Err(self.error("expected a key"));
+                    }
+                    let key = self.string()?;
+                    if !self.eat(b':') {
+                        return Err(self.error("expected `:`"));
+                    }
+                    path.push(Step::Key(key));
+                    self.value(path)?;
+                    path.pop();
+                    if !self.eat(b',') && !self.eat(b'}') {
+                        return Err(self.error("expected `,` or `}`"));
+                    } else if self.text.as_bytes()[self.pos - 1] == b'}' {
+                        break;
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut index = 0;
+                while !self.eat(b']') {
+                    path.push(Step::Index(index));
+                    self.value(path)?;
+                    path.pop();
+                    index += 1;
+                    if !self.eat(b',') && !self.eat(b']') {
+                        return Err(self.error("expected `,` or `]`"));
+                    } else if self.text.as_bytes()[self.pos - 1] == b']' {
+                        break;
+                    }
+                }
+            }
+            Some(b'"') => {
+                self.string()?;
+            }
+            _ => {
+                let rest = &self.text[self.pos..];
+                self.pos += rest.find(|c: char| c.is_whitespace() || ",]}/".contains(c)).unwrap_or(rest.len());
+                if self.pos == start {
+                    return Err(self.error("expected a value"));
+                }
+            }
+        }
+        self.found.push(Located { path: path.clone(), start, end: self.pos });
+        Ok(())
+    }
+}
+
+// ─── TOML ───────────────────────────────────────────────────────────────────
+
+struct TomlScan<'a> {
+    text: &'a str,
+    pos: usize,
+    found: Vec<Located>,
+}
+
+impl<'a> TomlScan<'a> {
+    fn values(text: &'a str) -> Result<Vec<Located>, String> {
+        let mut scan = TomlScan { text, pos: 0, found: Vec::new() };
+        let mut table: Vec<Step> = Vec::new();
+        // Arrays of tables, by path, with how many tables each has so far
+        let mut arrays: Vec<(Vec<Step>, usize)> = Vec::new();
+        loop {
+            scan.skip_blank(true);
+            if scan.pos >= text.len() {
+                return Ok(scan.found);
+            }
+            if scan.eat("[[") {
+                let keys = scan.key()?;
+                scan.expect("]]")?;
+                let (last, parents) = keys.split_last().expect("keys are never empty");
+                let mut path = table_path(parents, &arrays);
+                path.push(Step::Key(last.clone()));
+                let count = match arrays.iter_mut().find(|(p, _)| *p == path) {
+                    Some((_, count)) => {
+                        *count += 1;
+                        *count
+                    }
+                    None => {
+                        arrays.push((path.clone(), 1));
+                        1
+                    }
+                };
+                path.push(Step::Index(count - 1));
+                table = path;
+            } else if scan.eat("[") {
+                let keys = scan.key()?;
+                scan.expect("]")?;
+                table = table_path(&keys, &arrays);
+            } else {
+                let keys = scan.key()?;
+                scan.expect("=")?;
+                let mut path = table.clone();
+                path.extend(keys.into_iter().map(Step::Key));
+                scan.value(&mut path)?;
+            }
+            scan.skip_blank(false);
+            if scan.pos < text.len() && !scan.eat("\n") && !scan.eat("\r\n") {
+                return Err(scan.error("expected the end of the line"));
+            }
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("line {}: {}", line_col(self.text, self.pos).0 + 1, message)
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.rest().starts_with(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        self.skip_blank(false);
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", token)))
+        }
+    }
+
+    /// Skip spaces, tabs and comments, and with `newlines` line breaks too,
+    /// `\n` or `\r\n`.
+    fn skip_blank(&mut self, newlines: bool) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start_matches(|c: char| c == ' ' || c == '\t' || (newlines && (c == '\n' || c == '\r')));
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.starts_with('#') {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else {
+                return;
+            }
+        }
+    }
+
+    /// A dotted key, such as `a."b.c".d`.
+    fn key(&mut self) -> Result<Vec<String>, String> {
+        let mut keys = Vec::new();
+        loop {
+            self.skip_blank(false);
+            let rest = self.rest();
+            let key = if rest.starts_with('"') {
+                let len = self.quoted_len('"', true)?;
+                let key = serde_json::from_str(&rest[..len]).map_err(|e| self.error(&e.to_string()))?;
+                self.pos += len;
+                key
+            } else if rest.starts_with('\'') {
+                let len = self.quoted_len('\'', false)?;
+                self.pos += len;
+                rest[1..len - 1].to_string()
+            } else {
+                let len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-')).unwrap_or(rest.len());
+                if len == 0 {
+                    return Err(self.error("expected a key"));
+                }
+                self.pos += len;
+                rest[..len].to_string()
+            };
+            keys.push(key);
+            self.skip_blank(false);
+            if !self.eat(".") {
+                return Ok(keys);
+            }
+        }
+    }
+
+    /// Length of the one-line string starting at the cursor, quotes included.
+    fn quoted_len(&self, quote: char, escapes: bool) -> Result<usize, String> {
+        let mut chars = self.rest().char_indices().skip(1);
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' if escapes => {
+                    chars.next();
+                }
+                '\n' => break,
+                c if c == quote => return Ok(i + 1),
+                _ => {}
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn value(&mut self, path: &mut Vec<Step>) -> Result<(), String> {
+        self.skip_blank(false);
+        let start = self.pos;
+        let rest = self.rest();
+        if let Some(quotes) = ["\"\"\"", "'''"].into_iter().find(|q| rest.starts_with(q)) {
+            let escapes = quotes == "\"\"\"";
+            let mut i = 3;
+            loop {
+                match rest[i..].chars().next() {
+                    Some('\\') if escapes => i += 1 + rest[i + 1..].chars().next().map_or(0, char::len_utf8),
+                    Some(_) if rest[i..].starts_with(quotes) => break,
+                    Some(c) => i += c.len_utf8(),
+                    None => return Err(self.error("unterminated string")),
+                }
+            }
+            // Up to two quotes may end the content just before the delimiter
+            let extra = rest[i + 3..].len() - rest[i + 3..].trim_start_matches(&quotes[..1]).len();
+            self.pos += i + 3 + extra.min(2);
+        } else if rest.starts_with('"') {
+            self.pos += self.quoted_len('"', true)?;
+        } else if rest.starts_with('\'') {
+            self.pos += self.quoted_len('\'', false)?;
+        } else if self.eat("[") {
+            let mut index = 0;
+            loop {
+                self.skip_blank(true);
+                if self.eat("]") {
+                    break;
+                }
+                path.push(Step::Index(index));
+                self.value(path)?;
+                path.pop();
+                index += 1;
+                self.skip_blank(true);
+                if !self.eat(",") && !self.rest().starts_with(']') {
+                    return Err(self.error("expected `,` or `]`"));
+                }
+            }
+        } else if self.eat("{") {
+            loop {
+                self.skip_blank(true);
+                if self.eat("}") {
+                    break;
+                }
+                let keys = self.key()?;
+                self.expect("=")?;
+                let depth = path.len();
+                path.extend(keys.into_iter().map(Step::Key));
+                self.value(path)?;
+                path.truncate(depth);
+                self.skip_blank(true);
+                if !self.eat(",") && !self.rest().starts_with('}') {
+                    return Err(self.error("expected `,` or `}`"));
+                }
+            }
+        } else {
+            // Numbers, booleans and dates, which may contain a space
+            let len = rest.find([',', ']', '}', '#', '\n']).unwrap_or(rest.len());
+            let len = rest[..len].trim_end().len();
+            if len == 0 {
+                return Err(self.error("expected a value"));
+            }
+            self.pos += len;
+        }
+        self.found.push(Located { path: path.clone(), start, end: self.pos });
+        Ok(())
+    }
+}
+
+/// The path of a `[table]` header's keys, inside the latest table of any
+/// array of tables along the way.
+fn table_path(keys: &[String], arrays: &[(Vec<Step>, usize)]) -> Vec<Step> {
+    let mut path = Vec::new();
+    for key in keys {
+        path.push(Step::Key(key.clone()));
+        if let Some((_, count)) = arrays.iter().find(|(p, _)| *p == path) {
+            path.push(Step::Index(count - 1));
+        }
+    }
+    path
+}
+
+// ─── YAML ───────────────────────────────────────────────────────────────────
+
+/// An open block mapping or sequence.
+struct Frame {
+    /// Column of its keys or dashes.
+    column: usize,
+    path: Vec<Step>,
+    /// For a sequence, the index of its next item.
+    next_index: Option<usize>,
+}
+
+/// Block mappings and sequences, with scalar, quoted, flow and block scalar
+/// values. Flow collections are located whole, without their contents.
+struct YamlScan<'a> {
+    text: &'a str,
+    /// Byte offset and text of every line.
+    lines: Vec<(usize, &'a str)>,
+    frames: Vec<Frame>,
+    /// A key or item whose value is the block on the lines below, with the
+    /// column of the key or dash.
+    pending: Option<(Vec<Step>, usize)>,
+    found: Vec<Located>,
+}
+
+fn yaml_values(text: &str) -> Result<Vec<Located>, String> {
+    let mut offset = 0;
+    let lines = text.split('\n')
+        .map(|line| {
+            let start = offset;
+            offset += line.len() + 1;
+            (start, line)
+        })
+        .collect();
+    let mut scan = YamlScan { text, lines, frames: Vec::new(), pending: None, found: Vec::new() };
+    let mut i = 0;
+    while i < scan.lines.len() {
+        let line = scan.lines[i].1;
+        let body = line.trim_start_matches(' ');
+        if body.starts_with("---") {
+            scan.frames.clear();
+            scan.pending = None;
+        }
+        if body.is_empty() || body.starts_with(['#', '%']) || body.starts_with("---") || body.starts_with("...") {
+            i += 1;
+            continue;
+        }
+        i = scan.entry(i, line.len() - body.len())?;
+    }
+    Ok(scan.found)
+}
+
+impl YamlScan<'_> {
+    /// Read the mapping key or sequence item starting at `column` of line
+    /// `i`. Returns the next line to read.
+    fn entry(&mut self, i: usize, column: usize) -> Result<usize, String> {
+        let line = self.lines[i].1;
+        let rest = &line[column..];
+        if rest == "-" || rest.starts_with("- ") {
+            while self.frames.last().is_some_and(|f| f.column > column) {
+                self.frames.pop();
+            }
+            let index = match self.frames.last_mut() {
+                Some(Frame { column: c, next_index: Some(next), .. }) if *c == column => {
+                    *next += 1;
+                    *next - 1
+                }
+                _ => {
+                    let path = self.take_pending(column);
+                    self.frames.push(Frame { column, path, next_index: Some(1) });
+                    0
+                }
+            };
+            let mut path = self.frames.last().expect("a sequence is open").path.clone();
+            path.push(Step::Index(index));
+            let inner = column + 1 + (rest[1..].len() - rest[1..].trim_start_matches(' ').len());
+            let item = &line[inner..];
+            if item.is_empty() || item.starts_with('#') {
+                self.pending = Some((path, column));
+                return Ok(i + 1);
+            }
+            if item == "-" || item.starts_with("- ") || mapping_key(item).is_some() {
+                self.pending = Some((path, column));
+                return self.entry(i, inner);
+            }
+            return self.value(path, i, inner, column);
+        }
+
+        let Some((key, colon)) = mapping_key(rest) else {
+            return Ok(i + 1);
+        };
+        // A sequence written at its parent key's column ends at the next key
+        while self.frames.last().is_some_and(|f| f.column > column || (f.column == column && f.next_index.is_some())) {
+            self.frames.pop();
+        }
+        if self.frames.last().is_none_or(|f| f.column != column) {
+            let path = self.take_pending(column);
+            self.frames.push(Frame { column, path, next_index: None });
+        }
+        self.pending = None;
+        let mut path = self.frames.last().expect("a mapping is open").path.clone();
+        path.push(Step::Key(key));
+        let after = &rest[colon + 1..];
+        let value_column = column + colon + 1 + (after.len() - after.trim_start_matches(' ').len());
+        let value = &line[value_column..];
+        if value.is_empty() || value.starts_with('#') {
+            self.pending = Some((path, column));
+            return Ok(i + 1);
+        }
+        self.value(path, i, value_column, column)
+    }
+
+    fn take_pending(&mut self, column: usize) -> Vec<Step> {
+        match self.pending.take() {
+            Some((path, owner)) if owner <= column => path,
+            _ => self.frames.last().map(|f| f.path.clone()).unwrap_or_default(),
+        }
+    }
+
+    fn error(&self, i: usize, message: &str) -> String {
+        format!("line {}: {}", i + 1, message)
+    }
+
+    /// Locate the value starting at `column` of line `i`, owned by the key
+    /// or dash at column `owner`. Returns the next line to read.
+    fn value(&mut self, path: Vec<Step>, i: usize, mut column: usize, owner: usize) -> Result<usize, String> {
+        let (offset, line) = self.lines[i];
+        // Anchors and tags stay in front of the value
+        while line[column..].starts_with(['&', '!']) {
+            let token = line[column..].find(' ').unwrap_or(line.len() - column);
+            column += token + (line[column + token..].len() - line[column + token..].trim_start_matches(' ').len());
+            if column == line.len() {
+                self.pending = Some((path, owner));
+                return Ok(i + 1);
+            }
+        }
+        let start = offset + column;
+        let end = match line.as_bytes()[column] {
+            b'|' | b'>' => {
+                let mut end = offset + line.len();
+                for &(next_offset, next) in &self.lines[i + 1..] {
+                    let body = next.trim_start_matches(' ');
+                    if body.is_empty() {
+                        continue;
+                    }
+                    if next.len() - body.len() <= owner {
+                        break;
+                    }
+                    end = next_offset + next.len();
+                }
+                end
+            }
+            b'"' => start + quoted_len(&self.text[start..], '"').ok_or_else(|| self.error(i, "unterminated string"))?,
+            b'\'' => start + quoted_len(&self.text[start..], '\'').ok_or_else(|| self.error(i, "unterminated string"))?,
+            b'[' | b'{' => start + flow_len(&self.text[start..]).ok_or_else(|| self.error(i, "unterminated flow collection"))?,
+            _ => {
+                let plain = &line[column..];
+                let plain = plain.find(" #").map_or(plain, |comment| &plain[..comment]);
+                start + plain.trim_end().len()
+            }
+        };
+        self.found.push(Located { path, start, end });
+        Ok(self.lines.partition_point(|&(line_offset, _)| line_offset < end))
+    }
+}
+
+/// The key and the byte index of its `:`, for a `key: value` line.
+fn mapping_key(rest: &str) -> Option<(String, usize)> {
+    let (key, after) = match rest.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let len = quoted_len(rest, quote)?;
+            let key = if quote == '"' {
+                serde_json::from_str(&rest[..len]).ok()?
+            } else {
+                rest[1..len - 1].replace("''", "'")
+            };
+            (key, len + (rest[len..].len() - rest[len..].trim_start_matches(' ').len()))
+        }
+        '#' | '-' | '[' | '{' | '?' | '|' | '>' => return None,
+        _ => {
+            let colon = rest.match_indices(':').map(|(i, _)| i)
+                .find(|&i| rest[i + 1..].is_empty() || rest[i + 1..].starts_with(' '))?;
+            if rest[..colon].contains(" #") {
+                return None;
+            }
+            (rest[..colon].trim_end().to_string(), colon)
+        }
+    };
+    rest[after..].starts_with(':').then_some((key, after))
+}
+
+/// Length of the quoted scalar at the start of `text`, quotes included.
+/// Double quotes take backslash escapes, and single quotes escape
+/// themselves by doubling.
+fn quoted_len(text: &str, quote: char) -> Option<usize> {
+    let mut chars = text.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if quote == '"' => {
+                chars.next();
+            }
+            '\'' if quote == '\'' && chars.peek().is_some_and(|&(_, next)| next == '\'') => {
+                chars.next();
+            }
+            c if c == quote => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Length of the flow collection at the start of `text`, brackets included.
+fn flow_len(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '[' | '{' => depth += 1,
+            ']' | '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            '"' | '\'' => {
+                let len = quoted_len(&text[i..], c)?;
+                chars.nth(text[i..i + len].chars().count() - 2);
+            }
+            _ => {}
+        }
+    }
+    None
+}
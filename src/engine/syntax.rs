@@ -0,0 +1,114 @@
+use std::path::Path;
+
+use tree_sitter::{Node, Parser};
+use xxhash_rust::xxh32::xxh32;
+
+use super::HASH_SEED;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Syntax Anchors
+// ═══════════════════════════════════════════════════════════════════════════
+//
+// The definitions enclosing a line come from a tree-sitter parse of the
+// whole file instead of the scoped heuristic, and seed its hash the same
+// way: each definition's `keyword name` is hashed into the seed of the one
+// around it. A file that does not parse still gets a tree, with its errors
+// confined to the nodes around them, so only the lines near an error can
+// change seed.
+
+/// A language whose syntax tree seeds hashes in [`crate::AnchorMode::Syntax`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Grammar {
+    Rust,
+    Python,
+}
+
+impl Grammar {
+    pub const ALL: [Grammar; 2] = [Grammar::Rust, Grammar::Python];
+
+    /// The grammar for `path`'s extension: `.rs`, or `.py` and `.pyi`.
+    pub fn for_path(path: impl AsRef<Path>) -> Option<Grammar> {
+        match path.as_ref().extension()?.to_str()? {
+            "rs" => Some(Grammar::Rust),
+            "py" | "pyi" => Some(Grammar::Python),
+            _ => None,
+        }
+    }
+
+    /// The grammar's name, as in `--anchors syntax:rust`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Grammar::Rust => "rust",
+            Grammar::Python => "python",
+        }
+    }
+
+    fn language(self) -> tree_sitter::Language {
+        match self {
+            Grammar::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Grammar::Python => tree_sitter_python::LANGUAGE.into(),
+        }
+    }
+
+    /// `keyword name` for a definition, e.g. `fn parse` or
+    /// `impl Display for Foo`, as the scoped heuristic names it; `None` for
+    /// any other node.
+    fn definition_identity(self, node: Node<'_>, source: &str) -> Option<String> {
+        let keyword = match (self, node.kind()) {
+            (Grammar::Rust, "function_item") => "fn",
+            (Grammar::Rust, "struct_item") => "struct",
+            (Grammar::Rust, "enum_item") => "enum",
+            (Grammar::Rust, "union_item") => "union",
+            (Grammar::Rust, "trait_item") => "trait",
+            (Grammar::Rust, "mod_item") => "mod",
+            (Grammar::Rust, "impl_item") => {
+                // Everything between `impl` and the body: generics, trait
+                // and type, with its whitespace collapsed
+                let body = node.child_by_field_name("body")?;
+                let mut cursor = node.walk();
+                let impl_keyword = node.children(&mut cursor).find(|child| child.kind() == "impl")?;
+                let signature = source.get(impl_keyword.end_byte()..body.start_byte())?;
+                return Some(format!("impl {}", signature.split_whitespace().collect::<Vec<_>>().join(" ")));
+            }
+            (Grammar::Python, "function_definition") => "def",
+            (Grammar::Python, "class_definition") => "class",
+            _ => return None,
+        };
+        // `mod name;` and other definitions without a body open no scope
+        node.child_by_field_name("body")?;
+        let name = node.child_by_field_name("name")?.utf8_text(source.as_bytes()).ok()?;
+        Some(format!("{} {}", keyword, name))
+    }
+}
+
+/// The seed of each of the first `line_count` lines of `source`: the chain
+/// of the definitions enclosing it in `grammar`'s syntax tree.
+pub(crate) fn line_seeds(source: &str, line_count: usize, grammar: Grammar) -> Vec<u32> {
+    let mut parser = Parser::new();
+    parser.set_language(&grammar.language()).expect("the grammars are built against this tree-sitter");
+    let tree = parser.parse(source, None).expect("no timeout or cancellation is set");
+
+    let mut seeds = vec![HASH_SEED; line_count];
+    // Each definition seeds all its lines before the definitions inside it
+    // are reached and seed theirs
+    let mut pending = vec![(tree.root_node(), HASH_SEED)];
+    while let Some((node, outer)) = pending.pop() {
+        let seed = match grammar.definition_identity(node, source) {
+            Some(identity) => {
+                let seed = xxh32(identity.as_bytes(), outer);
+                let end = node.end_position();
+                // A node ending with its newline ends on the line before
+                let last_row = if end.column == 0 && end.row > node.start_position().row { end.row - 1 } else { end.row };
+                let rows = node.start_position().row..(last_row + 1).min(line_count);
+                if let Some(lines) = seeds.get_mut(rows) {
+                    lines.fill(seed);
+                }
+                seed
+            }
+            None => outer,
+        };
+        let mut cursor = node.walk();
+        pending.extend(node.named_children(&mut cursor).map(|child| (child, seed)));
+    }
+    seeds
+}
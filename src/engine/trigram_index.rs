@@ -0,0 +1,83 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::vec;
+
+use super::max_edit_distance;
+use crate::line_similarity_at_least;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Trigram Index
+// ═══════════════════════════════════════════════════════════════════════════
+
+type Trigram = [char; 3];
+
+/// Lines indexed by their character trigrams, so a fuzzy search only runs
+/// edit distance against lines that share enough trigrams with the needle.
+///
+/// The filter never drops a real match: one edit touches at most three
+/// trigrams, so two strings within `k` edits of each other share at least
+/// `max_len - 2 - 3k` trigrams. Lines below that are skipped unscored.
+/// Build the index once to search the same lines for several needles.
+#[derive(Debug, Clone)]
+pub struct TrigramIndex<'a, L = String> {
+    lines: &'a [L],
+    /// Trimmed length of each line, in characters.
+    char_lens: Vec<usize>,
+    /// For each trigram, the lines containing it and how many times.
+    postings: BTreeMap<Trigram, Vec<(usize, usize)>>,
+}
+
+impl<'a, L: AsRef<str>> TrigramIndex<'a, L> {
+    pub fn new(lines: &'a [L]) -> TrigramIndex<'a, L> {
+        let mut char_lens = Vec::with_capacity(lines.len());
+        let mut postings: BTreeMap<Trigram, Vec<(usize, usize)>> = BTreeMap::new();
+        for (i, line) in lines.iter().enumerate() {
+            let chars: Vec<char> = line.as_ref().trim().chars().collect();
+            char_lens.push(chars.len());
+            for (trigram, count) in trigram_counts(&chars) {
+                postings.entry(trigram).or_default().push((i, count));
+            }
+        }
+        TrigramIndex { lines, char_lens, postings }
+    }
+
+    /// Indexes (0-based) of the lines that may be at least `min_similarity`
+    /// similar to `needle`, in line order.
+    pub fn candidates(&self, needle: &str, min_similarity: f64) -> Vec<usize> {
+        let needle: Vec<char> = needle.trim().chars().collect();
+        let mut shared = vec![0usize; self.lines.len()];
+        for (trigram, count) in trigram_counts(&needle) {
+            for &(i, line_count) in self.postings.get(&trigram).into_iter().flatten() {
+                shared[i] += count.min(line_count);
+            }
+        }
+        (0..self.lines.len())
+            .filter(|&i| {
+                let max_len = self.char_lens[i].max(needle.len());
+                let max_distance = max_edit_distance(min_similarity, max_len);
+                let required = max_len.saturating_sub(2).saturating_sub(max_distance.saturating_mul(3));
+                shared[i] >= required
+            })
+            .collect()
+    }
+
+    /// [`find_fuzzy_match`](crate::find_fuzzy_match) over the indexed lines.
+    pub fn find_fuzzy_match(&self, needle: &str, min_similarity: f64) -> Vec<(usize, f64)> {
+        let mut candidates: Vec<(usize, f64)> = self.candidates(needle, min_similarity)
+            .into_iter()
+            .filter_map(|i| Some((i + 1, line_similarity_at_least(self.lines[i].as_ref(), needle, min_similarity)?)))
+            .collect();
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        candidates
+    }
+}
+
+/// Each distinct trigram of `chars` with its number of occurrences.
+fn trigram_counts(chars: &[char]) -> BTreeMap<Trigram, usize> {
+    let mut counts = BTreeMap::new();
+    for window in chars.windows(3) {
+        *counts.entry([window[0], window[1], window[2]]).or_insert(0) += 1;
+    }
+    counts
+}
@@ -0,0 +1,142 @@
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::{apply_hashline_edits_with_options, line_byte_offsets, parse_edits, ApplyOptions, HashedFile, HashlineError};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// C ABI
+// ═══════════════════════════════════════════════════════════════════════════
+//
+// Every function takes NUL-terminated UTF-8 and returns a JSON string owned by
+// the library, or NULL on failure. Strings returned here must be released
+// with `hashline_free`; the failure itself is available from
+// `hashline_last_error` on the same thread. See `include/hashline.h`.
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Read a file and return its lines with their anchors and the byte offset
+/// each starts at: `{"file": path, "total_lines": n, "total_bytes": n,
+/// "lines": [{"anchor": "1#AB", "byte_offset": 0, "text": "..."}]}`.
+///
+/// # Safety
+///
+/// `path` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn hashline_read(path: *const c_char) -> *mut c_char {
+    ffi_call(|| {
+        let path = unsafe { str_arg(path, "path") }?;
+        let content = std::fs::read_to_string(path)?;
+        let file = HashedFile::from_content(&content);
+        let lines: Vec<serde_json::Value> = file.lines().iter().zip(line_byte_offsets(&content)).enumerate()
+            .map(|(i, (text, byte_offset))| serde_json::json!({
+                "anchor": file.anchor(i + 1).expect("line lies within the file").to_string(),
+                "byte_offset": byte_offset,
+                "text": text,
+            }))
+            .collect();
+        Ok(serde_json::json!({
+            "file": path,
+            "total_lines": file.len(),
+            "total_bytes": content.len(),
+            "lines": lines,
+        }))
+    })
+}
+
+/// Apply a JSON array of edits to `content` and return the outcome:
+/// `{"content", "first_changed_line", "relocations", "statuses", "bypassed"}`.
+/// Nothing is written to disk.
+///
+/// `options_json` is an optional JSON object of [`ApplyOptions`] fields; pass
+/// NULL for the defaults.
+///
+/// # Safety
+///
+/// `content` and `edits_json` must be valid NUL-terminated strings;
+/// `options_json` must be one or NULL.
+#[no_mangle]
+pub unsafe extern "C" fn hashline_apply_edits(
+    content: *const c_char,
+    edits_json: *const c_char,
+    options_json: *const c_char,
+) -> *mut c_char {
+    ffi_call(|| {
+        let content = unsafe { str_arg(content, "content") }?;
+        let edits = parse_edits(unsafe { str_arg(edits_json, "edits_json") }?)?;
+        let options: ApplyOptions = if options_json.is_null() {
+            ApplyOptions::default()
+        } else {
+            serde_json::from_str(unsafe { str_arg(options_json, "options_json") }?)
+                .map_err(|e| invalid_argument(format!("Failed to parse options: {}", e)))?
+        };
+        let outcome = apply_hashline_edits_with_options(content, &edits, &options)?;
+        Ok(serde_json::to_value(outcome).expect("ApplyOutcome serializes to JSON"))
+    })
+}
+
+/// The JSON error object for the last call on this thread that returned
+/// NULL, or NULL if it succeeded. The pointer stays valid until the next
+/// `hashline_*` call on the same thread and must not be freed.
+#[no_mangle]
+pub extern "C" fn hashline_last_error() -> *const c_char {
+    LAST_ERROR.with(|slot| slot.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+/// Release a string returned by `hashline_read` or `hashline_apply_edits`.
+///
+/// # Safety
+///
+/// `s` must be NULL or a pointer returned by this library that has not been
+/// freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn hashline_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+/// Run one call, turning its result into an owned JSON string or NULL plus
+/// the thread's last error. Panics are reported as errors rather than
+/// unwinding into C.
+fn ffi_call(f: impl FnOnce() -> Result<serde_json::Value, HashlineError>) -> *mut c_char {
+    let error = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => {
+            set_last_error(None);
+            return into_c_string(value.to_string());
+        }
+        Ok(Err(e)) => e.to_json(),
+        Err(_) => serde_json::json!({ "error": "panic", "message": "hashline-tools panicked" }),
+    };
+    set_last_error(Some(error.to_string()));
+    ptr::null_mut()
+}
+
+/// # Safety
+///
+/// `s` must be NULL or a valid NUL-terminated string that outlives the
+/// returned slice.
+unsafe fn str_arg<'a>(s: *const c_char, name: &str) -> Result<&'a str, HashlineError> {
+    if s.is_null() {
+        return Err(invalid_argument(format!("{} must not be NULL", name)));
+    }
+    unsafe { CStr::from_ptr(s) }.to_str()
+        .map_err(|_| invalid_argument(format!("{} is not valid UTF-8", name)))
+}
+
+fn invalid_argument(message: String) -> HashlineError {
+    HashlineError::Validation(vec![message])
+}
+
+fn into_c_string(s: String) -> *mut c_char {
+    // JSON escapes control characters, so serialized output never holds a NUL
+    CString::new(s).expect("JSON output contains no NUL bytes").into_raw()
+}
+
+fn set_last_error(error: Option<String>) {
+    let error = error.map(|e| CString::new(e).expect("JSON output contains no NUL bytes"));
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = error);
+}
@@ -0,0 +1,143 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use super::GitRef;
+use crate::Operation;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// The `git` Command
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// A `git` command run in `dir`, with stdin closed.
+fn git(dir: &Path) -> Command {
+    let mut git = Command::new("git");
+    git.arg("-C").arg(dir).stdin(Stdio::null());
+    git
+}
+
+/// Run `command`, failing only if git could not be started.
+fn run_git(command: &mut Command) -> Result<Output, String> {
+    command.output().map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => "git not found on PATH; install git to use git checkpoints and --git-changed".to_string(),
+        _ => format!("Failed to run git: {}", e),
+    })
+}
+
+/// Run `command` and return its trimmed stdout, failing with its stderr if
+/// it exits unsuccessfully.
+fn git_stdout(command: &mut Command) -> Result<String, String> {
+    let output = run_git(command)?;
+    if !output.status.success() {
+        let args: Vec<_> = command.get_args().skip(2).map(|arg| arg.to_string_lossy()).collect();
+        return Err(format!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim_end().to_string())
+}
+
+/// The top directory of the repository containing `dir`.
+pub(crate) fn repo_root(dir: &Path) -> Result<PathBuf, String> {
+    let output = run_git(git(dir).args(["rev-parse", "--show-toplevel"]))?;
+    if !output.status.success() {
+        return Err(format!("{} is not in a git repository", dir.display()));
+    }
+    Ok(PathBuf::from(String::from_utf8_lossy(&output.stdout).trim_end()))
+}
+
+/// Commit the working tree at `root` as it is on disk, untracked files that
+/// are not ignored included, with `HEAD` as the parent. The index, `HEAD`
+/// and branches are left alone. Returns the new commit.
+pub(crate) fn commit_working_tree(root: &Path, message: &str) -> Result<String, String> {
+    let git_dir = PathBuf::from(git_stdout(git(root).args(["rev-parse", "--absolute-git-dir"]))?);
+    // Stage everything into a private index, so the real one is left alone
+    let index = git_dir.join(format!("hashline-checkpoint-index-{}", std::process::id()));
+    let with_index = |args: &[&str]| git_stdout(git(root).env("GIT_INDEX_FILE", &index).args(args));
+    let head = run_git(git(root).args(["rev-parse", "--verify", "--quiet", "HEAD"]))?;
+    let head = head.status.success().then(|| String::from_utf8_lossy(&head.stdout).trim().to_string());
+    let tree = with_index(&["read-tree", head.as_deref().unwrap_or("--empty")])
+        .and_then(|_| with_index(&["add", "--all", "--", ":/"]))
+        .and_then(|_| with_index(&["write-tree"]));
+    let _ = fs::remove_file(&index);
+    let tree = tree?;
+
+    let mut commit_tree = git(root);
+    commit_tree
+        .env("GIT_AUTHOR_NAME", "hashline-tools")
+        .env("GIT_AUTHOR_EMAIL", "hashline-tools@localhost")
+        .env("GIT_COMMITTER_NAME", "hashline-tools")
+        .env("GIT_COMMITTER_EMAIL", "hashline-tools@localhost")
+        .args(["commit-tree", &tree, "-m", message]);
+    if let Some(head) = &head {
+        commit_tree.args(["-p", head]);
+    }
+    git_stdout(&mut commit_tree)
+}
+
+/// Point the ref `name` at `commit`, creating it if needed.
+pub(crate) fn update_ref(root: &Path, name: &str, commit: &str) -> Result<(), String> {
+    git_stdout(git(root).args(["update-ref", name, commit])).map(drop)
+}
+
+/// The refs under `prefix` in the repository containing `dir`, in no
+/// particular order.
+pub(crate) fn list_refs(dir: &Path, prefix: &str) -> Result<Vec<GitRef>, String> {
+    let format = "--format=%(refname) %(objectname) %(contents:subject)";
+    let listing = git_stdout(git(dir).args(["for-each-ref", format, prefix]))?;
+    Ok(listing.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, ' ');
+            let name = fields.next()?.strip_prefix(prefix)?.to_string();
+            let commit = fields.next()?.to_string();
+            Some(GitRef { name, commit, subject: fields.next().unwrap_or_default().to_string() })
+        })
+        .collect())
+}
+
+/// The files restoring `commit` into the working tree at `root` would
+/// rewrite, and the tracked ones it would delete.
+pub(crate) fn restore_changes(root: &Path, commit: &str) -> Result<Vec<(Operation, PathBuf)>, String> {
+    let listing = git_stdout(git(root).args(["diff", "--name-status", "--no-renames", "-z", commit, "--", ":/"]))?;
+    let mut fields = listing.split('\0').filter(|field| !field.is_empty());
+    let mut changes = Vec::new();
+    while let (Some(status), Some(path)) = (fields.next(), fields.next()) {
+        // Relative to the checkpoint, "added" files are the ones it lacks
+        let operation = if status == "A" { Operation::Delete } else { Operation::Write };
+        changes.push((operation, root.join(path)));
+    }
+    Ok(changes)
+}
+
+/// The `(start, count)` of each hunk of `file_path`'s diff against `HEAD`,
+/// as on the new side of its `@@` header, or `None` if `HEAD` does not have
+/// the file.
+pub(crate) fn head_hunks(file_path: &str) -> Result<Option<Vec<(usize, usize)>>, String> {
+    let path = Path::new(file_path);
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let name = path.file_name().ok_or_else(|| format!("Not a file: {}", file_path))?;
+    // `HEAD:./name` names the file in HEAD relative to `dir`
+    let in_head = format!("HEAD:./{}", name.to_string_lossy());
+    if !run_git(git(dir).args(["cat-file", "-e", &in_head]))?.status.success() {
+        if !run_git(git(dir).args(["rev-parse", "--git-dir"]))?.status.success() {
+            return Err(format!("{} is not in a git repository", file_path));
+        }
+        return Ok(None);
+    }
+    let diff = git_stdout(git(dir).args(["diff", "--no-color", "--no-ext-diff", "--unified=0", "HEAD", "--"]).arg(name))?;
+    Ok(Some(diff.lines().filter_map(hunk_new_side).collect()))
+}
+
+/// The new side of a `@@ -a,b +c,d @@` hunk header.
+fn hunk_new_side(line: &str) -> Option<(usize, usize)> {
+    let new = line.strip_prefix("@@ -")?.split(' ').nth(1)?.strip_prefix('+')?;
+    match new.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((new.parse().ok()?, 1)),
+    }
+}
+
+/// Make the working tree at `root` match `commit`, as [`restore_changes`]
+/// lists, without touching the index.
+pub(crate) fn restore_working_tree(root: &Path, commit: &str) -> Result<(), String> {
+    git_stdout(git(root).args(["restore", "--worktree", "--source", commit, "--", ":/"])).map(drop)
+}
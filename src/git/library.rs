@@ -0,0 +1,157 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use git2::build::CheckoutBuilder;
+use git2::{Commit, Delta, DiffOptions, IndexAddOption, Oid, Repository, Signature};
+
+use super::GitRef;
+use crate::Operation;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// libgit2
+// ═══════════════════════════════════════════════════════════════════════════
+
+fn git_error(e: git2::Error) -> String {
+    format!("git: {}", e.message())
+}
+
+fn open(dir: &Path) -> Result<Repository, String> {
+    Repository::discover(dir).map_err(|_| format!("{} is not in a git repository", dir.display()))
+}
+
+fn find_commit<'r>(repo: &'r Repository, commit: &str) -> Result<Commit<'r>, String> {
+    Oid::from_str(commit).and_then(|oid| repo.find_commit(oid)).map_err(git_error)
+}
+
+/// The top directory of the repository containing `dir`.
+pub(crate) fn repo_root(dir: &Path) -> Result<PathBuf, String> {
+    let repo = open(dir)?;
+    let workdir = repo.workdir().ok_or_else(|| format!("{} is in a bare repository", dir.display()))?;
+    Ok(workdir.components().collect())
+}
+
+/// Commit the working tree at `root` as it is on disk, untracked files that
+/// are not ignored included, with `HEAD` as the parent. The index, `HEAD`
+/// and branches are left alone. Returns the new commit.
+pub(crate) fn commit_working_tree(root: &Path, message: &str) -> Result<String, String> {
+    let repo = open(root)?;
+    let head = repo.head().and_then(|head| head.peel_to_commit()).ok();
+    // Stage everything in memory only; the index is never written back
+    let mut index = repo.index().map_err(git_error)?;
+    match &head {
+        Some(head) => head.tree().and_then(|tree| index.read_tree(&tree)),
+        None => index.clear(),
+    }.map_err(git_error)?;
+    index.add_all(["*"], IndexAddOption::DEFAULT, None).map_err(git_error)?;
+    index.update_all(["*"], None).map_err(git_error)?;
+    let tree = index.write_tree().and_then(|tree| repo.find_tree(tree)).map_err(git_error)?;
+
+    let signature = Signature::now("hashline-tools", "hashline-tools@localhost").map_err(git_error)?;
+    let parents: Vec<&Commit> = head.iter().collect();
+    let commit = repo.commit(None, &signature, &signature, message, &tree, &parents).map_err(git_error)?;
+    Ok(commit.to_string())
+}
+
+/// Point the ref `name` at `commit`, creating it if needed.
+pub(crate) fn update_ref(root: &Path, name: &str, commit: &str) -> Result<(), String> {
+    let repo = open(root)?;
+    let oid = Oid::from_str(commit).map_err(git_error)?;
+    repo.reference(name, oid, true, "hashline-tools: checkpoint").map(drop).map_err(git_error)
+}
+
+/// The refs under `prefix` in the repository containing `dir`, in no
+/// particular order.
+pub(crate) fn list_refs(dir: &Path, prefix: &str) -> Result<Vec<GitRef>, String> {
+    let repo = open(dir)?;
+    let mut refs = Vec::new();
+    for reference in repo.references_glob(&format!("{}*", prefix)).map_err(git_error)? {
+        let reference = reference.map_err(git_error)?;
+        let Some(name) = reference.name().ok().and_then(|name| name.strip_prefix(prefix)) else { continue };
+        let commit = reference.peel_to_commit().map_err(git_error)?;
+        refs.push(GitRef {
+            name: name.to_string(),
+            commit: commit.id().to_string(),
+            subject: commit.summary().ok().flatten().unwrap_or_default().to_string(),
+        });
+    }
+    Ok(refs)
+}
+
+/// The files restoring `commit` into the working tree at `root` would
+/// rewrite, and the tracked ones it would delete, relative to `root`.
+fn changes(repo: &Repository, commit: &Commit) -> Result<Vec<(Operation, PathBuf)>, String> {
+    let tree = commit.tree().map_err(git_error)?;
+    let diff = repo.diff_tree_to_workdir_with_index(Some(&tree), None).map_err(git_error)?;
+    Ok(diff.deltas()
+        .filter_map(|delta| {
+            // Relative to the checkpoint, "added" files are the ones it lacks
+            let operation = match delta.status() {
+                Delta::Added => Operation::Delete,
+                Delta::Unmodified | Delta::Ignored | Delta::Untracked => return None,
+                _ => Operation::Write,
+            };
+            let path = delta.new_file().path().or(delta.old_file().path())?;
+            Some((operation, path.to_path_buf()))
+        })
+        .collect())
+}
+
+/// The files restoring `commit` into the working tree at `root` would
+/// rewrite, and the tracked ones it would delete.
+pub(crate) fn restore_changes(root: &Path, commit: &str) -> Result<Vec<(Operation, PathBuf)>, String> {
+    let repo = open(root)?;
+    let changes = changes(&repo, &find_commit(&repo, commit)?)?;
+    Ok(changes.into_iter().map(|(operation, path)| (operation, root.join(path))).collect())
+}
+
+/// The `(start, count)` of each hunk of `file_path`'s diff against `HEAD`,
+/// as on the new side of its `@@` header, or `None` if `HEAD` does not have
+/// the file.
+pub(crate) fn head_hunks(file_path: &str) -> Result<Option<Vec<(usize, usize)>>, String> {
+    let path = Path::new(file_path);
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let name = path.file_name().ok_or_else(|| format!("Not a file: {}", file_path))?;
+    let repo = Repository::discover(dir).map_err(|_| format!("{} is not in a git repository", file_path))?;
+    let root = repo.workdir().ok_or_else(|| format!("{} is in a bare repository", file_path))?;
+    // Paths in the repository are relative to its top directory
+    let canonical = |path: &Path| fs::canonicalize(path).map_err(|e| format!("Failed to resolve {}: {}", path.display(), e));
+    let in_repo = canonical(dir)?.strip_prefix(canonical(root)?).map(|dir| dir.join(name))
+        .map_err(|_| format!("{} is not in a git repository", file_path))?;
+    let Ok(head) = repo.head().and_then(|head| head.peel_to_tree()) else { return Ok(None) };
+    if head.get_path(&in_repo).is_err() {
+        return Ok(None);
+    }
+
+    let mut options = DiffOptions::new();
+    options.pathspec(&in_repo).disable_pathspec_match(true).context_lines(0);
+    let diff = repo.diff_tree_to_workdir_with_index(Some(&head), Some(&mut options)).map_err(git_error)?;
+    let mut hunks = Vec::new();
+    diff.foreach(&mut |_, _| true, None, Some(&mut |_, hunk| {
+        hunks.push((hunk.new_start() as usize, hunk.new_lines() as usize));
+        true
+    }), None).map_err(git_error)?;
+    Ok(Some(hunks))
+}
+
+/// Make the working tree at `root` match `commit`, as [`restore_changes`]
+/// lists, without touching the index.
+pub(crate) fn restore_working_tree(root: &Path, commit: &str) -> Result<(), String> {
+    let repo = open(root)?;
+    let commit = find_commit(&repo, commit)?;
+    let (deleted, written): (Vec<_>, Vec<_>) = changes(&repo, &commit)?
+        .into_iter()
+        .partition(|(operation, _)| *operation == Operation::Delete);
+    // A checkout with no paths would check out every path
+    if !written.is_empty() {
+        let mut checkout = CheckoutBuilder::new();
+        checkout.force().update_index(false).disable_pathspec_match(true);
+        for (_, path) in &written {
+            checkout.path(path.as_path());
+        }
+        repo.checkout_tree(commit.as_object(), Some(&mut checkout)).map_err(git_error)?;
+    }
+    for (_, path) in deleted {
+        fs::remove_file(root.join(&path)).map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
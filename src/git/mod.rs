@@ -0,0 +1,35 @@
+// ═══════════════════════════════════════════════════════════════════════════
+// Git Backends
+// ═══════════════════════════════════════════════════════════════════════════
+//
+// Git checkpoints and `read --git-changed` run the `git` command found on
+// `PATH` by default. That keeps a C library and its build out of every
+// target, and lets git read the user's own configuration, attributes and
+// worktree layout as it always does, but those commands then need git
+// installed; without it they fail with a `git not found on PATH` error and
+// nothing else is affected. Built with the `git2` feature they link libgit2
+// instead, so git need not be installed and no process is started. Both
+// backends provide the operations below under the same names.
+
+#[cfg(not(feature = "git2"))]
+mod command;
+#[cfg(feature = "git2")]
+mod library;
+
+#[cfg(not(feature = "git2"))]
+pub(crate) use command::{
+    commit_working_tree, head_hunks, list_refs, repo_root, restore_changes, restore_working_tree, update_ref,
+};
+#[cfg(feature = "git2")]
+pub(crate) use library::{
+    commit_working_tree, head_hunks, list_refs, repo_root, restore_changes, restore_working_tree, update_ref,
+};
+
+/// A ref found by `list_refs`.
+pub(crate) struct GitRef {
+    /// The ref's name without the prefix it was listed by.
+    pub name: String,
+    pub commit: String,
+    /// The first line of the commit's message.
+    pub subject: String,
+}
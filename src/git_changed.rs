@@ -0,0 +1,78 @@
+use std::fs;
+use std::io::Write;
+use std::ops::Range;
+
+use crate::git::head_hunks;
+use crate::{annotate_lines_with_mode, AnchorMode, ReadOptions};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Git Changed Regions
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Unchanged lines shown on each side of a changed region.
+pub const GIT_CHANGED_CONTEXT: usize = 2;
+
+/// The lines of `file_path` that differ from git's `HEAD`, as 0-based
+/// ranges of the working-tree file in order. A deletion is an empty range
+/// at the line after it. A file that `HEAD` does not have is changed
+/// throughout. Runs the `git` command on `PATH`, which must be installed,
+/// unless built with the `git2` feature.
+pub fn git_changed_lines(file_path: &str) -> Result<Vec<Range<usize>>, String> {
+    let Some(hunks) = head_hunks(file_path)? else {
+        let content = fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+        return Ok(std::iter::once(0..content.lines().count()).collect());
+    };
+    Ok(hunks.into_iter()
+        .map(|(start, count)| {
+            // A deletion's start is the line before it, so it falls after that line
+            let start = if count == 0 { start } else { start - 1 };
+            start..start + count
+        })
+        .collect())
+}
+
+/// Print the lines changed since `HEAD`, with [`GIT_CHANGED_CONTEXT`]
+/// lines around each change, as regions separated by `...`.
+pub(crate) fn read_git_changed(out: &mut impl Write, file_path: &str, options: &ReadOptions) -> Result<(), String> {
+    let changed = git_changed_lines(file_path)?;
+    let content = fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let total_lines = content.lines().count();
+    let mut regions: Vec<Range<usize>> = Vec::new();
+    for range in changed {
+        let region = range.start.saturating_sub(GIT_CHANGED_CONTEXT)..(range.end + GIT_CHANGED_CONTEXT).min(total_lines);
+        match regions.last_mut() {
+            Some(last) if region.start <= last.end => last.end = last.end.max(region.end),
+            _ if region.is_empty() => {}
+            _ => regions.push(region),
+        }
+    }
+    let write_error = |e: std::io::Error| format!("Failed to write output: {}", e);
+    let mode = options.anchor_mode.for_path(file_path);
+    write_regions(out, &content, &regions, total_lines, mode).map_err(write_error)
+}
+
+fn write_regions(
+    out: &mut impl Write,
+    content: &str,
+    regions: &[Range<usize>],
+    total_lines: usize,
+    mode: AnchorMode,
+) -> std::io::Result<()> {
+    writeln!(out, "<file>")?;
+    let mut lines = annotate_lines_with_mode(content, mode).enumerate();
+    for (i, region) in regions.iter().enumerate() {
+        if i > 0 {
+            writeln!(out, "...")?;
+        }
+        for (_, line) in lines.by_ref().skip_while(|(n, _)| *n < region.start).take(region.len()) {
+            writeln!(out, "{}", line)?;
+        }
+    }
+    if regions.is_empty() {
+        write!(out, "(No changes since HEAD - {} total lines)", total_lines)?;
+    } else {
+        let plural = if regions.len() == 1 { "" } else { "s" };
+        write!(out, "\n({} changed region{} since HEAD - {} total lines)", regions.len(), plural, total_lines)?;
+    }
+    write!(out, "\n</file>")
+}
@@ -0,0 +1,118 @@
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::git::{commit_working_tree, list_refs, repo_root, restore_changes, restore_working_tree, update_ref};
+use crate::{render_error, HashlineError, OutputFormat, Policy};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Git Checkpoints
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Git checkpoints are commits kept alive by refs under this prefix, one per
+/// checkpoint, named by its number. They are on no branch, so `git log` and
+/// pushes leave them out.
+pub const GIT_CHECKPOINT_REF_PREFIX: &str = "refs/hashline/checkpoints/";
+
+/// A snapshot of a repository's working tree, saved by [`create_git_checkpoint`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GitCheckpoint {
+    /// The checkpoint's number; later checkpoints have higher ones.
+    pub id: u64,
+    /// The snapshot commit.
+    pub commit: String,
+    pub message: String,
+}
+
+/// Save the working tree of the repository containing `dir` as a commit on
+/// a hidden ref, without touching the working tree, the index or any branch.
+/// Tracked files are saved as they are on disk, and so are untracked files
+/// that are not ignored.
+///
+/// Runs the `git` command on `PATH`, which must be installed, unless built
+/// with the `git2` feature.
+pub fn create_git_checkpoint(dir: &Path, message: Option<&str>) -> Result<GitCheckpoint, String> {
+    let root = repo_root(dir)?;
+    let id = list_git_checkpoints(&root)?.last().map_or(1, |latest| latest.id + 1);
+    let message = message.map_or_else(|| format!("hashline checkpoint {}", id), str::to_string);
+    let commit = commit_working_tree(&root, &message)?;
+    update_ref(&root, &format!("{}{}", GIT_CHECKPOINT_REF_PREFIX, id), &commit)?;
+    Ok(GitCheckpoint { id, commit, message })
+}
+
+/// The checkpoints of the repository containing `dir`, oldest first.
+pub fn list_git_checkpoints(dir: &Path) -> Result<Vec<GitCheckpoint>, String> {
+    let mut checkpoints: Vec<GitCheckpoint> = list_refs(dir, GIT_CHECKPOINT_REF_PREFIX)?
+        .into_iter()
+        .filter_map(|r| Some(GitCheckpoint { id: r.name.parse().ok()?, commit: r.commit, message: r.subject }))
+        .collect();
+    checkpoints.sort_by_key(|checkpoint| checkpoint.id);
+    Ok(checkpoints)
+}
+
+/// Put the working tree back as checkpoint `id` (the latest when `None`)
+/// saved it. The current working tree is saved as a new checkpoint first,
+/// which is returned with the restored one, so a restore can be undone.
+///
+/// Files the checkpoint has are rewritten, and tracked files it does not
+/// have are deleted. Untracked files created since are left alone. The
+/// index and branches are not touched. Nothing is saved or restored unless
+/// `policy` allows every one of those writes and deletions.
+pub fn restore_git_checkpoint(dir: &Path, id: Option<u64>, policy: &Policy) -> Result<(GitCheckpoint, GitCheckpoint), HashlineError> {
+    let root = repo_root(dir).map_err(HashlineError::GitRestore)?;
+    let checkpoints = list_git_checkpoints(&root).map_err(HashlineError::GitRestore)?;
+    let restored = match id {
+        Some(id) => checkpoints.iter().find(|c| c.id == id).ok_or_else(|| format!("Unknown checkpoint {}", id)),
+        None => checkpoints.last().ok_or_else(|| "No git checkpoints to restore".to_string()),
+    }.map_err(HashlineError::GitRestore)?.clone();
+    for (operation, path) in restore_changes(&root, &restored.commit).map_err(HashlineError::GitRestore)? {
+        policy.check(operation, &path, false)?;
+    }
+    let saved = create_git_checkpoint(&root, Some(&format!("before restoring checkpoint {}", restored.id)))
+        .map_err(HashlineError::GitRestore)?;
+    restore_working_tree(&root, &restored.commit).map_err(HashlineError::GitRestore)?;
+    Ok((restored, saved))
+}
+
+fn short(commit: &str) -> &str {
+    &commit[..commit.len().min(12)]
+}
+
+/// Create a git checkpoint, or list them when `list` is set.
+pub fn cmd_git_checkpoint(dir: &Path, message: Option<&str>, list: bool, format: OutputFormat) -> Result<String, String> {
+    if list {
+        let checkpoints = repo_root(dir)
+            .and_then(|root| list_git_checkpoints(&root))
+            .map_err(|e| render_error(format, "git", e))?;
+        if format == OutputFormat::Json {
+            return Ok(serde_json::json!({ "status": "ok", "checkpoints": checkpoints }).to_string());
+        }
+        if checkpoints.is_empty() {
+            return Ok("No git checkpoints".to_string());
+        }
+        let lines: Vec<String> = checkpoints.iter()
+            .map(|c| format!("  - {} ({}): {}", c.id, short(&c.commit), c.message))
+            .collect();
+        return Ok(format!("Git checkpoints:\n{}", lines.join("\n")));
+    }
+    let checkpoint = create_git_checkpoint(dir, message).map_err(|e| render_error(format, "git", e))?;
+    Ok(match format {
+        OutputFormat::Json => serde_json::json!({ "status": "checkpointed", "checkpoint": checkpoint }).to_string(),
+        OutputFormat::Text => format!(
+            "Saved git checkpoint {} as {}{} ({})",
+            checkpoint.id, GIT_CHECKPOINT_REF_PREFIX, checkpoint.id, short(&checkpoint.commit),
+        ),
+    })
+}
+
+/// Restore a git checkpoint and report what was saved in its place.
+pub fn cmd_restore_git_checkpoint(dir: &Path, id: Option<u64>, policy: &Policy, format: OutputFormat) -> Result<String, String> {
+    let (restored, saved) = restore_git_checkpoint(dir, id, policy).map_err(|e| render_error(format, e.kind(), e.to_string()))?;
+    Ok(match format {
+        OutputFormat::Json => serde_json::json!({ "status": "restored", "checkpoint": restored, "saved": saved }).to_string(),
+        OutputFormat::Text => format!(
+            "Restored git checkpoint {} ({}). The working tree it replaced is checkpoint {}",
+            restored.id, short(&restored.commit), saved.id,
+        ),
+    })
+}
@@ -0,0 +1,259 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{content_fingerprint, render_error, HashlineError, Operation, OutputFormat, Policy};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Transaction Journal
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Where transaction journals and undo checkpoints live unless another
+/// directory is given, relative to the working directory.
+pub const DEFAULT_JOURNAL_DIR: &str = ".hashline/journal";
+
+/// Where transaction journals live unless another directory is given: the
+/// project's own [`DEFAULT_JOURNAL_DIR`], so users and projects never share one.
+pub fn default_journal_dir() -> PathBuf {
+    PathBuf::from(DEFAULT_JOURNAL_DIR)
+}
+
+// Journals and checkpoints hold whole pre-edit files, and `recover` and
+// `undo` write what they hold over the files they name. So their
+// directories and files are created for the owner alone, and a directory
+// that another user owns or may write to is refused rather than trusted.
+
+/// Create `dir` and any missing parents, owner-only on Unix, and check that
+/// it is private.
+pub(crate) fn create_private_dir(dir: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    }
+    #[cfg(not(unix))]
+    fs::create_dir_all(dir)?;
+    check_private_dir(dir)
+}
+
+/// Refuse `dir` if it belongs to another user or others may write to it.
+pub(crate) fn check_private_dir(dir: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+        let metadata = fs::metadata(dir)?;
+        // SAFETY: geteuid() has no preconditions and cannot fail
+        let uid = unsafe { libc::geteuid() };
+        if metadata.uid() != uid || metadata.permissions().mode() & 0o022 != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{} must belong to the current user and not be writable by others", dir.display()),
+            ));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+/// Write `bytes` to `path` as a file only its owner may read, synced to disk.
+pub(crate) fn write_private(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+/// Record of a multi-file transaction that is in the middle of committing.
+///
+/// It is written, together with a backup of every target, before the first
+/// target is replaced, and removed once the last one is. A journal left
+/// behind means the process died mid-commit; [`recover_transactions`] uses
+/// it to finish or undo the transaction.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Journal {
+    pub files: Vec<JournalEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct JournalEntry {
+    /// Absolute path of the file being replaced.
+    pub path: PathBuf,
+    /// Temp file holding the new content, renamed over `path` on commit.
+    pub staged: PathBuf,
+    /// Copy of the original content.
+    pub backup: PathBuf,
+    /// Fingerprint of the new content, to tell whether the rename happened.
+    pub new_fingerprint: String,
+}
+
+/// A journal written to disk, removed again with [`JournalHandle::finish`].
+pub(crate) struct JournalHandle {
+    path: PathBuf,
+    backups: Vec<PathBuf>,
+}
+
+static JOURNAL_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+impl Journal {
+    /// Back up each target's current content and durably write the journal,
+    /// in `dir` kept private to the current user. `files` pairs each target
+    /// with its staged temp file, new content and original content.
+    pub fn begin(dir: &Path, files: &[(&Path, &Path, &str, &str)]) -> std::io::Result<JournalHandle> {
+        create_private_dir(dir)?;
+        let id = format!(
+            "{}-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or(0),
+            JOURNAL_COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let mut handle = JournalHandle { path: dir.join(format!("txn-{}.json", id)), backups: Vec::new() };
+        let mut journal = Journal { files: Vec::new() };
+        for (i, (target, staged, new_content, old_content)) in files.iter().enumerate() {
+            let backup = dir.join(format!("txn-{}.{}.bak", id, i));
+            write_private(&backup, old_content.as_bytes())?;
+            handle.backups.push(backup.clone());
+            journal.files.push(JournalEntry {
+                path: std::path::absolute(target)?,
+                staged: std::path::absolute(staged)?,
+                backup,
+                new_fingerprint: content_fingerprint(new_content),
+            });
+        }
+
+        // Write then rename, so a crash never leaves a half-written journal
+        let temp = handle.path.with_extension("json.tmp");
+        write_private(&temp, &serde_json::to_vec(&journal).expect("journal serializes"))?;
+        fs::rename(&temp, &handle.path)?;
+        Ok(handle)
+    }
+}
+
+impl JournalHandle {
+    /// The transaction finished (or was rolled back in-process): drop the journal.
+    pub fn finish(self) {
+        let _ = fs::remove_file(&self.path);
+        for backup in &self.backups {
+            let _ = fs::remove_file(backup);
+        }
+    }
+}
+
+/// What recovery did with one interrupted transaction.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecoveredTransaction {
+    pub journal: PathBuf,
+    /// "completed" or "rolled_back".
+    pub action: &'static str,
+    pub files: Vec<PathBuf>,
+}
+
+/// Finish or undo every transaction journaled in `dir`.
+///
+/// A transaction is completed when every file either already has its new
+/// content or still has its staged temp file. Otherwise, or when `rollback`
+/// is set, every file is restored from its backup. A `dir` that another
+/// user owns or may write to is refused, as its journals may be planted.
+/// Nothing is touched when `policy` forbids writing any journaled file.
+pub fn recover_transactions(dir: &Path, rollback: bool, policy: &Policy) -> Result<Vec<RecoveredTransaction>, HashlineError> {
+    let entries = match check_private_dir(dir).and_then(|_| fs::read_dir(dir)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(HashlineError::Recovery(format!("Failed to read journal directory {}: {}", dir.display(), e))),
+    };
+    let mut journals: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    journals.sort();
+
+    let journals = journals.into_iter()
+        .map(|journal_path| {
+            let text = fs::read_to_string(&journal_path)
+                .map_err(|e| HashlineError::Recovery(format!("Failed to read journal {}: {}", journal_path.display(), e)))?;
+            let journal: Journal = serde_json::from_str(&text)
+                .map_err(|e| HashlineError::Recovery(format!("Corrupt journal {}: {}", journal_path.display(), e)))?;
+            Ok((journal_path, journal))
+        })
+        .collect::<Result<Vec<_>, HashlineError>>()?;
+    for (_, journal) in &journals {
+        for entry in &journal.files {
+            policy.check(Operation::Write, &entry.path, false)?;
+        }
+    }
+
+    let mut recovered = Vec::new();
+    for (journal_path, journal) in journals {
+        let is_done = |entry: &JournalEntry| {
+            fs::read_to_string(&entry.path).is_ok_and(|c| content_fingerprint(&c) == entry.new_fingerprint)
+        };
+        let can_complete = journal.files.iter().all(|f| is_done(f) || f.staged.exists());
+
+        let action = if can_complete && !rollback {
+            for entry in journal.files.iter().filter(|f| !is_done(f)) {
+                fs::rename(&entry.staged, &entry.path)
+                    .map_err(|e| HashlineError::Recovery(format!("Failed to complete {}: {}", entry.path.display(), e)))?;
+            }
+            "completed"
+        } else {
+            for entry in &journal.files {
+                fs::copy(&entry.backup, &entry.path)
+                    .map_err(|e| HashlineError::Recovery(format!("Failed to restore {}: {}", entry.path.display(), e)))?;
+                let _ = fs::remove_file(&entry.staged);
+            }
+            "rolled_back"
+        };
+
+        for entry in &journal.files {
+            let _ = fs::remove_file(&entry.backup);
+        }
+        let _ = fs::remove_file(&journal_path);
+        recovered.push(RecoveredTransaction {
+            journal: journal_path,
+            action,
+            files: journal.files.into_iter().map(|f| f.path).collect(),
+        });
+    }
+    Ok(recovered)
+}
+
+/// Recover interrupted transactions and report what was done.
+pub fn cmd_recover(dir: &Path, rollback: bool, policy: &Policy, format: OutputFormat) -> Result<String, String> {
+    let recovered = recover_transactions(dir, rollback, policy).map_err(|e| {
+        let kind = if matches!(e, HashlineError::Policy(_)) { e.kind() } else { "recover_failed" };
+        render_error(format, kind, e.to_string())
+    })?;
+
+    if format == OutputFormat::Json {
+        return Ok(serde_json::json!({
+            "status": if recovered.is_empty() { "clean" } else { "recovered" },
+            "transactions": recovered,
+        }).to_string());
+    }
+    if recovered.is_empty() {
+        return Ok("No interrupted transactions found.".to_string());
+    }
+    let mut output = format!(
+        "Recovered {} interrupted transaction{}:",
+        recovered.len(),
+        if recovered.len() == 1 { "" } else { "s" }
+    );
+    for txn in &recovered {
+        let files: Vec<String> = txn.files.iter().map(|f| f.display().to_string()).collect();
+        output.push_str(&format!("\n  - {}: {}", txn.action.replace('_', " "), files.join(", ")));
+    }
+    Ok(output)
+}
@@ -0,0 +1,67 @@
+use std::io::Write;
+
+use log::LevelFilter;
+use serde::Deserialize;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Diagnostics Logging
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Environment variable with the log filter, such as `debug` or
+/// `hashline_tools::engine=trace`, used when `--log-level` is not given.
+pub const LOG_ENV_VAR: &str = "HASHLINE_LOG";
+
+/// How much diagnostic logging goes to stderr.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    /// Nothing (default)
+    #[default]
+    Off,
+    Error,
+    Warn,
+    /// Checks skipped by --force and how long each command took
+    Info,
+    /// Stale anchors, relocation attempts, skipped edits and phase timings
+    Debug,
+    /// Relocation candidates
+    Trace,
+}
+
+impl LogLevel {
+    pub fn filter(self) -> LevelFilter {
+        match self {
+            LogLevel::Off => LevelFilter::Off,
+            LogLevel::Error => LevelFilter::Error,
+            LogLevel::Warn => LevelFilter::Warn,
+            LogLevel::Info => LevelFilter::Info,
+            LogLevel::Debug => LevelFilter::Debug,
+            LogLevel::Trace => LevelFilter::Trace,
+        }
+    }
+}
+
+/// Write log records to stderr, one `key=value` line each, so stdout stays
+/// the command's result alone:
+///
+/// ```text
+/// level=DEBUG target=hashline_tools::engine msg="edits[0].pos: 12#VK relocated to 14#VK (hash)"
+/// ```
+///
+/// `level` wins over `HASHLINE_LOG`; with neither, nothing is logged. Only
+/// the first call in a process takes effect.
+pub fn init_logging(level: Option<LogLevel>) {
+    let mut builder = env_logger::Builder::new();
+    match (level, std::env::var(LOG_ENV_VAR)) {
+        (Some(level), _) => builder.filter_level(level.filter()),
+        (None, Ok(filters)) => builder.parse_filters(&filters),
+        (None, Err(_)) => builder.filter_level(LevelFilter::Off),
+    };
+    builder
+        .format(|buf, record| {
+            writeln!(buf, "level={} target={} msg={:?}", record.level(), record.target(), record.args().to_string())
+        })
+        .target(env_logger::Target::Stderr)
+        .try_init()
+        .ok();
+}
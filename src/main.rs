@@ -1,25 +1,262 @@
-use hashline_tools::{Cli, Commands, cmd_read, cmd_edit};
-use clap::Parser;
+use hashline_tools::{ApplyOptions, BackupsAction, CancelToken, CANCELLED_MESSAGE, Cli, Commands, Config, ProjectConfig, init_logging, EditOptions, EditReviewer, HashCache, Limits, Policy, ReadOptions, SearchOptions, cmd_read_with_options, cmd_search, cmd_edit_with_options, cmd_multi_edit, cmd_plan, cmd_commit, cmd_recover, cmd_undo, cmd_bench, cmd_export_series, cmd_git_checkpoint, cmd_restore_git_checkpoint, default_journal_dir, audit_log_path, cmd_stats, stats_file_path, cmd_manifest, cmd_verify_manifest, cmd_drift, cmd_backups_list, cmd_backups_restore, cmd_backups_prune, read_payload, DEFAULT_BACKUP_DIR, DEFAULT_STATS_FILE};
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Instant;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
+fn main() {
+    let started = Instant::now();
+    let result = run();
+    log::info!("command {} in {:.1?}", if result.is_ok() { "succeeded" } else { "failed" }, started.elapsed());
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        // Like a shell reports a command killed by Ctrl-C
+        let code = if e.to_string() == CANCELLED_MESSAGE { 130 } else { 1 };
+        std::process::exit(code);
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::from_env()?;
+    let matches = config.apply_defaults(Cli::command()).get_matches();
+    let mut cli = Cli::from_arg_matches(&matches)?;
+    init_logging(cli.log_level);
+    let given = |id| matches.value_source(id) == Some(ValueSource::CommandLine);
+    let project = ProjectConfig::for_path(target_path(&cli.command))?;
+    for file in project.files.iter().rev() {
+        log::debug!("project settings from {}", file.display());
+    }
+    cli.anchors = project.pin_anchors(cli.anchors, given("anchors"))?;
+    if !given("no_editorconfig") {
+        cli.no_editorconfig = project.editorconfig.map_or(cli.no_editorconfig, |editorconfig| !editorconfig);
+    }
+    let policy = Policy::from_env()?.and(project.policy);
+    let hash_cache = cli.hash_cache.map(HashCache::new);
+    let audit_log = audit_log_path(cli.audit_log).or(config.audit_log);
+    let checkpoints = config.checkpoints.unwrap_or(true);
+    let stats_file = stats_file_path(cli.record_stats).or(config.stats_file);
+    let limits = Limits {
+        max_file_bytes: cli.max_file_size,
+        max_edits: cli.max_edits,
+        max_payload_lines: cli.max_payload_lines,
+    };
     match cli.command {
-        Commands::Read { file_path, offset, limit } => {
-            let result = cmd_read(&file_path, offset, limit)?;
+        Commands::Read { file_path, offset, limit, continue_from, after, byte_offsets, fence, columns, git_changed } => {
+            let options = ReadOptions {
+                offset, limit, hash_cache, memory_budget: cli.memory_budget, anchor_mode: cli.anchors, fence, columns,
+                git_changed, continue_from, after, byte_offsets, audit_log, cancel: CancelToken::on_interrupt(),
+            };
+            let mut out = io::BufWriter::new(io::stdout().lock());
+            cmd_read_with_options(&mut out, &file_path, &options)?;
+            writeln!(out)?;
+            out.flush()?;
+        }
+        Commands::Search { dir, pattern, recursive, max_results } => {
+            let options = SearchOptions {
+                recursive, max_results, anchor_mode: cli.anchors, audit_log, cancel: CancelToken::on_interrupt(),
+            };
+            let mut out = io::BufWriter::new(io::stdout().lock());
+            cmd_search(&mut out, &dir, &pattern, &options)?;
+            writeln!(out)?;
+            out.flush()?;
+        }
+        Commands::Edit {
+            file_path, edits, edits_stdin, shorthand, cell, fence, auto_rebase, fix_off_by_one, mismatch_context,
+            conflict_markers, print_conflicts, allow_partial, no_dedupe, merge_overlaps,
+            sequential, validate_only, dry_run, suggest, interactive, diff_format, format,
+        } => {
+            let edits_json = match shorthand.to_edits_json()? {
+                Some(json) => json,
+                None => read_stdin_or(edits, edits_stdin, cli.max_payload, "--edits, --edits-stdin or a shorthand flag such as --replace required")?,
+            };
+            let options = EditOptions {
+                apply: ApplyOptions {
+                    auto_rebase,
+                    allow_partial,
+                    fix_off_by_one,
+                    mismatch_context,
+                    conflict_markers: conflict_markers || print_conflicts,
+                    force: cli.force,
+                    dedupe: !no_dedupe,
+                    merge_overlaps,
+                    sequential,
+                    anchor_mode: cli.anchors,
+                    fuzzy_threshold: cli.fuzzy_threshold,
+                    fuzzy_max_candidates: cli.fuzzy_max_candidates,
+                    line_ending: cli.line_ending,
+                    limits,
+                    ..ApplyOptions::default()
+                },
+                format,
+                validate_only,
+                dry_run,
+                suggest,
+                print_conflicts,
+                audit_log,
+                journal_dir: cli.journal_dir,
+                checkpoints,
+                backup_dir: cli.backup,
+                verify_cmd: cli.verify_cmd,
+                policy,
+                hash_cache,
+                memory_budget: cli.memory_budget,
+                editorconfig: !cli.no_editorconfig,
+                cell,
+                fence,
+                stats_file,
+                diff_format,
+                review: interactive.then(EditReviewer::terminal),
+                ..EditOptions::default()
+            };
+            let result = cmd_edit_with_options(&file_path, &edits_json, &options)?;
             println!("{}", result);
         }
-        Commands::Edit { file_path, edits, edits_stdin } => {
-            let edits_json = if edits_stdin {
-                use std::io::{self, Read};
-                let mut buffer = String::new();
-                io::stdin().read_to_string(&mut buffer)?;
-                buffer
-            } else {
-                edits.ok_or("--edits or --edits-stdin required")?
+        Commands::Plan { file_path, edits, edits_stdin, auto_rebase, allow_partial, sequential, format } => {
+            let edits_json = read_edits(edits, edits_stdin, cli.max_payload)?;
+            let options = EditOptions {
+                apply: ApplyOptions {
+                    auto_rebase, allow_partial, sequential, force: cli.force, anchor_mode: cli.anchors,
+                    fuzzy_threshold: cli.fuzzy_threshold, fuzzy_max_candidates: cli.fuzzy_max_candidates,
+                    line_ending: cli.line_ending, limits,
+                    ..ApplyOptions::default()
+                },
+                format,
+                audit_log,
+                policy,
+                memory_budget: cli.memory_budget,
+                editorconfig: !cli.no_editorconfig,
+                ..EditOptions::default()
             };
-            let result = cmd_edit(&file_path, &edits_json)?;
+            let result = cmd_plan(&file_path, &edits_json, &options)?;
+            println!("{}", result);
+        }
+        Commands::Commit { token, token_stdin, format } => {
+            let token = read_stdin_or(token, token_stdin, cli.max_payload, "--token or --token-stdin required")?;
+            let options = EditOptions {
+                apply: ApplyOptions { force: cli.force, limits, ..ApplyOptions::default() },
+                format,
+                audit_log,
+                journal_dir: cli.journal_dir,
+                checkpoints,
+                backup_dir: cli.backup,
+                verify_cmd: cli.verify_cmd,
+                policy,
+                memory_budget: cli.memory_budget,
+                ..EditOptions::default()
+            };
+            let result = cmd_commit(&token, &options)?;
+            println!("{}", result);
+        }
+        Commands::MultiEdit { edits, edits_stdin, auto_rebase, mismatch_context, format } => {
+            let payload_json = read_edits(edits, edits_stdin, cli.max_payload)?;
+            let options = EditOptions {
+                apply: ApplyOptions {
+                    auto_rebase, mismatch_context, force: cli.force, anchor_mode: cli.anchors,
+                    fuzzy_threshold: cli.fuzzy_threshold, fuzzy_max_candidates: cli.fuzzy_max_candidates,
+                    line_ending: cli.line_ending, limits,
+                    ..ApplyOptions::default()
+                },
+                format,
+                audit_log,
+                journal_dir: cli.journal_dir,
+                checkpoints,
+                backup_dir: cli.backup,
+                verify_cmd: cli.verify_cmd,
+                policy,
+                memory_budget: cli.memory_budget,
+                editorconfig: !cli.no_editorconfig,
+                ..EditOptions::default()
+            };
+            let result = cmd_multi_edit(&payload_json, &options)?;
+            println!("{}", result);
+        }
+        Commands::Recover { rollback, format } => {
+            let journal_dir = cli.journal_dir.unwrap_or_else(default_journal_dir);
+            let result = cmd_recover(&journal_dir, rollback, &policy, format)?;
+            println!("{}", result);
+        }
+        Commands::Undo { to, format } => {
+            let journal_dir = cli.journal_dir.unwrap_or_else(default_journal_dir);
+            let result = cmd_undo(&journal_dir, to.as_deref(), &policy, format)?;
+            println!("{}", result);
+        }
+        Commands::ExportSeries { from, root, output_dir, format } => {
+            let journal_dir = cli.journal_dir.unwrap_or_else(default_journal_dir);
+            let result = cmd_export_series(&journal_dir, from.as_deref(), &root, &output_dir, &policy, format)?;
+            println!("{}", result);
+        }
+        Commands::Checkpoint { message, list, format } => {
+            let result = cmd_git_checkpoint(Path::new("."), message.as_deref(), list, format)?;
+            println!("{}", result);
+        }
+        Commands::RestoreCheckpoint { id, format } => {
+            let result = cmd_restore_git_checkpoint(Path::new("."), id, &policy, format)?;
+            println!("{}", result);
+        }
+        Commands::Manifest { path, output, format } => {
+            let result = cmd_manifest(Path::new(&path), output.as_deref(), cli.anchors, &policy, format)?;
+            println!("{}", result);
+        }
+        Commands::VerifyManifest { manifest, format } => {
+            let result = cmd_verify_manifest(&manifest, format)?;
+            println!("{}", result);
+        }
+        Commands::Drift { manifest, format } => {
+            let result = cmd_drift(&manifest, format)?;
+            println!("{}", result);
+        }
+        Commands::Backups { action } => {
+            let dir = cli.backup.unwrap_or_else(|| DEFAULT_BACKUP_DIR.into());
+            let result = match action {
+                BackupsAction::List { file, format } => cmd_backups_list(&dir, file.as_deref(), format)?,
+                BackupsAction::Restore { file, version, format } => cmd_backups_restore(&dir, &file, version.as_deref(), &policy, format)?,
+                BackupsAction::Prune { keep_days, format } => cmd_backups_prune(&dir, keep_days, format)?,
+            };
+            println!("{}", result);
+        }
+        Commands::Stats { sessions, format } => {
+            let path = stats_file.unwrap_or_else(|| DEFAULT_STATS_FILE.into());
+            let result = cmd_stats(&path, sessions, format)?;
+            println!("{}", result);
+        }
+        Commands::Bench { file_path, iterations, edits, auto_rebase, sequential, format } => {
+            let options = EditOptions {
+                apply: ApplyOptions { auto_rebase, sequential, ..ApplyOptions::default() },
+                format,
+                hash_cache,
+                ..EditOptions::default()
+            };
+            let result = cmd_bench(&file_path, iterations, edits, &options)?;
             println!("{}", result);
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// The path whose `.hashlinerc` files apply: the file or directory the
+/// command works on, or the current directory.
+fn target_path(command: &Commands) -> &Path {
+    match command {
+        Commands::Read { file_path, .. }
+        | Commands::Edit { file_path, .. }
+        | Commands::Plan { file_path, .. }
+        | Commands::Bench { file_path, .. } => Path::new(file_path),
+        Commands::Search { dir, .. } => Path::new(dir),
+        Commands::Manifest { path, .. } => Path::new(path),
+        Commands::VerifyManifest { manifest, .. } | Commands::Drift { manifest, .. } => manifest,
+        _ => Path::new("."),
+    }
+}
+
+fn read_edits(edits: Option<String>, edits_stdin: bool, max_payload: u64) -> Result<String, Box<dyn std::error::Error>> {
+    read_stdin_or(edits, edits_stdin, max_payload, "--edits or --edits-stdin required")
+}
+
+fn read_stdin_or(value: Option<String>, from_stdin: bool, max_payload: u64, missing: &str) -> Result<String, Box<dyn std::error::Error>> {
+    if from_stdin {
+        Ok(read_payload(io::stdin().lock(), max_payload)?)
+    } else {
+        Ok(value.ok_or(missing)?)
+    }
+}
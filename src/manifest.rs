@@ -0,0 +1,448 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::next_read_command;
+use crate::search::{FileWalk, Stop};
+use crate::{content_fingerprint, line_hash, render_error, AnchorMode, CancelToken, HashedFile, LineHash, Operation, OutputFormat, Policy, ReadOptions};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Anchor Manifests
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// The extension of manifest files. `manifest FILE` writes `FILE.hashline`.
+pub const MANIFEST_EXTENSION: &str = "hashline";
+
+/// Where `manifest DIR` writes its manifest, inside the directory.
+pub const DIR_MANIFEST_FILE_NAME: &str = "manifest.hashline";
+
+/// The manifest format written; newer ones are refused.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Lines of a changed region `drift` shows per file in text output; the
+/// JSON report has them all.
+pub const DRIFT_REGION_LINES: usize = 40;
+
+/// The anchors of a file, or of every file under a directory, as of when the
+/// manifest was written. Paths are relative to the manifest's directory,
+/// with `/` separators, so a committed manifest holds wherever the checkout
+/// is.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    /// How the anchors were hashed; they are checked in the same mode.
+    pub anchors: AnchorMode,
+    /// The file or directory the manifest covers, `.` for the manifest's
+    /// own directory.
+    pub root: String,
+    pub files: BTreeMap<String, ManifestFile>,
+}
+
+/// One file of a [`Manifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// SHA-256 of the content, which catches changes the anchors do not,
+    /// such as to whitespace.
+    pub fingerprint: String,
+    /// `LINE#HASH` for every line.
+    pub anchors: Vec<String>,
+}
+
+/// How a file differs from its manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Drift {
+    Unchanged,
+    /// The content changed. `first_changed_line` is the first line whose
+    /// anchor differs, `None` when only whitespace did; `was` and `now` are
+    /// that line's anchors, where it exists.
+    Modified {
+        first_changed_line: Option<usize>,
+        was: Option<String>,
+        now: Option<String>,
+        old_lines: usize,
+        new_lines: usize,
+    },
+    /// Listed in the manifest, but gone or no longer text.
+    Missing,
+    /// Under the manifest's directory root, but not listed.
+    Added,
+}
+
+/// One file's [`Drift`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileDrift {
+    /// The path, as listed in the manifest.
+    pub path: String,
+    #[serde(flatten)]
+    pub drift: Drift,
+}
+
+/// The lines of a file that changed since its manifest, as one region from
+/// the first changed line to the last. The lines before it are unchanged and
+/// keep their anchors.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChangedRegion {
+    /// The region's first line, 1-indexed. It started there in the manifest
+    /// too.
+    pub start: usize,
+    /// Lines the region had in the manifest.
+    pub old_lines: usize,
+    /// Lines it has now.
+    pub new_lines: usize,
+    /// `LINE#HASH:text` for each of its current lines.
+    pub lines: Vec<String>,
+}
+
+/// Where `manifest` writes the manifest of `path` by default: beside a file,
+/// inside a directory.
+pub fn default_manifest_path(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        return path.join(DIR_MANIFEST_FILE_NAME);
+    }
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", MANIFEST_EXTENSION));
+    PathBuf::from(name)
+}
+
+impl Manifest {
+    /// The anchors of `path`, a file or a directory, for a manifest written
+    /// to `manifest_path`, which must be in `path`'s directory or above it.
+    /// A directory's files are found as `search --recursive` finds them:
+    /// ignored files, files that are not UTF-8 text, manifests and
+    /// `.hashline` directories are left out.
+    pub fn build(path: &Path, manifest_path: &Path, mode: AnchorMode) -> Result<Manifest, String> {
+        let base = manifest_dir(manifest_path)?;
+        let absolute = fs::canonicalize(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let root = absolute.strip_prefix(&base).map_err(|_| {
+            format!("The manifest {} must be in a directory containing {}", manifest_path.display(), path.display())
+        })?;
+        let mut files = BTreeMap::new();
+        for key in list_files(&base, root)? {
+            let Some(content) = read_text(&base.join(&key)).map_err(|e| format!("Failed to read {}: {}", key, e))? else {
+                continue;
+            };
+            let file = HashedFile::from_content_with_mode(&content, mode.for_path(&key));
+            let anchors = (1..=file.len()).map(|line| file.anchor(line).expect("line lies within the file").to_string()).collect();
+            files.insert(key, ManifestFile { fingerprint: content_fingerprint(&content), anchors });
+        }
+        Ok(Manifest { version: MANIFEST_VERSION, anchors: mode, root: path_key(root), files })
+    }
+
+    /// Read the manifest at `path`.
+    pub fn load(path: &Path) -> Result<Manifest, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Failed to read manifest {}: {}", path.display(), e))?;
+        let manifest: Manifest = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid manifest {}: {}", path.display(), e))?;
+        if manifest.version > MANIFEST_VERSION {
+            return Err(format!(
+                "Manifest {} is version {}; this build reads up to version {}",
+                path.display(), manifest.version, MANIFEST_VERSION,
+            ));
+        }
+        Ok(manifest)
+    }
+
+    /// Write the manifest to `path` as pretty-printed JSON, one anchor per
+    /// line so that a diff of a committed manifest shows the lines that
+    /// moved. Creates the directory if needed.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let write_error = |e: io::Error| format!("Failed to write manifest {}: {}", path.display(), e);
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(write_error)?;
+        }
+        let mut text = serde_json::to_string_pretty(self).expect("manifest serializes");
+        text.push('\n');
+        fs::write(path, text).map_err(write_error)
+    }
+
+    /// How each file differs from the manifest at `manifest_path`, which
+    /// this was loaded from: the files it lists in path order, then the
+    /// files added under a directory root.
+    pub fn verify(&self, manifest_path: &Path) -> Result<Vec<FileDrift>, String> {
+        let base = manifest_dir(manifest_path)?;
+        let mut report = Vec::new();
+        for (key, listed) in &self.files {
+            let drift = match read_text(&base.join(key)) {
+                Ok(Some(content)) => listed.drift(&content, self.anchors.for_path(key)),
+                Ok(None) => Drift::Missing,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Drift::Missing,
+                Err(e) => return Err(format!("Failed to read {}: {}", key, e)),
+            };
+            report.push(FileDrift { path: key.clone(), drift });
+        }
+        let root = base.join(&self.root);
+        if root.is_dir() {
+            let root = Path::new(&self.root).components().filter(|c| *c != Component::CurDir).collect::<PathBuf>();
+            for key in list_files(&base, &root)? {
+                if self.files.contains_key(&key) || !matches!(read_text(&base.join(&key)), Ok(Some(_))) {
+                    continue;
+                }
+                report.push(FileDrift { path: key, drift: Drift::Added });
+            }
+        }
+        Ok(report)
+    }
+}
+
+impl ManifestFile {
+    fn drift(&self, content: &str, mode: AnchorMode) -> Drift {
+        if content_fingerprint(content) == self.fingerprint {
+            return Drift::Unchanged;
+        }
+        let file = HashedFile::from_content_with_mode(content, mode);
+        let now: Vec<String> = (1..=file.len()).map(|line| file.anchor(line).expect("line lies within the file").to_string()).collect();
+        let first_changed = (0..self.anchors.len().max(now.len())).find(|&i| self.anchors.get(i) != now.get(i));
+        Drift::Modified {
+            first_changed_line: first_changed.map(|i| i + 1),
+            was: first_changed.and_then(|i| self.anchors.get(i).cloned()),
+            now: first_changed.and_then(|i| now.get(i).cloned()),
+            old_lines: self.anchors.len(),
+            new_lines: now.len(),
+        }
+    }
+}
+
+impl ManifestFile {
+    /// The region of `content`, the file's current content, that changed
+    /// since the manifest, or `None` if every anchor still holds. Unchanged
+    /// lines are matched from both ends. A chained anchor cannot be compared
+    /// once the chain has changed, so at the end a line counts as unchanged
+    /// if it hashes to its old anchor when chained from the old line above.
+    pub fn changed_region(&self, content: &str, mode: AnchorMode) -> Option<ChangedRegion> {
+        let file = HashedFile::from_content_with_mode(content, mode);
+        let old: Vec<&str> = self.anchors.iter().map(|anchor| anchor.split_once('#').map_or("", |(_, hash)| hash)).collect();
+        let new = file.hashes();
+        let prefix = old.iter().zip(new).take_while(|(old, new)| new == *old).count();
+        if prefix == old.len() && prefix == new.len() {
+            return None;
+        }
+        let unchanged_at_end = |k: usize| {
+            let (i, j) = (old.len() - 1 - k, new.len() - 1 - k);
+            match mode {
+                AnchorMode::Chained => {
+                    let above = if i == 0 { Ok(None) } else { old[i - 1].parse::<LineHash>().map(Some) };
+                    above.is_ok_and(|above| line_hash(i + 1, &file.lines()[j], above).as_str() == old[i])
+                }
+                _ => new[j] == *old[i],
+            }
+        };
+        let suffix = (0..old.len().min(new.len()) - prefix).take_while(|&k| unchanged_at_end(k)).count();
+        let new_lines = new.len() - prefix - suffix;
+        Some(ChangedRegion {
+            start: prefix + 1,
+            old_lines: old.len() - prefix - suffix,
+            new_lines,
+            lines: file.annotate(prefix + 1..=prefix + new_lines),
+        })
+    }
+}
+
+/// The canonical directory a manifest at `manifest_path` is in.
+fn manifest_dir(manifest_path: &Path) -> Result<PathBuf, String> {
+    let dir = manifest_path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    fs::canonicalize(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))
+}
+
+/// The files under `root`, a path relative to `base`, as manifest keys.
+fn list_files(base: &Path, root: &Path) -> Result<Vec<String>, String> {
+    let absolute = base.join(root);
+    let cancel = CancelToken::default();
+    let mut walk = FileWalk::new(&absolute, true, &cancel);
+    let mut keys = Vec::new();
+    let walked = walk.walk(root, &absolute, &mut |path| {
+        let skipped = path.extension().is_some_and(|ext| ext == MANIFEST_EXTENSION)
+            || path.components().any(|c| c.as_os_str() == ".hashline");
+        if !skipped {
+            keys.push(path_key(path));
+        }
+        Ok(())
+    });
+    match walked {
+        Ok(()) => Ok(keys),
+        Err(Stop::Io(e)) => Err(format!("Failed to list {}: {}", absolute.display(), e)),
+        Err(Stop::Limit | Stop::Cancelled) => unreachable!("manifest walks have no limit and are not cancelled"),
+    }
+}
+
+/// `path`'s components joined with `/`, or `.` for the empty path.
+fn path_key(path: &Path) -> String {
+    let key = path.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+    if key.is_empty() { ".".to_string() } else { key }
+}
+
+/// The file's content, or `None` if it is not UTF-8 text.
+fn read_text(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) if content.contains('\0') => Ok(None),
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Write the manifest of `path` to `output`, or to its
+/// [`default_manifest_path`], and report how much it covers. `policy` must
+/// allow writing the manifest.
+pub fn cmd_manifest(path: &Path, output: Option<&Path>, mode: AnchorMode, policy: &Policy, format: OutputFormat) -> Result<String, String> {
+    let output = output.map_or_else(|| default_manifest_path(path), Path::to_path_buf);
+    policy.check(Operation::Write, &output, false)
+        .map_err(|e| render_error(format, "policy_violation", e.to_string()))?;
+    let manifest = Manifest::build(path, &output, mode)
+        .and_then(|manifest| manifest.save(&output).map(|()| manifest))
+        .map_err(|e| render_error(format, "io", e))?;
+    let lines: usize = manifest.files.values().map(|file| file.anchors.len()).sum();
+    let files = manifest.files.len();
+    Ok(match format {
+        OutputFormat::Json => serde_json::json!({
+            "status": "ok",
+            "manifest": output,
+            "files": files,
+            "lines": lines,
+        }).to_string(),
+        OutputFormat::Text => format!(
+            "Wrote the anchors of {} file{} ({} lines) to {}",
+            files, if files == 1 { "" } else { "s" }, lines, output.display(),
+        ),
+    })
+}
+
+/// Check the files of the manifest at `manifest_path`. Fails, listing each
+/// file that drifted, if any did.
+pub fn cmd_verify_manifest(manifest_path: &Path, format: OutputFormat) -> Result<String, String> {
+    let report = Manifest::load(manifest_path)
+        .and_then(|manifest| manifest.verify(manifest_path))
+        .map_err(|e| render_error(format, "io", e))?;
+    let drifted: Vec<&FileDrift> = report.iter().filter(|file| file.drift != Drift::Unchanged).collect();
+    let summary = format!(
+        "{} of {} file{} in {} drifted",
+        drifted.len(), report.len(), if report.len() == 1 { "" } else { "s" }, manifest_path.display(),
+    );
+    if format == OutputFormat::Json {
+        let files = serde_json::to_value(&drifted).expect("drift serializes");
+        return if drifted.is_empty() {
+            Ok(serde_json::json!({ "status": "ok", "files": report.len(), "drifted": files }).to_string())
+        } else {
+            Err(serde_json::json!({
+                "status": "error",
+                "error": "manifest_drift",
+                "message": summary,
+                "files": report.len(),
+                "drifted": files,
+            }).to_string())
+        };
+    }
+    if drifted.is_empty() {
+        return Ok(match report.len() {
+            1 => format!("The file matches {}", manifest_path.display()),
+            files => format!("All {} files match {}", files, manifest_path.display()),
+        });
+    }
+    let lines: Vec<String> = drifted.iter().map(|file| format!("  - {}", describe(file))).collect();
+    Err(format!("{}:\n{}", summary, lines.join("\n")))
+}
+
+fn describe(file: &FileDrift) -> String {
+    match &file.drift {
+        Drift::Unchanged => format!("{}: unchanged", file.path),
+        Drift::Missing => format!("{}: missing", file.path),
+        Drift::Added => format!("{}: added", file.path),
+        Drift::Modified { first_changed_line: None, .. } => format!("{}: whitespace changed", file.path),
+        Drift::Modified { first_changed_line: Some(line), was, now, old_lines, new_lines } => {
+            let anchor = |anchor: &Option<String>| anchor.clone().unwrap_or_else(|| "none".to_string());
+            let size = if old_lines == new_lines {
+                format!("{} lines", new_lines)
+            } else {
+                format!("{} lines, was {}", new_lines, old_lines)
+            };
+            format!("{}: modified from line {} (was {}, now {}); {}", file.path, line, anchor(was), anchor(now), size)
+        }
+    }
+}
+
+/// Report what changed since the manifest at `manifest_path`: the files
+/// added and removed, and for each modified one the region that changed,
+/// with its lines' current anchors.
+pub fn cmd_drift(manifest_path: &Path, format: OutputFormat) -> Result<String, String> {
+    let manifest = Manifest::load(manifest_path).map_err(|e| render_error(format, "io", e))?;
+    let report = manifest.verify(manifest_path).map_err(|e| render_error(format, "io", e))?;
+    let base = manifest_path.parent().unwrap_or(Path::new(""));
+    let mut changes = Vec::new();
+    for file in report.iter().filter(|file| file.drift != Drift::Unchanged) {
+        let region = match file.drift {
+            Drift::Modified { .. } => {
+                let content = fs::read_to_string(base.join(&file.path))
+                    .map_err(|e| render_error(format, "io", format!("Failed to read {}: {}", file.path, e)))?;
+                manifest.files[&file.path].changed_region(&content, manifest.anchors.for_path(&file.path))
+            }
+            _ => None,
+        };
+        changes.push((file, region));
+    }
+    let count = |wanted: fn(&Drift) -> bool| report.iter().filter(|file| wanted(&file.drift)).count();
+    let added = count(|drift| *drift == Drift::Added);
+    let missing = count(|drift| *drift == Drift::Missing);
+    let modified = count(|drift| matches!(drift, Drift::Modified { .. }));
+    let unchanged = count(|drift| *drift == Drift::Unchanged);
+
+    if format == OutputFormat::Json {
+        let files: Vec<serde_json::Value> = changes.iter().map(|(file, region)| {
+            let mut value = serde_json::to_value(file).expect("drift serializes");
+            if let Some(region) = region {
+                value["region"] = serde_json::to_value(region).expect("region serializes");
+            }
+            value
+        }).collect();
+        return Ok(serde_json::json!({
+            "status": "ok",
+            "added": added,
+            "missing": missing,
+            "modified": modified,
+            "unchanged": unchanged,
+            "files": files,
+        }).to_string());
+    }
+    let mut out = format!(
+        "Since {}: {} added, {} missing, {} modified, {} unchanged",
+        manifest_path.display(), added, missing, modified, unchanged,
+    );
+    for (file, region) in &changes {
+        out += &match (&file.drift, region) {
+            (Drift::Added, _) => format!("\nadded {}", file.path),
+            (Drift::Missing, _) => format!("\nmissing {}", file.path),
+            (_, None) => format!("\nmodified {}: whitespace changed", file.path),
+            (_, Some(region)) => describe_region(&file.path, &base.join(&file.path), region, manifest.anchors),
+        };
+    }
+    Ok(out)
+}
+
+/// A modified file's line in the `drift` report, followed by the changed
+/// region's lines. `key` is the file as the manifest lists it, `path` where
+/// it is from the working directory.
+fn describe_region(key: &str, path: &Path, region: &ChangedRegion, mode: AnchorMode) -> String {
+    let span = |count: usize| match count {
+        1 => format!("line {}", region.start),
+        _ => format!("lines {}-{}", region.start, region.start + count - 1),
+    };
+    let what = match (region.old_lines, region.new_lines) {
+        (old, 0) => format!("{} line{} removed after line {}", old, if old == 1 { "" } else { "s" }, region.start - 1),
+        (0, new) => format!("{} inserted", span(new)),
+        (old, new) => format!("{} changed, was {}", span(new), span(old)),
+    };
+    let mut out = format!("\nmodified {}: {}", key, what);
+    for line in region.lines.iter().take(DRIFT_REGION_LINES) {
+        out += &format!("\n{}", line);
+    }
+    let more = region.lines.len().saturating_sub(DRIFT_REGION_LINES);
+    if more > 0 {
+        let options = ReadOptions { anchor_mode: mode, limit: Some(more), ..ReadOptions::default() };
+        let offset = format!("--offset {}", region.start - 1 + DRIFT_REGION_LINES);
+        let next = next_read_command(&path.display().to_string(), offset, &options);
+        out += &format!("\n({} more changed lines. Next: {})", more, next);
+    }
+    out
+}
@@ -0,0 +1,190 @@
+use std::convert::Infallible;
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::commands::next_read_command;
+use crate::{annotate_lines_with_mode, ReadOptions};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Markdown Code Fences
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// A fenced code block (```` ``` ```` or `~~~`) in a Markdown document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownFence {
+    /// 0-based position among the document's fences.
+    pub index: usize,
+    /// The info string after the opening marker, e.g. `rust title="main.rs"`.
+    pub info: String,
+    /// 1-based line of the opening marker.
+    pub line: usize,
+    /// Byte range of the lines between the markers.
+    pub content: Range<usize>,
+    /// Whether a closing marker ends the block. An unclosed block runs to
+    /// the end of the document.
+    pub closed: bool,
+}
+
+impl MarkdownFence {
+    /// The block's language, the first word of its info string.
+    pub fn language(&self) -> Option<&str> {
+        self.info.split_whitespace().next()
+    }
+
+    /// Whether `name` is the block's language or the value of one of its
+    /// `key=value` attributes, such as `title="main.rs"`.
+    pub fn is_named(&self, name: &str) -> bool {
+        self.info.split_whitespace().enumerate().any(|(i, word)| match word.split_once('=') {
+            Some((_, value)) => value.trim_matches(['"', '\'']) == name,
+            None => i == 0 && word == name,
+        })
+    }
+}
+
+/// The fenced code blocks of a Markdown document, in order. Only top-level
+/// fences are found: ones inside block quotes or indented under list items
+/// more than three spaces are not.
+pub fn markdown_fences(content: &str) -> Vec<MarkdownFence> {
+    struct Open {
+        marker: char,
+        len: usize,
+        info: String,
+        line: usize,
+        start: usize,
+    }
+    let mut fences = Vec::new();
+    let mut open: Option<Open> = None;
+    let mut offset = 0;
+    for (i, raw) in content.split_inclusive('\n').enumerate() {
+        let line = raw.trim_end_matches(['\n', '\r']);
+        let next = offset + raw.len();
+        match (&open, fence_marker(line)) {
+            (None, Some((marker, len, info))) if !(marker == '`' && info.contains('`')) => {
+                open = Some(Open { marker, len, info: info.to_string(), line: i + 1, start: next });
+            }
+            (Some(o), Some((marker, len, ""))) if marker == o.marker && len >= o.len => {
+                let o = open.take().expect("fence is open");
+                fences.push(MarkdownFence { index: fences.len(), info: o.info, line: o.line, content: o.start..offset, closed: true });
+            }
+            _ => {}
+        }
+        offset = next;
+    }
+    if let Some(o) = open {
+        fences.push(MarkdownFence { index: fences.len(), info: o.info, line: o.line, content: o.start..content.len(), closed: false });
+    }
+    fences
+}
+
+/// The marker character, marker length and info string of a fence line.
+fn fence_marker(line: &str) -> Option<(char, usize, &str)> {
+    let body = line.trim_start_matches(' ');
+    if line.len() - body.len() > 3 {
+        return None;
+    }
+    let marker = body.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = body.len() - body.trim_start_matches(marker).len();
+    (len >= 3).then(|| (marker, len, body[len..].trim()))
+}
+
+/// Picks one fence: by index, or by language or attribute value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenceSelector {
+    Index(usize),
+    Name(String),
+}
+
+impl FromStr for FenceSelector {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse() {
+            Ok(index) => FenceSelector::Index(index),
+            Err(_) => FenceSelector::Name(s.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for FenceSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenceSelector::Index(index) => write!(f, "{}", index),
+            FenceSelector::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl FenceSelector {
+    /// The one fence this selects. Errors list the fences there are.
+    pub fn select<'a>(&self, fences: &'a [MarkdownFence]) -> Result<&'a MarkdownFence, String> {
+        let matching: Vec<&MarkdownFence> = match self {
+            FenceSelector::Index(index) => fences.get(*index).into_iter().collect(),
+            FenceSelector::Name(name) => fences.iter().filter(|f| f.is_named(name)).collect(),
+        };
+        match matching[..] {
+            [fence] => Ok(fence),
+            [] if fences.is_empty() => Err("The file has no fenced code blocks".to_string()),
+            [] => Err(format!("No fence matches '{}'. Fences:{}", self, list_fences(fences.iter()))),
+            _ => Err(format!(
+                "{} fences match '{}'; select one by index:{}",
+                matching.len(),
+                self,
+                list_fences(matching.into_iter())
+            )),
+        }
+    }
+}
+
+fn list_fences<'a>(fences: impl Iterator<Item = &'a MarkdownFence>) -> String {
+    fences
+        .map(|f| format!("\n  {}: {} (line {})", f.index, if f.info.is_empty() { "(no info)" } else { &f.info }, f.line))
+        .collect()
+}
+
+/// `read` of one fence: its lines with anchors hashed within the fence.
+/// `offset` and `limit` count lines of the fence.
+pub(crate) fn read_fence(
+    out: &mut impl Write,
+    file_path: &str,
+    selector: &FenceSelector,
+    options: &ReadOptions,
+) -> Result<(), String> {
+    let content = fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let fences = markdown_fences(&content);
+    let fence = selector.select(&fences)?;
+    let start = options.offset.unwrap_or(0);
+    let count = options.limit.unwrap_or(2000);
+    write_fence(out, file_path, fence, &content[fence.content.clone()], start, count, options)
+        .map_err(|e| format!("Failed to write output: {}", e))
+}
+
+fn write_fence(
+    out: &mut impl Write,
+    file_path: &str,
+    fence: &MarkdownFence,
+    body: &str,
+    start: usize,
+    count: usize,
+    options: &ReadOptions,
+) -> std::io::Result<()> {
+    let total_lines = body.lines().count();
+    let info = fence.info.replace('&', "&amp;").replace('"', "&quot;");
+    writeln!(out, "<fence index=\"{}\" line=\"{}\" info=\"{}\">", fence.index, fence.line, info)?;
+    let mut last = None;
+    for line in annotate_lines_with_mode(body, options.anchor_mode).skip(start).take(count) {
+        writeln!(out, "{}", line)?;
+        last = Some(line.anchor());
+    }
+    let end = start.saturating_add(count).min(total_lines);
+    if end < total_lines {
+        let last = last.map(|anchor| format!(". Last line shown: {}", anchor)).unwrap_or_default();
+        writeln!(out, "\n(Fence has more lines. Use 'offset' parameter to read beyond line {}{})", end, last)?;
+        writeln!(out, "Next: {}", next_read_command(file_path, format!("--offset {}", end), options))?;
+    } else {
+        writeln!(out, "\n(End of fence - {} total lines)", total_lines)?;
+    }
+    write!(out, "</fence>")
+}
@@ -0,0 +1,126 @@
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use crate::{render_error, EditOptions, HashlineEdit, HashlineError, LimitExceeded, OutputFormat};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Memory Budget
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Bytes an in-memory edit holds per byte of the file: the content, its
+/// hashed lines, the edited content and the rehashed lines, with headroom for
+/// per-line overhead.
+pub const EDIT_MEMORY_PER_BYTE: u64 = 6;
+
+/// Largest payload `--edits-stdin` and `--token-stdin` read unless
+/// `--max-payload` says otherwise. Keep in step with the flag's default.
+pub const DEFAULT_MAX_PAYLOAD: u64 = 64 << 20;
+
+/// Read a whole payload from `reader`, refusing one over `limit` bytes
+/// instead of buffering it.
+pub fn read_payload(reader: impl Read, limit: u64) -> Result<String, String> {
+    let mut bytes = Vec::new();
+    reader.take(limit.saturating_add(1)).read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read stdin: {}", e))?;
+    if bytes.len() as u64 > limit {
+        return Err(format!("Payload on stdin is larger than {} bytes; raise --max-payload to allow it", limit));
+    }
+    String::from_utf8(bytes).map_err(|_| "Payload on stdin is not valid UTF-8".to_string())
+}
+
+/// Parse a size such as `512M`, `2G`, `64KiB` or `1000000`. Suffixes are
+/// powers of 1024 and case-insensitive.
+pub fn parse_byte_size(s: &str) -> Result<u64, String> {
+    let trimmed = s.trim();
+    let upper = trimmed.to_ascii_uppercase();
+    let digits = upper.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let multiplier: u64 = match &upper[digits.len()..] {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        unit => return Err(format!("Unknown size unit '{}' in '{}'", unit, s)),
+    };
+    let value: u64 = digits.trim().parse().map_err(|_| format!("Invalid size '{}'", s))?;
+    value.checked_mul(multiplier).ok_or_else(|| format!("Size '{}' is too large", s))
+}
+
+/// Whether reading a file of `size` bytes whole would exceed `budget`.
+pub(crate) fn exceeds_budget(size: u64, budget: Option<u64>) -> bool {
+    budget.is_some_and(|budget| size > budget)
+}
+
+impl EditOptions {
+    /// Refuse to edit `paths` in memory when together they would need more
+    /// than the memory budget. Files that cannot be read are left for the
+    /// read itself to report.
+    pub(crate) fn check_memory_budget<'a>(&self, paths: impl IntoIterator<Item = &'a Path>) -> Result<(), String> {
+        let Some(budget) = self.memory_budget else {
+            return Ok(());
+        };
+        let mut total = 0u64;
+        let mut largest: Option<(&Path, u64)> = None;
+        for path in paths {
+            let Ok(size) = fs::metadata(path).map(|m| m.len()) else {
+                continue;
+            };
+            total = total.saturating_add(size);
+            if largest.is_none_or(|(_, s)| size > s) {
+                largest = Some((path, size));
+            }
+        }
+        let needed = total.saturating_mul(EDIT_MEMORY_PER_BYTE);
+        match largest {
+            Some((path, size)) if needed > budget => Err(render_error(
+                self.format,
+                "too_large",
+                format!(
+                    "File too large for in-memory mode: {} is {} bytes, and editing needs about {} bytes, over the {}-byte memory budget",
+                    path.display(),
+                    size,
+                    needed,
+                    budget
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Per-Call Limits
+// ═══════════════════════════════════════════════════════════════════════════
+
+impl EditOptions {
+    /// Refuse, before reading them, files over `apply.limits`'s size cap.
+    /// Files that cannot be read are left for the read itself to report.
+    pub(crate) fn check_file_limits<'a>(&self, paths: impl IntoIterator<Item = &'a Path>) -> Result<(), String> {
+        for path in paths {
+            let Ok(size) = fs::metadata(path).map(|m| m.len()) else {
+                continue;
+            };
+            self.apply.limits.check_file_bytes(size).map_err(|e| self.render_limit_error(path, e))?;
+        }
+        Ok(())
+    }
+
+    /// Refuse a batch for `path` over `apply.limits`'s edit or line caps,
+    /// before it is planned.
+    pub(crate) fn check_batch_limits(&self, path: &Path, edits: &[HashlineEdit]) -> Result<(), String> {
+        self.apply.limits.check_batch(edits).map_err(|e| self.render_limit_error(path, e))
+    }
+
+    fn render_limit_error(&self, path: &Path, exceeded: LimitExceeded) -> String {
+        match self.format {
+            OutputFormat::Text => format!("{}: {}", path.display(), exceeded),
+            OutputFormat::Json => {
+                let mut value = HashlineError::from(exceeded).to_json();
+                value["status"] = "error".into();
+                value["file"] = path.display().to_string().into();
+                value.to_string()
+            }
+        }
+    }
+}
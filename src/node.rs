@@ -0,0 +1,275 @@
+//! N-API bindings; see the `node` feature. Exported to JavaScript as camelCase
+//! functions and plain objects, so these names never reach the crate root.
+
+use napi::{Error, Status};
+use napi_derive::napi;
+use serde::Serialize;
+
+use crate::{
+    apply_hashline_edits_with_options, generate_hash_aware_diff, line_byte_offsets, validate_hashline_edits_with_options,
+    write_atomic, edits_from_value, HashedFile, HashlineError, HashlineEdit, RelocationMethod,
+};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Node.js Bindings
+// ═══════════════════════════════════════════════════════════════════════════
+//
+// Mirrors of the engine types as plain JS objects; `napi build` generates the
+// matching TypeScript declarations. Failures throw an Error whose message is
+// a JSON error object (`error`, `message`, and `mismatches` for stale
+// anchors), as returned by `HashlineError::to_json`.
+
+/// One edit, as accepted by the CLI's `--edits` array.
+#[napi(object)]
+#[derive(Serialize)]
+pub struct Edit {
+    /// "replace", "append", "prepend", "delete" or "move".
+    pub op: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pos: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    /// Where a "move" puts its lines: just below this line.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    /// Required by every op except "delete" and "move".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_lines: Option<Vec<String>>,
+}
+
+/// Overrides for [`crate::ApplyOptions`]; unset fields keep their defaults.
+#[napi(object, js_name = "ApplyOptions")]
+#[derive(Serialize)]
+pub struct JsApplyOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_rebase: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rebase_window: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_partial: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix_off_by_one: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mismatch_context: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflict_markers: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dedupe: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merge_overlaps: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sequential: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fuzzy_threshold: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fuzzy_max_candidates: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limits: Option<JsLimits>,
+}
+
+/// Caps for [`crate::Limits`]; unset caps are off.
+#[napi(object, js_name = "Limits")]
+#[derive(Serialize)]
+pub struct JsLimits {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_file_bytes: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_edits: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_payload_lines: Option<u32>,
+}
+
+#[napi(object)]
+pub struct AnchoredLine {
+    pub line: u32,
+    pub hash: String,
+    /// `LINE#HASH`, ready to use as an edit's `pos` or `end`.
+    pub anchor: String,
+    /// The byte offset the line starts at, in UTF-8 bytes.
+    pub byte_offset: i64,
+    pub text: String,
+}
+
+#[napi(object)]
+pub struct ReadResult {
+    pub total_lines: u32,
+    /// The file's length in bytes.
+    pub total_bytes: i64,
+    pub lines: Vec<AnchoredLine>,
+}
+
+/// Outcome of a single submitted edit.
+#[napi(object, js_name = "EditStatus")]
+pub struct JsEditStatus {
+    /// "valid", "applied", "conflicted", "duplicate", "merged" or "skipped".
+    pub status: String,
+    /// For skipped edits: "mismatch", "overlap" or "invalid".
+    pub reason: Option<String>,
+    pub message: Option<String>,
+    /// For duplicates: the edit this one repeats.
+    pub of: Option<u32>,
+    /// For merged edits: the edit that absorbed this one.
+    pub into: Option<u32>,
+}
+
+/// A stale anchor that auto-rebase moved to a different line.
+#[napi(object, js_name = "Relocation")]
+pub struct JsRelocation {
+    pub edit_index: u32,
+    pub field: String,
+    pub from: String,
+    pub to: String,
+    /// "hash", "content", "expected_lines" or "off_by_one".
+    pub method: String,
+    /// For content matches: how similar the matched line was, 0 to 1.
+    pub similarity: Option<f64>,
+}
+
+/// A failed check that `force` ignored.
+#[napi(object, js_name = "BypassedCheck")]
+pub struct JsBypassedCheck {
+    pub edit_index: u32,
+    pub field: String,
+    pub message: String,
+}
+
+#[napi(object)]
+pub struct ApplyResult {
+    pub content: String,
+    /// Whether the edits changed the content at all.
+    pub changed: bool,
+    pub first_changed_line: Option<u32>,
+    pub relocations: Vec<JsRelocation>,
+    pub statuses: Vec<JsEditStatus>,
+    pub bypassed: Vec<JsBypassedCheck>,
+    /// The hash-aware diff, empty when nothing changed.
+    pub diff: String,
+}
+
+/// Read a file and return every line with its anchor.
+#[napi]
+pub fn read(path: String) -> napi::Result<ReadResult> {
+    let content = std::fs::read_to_string(&path).map_err(|e| to_js_error(e.into()))?;
+    let file = HashedFile::from_content(&content);
+    let lines = file.lines().iter().zip(file.hashes()).zip(line_byte_offsets(&content)).enumerate()
+        .map(|(i, ((text, hash), byte_offset))| AnchoredLine {
+            line: (i + 1) as u32,
+            hash: hash.to_string(),
+            anchor: format!("{}#{}", i + 1, hash),
+            byte_offset: byte_offset as i64,
+            text: text.to_string(),
+        })
+        .collect();
+    Ok(ReadResult { total_lines: file.len() as u32, total_bytes: content.len() as i64, lines })
+}
+
+/// Apply edits to `content` in memory.
+#[napi]
+pub fn apply_edits(content: String, edits: Vec<Edit>, options: Option<JsApplyOptions>) -> napi::Result<ApplyResult> {
+    apply(&content, edits, options)
+}
+
+/// Apply edits to a file and write it back if anything changed.
+#[napi]
+pub fn edit_file(path: String, edits: Vec<Edit>, options: Option<JsApplyOptions>) -> napi::Result<ApplyResult> {
+    let content = std::fs::read_to_string(&path).map_err(|e| to_js_error(e.into()))?;
+    let result = apply(&content, edits, options)?;
+    if result.changed {
+        write_atomic(std::path::Path::new(&path), &result.content).map_err(|e| to_js_error(e.into()))?;
+    }
+    Ok(result)
+}
+
+/// Check each edit against `content` without applying anything.
+#[napi]
+pub fn validate_edits(content: String, edits: Vec<Edit>, options: Option<JsApplyOptions>) -> napi::Result<Vec<JsEditStatus>> {
+    let edits = to_engine_edits(edits)?;
+    let options = to_engine_options(options)?;
+    Ok(validate_hashline_edits_with_options(&content, &edits, &options).iter().map(JsEditStatus::from).collect())
+}
+
+fn apply(content: &str, edits: Vec<Edit>, options: Option<JsApplyOptions>) -> napi::Result<ApplyResult> {
+    let edits = to_engine_edits(edits)?;
+    let options = to_engine_options(options)?;
+    let outcome = apply_hashline_edits_with_options(content, &edits, &options).map_err(to_js_error)?;
+    let changed = outcome.content != content;
+    let diff = if changed {
+        generate_hash_aware_diff(content, &outcome.content, outcome.first_changed_line.unwrap_or(1))
+    } else {
+        String::new()
+    };
+    Ok(ApplyResult {
+        changed,
+        first_changed_line: outcome.first_changed_line.map(|line| line as u32),
+        relocations: outcome.relocations.iter().map(|r| JsRelocation {
+            edit_index: r.edit_index as u32,
+            field: r.field.to_string(),
+            from: r.from.to_string(),
+            to: r.to.to_string(),
+            method: match r.method {
+                RelocationMethod::Hash => "hash",
+                RelocationMethod::Fuzzy { .. } => "content",
+                RelocationMethod::ExpectedLines => "expected_lines",
+                RelocationMethod::OffByOne => "off_by_one",
+            }.to_string(),
+            similarity: match r.method {
+                RelocationMethod::Fuzzy { similarity } => Some(similarity),
+                _ => None,
+            },
+        }).collect(),
+        statuses: outcome.statuses.iter().map(JsEditStatus::from).collect(),
+        bypassed: outcome.bypassed.iter().map(|b| JsBypassedCheck {
+            edit_index: b.edit_index as u32,
+            field: b.field.to_string(),
+            message: b.message.clone(),
+        }).collect(),
+        diff,
+        content: outcome.content,
+    })
+}
+
+impl From<&crate::EditStatus> for JsEditStatus {
+    fn from(status: &crate::EditStatus) -> Self {
+        use crate::EditStatus::*;
+        let js = |status: &str| JsEditStatus { status: status.to_string(), reason: None, message: None, of: None, into: None };
+        match status {
+            Valid => js("valid"),
+            Applied => js("applied"),
+            Conflicted { message } => JsEditStatus { message: Some(message.clone()), ..js("conflicted") },
+            Duplicate { of } => JsEditStatus { of: Some(*of as u32), ..js("duplicate") },
+            Merged { into } => JsEditStatus { into: Some(*into as u32), ..js("merged") },
+            Skipped { reason, message } => JsEditStatus {
+                reason: Some(reason.to_string()),
+                message: Some(message.clone()),
+                ..js("skipped")
+            },
+        }
+    }
+}
+
+/// Edits go through the same deserializer as the CLI's JSON, so both report
+/// malformed anchors and unknown ops identically.
+fn to_engine_edits(edits: Vec<Edit>) -> napi::Result<Vec<HashlineEdit>> {
+    let value = serde_json::to_value(edits).map_err(|e| invalid_arg(format!("Failed to parse edits: {}", e)))?;
+    edits_from_value(value).map_err(|e| invalid_arg(e.to_string()))
+}
+
+fn to_engine_options(options: Option<JsApplyOptions>) -> napi::Result<crate::ApplyOptions> {
+    let Some(options) = options else {
+        return Ok(crate::ApplyOptions::default());
+    };
+    let value = serde_json::to_value(options).map_err(|e| invalid_arg(format!("Failed to parse options: {}", e)))?;
+    serde_json::from_value(value).map_err(|e| invalid_arg(format!("Failed to parse options: {}", e)))
+}
+
+fn invalid_arg(message: String) -> Error {
+    Error::new(Status::InvalidArg, serde_json::json!({ "error": "invalid_edits", "message": message }).to_string())
+}
+
+fn to_js_error(e: HashlineError) -> Error {
+    Error::new(Status::GenericFailure, e.to_json().to_string())
+}
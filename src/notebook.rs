@@ -0,0 +1,346 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::ops::Range;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::value::RawValue;
+
+use crate::audit::{audit_forced_edit, forced_audit_log};
+use crate::commands::next_read_command;
+use crate::{
+    annotate_lines_with_mode, apply_and_diff_hashed_file, edits_from_value, parse_edits, render_apply_error,
+    render_error, render_hash_aware_diff, validate_hashed_file, write_atomic, AppliedEdits, EditOptions, HashedFile,
+    HashlineEdit, OutputFormat, ReadOptions,
+};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Jupyter Notebooks
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Whether `path` names a Jupyter notebook (`.ipynb`).
+pub fn is_notebook(path: impl AsRef<Path>) -> bool {
+    path.as_ref().extension().is_some_and(|ext| ext.eq_ignore_ascii_case("ipynb"))
+}
+
+/// A Jupyter notebook whose cell sources can be read and rewritten. Only
+/// the `source` values of rewritten cells change; every other byte of the
+/// notebook, outputs and metadata included, is kept as it was.
+#[derive(Debug, Clone)]
+pub struct Notebook {
+    text: String,
+    cells: Vec<NotebookCell>,
+}
+
+/// One cell of a [`Notebook`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotebookCell {
+    /// 0-based position in the notebook.
+    pub index: usize,
+    /// `code`, `markdown` or `raw`.
+    pub cell_type: String,
+    /// The cell's source as one string.
+    pub source: String,
+    /// Byte range of the `source` value in the notebook's JSON text.
+    span: Range<usize>,
+}
+
+#[derive(Deserialize)]
+struct RawNotebook<'a> {
+    #[serde(borrow)]
+    cells: Vec<&'a RawValue>,
+}
+
+#[derive(Deserialize)]
+struct RawCell<'a> {
+    cell_type: String,
+    #[serde(borrow)]
+    source: &'a RawValue,
+}
+
+/// nbformat stores a source as one string or as a list of lines.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Source {
+    Text(String),
+    Lines(Vec<String>),
+}
+
+impl Notebook {
+    pub fn parse(text: impl Into<String>) -> Result<Notebook, String> {
+        let text = text.into();
+        let invalid = |e: serde_json::Error| format!("Invalid notebook: {}", e);
+        let raw: RawNotebook = serde_json::from_str(&text).map_err(invalid)?;
+        let mut cells = Vec::with_capacity(raw.cells.len());
+        for (index, raw_cell) in raw.cells.iter().enumerate() {
+            let cell: RawCell = serde_json::from_str(raw_cell.get()).map_err(invalid)?;
+            let source = match serde_json::from_str(cell.source.get()).map_err(invalid)? {
+                Source::Text(source) => source,
+                Source::Lines(lines) => lines.concat(),
+            };
+            // Borrowed raw values are slices of `text`
+            let start = cell.source.get().as_ptr() as usize - text.as_ptr() as usize;
+            let span = start..start + cell.source.get().len();
+            cells.push(NotebookCell { index, cell_type: cell.cell_type, source, span });
+        }
+        Ok(Notebook { text, cells })
+    }
+
+    pub fn cells(&self) -> &[NotebookCell] {
+        &self.cells
+    }
+
+    /// The notebook's JSON text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The notebook's JSON text with the sources of the cells in `sources`
+    /// replaced, each stored in the form (string or list of lines) and
+    /// indentation the cell used before.
+    pub fn with_sources(&self, sources: &BTreeMap<usize, String>) -> String {
+        let mut text = self.text.clone();
+        for (&index, source) in sources.iter().rev() {
+            let cell = &self.cells[index];
+            text.replace_range(cell.span.clone(), &self.source_json(cell, source));
+        }
+        text
+    }
+
+    fn source_json(&self, cell: &NotebookCell, source: &str) -> String {
+        let raw = &self.text[cell.span.clone()];
+        if raw.starts_with('"') {
+            return serde_json::to_string(source).expect("strings serialize");
+        }
+        if source.is_empty() {
+            return "[]".to_string();
+        }
+        let (item_indent, close_indent) = match (raw.find('\n'), raw.rfind('\n')) {
+            (Some(first), Some(last)) => (leading_whitespace(&raw[first + 1..]), &raw[last + 1..raw.len() - 1]),
+            _ => {
+                // An empty or one-line list: indent one level past the key's line
+                let line_start = self.text[..cell.span.start].rfind('\n').map_or(0, |i| i + 1);
+                let key_indent = leading_whitespace(&self.text[line_start..]);
+                let unit = self.text.find('\n').map_or(" ", |i| leading_whitespace(&self.text[i + 1..]));
+                return format_lines(source, &format!("{}{}", key_indent, unit), key_indent);
+            }
+        };
+        format_lines(source, item_indent, close_indent)
+    }
+}
+
+fn leading_whitespace(text: &str) -> &str {
+    &text[..text.len() - text.trim_start_matches([' ', '\t']).len()]
+}
+
+/// `source` as a pretty-printed JSON list of lines, each keeping its `\n`.
+fn format_lines(source: &str, item_indent: &str, close_indent: &str) -> String {
+    let items: Vec<String> = source.split_inclusive('\n')
+        .map(|line| format!("{}{}", item_indent, serde_json::to_string(line).expect("strings serialize")))
+        .collect();
+    format!("[\n{}\n{}]", items.join(",\n"), close_indent)
+}
+
+/// `read` for a notebook: each cell's source with anchors hashed within the
+/// cell. `offset` and `limit` count cells rather than lines.
+pub(crate) fn read_notebook(out: &mut impl Write, file_path: &str, options: &ReadOptions) -> Result<(), String> {
+    let text = fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let notebook = Notebook::parse(text)?;
+    let start = options.offset.unwrap_or(0);
+    let count = options.limit.unwrap_or(2000);
+    write_notebook(out, file_path, &notebook, start, count, options)
+        .map_err(|e| format!("Failed to write output: {}", e))
+}
+
+fn write_notebook(
+    out: &mut impl Write,
+    file_path: &str,
+    notebook: &Notebook,
+    start: usize,
+    count: usize,
+    options: &ReadOptions,
+) -> std::io::Result<()> {
+    let total = notebook.cells().len();
+    writeln!(out, "<notebook>")?;
+    let mut last = None;
+    for cell in notebook.cells().iter().skip(start).take(count) {
+        writeln!(out, "<cell index=\"{}\" type=\"{}\">", cell.index, cell.cell_type)?;
+        for line in annotate_lines_with_mode(&cell.source, options.anchor_mode) {
+            writeln!(out, "{}", line)?;
+            last = Some((line.anchor(), cell.index));
+        }
+        writeln!(out, "</cell>")?;
+    }
+    let end = start.saturating_add(count).min(total);
+    if end < total {
+        let last = last.map(|(anchor, cell)| format!(". Last line shown: {} of cell {}", anchor, cell)).unwrap_or_default();
+        writeln!(out, "\n(Notebook has more cells. Use 'offset' parameter to read beyond cell {}{})", end - 1, last)?;
+        writeln!(out, "Next: {}", next_read_command(file_path, format!("--offset {}", end), options))?;
+    } else {
+        writeln!(out, "\n(End of notebook - {} total cells)", total)?;
+    }
+    write!(out, "</notebook>")
+}
+
+/// `edit` for a notebook. `edits_json` keys the edits by cell index, e.g.
+/// `{"2": [...]}`, or is a plain edit list for `options.cell`. Each cell's
+/// anchors are checked against that cell alone, and only the sources of the
+/// cells that change are rewritten.
+pub(crate) fn edit_notebook(file_path: &str, edits_json: &str, options: &EditOptions) -> Result<String, String> {
+    let format = options.format;
+    let audit_log = forced_audit_log(options)?;
+    let text = fs::read_to_string(file_path)
+        .map_err(|e| render_error(format, "io", format!("Failed to read file: {}", e)))?;
+    let notebook = Notebook::parse(text).map_err(|e| render_error(format, "invalid_notebook", e))?;
+    let cell_edits = notebook_edits(edits_json, options.cell)
+        .map_err(|(kind, message)| render_error(format, kind, message))?;
+    if let Some(&missing) = cell_edits.keys().find(|&&cell| cell >= notebook.cells().len()) {
+        return Err(render_error(format, "invalid_edits", format!(
+            "Notebook has no cell {}; it has {} cells",
+            missing,
+            notebook.cells().len()
+        )));
+    }
+    let mode = options.apply.anchor_mode;
+    let hashed = |cell: usize| {
+        let source = &notebook.cells()[cell].source;
+        (source, HashedFile::from_content_with_mode(source, mode))
+    };
+
+    if options.validate_only {
+        let statuses: BTreeMap<String, _> = cell_edits.iter()
+            .map(|(&cell, edits)| {
+                let (source, file) = hashed(cell);
+                (cell.to_string(), validate_hashed_file(source, &file, edits, &options.apply))
+            })
+            .collect();
+        let failed = statuses.values().flatten().filter(|s| !s.is_ok()).count();
+        let output = match format {
+            OutputFormat::Json => serde_json::json!({
+                "status": if failed == 0 { "valid" } else { "invalid" },
+                "cells": statuses,
+            }).to_string(),
+            OutputFormat::Text if failed == 0 => "Validation passed: every edit would apply cleanly.".to_string(),
+            OutputFormat::Text => {
+                let mut output = format!("Validation failed: {} edits would be skipped.", failed);
+                for (cell, statuses) in &statuses {
+                    for (i, status) in statuses.iter().enumerate() {
+                        output.push_str(&format!("\n  - cell {} edits[{}]: {}", cell, i, status));
+                    }
+                }
+                output
+            }
+        };
+        return if failed == 0 { Ok(output) } else { Err(output) };
+    }
+
+    let mut applied: Vec<(usize, AppliedEdits)> = Vec::new();
+    for (&cell, edits) in &cell_edits {
+        let (source, file) = hashed(cell);
+        let result = apply_and_diff_hashed_file(source, file, edits, &options.apply)
+            .map_err(|e| cell_error(cell, &render_apply_error(&e, format), format))?;
+        if result.outcome.content != *source {
+            applied.push((cell, result));
+        }
+    }
+    if applied.is_empty() {
+        return Ok(match format {
+            OutputFormat::Text => "No changes made".to_string(),
+            OutputFormat::Json => serde_json::json!({ "status": "unchanged" }).to_string(),
+        });
+    }
+
+    let sources: BTreeMap<usize, String> = applied.iter()
+        .map(|(cell, result)| (*cell, result.outcome.content.clone()))
+        .collect();
+    let new_text = notebook.with_sources(&sources);
+    let mut checkpoint = None;
+    if !options.dry_run {
+        let bypassed: Vec<_> = applied.iter().flat_map(|(_, r)| r.outcome.bypassed.iter().cloned()).collect();
+        audit_forced_edit(audit_log, file_path, &bypassed, options)?;
+        let path = Path::new(file_path);
+        options.record_backup(&[(path, notebook.text(), &new_text)])?;
+        checkpoint = options.record_checkpoint(&[(path, notebook.text(), &new_text)])?;
+        if let Err(e) = write_atomic(path, &new_text) {
+            options.discard_checkpoint(checkpoint.as_deref());
+            return Err(render_error(format, "io", format!("Failed to write file: {}", e)));
+        }
+        options.verify_written(&[(path, notebook.text())], checkpoint.as_deref())?;
+    }
+
+    let diffs: Vec<(usize, &AppliedEdits, String)> = applied.iter()
+        .map(|(cell, result)| {
+            let first_changed_line = result.outcome.first_changed_line.unwrap_or(1);
+            (*cell, result, render_hash_aware_diff(&result.diff, first_changed_line))
+        })
+        .collect();
+    if format == OutputFormat::Json {
+        let cells: Vec<serde_json::Value> = diffs.iter()
+            .map(|(cell, result, diff)| serde_json::json!({
+                "cell": cell,
+                "first_changed_line": result.outcome.first_changed_line.unwrap_or(1),
+                "relocations": result.outcome.relocations,
+                "statuses": result.outcome.statuses,
+                "diff": diff,
+            }))
+            .collect();
+        return Ok(serde_json::json!({
+            "status": if options.dry_run { "dry_run" } else { "applied" },
+            "file": file_path,
+            "checkpoint": checkpoint,
+            "cells": cells,
+        }).to_string());
+    }
+
+    let summary = if options.dry_run {
+        format!("Dry run: edits to {} cell{} validated, nothing written.", applied.len(), plural(applied.len()))
+    } else {
+        format!("Edit applied successfully to {} cell{}.", applied.len(), plural(applied.len()))
+    };
+    let mut output = summary + &checkpoint.map(|id| format!("\nCheckpoint: {}", id)).unwrap_or_default();
+    for (cell, _, diff) in &diffs {
+        output.push_str(&format!("\n\n<diff cell=\"{}\">\n--- {}\n+++ {}\n{}\n</diff>", cell, file_path, file_path, diff));
+    }
+    Ok(output)
+}
+
+/// The edits for each cell, from a `{"<cell>": [...]}` object or, with
+/// `cell` set, a plain edit document for that cell.
+fn notebook_edits(
+    edits_json: &str,
+    cell: Option<usize>,
+) -> Result<BTreeMap<usize, Vec<HashlineEdit>>, (&'static str, String)> {
+    if let Some(cell) = cell {
+        let edits = parse_edits(edits_json).map_err(|e| (e.kind(), e.to_string()))?;
+        return Ok(BTreeMap::from([(cell, edits)]));
+    }
+    let unkeyed = || (
+        "invalid_edits",
+        "Notebook edits must name their cell: key them by cell index, e.g. {\"2\": [...]}, or pass --cell N".to_string(),
+    );
+    let documents: BTreeMap<String, serde_json::Value> = serde_json::from_str(edits_json).map_err(|_| unkeyed())?;
+    documents.into_iter()
+        .map(|(key, document)| {
+            let cell = key.parse::<usize>().map_err(|_| unkeyed())?;
+            let edits = edits_from_value(document).map_err(|e| (e.kind(), format!("Cell {}: {}", cell, e)))?;
+            Ok((cell, edits))
+        })
+        .collect()
+}
+
+/// Tag a rendered error with the cell it came from.
+fn cell_error(cell: usize, rendered: &str, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Text => format!("Cell {}: {}", cell, rendered),
+        OutputFormat::Json => {
+            let mut value: serde_json::Value = serde_json::from_str(rendered).expect("errors render as JSON");
+            value["cell"] = cell.into();
+            value.to_string()
+        }
+    }
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 { "" } else { "s" }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::checkpoint::{load_checkpoints, Checkpoint};
+use crate::{checkpoint_dir, content_fingerprint, render_error, unified_diff, HashlineError, Operation, OutputFormat, Policy};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Patch Series Export
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// One patch of an exported series.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportedPatch {
+    /// The checkpoint the patch was made from.
+    pub checkpoint: String,
+    /// Where the patch was written.
+    pub path: PathBuf,
+    pub subject: String,
+}
+
+/// Write one `git format-patch` style patch per checkpointed edit batch in
+/// `journal_dir`, from checkpoint `from` on (every stored one when `None`),
+/// into `out_dir`. File paths in the patches are relative to `root`, so
+/// `git am` run there applies the series commit by commit.
+///
+/// Checkpoints keep each batch's previous content, so a batch's result is
+/// the previous content saved by the next batch to touch the file, or the
+/// file itself for the last one. A file changed by anything else in
+/// between makes that result unknown, and the export fails. So does a
+/// patch path `policy` does not allow writing, before anything is written.
+pub fn export_patch_series(journal_dir: &Path, from: Option<&str>, root: &Path, out_dir: &Path, policy: &Policy) -> Result<Vec<ExportedPatch>, HashlineError> {
+    let patches = patch_series(journal_dir, from, root, out_dir).map_err(HashlineError::Export)?;
+    for (patch, _) in &patches {
+        policy.check(Operation::Write, &patch.path, false)?;
+    }
+    fs::create_dir_all(out_dir)
+        .map_err(|e| HashlineError::Export(format!("Failed to create {}: {}", out_dir.display(), e)))?;
+    patches.into_iter().map(|(patch, text)| {
+        fs::write(&patch.path, text)
+            .map_err(|e| HashlineError::Export(format!("Failed to write {}: {}", patch.path.display(), e)))?;
+        Ok(patch)
+    }).collect()
+}
+
+/// The patches of the series [`export_patch_series`] writes, with their text.
+fn patch_series(journal_dir: &Path, from: Option<&str>, root: &Path, out_dir: &Path) -> Result<Vec<(ExportedPatch, String)>, String> {
+    let checkpoints = load_checkpoints(&checkpoint_dir(journal_dir), from)?;
+    if checkpoints.is_empty() {
+        return Err("No checkpoints to export".to_string());
+    }
+    let root = std::path::absolute(root).map_err(|e| format!("Failed to resolve {}: {}", root.display(), e))?;
+
+    // Walk back from the newest batch: each file's content after a batch is
+    // what it was before the next batch touching it, or what it is now
+    let mut after: HashMap<PathBuf, String> = HashMap::new();
+    let mut batches = Vec::with_capacity(checkpoints.len());
+    for checkpoint in checkpoints.iter().rev() {
+        let mut files = Vec::new();
+        for file in &checkpoint.files {
+            let new = match after.remove(&file.path) {
+                Some(content) => content,
+                None => fs::read_to_string(&file.path)
+                    .map_err(|e| format!("Failed to read {}: {}", file.path.display(), e))?,
+            };
+            if content_fingerprint(&new) != file.after_fingerprint {
+                return Err(format!(
+                    "{} was changed outside checkpoint {}, so the batch's result is unknown",
+                    file.path.display(), checkpoint.id,
+                ));
+            }
+            let old = fs::read_to_string(&file.backup)
+                .map_err(|e| format!("Failed to read backup for {}: {}", file.path.display(), e))?;
+            let relative = file.path.strip_prefix(&root)
+                .map_err(|_| format!("{} is outside {}", file.path.display(), root.display()))?;
+            let relative = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+            after.insert(file.path.clone(), old.clone());
+            files.push((relative, old, new));
+        }
+        batches.push((checkpoint, files));
+    }
+    batches.reverse();
+
+    let total = batches.len();
+    Ok(batches.into_iter().enumerate().map(|(i, (checkpoint, files))| {
+        let names: Vec<&str> = files.iter().map(|(name, _, _)| name.as_str()).collect();
+        let subject = format!("Edit {}", names.join(", "));
+        let path = out_dir.join(format!("{:04}-{}.patch", i + 1, slug(&subject)));
+        let patch = format_patch(checkpoint, &subject, i + 1, total, &files);
+        (ExportedPatch { checkpoint: checkpoint.id.clone(), path, subject }, patch)
+    }).collect())
+}
+
+fn format_patch(checkpoint: &Checkpoint, subject: &str, number: usize, total: usize, files: &[(String, String, String)]) -> String {
+    let mut patch = format!(
+        "From 0000000000000000000000000000000000000000 Mon Sep 17 00:00:00 2001\n\
+         From: hashline-tools <hashline-tools@localhost>\n\
+         Date: {}\n\
+         Subject: [PATCH {}/{}] {}\n\n\
+         Edit batch recorded as checkpoint {}.\n---\n",
+        rfc2822_date(checkpoint.created()), number, total, subject, checkpoint.id,
+    );
+    for (name, old, new) in files {
+        let (a, b) = (format!("a/{}", name), format!("b/{}", name));
+        patch.push_str(&format!("\ndiff --git {} {}\n", a, b));
+        patch.push_str(&unified_diff(old, new, &a, &b));
+    }
+    patch.push_str("-- \nhashline-tools\n\n");
+    patch
+}
+
+/// `subject` as `git format-patch` names files: runs of anything but ASCII
+/// letters, digits, `.` and `_` become one `-`, up to 52 characters.
+fn slug(subject: &str) -> String {
+    let mut slug = String::new();
+    for c in subject.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.truncate(52);
+    slug.trim_end_matches(['-', '.']).to_string()
+}
+
+/// `secs` since the Unix epoch as an RFC 2822 date in UTC.
+fn rfc2822_date(secs: u64) -> String {
+    const DAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let days = secs / 86_400;
+    let time = secs % 86_400;
+    let (year, month, day) = civil_date(days);
+    format!(
+        "{}, {} {} {} {:02}:{:02}:{:02} +0000",
+        DAYS[(days % 7) as usize], day, MONTHS[(month - 1) as usize], year, time / 3_600, time / 60 % 60, time % 60,
+    )
+}
+
+/// The year, month and day `days` after 1970-01-01 (Howard Hinnant's
+/// algorithm).
+pub(crate) fn civil_date(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + u64::from(month <= 2), month, day)
+}
+
+/// Export checkpointed edit batches as a patch series and list the patches.
+pub fn cmd_export_series(journal_dir: &Path, from: Option<&str>, root: &Path, out_dir: &Path, policy: &Policy, format: OutputFormat) -> Result<String, String> {
+    let patches = export_patch_series(journal_dir, from, root, out_dir, policy)
+        .map_err(|e| render_error(format, e.kind(), e.to_string()))?;
+    if format == OutputFormat::Json {
+        return Ok(serde_json::json!({ "status": "exported", "patches": patches }).to_string());
+    }
+    let mut output = format!("Exported {} patch{}:", patches.len(), if patches.len() == 1 { "" } else { "es" });
+    for patch in &patches {
+        output.push_str(&format!("\n  - {}", patch.path.display()));
+    }
+    Ok(output)
+}
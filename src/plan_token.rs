@@ -0,0 +1,182 @@
+use std::fs;
+use std::path::Path;
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::audit::AuditedCommand;
+use crate::{
+    apply_hashline_cmd, apply_hashline_edits_with_options, hash_aware_diff_with, parse_edits,
+    render_apply_error, render_error, render_hash_aware_diff, ApplyOptions, EditOptions, HashedFile, HashlineEdit,
+    HashlineError, Operation, OutputFormat,
+};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Two-phase Plan / Commit
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Environment variable holding the key plan tokens are signed with.
+pub const PLAN_KEY_ENV_VAR: &str = "HASHLINE_PLAN_KEY";
+
+/// A validated edit batch, bound to the exact file content it was checked against.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanToken {
+    pub file: String,
+    /// [`content_fingerprint`] of the file when the plan was made.
+    pub fingerprint: String,
+    pub edits: Vec<HashlineEdit>,
+    pub options: ApplyOptions,
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+impl PlanToken {
+    /// Encode as `<hex payload>.<hex signature>`, signed with `key`.
+    pub fn encode(&self, key: &[u8]) -> String {
+        let payload = serde_json::to_vec(self).expect("plan token serializes");
+        format!("{}.{}", to_hex(&payload), to_hex(&sign(key, &payload)))
+    }
+
+    /// Decode a token produced by [`PlanToken::encode`], rejecting it unless
+    /// the signature matches `key`.
+    pub fn decode(token: &str, key: &[u8]) -> Result<PlanToken, HashlineError> {
+        let malformed = || HashlineError::InvalidToken("Malformed plan token".to_string());
+        let (payload_hex, signature_hex) = token.trim().split_once('.').ok_or_else(malformed)?;
+        let payload = from_hex(payload_hex).ok_or_else(malformed)?;
+        let signature = from_hex(signature_hex).ok_or_else(malformed)?;
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+        mac.update(&payload);
+        mac.verify_slice(&signature).map_err(|_| HashlineError::InvalidToken(
+            "Plan token signature does not match; it was altered or signed with another key".to_string(),
+        ))?;
+        serde_json::from_slice(&payload)
+            .map_err(|e| HashlineError::InvalidToken(format!("Malformed plan token: {}", e)))
+    }
+}
+
+/// SHA-256 of a file's content, hex encoded.
+pub fn content_fingerprint(content: &str) -> String {
+    to_hex(&Sha256::digest(content.as_bytes()))
+}
+
+/// The signing key from `HASHLINE_PLAN_KEY`. Anyone could sign a token with
+/// a key everyone knows, so without one no token is issued or accepted.
+fn plan_key(format: OutputFormat) -> Result<Vec<u8>, String> {
+    std::env::var(PLAN_KEY_ENV_VAR).ok()
+        .filter(|key| !key.is_empty())
+        .map(String::into_bytes)
+        .ok_or_else(|| render_error(
+            format,
+            "missing_plan_key",
+            format!("{} is not set; plan tokens need a signing key", PLAN_KEY_ENV_VAR),
+        ))
+}
+
+fn sign(key: &[u8], payload: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(payload);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Validate edits against a file and return a signed token for [`cmd_commit`].
+/// Nothing is written.
+pub fn cmd_plan(file_path: &str, edits_json: &str, options: &EditOptions) -> Result<String, String> {
+    let result = plan(file_path, edits_json, options);
+    let plan = AuditedCommand { event: "plan", files: vec![file_path], payload: Some(edits_json) };
+    plan.audit(options.audit_log.as_deref(), options.format, result)
+}
+
+fn plan(file_path: &str, edits_json: &str, options: &EditOptions) -> Result<String, String> {
+    let format = options.format;
+    let key = plan_key(format)?;
+    options.policy.check(Operation::Edit, Path::new(file_path), options.apply.force)
+        .map_err(|e| render_error(format, "policy_violation", e.to_string()))?;
+    options.check_memory_budget([Path::new(file_path)])?;
+    options.check_file_limits([Path::new(file_path)])?;
+    let content = fs::read_to_string(file_path)
+        .map_err(|e| render_error(format, "io", format!("Failed to read file: {}", e)))?;
+    let edits = parse_edits(edits_json)
+        .map_err(|e| render_error(format, e.kind(), e.to_string()))?;
+
+    let apply = options.apply_options_for(Path::new(file_path)).map_err(|e| render_error(format, "io", e))?;
+    let outcome = apply_hashline_edits_with_options(&content, &edits, &apply)
+        .map_err(|e| render_apply_error(&e, format))?;
+    let first_changed_line = outcome.first_changed_line.unwrap_or(1);
+    let new_file = HashedFile::from_content_with_mode(&outcome.content, apply.anchor_mode);
+    let token = PlanToken {
+        file: file_path.to_string(),
+        fingerprint: content_fingerprint(&content),
+        edits,
+        options: apply.into_owned(),
+    }.encode(&key);
+    let diff = render_hash_aware_diff(&hash_aware_diff_with(&content, &new_file, first_changed_line), first_changed_line);
+
+    Ok(match format {
+        OutputFormat::Json => serde_json::json!({
+            "status": "planned",
+            "file": file_path,
+            "token": token,
+            "relocations": outcome.relocations,
+            "statuses": outcome.statuses,
+            "diff": diff,
+        }).to_string(),
+        OutputFormat::Text => format!(
+            "Plan validated; commit it with `hashline-tools commit --token <token>`.\n\n<token>\n{}\n</token>\n\n<diff>\n--- {}\n+++ {}\n{}\n</diff>",
+            token, file_path, file_path, diff
+        ),
+    })
+}
+
+/// Apply a plan made by [`cmd_plan`], provided the file is byte-for-byte
+/// what the plan was validated against. The edits are validated again as
+/// they are applied, so a token can never write what `edit` would refuse.
+pub fn cmd_commit(token: &str, options: &EditOptions) -> Result<String, String> {
+    let result = commit(token, options);
+    let file = plan_key(options.format).ok()
+        .and_then(|key| PlanToken::decode(token, &key).ok())
+        .map(|plan| plan.file);
+    let commit = AuditedCommand { event: "commit", files: file.iter().map(String::as_str).collect(), payload: Some(token) };
+    commit.audit(options.audit_log.as_deref(), options.format, result)
+}
+
+fn commit(token: &str, options: &EditOptions) -> Result<String, String> {
+    let format = options.format;
+    let plan = PlanToken::decode(token, &plan_key(format)?)
+        .map_err(|e| render_error(format, "invalid_token", e.to_string()))?;
+    // Forcing and limits are the committer's call, whatever the token says
+    let force = options.apply.force;
+    options.policy.check(Operation::Edit, Path::new(&plan.file), force)
+        .map_err(|e| render_error(format, "policy_violation", e.to_string()))?;
+    options.check_verify_policy()?;
+    options.check_memory_budget([Path::new(&plan.file)])?;
+    options.check_file_limits([Path::new(&plan.file)])?;
+    let content = fs::read_to_string(&plan.file)
+        .map_err(|e| render_error(format, "io", format!("Failed to read file: {}", e)))?;
+    if content_fingerprint(&content) != plan.fingerprint {
+        return Err(render_error(
+            format,
+            "stale_plan",
+            format!("{} has changed since the plan was made; plan the edits again", plan.file),
+        ));
+    }
+
+    // The plan already carries the editorconfig it was validated with
+    let apply = ApplyOptions { force, limits: options.apply.limits, ..plan.options.clone() };
+    let options = EditOptions { apply, editorconfig: false, ..options.clone() };
+    apply_hashline_cmd(&content, &plan.file, &plan.edits, &options)
+}
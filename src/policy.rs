@@ -0,0 +1,176 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::HashlineError;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Operator Policy
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Environment variable naming the JSON policy file.
+pub const POLICY_ENV_VAR: &str = "HASHLINE_POLICY";
+
+/// Guardrails set by whoever deploys the tool, as opposed to the agent calling it.
+///
+/// Loaded from the JSON file named by `HASHLINE_POLICY`:
+///
+/// ```json
+/// { "forbid_force": true, "allowed_paths": ["src/**", "tests/**"] }
+/// ```
+///
+/// Relative globs are resolved against the policy file's directory. With no
+/// policy file everything is allowed.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Policy {
+    /// Refuse `--force`.
+    pub forbid_force: bool,
+    /// Refuse whole-file writes.
+    pub forbid_write: bool,
+    /// Refuse file deletion.
+    pub forbid_delete: bool,
+    /// Refuse `--verify-cmd`, which runs a shell command.
+    pub forbid_verify_cmd: bool,
+    /// Globs a target path must match. Empty allows any path.
+    pub allowed_paths: Vec<String>,
+    /// Directory relative globs are resolved against.
+    #[serde(skip)]
+    pub base_dir: Option<PathBuf>,
+    /// Further policies that must allow an operation too, such as those of
+    /// `.hashlinerc` files.
+    #[serde(skip)]
+    pub also: Vec<Policy>,
+}
+
+/// The kind of change a command is about to make.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    /// Anchored line edits.
+    Edit,
+    /// Replacing a file's whole content.
+    Write,
+    /// Removing a file.
+    Delete,
+}
+
+/// A request refused by the operator policy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyViolation(pub String);
+
+impl std::fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Policy violation: {}", self.0)
+    }
+}
+
+impl std::error::Error for PolicyViolation {}
+
+impl Policy {
+    /// Load the policy named by `HASHLINE_POLICY`, or the permissive default
+    /// when the variable is unset.
+    pub fn from_env() -> Result<Policy, HashlineError> {
+        match std::env::var_os(POLICY_ENV_VAR) {
+            Some(path) if !path.is_empty() => Policy::load(Path::new(&path)),
+            _ => Ok(Policy::default()),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Policy, HashlineError> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| HashlineError::InvalidPolicy(format!("Failed to read policy {}: {}", path.display(), e)))?;
+        let policy: Policy = serde_json::from_str(&text)
+            .map_err(|e| HashlineError::InvalidPolicy(format!("Invalid policy {}: {}", path.display(), e)))?;
+        policy.located(path)
+    }
+
+    /// Check the globs of a policy read from the file at `path`, and resolve
+    /// them against its directory.
+    pub(crate) fn located(mut self, path: &Path) -> Result<Policy, HashlineError> {
+        for pattern in &self.allowed_paths {
+            glob::Pattern::new(pattern)
+                .map_err(|e| HashlineError::InvalidPolicy(
+                    format!("Invalid glob {:?} in policy {}: {}", pattern, path.display(), e)
+                ))?;
+        }
+        self.base_dir = path.parent().map(absolute_path);
+        Ok(self)
+    }
+
+    /// This policy, further restricted by `other`.
+    pub fn and(mut self, other: Policy) -> Policy {
+        self.also.push(other);
+        self
+    }
+
+    /// Check that `operation` on `path` is allowed, with or without force.
+    pub fn check(&self, operation: Operation, path: &Path, force: bool) -> Result<(), PolicyViolation> {
+        if force && self.forbid_force {
+            return Err(PolicyViolation("--force is disabled by policy".to_string()));
+        }
+        match operation {
+            Operation::Write if self.forbid_write => {
+                return Err(PolicyViolation("whole-file writes are disabled by policy".to_string()));
+            }
+            Operation::Delete if self.forbid_delete => {
+                return Err(PolicyViolation("deleting files is disabled by policy".to_string()));
+            }
+            _ => {}
+        }
+        if !self.allows_path(path) {
+            return Err(PolicyViolation(format!(
+                "{} is outside the allowed paths ({})",
+                path.display(),
+                self.allowed_paths.join(", ")
+            )));
+        }
+        self.also.iter().try_for_each(|policy| policy.check(operation, path, force))
+    }
+
+    /// Check that running a `--verify-cmd` shell command is allowed.
+    pub fn check_verify_cmd(&self) -> Result<(), PolicyViolation> {
+        if self.forbid_verify_cmd {
+            return Err(PolicyViolation("--verify-cmd is disabled by policy".to_string()));
+        }
+        self.also.iter().try_for_each(Policy::check_verify_cmd)
+    }
+
+    fn allows_path(&self, path: &Path) -> bool {
+        if self.allowed_paths.is_empty() {
+            return true;
+        }
+        let target = resolve_target(path);
+        let base = self.base_dir.clone().unwrap_or_else(|| absolute_path(Path::new(".")));
+        self.allowed_paths.iter().any(|pattern| {
+            let full = if Path::new(pattern).is_absolute() {
+                pattern.clone()
+            } else {
+                base.join(pattern).to_string_lossy().into_owned()
+            };
+            glob::Pattern::new(&full).is_ok_and(|p| p.matches_path(&target))
+        })
+    }
+}
+
+/// Absolute path with `.`/`..` and symlinks resolved, so that `../` tricks
+/// cannot escape the allowed globs. Files that do not exist yet are resolved
+/// through their parent directory.
+fn resolve_target(path: &Path) -> PathBuf {
+    if let Ok(real) = fs::canonicalize(path) {
+        return real;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => {
+            let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+            resolve_target(parent).join(name)
+        }
+        _ => absolute_path(path),
+    }
+}
+
+fn absolute_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path)
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,114 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::config::value_name;
+use crate::{AnchorMode, Policy};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Project Configuration
+// ═══════════════════════════════════════════════════════════════════════════
+
+pub const PROJECT_CONFIG_FILE_NAME: &str = ".hashlinerc";
+
+/// One `.hashlinerc` file, in TOML:
+///
+/// ```toml
+/// anchors = "scoped"
+/// ignore = ["generated/", "*.min.js"]
+///
+/// [policy]
+/// forbid_force = true
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ProjectConfigFile {
+    /// Stop looking further up.
+    root: bool,
+    anchors: Option<AnchorMode>,
+    editorconfig: Option<bool>,
+    ignore: Vec<String>,
+    policy: Option<Policy>,
+}
+
+impl ProjectConfigFile {
+    /// The file in `dir`, if there is one.
+    fn load(dir: &Path) -> Result<Option<ProjectConfigFile>, String> {
+        let path = dir.join(PROJECT_CONFIG_FILE_NAME);
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        let invalid = |e: String| format!("Invalid {}: {}", path.display(), e);
+        let mut file: ProjectConfigFile = toml::from_str(&text).map_err(|e| invalid(e.to_string()))?;
+        if let Some(policy) = file.policy.take() {
+            file.policy = Some(policy.located(&path).map_err(|e| e.to_string())?);
+        }
+        Ok(Some(file))
+    }
+}
+
+/// What the `.hashlinerc` files above a path pin for its project, so every
+/// collaborator's agent works on it the same way.
+#[derive(Debug, Clone, Default)]
+pub struct ProjectConfig {
+    /// The files read, nearest first.
+    pub files: Vec<PathBuf>,
+    /// How line hashes are derived, from the nearest file setting it.
+    pub anchors: Option<AnchorMode>,
+    /// Whether `.editorconfig` files are followed, from the nearest file
+    /// setting it.
+    pub editorconfig: Option<bool>,
+    /// The restrictions of every file's `[policy]` table, all of which apply.
+    pub policy: Policy,
+}
+
+impl ProjectConfig {
+    /// The settings of the `.hashlinerc` files in `path`'s directory (or in
+    /// `path`, for a directory) and those above it, up to the first that
+    /// declares `root = true`. Nearer files override farther ones.
+    pub fn for_path(path: impl AsRef<Path>) -> Result<ProjectConfig, String> {
+        let path = path.as_ref();
+        let path = std::path::absolute(path).map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))?;
+        let start = if path.is_dir() { path.as_path() } else { path.parent().unwrap_or(&path) };
+        let mut config = ProjectConfig::default();
+        for dir in start.ancestors() {
+            let Some(file) = ProjectConfigFile::load(dir)? else {
+                continue;
+            };
+            config.files.push(dir.join(PROJECT_CONFIG_FILE_NAME));
+            config.anchors = config.anchors.or(file.anchors);
+            config.editorconfig = config.editorconfig.or(file.editorconfig);
+            if let Some(policy) = file.policy {
+                config.policy = config.policy.and(policy);
+            }
+            if file.root {
+                break;
+            }
+        }
+        Ok(config)
+    }
+
+    /// The anchor mode to use when `anchors` was asked for: the pinned one,
+    /// if any. Asking for another one on the command line is an error.
+    pub fn pin_anchors(&self, anchors: AnchorMode, from_command_line: bool) -> Result<AnchorMode, String> {
+        match self.anchors {
+            Some(pinned) if from_command_line && pinned != anchors => Err(format!(
+                "--anchors {} conflicts with anchors = \"{}\" pinned by {}",
+                value_name(anchors), value_name(pinned), PROJECT_CONFIG_FILE_NAME,
+            )),
+            Some(pinned) => Ok(pinned),
+            None => Ok(anchors),
+        }
+    }
+}
+
+/// The `ignore` globs of the `.hashlinerc` file in `dir`, which `search`
+/// follows like `.gitignore` rules. A missing or invalid file has none.
+pub(crate) fn project_ignore_rules(dir: &Path) -> Option<String> {
+    let file = ProjectConfigFile::load(dir).ok()??;
+    Some(file.ignore.join("\n"))
+}
@@ -0,0 +1,118 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+use crate::config::value_name;
+#[cfg(feature = "tree-sitter")]
+use crate::Grammar;
+use crate::{AnchorMode, AnchorRef};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Read Continuation Tokens
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Where a truncated `read` ended, so `read --continue` resumes right after
+/// it. Encoded as `LINE#HASH.MODE.PREFIX`, e.g. `2000#VK.c.3f9a1b2c4d5e6f70`:
+/// the anchor of the last line shown, the anchor mode (`c`hained, `s`coped,
+/// or syn`t`ax, followed by `r` or `p` for a Rust or Python grammar) and a fingerprint of every byte up to the end of that line,
+/// which any change above the resume point alters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadToken {
+    /// The last line shown.
+    pub anchor: AnchorRef,
+    pub anchor_mode: AnchorMode,
+    /// The first 16 hex digits of the SHA-256 of the file up to the end of
+    /// the last line shown.
+    pub prefix: String,
+}
+
+impl ReadToken {
+    /// The token for a read of `path` that ended at `anchor`.
+    pub(crate) fn issue(path: &Path, anchor: AnchorRef, anchor_mode: AnchorMode) -> io::Result<ReadToken> {
+        let prefix = prefix_fingerprint(path, anchor.line)?.unwrap_or_default();
+        Ok(ReadToken { anchor, anchor_mode, prefix })
+    }
+
+    /// Fail unless `path` is unchanged up to the end of the token's line and
+    /// is read in the same anchor mode, so the resumed read neither skips
+    /// nor repeats lines.
+    pub(crate) fn check(&self, path: &Path, anchor_mode: AnchorMode) -> Result<(), String> {
+        if anchor_mode != self.anchor_mode {
+            return Err(format!(
+                "Continuation token {} is for {} anchors; read with --anchors {}",
+                self, value_name(self.anchor_mode), value_name(self.anchor_mode),
+            ));
+        }
+        let prefix = prefix_fingerprint(path, self.anchor.line).map_err(|e| format!("Failed to read file: {}", e))?;
+        if prefix.as_deref() != Some(self.prefix.as_str()) {
+            return Err(format!(
+                "{} changed at or above line {} since the read that issued continuation token {}; read it again",
+                path.display(), self.anchor.line, self,
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for ReadToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mode = match self.anchor_mode {
+            AnchorMode::Chained => "c",
+            AnchorMode::Scoped => "s",
+            #[cfg(feature = "tree-sitter")]
+            AnchorMode::Syntax(None) => "t",
+            #[cfg(feature = "tree-sitter")]
+            AnchorMode::Syntax(Some(Grammar::Rust)) => "tr",
+            #[cfg(feature = "tree-sitter")]
+            AnchorMode::Syntax(Some(Grammar::Python)) => "tp",
+        };
+        write!(f, "{}.{}.{}", self.anchor, mode, self.prefix)
+    }
+}
+
+impl std::str::FromStr for ReadToken {
+    type Err = String;
+
+    fn from_str(token: &str) -> Result<Self, Self::Err> {
+        let malformed = || format!("Malformed continuation token {:?}", token);
+        let mut parts = token.trim().split('.');
+        let (Some(anchor), Some(mode), Some(prefix), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+            return Err(malformed());
+        };
+        let anchor: AnchorRef = anchor.parse().map_err(|_| malformed())?;
+        let anchor_mode = match mode {
+            "c" => AnchorMode::Chained,
+            "s" => AnchorMode::Scoped,
+            #[cfg(feature = "tree-sitter")]
+            "t" => AnchorMode::Syntax(None),
+            #[cfg(feature = "tree-sitter")]
+            "tr" => AnchorMode::Syntax(Some(Grammar::Rust)),
+            #[cfg(feature = "tree-sitter")]
+            "tp" => AnchorMode::Syntax(Some(Grammar::Python)),
+            _ => return Err(malformed()),
+        };
+        if anchor.line == 0 || prefix.len() != 16 || !prefix.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(malformed());
+        }
+        Ok(ReadToken { anchor, anchor_mode, prefix: prefix.to_ascii_lowercase() })
+    }
+}
+
+/// The first 16 hex digits of the SHA-256 of `path`'s first `lines` lines,
+/// with their line endings, or `None` if it has fewer.
+fn prefix_fingerprint(path: &Path, lines: usize) -> io::Result<Option<String>> {
+    let mut reader = BufReader::new(fs::File::open(path)?);
+    let mut hasher = Sha256::new();
+    let mut line = Vec::new();
+    for _ in 0..lines {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(None);
+        }
+        hasher.update(&line);
+    }
+    let digest = hasher.finalize();
+    Ok(Some(digest[..8].iter().map(|b| format!("{:02x}", b)).collect()))
+}
@@ -0,0 +1,87 @@
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::sync::Arc;
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Interactive Review
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// What the reviewer of `edit --interactive` answered for one pending edit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewDecision {
+    /// Apply this edit.
+    Accept,
+    /// Leave this edit out.
+    Reject,
+    /// Apply this edit and every one after it without asking.
+    AcceptAll,
+    /// Leave this edit and every one after it out.
+    Quit,
+}
+
+/// One edit of a batch waiting for the reviewer's decision.
+#[derive(Debug)]
+pub struct PendingEdit<'a> {
+    pub file: &'a str,
+    /// Index of the edit in the batch.
+    pub index: usize,
+    /// Number of edits in the batch.
+    pub total: usize,
+    /// Hash-aware diff of the edit applied on its own.
+    pub diff: &'a str,
+}
+
+type Review = dyn Fn(&PendingEdit) -> ReviewDecision + Send + Sync;
+
+/// Decides, edit by edit, which edits of a batch are written.
+///
+/// ```
+/// # use hashline_tools::{EditReviewer, ReviewDecision};
+/// // Keep only the first edit of every batch
+/// let reviewer = EditReviewer::new(|edit| {
+///     if edit.index == 0 { ReviewDecision::Accept } else { ReviewDecision::Quit }
+/// });
+/// ```
+#[derive(Clone)]
+pub struct EditReviewer(Arc<Review>);
+
+impl EditReviewer {
+    pub fn new(review: impl Fn(&PendingEdit) -> ReviewDecision + Send + Sync + 'static) -> Self {
+        EditReviewer(Arc::new(review))
+    }
+
+    /// Show each edit on stderr and read the answer from stdin. End of input
+    /// counts as quitting.
+    pub fn terminal() -> Self {
+        EditReviewer::new(|edit| {
+            let mut stderr = io::stderr().lock();
+            let _ = writeln!(stderr, "\nedits[{}] of {} in {}:\n{}", edit.index, edit.total, edit.file, edit.diff);
+            loop {
+                let _ = write!(stderr, "Apply this edit? [y]es, [n]o, [a]ll remaining, [q]uit: ");
+                let _ = stderr.flush();
+                let mut answer = String::new();
+                match io::stdin().lock().read_line(&mut answer) {
+                    Ok(0) | Err(_) => return ReviewDecision::Quit,
+                    Ok(_) => {}
+                }
+                match answer.trim().to_ascii_lowercase().as_str() {
+                    "y" | "yes" => return ReviewDecision::Accept,
+                    "n" | "no" => return ReviewDecision::Reject,
+                    "a" | "all" => return ReviewDecision::AcceptAll,
+                    "q" | "quit" => return ReviewDecision::Quit,
+                    _ => {}
+                }
+            }
+        })
+    }
+
+    pub(crate) fn review(&self, edit: &PendingEdit) -> ReviewDecision {
+        (self.0)(edit)
+    }
+}
+
+impl fmt::Debug for EditReviewer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EditReviewer")
+    }
+}
@@ -0,0 +1,302 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use glob::{MatchOptions, Pattern};
+use regex::Regex;
+
+use crate::audit::AuditedCommand;
+use crate::project_config::project_ignore_rules;
+use crate::{read_lines, AnchorMode, CancelToken, LineHasher, CANCELLED_MESSAGE};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Search
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Files whose ignore rules `search` follows, in each directory, besides the
+/// `ignore` globs of `.hashlinerc` files.
+pub const IGNORE_FILE_NAMES: [&str; 2] = [".gitignore", ".ignore"];
+
+/// Options for the `search` command.
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    /// Search subdirectories too, not only the files directly in the directory.
+    pub recursive: bool,
+    /// Stop after this many matching lines.
+    pub max_results: Option<usize>,
+    /// How the printed hashes are derived.
+    pub anchor_mode: AnchorMode,
+    /// Record the search in this audit log.
+    pub audit_log: Option<PathBuf>,
+    /// Stop searching, failing with [`CANCELLED_MESSAGE`], once cancelled.
+    pub cancel: CancelToken,
+}
+
+/// Write every line under `dir` matching the regex `pattern` as
+/// `path:LINE#HASH:text`, as each file is searched, and end with a count.
+/// Returns the number of matches. Files and directories excluded by
+/// `.gitignore`, `.ignore` or `.hashlinerc` files are skipped, as are `.git` directories and
+/// files that are not UTF-8 text. `dir` may also be a single file.
+pub fn cmd_search(out: &mut impl Write, dir: &str, pattern: &str, options: &SearchOptions) -> Result<usize, String> {
+    let result = search(out, dir, pattern, options);
+    let search = AuditedCommand { event: "search", files: vec![dir], payload: Some(pattern) };
+    search.audit(options.audit_log.as_deref(), crate::OutputFormat::Text, result)
+}
+
+fn search(out: &mut impl Write, dir: &str, pattern: &str, options: &SearchOptions) -> Result<usize, String> {
+    let regex = Regex::new(pattern).map_err(|e| format!("Invalid pattern: {}", e))?;
+    let root = Path::new(dir);
+    let absolute = fs::canonicalize(root).map_err(|e| format!("Failed to read {}: {}", dir, e))?;
+    let mut search = Search { out, regex, options, matches: 0, files: 0 };
+    let mut walk = FileWalk::new(&absolute, options.recursive, &options.cancel);
+    let walked = walk.walk(root, &absolute, &mut |path| search.search_file(path).map(drop));
+    let write_error = |e: io::Error| format!("Failed to write output: {}", e);
+    match walked {
+        Ok(()) | Err(Stop::Limit) => {}
+        Err(Stop::Cancelled) => return Err(CANCELLED_MESSAGE.to_string()),
+        Err(Stop::Io(e)) => return Err(write_error(e)),
+    }
+    let (matches, files) = (search.matches, search.files);
+    let summary = if options.max_results.is_some_and(|max| matches >= max) {
+        format!("(Stopped after {} matches. Raise --max-results to see more)", matches)
+    } else {
+        format!("({} match{} in {} file{})", matches, if matches == 1 { "" } else { "es" }, files, if files == 1 { "" } else { "s" })
+    };
+    let separator = if matches > 0 { "\n" } else { "" };
+    write!(search.out, "{}{}", separator, summary).map_err(write_error)?;
+    Ok(matches)
+}
+
+/// Why a walk ended early.
+pub(crate) enum Stop {
+    /// `max_results` was reached.
+    Limit,
+    /// The search was cancelled.
+    Cancelled,
+    /// Writing the results failed.
+    Io(io::Error),
+}
+
+impl From<io::Error> for Stop {
+    fn from(e: io::Error) -> Self {
+        Stop::Io(e)
+    }
+}
+
+struct Search<'a, W> {
+    out: &'a mut W,
+    regex: Regex,
+    options: &'a SearchOptions,
+    matches: usize,
+    /// Files with at least one match.
+    files: usize,
+}
+
+impl<W: Write> Search<'_, W> {
+    /// Print the matching lines of one file. Unreadable and binary files
+    /// have none.
+    fn search_file(&mut self, path: &Path) -> Result<usize, Stop> {
+        let Ok(file) = fs::File::open(path) else {
+            return Ok(0);
+        };
+        let mut reader = BufReader::new(file);
+        if reader.fill_buf().map_or(true, |head| head.contains(&0)) {
+            return Ok(0);
+        }
+        let mode = self.options.anchor_mode.for_path(path);
+        // Syntax anchors parse the whole file before the first line is hashed
+        let (mut hasher, reader): (_, Box<dyn BufRead>) = if mode.hashes_whole_file() {
+            let mut content = String::new();
+            if reader.read_to_string(&mut content).is_err() {
+                return Ok(0);
+            }
+            (LineHasher::for_content(mode, &content), Box::new(io::Cursor::new(content)))
+        } else {
+            (LineHasher::new(mode), Box::new(reader))
+        };
+        let mut found = 0;
+        for (i, line) in read_lines(reader).enumerate() {
+            if self.options.cancel.is_cancelled() {
+                return Err(Stop::Cancelled);
+            }
+            // Invalid UTF-8 ends the search of the file, like a binary one
+            let Ok(line) = line else {
+                break;
+            };
+            let hash = hasher.hash(i + 1, &line);
+            if !self.regex.is_match(&line) {
+                continue;
+            }
+            writeln!(self.out, "{}:{}#{}:{}", path.display(), i + 1, hash, line)?;
+            found += 1;
+            self.matches += 1;
+            if found == 1 {
+                self.files += 1;
+            }
+            if self.options.max_results.is_some_and(|max| self.matches >= max) {
+                return Err(Stop::Limit);
+            }
+        }
+        Ok(found)
+    }
+}
+
+/// A walk over the files under a directory that no `.gitignore`, `.ignore`
+/// or `.hashlinerc` file excludes, in name order. `.git` directories are
+/// skipped.
+pub(crate) struct FileWalk<'a> {
+    recursive: bool,
+    cancel: &'a CancelToken,
+    /// The ignore files in force, outermost first.
+    ignores: Vec<IgnoreFile>,
+}
+
+impl<'a> FileWalk<'a> {
+    /// A walk starting at `absolute`, a canonical path, following the ignore
+    /// files above it too. Without `recursive` only the files directly in
+    /// the directory are visited.
+    pub(crate) fn new(absolute: &Path, recursive: bool, cancel: &'a CancelToken) -> Self {
+        FileWalk { recursive, cancel, ignores: ancestor_ignores(absolute) }
+    }
+
+    /// Call `visit` with each file under `shown`, located at `absolute`, as
+    /// a path below `shown`. `shown` may also be a single file.
+    pub(crate) fn walk(&mut self, shown: &Path, absolute: &Path, visit: &mut impl FnMut(&Path) -> Result<(), Stop>) -> Result<(), Stop> {
+        if absolute.is_dir() {
+            self.walk_dir(shown, absolute, visit)
+        } else {
+            visit(shown)
+        }
+    }
+
+    fn walk_dir(&mut self, shown: &Path, absolute: &Path, visit: &mut impl FnMut(&Path) -> Result<(), Stop>) -> Result<(), Stop> {
+        let pushed = self.push_ignores(absolute);
+        let Ok(entries) = fs::read_dir(absolute) else {
+            self.ignores.truncate(self.ignores.len() - pushed);
+            return Ok(());
+        };
+        let mut entries: Vec<(String, bool)> = entries
+            .filter_map(Result::ok)
+            .filter_map(|e| Some((e.file_name().into_string().ok()?, e.file_type().ok()?.is_dir())))
+            .collect();
+        entries.sort();
+        let mut result = Ok(());
+        for (name, is_dir) in entries {
+            if self.cancel.is_cancelled() {
+                result = Err(Stop::Cancelled);
+                break;
+            }
+            let path = absolute.join(&name);
+            if (is_dir && name == ".git") || self.is_ignored(&path, is_dir) {
+                continue;
+            }
+            result = if is_dir {
+                if !self.recursive {
+                    continue;
+                }
+                self.walk_dir(&shown.join(&name), &path, visit)
+            } else {
+                visit(&shown.join(&name))
+            };
+            if result.is_err() {
+                break;
+            }
+        }
+        self.ignores.truncate(self.ignores.len() - pushed);
+        result
+    }
+
+    /// Load the ignore files of `dir`. Returns how many were found.
+    fn push_ignores(&mut self, dir: &Path) -> usize {
+        let before = self.ignores.len();
+        self.ignores.extend(IgnoreFile::load_all(dir));
+        self.ignores.len() - before
+    }
+
+    /// Whether the rules in force exclude `path`. The last rule matching it
+    /// decides, so deeper files and later lines override earlier ones.
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.ignores.iter()
+            .flat_map(|file| file.verdicts(path, is_dir))
+            .last()
+            .unwrap_or(false)
+    }
+}
+
+/// The ignore files of the directories above `start`, up to the enclosing
+/// git repository's root, outermost first. Rules there apply to the files
+/// being searched too. Outside a repository there are none.
+fn ancestor_ignores(start: &Path) -> Vec<IgnoreFile> {
+    let ancestors: Vec<&Path> = start.ancestors().skip(1).collect();
+    let Some(root) = start.ancestors().position(|dir| dir.join(".git").exists()) else {
+        return Vec::new();
+    };
+    ancestors[..root].iter()
+        .rev()
+        .flat_map(|dir| IgnoreFile::load_all(dir))
+        .collect()
+}
+
+/// The rules of one `.gitignore` or `.ignore` file, or the `ignore` globs of
+/// a `.hashlinerc` file.
+struct IgnoreFile {
+    /// The directory the file is in, which its paths are relative to.
+    base: PathBuf,
+    rules: Vec<IgnoreRule>,
+}
+
+struct IgnoreRule {
+    pattern: Pattern,
+    /// `!pattern`: re-include what an earlier rule excluded.
+    negated: bool,
+    /// `pattern/`: only match directories.
+    dir_only: bool,
+    /// Patterns with a `/` before their end match the path from `base`;
+    /// others match the name at any depth.
+    anchored: bool,
+}
+
+impl IgnoreFile {
+    /// The ignore files of `dir`, in the order their rules apply.
+    fn load_all(dir: &Path) -> impl Iterator<Item = IgnoreFile> + '_ {
+        IGNORE_FILE_NAMES.iter()
+            .filter_map(|name| fs::read_to_string(dir.join(name)).ok())
+            .chain(project_ignore_rules(dir))
+            .map(|text| IgnoreFile { base: dir.to_path_buf(), rules: text.lines().filter_map(IgnoreRule::parse).collect() })
+    }
+
+    /// For each rule matching `path`, whether it excludes it.
+    fn verdicts<'a>(&'a self, path: &Path, is_dir: bool) -> impl Iterator<Item = bool> + 'a {
+        let relative = path.strip_prefix(&self.base).ok().map(|p| {
+            p.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/")
+        });
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned());
+        let options = MatchOptions { case_sensitive: true, require_literal_separator: true, require_literal_leading_dot: false };
+        self.rules.iter().filter_map(move |rule| {
+            let relative = relative.as_deref()?;
+            let subject = if rule.anchored { relative } else { name.as_deref()? };
+            let matched = (is_dir || !rule.dir_only) && rule.pattern.matches_with(subject, options);
+            matched.then_some(!rule.negated)
+        })
+    }
+}
+
+impl IgnoreRule {
+    fn parse(line: &str) -> Option<IgnoreRule> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let pattern = Pattern::new(line.strip_prefix('/').unwrap_or(line)).ok()?;
+        Some(IgnoreRule { pattern, negated, dir_only, anchored })
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::patch_series::civil_date;
+use crate::{render_error, AppliedEdits, EditOptions, EditStatus, HashlineError, OutputFormat, SkipReason};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Edit Session Statistics
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Environment variable naming the stats file when `--record-stats` is not
+/// given.
+pub const STATS_ENV_VAR: &str = "HASHLINE_STATS";
+
+/// Environment variable naming the session batches are counted under.
+/// Without it, each UTC day is a session.
+pub const SESSION_ENV_VAR: &str = "HASHLINE_SESSION";
+
+/// Where `--record-stats` without a value keeps its stats, relative to the
+/// working directory.
+pub const DEFAULT_STATS_FILE: &str = ".hashline/stats.jsonl";
+
+/// The stats file to use: `flag` if given, else the one `HASHLINE_STATS`
+/// names.
+pub fn stats_file_path(flag: Option<PathBuf>) -> Option<PathBuf> {
+    flag.or_else(|| std::env::var_os(STATS_ENV_VAR).filter(|v| !v.is_empty()).map(PathBuf::from))
+}
+
+/// What happened to one `edit` batch, as recorded in the stats file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BatchStats {
+    pub session: String,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub file: String,
+    /// Edits submitted.
+    pub edits: usize,
+    /// Edits applied.
+    pub applied: usize,
+    /// Stale anchors left unresolved, whether they failed the batch or were
+    /// skipped or written as conflicts.
+    pub mismatches: usize,
+    /// Whether stale anchors were relocated when possible.
+    pub auto_rebase: bool,
+    /// Stale anchors relocated.
+    pub rebased: usize,
+    /// Whether the batch failed as a whole.
+    pub failed: bool,
+}
+
+impl BatchStats {
+    /// The stats of a batch of `edits` edits to `file` that came to `result`.
+    pub(crate) fn new(file: &str, edits: usize, auto_rebase: bool, result: &Result<AppliedEdits, HashlineError>) -> Self {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let session = std::env::var(SESSION_ENV_VAR).ok().filter(|s| !s.is_empty()).unwrap_or_else(|| {
+            let (year, month, day) = civil_date(timestamp / 86_400);
+            format!("{:04}-{:02}-{:02}", year, month, day)
+        });
+        let stats = BatchStats { session, timestamp, file: file.to_string(), edits, auto_rebase, ..BatchStats::default() };
+        match result {
+            Ok(applied) => {
+                let statuses = &applied.outcome.statuses;
+                let stale = |s: &&EditStatus| {
+                    s.is_conflicted() || matches!(s, EditStatus::Skipped { reason: SkipReason::Mismatch, .. })
+                };
+                BatchStats {
+                    applied: statuses.iter().filter(|s| s.is_applied()).count(),
+                    mismatches: statuses.iter().filter(stale).count(),
+                    rebased: applied.outcome.relocations.len(),
+                    ..stats
+                }
+            }
+            Err(HashlineError::Mismatch(e)) => BatchStats { mismatches: e.mismatches.len(), failed: true, ..stats },
+            Err(_) => BatchStats { failed: true, ..stats },
+        }
+    }
+
+    /// Append the stats to the file at `path` as one JSON line, creating it
+    /// and its directory if needed.
+    pub(crate) fn append(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut line = serde_json::to_string(self).map_err(io::Error::other)?;
+        line.push('\n');
+        OpenOptions::new().create(true).append(true).open(path)?.write_all(line.as_bytes())
+    }
+}
+
+/// The batches of one session, or of all of them, added up.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SessionStats {
+    /// The session, or `None` for the total of all sessions.
+    pub session: Option<String>,
+    pub batches: usize,
+    pub edits: usize,
+    pub applied: usize,
+    /// Batches with at least one unresolved stale anchor.
+    pub mismatched_batches: usize,
+    /// Stale anchors relocated, in batches with auto-rebase on.
+    pub rebased: usize,
+    /// Stale anchors seen in batches with auto-rebase on.
+    pub rebase_attempts: usize,
+}
+
+impl SessionStats {
+    fn add(&mut self, batch: &BatchStats) {
+        self.batches += 1;
+        self.edits += batch.edits;
+        self.applied += batch.applied;
+        self.mismatched_batches += usize::from(batch.mismatches > 0);
+        if batch.auto_rebase {
+            self.rebased += batch.rebased;
+            self.rebase_attempts += batch.rebased + batch.mismatches;
+        }
+    }
+
+    /// The share of batches with an unresolved stale anchor.
+    pub fn mismatch_rate(&self) -> f64 {
+        ratio(self.mismatched_batches, self.batches).unwrap_or(0.0)
+    }
+
+    /// The share of stale anchors auto-rebase relocated, if it met any.
+    pub fn rebase_success_rate(&self) -> Option<f64> {
+        ratio(self.rebased, self.rebase_attempts)
+    }
+
+    /// Edits per batch.
+    pub fn average_batch_size(&self) -> f64 {
+        ratio(self.edits, self.batches).unwrap_or(0.0)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).expect("stats serialize");
+        value["mismatch_rate"] = self.mismatch_rate().into();
+        value["rebase_success_rate"] = self.rebase_success_rate().into();
+        value["average_batch_size"] = self.average_batch_size().into();
+        value
+    }
+}
+
+fn ratio(part: usize, whole: usize) -> Option<f64> {
+    (whole > 0).then(|| part as f64 / whole as f64)
+}
+
+/// The batches recorded in the stats file at `path`. A missing file has
+/// none; lines that do not parse, such as one cut short by a crash, are
+/// skipped.
+pub fn load_batch_stats(path: &Path) -> Result<Vec<BatchStats>, String> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to read stats {}: {}", path.display(), e)),
+    }
+}
+
+/// `batches` added up per session, in the order the sessions started.
+pub fn session_stats(batches: &[BatchStats]) -> Vec<SessionStats> {
+    let mut sessions: BTreeMap<&str, (u64, SessionStats)> = BTreeMap::new();
+    for batch in batches {
+        let (started, stats) = sessions.entry(&batch.session).or_insert_with(|| {
+            (batch.timestamp, SessionStats { session: Some(batch.session.clone()), ..SessionStats::default() })
+        });
+        *started = (*started).min(batch.timestamp);
+        stats.add(batch);
+    }
+    let mut sessions: Vec<(u64, SessionStats)> = sessions.into_values().collect();
+    sessions.sort_by_key(|(started, _)| *started);
+    sessions.into_iter().map(|(_, stats)| stats).collect()
+}
+
+/// Summarize the stats file at `path`: the totals, or with `sessions` one
+/// row per session.
+pub fn cmd_stats(path: &Path, sessions: bool, format: OutputFormat) -> Result<String, String> {
+    let batches = load_batch_stats(path).map_err(|e| render_error(format, "io", e))?;
+    let per_session = session_stats(&batches);
+    let mut total = SessionStats::default();
+    batches.iter().for_each(|batch| total.add(batch));
+
+    if format == OutputFormat::Json {
+        let mut value = serde_json::json!({ "status": "ok", "total": total.to_json() });
+        if sessions {
+            value["sessions"] = per_session.iter().map(SessionStats::to_json).collect();
+        }
+        return Ok(value.to_string());
+    }
+    if batches.is_empty() {
+        return Ok(format!("No edit batches recorded in {}", path.display()));
+    }
+    let percent = |rate: Option<f64>| rate.map_or("-".to_string(), |r| format!("{:.0}%", r * 100.0));
+    if sessions {
+        let mut rows = vec![[
+            "SESSION", "BATCHES", "EDITS", "APPLIED", "MISMATCH", "REBASED", "AVG BATCH",
+        ].map(str::to_string)];
+        for s in &per_session {
+            rows.push([
+                s.session.clone().unwrap_or_default(),
+                s.batches.to_string(),
+                s.edits.to_string(),
+                s.applied.to_string(),
+                percent(Some(s.mismatch_rate())),
+                percent(s.rebase_success_rate()),
+                format!("{:.1}", s.average_batch_size()),
+            ]);
+        }
+        let widths: Vec<usize> = (0..7).map(|i| rows.iter().map(|r| r[i].len()).max().unwrap_or(0)).collect();
+        let lines: Vec<String> = rows.iter()
+            .map(|row| row.iter().zip(&widths).map(|(cell, w)| format!("{:<w$}", cell, w = w)).collect::<Vec<_>>().join("  ").trim_end().to_string())
+            .collect();
+        return Ok(lines.join("\n"));
+    }
+    Ok(format!(
+        "Edit batches: {} in {} session{}\nEdits applied: {} of {}\nAverage batch size: {:.1} edits\n\
+         Mismatch rate: {} of batches\nAuto-rebase success rate: {} of stale anchors ({} of {})",
+        total.batches, per_session.len(), if per_session.len() == 1 { "" } else { "s" },
+        total.applied, total.edits, total.average_batch_size(),
+        percent(Some(total.mismatch_rate())), percent(total.rebase_success_rate()), total.rebased, total.rebase_attempts,
+    ))
+}
+
+impl EditOptions {
+    /// Record the stats of a batch that came to `result`, if a stats file is
+    /// set. Validation-only, dry and suggestion runs are not recorded. A
+    /// stats file that cannot be written is logged, and does not fail the
+    /// edit.
+    pub(crate) fn record_stats(&self, file: &str, edits: usize, result: &Result<AppliedEdits, HashlineError>) {
+        let Some(path) = &self.stats_file else {
+            return;
+        };
+        if self.validate_only || self.dry_run || self.suggest || self.print_conflicts {
+            return;
+        }
+        let stats = BatchStats::new(file, edits, self.apply.auto_rebase, result);
+        if let Err(e) = stats.append(path) {
+            log::warn!("Failed to write stats {}: {}", path.display(), e);
+        }
+    }
+}
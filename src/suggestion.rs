@@ -0,0 +1,93 @@
+use std::ops::Range;
+
+use serde::Serialize;
+
+use crate::{diff_lines, render_error, OutputFormat};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// GitHub Suggested Changes
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// A GitHub pull request review comment holding a ```` ```suggestion ````
+/// block, with the fields GitHub's "create a review" API takes for one of
+/// its `comments`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Suggestion {
+    pub path: String,
+    /// The first line the suggestion replaces, when it spans several.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<usize>,
+    /// The last line the suggestion replaces, 1-based.
+    pub line: usize,
+    /// Always `RIGHT`: lines are those of the file as it is, before the edit.
+    pub side: &'static str,
+    pub body: String,
+}
+
+/// One suggestion per changed run of lines turning `old` into `new`. A pure
+/// insertion cannot be commented on by itself, so it also replaces the line
+/// above it (below it at the top of the file) with that line plus the
+/// inserted ones.
+pub fn github_suggestions(path: &str, old: &str, new: &str) -> Vec<Suggestion> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let mut hunks: Vec<(Range<usize>, Range<usize>)> = Vec::new();
+    for run in diff_lines(&old_lines, &new_lines).into_iter().filter(|run| !run.equal) {
+        let (old_range, new_range) = (run.old, run.new);
+        match hunks.last_mut() {
+            Some((old, new)) if old.end == old_range.start && new.end == new_range.start => {
+                old.end = old_range.end;
+                new.end = new_range.end;
+            }
+            _ => hunks.push((old_range, new_range)),
+        }
+    }
+    hunks.into_iter().filter_map(|(old, new)| {
+        let (old, new) = match (old.is_empty(), old.start) {
+            (false, _) => (old, new),
+            (true, 0) if old_lines.is_empty() => return None,
+            (true, 0) => (0..1, new.start..new.end + 1),
+            (true, start) => (start - 1..start, new.start - 1..new.end),
+        };
+        let replacement = new_lines[new].join("\n");
+        let start_line = (old.len() > 1).then_some(old.start + 1);
+        Some(Suggestion { path: path.to_string(), start_line, line: old.end, side: "RIGHT", body: suggestion_block(&replacement) })
+    }).collect()
+}
+
+/// `text` in a suggestion fence longer than any run of backticks in it.
+fn suggestion_block(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    let newline = if text.is_empty() { "" } else { "\n" };
+    format!("{}suggestion\n{}{}{}", fence, text, newline, fence)
+}
+
+/// Render the edit of `path` from `old` to `new` as review comments, for
+/// `edit --suggest`.
+pub(crate) fn render_suggestions(path: &str, old: &str, new: &str, format: OutputFormat) -> Result<String, String> {
+    let suggestions = github_suggestions(path, old, new);
+    if suggestions.is_empty() {
+        let message = format!("The edit of {} cannot be written as suggestions: it only fills an empty file or changes the final newline", path);
+        return Err(render_error(format, "not_suggestable", message));
+    }
+    Ok(match format {
+        OutputFormat::Json => serde_json::json!({
+            "status": "suggested",
+            "file": path,
+            "comments": suggestions,
+        }).to_string(),
+        OutputFormat::Text => {
+            let count = suggestions.len();
+            let mut out = format!("{} suggestion{} for {}, nothing written:", count, if count == 1 { "" } else { "s" }, path);
+            for suggestion in &suggestions {
+                let lines = match suggestion.start_line {
+                    Some(start) => format!("lines {}-{}", start, suggestion.line),
+                    None => format!("line {}", suggestion.line),
+                };
+                out.push_str(&format!("\n\n{} {}:\n{}", suggestion.path, lines, suggestion.body));
+            }
+            out
+        }
+    })
+}
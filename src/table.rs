@@ -0,0 +1,61 @@
+use std::fs;
+use std::io::Write;
+
+use crate::commands::next_read_command;
+use crate::{annotate_lines_with_mode, detect_delimiter, split_fields, ReadOptions};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Delimited Files
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// `read --columns`: each row after the header with its fields labeled by
+/// the header's column names, e.g. `3#KM:id=7 | name="Lee, A"`. The fields
+/// are shown as written, quotes included; rows that cannot be split are
+/// shown as they are. Anchors are the file's own, so they work for
+/// `set_cell`, `set_column` and line edits alike.
+pub(crate) fn read_table(out: &mut impl Write, file_path: &str, options: &ReadOptions) -> Result<(), String> {
+    let content = fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    write_table(out, file_path, &content, options).map_err(|e| format!("Failed to write output: {}", e))
+}
+
+fn write_table(out: &mut impl Write, file_path: &str, content: &str, options: &ReadOptions) -> std::io::Result<()> {
+    let delimiter = detect_delimiter(content);
+    let header: Vec<String> = content.lines().next()
+        .and_then(|line| split_fields(line, delimiter).ok())
+        .map(|fields| fields.into_iter().map(|field| field.value).collect())
+        .unwrap_or_default();
+    let start = options.offset.unwrap_or(0);
+    let count = options.limit.unwrap_or(2000);
+    let total_lines = content.lines().count();
+
+    let shown = delimiter.escape_default().to_string().replace('"', "&quot;");
+    writeln!(out, "<table delimiter=\"{}\" columns=\"{}\">", shown, header.len())?;
+    let mut last = None;
+    for line in annotate_lines_with_mode(content, options.anchor_mode).skip(start).take(count) {
+        last = Some(line.anchor());
+        let fields = split_fields(line.text, delimiter).ok().filter(|_| line.line > 1);
+        let Some(fields) = fields else {
+            writeln!(out, "{}", line)?;
+            continue;
+        };
+        let labeled: Vec<String> = fields.iter().enumerate()
+            .map(|(i, field)| {
+                let text = &line.text[field.span.clone()];
+                match header.get(i).filter(|name| !name.is_empty()) {
+                    Some(name) => format!("{}={}", name, text),
+                    None => format!("{}={}", i, text),
+                }
+            })
+            .collect();
+        writeln!(out, "{}#{}:{}", line.line, line.hash, labeled.join(" | "))?;
+    }
+    let end = start.saturating_add(count).min(total_lines);
+    if end < total_lines {
+        let last = last.map(|anchor| format!(". Last line shown: {}", anchor)).unwrap_or_default();
+        writeln!(out, "\n(File has more lines. Use 'offset' parameter to read beyond line {}{})", end, last)?;
+        writeln!(out, "Next: {}", next_read_command(file_path, format!("--offset {}", end), options))?;
+    } else {
+        writeln!(out, "\n(End of file - {} total lines)", total_lines)?;
+    }
+    write!(out, "</table>")
+}
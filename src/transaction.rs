@@ -0,0 +1,332 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::audit::{audit_forced_edit, forced_audit_log, AuditedCommand};
+use crate::journal::{default_journal_dir, Journal};
+use crate::{
+    apply_hashline_edits_with_options, edits_from_value, hash_aware_diff_with, render_error, render_hash_aware_diff,
+    resolve_symlink, stage_file, sync_dir, write_atomic, ApplyOptions, BypassedCheck, EditOptions, HashedFile, HashlineEdit, HashlineError, Operation, OutputFormat,
+    Relocation,
+};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Multi-file Transactions
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Edits for several files, keyed by path. Applied all-or-nothing.
+pub type MultiFileEdits = BTreeMap<String, Vec<HashlineEdit>>;
+
+/// A file that a transaction will (or did) rewrite.
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub path: String,
+    pub old_content: String,
+    pub new_content: String,
+    pub first_changed_line: Option<usize>,
+    pub relocations: Vec<Relocation>,
+    /// Checks skipped because the transaction was forced.
+    pub bypassed: Vec<BypassedCheck>,
+}
+
+/// Why a file in a transaction could not be edited.
+#[derive(Debug, Clone)]
+pub struct FileFailure {
+    pub path: String,
+    pub message: String,
+    /// Structured mismatch details, when the failure was a hash mismatch.
+    pub mismatch: Option<serde_json::Value>,
+}
+
+/// Error returned when a transaction is aborted. No file has been modified.
+#[derive(Debug)]
+pub enum TransactionError {
+    /// One or more files failed validation.
+    Validation(Vec<FileFailure>),
+    /// Writing the staged files failed.
+    Io(String),
+}
+
+impl std::fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Transaction aborted; no files were modified.")?;
+        match self {
+            TransactionError::Validation(failures) => {
+                for failure in failures {
+                    write!(f, "\n{}: {}\n", failure.path, failure.message)?;
+                }
+                Ok(())
+            }
+            TransactionError::Io(message) => write!(f, "\n{}", message),
+        }
+    }
+}
+
+impl std::error::Error for TransactionError {}
+
+/// Validate and compute every file's new content without touching disk.
+/// Fails if any file cannot be read or any of its edits fail to validate.
+pub fn plan_multi_file_edits(
+    edits: &MultiFileEdits,
+    options: &ApplyOptions,
+) -> Result<Vec<FileChange>, TransactionError> {
+    plan_file_changes(edits, |_| Ok(Cow::Borrowed(options)))
+}
+
+/// [`plan_multi_file_edits`] with options chosen per file, e.g. to follow
+/// each file's `.editorconfig`.
+fn plan_file_changes<'o>(
+    edits: &MultiFileEdits,
+    options_for: impl Fn(&Path) -> Result<Cow<'o, ApplyOptions>, String>,
+) -> Result<Vec<FileChange>, TransactionError> {
+    let mut changes = Vec::new();
+    let mut failures = Vec::new();
+
+    for (path, file_edits) in edits {
+        let options = match options_for(Path::new(path)) {
+            // Partial application or conflict blocks would defeat the all-or-nothing guarantee
+            Ok(options) => ApplyOptions { allow_partial: false, conflict_markers: false, ..options.into_owned() },
+            Err(message) => {
+                failures.push(FileFailure { path: path.clone(), message, mismatch: None });
+                continue;
+            }
+        };
+        let old_content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                failures.push(FileFailure {
+                    path: path.clone(),
+                    message: format!("Failed to read file: {}", e),
+                    mismatch: None,
+                });
+                continue;
+            }
+        };
+        match apply_hashline_edits_with_options(&old_content, file_edits, &options) {
+            Ok(outcome) => changes.push(FileChange {
+                path: path.clone(),
+                old_content,
+                new_content: outcome.content,
+                first_changed_line: outcome.first_changed_line,
+                relocations: outcome.relocations,
+                bypassed: outcome.bypassed,
+            }),
+            Err(e) => {
+                let mismatch_err = match &e {
+                    HashlineError::Mismatch(m) => Some(m),
+                    _ => None,
+                };
+                failures.push(FileFailure {
+                    path: path.clone(),
+                    message: match mismatch_err {
+                        Some(m) => format!("Hash mismatch error:\n{}", m),
+                        None => format!("Edit failed: {}", e),
+                    },
+                    mismatch: mismatch_err.map(|m| m.to_json()),
+                });
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        return Err(TransactionError::Validation(failures));
+    }
+    Ok(changes)
+}
+
+/// Apply edits to several files with all-or-nothing semantics.
+///
+/// Every file is validated first. New contents are then staged in sibling
+/// temp files, and only once all of them are staged are they renamed over
+/// the originals. Returns the changes that were written.
+pub fn apply_multi_file_edits(
+    edits: &MultiFileEdits,
+    options: &ApplyOptions,
+) -> Result<Vec<FileChange>, TransactionError> {
+    commit_file_changes(plan_multi_file_edits(edits, options)?, &default_journal_dir())
+}
+
+/// Write planned changes all-or-nothing. Returns the changes that were
+/// written; files whose content is unchanged are left alone.
+///
+/// A journal in `journal_dir` covers the renames, so that a process killed
+/// mid-commit can be finished or undone with `recover`.
+pub fn commit_file_changes(changes: Vec<FileChange>, journal_dir: &Path) -> Result<Vec<FileChange>, TransactionError> {
+    let changes: Vec<FileChange> = changes
+        .into_iter()
+        .filter(|c| c.new_content != c.old_content)
+        .collect();
+
+    // Stage every file before touching any target, next to the file a
+    // symlink points to so the rename replaces that file and not the link
+    let mut targets: Vec<PathBuf> = Vec::new();
+    let mut staged: Vec<PathBuf> = Vec::new();
+    for change in &changes {
+        let target = resolve_symlink(Path::new(&change.path));
+        match target.and_then(|target| Ok((stage_file(&target, change.new_content.as_bytes())?, target))) {
+            Ok((temp_path, target)) => {
+                staged.push(temp_path);
+                targets.push(target);
+            }
+            Err(e) => {
+                for temp_path in &staged {
+                    let _ = fs::remove_file(temp_path);
+                }
+                return Err(TransactionError::Io(format!("Failed to stage {}: {}", change.path, e)));
+            }
+        }
+    }
+
+    let journal_files: Vec<(&Path, &Path, &str, &str)> = changes.iter().zip(&targets).zip(&staged)
+        .map(|((c, target), temp_path)| (target.as_path(), temp_path.as_path(), c.new_content.as_str(), c.old_content.as_str()))
+        .collect();
+    let journal = match Journal::begin(journal_dir, &journal_files) {
+        Ok(journal) => journal,
+        Err(e) => {
+            for temp_path in &staged {
+                let _ = fs::remove_file(temp_path);
+            }
+            return Err(TransactionError::Io(format!("Failed to write transaction journal: {}", e)));
+        }
+    };
+
+    // Commit: rename staged files into place. Renames only fail in unusual
+    // situations (e.g. the directory vanished); restore what was already
+    // committed so the batch still lands all-or-nothing.
+    for (i, ((change, target), temp_path)) in changes.iter().zip(&targets).zip(&staged).enumerate() {
+        if let Err(e) = fs::rename(temp_path, target) {
+            for (done, done_target) in changes[..i].iter().zip(&targets) {
+                let _ = write_atomic(done_target, &done.old_content);
+            }
+            for temp_path in &staged[i..] {
+                let _ = fs::remove_file(temp_path);
+            }
+            journal.finish();
+            return Err(TransactionError::Io(format!("Failed to write {}: {}", change.path, e)));
+        }
+    }
+    // Make the renames durable before the journal that could undo them goes
+    let mut synced = BTreeSet::new();
+    for target in targets.iter().filter(|target| synced.insert(target.parent())) {
+        sync_dir(target);
+    }
+
+    journal.finish();
+    Ok(changes)
+}
+
+/// Parse a `{path: [edits]}` payload and apply it as one transaction.
+pub fn cmd_multi_edit(payload_json: &str, options: &EditOptions) -> Result<String, String> {
+    let result = multi_edit(payload_json, options);
+    let documents: BTreeMap<String, serde_json::Value> = serde_json::from_str(payload_json).unwrap_or_default();
+    let files = documents.keys().map(String::as_str).collect();
+    let multi_edit = AuditedCommand { event: "multi_edit", files, payload: Some(payload_json) };
+    multi_edit.audit(options.audit_log.as_deref(), options.format, result)
+}
+
+fn multi_edit(payload_json: &str, options: &EditOptions) -> Result<String, String> {
+    let documents: BTreeMap<String, serde_json::Value> = serde_json::from_str(payload_json)
+        .map_err(|e| render_error(options.format, "invalid_edits", format!("Failed to parse edits: {}", e)))?;
+    let edits = documents.into_iter()
+        .map(|(path, document)| edits_from_value(document).map(|edits| (path, edits)))
+        .collect::<Result<MultiFileEdits, _>>()
+        .map_err(|e| render_error(options.format, e.kind(), e.to_string()))?;
+
+    for path in edits.keys() {
+        options.policy.check(Operation::Edit, Path::new(path), options.apply.force)
+            .map_err(|e| render_error(options.format, "policy_violation", e.to_string()))?;
+    }
+    options.check_verify_policy()?;
+    options.check_memory_budget(edits.keys().map(Path::new))?;
+    options.check_file_limits(edits.keys().map(Path::new))?;
+    for (path, file_edits) in &edits {
+        options.check_batch_limits(Path::new(path), file_edits)?;
+    }
+    let audit_log = forced_audit_log(options)?;
+    let planned = plan_file_changes(&edits, |path| options.apply_options_for(path))
+        .map_err(|e| render_transaction_error(&e, options.format))?;
+    for change in planned.iter().filter(|c| c.new_content != c.old_content) {
+        audit_forced_edit(audit_log, &change.path, &change.bypassed, options)?;
+    }
+    let touched: Vec<(&Path, &str, &str)> = planned.iter()
+        .filter(|c| c.new_content != c.old_content)
+        .map(|c| (Path::new(&c.path), c.old_content.as_str(), c.new_content.as_str()))
+        .collect();
+    let checkpoint = if touched.is_empty() {
+        None
+    } else {
+        options.record_backup(&touched)?;
+        options.record_checkpoint(&touched)?
+    };
+    let journal_dir = options.journal_dir.clone().unwrap_or_else(default_journal_dir);
+    let changes = commit_file_changes(planned, &journal_dir).map_err(|e| {
+        options.discard_checkpoint(checkpoint.as_deref());
+        render_transaction_error(&e, options.format)
+    })?;
+    let written: Vec<(&Path, &str)> = changes.iter().map(|c| (Path::new(&c.path), c.old_content.as_str())).collect();
+    options.verify_written(&written, checkpoint.as_deref())?;
+
+    let diffs: Vec<(&FileChange, String)> = changes.iter()
+        .map(|c| {
+            let first_changed_line = c.first_changed_line.unwrap_or(1);
+            let new_file = HashedFile::from_content_with_mode(&c.new_content, options.apply.anchor_mode.for_path(&c.path));
+            let hunks = hash_aware_diff_with(&c.old_content, &new_file, first_changed_line);
+            (c, render_hash_aware_diff(&hunks, first_changed_line))
+        })
+        .collect();
+
+    if options.format == OutputFormat::Json {
+        let files: Vec<serde_json::Value> = diffs.iter()
+            .map(|(c, diff)| serde_json::json!({
+                "file": c.path,
+                "first_changed_line": c.first_changed_line,
+                "relocations": c.relocations,
+                "bypassed": c.bypassed,
+                "diff": diff,
+            }))
+            .collect();
+        return Ok(serde_json::json!({
+            "status": if changes.is_empty() { "unchanged" } else { "applied" },
+            "checkpoint": checkpoint,
+            "files": files,
+        }).to_string());
+    }
+
+    if changes.is_empty() {
+        return Ok("No changes made".to_string());
+    }
+    let mut output = format!(
+        "Transaction applied to {} file{}.",
+        changes.len(),
+        if changes.len() == 1 { "" } else { "s" }
+    );
+    if let Some(id) = &checkpoint {
+        output.push_str(&format!("\nCheckpoint: {}", id));
+    }
+    for (c, diff) in &diffs {
+        output.push_str(&format!("\n\n<diff>\n--- {}\n+++ {}\n{}\n</diff>", c.path, c.path, diff));
+    }
+    Ok(output)
+}
+
+fn render_transaction_error(error: &TransactionError, format: OutputFormat) -> String {
+    match (format, error) {
+        (OutputFormat::Text, _) => error.to_string(),
+        (OutputFormat::Json, TransactionError::Io(message)) => render_error(format, "io", message.clone()),
+        (OutputFormat::Json, TransactionError::Validation(failures)) => {
+            let files: Vec<serde_json::Value> = failures.iter()
+                .map(|f| serde_json::json!({
+                    "file": f.path,
+                    "message": f.message,
+                    "mismatch": f.mismatch,
+                }))
+                .collect();
+            serde_json::json!({
+                "status": "error",
+                "error": "transaction_failed",
+                "files": files,
+            }).to_string()
+        }
+    }
+}
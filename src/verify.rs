@@ -0,0 +1,139 @@
+use std::fmt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::audit::audit_event;
+use crate::{render_error, write_atomic, EditOptions, OutputFormat};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Verify Command
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Lines from the end of a failed verify command's output kept in the error.
+pub const VERIFY_OUTPUT_LINES: usize = 60;
+
+/// A verify command that did not exit successfully.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyFailure {
+    pub command: String,
+    /// The exit code, or `None` if the command was killed by a signal or
+    /// could not be started.
+    pub exit_code: Option<i32>,
+    /// The last [`VERIFY_OUTPUT_LINES`] lines of stdout and stderr.
+    pub output: String,
+}
+
+impl fmt::Display for VerifyFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.exit_code {
+            Some(code) => write!(f, "`{}` exited with status {}", self.command, code),
+            None => write!(f, "`{}` did not run to completion", self.command),
+        }
+    }
+}
+
+/// Run `command` through the shell (`sh -c`, or `cmd /C` on Windows) in the
+/// current directory, with stdin closed. Succeeds if it exits with status 0.
+pub fn run_verify_command(command: &str) -> Result<(), VerifyFailure> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let started = std::time::Instant::now();
+    let output = shell.arg(command).stdin(Stdio::null()).output();
+    log::debug!("verify command {:?} ran in {:.1?}", command, started.elapsed());
+    let failure = |exit_code, output: String| VerifyFailure { command: command.to_string(), exit_code, output };
+    match output {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => {
+            let combined = String::from_utf8_lossy(&output.stdout).into_owned() + &String::from_utf8_lossy(&output.stderr);
+            Err(failure(output.status.code(), tail_lines(&combined, VERIFY_OUTPUT_LINES)))
+        }
+        Err(e) => Err(failure(None, format!("Failed to start the command: {}", e))),
+    }
+}
+
+fn tail_lines(text: &str, count: usize) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let skipped = lines.len().saturating_sub(count);
+    let tail = lines[skipped..].join("\n");
+    if skipped > 0 {
+        format!("({} earlier lines omitted)\n{}", skipped, tail)
+    } else {
+        tail
+    }
+}
+
+impl EditOptions {
+    /// Refuse a verify command the operator policy forbids, before anything
+    /// is read or written.
+    pub(crate) fn check_verify_policy(&self) -> Result<(), String> {
+        match &self.verify_cmd {
+            Some(_) => self.policy.check_verify_cmd().map_err(|e| render_error(self.format, "policy_violation", e.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Run the verify command, if any, after `files` were written. When it
+    /// fails, each file gets its `(path, old_content)` back, the write's
+    /// checkpoint is dropped, and the error carries the command's output.
+    pub(crate) fn verify_written(&self, files: &[(&Path, &str)], checkpoint: Option<&str>) -> Result<(), String> {
+        let Some(command) = &self.verify_cmd else {
+            return Ok(());
+        };
+        let files_shown: Vec<String> = files.iter().map(|(path, _)| path.display().to_string()).collect();
+        let Err(failure) = run_verify_command(command) else {
+            let record = serde_json::json!({ "event": "verify", "files": files_shown, "command": command, "status": "ok" });
+            return audit_event(self.audit_log.as_deref(), record)
+                .map_err(|e| render_error(self.format, "io", format!("{}; the edit was written and verified", e)));
+        };
+        let mut restored = Vec::new();
+        let mut unrestored = Vec::new();
+        for (path, old_content) in files {
+            match write_atomic(path, old_content) {
+                Ok(()) => restored.push(path.display().to_string()),
+                Err(e) => unrestored.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+        if unrestored.is_empty() {
+            self.discard_checkpoint(checkpoint);
+        }
+        let record = serde_json::json!({
+            "event": "verify",
+            "files": files_shown,
+            "command": command,
+            "status": "error",
+            "exit_code": failure.exit_code,
+            "rolled_back": restored,
+        });
+        let audit_error = audit_event(self.audit_log.as_deref(), record).err();
+        Err(match self.format {
+            OutputFormat::Json => serde_json::json!({
+                "status": "error",
+                "error": "verify_failed",
+                "message": format!("Verification failed: {}", failure),
+                "command": failure.command,
+                "exit_code": failure.exit_code,
+                "output": failure.output,
+                "rolled_back": restored,
+                "rollback_errors": unrestored,
+                "audit_error": audit_error,
+            }).to_string(),
+            OutputFormat::Text => {
+                let rollback = if unrestored.is_empty() {
+                    format!("The edit was rolled back: {}", restored.join(", "))
+                } else {
+                    let saved = checkpoint.map(|id| format!("; checkpoint {} holds the previous content", id));
+                    format!("Rolling back failed for {}{}", unrestored.join(", "), saved.unwrap_or_default())
+                };
+                let audit_error = audit_error.map(|e| format!("\n{}", e)).unwrap_or_default();
+                format!("Verification failed: {}. {}{}\n\n<output>\n{}\n</output>", failure, rollback, audit_error, failure.output)
+            }
+        })
+    }
+}
@@ -0,0 +1,82 @@
+//! wasm-bindgen exports for the browser package in `wasm/`; see the `wasm`
+//! feature. Values cross the boundary as JSON strings and `wasm/index.js`
+//! turns them into objects.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    apply_hashline_edits_with_options, generate_hash_aware_diff, line_byte_offsets, parse_edits,
+    validate_hashline_edits_with_options, ApplyOptions, HashedFile, HashlineEdit, HashlineError,
+};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// WASM Exports
+// ═══════════════════════════════════════════════════════════════════════════
+//
+// Failures are thrown as the JSON error object string from
+// `HashlineError::to_json`.
+
+/// `[{"line", "hash", "anchor", "byte_offset", "text"}]` for every line of
+/// `content`. `byte_offset` counts UTF-8 bytes, not JavaScript string units.
+#[wasm_bindgen(js_name = hashLines)]
+pub fn hash_lines(content: &str) -> String {
+    let file = HashedFile::from_content(content);
+    let lines: Vec<serde_json::Value> = file.lines().iter().zip(file.hashes()).zip(line_byte_offsets(content)).enumerate()
+        .map(|(i, ((text, hash), byte_offset))| serde_json::json!({
+            "line": i + 1,
+            "hash": hash,
+            "anchor": format!("{}#{}", i + 1, hash),
+            "byte_offset": byte_offset,
+            "text": text,
+        }))
+        .collect();
+    serde_json::Value::from(lines).to_string()
+}
+
+/// Apply a JSON edit array to `content`; returns the serialized
+/// [`crate::ApplyOutcome`] plus the hash-aware `diff`.
+#[wasm_bindgen(js_name = applyEdits)]
+pub fn apply_edits(content: &str, edits_json: &str, options_json: Option<String>) -> Result<String, JsValue> {
+    apply(content, edits_json, options_json.as_deref()).map_err(|e| JsValue::from_str(&e))
+}
+
+/// One `EditStatus` per edit in the JSON array, without applying anything.
+#[wasm_bindgen(js_name = validateEdits)]
+pub fn validate_edits(content: &str, edits_json: &str, options_json: Option<String>) -> Result<String, JsValue> {
+    validate(content, edits_json, options_json.as_deref()).map_err(|e| JsValue::from_str(&e))
+}
+
+/// The hash-aware diff between two versions of a file.
+#[wasm_bindgen]
+pub fn diff(old_content: &str, new_content: &str, first_changed_line: usize) -> String {
+    generate_hash_aware_diff(old_content, new_content, first_changed_line)
+}
+
+fn apply(content: &str, edits_json: &str, options_json: Option<&str>) -> Result<String, String> {
+    let (edits, options) = parse_request(edits_json, options_json)?;
+    let outcome = apply_hashline_edits_with_options(content, &edits, &options)
+        .map_err(|e| e.to_json().to_string())?;
+    let diff = match outcome.first_changed_line {
+        Some(line) if outcome.content != content => generate_hash_aware_diff(content, &outcome.content, line),
+        _ => String::new(),
+    };
+    let mut value = serde_json::to_value(&outcome).expect("ApplyOutcome serializes to JSON");
+    value["diff"] = diff.into();
+    Ok(value.to_string())
+}
+
+fn validate(content: &str, edits_json: &str, options_json: Option<&str>) -> Result<String, String> {
+    let (edits, options) = parse_request(edits_json, options_json)?;
+    let statuses = validate_hashline_edits_with_options(content, &edits, &options);
+    Ok(serde_json::to_string(&statuses).expect("EditStatus serializes to JSON"))
+}
+
+fn parse_request(edits_json: &str, options_json: Option<&str>) -> Result<(Vec<HashlineEdit>, ApplyOptions), String> {
+    let invalid = |message: String| HashlineError::Validation(vec![message]).to_json().to_string();
+    let edits = parse_edits(edits_json).map_err(|e| e.to_json().to_string())?;
+    let options = match options_json {
+        Some(json) => serde_json::from_str(json).map_err(|e| invalid(format!("Failed to parse options: {}", e)))?,
+        None => ApplyOptions::default(),
+    };
+    Ok((edits, options))
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "fs")]
+
+use std::fs;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+fn leftovers(dir: &std::path::Path) -> Vec<String> {
+    fs::read_dir(dir).unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".tmp"))
+        .collect()
+}
+
+#[test]
+fn test_write_atomic_replaces_content_without_leftovers() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("a.txt");
+    write_atomic(&path, "new file\n").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "new file\n");
+    write_atomic(&path, "replaced\n").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "replaced\n");
+    assert!(leftovers(dir.path()).is_empty());
+
+    // A missing directory fails before anything is written
+    assert!(write_atomic(&dir.path().join("missing").join("a.txt"), "x").is_err());
+}
+
+#[cfg(all(unix, feature = "cli"))]
+#[test]
+fn test_edit_keeps_permissions_and_follows_symlinks() {
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("run.sh");
+    fs::write(&path, "echo one\n").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o750)).unwrap();
+    let link = dir.path().join("link.sh");
+    symlink(&path, &link).unwrap();
+
+    let anchor = HashedFile::from_content("echo one\n").anchor(1).unwrap().to_string();
+    let edits = serde_json::json!([{"op": "replace", "pos": anchor, "lines": ["echo two"]}]).to_string();
+    cmd_edit(link.to_str().unwrap(), &edits).unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "echo two\n");
+    assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+    assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o750);
+    assert!(leftovers(dir.path()).is_empty());
+}
+
+#[cfg(all(unix, feature = "cli"))]
+#[test]
+fn test_multi_edit_replaces_the_symlinked_file() {
+    use std::os::unix::fs::symlink;
+
+    let dir = TempDir::new().unwrap();
+    let real = dir.path().join("real.txt");
+    let link = dir.path().join("link.txt");
+    let plain = dir.path().join("plain.txt");
+    fs::write(&real, "a\n").unwrap();
+    fs::write(&plain, "b\n").unwrap();
+    symlink(&real, &link).unwrap();
+
+    let anchor = |content: &str| HashedFile::from_content(content).anchor(1).unwrap().to_string();
+    let payload = serde_json::json!({
+        link.to_str().unwrap(): [{"op": "replace", "pos": anchor("a\n"), "lines": ["A"]}],
+        plain.to_str().unwrap(): [{"op": "replace", "pos": anchor("b\n"), "lines": ["B"]}],
+    });
+    let options = EditOptions { journal_dir: Some(dir.path().join("journal")), ..EditOptions::default() };
+    cmd_multi_edit(&payload.to_string(), &options).unwrap();
+
+    assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+    assert_eq!(fs::read_to_string(&real).unwrap(), "A\n");
+    assert_eq!(fs::read_to_string(&plain).unwrap(), "B\n");
+    assert!(leftovers(dir.path()).is_empty());
+}
@@ -0,0 +1,119 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::path::Path;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+fn records(log_path: &Path) -> Vec<serde_json::Value> {
+    fs::read_to_string(log_path).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+}
+
+#[test]
+fn test_every_command_is_recorded() {
+    let dir = TempDir::new().unwrap();
+    let log_path = dir.path().join("audit.jsonl");
+    let file_path = dir.path().join("notes.txt");
+    fs::write(&file_path, "one\ntwo\n").unwrap();
+    let path = file_path.to_str().unwrap();
+
+    let read = ReadOptions { audit_log: Some(log_path.clone()), ..ReadOptions::default() };
+    cmd_read_with_options(&mut Vec::new(), path, &read).unwrap();
+
+    let options = EditOptions { audit_log: Some(log_path.clone()), ..EditOptions::default() };
+    let anchor = HashedFile::from_content("one\ntwo\n").anchor(2).unwrap();
+    let edits = format!(r#"[{{"op": "replace", "pos": "{}", "lines": ["2"]}}]"#, anchor);
+    cmd_edit_with_options(path, &edits, &options).unwrap();
+    let json = EditOptions { format: OutputFormat::Json, ..options.clone() };
+    cmd_edit_with_options(path, &edits, &json).unwrap_err();
+    cmd_edit_with_options(path, "not json", &options).unwrap_err();
+
+    let records = records(&log_path);
+    assert_eq!(records.len(), 4);
+    assert_eq!(records[0]["event"], "read");
+    assert_eq!(records[0]["files"][0], path);
+    assert_eq!(records[0]["status"], "ok");
+    assert!(records[0]["payload_sha256"].is_null());
+    assert!(records[0]["timestamp"].as_u64().unwrap() > 0);
+
+    assert_eq!(records[1]["event"], "edit");
+    assert_eq!(records[1]["status"], "ok");
+    assert_eq!(records[1]["payload_sha256"], content_fingerprint(&edits));
+
+    // The anchor went stale with the first edit
+    assert_eq!(records[2]["status"], "error");
+    assert_eq!(records[2]["error"], "hash_mismatch");
+    assert_eq!(records[2]["payload_sha256"], records[1]["payload_sha256"]);
+    // Text errors carry no kind
+    assert_eq!(records[3]["error"], "error");
+    assert!(records[3]["message"].as_str().unwrap().starts_with("Failed to parse edits"));
+}
+
+#[test]
+fn test_plans_commits_and_transactions_are_recorded() {
+    let dir = TempDir::new().unwrap();
+    let log_path = dir.path().join("audit.jsonl");
+    let first = dir.path().join("a.txt");
+    let second = dir.path().join("b.txt");
+    fs::write(&first, "a\n").unwrap();
+    fs::write(&second, "b\n").unwrap();
+    let (first, second) = (first.to_str().unwrap(), second.to_str().unwrap());
+    std::env::set_var(PLAN_KEY_ENV_VAR, "audit-test-key");
+    let options = EditOptions {
+        audit_log: Some(log_path.clone()),
+        format: OutputFormat::Json,
+        ..EditOptions::default()
+    };
+
+    let edits = format!(r#"[{{"op": "replace", "pos": "{}", "lines": ["A"]}}]"#, HashedFile::from_content("a\n").anchor(1).unwrap());
+    let plan: serde_json::Value = serde_json::from_str(&cmd_plan(first, &edits, &options).unwrap()).unwrap();
+    let token = plan["token"].as_str().unwrap();
+    cmd_commit(token, &options).unwrap();
+    cmd_commit(token, &options).unwrap_err();
+
+    let payload = serde_json::json!({ second: [{"op": "append", "lines": ["c"]}] }).to_string();
+    cmd_multi_edit(&payload, &options).unwrap();
+
+    let records = records(&log_path);
+    let events: Vec<&str> = records.iter().map(|r| r["event"].as_str().unwrap()).collect();
+    assert_eq!(events, ["plan", "commit", "commit", "multi_edit"]);
+    assert_eq!(records[1]["files"][0], first);
+    assert_eq!(records[1]["payload_sha256"], content_fingerprint(token));
+    assert_eq!(records[2]["error"], "stale_plan");
+    assert_eq!(records[3]["files"][0], second);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_verify_runs_are_recorded() {
+    let dir = TempDir::new().unwrap();
+    let log_path = dir.path().join("audit.jsonl");
+    let file_path = dir.path().join("notes.txt");
+    fs::write(&file_path, "one\n").unwrap();
+    let path = file_path.to_str().unwrap();
+    let options = EditOptions {
+        audit_log: Some(log_path.clone()),
+        verify_cmd: Some("exit 2".to_string()),
+        ..EditOptions::default()
+    };
+    cmd_edit_with_options(path, r#"[{"op": "append", "lines": ["two"]}]"#, &options).unwrap_err();
+
+    let records = records(&log_path);
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0]["event"], "verify");
+    assert_eq!(records[0]["command"], "exit 2");
+    assert_eq!(records[0]["exit_code"], 2);
+    assert_eq!(records[0]["rolled_back"][0], path);
+    assert_eq!(records[1]["event"], "edit");
+    assert_eq!(records[1]["status"], "error");
+}
+
+#[test]
+fn test_audit_log_path_falls_back_to_the_environment() {
+    std::env::set_var(AUDIT_LOG_ENV_VAR, "/var/log/hashline.jsonl");
+    assert_eq!(audit_log_path(None).unwrap(), Path::new("/var/log/hashline.jsonl"));
+    assert_eq!(audit_log_path(Some("mine.jsonl".into())).unwrap(), Path::new("mine.jsonl"));
+    std::env::remove_var(AUDIT_LOG_ENV_VAR);
+    assert_eq!(audit_log_path(None), None);
+}
@@ -0,0 +1,204 @@
+use hashline_tools::*;
+
+// Helper function to compute cumulative hashes for a file and get a specific line's hash
+fn get_line_hash(content: &str, line_num: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut prev_hash: Option<&str> = None;
+    let mut cumulative_hashes: Vec<String> = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let ln = i + 1;
+        let hash = compute_line_hash(ln, line, prev_hash);
+        cumulative_hashes.push(hash);
+        prev_hash = Some(&cumulative_hashes[i]);
+    }
+
+    cumulative_hashes[line_num - 1].clone()
+}
+
+fn rebase_options() -> ApplyOptions {
+    ApplyOptions { auto_rebase: true, ..ApplyOptions::default() }
+}
+
+#[test]
+fn test_stale_anchor_fails_without_auto_rebase() {
+    let original = "fn main() {\n    let x = 1;\n    println!(\"{}\", x);\n}\n";
+    let stale_hash = get_line_hash(original, 2);
+    let current = format!("// header\n{}", original);
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 2, hash: stale_hash },
+            end: None,
+            lines: vec!["    let x = 2;".to_string()],
+            expected_lines: None,
+        }
+    ];
+
+    let result = apply_hashline_edits(&current, &edits);
+    assert!(result.is_err(), "Stale anchor should be rejected when auto-rebase is off");
+}
+
+#[test]
+fn test_auto_rebase_relocates_by_hash_when_the_content_agrees() {
+    let original = "fn main() {\n    let total = compute(1, 2);\n}\n";
+    let scoped = ApplyOptions { anchor_mode: AnchorMode::Scoped, ..rebase_options() };
+    let anchor = HashedFile::from_content_with_mode(original, AnchorMode::Scoped).anchor(2).unwrap();
+    // Scoped hashes survive lines inserted above, so the hash and the
+    // content both point at the shifted line
+    let current = format!("// header\n// notes\n{}", original);
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: anchor,
+            end: None,
+            lines: vec!["    let total = compute(1, 3);".to_string()],
+            expected_lines: None,
+        }
+    ];
+
+    let outcome = apply_hashline_edits_with_options(&current, &edits, &scoped).unwrap();
+    assert_eq!(outcome.content, "// header\n// notes\nfn main() {\n    let total = compute(1, 3);\n}\n");
+    assert_eq!(outcome.relocations.len(), 1);
+    let relocation = &outcome.relocations[0];
+    assert_eq!((relocation.edit_index, relocation.field), (0, "pos"));
+    assert_eq!((relocation.from.line, relocation.to.line), (2, 4));
+    assert_eq!(relocation.method, RelocationMethod::Hash);
+}
+
+#[test]
+fn test_auto_rebase_ignores_a_hash_that_recurs_by_chance() {
+    let lines: Vec<String> = (1..=60).map(|n| format!("let v{} = {};", n, n * 7)).collect();
+    let original = lines.join("\n") + "\n";
+    let anchor = HashedFile::from_content(&original).anchor(30).unwrap();
+
+    // Another writer edits line 30 in place until, by chance, exactly one
+    // other line in the rebase window ends up with the anchor's hash
+    let (current, hit) = (0..10_000)
+        .find_map(|i| {
+            let mut edited = lines.clone();
+            edited[29] = format!("let v30 = {};", i);
+            let current = edited.join("\n") + "\n";
+            let file = HashedFile::from_content(&current);
+            let hits: Vec<usize> = (10..=50).filter(|&n| n != 30 && file.hash_at(n) == Some(anchor.hash.as_str())).collect();
+            match hits.as_slice() {
+                [only] => Some((current, *only)),
+                _ => None,
+            }
+        })
+        .unwrap();
+
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: anchor,
+            end: None,
+            lines: vec!["let v30 = 211;".to_string()],
+            expected_lines: None,
+        }
+    ];
+    let err = apply_hashline_edits_with_options(&current, &edits, &rebase_options()).unwrap_err();
+    let HashlineError::Mismatch(mismatch) = err else { panic!("expected a mismatch error, got {}", err) };
+    assert!(mismatch.mismatches[0].candidates.iter().any(|c| c.anchor.line == hit && c.method == RelocationMethod::Hash));
+}
+
+#[test]
+fn test_auto_rebase_relocates_by_content() {
+    let original = "fn main() {\n    let total = compute(1, 2);\n    println!(\"{}\", total);\n}\n";
+    let stale_hash = get_line_hash(original, 2);
+    // A line was inserted above since the anchor was read
+    let current = format!("// header\n{}", original);
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 2, hash: stale_hash },
+            end: None,
+            lines: vec!["    let total = compute(1, 3);".to_string()],
+            expected_lines: None,
+        }
+    ];
+
+    let outcome = apply_hashline_edits_with_options(&current, &edits, &rebase_options()).unwrap();
+    assert_eq!(
+        outcome.content,
+        "// header\nfn main() {\n    let total = compute(1, 3);\n    println!(\"{}\", total);\n}\n"
+    );
+    assert_eq!(outcome.relocations.len(), 1);
+    assert_eq!(outcome.relocations[0].to.line, 3);
+    assert!(matches!(outcome.relocations[0].method, RelocationMethod::Fuzzy { .. }));
+}
+
+#[test]
+fn test_auto_rebase_refuses_ambiguous_content_match() {
+    let original = "start\n    value = 1;\nmiddle\n    value = 1;\nend\n";
+    let stale_hash = get_line_hash(original, 2);
+    let current = format!("// header\n{}", original);
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 2, hash: stale_hash },
+            end: None,
+            lines: vec!["    value = 2;".to_string()],
+            expected_lines: None,
+        }
+    ];
+
+    let result = apply_hashline_edits_with_options(&current, &edits, &rebase_options());
+    assert!(result.is_err(), "Ambiguous relocation should not be applied");
+}
+
+#[test]
+fn test_auto_rebase_refuses_line_edited_in_place() {
+    let original = "fn main() {\n    let total = compute(1, 2);\n}\n";
+    let stale_hash = get_line_hash(original, 2);
+    // Someone else changed the anchored line itself
+    let current = "fn main() {\n    let total = compute(1, 5);\n}\n";
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 2, hash: stale_hash },
+            end: None,
+            lines: vec!["    let total = compute(1, 3);".to_string()],
+            expected_lines: None,
+        }
+    ];
+
+    let result = apply_hashline_edits_with_options(current, &edits, &rebase_options());
+    assert!(result.is_err(), "In-place modifications must still be reported as mismatches");
+}
+
+#[test]
+fn test_relocation_display() {
+    let relocation = Relocation {
+        edit_index: 3,
+        field: "end",
+        from: AnchorRef { line: 8, hash: "RT".to_string() },
+        to: AnchorRef { line: 10, hash: "XH".to_string() },
+        method: RelocationMethod::Fuzzy { similarity: 0.85 },
+    };
+    assert_eq!(relocation.to_string(), "edits[3].end: 8#RT -> 10#XH (content match, 85% similar)");
+}
+
+#[test]
+fn test_fuzzy_threshold_and_max_candidates() {
+    let original = "fn main() {\n    let total = compute(1, 2);\n    println!(\"{}\", total);\n}\n";
+    let stale_hash = get_line_hash(original, 2);
+    let current = format!("// header\n{}", original);
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 2, hash: stale_hash.clone() },
+            end: None,
+            lines: vec!["    let total = compute(1, 3);".to_string()],
+            expected_lines: None,
+        }
+    ];
+    // Too strict a threshold finds nothing to relocate to
+    let strict = ApplyOptions { fuzzy_threshold: 0.99, ..rebase_options() };
+    assert!(apply_hashline_edits_with_options(&current, &edits, &strict).is_err());
+    let loose = ApplyOptions { fuzzy_threshold: 0.9, ..rebase_options() };
+    assert_eq!(apply_hashline_edits_with_options(&current, &edits, &loose).unwrap().relocations[0].to.line, 3);
+
+    // Mismatch errors list the best content matches only, up to the limit
+    let current = "// a\n// b\n    let total = compute(1, 4);\n    let total = compute(1, 2);\n    let total = compute(9, 9);\n";
+    let candidates = |options: &ApplyOptions| match apply_hashline_edits_with_options(current, &edits, options) {
+        Err(HashlineError::Mismatch(e)) => e.mismatches[0].candidates.iter().map(|c| c.anchor.line).collect::<Vec<_>>(),
+        other => panic!("expected a mismatch, got {:?}", other),
+    };
+    assert_eq!(candidates(&ApplyOptions::default()), [3, 4, 5]);
+    assert_eq!(candidates(&ApplyOptions { fuzzy_max_candidates: Some(1), ..ApplyOptions::default() }), [3]);
+    assert_eq!(candidates(&ApplyOptions { fuzzy_threshold: 0.95, ..ApplyOptions::default() }), [3, 4]);
+}
@@ -0,0 +1,153 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::time::{Duration, UNIX_EPOCH};
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+fn replace_first_line(path: &std::path::Path, text: &str, options: &EditOptions) {
+    let content = fs::read_to_string(path).unwrap();
+    let anchor = HashedFile::from_content(&content).anchor(1).unwrap().to_string();
+    let edits = serde_json::json!([{"op": "replace", "pos": anchor, "lines": [text]}]).to_string();
+    cmd_edit_with_options(path.to_str().unwrap(), &edits, options).unwrap();
+}
+
+#[test]
+fn test_edits_keep_every_pre_edit_version() {
+    let dir = TempDir::new().unwrap();
+    let store = dir.path().join("backups");
+    let path = dir.path().join("a.txt");
+    fs::write(&path, "one\n").unwrap();
+    let options = EditOptions { backup_dir: Some(store.clone()), ..EditOptions::default() };
+
+    replace_first_line(&path, "two", &options);
+    replace_first_line(&path, "one", &options);
+    replace_first_line(&path, "three", &options);
+
+    let entries = list_backups(&store, Some(&path)).unwrap();
+    let versions: Vec<_> = entries.iter().map(|e| e.fingerprint.clone()).collect();
+    assert_eq!(versions, [content_fingerprint("one\n"), content_fingerprint("two\n"), content_fingerprint("one\n")]);
+    assert_eq!(entries[0].path, path);
+    // Identical versions share one stored copy
+    assert_eq!(fs::read_dir(store.join("objects")).unwrap().count(), 2);
+
+    let listed = cmd_backups_list(&store, None, OutputFormat::Text).unwrap();
+    assert!(listed.starts_with(&format!("3 backups in {}:\n", store.display())), "{}", listed);
+    assert!(listed.contains(&format!("  {}  ", &content_fingerprint("one\n")[..12])), "{}", listed);
+    assert_eq!(cmd_backups_list(&dir.path().join("none"), None, OutputFormat::Text).unwrap(),
+        format!("No backups in {}", dir.path().join("none").display()));
+}
+
+#[test]
+fn test_restore_picks_latest_or_given_version() {
+    let dir = TempDir::new().unwrap();
+    let store = dir.path().join("backups");
+    let path = dir.path().join("a.txt");
+    fs::write(&path, "one\n").unwrap();
+    let options = EditOptions { backup_dir: Some(store.clone()), ..EditOptions::default() };
+    replace_first_line(&path, "two", &options);
+    replace_first_line(&path, "three", &options);
+
+    let restored = restore_backup(&store, &path, None, &Policy::default()).unwrap();
+    assert_eq!(restored.fingerprint, content_fingerprint("two\n"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "two\n");
+    // The content the restore replaced was backed up first
+    assert_eq!(list_backups(&store, Some(&path)).unwrap().last().unwrap().fingerprint, content_fingerprint("three\n"));
+
+    let one = content_fingerprint("one\n");
+    let output = cmd_backups_restore(&store, &path, Some(&one[..8]), &Policy::default(), OutputFormat::Json).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(json["status"], "restored");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "one\n");
+
+    let error = restore_backup(&store, &path, Some("zzzz"), &Policy::default()).unwrap_err().to_string();
+    assert!(error.contains("No backup of"), "{}", error);
+    // Too short a prefix is refused, even when it matches a single version
+    for version in ["", &one[..3]] {
+        let error = restore_backup(&store, &path, Some(version), &Policy::default()).unwrap_err().to_string();
+        assert!(error.contains("too short"), "{}", error);
+    }
+    assert_eq!(fs::read_to_string(&path).unwrap(), "one\n");
+    let error = restore_backup(&store, &dir.path().join("other.txt"), None, &Policy::default()).unwrap_err().to_string();
+    assert!(error.starts_with("No backups of"), "{}", error);
+}
+
+#[test]
+fn test_prune_drops_old_versions_and_unreferenced_contents() {
+    let dir = TempDir::new().unwrap();
+    let store = dir.path().join("backups");
+    let a = dir.path().join("a.txt");
+    let b = dir.path().join("b.txt");
+    record_backup(&store, &[(&a, "old\n"), (&b, "shared\n")]).unwrap();
+
+    // Nothing is older than the epoch
+    assert_eq!(prune_backups(&store, UNIX_EPOCH).unwrap(), PrunedBackups::default());
+
+    // Back-date the first versions
+    let index = store.join("index.jsonl");
+    let text: String = fs::read_to_string(&index).unwrap().lines()
+        .map(|line| {
+            let entry = BackupEntry { timestamp: 1_000, ..serde_json::from_str(line).unwrap() };
+            serde_json::to_string(&entry).unwrap() + "\n"
+        })
+        .collect();
+    fs::write(&index, text).unwrap();
+    record_backup(&store, &[(&b, "shared\n")]).unwrap();
+
+    let cutoff = UNIX_EPOCH + Duration::from_secs(2_000);
+    let pruned = prune_backups(&store, cutoff).unwrap();
+    assert_eq!(pruned, PrunedBackups { entries: 2, objects: 1, bytes: 4 });
+    assert_eq!(list_backups(&store, None).unwrap().len(), 1);
+    assert!(!store.join("objects").join(content_fingerprint("old\n")).exists());
+    assert!(store.join("objects").join(content_fingerprint("shared\n")).exists());
+
+    let output = cmd_backups_prune(&store, 0, OutputFormat::Text).unwrap();
+    assert!(output.starts_with("Pruned "), "{}", output);
+}
+
+#[test]
+fn test_prune_keeps_versions_recorded_while_it_runs() {
+    let dir = TempDir::new().unwrap();
+    let store = dir.path().join("backups");
+    let path = dir.path().join("a.txt");
+    const RECORDED: usize = 2000;
+
+    let recorder = {
+        let (store, path) = (store.clone(), path.clone());
+        std::thread::spawn(move || {
+            for i in 0..RECORDED {
+                record_backup(&store, &[(&path, &format!("{}\n", i % 7))]).unwrap();
+            }
+        })
+    };
+    // A cutoff in the future drops every version a prune sees
+    let cutoff = std::time::SystemTime::now() + Duration::from_secs(86_400);
+    let mut pruned = 0;
+    while !recorder.is_finished() {
+        pruned += prune_backups(&store, cutoff).unwrap().entries;
+    }
+    recorder.join().unwrap();
+
+    let remaining = list_backups(&store, None).unwrap();
+    assert_eq!(pruned + remaining.len(), RECORDED);
+    for entry in remaining {
+        assert!(store.join("objects").join(&entry.fingerprint).exists());
+    }
+}
+
+#[test]
+fn test_restore_checks_the_policy() {
+    let dir = TempDir::new().unwrap();
+    let store = dir.path().join("backups");
+    let path = dir.path().join("a.txt");
+    fs::write(&path, "one\n").unwrap();
+    replace_first_line(&path, "two", &EditOptions { backup_dir: Some(store.clone()), ..EditOptions::default() });
+
+    let policy = Policy { forbid_write: true, ..Policy::default() };
+    let output = cmd_backups_restore(&store, &path, None, &policy, OutputFormat::Json).unwrap_err();
+    let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(json["error"], "policy_violation", "{}", output);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "two\n");
+    assert_eq!(list_backups(&store, Some(&path)).unwrap().len(), 1);
+}
@@ -0,0 +1,36 @@
+#![cfg(feature = "cli")]
+
+use hashline_tools::*;
+use std::io::Write;
+
+#[test]
+fn test_bench_reports_every_phase_without_writing() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    let content: String = (1..=50).map(|i| format!("line {}\n", i)).collect();
+    write!(file, "{}", content).unwrap();
+    let path = file.path().to_str().unwrap();
+
+    let report = run_bench(path, 2, 10, &EditOptions::default()).unwrap();
+    assert_eq!((report.lines, report.bytes, report.iterations, report.edits), (50, content.len(), 2, 10));
+    assert!(!report.hash_cache);
+    assert!(!report.parallel_hashing);
+    let phases: Vec<&str> = report.phases.iter().map(|p| p.phase).collect();
+    assert_eq!(phases, vec!["hash", "read", "validate", "apply"]);
+    assert!(report.phases.iter().all(|p| p.min_ms <= p.mean_ms && p.lines_per_sec.is_finite()));
+    assert_eq!(std::fs::read_to_string(path).unwrap(), content);
+}
+
+#[test]
+fn test_bench_caps_edits_at_file_length_and_renders_json() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    write!(file, "a\nb\nc\n").unwrap();
+    let path = file.path().to_str().unwrap();
+    let options = EditOptions { format: OutputFormat::Json, ..EditOptions::default() };
+
+    let output: serde_json::Value = serde_json::from_str(&cmd_bench(path, 1, 100, &options).unwrap()).unwrap();
+    assert_eq!(output["edits"], 3);
+    assert_eq!(output["phases"].as_array().unwrap().len(), 4);
+
+    let err: serde_json::Value = serde_json::from_str(&cmd_bench("/nonexistent/file", 1, 1, &options).unwrap_err()).unwrap();
+    assert_eq!(err["error"], "io");
+}
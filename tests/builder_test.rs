@@ -0,0 +1,62 @@
+use hashline_tools::*;
+
+#[test]
+fn test_builder_matches_json_edits() {
+    let built = edits()
+        .replace("2#TJ", ["two"])
+        .expecting(["TWO"])
+        .replace_range("4#QX", "5#ZP", ["four", "five"])
+        .append_after("6#MV", ["after six"])
+        .prepend_before(AnchorRef { line: 1, hash: "NK".to_string() }, ["before one"])
+        .delete("7#RS")
+        .delete_range("8#VW", "9#SN")
+        .append(["at end"])
+        .prepend(["at start"])
+        .build()
+        .unwrap();
+
+    let json: Vec<HashlineEdit> = serde_json::from_str(r#"[
+        {"op": "replace", "pos": "2#TJ", "lines": ["two"], "expected_lines": ["TWO"]},
+        {"op": "replace", "pos": "4#QX", "end": "5#ZP", "lines": ["four", "five"]},
+        {"op": "append", "pos": "6#MV", "lines": ["after six"]},
+        {"op": "prepend", "pos": "1#NK", "lines": ["before one"]},
+        {"op": "delete", "pos": "7#RS"},
+        {"op": "delete", "pos": "8#VW", "end": "9#SN"},
+        {"op": "append", "lines": ["at end"]},
+        {"op": "prepend", "lines": ["at start"]}
+    ]"#).unwrap();
+    assert_eq!(built, json);
+}
+
+#[test]
+fn test_builder_reports_every_error_with_its_index() {
+    let err = edits()
+        .replace("2TJ", ["x"])
+        .replace_range("5#QX", "4#ZP", ["y"])
+        .append_after("0#ZZ", ["z"])
+        .append(["ok"])
+        .expecting(["nope"])
+        .build()
+        .unwrap_err();
+
+    let HashlineError::Validation(errors) = err else { panic!("expected validation errors, got {}", err) };
+    assert_eq!(errors.len(), 4, "got: {:?}", errors);
+    assert!(errors[0].starts_with("edits[0]: Invalid anchor format '2TJ'"));
+    assert_eq!(errors[1], "edits[1]: range start line 5 must be <= end line 4");
+    assert_eq!(errors[2], "edits[2]: Line 0 must be >= 1");
+    assert_eq!(errors[3], "edits[3]: expected_lines needs an anchored edit");
+}
+
+#[test]
+fn test_builder_batch_applies() {
+    let content = "one\ntwo\nthree\n";
+    let file = HashedFile::from_content(content);
+    let batch = edits()
+        .replace(file.anchor(2).unwrap(), ["TWO"])
+        .delete(file.anchor(3).unwrap())
+        .build()
+        .unwrap();
+
+    let (result, _) = apply_hashline_edits(content, &batch).unwrap();
+    assert_eq!(result, "one\nTWO\n");
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+fn read(path: &str, options: &ReadOptions) -> String {
+    let mut out = Vec::new();
+    cmd_read_with_options(&mut out, path, options).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_line_byte_offsets() {
+    let offsets = |content| line_byte_offsets(content).collect::<Vec<_>>();
+    assert_eq!(offsets(""), Vec::<usize>::new());
+    assert_eq!(offsets("a\r\nbé\n\nd"), vec![0, 3, 7, 8]);
+    assert_eq!(offsets("a\n"), vec![0]);
+    assert_eq!(offsets("\n\n"), vec![0, 1]);
+    assert_eq!(line_byte_offsets("a\r\nbé\n\nd").count(), annotate_lines("a\r\nbé\n\nd").count());
+}
+
+#[test]
+fn test_read_prefixes_lines_with_byte_offsets() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("f.txt");
+    fs::write(&path, "a\r\nbé\n\nd\ne").unwrap();
+    let path = path.to_str().unwrap();
+    let file = HashedFile::from_content("a\r\nbé\n\nd\ne");
+
+    let options = ReadOptions { offset: Some(1), limit: Some(2), byte_offsets: true, ..ReadOptions::default() };
+    let output = read(path, &options);
+    assert!(output.contains(&format!("\n@3 {}:bé\n@7 {}:\n", file.anchor(2).unwrap(), file.anchor(3).unwrap())), "{}", output);
+    assert!(output.contains(". File is 11 bytes)\nNext: "), "{}", output);
+    assert!(output.contains(" --byte-offsets --offset 3 --limit 2"), "{}", output);
+
+    let output = read(path, &ReadOptions { offset: Some(3), byte_offsets: true, ..ReadOptions::default() });
+    assert!(output.contains(&format!("@8 {}:d\n@10 {}:e\n", file.anchor(4).unwrap(), file.anchor(5).unwrap())), "{}", output);
+    assert!(output.ends_with("(End of file - 5 total lines, 11 bytes)\n</file>"), "{}", output);
+
+    // Streamed and cached reads agree
+    for options in [
+        ReadOptions { memory_budget: Some(1), ..options.clone() },
+        ReadOptions { hash_cache: Some(HashCache::new(dir.path().join("cache"))), ..options.clone() },
+    ] {
+        assert_eq!(read(path, &options), read(path, &ReadOptions { byte_offsets: true, offset: Some(1), limit: Some(2), ..ReadOptions::default() }));
+    }
+}
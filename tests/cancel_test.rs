@@ -0,0 +1,39 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+#[test]
+fn test_cancelled_search_stops() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("a.txt"), "needle\n").unwrap();
+    let cancel = CancelToken::new();
+    let options = SearchOptions { recursive: true, cancel: cancel.clone(), ..SearchOptions::default() };
+    assert_eq!(cmd_search(&mut Vec::new(), dir.path().to_str().unwrap(), "needle", &options), Ok(1));
+
+    cancel.cancel();
+    let mut out = Vec::new();
+    let err = cmd_search(&mut out, dir.path().to_str().unwrap(), "needle", &options).unwrap_err();
+    assert_eq!(err, CANCELLED_MESSAGE);
+    assert!(out.is_empty());
+}
+
+#[test]
+fn test_cancelled_streaming_read_stops() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("big.txt");
+    fs::write(&path, "line\n".repeat(100)).unwrap();
+    let path = path.to_str().unwrap();
+    let cancel = CancelToken::new();
+    let options = ReadOptions { memory_budget: Some(16), cancel: cancel.clone(), ..ReadOptions::default() };
+    cmd_read_with_options(&mut Vec::new(), path, &options).unwrap();
+
+    cancel.cancel();
+    assert!(cancel.is_cancelled());
+    assert_eq!(cmd_read_with_options(&mut Vec::new(), path, &options).unwrap_err(), CANCELLED_MESSAGE);
+    // Only streaming reads check the token
+    let whole = ReadOptions { memory_budget: None, ..options };
+    cmd_read_with_options(&mut Vec::new(), path, &whole).unwrap();
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "cli")]
+
+use clap::Parser;
+use hashline_tools::*;
+
+fn shorthand(args: &[&str]) -> EditShorthand {
+    let cli = Cli::try_parse_from(["hashline-tools", "edit", "f.txt"].iter().chain(args)).unwrap();
+    match cli.command {
+        Commands::Edit { shorthand, .. } => shorthand,
+        _ => unreachable!(),
+    }
+}
+
+fn parsed(args: &[&str]) -> Vec<HashlineEdit> {
+    parse_edits(&shorthand(args).to_edits_json().unwrap().unwrap()).unwrap()
+}
+
+#[test]
+fn test_shorthand_flags_build_edits() {
+    let a: AnchorRef = "4#KX".parse().unwrap();
+    let b: AnchorRef = "9#MB".parse().unwrap();
+    assert_eq!(parsed(&["--replace", "4#KX", "one\ntwo"]), vec![HashlineEdit::Replace {
+        pos: a.clone(), end: None, lines: vec!["one".to_string(), "two".to_string()], expected_lines: None,
+    }]);
+    assert_eq!(parsed(&["--delete", "4#KX..9#MB"]), vec![HashlineEdit::Delete {
+        pos: a.clone(), end: Some(b), expected_lines: None,
+    }]);
+    assert_eq!(parsed(&["--insert-before", "4#KX", "@@decorator"]), vec![HashlineEdit::Prepend {
+        pos: Some(a), lines: vec!["@decorator".to_string()], expected_lines: None,
+    }]);
+    assert!(shorthand(&[]).to_edits_json().unwrap().is_none());
+}
+
+#[test]
+fn test_shorthand_reads_snippet_files() {
+    let dir = std::env::temp_dir().join(format!("hashline-shorthand-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let snippet = dir.join("snippet.txt");
+    std::fs::write(&snippet, "x\ny\n").unwrap();
+
+    let text = format!("@{}", snippet.display());
+    let edits = parsed(&["--insert-after", "20#SN", &text]);
+    assert!(matches!(&edits[..], [HashlineEdit::Append { lines, .. }] if lines == &["x", "y"]));
+
+    let missing = format!("@{}", dir.join("missing.txt").display());
+    assert!(shorthand(&["--insert-after", "20#SN", &missing]).to_edits_json().is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_shorthand_conflicts_with_edits_json() {
+    let result = Cli::try_parse_from(["hashline-tools", "edit", "f.txt", "--edits", "[]", "--delete", "4#KX"]);
+    assert!(result.is_err());
+}
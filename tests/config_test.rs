@@ -0,0 +1,107 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+
+use clap::{CommandFactory, FromArgMatches};
+use hashline_tools::*;
+use tempfile::TempDir;
+
+fn load(text: &str) -> Result<Config, String> {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    fs::write(&path, text).unwrap();
+    Config::load(&path)
+}
+
+fn parse(config: &Config, args: &[&str]) -> Cli {
+    let matches = config.apply_defaults(Cli::command()).try_get_matches_from(["hashline-tools"].iter().chain(args)).unwrap();
+    Cli::from_arg_matches(&matches).unwrap()
+}
+
+#[test]
+fn test_config_file_parses() {
+    let config = load(
+        "# Team defaults\nanchors = \"scoped\"\nformat = \"json\"\nmismatch_context = 4\n\
+         editorconfig = false\ncheckpoints = false\nmemory_budget = \"512M\"\nverify_cmd = 'cargo check'\n",
+    ).unwrap();
+    assert_eq!(config, Config {
+        anchors: Some(AnchorMode::Scoped),
+        format: Some(OutputFormat::Json),
+        mismatch_context: Some(4),
+        editorconfig: Some(false),
+        checkpoints: Some(false),
+        memory_budget: Some(512 * 1024 * 1024),
+        verify_cmd: Some("cargo check".to_string()),
+        ..Config::default()
+    });
+    assert_eq!(load("memory_budget = 4096\n").unwrap().memory_budget, Some(4096));
+    assert_eq!(load("").unwrap(), Config::default());
+}
+
+#[test]
+fn test_config_file_with_crlf_and_multi_line_strings() {
+    let config = load("# Team defaults\r\nanchors = \"scoped\"\r\nformat = \"json\"   # for the agent\r\n").unwrap();
+    assert_eq!((config.anchors, config.format), (Some(AnchorMode::Scoped), Some(OutputFormat::Json)));
+
+    let config = load("verify_cmd = \"\"\"\r\ncargo test -- \"a b\" \\\"c\\\" \"\"\"\"\r\n").unwrap();
+    assert_eq!(config.verify_cmd.as_deref(), Some("cargo test -- \"a b\" \"c\" \""));
+}
+
+#[test]
+fn test_config_file_errors() {
+    let err = load("anchor = \"scoped\"\n").unwrap_err();
+    assert!(err.contains("unknown field `anchor`"), "{}", err);
+    assert!(load("format = \"yaml\"\n").unwrap_err().starts_with("Invalid config "));
+    assert!(load("memory_budget = \"lots\"\n").is_err());
+    assert!(Config::load(std::path::Path::new("/nonexistent/config.toml")).unwrap_err().starts_with("Failed to read config "));
+}
+
+#[test]
+fn test_config_values_become_flag_defaults() {
+    let config = Config {
+        anchors: Some(AnchorMode::Scoped),
+        format: Some(OutputFormat::Json),
+        mismatch_context: Some(7),
+        diff_format: Some(DiffFormat::Unified),
+        editorconfig: Some(false),
+        memory_budget: Some(1024),
+        max_payload: Some(4096),
+        ..Config::default()
+    };
+    let cli = parse(&config, &["edit", "f.txt"]);
+    assert_eq!(cli.anchors, AnchorMode::Scoped);
+    assert!(cli.no_editorconfig);
+    assert_eq!(cli.memory_budget, Some(1024));
+    assert_eq!(cli.max_payload, 4096);
+    match cli.command {
+        Commands::Edit { format, mismatch_context, diff_format, .. } => {
+            assert_eq!((format, mismatch_context, diff_format), (OutputFormat::Json, 7, DiffFormat::Unified));
+        }
+        _ => unreachable!(),
+    }
+
+    // Flags on the command line still win
+    let cli = parse(&config, &["--anchors", "chained", "--memory-budget", "2K", "edit", "f.txt", "--format", "text"]);
+    assert_eq!((cli.anchors, cli.memory_budget), (AnchorMode::Chained, Some(2048)));
+    match cli.command {
+        Commands::Edit { format, .. } => assert_eq!(format, OutputFormat::Text),
+        _ => unreachable!(),
+    }
+
+    let cli = parse(&Config::default(), &["edit", "f.txt"]);
+    assert_eq!((cli.anchors, cli.no_editorconfig, cli.memory_budget), (AnchorMode::Chained, false, None));
+}
+
+#[test]
+fn test_fuzzy_settings_from_config_and_flags() {
+    let config = load("fuzzy_threshold = 0.85\nfuzzy_max_candidates = 2\n").unwrap();
+    let cli = parse(&config, &["edit", "f.txt"]);
+    assert_eq!((cli.fuzzy_threshold, cli.fuzzy_max_candidates), (0.85, Some(2)));
+    let cli = parse(&config, &["--fuzzy-threshold", "0.5", "edit", "f.txt"]);
+    assert_eq!(cli.fuzzy_threshold, 0.5);
+
+    let cli = parse(&Config::default(), &["edit", "f.txt"]);
+    assert_eq!((cli.fuzzy_threshold, cli.fuzzy_max_candidates), (DEFAULT_FUZZY_THRESHOLD, None));
+    let err = Cli::command().try_get_matches_from(["hashline-tools", "--fuzzy-threshold", "1.5", "edit", "f.txt"]).unwrap_err();
+    assert!(err.to_string().contains("expected a number from 0.0 to 1.0"), "{}", err);
+}
@@ -0,0 +1,95 @@
+use hashline_tools::*;
+#[cfg(feature = "cli")]
+use std::fs;
+#[cfg(feature = "cli")]
+use std::io::Write;
+#[cfg(feature = "cli")]
+use tempfile::NamedTempFile;
+
+// Helper function to compute cumulative hashes for a file and get a specific line's hash
+fn get_line_hash(content: &str, line_num: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut prev_hash: Option<&str> = None;
+    let mut cumulative_hashes: Vec<String> = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let ln = i + 1;
+        let hash = compute_line_hash(ln, line, prev_hash);
+        cumulative_hashes.push(hash);
+        prev_hash = Some(&cumulative_hashes[i]);
+    }
+
+    cumulative_hashes[line_num - 1].clone()
+}
+
+fn conflict_options() -> ApplyOptions {
+    ApplyOptions { conflict_markers: true, ..ApplyOptions::default() }
+}
+
+#[test]
+fn test_stale_replace_becomes_conflict_block() {
+    let content = "one\ntwo\nthree\nfour\n";
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 2, hash: "ZZ".to_string() },
+            end: None,
+            lines: vec!["TWO".to_string()],
+            expected_lines: None,
+        },
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 4, hash: get_line_hash(content, 4) },
+            end: None,
+            lines: vec!["FOUR".to_string()],
+            expected_lines: None,
+        },
+    ];
+
+    let outcome = apply_hashline_edits_with_options(content, &edits, &conflict_options()).unwrap();
+    assert_eq!(
+        outcome.content,
+        "one\n<<<<<<< current (line 2)\ntwo\n=======\nTWO\n>>>>>>> edits[0] (stale anchor 2#ZZ)\nthree\nFOUR\n"
+    );
+    assert!(outcome.statuses[0].is_conflicted());
+    assert!(outcome.statuses[1].is_applied());
+}
+
+#[test]
+fn test_stale_append_keeps_anchor_line_on_both_sides() {
+    let content = "one\ntwo\n";
+    let edits = vec![
+        HashlineEdit::Append {
+            pos: Some(AnchorRef { line: 1, hash: "ZZ".to_string() }),
+            lines: vec!["inserted".to_string()],
+            expected_lines: None,
+        },
+    ];
+
+    let outcome = apply_hashline_edits_with_options(content, &edits, &conflict_options()).unwrap();
+    assert_eq!(
+        outcome.content,
+        "<<<<<<< current (line 1)\none\n=======\none\ninserted\n>>>>>>> edits[0] (stale anchor 1#ZZ)\ntwo\n"
+    );
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_cmd_edit_print_conflicts_leaves_file_untouched() {
+    let content = "one\ntwo\nthree\n";
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(temp_file, "{}", content).unwrap();
+    let path = temp_file.path().to_str().unwrap().to_string();
+
+    let options = EditOptions {
+        apply: conflict_options(),
+        print_conflicts: true,
+        ..EditOptions::default()
+    };
+    let output = cmd_edit_with_options(
+        &path,
+        r#"[{"op": "replace", "pos": "2#ZZ", "end": "3#ZZ", "lines": ["new"]}]"#,
+        &options,
+    ).unwrap();
+
+    assert!(output.starts_with("one\n<<<<<<< current (lines 2-3)\ntwo\nthree\n=======\nnew\n>>>>>>>"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), content);
+}
@@ -0,0 +1,61 @@
+use hashline_tools::*;
+
+const CONTENT: &str = "one\ntwo\nthree\nfour\nfive\n";
+
+fn anchor(file: &HashedFile, line: usize) -> AnchorRef {
+    file.anchor(line).unwrap()
+}
+
+#[test]
+fn test_delete_removes_lines_and_diffs_as_deletions() {
+    let file = HashedFile::from_content(CONTENT);
+    let edits = vec![
+        HashlineEdit::Delete { pos: anchor(&file, 2), end: Some(anchor(&file, 3)), expected_lines: None },
+        HashlineEdit::Delete { pos: anchor(&file, 5), end: None, expected_lines: None },
+    ];
+    let applied = apply_and_diff(CONTENT, &edits, &ApplyOptions::default()).unwrap();
+    assert_eq!(applied.outcome.content, "one\nfour\n");
+    assert_eq!(applied.outcome.first_changed_line, Some(2));
+
+    let changes: Vec<(DiffLineKind, &str)> = applied.diff.iter()
+        .flat_map(|h| &h.lines)
+        .filter(|l| l.kind != DiffLineKind::Context)
+        .map(|l| (l.kind, l.text.as_str()))
+        .collect();
+    assert_eq!(changes, [(DiffLineKind::Delete, "two"), (DiffLineKind::Delete, "three"), (DiffLineKind::Delete, "five")]);
+    assert!(applied.changed.is_empty());
+}
+
+#[test]
+fn test_delete_overlaps_and_duplicates() {
+    let file = HashedFile::from_content(CONTENT);
+    let delete = HashlineEdit::Delete { pos: anchor(&file, 2), end: Some(anchor(&file, 4)), expected_lines: None };
+    let replace = HashlineEdit::Replace { pos: anchor(&file, 3), end: None, lines: vec!["3".into()], expected_lines: None };
+    let err = apply_hashline_edits(CONTENT, &[delete.clone(), replace]).unwrap_err();
+    assert!(err.to_string().contains("edits[0] delete at lines 2-4 overlaps with edits[1] replace [\"3\"]"), "{}", err);
+
+    // A replace with no lines makes the same change, so it collapses into the delete
+    let empty = HashlineEdit::Replace { pos: anchor(&file, 2), end: Some(anchor(&file, 4)), lines: vec![], expected_lines: None };
+    let outcome = apply_hashline_edits_with_options(CONTENT, &[delete.clone(), empty], &ApplyOptions::default()).unwrap();
+    assert_eq!(outcome.content, "one\nfive\n");
+    assert_eq!(outcome.statuses[1], EditStatus::Duplicate { of: 0 });
+}
+
+#[test]
+fn test_delete_checks_expected_lines_and_stale_anchors() {
+    let file = HashedFile::from_content(CONTENT);
+    let stale = AnchorRef { line: 2, hash: anchor(&file, 1).hash };
+
+    // Content that still matches vouches for a stale anchor
+    let vouched = HashlineEdit::Delete { pos: stale.clone(), end: None, expected_lines: Some(vec!["two".into()]) };
+    assert_eq!(apply_hashline_edits(CONTENT, &[vouched]).unwrap().0, "one\nthree\nfour\nfive\n");
+
+    let edit = HashlineEdit::Delete { pos: stale, end: None, expected_lines: None };
+    let err = apply_hashline_edits(CONTENT, std::slice::from_ref(&edit)).unwrap_err();
+    assert!(matches!(err, HashlineError::Mismatch(_)), "{}", err);
+
+    // In conflict-marker mode the stale delete shows the line against nothing
+    let options = ApplyOptions { conflict_markers: true, ..ApplyOptions::default() };
+    let outcome = apply_hashline_edits_with_options(CONTENT, &[edit], &options).unwrap();
+    assert!(outcome.content.contains("<<<<<<< current (line 2)\ntwo\n=======\n>>>>>>> edits[0]"), "{}", outcome.content);
+}
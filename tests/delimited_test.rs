@@ -0,0 +1,107 @@
+#[cfg(feature = "cli")]
+use std::fs;
+
+use hashline_tools::*;
+#[cfg(feature = "cli")]
+use tempfile::TempDir;
+
+const PEOPLE_CSV: &str = "\
+id,name,email,city
+1,\"Lee, Ann\",ann@example.com,Oslo
+2,Bob,bob@example.com,\"Bergen\"
+3,Cy,,Tromsø
+";
+
+fn anchor(content: &str, line: usize) -> AnchorRef {
+    HashedFile::from_content(content).anchor(line).unwrap()
+}
+
+fn apply(content: &str, batch: &[HashlineEdit]) -> Result<ApplyOutcome, HashlineError> {
+    apply_hashline_edits_with_options(content, batch, &ApplyOptions::default())
+}
+
+#[test]
+fn test_rows_split_into_fields_with_their_quoting() {
+    assert_eq!(detect_delimiter(PEOPLE_CSV), ',');
+    assert_eq!(detect_delimiter("a\tb\tc\n1\t2\t3\n"), '\t');
+    assert_eq!(detect_delimiter("\"a;b\",c;d;e\n"), ';');
+    assert_eq!(detect_delimiter("single column\n"), ',');
+
+    let fields = split_fields("1,\"Lee, Ann\",\"say \"\"hi\"\"\",", ',').unwrap();
+    let values: Vec<&str> = fields.iter().map(|f| f.value.as_str()).collect();
+    assert_eq!(values, ["1", "Lee, Ann", "say \"hi\"", ""]);
+    assert_eq!(fields[1].span, 2..12);
+    assert!(fields[1].quoted && !fields[3].quoted);
+
+    assert!(split_fields("1,\"open", ',').unwrap_err().contains("does not close on this line"));
+    assert!(split_fields("\"a\"b,c", ',').unwrap_err().contains("text after its closing quote"));
+}
+
+#[test]
+fn test_set_cell_rewrites_one_field() {
+    let batch = edits().set_cell(anchor(PEOPLE_CSV, 3), "email", "robert@example.com").build().unwrap();
+    let outcome = apply(PEOPLE_CSV, &batch).unwrap();
+    assert_eq!(outcome.content, PEOPLE_CSV.replace("bob@example.com", "robert@example.com"));
+    assert_eq!(outcome.first_changed_line, Some(3));
+
+    // A quoted field stays quoted, and a value with the delimiter gets quotes
+    let batch = edits().set_cell(anchor(PEOPLE_CSV, 3), 3, "Bodø").build().unwrap();
+    assert!(apply(PEOPLE_CSV, &batch).unwrap().content.contains(",\"Bodø\"\n"));
+    let batch = edits().set_cell(anchor(PEOPLE_CSV, 4), "name", "Cy \"C\", Jr").build().unwrap();
+    assert!(apply(PEOPLE_CSV, &batch).unwrap().content.contains("3,\"Cy \"\"C\"\", Jr\",,Tromsø\n"));
+
+    let tsv = "a\tb\n1\tx,y\n";
+    let batch = edits().set_cell(anchor(tsv, 2), "b", "z,w").build().unwrap();
+    assert_eq!(apply(tsv, &batch).unwrap().content, "a\tb\n1\tz,w\n");
+}
+
+#[test]
+fn test_set_column_rewrites_a_row_range() {
+    let batch = edits().set_column(anchor(PEOPLE_CSV, 2), anchor(PEOPLE_CSV, 4), "city", ["Oslo"]).build().unwrap();
+    let edited = apply(PEOPLE_CSV, &batch).unwrap().content;
+    assert_eq!(edited, PEOPLE_CSV.replace("\"Bergen\"", "\"Oslo\"").replace("Tromsø", "Oslo"));
+
+    let batch = parse_edits(&format!(
+        r#"[{{"op": "set_column", "pos": "{}", "end": "{}", "column": 0, "values": ["10", "20"]}}]"#,
+        anchor(PEOPLE_CSV, 2),
+        anchor(PEOPLE_CSV, 3),
+    )).unwrap();
+    let edited = apply(PEOPLE_CSV, &batch).unwrap().content;
+    assert!(edited.contains("\n10,\"Lee, Ann\",") && edited.contains("\n20,Bob,"));
+}
+
+#[test]
+fn test_cell_edit_errors_name_the_problem() {
+    let err = |batch: Vec<HashlineEdit>| apply(PEOPLE_CSV, &batch).unwrap_err().to_string();
+    let missing = edits().set_cell(anchor(PEOPLE_CSV, 2), "phone", "1").build().unwrap();
+    assert!(err(missing).contains("set_cell: no column `phone` in the header row (id, name, email, city)"));
+    let short = edits().set_cell(anchor(PEOPLE_CSV, 2), 7, "1").build().unwrap();
+    assert!(err(short).contains("row 2 has 4 fields, so no column 7"));
+    let counts = edits().set_column(anchor(PEOPLE_CSV, 2), anchor(PEOPLE_CSV, 4), 0, ["1", "2"]).build().unwrap();
+    assert!(err(counts).contains("2 values for 3 rows"));
+    let multiline = edits().set_cell(anchor(PEOPLE_CSV, 2), 0, "a\nb").build().unwrap();
+    assert!(err(multiline).contains("values must fit on their row"));
+
+    let stale = edits().set_cell("2#ZZ", "name", "Ann").build().unwrap();
+    assert!(matches!(apply(PEOPLE_CSV, &stale), Err(HashlineError::Mismatch(_))));
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_read_columns_labels_each_field() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("people.csv");
+    fs::write(&path, PEOPLE_CSV).unwrap();
+
+    let options = ReadOptions { columns: true, ..ReadOptions::default() };
+    let mut out = Vec::new();
+    cmd_read_with_options(&mut out, path.to_str().unwrap(), &options).unwrap();
+    let output = String::from_utf8(out).unwrap();
+    assert_eq!(output, format!(
+        "<table delimiter=\",\" columns=\"4\">\n{}:id,name,email,city\n{}:id=1 | name=\"Lee, Ann\" | email=ann@example.com | city=Oslo\n{}:id=2 | name=Bob | email=bob@example.com | city=\"Bergen\"\n{}:id=3 | name=Cy | email= | city=Tromsø\n\n(End of file - 4 total lines)\n</table>",
+        anchor(PEOPLE_CSV, 1),
+        anchor(PEOPLE_CSV, 2),
+        anchor(PEOPLE_CSV, 3),
+        anchor(PEOPLE_CSV, 4),
+    ));
+}
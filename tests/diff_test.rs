@@ -0,0 +1,107 @@
+use hashline_tools::*;
+
+#[test]
+fn test_hash_aware_diff_structure() {
+    let old = "one\ntwo\nthree\n";
+    let new = "one\nTWO\nthree\n";
+    let file = HashedFile::from_content(new);
+
+    let hunks = hash_aware_diff(old, new, 2);
+    assert_eq!(hunks.len(), 1);
+    let kinds: Vec<(DiffLineKind, usize)> = hunks[0].lines.iter().map(|l| (l.kind, l.line)).collect();
+    assert_eq!(kinds, vec![
+        (DiffLineKind::Context, 1),
+        (DiffLineKind::Delete, 2),
+        (DiffLineKind::Insert, 2),
+        (DiffLineKind::Context, 3),
+    ]);
+    assert_eq!(hunks[0].lines[1].hash, None);
+    assert_eq!(hunks[0].lines[1].text, "two");
+    assert_eq!(hunks[0].lines[2].hash.as_deref(), file.hash_at(2));
+    assert_eq!(hunks[0].lines[2].to_string(), format!("+2#{}:TWO", file.hash_at(2).unwrap()));
+}
+
+#[test]
+fn test_distant_changes_render_as_separate_hunks() {
+    let old: String = (1..=30).map(|i| format!("line {}\n", i)).collect();
+    let new = old.replace("line 2\n", "line two\n").replace("line 28\n", "line twenty-eight\n");
+
+    let hunks = hash_aware_diff(&old, &new, 2);
+    assert_eq!(hunks.len(), 2);
+    let rendered = generate_hash_aware_diff(&old, &new, 2);
+    assert_eq!(rendered.matches("\n...\n").count(), 1);
+    assert!(rendered.ends_with("Lines not shown from line 2 on have new hashes too; re-read them before anchoring to them."));
+}
+
+#[test]
+fn test_apply_and_diff_returns_refreshed_anchors() {
+    let content = "one\ntwo\nthree\n";
+    let file = HashedFile::from_content(content);
+    let batch = edits().replace(file.anchor(2).unwrap(), ["TWO", "2.5"]).build().unwrap();
+
+    let applied = apply_and_diff(content, &batch, &ApplyOptions::default()).unwrap();
+    assert_eq!(applied.outcome.content, "one\nTWO\n2.5\nthree\n");
+    assert_eq!(applied.file, HashedFile::from_content(&applied.outcome.content));
+    let changed: Vec<&str> = applied.changed.iter().map(|a| a.text.as_str()).collect();
+    assert_eq!(changed, vec!["TWO", "2.5"]);
+    assert_eq!(applied.diff, hash_aware_diff(content, &applied.outcome.content, 2));
+
+    // The refreshed anchors are valid for the next batch
+    let next = edits().delete(applied.file.anchor(4).unwrap()).build().unwrap();
+    let applied = apply_and_diff(&applied.outcome.content, &next, &ApplyOptions::default()).unwrap();
+    assert_eq!(applied.outcome.content, "one\nTWO\n2.5\n");
+
+    let unchanged = apply_and_diff(content, &[], &ApplyOptions::default()).unwrap();
+    assert!(unchanged.diff.is_empty() && unchanged.changed.is_empty());
+}
+
+#[test]
+fn test_diff_of_a_local_edit_in_a_large_file() {
+    let old: String = (1..=100_000).map(|i| format!("line {}\n", i % 7)).collect();
+    let mut lines: Vec<&str> = old.lines().collect();
+    lines.insert(50_000, "inserted");
+    lines[10] = "changed";
+    let new = lines.join("\n") + "\n";
+    let file = HashedFile::from_content(&new);
+
+    let hunks = hash_aware_diff(&old, &new, 11);
+    let changes: Vec<(DiffLineKind, usize)> = hunks.iter()
+        .flat_map(|h| &h.lines)
+        .filter(|l| l.kind != DiffLineKind::Context)
+        .map(|l| (l.kind, l.line))
+        .collect();
+    assert_eq!(hunks.len(), 2);
+    assert_eq!(changes, vec![(DiffLineKind::Delete, 11), (DiffLineKind::Insert, 11), (DiffLineKind::Insert, 50_001)]);
+
+    let changed: Vec<(usize, String)> = changed_line_anchors(&old, &new).into_iter()
+        .map(|a| (a.anchor.line, a.text))
+        .collect();
+    assert_eq!(changed, vec![(11, "changed".to_string()), (50_001, "inserted".to_string())]);
+    assert_eq!(hunks[1].lines.iter().find(|l| l.text == "inserted").unwrap().hash.as_deref(), file.hash_at(50_001));
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_edit_diff_formats() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("a.txt");
+    let path_str = path.to_str().unwrap();
+    let content = "one\ntwo\nthree\n";
+    let anchor = HashedFile::from_content(content).anchor(2).unwrap().to_string();
+    let edits = format!(r#"[{{"op": "replace", "pos": "{}", "lines": ["TWO"]}}]"#, anchor);
+
+    std::fs::write(&path, content).unwrap();
+    let options = EditOptions { diff_format: DiffFormat::Unified, ..EditOptions::default() };
+    let output = cmd_edit_with_options(path_str, &edits, &options).unwrap();
+    let expected = format!("--- {0}\n+++ {0}\n@@ -1,3 +1,3 @@\n one\n-two\n+TWO\n three\n", path_str);
+    assert!(output.ends_with(&format!("\n\n<diff>\n{}</diff>", expected)), "{}", output);
+    assert_eq!(unified_diff(content, "one\nTWO\nthree\n", path_str, path_str), expected);
+
+    std::fs::write(&path, content).unwrap();
+    let options = EditOptions { diff_format: DiffFormat::None, dry_run: true, ..EditOptions::default() };
+    let output = cmd_edit_with_options(path_str, &edits, &options).unwrap();
+    assert!(!output.contains("<diff>") && output.contains("Anchors after the edit:"), "{}", output);
+    let options = EditOptions { format: OutputFormat::Json, ..options };
+    let json: serde_json::Value = serde_json::from_str(&cmd_edit_with_options(path_str, &edits, &options).unwrap()).unwrap();
+    assert!(json["diff"].is_null());
+}
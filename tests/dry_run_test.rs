@@ -0,0 +1,54 @@
+use hashline_tools::*;
+#[cfg(feature = "cli")]
+use std::fs;
+#[cfg(feature = "cli")]
+use std::io::Write;
+#[cfg(feature = "cli")]
+use tempfile::NamedTempFile;
+
+// Helper function to compute cumulative hashes for a file and get a specific line's hash
+fn get_line_hash(content: &str, line_num: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut prev_hash: Option<&str> = None;
+    let mut cumulative_hashes: Vec<String> = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let ln = i + 1;
+        let hash = compute_line_hash(ln, line, prev_hash);
+        cumulative_hashes.push(hash);
+        prev_hash = Some(&cumulative_hashes[i]);
+    }
+
+    cumulative_hashes[line_num - 1].clone()
+}
+
+#[test]
+fn test_changed_line_anchors() {
+    let old = "one\ntwo\nthree\n";
+    let new = "one\nTWO\n2.5\nthree\n";
+    let anchors = changed_line_anchors(old, new);
+
+    let lines: Vec<(usize, &str)> = anchors.iter().map(|a| (a.anchor.line, a.text.as_str())).collect();
+    assert_eq!(lines, vec![(2, "TWO"), (3, "2.5")]);
+    assert_eq!(anchors[0].anchor.hash, get_line_hash(new, 2));
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_cmd_edit_dry_run_does_not_write() {
+    let content = "one\ntwo\nthree\n";
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(temp_file, "{}", content).unwrap();
+    let path = temp_file.path().to_str().unwrap().to_string();
+
+    let options = EditOptions { dry_run: true, format: OutputFormat::Json, ..EditOptions::default() };
+    let edits = format!(r#"[{{"op": "replace", "pos": "2#{}", "lines": ["TWO"]}}]"#, get_line_hash(content, 2));
+    let output = cmd_edit_with_options(&path, &edits, &options).unwrap();
+
+    let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(value["status"], "dry_run");
+    let expected_anchor = format!("2#{}", get_line_hash("one\nTWO\nthree\n", 2));
+    assert_eq!(value["anchors"][0]["anchor"], expected_anchor);
+    assert_eq!(value["anchors"][0]["text"], "TWO");
+    assert_eq!(fs::read_to_string(&path).unwrap(), content);
+}
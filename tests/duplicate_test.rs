@@ -16,7 +16,6 @@ fn get_line_hash(content: &str, line_num: usize) -> String {
     cumulative_hashes[line_num - 1].clone()
 }
 
-use hashline_tools::*;
 
 #[test]
 fn test_replace_single_line_no_duplicate() {
@@ -26,6 +25,7 @@ fn test_replace_single_line_no_duplicate() {
             pos: AnchorRef { line: 2, hash: get_line_hash(content, 2) },
             end: None,
             lines: vec!["REPLACED".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -45,6 +45,7 @@ fn test_replace_range_no_duplicate() {
             pos: AnchorRef { line: 2, hash: get_line_hash(content, 2) },
             end: Some(AnchorRef { line: 4, hash: get_line_hash(content, 4) }),
             lines: vec!["replaced".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -63,6 +64,7 @@ fn test_append_after_no_duplicate() {
         HashlineEdit::Append {
             pos: Some(AnchorRef { line: 2, hash: get_line_hash(content, 2) }),
             lines: vec!["line 3".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -79,11 +81,13 @@ fn test_multiple_edits_no_duplicate() {
             pos: AnchorRef { line: 2, hash: get_line_hash(content, 2) },
             end: None,
             lines: vec!["modified 2".to_string()],
+            expected_lines: None,
         },
         HashlineEdit::Replace {
             pos: AnchorRef { line: 4, hash: get_line_hash(content, 4) },
             end: None,
             lines: vec!["modified 4".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -103,6 +107,7 @@ fn test_replace_preserves_all_lines() {
             pos: AnchorRef { line: 2, hash: get_line_hash(content, 2) },
             end: None,
             lines: vec!["    // modified comment".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -123,6 +128,7 @@ fn test_replace_preserves_trailing_newline() {
             pos: AnchorRef { line: 2, hash: get_line_hash(content, 2) },
             end: None,
             lines: vec!["    // modified".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -145,11 +151,13 @@ fn test_overlapping_edits_rejected() {
             pos: AnchorRef { line: 2, hash: get_line_hash(content, 2) },
             end: Some(AnchorRef { line: 4, hash: get_line_hash(content, 4) }),
             lines: vec!["replaced range".to_string()],
+            expected_lines: None,
         },
         HashlineEdit::Replace {
             pos: AnchorRef { line: 3, hash: get_line_hash(content, 3) },
             end: None,
             lines: vec!["replaced single".to_string()],
+            expected_lines: None,
         },
     ];
     
@@ -161,6 +169,8 @@ fn test_overlapping_edits_rejected() {
     assert!(error.contains("Overlapping edits detected"), "Error should mention overlapping edits. Got: {}", error);
     assert!(error.contains("lines 2-4"), "Error should mention first range. Got: {}", error);
     assert!(error.contains("lines 3-3"), "Error should mention second range. Got: {}", error);
+    assert!(error.contains(r#"edits[0] replace ["replaced range"] at lines 2-4"#), "Error should identify the first edit. Got: {}", error);
+    assert!(error.contains(r#"edits[1] replace ["replaced single"] at lines 3-3"#), "Error should identify the second edit. Got: {}", error);
 }
 
 #[test]
@@ -173,11 +183,13 @@ fn test_non_overlapping_edits_succeed() {
             pos: AnchorRef { line: 1, hash: get_line_hash(content, 1) },
             end: Some(AnchorRef { line: 2, hash: get_line_hash(content, 2) }),
             lines: vec!["first range".to_string()],
+            expected_lines: None,
         },
         HashlineEdit::Replace {
             pos: AnchorRef { line: 4, hash: get_line_hash(content, 4) },
             end: Some(AnchorRef { line: 5, hash: get_line_hash(content, 5) }),
             lines: vec!["second range".to_string()],
+            expected_lines: None,
         },
     ];
     
@@ -204,11 +216,13 @@ fn test_adjacent_edits_succeed() {
             pos: AnchorRef { line: 1, hash: get_line_hash(content, 1) },
             end: Some(AnchorRef { line: 2, hash: get_line_hash(content, 2) }),
             lines: vec!["first".to_string()],
+            expected_lines: None,
         },
         HashlineEdit::Replace {
             pos: AnchorRef { line: 3, hash: get_line_hash(content, 3) },
             end: Some(AnchorRef { line: 4, hash: get_line_hash(content, 4) }),
             lines: vec!["second".to_string()],
+            expected_lines: None,
         },
     ];
     
@@ -234,6 +248,7 @@ fn test_cumulative_hash_prevents_stale_edit() {
             pos: AnchorRef { line: 2, hash: get_line_hash(content, 2) },
             end: None,
             lines: vec!["MODIFIED".to_string()],
+            expected_lines: None,
         }
     ];
     
@@ -251,6 +266,7 @@ fn test_cumulative_hash_prevents_stale_edit() {
             pos: AnchorRef { line: 2, hash: stale_hash },
             end: None,
             lines: vec!["SHOULD_FAIL".to_string()],
+            expected_lines: None,
         }
     ];
     
@@ -287,6 +303,7 @@ fn test_cumulative_hash_invalidation_chain() {
             pos: AnchorRef { line: 2, hash: h2.clone() },
             end: None,
             lines: vec!["MODIFIED".to_string()],
+            expected_lines: None,
         }
     ];
     
@@ -294,33 +311,36 @@ fn test_cumulative_hash_invalidation_chain() {
     
     // Now try to edit line 3 with its ORIGINAL hash
     // Should fail because line 3's hash depends on line 2's hash (cumulative)
-    let result2 = apply_hashline_edits(&result, &vec![
+    let result2 = apply_hashline_edits(&result, &[
         HashlineEdit::Replace {
             pos: AnchorRef { line: 3, hash: h3 },
             end: None,
             lines: vec!["should fail".to_string()],
+            expected_lines: None,
         }
     ]);
     
     assert!(result2.is_err(), "Edit at line 3 with stale hash should fail");
     
     // Similarly, try to edit line 4 with its ORIGINAL hash
-    let result3 = apply_hashline_edits(&result, &vec![
+    let result3 = apply_hashline_edits(&result, &[
         HashlineEdit::Replace {
             pos: AnchorRef { line: 4, hash: h4 },
             end: None,
             lines: vec!["should fail".to_string()],
+            expected_lines: None,
         }
     ]);
     
     assert!(result3.is_err(), "Edit at line 4 with stale hash should fail");
     
     // But editing line 1 should work (it's before the change)
-    let result4 = apply_hashline_edits(&result, &vec![
+    let result4 = apply_hashline_edits(&result, &[
         HashlineEdit::Replace {
             pos: AnchorRef { line: 1, hash: h1 },
             end: None,
             lines: vec!["line 1 modified".to_string()],
+            expected_lines: None,
         }
     ]);
     
@@ -336,10 +356,12 @@ fn test_overlapping_replace_and_prepend() {
             pos: AnchorRef { line: 1, hash: get_line_hash(content, 1) },
             end: None,
             lines: vec!["replaced".to_string()],
+            expected_lines: None,
         },
         HashlineEdit::Prepend {
             pos: Some(AnchorRef { line: 1, hash: get_line_hash(content, 1) }),
             lines: vec!["prepended".to_string()],
+            expected_lines: None,
         }
     ];
     let result = apply_hashline_edits(content, &edits);
@@ -354,10 +376,12 @@ fn test_overlapping_append_and_prepend_same_line() {
         HashlineEdit::Append {
             pos: Some(AnchorRef { line: 2, hash: get_line_hash(content, 2) }),
             lines: vec!["appended".to_string()],
+            expected_lines: None,
         },
         HashlineEdit::Prepend {
             pos: Some(AnchorRef { line: 2, hash: get_line_hash(content, 2) }),
             lines: vec!["prepended".to_string()],
+            expected_lines: None,
         }
     ];
     let result = apply_hashline_edits(content, &edits);
@@ -374,10 +398,12 @@ fn test_non_overlapping_replace_and_append() {
             pos: AnchorRef { line: 1, hash: get_line_hash(content, 1) },
             end: None,
             lines: vec!["replaced".to_string()],
+            expected_lines: None,
         },
         HashlineEdit::Append {
             pos: Some(AnchorRef { line: 1, hash: get_line_hash(content, 1) }),
             lines: vec!["appended".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -395,10 +421,12 @@ fn test_non_overlapping_replace_range_and_append() {
             pos: AnchorRef { line: 2, hash: get_line_hash(content, 2) },
             end: Some(AnchorRef { line: 3, hash: get_line_hash(content, 3) }),
             lines: vec!["replaced".to_string()],
+            expected_lines: None,
         },
         HashlineEdit::Append {
             pos: Some(AnchorRef { line: 3, hash: get_line_hash(content, 3) }),
             lines: vec!["appended".to_string()],
+            expected_lines: None,
         }
     ];
     // These don't overlap - append inserts at position 4, replace is at 2-3
@@ -416,10 +444,12 @@ fn test_overlapping_replace_range_with_prepend() {
             pos: AnchorRef { line: 2, hash: get_line_hash(content, 2) },
             end: Some(AnchorRef { line: 4, hash: get_line_hash(content, 4) }),
             lines: vec!["replaced".to_string()],
+            expected_lines: None,
         },
         HashlineEdit::Prepend {
             pos: Some(AnchorRef { line: 2, hash: get_line_hash(content, 2) }),
             lines: vec!["prepended".to_string()],
+            expected_lines: None,
         }
     ];
     let result = apply_hashline_edits(content, &edits);
@@ -435,10 +465,12 @@ fn test_non_overlapping_replace_different_lines() {
             pos: AnchorRef { line: 1, hash: get_line_hash(content, 1) },
             end: None,
             lines: vec!["replaced 1".to_string()],
+            expected_lines: None,
         },
         HashlineEdit::Append {
             pos: Some(AnchorRef { line: 3, hash: get_line_hash(content, 3) }),
             lines: vec!["appended".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -455,13 +487,152 @@ fn test_non_overlapping_append_eof_with_replace() {
             pos: AnchorRef { line: 1, hash: get_line_hash(content, 1) },
             end: None,
             lines: vec!["replaced".to_string()],
+            expected_lines: None,
         },
         HashlineEdit::Append {
             pos: None,
             lines: vec!["appended".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
     assert!(result.contains("replaced"));
     assert!(result.contains("appended"));
 }
+
+#[test]
+fn test_overlap_error_truncates_long_snippets() {
+    let content = "line 1\nline 2\nline 3\n";
+    let long_line = "x".repeat(100);
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 2, hash: get_line_hash(content, 2) },
+            end: None,
+            lines: vec![long_line, "second".to_string(), "third".to_string()],
+            expected_lines: None,
+        },
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 2, hash: get_line_hash(content, 2) },
+            end: None,
+            lines: vec![],
+            expected_lines: None,
+        },
+    ];
+
+    let error = apply_hashline_edits(content, &edits).unwrap_err().to_string();
+    let expected_first = format!("edits[0] replace [\"{}...\" +2 lines]", "x".repeat(40));
+    assert!(error.contains(&expected_first), "Got: {}", error);
+    assert!(error.contains("edits[1] replace [] at lines 2-2"), "Got: {}", error);
+}
+
+fn repeated_append(content: &str) -> Vec<HashlineEdit> {
+    let edit = HashlineEdit::Append {
+        pos: Some(AnchorRef { line: 1, hash: get_line_hash(content, 1) }),
+        lines: vec!["inserted".to_string()],
+        expected_lines: None,
+    };
+    vec![edit.clone(), edit]
+}
+
+#[test]
+fn test_duplicate_edits_are_reported() {
+    let content = "line 1\nline 2\n";
+    let outcome = apply_hashline_edits_with_options(content, &repeated_append(content), &ApplyOptions::default())
+        .unwrap();
+    assert_eq!(outcome.content, "line 1\ninserted\nline 2\n");
+    assert_eq!(outcome.statuses, vec![EditStatus::Applied, EditStatus::Duplicate { of: 0 }]);
+    assert_eq!(outcome.statuses[1].to_string(), "duplicate of edits[0]");
+}
+
+#[test]
+fn test_no_dedupe_reports_repeats_as_overlaps() {
+    let content = "line 1\nline 2\n";
+    let options = ApplyOptions { dedupe: false, ..ApplyOptions::default() };
+    // Without collapsing, the repeat collides with the original
+    let error = apply_hashline_edits_with_options(content, &repeated_append(content), &options)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("Overlapping edits detected"), "Got: {}", error);
+}
+
+fn merge_options() -> ApplyOptions {
+    ApplyOptions { merge_overlaps: true, ..ApplyOptions::default() }
+}
+
+#[test]
+fn test_merge_overlaps_absorbs_prepend_prefix() {
+    let content = "line 1\nline 2\nline 3\n";
+    let edits = vec![
+        HashlineEdit::Prepend {
+            pos: Some(AnchorRef { line: 2, hash: get_line_hash(content, 2) }),
+            lines: vec!["// note".to_string()],
+            expected_lines: None,
+        },
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 2, hash: get_line_hash(content, 2) },
+            end: None,
+            lines: vec!["// note".to_string(), "line two".to_string()],
+            expected_lines: None,
+        },
+    ];
+
+    assert!(apply_hashline_edits(content, &edits).is_err(), "Overlap should fail without merging");
+    let outcome = apply_hashline_edits_with_options(content, &edits, &merge_options()).unwrap();
+    assert_eq!(outcome.content, "line 1\n// note\nline two\nline 3\n");
+    assert_eq!(outcome.statuses, vec![EditStatus::Merged { into: 1 }, EditStatus::Applied]);
+}
+
+#[test]
+fn test_merge_overlaps_absorbs_identical_replace() {
+    let content = "line 1\nline 2\nline 3\n";
+    let edit = HashlineEdit::Replace {
+        pos: AnchorRef { line: 1, hash: get_line_hash(content, 1) },
+        end: Some(AnchorRef { line: 2, hash: get_line_hash(content, 2) }),
+        lines: vec!["merged".to_string()],
+        expected_lines: None,
+    };
+    let options = ApplyOptions { dedupe: false, ..merge_options() };
+
+    let outcome = apply_hashline_edits_with_options(content, &[edit.clone(), edit], &options).unwrap();
+    assert_eq!(outcome.content, "merged\nline 3\n");
+    assert_eq!(outcome.statuses[1].to_string(), "merged into edits[0]");
+}
+
+#[test]
+fn test_merge_overlaps_still_rejects_conflicting_content() {
+    let content = "line 1\nline 2\nline 3\n";
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 2, hash: get_line_hash(content, 2) },
+            end: None,
+            lines: vec!["ours".to_string()],
+            expected_lines: None,
+        },
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 2, hash: get_line_hash(content, 2) },
+            end: None,
+            lines: vec!["theirs".to_string()],
+            expected_lines: None,
+        },
+    ];
+
+    let error = apply_hashline_edits_with_options(content, &edits, &merge_options()).unwrap_err().to_string();
+    assert!(error.contains("Overlapping edits detected"), "Got: {}", error);
+}
+
+#[test]
+fn test_duplicates_compare_lines_not_joined_text() {
+    let content = "line 1\nline 2\n";
+    let at = |lines: Vec<&str>| HashlineEdit::Append {
+        pos: Some(AnchorRef { line: 1, hash: get_line_hash(content, 1) }),
+        lines: lines.into_iter().map(String::from).collect(),
+        expected_lines: None,
+    };
+    // Joined with newlines these read the same, but they are different edits
+    let edits = vec![at(vec!["a", "b"]), at(vec!["a\nb"]), at(vec!["a", "b"])];
+    let options = ApplyOptions { allow_partial: true, ..ApplyOptions::default() };
+    let outcome = apply_hashline_edits_with_options(content, &edits, &options).unwrap();
+    let messages: Vec<String> = outcome.statuses.iter().map(|s| s.to_string()).collect();
+    assert!(messages[1].contains("overlaps with edits[0]"), "{:?}", messages);
+    assert!(messages[2].contains("duplicate of edits[0]"), "{:?}", messages);
+}
@@ -0,0 +1,90 @@
+use hashline_tools::*;
+
+/// Sorts the lines of a block; refuses blocks containing blank lines.
+#[derive(Debug)]
+struct SortLines {
+    first: AnchorRef,
+    last: AnchorRef,
+}
+
+impl EditOp for SortLines {
+    fn name(&self) -> &str {
+        "sort_lines"
+    }
+
+    fn range(&self) -> (AnchorRef, AnchorRef) {
+        (self.first.clone(), self.last.clone())
+    }
+
+    fn validate(&self, lines: &[String]) -> Result<(), String> {
+        match lines.iter().position(|l| l.is_empty()) {
+            Some(i) => Err(format!("blank line at offset {}", i)),
+            None => Ok(()),
+        }
+    }
+
+    fn apply(&self, lines: &[String]) -> Vec<String> {
+        let mut sorted = lines.to_vec();
+        sorted.sort();
+        sorted
+    }
+}
+
+fn sort(file: &HashedFile, first: usize, last: usize) -> SortLines {
+    SortLines { first: file.anchor(first).unwrap(), last: file.anchor(last).unwrap() }
+}
+
+#[test]
+fn test_custom_op_applies_alongside_builtin_edits() {
+    let content = "use c;\nuse a;\nuse b;\n\nfn main() {}\n";
+    let file = HashedFile::from_content(content);
+    let batch = edits()
+        .custom(sort(&file, 1, 3))
+        .replace(file.anchor(5).unwrap(), ["fn main() { run(); }"])
+        .build()
+        .unwrap();
+
+    let outcome = apply_hashline_edits_with_options(content, &batch, &ApplyOptions::default()).unwrap();
+    assert_eq!(outcome.content, "use a;\nuse b;\nuse c;\n\nfn main() { run(); }\n");
+    assert_eq!(outcome.first_changed_line, Some(1));
+    assert_eq!(outcome.statuses, vec![EditStatus::Applied, EditStatus::Applied]);
+}
+
+#[test]
+fn test_custom_op_overlap_and_validation() {
+    let content = "b\na\n\nd\nc\n";
+    let file = HashedFile::from_content(content);
+
+    let overlapping = vec![
+        HashlineEdit::custom(sort(&file, 1, 2)),
+        HashlineEdit::Replace { pos: file.anchor(2).unwrap(), end: None, lines: vec!["x".into()], expected_lines: None },
+    ];
+    let err = apply_hashline_edits_with_options(content, &overlapping, &ApplyOptions::default()).unwrap_err();
+    let HashlineError::Overlap(overlaps) = err else { panic!("expected overlap, got {}", err) };
+    assert!(overlaps[0].contains("edits[0] sort_lines at lines 1-2"), "got: {:?}", overlaps);
+
+    let statuses = validate_hashline_edits(content, &[HashlineEdit::custom(sort(&file, 2, 4))]);
+    assert_eq!(statuses, vec![EditStatus::Skipped {
+        reason: SkipReason::Invalid,
+        message: "sort_lines: blank line at offset 1".to_string(),
+    }]);
+}
+
+#[test]
+fn test_custom_op_off_by_one_anchors_are_only_suggested() {
+    let content = "b\na\nc\n";
+    let file = HashedFile::from_content(content);
+    // Right hash, wrong line number
+    let first = AnchorRef { line: 2, hash: file.hash_at(1).unwrap().to_string() };
+    let op = || SortLines { first: first.clone(), last: file.anchor(2).unwrap() };
+
+    let err = apply_hashline_edits_with_options(content, &[HashlineEdit::custom(op())], &ApplyOptions::default()).unwrap_err();
+    assert!(matches!(err, HashlineError::Mismatch(_)));
+
+    // A custom op carries no content to confirm the neighbour by, so it is
+    // only suggested
+    let options = ApplyOptions { fix_off_by_one: true, ..ApplyOptions::default() };
+    let err = apply_hashline_edits_with_options(content, &[HashlineEdit::custom(op())], &options).unwrap_err();
+    let HashlineError::Mismatch(mismatch) = err else { panic!("expected a mismatch error, got {}", err) };
+    assert_eq!(mismatch.mismatches[0].off_by_one(), Some(&file.anchor(1).unwrap()));
+}
@@ -0,0 +1,90 @@
+#![cfg(feature = "std")]
+
+use hashline_tools::*;
+#[cfg(feature = "fs")]
+use std::fs;
+
+fn full_hashes(content: &str) -> Vec<String> {
+    let mut hashes: Vec<String> = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let hash = compute_line_hash(i + 1, line, hashes.last().map(|h| h.as_str()));
+        hashes.push(hash);
+    }
+    hashes
+}
+
+fn editor_hashes(editor: &Editor) -> Vec<String> {
+    (1..=editor.lines().len()).map(|line| editor.hash_at(line).unwrap().to_string()).collect()
+}
+
+#[test]
+fn test_editor_rehash_matches_full_recompute() {
+    let mut editor = Editor::from_content("unused.txt", "fn main() {\n    one();\n    two();\n}\n\n}\n");
+
+    // Successive batches, each anchored on the hashes the previous one produced
+    let batches = [
+        vec![HashlineEdit::Replace { pos: editor.anchor(2).unwrap(), end: None, lines: vec!["    uno();".into()], expected_lines: None }],
+        vec![HashlineEdit::Prepend { pos: None, lines: vec!["".into(), "// header".into()], expected_lines: None }],
+    ];
+    editor.apply(&batches[0]).unwrap();
+    assert_eq!(editor_hashes(&editor), full_hashes(editor.content()));
+    editor.apply(&batches[1]).unwrap();
+    assert_eq!(editor_hashes(&editor), full_hashes(editor.content()));
+
+    let edits = vec![HashlineEdit::Replace {
+        pos: editor.anchor(4).unwrap(),
+        end: Some(editor.anchor(5).unwrap()),
+        lines: vec![],
+        expected_lines: None,
+    }];
+    editor.apply(&edits).unwrap();
+    assert_eq!(editor.content(), "\n// header\nfn main() {\n}\n\n}\n");
+    assert_eq!(editor_hashes(&editor), full_hashes(editor.content()));
+    assert_eq!(editor.hash_at(0), None);
+    assert_eq!(editor.hash_at(editor.lines().len() + 1), None);
+}
+
+#[test]
+fn test_editor_failed_batch_leaves_buffer() {
+    let mut editor = Editor::from_content("unused.txt", "a\nb\n");
+    let stale = AnchorRef { line: 1, hash: "ZZ".to_string() };
+    let edits = vec![HashlineEdit::Replace { pos: stale, end: None, lines: vec!["x".into()], expected_lines: None }];
+
+    let err = editor.apply(&edits).unwrap_err();
+    assert!(matches!(err, HashlineError::Mismatch(_)));
+    assert_eq!(editor.content(), "a\nb\n");
+    assert!(!editor.is_dirty());
+}
+
+#[cfg(feature = "fs")]
+#[test]
+fn test_editor_writes_only_on_save() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("a.txt");
+    fs::write(&path, "a\nb\n").unwrap();
+
+    let mut editor = Editor::open(&path).unwrap();
+    let edits = vec![HashlineEdit::Append { pos: editor.anchor(2), lines: vec!["c".into()], expected_lines: None }];
+    editor.apply(&edits).unwrap();
+    assert!(editor.is_dirty());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "a\nb\n");
+
+    editor.save().unwrap();
+    assert!(!editor.is_dirty());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "a\nb\nc\n");
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test(flavor = "current_thread")]
+async fn test_editor_async_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("a.txt");
+    fs::write(&path, "a\nb\n").unwrap();
+
+    let mut editor = Editor::open_async(&path).await.unwrap();
+    let edits = vec![HashlineEdit::Replace { pos: editor.anchor(1).unwrap(), end: None, lines: vec!["A".into()], expected_lines: None }];
+    editor.apply(&edits).unwrap();
+    editor.save_async().await.unwrap();
+    assert!(!editor.is_dirty());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "A\nb\n");
+}
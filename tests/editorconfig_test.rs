@@ -0,0 +1,201 @@
+#[cfg(feature = "cli")]
+use std::fs;
+
+#[cfg(feature = "cli")]
+use clap::Parser;
+use hashline_tools::*;
+#[cfg(feature = "cli")]
+use tempfile::TempDir;
+
+fn editorconfig_options(editorconfig: EditorConfig) -> ApplyOptions {
+    ApplyOptions { editorconfig, ..ApplyOptions::default() }
+}
+
+#[test]
+fn test_inserted_lines_follow_the_indent_style() {
+    let content = "fn main() {\n    old();\n}\n";
+    let file = HashedFile::from_content(content);
+    let batch = edits()
+        .replace(file.anchor(2).unwrap(), ["\tnew();", "\t  if x {", "    \tdone();"])
+        .build()
+        .unwrap();
+
+    let spaces = EditorConfig { indent_style: Some(IndentStyle::Space), indent_size: Some(4), ..EditorConfig::default() };
+    let outcome = apply_hashline_edits_with_options(content, &batch, &editorconfig_options(spaces)).unwrap();
+    assert_eq!(outcome.content, "fn main() {\n    new();\n      if x {\n        done();\n}\n");
+
+    let tabs = EditorConfig { indent_style: Some(IndentStyle::Tab), indent_size: Some(4), ..EditorConfig::default() };
+    let outcome = apply_hashline_edits_with_options(content, &batch, &editorconfig_options(tabs)).unwrap();
+    assert_eq!(outcome.content, "fn main() {\n\tnew();\n\t  if x {\n\t\tdone();\n}\n");
+
+    // Without a size, tabs cannot be converted and the lines are left alone
+    let no_size = EditorConfig { indent_style: Some(IndentStyle::Space), ..EditorConfig::default() };
+    let outcome = apply_hashline_edits_with_options(content, &batch, &editorconfig_options(no_size)).unwrap();
+    assert_eq!(outcome.content, "fn main() {\n\tnew();\n\t  if x {\n    \tdone();\n}\n");
+}
+
+#[test]
+fn test_untouched_lines_keep_their_indentation() {
+    let content = "\tkeep();\nold();\n";
+    let file = HashedFile::from_content(content);
+    let batch = edits().append_after(file.anchor(2).unwrap(), ["\tadded();"]).build().unwrap();
+    let spaces = EditorConfig { indent_style: Some(IndentStyle::Space), indent_size: Some(2), ..EditorConfig::default() };
+
+    let outcome = apply_hashline_edits_with_options(content, &batch, &editorconfig_options(spaces)).unwrap();
+    assert_eq!(outcome.content, "\tkeep();\nold();\n  added();\n");
+}
+
+#[test]
+fn test_line_endings_and_final_newline() {
+    let content = "one\ntwo";
+    let file = HashedFile::from_content(content);
+    let batch = edits().replace(file.anchor(2).unwrap(), ["TWO"]).build().unwrap();
+    let apply = |editorconfig| apply_hashline_edits_with_options(content, &batch, &editorconfig_options(editorconfig)).unwrap().content;
+
+    assert_eq!(apply(EditorConfig::default()), "one\nTWO");
+    assert_eq!(apply(EditorConfig { insert_final_newline: Some(true), ..EditorConfig::default() }), "one\nTWO\n");
+    assert_eq!(
+        apply(EditorConfig { end_of_line: Some(EndOfLine::Crlf), insert_final_newline: Some(true), ..EditorConfig::default() }),
+        "one\r\nTWO\r\n"
+    );
+
+    let content = "one\r\ntwo\r\n";
+    let outcome = apply_hashline_edits_with_options(
+        content,
+        &batch,
+        &editorconfig_options(EditorConfig { end_of_line: Some(EndOfLine::Crlf), insert_final_newline: Some(false), ..EditorConfig::default() }),
+    ).unwrap();
+    assert_eq!(outcome.content, "one\r\nTWO");
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_editorconfig_files_are_resolved_up_to_the_root() {
+    let dir = TempDir::new().unwrap();
+    let sub = dir.path().join("src").join("nested");
+    fs::create_dir_all(&sub).unwrap();
+    fs::write(dir.path().join(".editorconfig"), "\
+root = true
+
+[*]
+indent_style = space
+indent_size = 4
+end_of_line = lf
+insert_final_newline = true
+
+[*.{md,txt}]
+insert_final_newline = unset
+
+[/src/**.rs]
+indent_size = 2
+
+[Makefile]
+indent_style = tab
+").unwrap();
+    fs::write(sub.join(".editorconfig"), "[*.rs]\nEnd_Of_Line = CRLF\n").unwrap();
+
+    let config = EditorConfig::for_file(sub.join("main.rs")).unwrap();
+    assert_eq!(config, EditorConfig {
+        indent_style: Some(IndentStyle::Space),
+        indent_size: Some(2),
+        end_of_line: Some(EndOfLine::Crlf),
+        insert_final_newline: Some(true),
+    });
+
+    let config = EditorConfig::for_file(dir.path().join("main.rs")).unwrap();
+    assert_eq!(config.indent_size, Some(4));
+    assert_eq!(config.end_of_line, Some(EndOfLine::Lf));
+
+    let config = EditorConfig::for_file(dir.path().join("notes.md")).unwrap();
+    assert_eq!(config.insert_final_newline, None);
+
+    let config = EditorConfig::for_file(sub.join("Makefile")).unwrap();
+    assert_eq!(config.indent_style, Some(IndentStyle::Tab));
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_edit_command_follows_the_editorconfig() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join(".editorconfig"), "root = true\n[*.py]\nindent_style = space\nindent_size = 4\nend_of_line = crlf\n").unwrap();
+    let path = dir.path().join("app.py");
+    let content = "def f():\r\n    pass\r\n";
+    fs::write(&path, content).unwrap();
+    let file_path = path.to_str().unwrap();
+    let anchor = HashedFile::from_content(content).anchor(2).unwrap();
+    let edits = format!(r#"[{{"op": "replace", "pos": "{}", "lines": ["\treturn 1"]}}]"#, anchor);
+
+    let options = EditOptions { editorconfig: true, checkpoints: false, ..EditOptions::default() };
+    cmd_edit_with_options(file_path, &edits, &options).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "def f():\r\n    return 1\r\n");
+
+    // Off unless asked for; the file keeps its own line endings
+    fs::write(&path, content).unwrap();
+    cmd_edit_with_options(file_path, &edits, &EditOptions::default()).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "def f():\r\n\treturn 1\r\n");
+
+    // Multi-file edits resolve each file's own editorconfig
+    fs::write(&path, content).unwrap();
+    let other = dir.path().join("notes.txt");
+    fs::write(&other, "a\r\n").unwrap();
+    let other_anchor = HashedFile::from_content("a\r\n").anchor(1).unwrap();
+    let payload = serde_json::json!({
+        file_path: [{"op": "replace", "pos": anchor.to_string(), "lines": ["\treturn 1"]}],
+        other.to_str().unwrap(): [{"op": "replace", "pos": other_anchor.to_string(), "lines": ["\tb"]}],
+    });
+    cmd_multi_edit(&payload.to_string(), &options).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "def f():\r\n    return 1\r\n");
+    assert_eq!(fs::read_to_string(&other).unwrap(), "\tb\r\n");
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_edits_keep_the_files_line_endings() {
+    assert_eq!(EndOfLine::detect("a\r\nb\r\n"), Some(EndOfLine::Crlf));
+    assert_eq!(EndOfLine::detect("a\r\nb\nc\r\n"), Some(EndOfLine::Crlf));
+    assert_eq!(EndOfLine::detect("a\r\nb\n"), Some(EndOfLine::Lf));
+    assert_eq!(EndOfLine::detect("no break"), None);
+
+    let content = "one\r\ntwo\r\nthree";
+    let file = HashedFile::from_content(content);
+    let edits = vec![
+        HashlineEdit::Replace { pos: file.anchor(1).unwrap(), end: None, lines: vec!["1".into()], expected_lines: None },
+        HashlineEdit::Append { pos: None, lines: vec!["four".into()], expected_lines: None },
+    ];
+    let outcome = apply_hashline_edits_with_options(content, &edits, &ApplyOptions::default()).unwrap();
+    assert_eq!(outcome.content, "1\r\ntwo\r\nthree\r\nfour");
+
+    // A configured end_of_line rewrites every line
+    let options = editorconfig_options(EditorConfig { end_of_line: Some(EndOfLine::Lf), ..EditorConfig::default() });
+    let outcome = apply_hashline_edits_with_options(content, &edits, &options).unwrap();
+    assert_eq!(outcome.content, "1\ntwo\nthree\nfour");
+
+    // --line-ending only picks the ending of the lines the edits insert
+    let options = ApplyOptions { line_ending: Some(EndOfLine::Lf), ..options };
+    let outcome = apply_hashline_edits_with_options(content, &edits, &options).unwrap();
+    assert_eq!(outcome.content, "1\ntwo\r\nthree\nfour");
+    let cli = Cli::try_parse_from(["hashline-tools", "--line-ending", "crlf", "edit", "f.txt"]).unwrap();
+    assert_eq!(cli.line_ending, Some(EndOfLine::Crlf));
+}
+
+#[test]
+fn test_mixed_line_endings_are_kept_line_by_line() {
+    let content = "a\r\nb\nc\r\nd\ne\r\n";
+    let file = HashedFile::from_content(content);
+    let anchor = |line| file.anchor(line).unwrap();
+    let edits = vec![
+        HashlineEdit::Replace { pos: anchor(2), end: None, lines: vec!["B".into(), "B2".into()], expected_lines: None },
+        HashlineEdit::Move { pos: anchor(4), end: None, after: anchor(5) },
+        HashlineEdit::Append { pos: None, lines: vec!["f".into()], expected_lines: None },
+    ];
+    let outcome = apply_hashline_edits_with_options(content, &edits, &ApplyOptions::default()).unwrap();
+    // Untouched and moved lines keep their own endings; inserted lines get
+    // the one most lines use
+    assert_eq!(outcome.content, "a\r\nB\r\nB2\r\nc\r\ne\r\nd\nf\r\n");
+
+    // A blank last line survives an edit elsewhere
+    let content = "x\n\n";
+    let pos = HashedFile::from_content(content).anchor(1).unwrap();
+    let edit = HashlineEdit::Replace { pos, end: None, lines: vec!["X".into()], expected_lines: None };
+    assert_eq!(apply_hashline_edits(content, &[edit]).unwrap().0, "X\n\n");
+}
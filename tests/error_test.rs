@@ -0,0 +1,29 @@
+use hashline_tools::*;
+
+fn edits(json: &str) -> Vec<HashlineEdit> {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn test_apply_errors_are_typed() {
+    let content = "a\nb\nc\n";
+    let a = format!("1#{}", compute_line_hash(1, "a", None));
+
+    let overlap = edits(&format!(
+        r#"[{{"op": "replace", "pos": "{a}", "lines": ["x"]}}, {{"op": "replace", "pos": "{a}", "lines": ["y"]}}]"#
+    ));
+    assert!(matches!(apply_hashline_edits(content, &overlap), Err(HashlineError::Overlap(pairs)) if pairs.len() == 1));
+
+    let out_of_range = edits(r#"[{"op": "replace", "pos": "9#ZZ", "lines": ["x"]}]"#);
+    let err = apply_hashline_edits(content, &out_of_range).unwrap_err();
+    assert!(matches!(&err, HashlineError::Validation(errors) if errors.len() == 1));
+    assert_eq!(err.to_string(), "Line 9 does not exist (file has 3 lines)");
+}
+
+#[test]
+fn test_anchor_from_str() {
+    let anchor: AnchorRef = "8#RT".parse().unwrap();
+    assert_eq!(anchor, AnchorRef { line: 8, hash: "RT".to_string() });
+    assert!(matches!("8RT".parse::<AnchorRef>(), Err(HashlineError::InvalidAnchor(_))));
+    assert!(matches!("x#RT".parse::<AnchorRef>(), Err(HashlineError::InvalidAnchor(_))));
+}
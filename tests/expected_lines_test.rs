@@ -0,0 +1,124 @@
+use hashline_tools::*;
+
+// Helper function to compute cumulative hashes for a file and get a specific line's hash
+fn get_line_hash(content: &str, line_num: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut prev_hash: Option<&str> = None;
+    let mut cumulative_hashes: Vec<String> = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let ln = i + 1;
+        let hash = compute_line_hash(ln, line, prev_hash);
+        cumulative_hashes.push(hash);
+        prev_hash = Some(&cumulative_hashes[i]);
+    }
+
+    cumulative_hashes[line_num - 1].clone()
+}
+
+fn strings(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|l| l.to_string()).collect()
+}
+
+#[test]
+fn test_expected_lines_tolerates_stale_hash_in_place() {
+    // Changing line 1 invalidates the chained hash of line 2, but not its content
+    let original = "first\nsecond\nthird\n";
+    let stale_hash = get_line_hash(original, 2);
+    let current = "FIRST\nsecond\nthird\n";
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 2, hash: stale_hash.clone() },
+            end: None,
+            lines: strings(&["2nd"]),
+            expected_lines: Some(strings(&["second"])),
+        }
+    ];
+
+    let outcome = apply_hashline_edits_with_options(current, &edits, &ApplyOptions::default()).unwrap();
+    assert_eq!(outcome.content, "FIRST\n2nd\nthird\n");
+    assert_eq!(outcome.relocations.len(), 1);
+    assert_eq!(outcome.relocations[0].from, AnchorRef { line: 2, hash: stale_hash });
+    assert_eq!(outcome.relocations[0].to.line, 2);
+    assert_eq!(outcome.relocations[0].method, RelocationMethod::ExpectedLines);
+}
+
+#[test]
+fn test_expected_lines_follows_shifted_range() {
+    let original = "a\nb\nc\nd\n";
+    let current = "new 1\nnew 2\na\nb\nc\nd\n";
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 2, hash: get_line_hash(original, 2) },
+            end: Some(AnchorRef { line: 3, hash: get_line_hash(original, 3) }),
+            lines: strings(&["B", "C"]),
+            expected_lines: Some(strings(&["b", "c"])),
+        }
+    ];
+
+    let outcome = apply_hashline_edits_with_options(current, &edits, &ApplyOptions::default()).unwrap();
+    assert_eq!(outcome.content, "new 1\nnew 2\na\nB\nC\nd\n");
+    let moved: Vec<(&str, usize)> = outcome.relocations.iter().map(|r| (r.field, r.to.line)).collect();
+    assert_eq!(moved, vec![("pos", 4), ("end", 5)]);
+}
+
+#[test]
+fn test_expected_lines_rejects_content_mismatch_even_with_valid_hash() {
+    let content = "first\nsecond\nthird\n";
+    let edits = vec![
+        HashlineEdit::Append {
+            pos: Some(AnchorRef { line: 2, hash: get_line_hash(content, 2) }),
+            lines: strings(&["inserted"]),
+            expected_lines: Some(strings(&["something else"])),
+        }
+    ];
+
+    let err = apply_hashline_edits(content, &edits).unwrap_err().to_string();
+    assert!(err.contains("does not match expected_lines"), "Got: {}", err);
+}
+
+#[test]
+fn test_expected_lines_ambiguous_shift_still_fails() {
+    let original = "x\ndup\ny\n";
+    let stale_hash = get_line_hash(original, 2);
+    let current = "w\nx\ndup\ny\ndup\n";
+    let edits = vec![
+        HashlineEdit::Prepend {
+            pos: Some(AnchorRef { line: 2, hash: stale_hash }),
+            lines: strings(&["before"]),
+            expected_lines: Some(strings(&["dup"])),
+        }
+    ];
+
+    let err = apply_hashline_edits(current, &edits).unwrap_err();
+    assert!(matches!(err, HashlineError::Mismatch(_)));
+}
+
+#[test]
+fn test_expected_lines_length_must_match_range() {
+    let content = "a\nb\nc\n";
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 1, hash: get_line_hash(content, 1) },
+            end: Some(AnchorRef { line: 3, hash: get_line_hash(content, 3) }),
+            lines: strings(&["z"]),
+            expected_lines: Some(strings(&["a", "b"])),
+        }
+    ];
+
+    let err = apply_hashline_edits(content, &edits).unwrap_err().to_string();
+    assert!(err.contains("expected_lines has 2 lines"), "Got: {}", err);
+}
+
+#[test]
+fn test_expected_lines_parsed_from_json() {
+    let edits: Vec<HashlineEdit> = serde_json::from_str(
+        r#"[{"op": "replace", "pos": "2#AB", "lines": ["x"], "expected_lines": ["old"]}]"#
+    ).unwrap();
+    match &edits[0] {
+        HashlineEdit::Replace { expected_lines, .. } => {
+            assert_eq!(expected_lines.as_deref(), Some(&["old".to_string()][..]));
+        }
+        other => panic!("unexpected edit: {:?}", other),
+    }
+}
@@ -0,0 +1,60 @@
+#![cfg(feature = "ffi")]
+
+use hashline_tools::*;
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+/// Take ownership of a string returned over the C ABI.
+fn take(s: *mut std::ffi::c_char) -> serde_json::Value {
+    assert!(!s.is_null(), "call failed: {}", last_error());
+    let json = unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_string();
+    unsafe { hashline_free(s) };
+    serde_json::from_str(&json).unwrap()
+}
+
+fn last_error() -> serde_json::Value {
+    let err = hashline_last_error();
+    assert!(!err.is_null());
+    serde_json::from_str(unsafe { CStr::from_ptr(err) }.to_str().unwrap()).unwrap()
+}
+
+#[test]
+fn test_ffi_read_then_apply() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("a.txt");
+    std::fs::write(&path, "one\ntwo\n").unwrap();
+
+    let c_path = CString::new(path.to_str().unwrap()).unwrap();
+    let read = take(unsafe { hashline_read(c_path.as_ptr()) });
+    assert_eq!(read["total_lines"], 2);
+    assert_eq!(read["total_bytes"], 8);
+    assert_eq!(read["lines"][1]["byte_offset"], 4);
+    let anchor = read["lines"][1]["anchor"].as_str().unwrap().to_string();
+    assert!(hashline_last_error().is_null());
+
+    let content = CString::new("one\ntwo\n").unwrap();
+    let edits = CString::new(serde_json::json!([{ "op": "replace", "pos": anchor, "lines": ["TWO"] }]).to_string()).unwrap();
+    let outcome = take(unsafe { hashline_apply_edits(content.as_ptr(), edits.as_ptr(), ptr::null()) });
+    assert_eq!(outcome["content"], "one\nTWO\n");
+    assert_eq!(outcome["first_changed_line"], 2);
+}
+
+#[test]
+fn test_ffi_reports_errors_as_json() {
+    let content = CString::new("one\ntwo\n").unwrap();
+    let edits = CString::new(r#"[{"op": "replace", "pos": "2#ZZ", "lines": ["TWO"]}]"#).unwrap();
+    let result = unsafe { hashline_apply_edits(content.as_ptr(), edits.as_ptr(), ptr::null()) };
+    assert!(result.is_null());
+    let err = last_error();
+    assert_eq!(err["error"], "hash_mismatch");
+    assert_eq!(err["mismatches"][0]["line"], 2);
+
+    let options = CString::new(r#"{"auto_rebase": "yes"}"#).unwrap();
+    let result = unsafe { hashline_apply_edits(content.as_ptr(), edits.as_ptr(), options.as_ptr()) };
+    assert!(result.is_null());
+    assert!(last_error()["message"].as_str().unwrap().starts_with("Failed to parse options"));
+
+    let result = unsafe { hashline_read(ptr::null()) };
+    assert!(result.is_null());
+    assert_eq!(last_error()["message"], "path must not be NULL");
+}
@@ -0,0 +1,84 @@
+use hashline_tools::*;
+#[cfg(feature = "cli")]
+use std::fs;
+#[cfg(feature = "cli")]
+use std::io::Write;
+#[cfg(feature = "cli")]
+use tempfile::{tempdir, NamedTempFile};
+
+#[test]
+fn test_force_applies_by_line_number_and_records_bypass() {
+    let content = "one\ntwo\nthree\n";
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 2, hash: "ZZ".to_string() },
+            end: None,
+            lines: vec!["TWO".to_string()],
+            expected_lines: Some(vec!["deux".to_string()]),
+        }
+    ];
+    let options = ApplyOptions { force: true, ..ApplyOptions::default() };
+
+    let outcome = apply_hashline_edits_with_options(content, &edits, &options).unwrap();
+    assert_eq!(outcome.content, "one\nTWO\nthree\n");
+    let fields: Vec<&str> = outcome.bypassed.iter().map(|b| b.field).collect();
+    assert_eq!(fields, vec!["expected_lines", "pos"]);
+    assert!(outcome.bypassed[1].to_string().starts_with("edits[0].pos: expected 2#ZZ, line is now 2#"));
+}
+
+#[test]
+fn test_force_does_not_bypass_range_errors() {
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 9, hash: "ZZ".to_string() },
+            end: None,
+            lines: vec!["x".to_string()],
+            expected_lines: None,
+        }
+    ];
+    let options = ApplyOptions { force: true, ..ApplyOptions::default() };
+    assert!(apply_hashline_edits_with_options("one\n", &edits, &options).is_err());
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_cmd_edit_force_requires_audit_log() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(temp_file, "one\ntwo\n").unwrap();
+    let path = temp_file.path().to_str().unwrap().to_string();
+
+    let options = EditOptions {
+        apply: ApplyOptions { force: true, ..ApplyOptions::default() },
+        ..EditOptions::default()
+    };
+    let err = cmd_edit_with_options(&path, r#"[{"op": "replace", "pos": "2#ZZ", "lines": ["x"]}]"#, &options)
+        .unwrap_err();
+    assert!(err.contains("--audit-log"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_cmd_edit_force_writes_audit_record() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("file.txt");
+    let log_path = dir.path().join("audit.jsonl");
+    fs::write(&file_path, "one\ntwo\n").unwrap();
+    let path = file_path.to_str().unwrap().to_string();
+
+    let options = EditOptions {
+        apply: ApplyOptions { force: true, ..ApplyOptions::default() },
+        audit_log: Some(log_path.clone()),
+        ..EditOptions::default()
+    };
+    let output = cmd_edit_with_options(&path, r#"[{"op": "replace", "pos": "2#ZZ", "lines": ["x"]}]"#, &options)
+        .unwrap();
+    assert!(output.contains("Forced past 1 failed check:"));
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "one\nx\n");
+
+    let log = fs::read_to_string(&log_path).unwrap();
+    let record: serde_json::Value = serde_json::from_str(log.lines().next().unwrap()).unwrap();
+    assert_eq!(record["event"], "force");
+    assert_eq!(record["file"], path);
+    assert_eq!(record["bypassed"][0]["field"], "pos");
+}
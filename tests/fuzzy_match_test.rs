@@ -0,0 +1,74 @@
+use hashline_tools::*;
+
+#[test]
+fn test_levenshtein_bounded_agrees_with_levenshtein() {
+    let words = ["", "a", "ab", "kitten", "sitting", "sittin", "flaw", "lawn", "let x = 1;", "let y = 2;", "ünïcödé", "unicode"];
+    for a in words {
+        for b in words {
+            let distance = levenshtein(a, b);
+            for max_distance in 0..12 {
+                let expected = (distance <= max_distance).then_some(distance);
+                assert_eq!(levenshtein_bounded(a, b, max_distance), expected, "{:?} vs {:?} within {}", a, b, max_distance);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_find_fuzzy_match_thresholds_and_order() {
+    let lines: Vec<String> = ["fn main() {", "    let total = 10;", "}", "    let totals = 10;", "    let x = 1;"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let matches = find_fuzzy_match(&lines, "let total = 10;", 0.7);
+    assert_eq!(matches.iter().map(|m| m.0).collect::<Vec<_>>(), vec![2, 4]);
+    assert_eq!(matches[0].1, 1.0);
+    assert!((matches[1].1 - (1.0 - 1.0 / 16.0)).abs() < 1e-9);
+
+    // At the exact threshold a match still counts
+    let lines = vec!["abcd".to_string()];
+    assert_eq!(find_fuzzy_match(&lines, "abcx", 0.75), vec![(1, 0.75)]);
+    assert!(find_fuzzy_match(&lines, "abxx", 0.75).is_empty());
+}
+
+#[test]
+fn test_trigram_index_keeps_every_match() {
+    let lines: Vec<String> = (0..2000)
+        .map(|i| format!("    let value_{} = compute({}, \"{}\");", i % 37, i % 11, "ab".repeat(i % 5)))
+        .collect();
+    let index = TrigramIndex::new(&lines);
+    for needle in ["let value_3 = compute(4, \"abab\");", "let value = compute();", "value_12", "", "x"] {
+        for min_similarity in [0.5, 0.7, 0.9, 1.0] {
+            let expected: Vec<usize> = lines.iter()
+                .enumerate()
+                .filter(|(_, line)| {
+                    let (a, b) = (line.trim(), needle.trim());
+                    let max_len = a.chars().count().max(b.chars().count());
+                    max_len == 0 || 1.0 - levenshtein(a, b) as f64 / max_len as f64 >= min_similarity
+                })
+                .map(|(i, _)| i + 1)
+                .collect();
+            let mut found: Vec<usize> = index.find_fuzzy_match(needle, min_similarity).into_iter().map(|m| m.0).collect();
+            found.sort();
+            assert_eq!(found, expected, "{:?} at {}", needle, min_similarity);
+        }
+    }
+
+    // Lines sharing too few trigrams with the needle are never scored
+    assert!(index.candidates("fn handle_request(req: Request) -> Response {", 0.7).is_empty());
+    assert_eq!(index.candidates("x", 0.0).len(), lines.len());
+}
+
+#[test]
+fn test_line_similarity_scores() {
+    assert_eq!(levenshtein("kitten", "sitting"), 3);
+    assert_eq!(levenshtein("sitting", "kitten"), 3);
+    assert_eq!(levenshtein("", "abc"), 3);
+    assert_eq!(levenshtein("ünï", "uni"), 2);
+
+    assert_eq!(line_similarity("  let x = 1;", "let x = 1;\t"), 1.0);
+    assert_eq!(line_similarity("", "   "), 1.0);
+    assert_eq!(line_similarity("abcd", "abcx"), 0.75);
+    assert_eq!(line_similarity_at_least("abcd", "abcx", 0.75), Some(0.75));
+    assert_eq!(line_similarity_at_least("abcd", "abxx", 0.75), None);
+}
@@ -0,0 +1,105 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C").arg(dir)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+/// A repository with `file.txt` holding lines `1`..`30` committed.
+fn setup() -> (TempDir, String) {
+    let dir = TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    let path = dir.path().join("file.txt");
+    let content: String = (1..=30).map(|n| format!("{}\n", n)).collect();
+    fs::write(&path, content).unwrap();
+    git(dir.path(), &["add", "file.txt"]);
+    git(dir.path(), &["commit", "-q", "-m", "initial"]);
+    (dir, path.to_str().unwrap().to_string())
+}
+
+/// `file_path` with each numbered line replaced by its text, or dropped for `None`.
+fn rewrite(file_path: &str, lines: &[(usize, Option<&str>)]) -> String {
+    let content: String = fs::read_to_string(file_path).unwrap().lines().enumerate()
+        .filter_map(|(i, line)| match lines.iter().find(|(n, _)| *n == i + 1) {
+            Some((_, text)) => text.map(|text| format!("{}\n", text)),
+            None => Some(format!("{}\n", line)),
+        })
+        .collect();
+    fs::write(file_path, &content).unwrap();
+    content
+}
+
+fn read_changed(file_path: &str) -> String {
+    let mut out = Vec::new();
+    let options = ReadOptions { git_changed: true, ..ReadOptions::default() };
+    cmd_read_with_options(&mut out, file_path, &options).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_changed_lines_follow_the_working_tree() {
+    let (_dir, path) = setup();
+    assert!(git_changed_lines(&path).unwrap().is_empty());
+
+    rewrite(&path, &[(5, Some("five")), (20, None), (25, Some("25\nnew"))]);
+    // The deletion of line 20 falls before the working tree's line 20
+    assert_eq!(git_changed_lines(&path).unwrap(), [4..5, 19..19, 24..25]);
+}
+
+#[test]
+fn test_read_shows_only_changed_regions() {
+    let (_dir, path) = setup();
+    assert_eq!(read_changed(&path), "<file>\n(No changes since HEAD - 30 total lines)\n</file>");
+
+    let content = rewrite(&path, &[(5, Some("five")), (28, Some("twenty-eight"))]);
+    let file = HashedFile::from_content(&content);
+    let shown = |range: std::ops::RangeInclusive<usize>| {
+        range.map(|line| format!("{}:{}\n", file.anchor(line).unwrap(), file.lines()[line - 1])).collect::<String>()
+    };
+    assert_eq!(
+        read_changed(&path),
+        format!("<file>\n{}...\n{}\n(2 changed regions since HEAD - 30 total lines)\n</file>", shown(3..=7), shown(26..=30)),
+    );
+}
+
+#[test]
+fn test_untracked_files_are_changed_throughout() {
+    let (dir, _) = setup();
+    let path = dir.path().join("new.txt");
+    fs::write(&path, "a\nb\n").unwrap();
+    let changed = git_changed_lines(path.to_str().unwrap()).unwrap();
+    assert_eq!((changed.len(), &changed[0]), (1, &(0..2)));
+
+    let outside = TempDir::new().unwrap();
+    let path = outside.path().join("loose.txt");
+    fs::write(&path, "a\n").unwrap();
+    let err = git_changed_lines(path.to_str().unwrap()).unwrap_err();
+    assert!(err.ends_with("is not in a git repository"));
+}
+
+#[test]
+fn test_files_in_subdirectories_diff_against_their_own_path() {
+    let (dir, _) = setup();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    let path = dir.path().join("src").join("file.txt");
+    fs::write(&path, "a\nb\nc\n").unwrap();
+    git(dir.path(), &["add", "src"]);
+    git(dir.path(), &["commit", "-q", "-m", "src"]);
+    let path = path.to_str().unwrap();
+    assert!(git_changed_lines(path).unwrap().is_empty());
+
+    rewrite(path, &[(1, Some("A")), (3, Some("C"))]);
+    assert_eq!(git_changed_lines(path).unwrap(), [0..1, 2..3]);
+}
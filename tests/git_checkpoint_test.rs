@@ -0,0 +1,150 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C").arg(dir)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// A repository with `tracked.txt` committed and `ignored.log` ignored.
+fn setup() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    fs::write(dir.path().join(".gitignore"), "*.log\n").unwrap();
+    fs::write(dir.path().join("tracked.txt"), "one\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "initial"]);
+    dir
+}
+
+#[test]
+fn test_checkpoint_leaves_the_repository_alone() {
+    let dir = setup();
+    let root = dir.path();
+    fs::write(root.join("tracked.txt"), "two\n").unwrap();
+    fs::write(root.join("new.txt"), "new\n").unwrap();
+    let status = git(root, &["status", "--porcelain"]);
+    let head = git(root, &["rev-parse", "HEAD"]);
+
+    let checkpoint = create_git_checkpoint(root, Some("before the batch")).unwrap();
+    assert_eq!(checkpoint.id, 1);
+    assert_eq!(checkpoint.message, "before the batch");
+    assert_eq!(git(root, &["status", "--porcelain"]), status);
+    assert_eq!(git(root, &["rev-parse", "HEAD"]), head);
+    assert_eq!(git(root, &["show", &format!("{}:tracked.txt", checkpoint.commit)]), "two\n");
+    assert_eq!(git(root, &["show", &format!("{}:new.txt", checkpoint.commit)]), "new\n");
+
+    let second = create_git_checkpoint(&root.join("."), None).unwrap();
+    assert_eq!(second.message, "hashline checkpoint 2");
+    assert_eq!(list_git_checkpoints(root).unwrap(), [checkpoint, second]);
+}
+
+#[test]
+fn test_restore_brings_files_back_and_saves_the_current_tree() {
+    let dir = setup();
+    let root = dir.path();
+    fs::write(root.join("ignored.log"), "log\n").unwrap();
+    let checkpoint = create_git_checkpoint(root, None).unwrap();
+    assert!(git(root, &["ls-tree", "-r", "--name-only", &checkpoint.commit]).lines().all(|name| name != "ignored.log"));
+
+    fs::write(root.join("tracked.txt"), "broken\n").unwrap();
+    fs::write(root.join("scratch.txt"), "scratch\n").unwrap();
+    git(root, &["add", "tracked.txt"]);
+    let staged = git(root, &["diff", "--cached"]);
+    let (restored, saved) = restore_git_checkpoint(root, None, &Policy::default()).unwrap();
+    assert_eq!(restored, checkpoint);
+    assert_eq!(saved.id, 2);
+    assert_eq!(fs::read_to_string(root.join("tracked.txt")).unwrap(), "one\n");
+    assert_eq!(git(root, &["diff", "--cached"]), staged);
+    // Untracked files created since the checkpoint stay
+    assert_eq!(fs::read_to_string(root.join("scratch.txt")).unwrap(), "scratch\n");
+
+    restore_git_checkpoint(root, Some(saved.id), &Policy::default()).unwrap();
+    assert_eq!(fs::read_to_string(root.join("tracked.txt")).unwrap(), "broken\n");
+    assert_eq!(restore_git_checkpoint(root, Some(9), &Policy::default()).unwrap_err().to_string(), "Unknown checkpoint 9");
+}
+
+#[test]
+fn test_checkpoint_commands_report_ids() {
+    let dir = setup();
+    let text = cmd_git_checkpoint(dir.path(), None, false, OutputFormat::Text).unwrap();
+    assert!(text.starts_with(&format!("Saved git checkpoint 1 as {}1 (", GIT_CHECKPOINT_REF_PREFIX)));
+
+    let listed: serde_json::Value =
+        serde_json::from_str(&cmd_git_checkpoint(dir.path(), None, true, OutputFormat::Json).unwrap()).unwrap();
+    assert_eq!(listed["checkpoints"][0]["id"], 1);
+
+    let restored = cmd_restore_git_checkpoint(dir.path(), Some(1), &Policy::default(), OutputFormat::Text).unwrap();
+    assert!(restored.ends_with("The working tree it replaced is checkpoint 2"));
+
+    let outside = TempDir::new().unwrap();
+    let err = cmd_git_checkpoint(outside.path(), None, false, OutputFormat::Json).unwrap_err();
+    assert!(err.contains("\"error\":\"git\""));
+}
+
+#[test]
+#[cfg(not(feature = "git2"))]
+fn test_checkpoint_without_git_on_path_says_so() {
+    let dir = setup();
+    let output = Command::new(env!("CARGO_BIN_EXE_hashline-tools"))
+        .current_dir(dir.path())
+        .env("PATH", "")
+        .args(["checkpoint", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("git not found on PATH"), "{}", stderr);
+}
+
+#[test]
+#[cfg(feature = "git2")]
+fn test_checkpoint_with_git2_needs_no_git_on_path() {
+    let dir = setup();
+    let output = Command::new(env!("CARGO_BIN_EXE_hashline-tools"))
+        .current_dir(dir.path())
+        .env("PATH", "")
+        .args(["checkpoint", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(list_git_checkpoints(dir.path()).unwrap().len(), 1);
+}
+
+#[test]
+fn test_restore_checks_the_policy() {
+    let dir = setup();
+    let root = dir.path();
+    let checkpoint = create_git_checkpoint(root, None).unwrap();
+    fs::write(root.join("added.txt"), "added\n").unwrap();
+    git(root, &["add", "added.txt"]);
+
+    // Restoring would delete the tracked file the checkpoint lacks
+    let policy = Policy { forbid_delete: true, ..Policy::default() };
+    let err = cmd_restore_git_checkpoint(root, None, &policy, OutputFormat::Json).unwrap_err();
+    let json: serde_json::Value = serde_json::from_str(&err).unwrap();
+    assert_eq!(json["error"], "policy_violation", "{}", err);
+    assert!(json["message"].as_str().unwrap().contains("deleting files"), "{}", err);
+    assert!(root.join("added.txt").exists());
+    assert_eq!(list_git_checkpoints(root).unwrap().len(), 1);
+
+    // ...and rewrite the tracked file changed since
+    let policy = Policy { forbid_write: true, ..Policy::default() };
+    assert!(restore_git_checkpoint(root, None, &policy).is_ok());
+    fs::write(root.join("tracked.txt"), "two\n").unwrap();
+    let err = restore_git_checkpoint(root, Some(checkpoint.id), &policy).unwrap_err();
+    assert!(matches!(err, HashlineError::Policy(_)), "{}", err);
+    assert_eq!(fs::read_to_string(root.join("tracked.txt")).unwrap(), "two\n");
+}
@@ -0,0 +1,140 @@
+#![cfg(feature = "fs")]
+
+use std::fs;
+use std::time::{Duration, SystemTime};
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+fn age(path: &std::path::Path, secs: u64) {
+    let file = fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() - Duration::from_secs(secs)).unwrap();
+}
+
+fn cache_entries(cache: &HashCache) -> Vec<std::path::PathBuf> {
+    match fs::read_dir(cache.dir()) {
+        Ok(entries) => entries.map(|e| e.unwrap().path()).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+#[test]
+fn test_unchanged_file_is_served_from_cache() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("big.txt");
+    fs::write(&path, "a\nb\nc\n").unwrap();
+    age(&path, 60);
+    let cache = HashCache::new(dir.path().join("cache"));
+
+    let (content, file) = cache.load(&path).unwrap();
+    assert_eq!(content, "a\nb\nc\n");
+    assert_eq!(file, HashedFile::from_content(&content));
+    let entries = cache_entries(&cache);
+    assert_eq!(entries.len(), 1);
+
+    // Doctor the entry: a hit returns its hashes without rehashing
+    let doctored = fs::read_to_string(&entries[0]).unwrap().replace(&file.hashes().iter().map(LineHash::as_str).collect::<String>(), "ZZZZZZ");
+    fs::write(&entries[0], doctored).unwrap();
+    assert_eq!(cache.load(&path).unwrap().1.hashes().iter().map(LineHash::as_str).collect::<Vec<_>>(), ["ZZ", "ZZ", "ZZ"]);
+
+    // Any change to size or mtime is a miss, and the entry is rewritten
+    fs::write(&path, "a\nb\nc\nd\n").unwrap();
+    age(&path, 30);
+    let (content, file) = cache.load(&path).unwrap();
+    assert_eq!(file, HashedFile::from_content(&content));
+    assert_eq!(cache.load(&path).unwrap().1, file);
+}
+
+#[test]
+fn test_recently_modified_files_are_not_cached() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("fresh.txt");
+    fs::write(&path, "a\n").unwrap();
+    let cache = HashCache::new(dir.path().join("cache"));
+
+    let (content, file) = cache.load(&path).unwrap();
+    assert_eq!(file, HashedFile::from_content(&content));
+    assert!(cache_entries(&cache).is_empty());
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_commands_use_the_cache() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("f.txt");
+    fs::write(&path, "one\ntwo\n").unwrap();
+    age(&path, 60);
+    let cache = HashCache::new(dir.path().join("cache"));
+    let file_path = path.to_str().unwrap();
+
+    let cached = cmd_read_with_cache(file_path, None, None, Some(&cache)).unwrap();
+    assert_eq!(cached, cmd_read(file_path, None, None).unwrap());
+
+    let anchor = HashedFile::from_content("one\ntwo\n").anchor(2).unwrap();
+    let edits = format!(r#"[{{"op": "replace", "pos": "{}", "lines": ["TWO"]}}]"#, anchor);
+    let options = EditOptions { hash_cache: Some(cache), ..EditOptions::default() };
+    cmd_edit_with_options(file_path, &edits, &options).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "one\nTWO\n");
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_windowed_reads_seek_through_the_line_index() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("huge.txt");
+    let content: String = (0..SEED_BLOCK_LINES * 3 + 10).map(|i| format!("line {}\r\n", i % 50)).collect();
+    fs::write(&path, &content).unwrap();
+    age(&path, 60);
+    let cache = HashCache::new(dir.path().join("cache"));
+    let file = HashedFile::from_content(&content);
+    let file_path = path.to_str().unwrap();
+
+    let deep = SEED_BLOCK_LINES * 2 + 5..SEED_BLOCK_LINES * 2 + 25;
+    let window = cache.load_window(&path, deep.clone()).unwrap();
+    assert_eq!(window.total_lines, file.len());
+    let expected: Vec<String> = file.annotate(deep.start + 1..=deep.end);
+    assert_eq!(window.annotated().map(|l| l.to_string()).collect::<Vec<_>>(), expected);
+
+    // The index has every block's offset, and seeds up to the end of the window
+    let entries = cache_entries(&cache);
+    assert_eq!(entries.len(), 1);
+    let original = fs::read(&entries[0]).unwrap();
+    let mut entry: serde_json::Value = serde_json::from_slice(&original).unwrap();
+    assert_eq!(entry["total_lines"], file.len());
+    let block_bytes = content.match_indices('\n').nth(SEED_BLOCK_LINES - 1).unwrap().0 + 1;
+    assert_eq!(entry["offsets"][0], block_bytes);
+    assert_eq!(entry["offsets"].as_array().unwrap().len(), 3);
+    let seeds = vec![file.hashes()[SEED_BLOCK_LINES - 1], file.hashes()[SEED_BLOCK_LINES * 2 - 1]];
+    assert_eq!(entry["seeds"], serde_json::json!(seeds));
+
+    // Later reads seek to the block's offset and start from its seed, so a
+    // doctored index shows in the result
+    let skip_a_line = file.lines()[SEED_BLOCK_LINES * 2].len() as u64 + 2;
+    entry["offsets"][1] = serde_json::json!(entry["offsets"][1].as_u64().unwrap() + skip_a_line);
+    entry["seeds"][1] = serde_json::json!("ZZ");
+    fs::write(&entries[0], serde_json::to_vec(&entry).unwrap()).unwrap();
+    let doctored = cache.load_window(&path, deep.clone()).unwrap();
+    assert_eq!(doctored.lines[0], file.lines()[deep.start + 1]);
+    assert_ne!(doctored.hashes, window.hashes);
+
+    fs::write(&entries[0], original).unwrap();
+
+    // Reads match the uncached command, and a later window extends the seeds
+    assert_eq!(
+        cmd_read_with_cache(file_path, Some(100), Some(20), Some(&cache)).unwrap(),
+        cmd_read(file_path, Some(100), Some(20)).unwrap()
+    );
+    let tail = cache.load_window(&path, SEED_BLOCK_LINES * 3..usize::MAX).unwrap();
+    let expected: Vec<String> = file.annotate(SEED_BLOCK_LINES * 3 + 1..=file.len());
+    assert_eq!(tail.annotated().map(|l| l.to_string()).collect::<Vec<_>>(), expected);
+    let entry: serde_json::Value = serde_json::from_slice(&fs::read(&entries[0]).unwrap()).unwrap();
+    assert_eq!(entry["seeds"].as_array().unwrap().len(), 3);
+
+    // A changed file is scanned again
+    fs::write(&path, "short\n").unwrap();
+    age(&path, 30);
+    assert_eq!(
+        cmd_read_with_cache(file_path, None, None, Some(&cache)).unwrap(),
+        cmd_read(file_path, None, None).unwrap()
+    );
+}
@@ -0,0 +1,124 @@
+use hashline_tools::*;
+
+#[test]
+fn test_annotate_clamps_to_file() {
+    let file = HashedFile::from_content("one\ntwo\nthree\n");
+    let hash = |line| file.hash_at(line).unwrap().to_string();
+
+    assert_eq!(file.annotate(2..=10), vec![format!("2#{}:two", hash(2)), format!("3#{}:three", hash(3))]);
+    assert_eq!(file.annotate(0..=1), vec![format!("1#{}:one", hash(1))]);
+    assert!(file.annotate(4..=5).is_empty());
+}
+
+#[test]
+fn test_revalidate_reports_current_anchor() {
+    let old = HashedFile::from_content("one\ntwo\nthree\n");
+    let new = HashedFile::from_content("one\nTWO\nthree\n");
+    let anchor = old.anchor(2).unwrap();
+
+    assert_eq!(old.revalidate(&anchor), AnchorState::Valid);
+    assert_eq!(new.revalidate(&anchor), AnchorState::Stale { current: new.anchor(2).unwrap() });
+    let past_end = AnchorRef { line: 4, hash: "ZZ".to_string() };
+    assert_eq!(new.revalidate(&past_end), AnchorState::OutOfRange { len: 3 });
+}
+
+#[test]
+fn test_update_matches_fresh_hashing() {
+    let mut file = HashedFile::from_content("a\nb\nc\nd\ne\n");
+    for content in ["a\nx\nc\nd\ne\n", "a\nx\ny\nc\nd\ne\n", "c\nd\ne\n", "", "z\n"] {
+        file.update(content.lines().map(|s| s.to_string()).collect());
+        assert_eq!(file, HashedFile::from_content(content), "after updating to {:?}", content);
+    }
+}
+
+#[test]
+fn test_annotate_lines_matches_hashed_file() {
+    let content = "fn main() {\n\n    run();\n}\n";
+    let file = HashedFile::from_content(content);
+
+    let lines: Vec<AnnotatedLine> = annotate_lines(content).collect();
+    assert_eq!(lines.len(), file.len());
+    for annotated in &lines {
+        assert_eq!(Some(annotated.anchor()), file.anchor(annotated.line));
+    }
+    assert_eq!(lines[2].text, "    run();");
+
+    // Filtering and formatting happen per line, without building the whole output
+    let non_blank: Vec<String> = annotate_lines(content).filter(|l| !l.text.is_empty()).map(|l| l.to_string()).collect();
+    assert_eq!(non_blank, vec![file.annotate(1..=1)[0].clone(), file.annotate(3..=3)[0].clone(), file.annotate(4..=4)[0].clone()]);
+}
+
+#[test]
+fn test_rehash_from_matches_full_rehash() {
+    let old = "a\nb\nc\nd\n";
+    let file = HashedFile::from_content(old);
+    for (new, first_changed_line) in [("a\nb\nC\nd\n", 3), ("a\nx\ny\nb\nc\nd\n", 2), ("a\n", 2), ("z\n", 1)] {
+        assert_eq!(file.rehash_from(new, first_changed_line), HashedFile::from_content(new), "for {:?}", new);
+    }
+    // A first_changed_line past the real first change only shortens the reused prefix
+    assert_eq!(file.rehash_from("X\nb\nc\nd\n", 3), HashedFile::from_content("X\nb\nc\nd\n"));
+}
+
+#[test]
+fn test_lines_are_borrowed_until_changed() {
+    use std::borrow::Cow;
+
+    let old = "a\nb\nc\nd\n";
+    let file = HashedFile::from_content(old);
+    assert!(file.lines().iter().all(|line| matches!(line, Cow::Borrowed(_))));
+
+    // Only the rewritten line is copied; the rest still borrow the old content
+    let new = file.rehash_from("a\nB\nc\nd\n", 2);
+    let owned: Vec<bool> = new.lines().iter().map(|line| matches!(line, Cow::Owned(_))).collect();
+    assert_eq!(owned, [false, true, false, false]);
+    assert_eq!(new, HashedFile::from_content("a\nB\nc\nd\n"));
+}
+
+#[test]
+fn test_large_file_hashes_match_sequential_chain() {
+    let content: String = (0..40_000)
+        .map(|i| if i % 7 == 0 { "\n".to_string() } else { format!("    let x{} = {};\r\n", i % 97, i) })
+        .collect();
+    let file = HashedFile::from_content(&content);
+    let sequential: Vec<String> = annotate_lines(&content).map(|l| l.hash.into()).collect();
+    assert_eq!(file.hashes(), sequential.as_slice());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_parallel_hashes_match_sequential_ones() {
+    // Several chunks on a pool of more than one thread, one of them inside a
+    // run of blank lines that keeps its chains from merging
+    let content: String = (0..PARALLEL_HASH_MIN_LINES * 2 + 123)
+        .map(|i| match i {
+            60_000..=100_000 => "\n".to_string(),
+            _ if i % 7 == 0 => format!("fn f{}() {{\n", i % 97),
+            _ if i % 7 == 6 => "}\n".to_string(),
+            _ => format!("    let x{} = {};\r\n", i % 97, i),
+        })
+        .collect();
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+    for mode in [AnchorMode::Chained, AnchorMode::Scoped] {
+        let file = pool.install(|| HashedFile::from_content_with_mode(&content, mode));
+        let sequential: Vec<LineHash> = annotate_lines_with_mode(&content, mode).map(|l| l.hash).collect();
+        assert!(file.hashes() == sequential, "{:?} hashes differ", mode);
+    }
+}
+
+#[test]
+fn test_line_hash_matches_compute_line_hash() {
+    let lines = ["", "  fn main() {\r", "\t}", "   ", "let  x =\u{a0}1;", ";"];
+    let mut prev_str: Option<String> = None;
+    let mut prev: Option<LineHash> = None;
+    for (i, line) in lines.iter().enumerate() {
+        let expected = compute_line_hash(i + 1, line, prev_str.as_deref());
+        let hash = line_hash(i + 1, line, prev);
+        assert_eq!(hash, expected, "line {}", i + 1);
+        assert_eq!(hash.to_string(), expected);
+        assert_eq!(expected.parse::<LineHash>().unwrap(), hash);
+        prev_str = Some(expected);
+        prev = Some(hash);
+    }
+    assert!("ab".parse::<LineHash>().is_err());
+    assert!("ZPM".parse::<LineHash>().is_err());
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "cli")]
+
+use hashline_tools::*;
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_editor_hooks_gate_and_observe_batches() {
+    let seen: Arc<Mutex<Vec<String>>> = Arc::default();
+    let log = Arc::clone(&seen);
+    let hooks = EditHooks::default()
+        .before_apply(|_, edits| if edits.len() > 1 { Err("one edit at a time".to_string()) } else { Ok(()) })
+        .after_apply(move |batch| {
+            let inserted = batch.diff.iter().flat_map(|h| &h.lines).filter(|l| l.kind == DiffLineKind::Insert).count();
+            log.lock().unwrap().push(format!("{}: {} edit, {} inserted", batch.path.display(), batch.edits.len(), inserted));
+        });
+    let mut editor = Editor::from_content("a.txt", "a\nb\n").with_hooks(hooks);
+
+    let append = |pos| HashlineEdit::Append { pos, lines: vec!["c".into()], expected_lines: None };
+    let err = editor.apply(&[append(None), append(None)]).unwrap_err();
+    assert!(matches!(&err, HashlineError::Rejected(m) if m == "one edit at a time"), "got: {}", err);
+    assert_eq!(err.kind(), "rejected");
+    assert_eq!(editor.content(), "a\nb\n");
+
+    editor.apply(&[append(editor.anchor(2))]).unwrap();
+    assert_eq!(editor.content(), "a\nb\nc\n");
+    assert_eq!(*seen.lock().unwrap(), vec!["a.txt: 1 edit, 1 inserted".to_string()]);
+}
+
+#[test]
+fn test_edit_command_runs_hooks_around_write() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("a.txt");
+    fs::write(&path, "a\n").unwrap();
+    let path_str = path.to_str().unwrap();
+    let edits = serde_json::json!([{ "op": "append", "lines": ["b"] }]).to_string();
+
+    let rejecting = EditOptions {
+        format: OutputFormat::Json,
+        hooks: EditHooks::default().before_apply(|_, _| Err("frozen".to_string())),
+        ..EditOptions::default()
+    };
+    let err = cmd_edit_with_options(path_str, &edits, &rejecting).unwrap_err();
+    let json: serde_json::Value = serde_json::from_str(&err).unwrap();
+    assert_eq!(json["error"], "rejected");
+    assert_eq!(json["message"], "Edit rejected: frozen");
+
+    // Dry runs do not ask for approval
+    let dry_run = EditOptions { dry_run: true, ..rejecting };
+    cmd_edit_with_options(path_str, &edits, &dry_run).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "a\n");
+
+    let written: Arc<Mutex<Option<String>>> = Arc::default();
+    let slot = Arc::clone(&written);
+    let observing = EditOptions {
+        hooks: EditHooks::default().after_apply(move |batch| {
+            *slot.lock().unwrap() = Some(fs::read_to_string(batch.path).unwrap());
+        }),
+        ..EditOptions::default()
+    };
+    cmd_edit_with_options(path_str, &edits, &observing).unwrap();
+    assert_eq!(written.lock().unwrap().as_deref(), Some("a\nb\n"));
+}
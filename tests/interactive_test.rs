@@ -0,0 +1,121 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+/// A reviewer that gives `answers` in turn, recording which edits it saw.
+fn scripted(answers: Vec<ReviewDecision>, seen: Arc<Mutex<Vec<(usize, String)>>>) -> EditReviewer {
+    let answers = Mutex::new(answers.into_iter());
+    EditReviewer::new(move |edit| {
+        seen.lock().unwrap().push((edit.index, edit.diff.to_string()));
+        answers.lock().unwrap().next().expect("asked more often than scripted")
+    })
+}
+
+fn batch(content: &str) -> String {
+    let file = HashedFile::from_content(content);
+    serde_json::json!([
+        {"op": "replace", "pos": file.anchor(1).unwrap().to_string(), "lines": ["ONE"]},
+        {"op": "replace", "pos": file.anchor(2).unwrap().to_string(), "lines": ["TWO"]},
+        {"op": "replace", "pos": file.anchor(3).unwrap().to_string(), "lines": ["THREE"]},
+    ]).to_string()
+}
+
+#[test]
+fn test_interactive_writes_only_accepted_edits() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("a.txt");
+    let content = "one\ntwo\nthree\n";
+    fs::write(&path, content).unwrap();
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let answers = vec![ReviewDecision::Accept, ReviewDecision::Reject, ReviewDecision::Accept];
+    let options = EditOptions { review: Some(scripted(answers, seen.clone())), ..EditOptions::default() };
+    let output = cmd_edit_with_options(path.to_str().unwrap(), &batch(content), &options).unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "ONE\ntwo\nTHREE\n");
+    assert!(output.contains("Rejected on review: edits[1]"), "{}", output);
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.iter().map(|(i, _)| *i).collect::<Vec<_>>(), [0, 1, 2]);
+    // Each edit is shown on its own
+    assert!(seen[1].1.contains("TWO") && !seen[1].1.contains("ONE"), "{}", seen[1].1);
+}
+
+#[test]
+fn test_interactive_json_statuses_keep_original_indexes() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("a.txt");
+    let content = "one\ntwo\nthree\n";
+    fs::write(&path, content).unwrap();
+
+    let answers = vec![ReviewDecision::Accept, ReviewDecision::Reject, ReviewDecision::Accept];
+    let options = EditOptions {
+        review: Some(scripted(answers, Arc::new(Mutex::new(Vec::new())))),
+        format: OutputFormat::Json,
+        ..EditOptions::default()
+    };
+    let output = cmd_edit_with_options(path.to_str().unwrap(), &batch(content), &options).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "ONE\ntwo\nTHREE\n");
+    assert_eq!(json["rejected"], serde_json::json!([1]));
+    let statuses = json["statuses"].as_array().unwrap();
+    assert_eq!(statuses.len(), 3);
+    assert_eq!(statuses[0]["status"], "applied");
+    assert_eq!(statuses[1]["status"], "skipped");
+    assert_eq!(statuses[1]["reason"], "rejected");
+    assert_eq!(statuses[2]["status"], "applied");
+}
+
+#[test]
+fn test_interactive_all_and_quit_stop_asking() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("a.txt");
+    let content = "one\ntwo\nthree\n";
+    fs::write(&path, content).unwrap();
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let options = EditOptions {
+        review: Some(scripted(vec![ReviewDecision::Reject, ReviewDecision::AcceptAll], seen.clone())),
+        ..EditOptions::default()
+    };
+    cmd_edit_with_options(path.to_str().unwrap(), &batch(content), &options).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "one\nTWO\nTHREE\n");
+    assert_eq!(seen.lock().unwrap().len(), 2);
+
+    fs::write(&path, content).unwrap();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let options = EditOptions {
+        review: Some(scripted(vec![ReviewDecision::Quit], seen.clone())),
+        format: OutputFormat::Json,
+        ..EditOptions::default()
+    };
+    let output = cmd_edit_with_options(path.to_str().unwrap(), &batch(content), &options).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(json["status"], "unchanged");
+    assert_eq!(json["rejected"], serde_json::json!([0, 1, 2]));
+    assert_eq!(fs::read_to_string(&path).unwrap(), content);
+    assert_eq!(seen.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn test_interactive_is_skipped_for_dry_runs_and_stale_batches() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("a.txt");
+    let content = "one\ntwo\nthree\n";
+    fs::write(&path, content).unwrap();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+
+    let options = EditOptions { dry_run: true, review: Some(scripted(vec![], seen.clone())), ..EditOptions::default() };
+    assert!(cmd_edit_with_options(path.to_str().unwrap(), &batch(content), &options).is_ok());
+
+    // A batch that fails as a whole is reported before anyone is asked
+    let options = EditOptions { review: Some(scripted(vec![], seen.clone())), ..EditOptions::default() };
+    let stale = batch("other\ntwo\nthree\n");
+    assert!(cmd_edit_with_options(path.to_str().unwrap(), &stale, &options).is_err());
+    assert!(seen.lock().unwrap().is_empty());
+    assert_eq!(fs::read_to_string(&path).unwrap(), content);
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "cli")]
+
+use hashline_tools::*;
+use std::fs;
+use std::path::Path;
+
+fn write_file(dir: &Path, name: &str, content: &str) -> String {
+    let path = dir.join(name);
+    fs::write(&path, content).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+/// Lay out what a transaction killed mid-commit leaves behind: backups of
+/// the originals and a journal listing each target and its staged file.
+fn interrupted_transaction(journal_dir: &Path, files: &[(&str, &str, &str)]) {
+    fs::create_dir_all(journal_dir).unwrap();
+    let entries: Vec<serde_json::Value> = files.iter().enumerate()
+        .map(|(i, (path, staged, new_content))| {
+            let backup = journal_dir.join(format!("txn-test.{}.bak", i));
+            fs::write(&backup, format!("original {}\n", i)).unwrap();
+            serde_json::json!({
+                "path": path,
+                "staged": staged,
+                "backup": backup,
+                "new_fingerprint": content_fingerprint(new_content),
+            })
+        })
+        .collect();
+    fs::write(journal_dir.join("txn-test.json"), serde_json::json!({ "files": entries }).to_string()).unwrap();
+}
+
+#[test]
+fn test_recover_completes_interrupted_commit() {
+    let dir = tempfile::tempdir().unwrap();
+    let journal_dir = dir.path().join("journal");
+    // a.txt was already renamed into place; b.txt is still staged
+    let a = write_file(dir.path(), "a.txt", "new a\n");
+    let b = write_file(dir.path(), "b.txt", "original 1\n");
+    let b_staged = write_file(dir.path(), ".b.txt.tmp", "new b\n");
+    interrupted_transaction(&journal_dir, &[(&a, "/nonexistent", "new a\n"), (&b, &b_staged, "new b\n")]);
+
+    let recovered = recover_transactions(&journal_dir, false, &Policy::default()).unwrap();
+    assert_eq!(recovered.len(), 1);
+    assert_eq!(recovered[0].action, "completed");
+    assert_eq!(fs::read_to_string(&a).unwrap(), "new a\n");
+    assert_eq!(fs::read_to_string(&b).unwrap(), "new b\n");
+    assert_eq!(fs::read_dir(&journal_dir).unwrap().count(), 0, "Journal and backups should be removed");
+}
+
+#[test]
+fn test_recover_rolls_back_when_staged_file_is_lost() {
+    let dir = tempfile::tempdir().unwrap();
+    let journal_dir = dir.path().join("journal");
+    let a = write_file(dir.path(), "a.txt", "new a\n");
+    let b = write_file(dir.path(), "b.txt", "original 1\n");
+    interrupted_transaction(&journal_dir, &[(&a, "/nonexistent", "new a\n"), (&b, "/nonexistent", "new b\n")]);
+
+    let output = cmd_recover(&journal_dir, false, &Policy::default(), OutputFormat::Text).unwrap();
+    assert!(output.contains("rolled back"), "Got: {}", output);
+    assert_eq!(fs::read_to_string(&a).unwrap(), "original 0\n");
+    assert_eq!(fs::read_to_string(&b).unwrap(), "original 1\n");
+}
+
+#[test]
+fn test_successful_transaction_leaves_no_journal() {
+    let dir = tempfile::tempdir().unwrap();
+    let journal_dir = dir.path().join("journal");
+    let a = write_file(dir.path(), "a.txt", "a1\n");
+    let options = EditOptions { journal_dir: Some(journal_dir.clone()), ..EditOptions::default() };
+    let payload = serde_json::json!({ a.clone(): [{ "op": "append", "lines": ["a2"] }] }).to_string();
+
+    cmd_multi_edit(&payload, &options).unwrap();
+    assert_eq!(fs::read_to_string(&a).unwrap(), "a1\na2\n");
+    assert_eq!(fs::read_dir(&journal_dir).unwrap().count(), 0);
+    assert_eq!(cmd_recover(&journal_dir, false, &Policy::default(), OutputFormat::Text).unwrap(), "No interrupted transactions found.");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_recover_refuses_a_journal_directory_others_can_write() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let journal_dir = dir.path().join("journal");
+    let a = write_file(dir.path(), "a.txt", "a1\n");
+    let options = EditOptions { journal_dir: Some(journal_dir.clone()), ..EditOptions::default() };
+    let payload = serde_json::json!({ a.clone(): [{ "op": "append", "lines": ["a2"] }] }).to_string();
+    cmd_multi_edit(&payload, &options).unwrap();
+    assert_eq!(fs::metadata(&journal_dir).unwrap().permissions().mode() & 0o777, 0o700);
+
+    // A journal planted where anyone may write is not trusted
+    interrupted_transaction(&journal_dir, &[(&a, "missing.tmp", "new\n")]);
+    fs::set_permissions(&journal_dir, fs::Permissions::from_mode(0o777)).unwrap();
+    let err = recover_transactions(&journal_dir, true, &Policy::default()).unwrap_err();
+    assert!(err.to_string().contains("not be writable by others"), "{}", err);
+    assert_eq!(fs::read_to_string(&a).unwrap(), "a1\na2\n");
+    assert!(cmd_multi_edit(&payload, &options).is_err());
+}
+
+#[test]
+fn test_recover_checks_the_policy() {
+    let dir = tempfile::tempdir().unwrap();
+    let journal_dir = dir.path().join("journal");
+    let a = write_file(dir.path(), "a.txt", "new a\n");
+    interrupted_transaction(&journal_dir, &[(&a, "/nonexistent", "newer a\n")]);
+
+    let policy = Policy { forbid_write: true, ..Policy::default() };
+    let err = cmd_recover(&journal_dir, false, &policy, OutputFormat::Json).unwrap_err();
+    let json: serde_json::Value = serde_json::from_str(&err).unwrap();
+    assert_eq!(json["error"], "policy_violation", "{}", err);
+    assert_eq!(fs::read_to_string(&a).unwrap(), "new a\n");
+    assert!(journal_dir.join("txn-test.json").exists());
+}
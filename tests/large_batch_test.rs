@@ -0,0 +1,65 @@
+use hashline_tools::*;
+
+#[test]
+fn test_large_batch_matches_independent_rewrite() {
+    let original: Vec<String> = (1..=20_000).map(|i| format!("line {}", i)).collect();
+    let content = original.join("\n") + "\n";
+    let file = HashedFile::from_content(&content);
+
+    // Every 10th line replaced, a line inserted after every 10th+5, and a
+    // range deleted near the top, so the gap has to travel the whole file
+    let mut batch = edits();
+    for line in (10..=20_000).step_by(10) {
+        batch = batch.replace(file.anchor(line).unwrap(), [format!("LINE {}", line)]);
+    }
+    for line in (5..20_000).step_by(10) {
+        batch = batch.append_after(file.anchor(line).unwrap(), [format!("after {}", line)]);
+    }
+    batch = batch.delete_range(file.anchor(1).unwrap(), file.anchor(3).unwrap());
+    let batch = batch.build().unwrap();
+    assert_eq!(batch.len(), 4_001);
+
+    let mut expected: Vec<String> = Vec::new();
+    for (i, text) in original.iter().enumerate() {
+        let line = i + 1;
+        match line {
+            1..=3 => {}
+            _ if line % 10 == 0 => expected.push(format!("LINE {}", line)),
+            _ => expected.push(text.clone()),
+        }
+        if line % 10 == 5 {
+            expected.push(format!("after {}", line));
+        }
+    }
+
+    let (new_content, first_changed_line) = apply_hashline_edits(&content, &batch).unwrap();
+    assert_eq!(new_content, expected.join("\n") + "\n");
+    assert_eq!(first_changed_line, Some(1));
+}
+
+#[test]
+fn test_large_batch_reports_every_overlap_at_once() {
+    let content: String = (1..=10_000).map(|i| format!("line {}\n", i)).collect();
+    let file = HashedFile::from_content(&content);
+
+    let mut batch = edits();
+    for line in 1..=10_000 {
+        batch = batch.replace(file.anchor(line).unwrap(), [format!("LINE {}", line)]);
+    }
+    // edits[10000] collides with edits[99..=101], the inserts with the
+    // replaces next to them, and the two inserts with each other because
+    // they share an anchor even though they share no line
+    batch = batch.replace_range(file.anchor(100).unwrap(), file.anchor(102).unwrap(), ["X"]);
+    batch = batch.append_after(file.anchor(5_000).unwrap(), ["after"]);
+    batch = batch.prepend_before(file.anchor(5_000).unwrap(), ["before"]);
+    let batch = batch.build().unwrap();
+
+    let Err(HashlineError::Overlap(overlaps)) = apply_hashline_edits(&content, &batch) else {
+        panic!("expected an overlap error");
+    };
+    let pairs: Vec<&str> = overlaps.iter()
+        .map(|o| o.trim_start_matches("  - ").split(' ').next().unwrap())
+        .collect();
+    assert_eq!(pairs, ["edits[99]", "edits[100]", "edits[101]", "edits[4999]", "edits[5000]", "edits[10001]"]);
+    assert!(overlaps[5].ends_with("overlaps with edits[10002] prepend [\"before\"] at lines 5000-5000"), "{}", overlaps[5]);
+}
@@ -0,0 +1,84 @@
+use hashline_tools::*;
+
+fn replace(anchor: AnchorRef, lines: &[&str]) -> HashlineEdit {
+    HashlineEdit::Replace { pos: anchor, end: None, lines: lines.iter().map(|l| l.to_string()).collect(), expected_lines: None }
+}
+
+#[test]
+fn test_batches_over_a_limit_are_refused() {
+    let content = "one\ntwo\nthree\n";
+    let file = HashedFile::from_content(content);
+    let edits = vec![replace(file.anchor(1).unwrap(), &["ONE", "1"]), replace(file.anchor(3).unwrap(), &["THREE"])];
+    let with = |limits| ApplyOptions { limits, ..ApplyOptions::default() };
+
+    let cases = [
+        (Limits { max_file_bytes: Some(13), ..Limits::default() }, Limit::FileBytes, 13, 14),
+        (Limits { max_edits: Some(1), ..Limits::default() }, Limit::Edits, 1, 2),
+        (Limits { max_payload_lines: Some(2), ..Limits::default() }, Limit::PayloadLines, 2, 3),
+    ];
+    for (limits, limit, max, actual) in cases {
+        let err = apply_hashline_edits_with_options(content, &edits, &with(limits)).unwrap_err();
+        assert!(matches!(err, HashlineError::LimitExceeded(e) if e == LimitExceeded { limit, max, actual }), "{:?}", limit);
+        assert!(apply_and_diff(content, &edits, &with(limits)).is_err());
+    }
+
+    let err = apply_hashline_edits_with_options(content, &edits, &with(Limits { max_edits: Some(1), ..Limits::default() })).unwrap_err();
+    assert_eq!(err.kind(), "limit_exceeded");
+    assert_eq!(err.to_string(), "Batch has 2 edits, over the limit of 1");
+    let json = err.to_json();
+    assert_eq!((json["error"].as_str(), json["limit"].as_str(), &json["max"], &json["actual"]), (Some("limit_exceeded"), Some("edits"), &1.into(), &2.into()));
+
+    let at_the_limits = Limits { max_file_bytes: Some(14), max_edits: Some(2), max_payload_lines: Some(3) };
+    let outcome = apply_hashline_edits_with_options(content, &edits, &with(at_the_limits)).unwrap();
+    assert_eq!(outcome.content, "ONE\n1\ntwo\nTHREE\n");
+    // Each step of a sequential batch is not held to the limits again
+    let bottom_up: Vec<HashlineEdit> = edits.iter().rev().cloned().collect();
+    let sequential = ApplyOptions { sequential: true, ..with(at_the_limits) };
+    assert_eq!(apply_hashline_edits_with_options(content, &bottom_up, &sequential).unwrap().content, outcome.content);
+}
+
+#[test]
+fn test_limits_deserialize_with_the_options() {
+    let options: ApplyOptions = serde_json::from_str(r#"{"limits": {"max_edits": 5}}"#).unwrap();
+    assert_eq!(options.limits, Limits { max_edits: Some(5), ..Limits::default() });
+    assert_eq!(ApplyOptions::default().limits, Limits::default());
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_edit_commands_refuse_requests_over_a_limit() {
+    use std::fs;
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("f.txt");
+    fs::write(&path, "one\ntwo\n").unwrap();
+    let file_path = path.to_str().unwrap();
+    let file = HashedFile::from_content("one\ntwo\n");
+    let edits = format!(
+        r#"[{{"op": "replace", "pos": "{}", "lines": ["ONE"]}}, {{"op": "replace", "pos": "{}", "lines": ["TWO"]}}]"#,
+        file.anchor(1).unwrap(),
+        file.anchor(2).unwrap()
+    );
+    let limited = |limits| EditOptions {
+        apply: ApplyOptions { limits, ..ApplyOptions::default() },
+        format: OutputFormat::Json,
+        checkpoints: false,
+        ..EditOptions::default()
+    };
+
+    let small = limited(Limits { max_file_bytes: Some(7), ..Limits::default() });
+    let err: serde_json::Value = serde_json::from_str(&cmd_edit_with_options(file_path, &edits, &small).unwrap_err()).unwrap();
+    assert_eq!((err["error"].as_str(), err["limit"].as_str(), &err["actual"]), (Some("limit_exceeded"), Some("file_bytes"), &8.into()));
+    assert_eq!(err["file"], file_path);
+
+    let few = limited(Limits { max_edits: Some(1), ..Limits::default() });
+    let validate_only = EditOptions { validate_only: true, ..few.clone() };
+    let err: serde_json::Value = serde_json::from_str(&cmd_edit_with_options(file_path, &edits, &validate_only).unwrap_err()).unwrap();
+    assert_eq!((err["error"].as_str(), err["limit"].as_str()), (Some("limit_exceeded"), Some("edits")));
+    let multi = serde_json::json!({ file_path: edits.parse::<serde_json::Value>().unwrap() }).to_string();
+    assert!(cmd_multi_edit(&multi, &few).unwrap_err().contains("limit_exceeded"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+
+    cmd_edit_with_options(file_path, &edits, &limited(Limits { max_file_bytes: Some(8), max_edits: Some(2), max_payload_lines: Some(2) })).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "ONE\nTWO\n");
+}
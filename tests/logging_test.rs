@@ -0,0 +1,23 @@
+#![cfg(feature = "cli")]
+
+use clap::Parser;
+use hashline_tools::*;
+
+#[test]
+fn test_log_level_flag() {
+    let cli = Cli::try_parse_from(["hashline-tools", "read", "f.txt", "--log-level", "debug"]).unwrap();
+    assert_eq!(cli.log_level, Some(LogLevel::Debug));
+    assert_eq!(Cli::try_parse_from(["hashline-tools", "read", "f.txt"]).unwrap().log_level, None);
+    assert!(Cli::try_parse_from(["hashline-tools", "--log-level", "verbose", "read", "f.txt"]).is_err());
+}
+
+#[test]
+fn test_init_logging_sets_the_level() {
+    assert_eq!(LogLevel::default().filter(), log::LevelFilter::Off);
+    init_logging(Some(LogLevel::Trace));
+    assert_eq!(log::max_level(), log::LevelFilter::Trace);
+    assert!(log::log_enabled!(target: "hashline_tools::engine", log::Level::Debug));
+    // Only the first call takes effect
+    init_logging(Some(LogLevel::Error));
+    assert_eq!(log::max_level(), log::LevelFilter::Trace);
+}
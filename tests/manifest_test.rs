@@ -0,0 +1,151 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+#[test]
+fn test_manifest_round_trips_and_reports_drift() {
+    let dir = TempDir::new().unwrap();
+    let src = dir.path().join("src");
+    fs::create_dir_all(src.join("gen")).unwrap();
+    fs::write(src.join("a.rs"), "fn a() {}\nfn b() {}\nfn c() {}\n").unwrap();
+    fs::write(src.join("gen/b.rs"), "one\ntwo\n").unwrap();
+    fs::write(src.join("gone.rs"), "x\n").unwrap();
+    fs::write(src.join("blob.bin"), b"\0\x01").unwrap();
+
+    let output = cmd_manifest(&src, None, AnchorMode::Chained, &Policy::default(), OutputFormat::Text).unwrap();
+    let manifest_path = src.join(DIR_MANIFEST_FILE_NAME);
+    assert_eq!(output, format!("Wrote the anchors of 3 files (6 lines) to {}", manifest_path.display()));
+    let manifest = Manifest::load(&manifest_path).unwrap();
+    assert_eq!(manifest.root, ".");
+    assert_eq!(manifest.files.keys().collect::<Vec<_>>(), ["a.rs", "gen/b.rs", "gone.rs"]);
+    let a = HashedFile::from_content("fn a() {}\nfn b() {}\nfn c() {}\n");
+    assert_eq!(manifest.files["a.rs"].anchors, (1..=3).map(|i| a.anchor(i).unwrap().to_string()).collect::<Vec<_>>());
+    assert_eq!(cmd_verify_manifest(&manifest_path, OutputFormat::Text).unwrap(), format!("All 3 files match {}", manifest_path.display()));
+
+    fs::write(src.join("a.rs"), "fn a() {}\nfn b2() {}\nfn c() {}\nfn d() {}\n").unwrap();
+    fs::write(src.join("gen/b.rs"), "one\n  two\n").unwrap();
+    fs::remove_file(src.join("gone.rs")).unwrap();
+    fs::write(src.join("new.rs"), "new\n").unwrap();
+    let report = manifest.verify(&manifest_path).unwrap();
+    let modified = HashedFile::from_content("fn a() {}\nfn b2() {}\nfn c() {}\nfn d() {}\n");
+    assert_eq!(report, [
+        FileDrift {
+            path: "a.rs".to_string(),
+            drift: Drift::Modified {
+                first_changed_line: Some(2),
+                was: Some(a.anchor(2).unwrap().to_string()),
+                now: Some(modified.anchor(2).unwrap().to_string()),
+                old_lines: 3,
+                new_lines: 4,
+            },
+        },
+        FileDrift {
+            path: "gen/b.rs".to_string(),
+            drift: Drift::Modified { first_changed_line: None, was: None, now: None, old_lines: 2, new_lines: 2 },
+        },
+        FileDrift { path: "gone.rs".to_string(), drift: Drift::Missing },
+        FileDrift { path: "new.rs".to_string(), drift: Drift::Added },
+    ]);
+
+    let error = cmd_verify_manifest(&manifest_path, OutputFormat::Text).unwrap_err();
+    assert!(error.starts_with(&format!("4 of 4 files in {} drifted:\n", manifest_path.display())), "{}", error);
+    assert!(error.contains("  - gen/b.rs: whitespace changed\n  - gone.rs: missing\n  - new.rs: added"), "{}", error);
+    let json: serde_json::Value = serde_json::from_str(&cmd_verify_manifest(&manifest_path, OutputFormat::Json).unwrap_err()).unwrap();
+    assert_eq!(json["error"], "manifest_drift");
+    assert_eq!(json["drifted"][0]["status"], "modified");
+    assert_eq!(json["drifted"][0]["first_changed_line"], 2);
+}
+
+#[test]
+fn test_file_manifest_sits_beside_the_file() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("gen.rs");
+    fs::write(&path, "a\nb\n").unwrap();
+    cmd_manifest(&path, None, AnchorMode::Scoped, &Policy::default(), OutputFormat::Text).unwrap();
+
+    let manifest_path = dir.path().join("gen.rs.hashline");
+    assert_eq!(default_manifest_path(&path), manifest_path);
+    let manifest = Manifest::load(&manifest_path).unwrap();
+    assert_eq!((manifest.root.as_str(), manifest.anchors), ("gen.rs", AnchorMode::Scoped));
+    // Files beside the one covered are not added
+    fs::write(dir.path().join("other.rs"), "x\n").unwrap();
+    assert!(cmd_verify_manifest(&manifest_path, OutputFormat::Text).is_ok());
+
+    // The manifest must be able to name the file by a relative path
+    let elsewhere = dir.path().join("sub/gen.hashline");
+    fs::create_dir_all(dir.path().join("sub")).unwrap();
+    let error = cmd_manifest(&path, Some(&elsewhere), AnchorMode::Chained, &Policy::default(), OutputFormat::Text).unwrap_err();
+    assert!(error.contains("must be in a directory containing"), "{}", error);
+
+    fs::write(&manifest_path, r#"{"version": 99, "anchors": "chained", "root": ".", "files": {}}"#).unwrap();
+    let error = cmd_verify_manifest(&manifest_path, OutputFormat::Text).unwrap_err();
+    assert!(error.contains("is version 99"), "{}", error);
+}
+
+#[test]
+fn test_changed_region_matches_unchanged_lines_at_both_ends() {
+    let old: String = (1..=30).map(|i| format!("line {}\n", i)).collect();
+    let new = old.replace("line 10\nline 11\n", "line 10\nten and a half\nline 11 changed\n");
+    for mode in [AnchorMode::Chained, AnchorMode::Scoped] {
+        let file = HashedFile::from_content_with_mode(&old, mode);
+        let listed = ManifestFile {
+            fingerprint: content_fingerprint(&old),
+            anchors: (1..=30).map(|i| file.anchor(i).unwrap().to_string()).collect(),
+        };
+        let region = listed.changed_region(&new, mode).unwrap();
+        let current = HashedFile::from_content_with_mode(&new, mode);
+        assert_eq!((region.start, region.old_lines, region.new_lines), (11, 1, 2), "{:?}", mode);
+        assert_eq!(region.lines, current.annotate(11..=12));
+        assert_eq!(listed.changed_region(&old, mode), None);
+
+        let shorter = old.replace("line 20\n", "");
+        let region = listed.changed_region(&shorter, mode).unwrap();
+        assert_eq!((region.start, region.old_lines, region.new_lines, region.lines.len()), (20, 1, 0, 0));
+    }
+}
+
+#[test]
+fn test_drift_reports_changed_regions() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("a.txt"), "one\ntwo\nthree\n").unwrap();
+    fs::write(dir.path().join("b.txt"), "b\n").unwrap();
+    fs::write(dir.path().join("c.txt"), "c\n").unwrap();
+    cmd_manifest(dir.path(), None, AnchorMode::Chained, &Policy::default(), OutputFormat::Text).unwrap();
+    let manifest_path = dir.path().join(DIR_MANIFEST_FILE_NAME);
+
+    fs::write(dir.path().join("a.txt"), "one\n2\n2.5\nthree\n").unwrap();
+    fs::remove_file(dir.path().join("b.txt")).unwrap();
+    fs::write(dir.path().join("d.txt"), "d\n").unwrap();
+    let a = HashedFile::from_content("one\n2\n2.5\nthree\n");
+    let report = cmd_drift(&manifest_path, OutputFormat::Text).unwrap();
+    assert_eq!(report, format!(
+        "Since {}: 1 added, 1 missing, 1 modified, 1 unchanged\nmodified {}: lines 2-3 changed, was line 2\n{}\n{}\nmissing b.txt\nadded d.txt",
+        manifest_path.display(), "a.txt", a.annotate(2..=2)[0], a.annotate(3..=3)[0],
+    ));
+
+    let json: serde_json::Value = serde_json::from_str(&cmd_drift(&manifest_path, OutputFormat::Json).unwrap()).unwrap();
+    assert_eq!((json["added"].as_u64(), json["modified"].as_u64()), (Some(1), Some(1)));
+    assert_eq!(json["files"][0]["path"], "a.txt");
+    assert_eq!(json["files"][0]["region"]["start"], 2);
+    assert_eq!(json["files"][0]["region"]["lines"][1], a.annotate(3..=3)[0]);
+    assert_eq!(json["files"][1]["status"], "missing");
+}
+
+#[test]
+fn test_manifest_output_is_checked_against_the_policy() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    let path = dir.path().join("src/a.rs");
+    fs::write(&path, "fn a() {}\n").unwrap();
+    fs::write(dir.path().join("policy.json"), r#"{"allowed_paths": ["src/**"]}"#).unwrap();
+    let policy = Policy::load(&dir.path().join("policy.json")).unwrap();
+
+    let outside = dir.path().join("a.hashline");
+    let error = cmd_manifest(&path, Some(&outside), AnchorMode::Chained, &policy, OutputFormat::Json).unwrap_err();
+    assert!(error.contains(r#""error":"policy_violation""#), "{}", error);
+    assert!(!outside.exists());
+    cmd_manifest(&path, None, AnchorMode::Chained, &policy, OutputFormat::Text).unwrap();
+}
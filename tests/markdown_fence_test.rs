@@ -0,0 +1,111 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+const README: &str = "\
+# Setup
+
+```sh
+cargo build
+```
+
+Then configure it:
+
+~~~toml title=\"config.toml\"
+[server]
+port = 8080
+~~~
+
+```rust
+fn main() {
+    println!(\"```\");
+}
+```
+
+```python
+print(1)
+";
+
+fn fence_anchor(index: usize, line: usize) -> String {
+    let fences = markdown_fences(README);
+    let body = &README[fences[index].content.clone()];
+    HashedFile::from_content(body).anchor(line).unwrap().to_string()
+}
+
+#[test]
+fn test_fences_are_found_with_their_info_and_content() {
+    let fences = markdown_fences(README);
+    assert_eq!(fences.len(), 4);
+    assert_eq!(fences[0].language(), Some("sh"));
+    assert_eq!(fences[0].line, 3);
+    assert_eq!(&README[fences[0].content.clone()], "cargo build\n");
+    assert_eq!(fences[1].info, "toml title=\"config.toml\"");
+    assert_eq!(&README[fences[2].content.clone()], "fn main() {\n    println!(\"```\");\n}\n");
+    assert!(fences[2].closed && !fences[3].closed);
+    assert_eq!(&README[fences[3].content.clone()], "print(1)\n");
+}
+
+#[test]
+fn test_fences_are_selected_by_index_language_or_attribute() {
+    let fences = markdown_fences(README);
+    let select = |s: &str| s.parse::<FenceSelector>().unwrap().select(&fences).map(|f| f.index);
+    assert_eq!(select("2"), Ok(2));
+    assert_eq!(select("rust"), Ok(2));
+    assert_eq!(select("config.toml"), Ok(1));
+    assert_eq!(select("toml"), Ok(1));
+    assert!(select("4").unwrap_err().contains("1: toml title=\"config.toml\" (line 9)"));
+    assert!(select("go").unwrap_err().starts_with("No fence matches 'go'"));
+
+    let twice = markdown_fences("```sh\na\n```\n```sh\nb\n```\n");
+    let err = FenceSelector::Name("sh".to_string()).select(&twice).unwrap_err();
+    assert!(err.starts_with("2 fences match 'sh'; select one by index"));
+}
+
+#[test]
+fn test_read_fence_hashes_the_fence_alone() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("README.md");
+    fs::write(&path, README).unwrap();
+
+    let options = ReadOptions { fence: Some(FenceSelector::Name("rust".to_string())), ..ReadOptions::default() };
+    let mut out = Vec::new();
+    cmd_read_with_options(&mut out, path.to_str().unwrap(), &options).unwrap();
+    let output = String::from_utf8(out).unwrap();
+    assert_eq!(output, format!(
+        "<fence index=\"2\" line=\"14\" info=\"rust\">\n{}:fn main() {{\n{}:    println!(\"```\");\n{}:}}\n\n(End of fence - 3 total lines)\n</fence>",
+        fence_anchor(2, 1),
+        fence_anchor(2, 2),
+        fence_anchor(2, 3),
+    ));
+}
+
+#[test]
+fn test_fence_edits_splice_into_the_document() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("README.md");
+    fs::write(&path, README).unwrap();
+    let file_path = path.to_str().unwrap();
+    let options = EditOptions { fence: Some(FenceSelector::Index(1)), checkpoints: false, ..EditOptions::default() };
+
+    let edits = format!(r#"[{{"op": "replace", "pos": "{}", "lines": ["port = 9090", "host = \"localhost\""]}}]"#, fence_anchor(1, 2));
+    let result = cmd_edit_with_options(file_path, &edits, &options).unwrap();
+    assert!(result.starts_with("Edit applied successfully (first change at line 2 of fence 1)."));
+    let edited = fs::read_to_string(&path).unwrap();
+    assert_eq!(edited, README.replace("port = 8080\n", "port = 9090\nhost = \"localhost\"\n"));
+
+    // Anchors from the whole document, or another fence, do not validate
+    let whole = HashedFile::from_content(&edited).anchor(2).unwrap();
+    let edits = format!(r#"[{{"op": "delete", "pos": "{}"}}]"#, whole);
+    assert!(cmd_edit_with_options(file_path, &edits, &options).unwrap_err().contains("Hash mismatch"));
+
+    // Deleting a fence's last line keeps its closing marker on a line of its own
+    let edits = format!(r#"[{{"op": "delete", "pos": "{}"}}]"#, fence_anchor(0, 1));
+    let options = EditOptions { fence: Some(FenceSelector::Name("sh".to_string())), ..options };
+    cmd_edit_with_options(file_path, &edits, &options).unwrap();
+    let edits = r#"[{"op": "append", "lines": ["cargo test"]}]"#;
+    cmd_edit_with_options(file_path, edits, &options).unwrap();
+    assert!(fs::read_to_string(&path).unwrap().starts_with("# Setup\n\n```sh\ncargo test\n```\n\nThen"));
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+#[test]
+fn test_parse_byte_size() {
+    assert_eq!(parse_byte_size("1000"), Ok(1000));
+    assert_eq!(parse_byte_size("64k"), Ok(64 << 10));
+    assert_eq!(parse_byte_size("512MiB"), Ok(512 << 20));
+    assert_eq!(parse_byte_size("2G"), Ok(2 << 30));
+    assert!(parse_byte_size("2X").is_err());
+    assert!(parse_byte_size("M").is_err());
+    assert!(parse_byte_size("99999999999T").is_err());
+}
+
+#[test]
+fn test_reads_over_budget_stream_the_same_output() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("f.txt");
+    fs::write(&path, "one\r\n\ntwo  three\nfour").unwrap();
+    let file_path = path.to_str().unwrap();
+
+    for (offset, limit) in [(None, None), (Some(1), Some(2)), (Some(3), None), (Some(4), None), (Some(10), Some(1))] {
+        let options = ReadOptions { offset, limit, memory_budget: Some(4), ..ReadOptions::default() };
+        let mut out = Vec::new();
+        cmd_read_with_options(&mut out, file_path, &options).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), cmd_read(file_path, offset, limit).unwrap(), "{:?} {:?}", offset, limit);
+    }
+}
+
+#[test]
+fn test_edits_over_budget_are_refused() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("f.txt");
+    fs::write(&path, "one\ntwo\n").unwrap();
+    let file_path = path.to_str().unwrap();
+    let anchor = HashedFile::from_content("one\ntwo\n").anchor(1).unwrap();
+    let edits = format!(r#"[{{"op": "replace", "pos": "{}", "lines": ["ONE"]}}]"#, anchor);
+
+    let tight = EditOptions { memory_budget: Some(8 * EDIT_MEMORY_PER_BYTE - 1), format: OutputFormat::Json, ..EditOptions::default() };
+    let err: serde_json::Value = serde_json::from_str(&cmd_edit_with_options(file_path, &edits, &tight).unwrap_err()).unwrap();
+    assert_eq!(err["error"], "too_large");
+    assert!(err["message"].as_str().unwrap().starts_with("File too large for in-memory mode:"));
+    let multi = serde_json::json!({ file_path: edits.parse::<serde_json::Value>().unwrap() }).to_string();
+    assert!(cmd_multi_edit(&multi, &tight).unwrap_err().contains("too_large"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+
+    let enough = EditOptions { memory_budget: Some(8 * EDIT_MEMORY_PER_BYTE), checkpoints: false, ..EditOptions::default() };
+    cmd_edit_with_options(file_path, &edits, &enough).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "ONE\ntwo\n");
+}
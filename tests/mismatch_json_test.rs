@@ -0,0 +1,132 @@
+use hashline_tools::*;
+#[cfg(feature = "cli")]
+use std::io::Write;
+#[cfg(feature = "cli")]
+use tempfile::NamedTempFile;
+
+// Helper function to compute cumulative hashes for a file and get a specific line's hash
+fn get_line_hash(content: &str, line_num: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut prev_hash: Option<&str> = None;
+    let mut cumulative_hashes: Vec<String> = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let ln = i + 1;
+        let hash = compute_line_hash(ln, line, prev_hash);
+        cumulative_hashes.push(hash);
+        prev_hash = Some(&cumulative_hashes[i]);
+    }
+
+    cumulative_hashes[line_num - 1].clone()
+}
+
+#[cfg(feature = "cli")]
+fn json_options() -> EditOptions {
+    EditOptions { format: OutputFormat::Json, ..EditOptions::default() }
+}
+
+#[test]
+fn test_mismatch_error_to_json_includes_current_state() {
+    let original = "fn main() {\n    let total = compute(1, 2);\n}\n";
+    let stale_hash = get_line_hash(original, 2);
+    let current = format!("// header\n{}", original);
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 2, hash: stale_hash.clone() },
+            end: None,
+            lines: vec!["    let total = compute(1, 3);".to_string()],
+            expected_lines: None,
+        }
+    ];
+
+    let err = apply_hashline_edits(&current, &edits).unwrap_err();
+    let HashlineError::Mismatch(mismatch) = err else { panic!("expected a mismatch error, got {}", err) };
+    let json = mismatch.to_json();
+
+    assert_eq!(json["error"], "hash_mismatch");
+    let entry = &json["mismatches"][0];
+    assert_eq!(entry["anchor"], format!("2#{}", stale_hash));
+    assert_eq!(entry["current_hash"], get_line_hash(&current, 2));
+    assert_eq!(entry["current_text"], "fn main() {");
+
+    let candidates = entry["candidates"].as_array().unwrap();
+    assert_eq!(candidates[0]["anchor"], format!("3#{}", get_line_hash(&current, 3)));
+    assert_eq!(candidates[0]["method"], "content");
+}
+
+#[test]
+fn test_anchor_ref_serializes_as_string() {
+    let anchor = AnchorRef { line: 8, hash: "RT".to_string() };
+    assert_eq!(serde_json::to_string(&anchor).unwrap(), "\"8#RT\"");
+    let back: AnchorRef = serde_json::from_str("\"8#RT\"").unwrap();
+    assert_eq!(back, anchor);
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_cmd_edit_json_mismatch_error() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(temp_file, "first\nsecond\nthird\n").unwrap();
+    let path = temp_file.path().to_str().unwrap().to_string();
+
+    let err = cmd_edit_with_options(
+        &path,
+        r#"[{"op": "replace", "pos": "2#ZZ", "lines": ["x"]}]"#,
+        &json_options(),
+    ).unwrap_err();
+
+    let value: serde_json::Value = serde_json::from_str(&err).expect("error should be JSON");
+    assert_eq!(value["status"], "error");
+    assert_eq!(value["error"], "hash_mismatch");
+    assert_eq!(value["mismatches"][0]["line"], 2);
+    assert_eq!(value["mismatches"][0]["current_text"], "second");
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_cmd_edit_json_success() {
+    let content = "first\nsecond\n";
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(temp_file, "{}", content).unwrap();
+    let path = temp_file.path().to_str().unwrap().to_string();
+
+    let edits_json = format!(r#"[{{"op": "replace", "pos": "2#{}", "lines": ["2nd"]}}]"#, get_line_hash(content, 2));
+    let output = cmd_edit_with_options(&path, &edits_json, &json_options()).unwrap();
+
+    let value: serde_json::Value = serde_json::from_str(&output).expect("output should be JSON");
+    assert_eq!(value["status"], "applied");
+    assert_eq!(value["first_changed_line"], 2);
+    assert!(value["diff"].as_str().unwrap().contains("2nd"));
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_cmd_edit_json_parse_error() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    writeln!(temp_file, "first").unwrap();
+    let path = temp_file.path().to_str().unwrap().to_string();
+
+    let err = cmd_edit_with_options(&path, "not json", &json_options()).unwrap_err();
+    let value: serde_json::Value = serde_json::from_str(&err).unwrap();
+    assert_eq!(value["error"], "invalid_edits");
+}
+
+#[test]
+fn test_mismatch_error_captures_context_lines() {
+    let content = (1..=10).map(|i| format!("line {}", i)).collect::<Vec<_>>().join("\n");
+    let file = HashedFile::from_content(&content);
+    let edits = vec![HashlineEdit::Replace {
+        pos: AnchorRef { line: 5, hash: "ZZ".to_string() },
+        end: None,
+        lines: vec!["x".to_string()],
+        expected_lines: None,
+    }];
+    let options = ApplyOptions { mismatch_context: 1, ..ApplyOptions::default() };
+
+    let err = apply_hashline_edits_with_options(&content, &edits, &options).unwrap_err();
+    let HashlineError::Mismatch(mismatch) = err else { panic!("expected a mismatch error, got {}", err) };
+    let lines: Vec<usize> = mismatch.context_lines.iter().map(|l| l.line).collect();
+    assert_eq!(lines, vec![4, 5, 6]);
+    assert_eq!(mismatch.context_lines[1].to_string(), format!("5#{}:line 5", file.hashes()[4]));
+    assert!(mismatch.to_string().contains(&format!(">>> 5#{}:line 5", file.hashes()[4])));
+}
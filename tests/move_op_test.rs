@@ -0,0 +1,62 @@
+use hashline_tools::*;
+
+const CONTENT: &str = "one\ntwo\nthree\nfour\nfive\n";
+
+fn anchor(file: &HashedFile, line: usize) -> AnchorRef {
+    file.anchor(line).unwrap()
+}
+
+#[test]
+fn test_move_down_and_up() {
+    let file = HashedFile::from_content(CONTENT);
+    let down = HashlineEdit::Move { pos: anchor(&file, 1), end: Some(anchor(&file, 2)), after: anchor(&file, 4) };
+    let outcome = apply_hashline_edits_with_options(CONTENT, &[down], &ApplyOptions::default()).unwrap();
+    assert_eq!(outcome.content, "three\nfour\none\ntwo\nfive\n");
+    assert_eq!(outcome.first_changed_line, Some(1));
+
+    let up = HashlineEdit::Move { pos: anchor(&file, 5), end: None, after: anchor(&file, 1) };
+    assert_eq!(apply_hashline_edits(CONTENT, &[up]).unwrap().0, "one\nfive\ntwo\nthree\nfour\n");
+
+    // Moves combine with other edits of the batch, each against the original lines
+    let edits: Vec<HashlineEdit> = serde_json::from_value(serde_json::json!([
+        {"op": "move", "pos": anchor(&file, 4).to_string(), "after": anchor(&file, 1).to_string()},
+        {"op": "replace", "pos": anchor(&file, 5).to_string(), "lines": ["FIVE"]},
+    ])).unwrap();
+    assert_eq!(apply_hashline_edits(CONTENT, &edits).unwrap().0, "one\nfour\ntwo\nthree\nFIVE\n");
+}
+
+#[test]
+fn test_move_checks_every_anchor() {
+    let file = HashedFile::from_content(CONTENT);
+    let stale = AnchorRef { line: 4, hash: anchor(&file, 1).hash };
+    let edit = HashlineEdit::Move { pos: anchor(&file, 1), end: None, after: stale };
+    let err = apply_hashline_edits(CONTENT, &[edit]).unwrap_err();
+    assert!(matches!(err, HashlineError::Mismatch(_)), "{}", err);
+
+    let inside = HashlineEdit::Move { pos: anchor(&file, 2), end: Some(anchor(&file, 4)), after: anchor(&file, 3) };
+    let err = apply_hashline_edits(CONTENT, &[inside]).unwrap_err();
+    assert!(err.to_string().contains("Move target line 3 is inside the moved lines 2-4"), "{}", err);
+
+    let error = edits().move_range(anchor(&file, 2), anchor(&file, 4), anchor(&file, 3)).build().unwrap_err();
+    assert!(error.to_string().contains("edits[0]: move target line 3 is inside the moved lines 2-4"), "{}", error);
+    let built = edits().move_line(anchor(&file, 5), anchor(&file, 1)).build().unwrap();
+    assert_eq!(built, [HashlineEdit::Move { pos: anchor(&file, 5), end: None, after: anchor(&file, 1) }]);
+}
+
+#[test]
+fn test_move_overlaps_with_edits_between() {
+    let file = HashedFile::from_content(CONTENT);
+    let edits = vec![
+        HashlineEdit::Move { pos: anchor(&file, 1), end: None, after: anchor(&file, 3) },
+        HashlineEdit::Replace { pos: anchor(&file, 2), end: None, lines: vec!["2".into()], expected_lines: None },
+    ];
+    let err = apply_hashline_edits(CONTENT, &edits).unwrap_err();
+    assert!(err.to_string().contains("edits[0] move below line 3 at lines 1-3 overlaps with edits[1]"), "{}", err);
+
+    // Version 1 anchors are migrated for `after` too
+    let payload = serde_json::json!([
+        {"op": "move", "pos": format!("1:{}", anchor(&file, 1).hash), "after": format!("5:{}", anchor(&file, 5).hash)},
+    ]);
+    let edits = parse_edits(&payload.to_string()).unwrap();
+    assert_eq!(edits, [HashlineEdit::Move { pos: anchor(&file, 1), end: None, after: anchor(&file, 5) }]);
+}
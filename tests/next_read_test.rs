@@ -0,0 +1,73 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+fn read(path: &str, options: &ReadOptions) -> String {
+    let mut out = Vec::new();
+    cmd_read_with_options(&mut out, path, options).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_truncated_read_names_the_last_line_and_the_next_commands() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("it's here.txt");
+    fs::write(&path, "one\ntwo\nthree\nfour\n").unwrap();
+    let path = path.to_str().unwrap();
+    let output = read(path, &ReadOptions { offset: Some(1), limit: Some(2), ..ReadOptions::default() });
+
+    let last = HashedFile::from_content("one\ntwo\nthree\nfour\n").anchor(3).unwrap();
+    assert!(output.contains(&format!("beyond line 3. Last line shown: {})", last)), "{}", output);
+    let quoted = format!("'{}'", path.replace('\'', r"'\''"));
+    assert!(output.contains(&format!("\nNext: hashline-tools read {} --continue {}.c.", quoted, last)), "{}", output);
+    assert!(output.contains(&format!("\n  or: hashline-tools read {} --offset 3 --limit 2\n", quoted)), "{}", output);
+
+    // Nothing to continue at the end of the file
+    let output = read(path, &ReadOptions { offset: Some(2), ..ReadOptions::default() });
+    assert!(!output.contains("Next:"), "{}", output);
+}
+
+#[test]
+fn test_next_command_keeps_the_anchor_mode() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("f.txt");
+    fs::write(&path, "one\ntwo\n").unwrap();
+    let path = path.to_str().unwrap();
+    let output = read(path, &ReadOptions { limit: Some(1), anchor_mode: AnchorMode::Scoped, ..ReadOptions::default() });
+    assert!(output.contains(&format!("\n  or: hashline-tools --anchors scoped read {} --offset 1 --limit 1", path)), "{}", output);
+}
+
+#[test]
+fn test_fence_and_table_reads_name_the_next_command() {
+    let dir = TempDir::new().unwrap();
+    let doc = dir.path().join("doc.md");
+    fs::write(&doc, "# Title\n\n```rust\nfn a() {}\nfn b() {}\n```\n").unwrap();
+    let doc = doc.to_str().unwrap();
+    let options = ReadOptions { limit: Some(1), fence: Some("rust".parse().unwrap()), ..ReadOptions::default() };
+    let output = read(doc, &options);
+    let last = HashedFile::from_content("fn a() {}\nfn b() {}\n").anchor(1).unwrap();
+    assert!(output.contains(&format!("Last line shown: {})\nNext: hashline-tools read {} --fence rust --offset 1 --limit 1\n", last, doc)), "{}", output);
+
+    let table = dir.path().join("t.csv");
+    fs::write(&table, "id,name\n1,a\n2,b\n").unwrap();
+    let table = table.to_str().unwrap();
+    let output = read(table, &ReadOptions { limit: Some(2), columns: true, ..ReadOptions::default() });
+    assert!(output.contains(&format!("Next: hashline-tools read {} --columns --offset 2 --limit 2\n", table)), "{}", output);
+}
+
+#[test]
+fn test_notebook_read_names_the_last_line_with_its_cell() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("n.ipynb");
+    let cell = |source: &str| serde_json::json!({ "cell_type": "code", "metadata": {}, "source": source, "outputs": [], "execution_count": null });
+    let notebook = serde_json::json!({ "cells": [cell("a = 1\nb = 2"), cell("print(a)")], "metadata": {}, "nbformat": 4, "nbformat_minor": 5 });
+    fs::write(&path, notebook.to_string()).unwrap();
+    let path = path.to_str().unwrap();
+    let output = read(path, &ReadOptions { limit: Some(1), ..ReadOptions::default() });
+    let last = HashedFile::from_content("a = 1\nb = 2").anchor(2).unwrap();
+    assert!(output.contains(&format!("beyond cell 0. Last line shown: {} of cell 0)", last)), "{}", output);
+    assert!(output.contains(&format!("Next: hashline-tools read {} --offset 1 --limit 1\n", path)), "{}", output);
+}
@@ -0,0 +1,176 @@
+#![cfg(feature = "cli")]
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+const NOTEBOOK: &str = r##"{
+ "cells": [
+  {
+   "cell_type": "markdown",
+   "metadata": {},
+   "source": [
+    "# Analysis\n",
+    "Loads the data."
+   ]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 1,
+   "metadata": {"tags": ["setup"]},
+   "outputs": [
+    {
+     "name": "stdout",
+     "output_type": "stream",
+     "text": ["loaded\n"]
+    }
+   ],
+   "source": [
+    "import pandas as pd\n",
+    "df = pd.read_csv(\"data.csv\")\n",
+    "print(\"loaded\")"
+   ]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": null,
+   "metadata": {},
+   "outputs": [],
+   "source": "df.describe()"
+  },
+  {
+   "cell_type": "code",
+   "execution_count": null,
+   "metadata": {},
+   "outputs": [],
+   "source": []
+  }
+ ],
+ "metadata": {"kernelspec": {"name": "python3"}},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}
+"##;
+
+fn write_notebook(dir: &TempDir) -> (std::path::PathBuf, String) {
+    let path = dir.path().join("analysis.ipynb");
+    fs::write(&path, NOTEBOOK).unwrap();
+    let file_path = path.to_str().unwrap().to_string();
+    (path, file_path)
+}
+
+fn cell_anchor(cell: usize, line: usize) -> String {
+    let notebook = Notebook::parse(NOTEBOOK).unwrap();
+    HashedFile::from_content(&notebook.cells()[cell].source).anchor(line).unwrap().to_string()
+}
+
+#[test]
+fn test_notebook_cells_parse_from_either_source_form() {
+    assert!(is_notebook("a/b.ipynb") && is_notebook("B.IPYNB") && !is_notebook("b.json"));
+    let notebook = Notebook::parse(NOTEBOOK).unwrap();
+    let cells = notebook.cells();
+    assert_eq!(cells.len(), 4);
+    assert_eq!(cells[0].cell_type, "markdown");
+    assert_eq!(cells[1].source, "import pandas as pd\ndf = pd.read_csv(\"data.csv\")\nprint(\"loaded\")");
+    assert_eq!(cells[2].source, "df.describe()");
+    assert_eq!(cells[3].source, "");
+    assert!(Notebook::parse("{\"cells\": [{\"source\": []}]}").is_err());
+}
+
+#[test]
+fn test_rewritten_sources_keep_the_rest_of_the_notebook() {
+    let notebook = Notebook::parse(NOTEBOOK).unwrap();
+    let sources = BTreeMap::from([
+        (1, "import pandas as pd\nprint(\"ready\")\n".to_string()),
+        (2, "df.head()\ndf.describe()".to_string()),
+        (3, "x = 1".to_string()),
+    ]);
+    let text = notebook.with_sources(&sources);
+    let expected = NOTEBOOK
+        .replace(
+            "    \"df = pd.read_csv(\\\"data.csv\\\")\\n\",\n    \"print(\\\"loaded\\\")\"",
+            "    \"print(\\\"ready\\\")\\n\"",
+        )
+        .replace("\"source\": \"df.describe()\"", "\"source\": \"df.head()\\ndf.describe()\"")
+        .replace("\"source\": []", "\"source\": [\n    \"x = 1\"\n   ]");
+    assert_eq!(text, expected);
+    assert_eq!(notebook.with_sources(&BTreeMap::new()), NOTEBOOK);
+}
+
+#[test]
+fn test_read_shows_each_cell_with_its_own_anchors() {
+    let dir = TempDir::new().unwrap();
+    let (_, file_path) = write_notebook(&dir);
+
+    let mut out = Vec::new();
+    cmd_read_with_options(&mut out, &file_path, &ReadOptions::default()).unwrap();
+    let output = String::from_utf8(out).unwrap();
+    assert!(output.starts_with("<notebook>\n<cell index=\"0\" type=\"markdown\">\n"));
+    assert!(output.contains(&format!("<cell index=\"1\" type=\"code\">\n{}:import pandas as pd\n", cell_anchor(1, 1))));
+    assert!(output.contains(&format!("<cell index=\"2\" type=\"code\">\n{}:df.describe()\n</cell>", cell_anchor(2, 1))));
+    assert!(output.ends_with("(End of notebook - 4 total cells)\n</notebook>"));
+
+    let options = ReadOptions { offset: Some(1), limit: Some(1), ..ReadOptions::default() };
+    let mut out = Vec::new();
+    cmd_read_with_options(&mut out, &file_path, &options).unwrap();
+    let output = String::from_utf8(out).unwrap();
+    assert!(!output.contains("index=\"0\"") && !output.contains("index=\"2\""));
+    assert!(output.contains("Use 'offset' parameter to read beyond cell 1"));
+}
+
+#[test]
+fn test_edit_rewrites_only_the_cells_it_targets() {
+    let dir = TempDir::new().unwrap();
+    let (path, file_path) = write_notebook(&dir);
+    let options = EditOptions { checkpoints: false, ..EditOptions::default() };
+
+    let edits = format!(
+        r#"{{"1": [{{"op": "replace", "pos": "{}", "lines": ["print(\"ready\")"]}}], "2": [{{"op": "prepend", "pos": "{}", "lines": ["df.head()"]}}]}}"#,
+        cell_anchor(1, 3),
+        cell_anchor(2, 1),
+    );
+    let result = cmd_edit_with_options(&file_path, &edits, &options).unwrap();
+    assert!(result.starts_with("Edit applied successfully to 2 cells."));
+    assert!(result.contains("<diff cell=\"1\">") && result.contains("<diff cell=\"2\">"));
+
+    let edited = fs::read_to_string(&path).unwrap();
+    let expected = NOTEBOOK
+        .replace("\"print(\\\"loaded\\\")\"\n", "\"print(\\\"ready\\\")\"\n")
+        .replace("\"source\": \"df.describe()\"", "\"source\": \"df.head()\\ndf.describe()\"");
+    assert_eq!(edited, expected);
+
+    // A plain edit list needs --cell
+    let edits = format!(r#"[{{"op": "delete", "pos": "{}"}}]"#, cell_anchor(0, 2));
+    let err = cmd_edit_with_options(&file_path, &edits, &options).unwrap_err();
+    assert!(err.contains("--cell"));
+    let options = EditOptions { cell: Some(0), ..options };
+    cmd_edit_with_options(&file_path, &edits, &options).unwrap();
+    assert_eq!(Notebook::parse(fs::read_to_string(&path).unwrap()).unwrap().cells()[0].source, "# Analysis");
+}
+
+#[test]
+fn test_notebook_edit_errors_name_the_cell() {
+    let dir = TempDir::new().unwrap();
+    let (path, file_path) = write_notebook(&dir);
+
+    // Anchors from one cell do not validate in another
+    let edits = format!(r#"{{"2": [{{"op": "delete", "pos": "{}"}}]}}"#, cell_anchor(1, 1));
+    let err = cmd_edit_with_options(&file_path, &edits, &EditOptions::default()).unwrap_err();
+    assert!(err.starts_with("Cell 2: Hash mismatch error"));
+    let json = EditOptions { format: OutputFormat::Json, ..EditOptions::default() };
+    let err: serde_json::Value = serde_json::from_str(&cmd_edit_with_options(&file_path, &edits, &json).unwrap_err()).unwrap();
+    assert_eq!(err["cell"], 2);
+
+    let edits = r#"{"9": [{"op": "delete", "pos": "1#ZZ"}]}"#;
+    let err = cmd_edit_with_options(&file_path, edits, &EditOptions::default()).unwrap_err();
+    assert!(err.contains("no cell 9"));
+
+    let edits = format!(r#"{{"1": [{{"op": "delete", "pos": "{}"}}]}}"#, cell_anchor(1, 1));
+    let validate = EditOptions { validate_only: true, ..EditOptions::default() };
+    assert!(cmd_edit_with_options(&file_path, &edits, &validate).unwrap().starts_with("Validation passed"));
+    let dry_run = EditOptions { dry_run: true, ..EditOptions::default() };
+    assert!(cmd_edit_with_options(&file_path, &edits, &dry_run).unwrap().starts_with("Dry run"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), NOTEBOOK);
+}
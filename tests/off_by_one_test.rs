@@ -0,0 +1,90 @@
+use hashline_tools::*;
+
+// Helper function to compute cumulative hashes for a file and get a specific line's hash
+fn get_line_hash(content: &str, line_num: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut prev_hash: Option<&str> = None;
+    let mut cumulative_hashes: Vec<String> = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let ln = i + 1;
+        let hash = compute_line_hash(ln, line, prev_hash);
+        cumulative_hashes.push(hash);
+        prev_hash = Some(&cumulative_hashes[i]);
+    }
+
+    cumulative_hashes[line_num - 1].clone()
+}
+
+const CONTENT: &str = "alpha\nbeta\ngamma\ndelta\n";
+
+fn off_by_one_edit() -> Vec<HashlineEdit> {
+    off_by_one_replace("gamma!")
+}
+
+fn off_by_one_replace(line: &str) -> Vec<HashlineEdit> {
+    // Anchor names line 2 but carries the hash of line 3
+    vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 2, hash: get_line_hash(CONTENT, 3) },
+            end: None,
+            lines: vec![line.to_string()],
+            expected_lines: None,
+        }
+    ]
+}
+
+#[test]
+fn test_off_by_one_reports_did_you_mean() {
+    let err = apply_hashline_edits(CONTENT, &off_by_one_edit()).unwrap_err();
+    let HashlineError::Mismatch(mismatch) = err else { panic!("expected a mismatch error, got {}", err) };
+
+    let expected_anchor = format!("3#{}", get_line_hash(CONTENT, 3));
+    assert_eq!(mismatch.mismatches[0].off_by_one().map(|a| a.to_string()), Some(expected_anchor.clone()));
+    assert!(
+        mismatch.to_string().contains(&format!("Did you mean line 3 ({})", expected_anchor)),
+        "Error should point at the adjacent line: {}", mismatch
+    );
+    assert_eq!(mismatch.to_json()["mismatches"][0]["did_you_mean"], expected_anchor);
+}
+
+#[test]
+fn test_fix_off_by_one_retargets_anchor() {
+    let options = ApplyOptions { fix_off_by_one: true, ..ApplyOptions::default() };
+    let outcome = apply_hashline_edits_with_options(CONTENT, &off_by_one_edit(), &options).unwrap();
+
+    assert_eq!(outcome.content, "alpha\nbeta\ngamma!\ndelta\n");
+    assert_eq!(outcome.relocations.len(), 1);
+    assert_eq!(outcome.relocations[0].to.line, 3);
+    assert_eq!(outcome.relocations[0].method, RelocationMethod::OffByOne);
+    assert!(outcome.relocations[0].to_string().ends_with("(off by one)"));
+}
+
+#[test]
+fn test_fix_off_by_one_needs_the_content_to_agree() {
+    // The hash alone may sit on a neighbour by chance, so an edit whose
+    // content does not point at that line is only given a suggestion
+    let options = ApplyOptions { fix_off_by_one: true, ..ApplyOptions::default() };
+    for edits in [off_by_one_replace("GAMMA"), off_by_one_replace("beta!")] {
+        let err = apply_hashline_edits_with_options(CONTENT, &edits, &options).unwrap_err();
+        let HashlineError::Mismatch(mismatch) = err else { panic!("expected a mismatch error, got {}", err) };
+        assert_eq!(mismatch.mismatches[0].off_by_one().map(|a| a.line), Some(3));
+    }
+}
+
+#[test]
+fn test_unrelated_mismatch_has_no_suggestion() {
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 2, hash: "ZZ".to_string() },
+            end: None,
+            lines: vec!["x".to_string()],
+            expected_lines: None,
+        }
+    ];
+    let options = ApplyOptions { fix_off_by_one: true, ..ApplyOptions::default() };
+    let err = apply_hashline_edits_with_options(CONTENT, &edits, &options).unwrap_err();
+    let HashlineError::Mismatch(mismatch) = err else { panic!("expected a mismatch error, got {}", err) };
+    assert!(mismatch.mismatches[0].off_by_one().is_none());
+    assert!(mismatch.to_json()["mismatches"][0]["did_you_mean"].is_null());
+}
@@ -0,0 +1,122 @@
+use hashline_tools::*;
+#[cfg(feature = "cli")]
+use std::io::Write;
+#[cfg(feature = "cli")]
+use tempfile::NamedTempFile;
+
+// Helper function to compute cumulative hashes for a file and get a specific line's hash
+fn get_line_hash(content: &str, line_num: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut prev_hash: Option<&str> = None;
+    let mut cumulative_hashes: Vec<String> = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let ln = i + 1;
+        let hash = compute_line_hash(ln, line, prev_hash);
+        cumulative_hashes.push(hash);
+        prev_hash = Some(&cumulative_hashes[i]);
+    }
+
+    cumulative_hashes[line_num - 1].clone()
+}
+
+fn partial_options() -> ApplyOptions {
+    ApplyOptions { allow_partial: true, ..ApplyOptions::default() }
+}
+
+fn replace(line: usize, hash: String, text: &str) -> HashlineEdit {
+    HashlineEdit::Replace {
+        pos: AnchorRef { line, hash },
+        end: None,
+        lines: vec![text.to_string()],
+        expected_lines: None,
+    }
+}
+
+#[test]
+fn test_partial_applies_valid_edits_and_skips_mismatches() {
+    let content = "a\nb\nc\nd\n";
+    let edits = vec![
+        replace(1, get_line_hash(content, 1), "A"),
+        replace(2, "ZZ".to_string(), "B"),
+        replace(4, get_line_hash(content, 4), "D"),
+    ];
+
+    let outcome = apply_hashline_edits_with_options(content, &edits, &partial_options()).unwrap();
+    assert_eq!(outcome.content, "A\nb\nc\nD\n");
+    assert!(outcome.statuses[0].is_applied());
+    assert!(matches!(outcome.statuses[1], EditStatus::Skipped { reason: SkipReason::Mismatch, .. }));
+    assert!(outcome.statuses[2].is_applied());
+}
+
+#[test]
+fn test_partial_skips_both_sides_of_an_overlap() {
+    let content = "a\nb\nc\nd\n";
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 1, hash: get_line_hash(content, 1) },
+            end: Some(AnchorRef { line: 2, hash: get_line_hash(content, 2) }),
+            lines: vec!["AB".to_string()],
+            expected_lines: None,
+        },
+        replace(2, get_line_hash(content, 2), "B"),
+        replace(4, get_line_hash(content, 4), "D"),
+    ];
+
+    let outcome = apply_hashline_edits_with_options(content, &edits, &partial_options()).unwrap();
+    assert_eq!(outcome.content, "a\nb\nc\nD\n");
+    assert_eq!(
+        outcome.statuses[0],
+        EditStatus::Skipped { reason: SkipReason::Overlap, message: "overlaps with edits[1]".to_string() }
+    );
+    assert!(matches!(outcome.statuses[1], EditStatus::Skipped { reason: SkipReason::Overlap, .. }));
+    assert!(outcome.statuses[2].is_applied());
+}
+
+#[test]
+fn test_partial_reports_invalid_edits() {
+    let content = "a\nb\n";
+    let edits = vec![
+        replace(9, "AB".to_string(), "nope"),
+        replace(2, get_line_hash(content, 2), "B"),
+    ];
+
+    let outcome = apply_hashline_edits_with_options(content, &edits, &partial_options()).unwrap();
+    assert_eq!(outcome.content, "a\nB\n");
+    match &outcome.statuses[0] {
+        EditStatus::Skipped { reason: SkipReason::Invalid, message } => {
+            assert!(message.contains("does not exist"), "Got: {}", message);
+        }
+        other => panic!("unexpected status: {:?}", other),
+    }
+}
+
+#[test]
+fn test_without_partial_batch_is_all_or_nothing() {
+    let content = "a\nb\n";
+    let edits = vec![
+        replace(1, get_line_hash(content, 1), "A"),
+        replace(2, "ZZ".to_string(), "B"),
+    ];
+    assert!(apply_hashline_edits(content, &edits).is_err());
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_cmd_edit_partial_lists_statuses() {
+    let content = "a\nb\n";
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(temp_file, "{}", content).unwrap();
+    let path = temp_file.path().to_str().unwrap().to_string();
+
+    let edits_json = format!(
+        r#"[{{"op": "replace", "pos": "1#{}", "lines": ["A"]}}, {{"op": "replace", "pos": "2#ZZ", "lines": ["B"]}}]"#,
+        get_line_hash(content, 1)
+    );
+    let options = EditOptions { apply: partial_options(), ..EditOptions::default() };
+    let output = cmd_edit_with_options(&path, &edits_json, &options).unwrap();
+
+    assert!(output.contains("Edit statuses:"), "Got: {}", output);
+    assert!(output.contains("edits[0]: applied"), "Got: {}", output);
+    assert!(output.contains("edits[1]: skipped (mismatch)"), "Got: {}", output);
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+fn replace_line(path: &str, line: usize, text: &str, options: &EditOptions) {
+    let anchor = HashedFile::from_content(&fs::read_to_string(path).unwrap()).anchor(line).unwrap();
+    let edits = format!(r#"[{{"op": "replace", "pos": "{}", "lines": ["{}"]}}]"#, anchor, text);
+    cmd_edit_with_options(path, &edits, options).unwrap();
+}
+
+fn setup() -> (TempDir, EditOptions) {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/main.rs"), "fn main() {\n    run();\n}\n").unwrap();
+    fs::write(dir.path().join("notes.txt"), "one\n").unwrap();
+    let options = EditOptions { journal_dir: Some(dir.path().join("journal")), checkpoints: true, ..EditOptions::default() };
+    (dir, options)
+}
+
+#[test]
+fn test_each_batch_becomes_a_patch() {
+    let (dir, options) = setup();
+    let main = dir.path().join("src/main.rs");
+    let main = main.to_str().unwrap();
+    replace_line(main, 2, "    start();", &options);
+    replace_line(dir.path().join("notes.txt").to_str().unwrap(), 1, "two", &options);
+    replace_line(main, 1, "pub fn main() {", &options);
+
+    let out = dir.path().join("patches");
+    let patches = export_patch_series(&dir.path().join("journal"), None, dir.path(), &out, &Policy::default()).unwrap();
+    let names: Vec<String> = patches.iter().map(|p| p.path.file_name().unwrap().to_string_lossy().into_owned()).collect();
+    assert_eq!(names, ["0001-Edit-src-main.rs.patch", "0002-Edit-notes.txt.patch", "0003-Edit-src-main.rs.patch"]);
+    assert_eq!(patches[0].subject, "Edit src/main.rs");
+
+    let first = fs::read_to_string(&patches[0].path).unwrap();
+    assert!(first.starts_with("From 0000000000000000000000000000000000000000 Mon Sep 17 00:00:00 2001\nFrom: "));
+    assert!(first.contains("\nSubject: [PATCH 1/3] Edit src/main.rs\n"));
+    assert!(first.contains(&format!("checkpoint {}", patches[0].checkpoint)));
+    assert!(first.contains(
+        "diff --git a/src/main.rs b/src/main.rs\n--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1,3 +1,3 @@\n fn main() {\n-    run();\n+    start();\n }\n"
+    ));
+    // The last batch's result is the file as it is now
+    let last = fs::read_to_string(&patches[2].path).unwrap();
+    assert!(last.contains("-fn main() {\n+pub fn main() {\n     start();\n"));
+
+    let from = export_patch_series(&dir.path().join("journal"), Some(&patches[1].checkpoint), dir.path(), &out, &Policy::default()).unwrap();
+    assert_eq!(from.len(), 2);
+    assert!(fs::read_to_string(&from[0].path).unwrap().contains("[PATCH 1/2] Edit notes.txt"));
+}
+
+#[test]
+fn test_outside_changes_fail_the_export() {
+    let (dir, options) = setup();
+    let notes = dir.path().join("notes.txt");
+    replace_line(notes.to_str().unwrap(), 1, "two", &options);
+    fs::write(&notes, "three\n").unwrap();
+
+    let journal = dir.path().join("journal");
+    let err = export_patch_series(&journal, None, dir.path(), &dir.path().join("patches"), &Policy::default()).unwrap_err();
+    assert!(err.to_string().ends_with("so the batch's result is unknown"));
+
+    fs::write(&notes, "two\n").unwrap();
+    let err = export_patch_series(&journal, None, &dir.path().join("src"), &dir.path().join("patches"), &Policy::default()).unwrap_err();
+    assert!(err.to_string().contains("is outside"));
+    let text = cmd_export_series(&journal, None, dir.path(), &dir.path().join("patches"), &Policy::default(), OutputFormat::Text).unwrap();
+    assert!(text.starts_with("Exported 1 patch:"));
+}
+
+#[test]
+fn test_export_checks_the_policy() {
+    let (dir, options) = setup();
+    replace_line(dir.path().join("notes.txt").to_str().unwrap(), 1, "two", &options);
+
+    let out = dir.path().join("patches");
+    let policy = Policy { forbid_write: true, ..Policy::default() };
+    let err = cmd_export_series(&dir.path().join("journal"), None, dir.path(), &out, &policy, OutputFormat::Json).unwrap_err();
+    let json: serde_json::Value = serde_json::from_str(&err).unwrap();
+    assert_eq!(json["error"], "policy_violation", "{}", err);
+    assert!(!out.exists());
+}
@@ -0,0 +1,57 @@
+use hashline_tools::*;
+use serde_json::json;
+
+const CONTENT: &str = "a\nb\nc\n";
+
+fn anchor(line: usize, text: &str, prev: Option<&str>) -> String {
+    format!("{}#{}", line, compute_line_hash(line, text, prev))
+}
+
+#[test]
+fn test_v1_legacy_shapes_are_migrated() {
+    let a_hash = compute_line_hash(1, "a", None);
+    let b_hash = compute_line_hash(2, "b", Some(&a_hash));
+    let c_hash = compute_line_hash(3, "c", Some(&b_hash));
+    let payload = json!([
+        { "set_line": { "anchor": format!("1:{}", a_hash), "new_text": "A" } },
+        { "insert_after": { "anchor": { "line": 3, "hash": c_hash }, "text": "d\ne" } },
+    ]);
+
+    let migrated = migrate_edits(payload.clone()).unwrap();
+    assert_eq!(migrated["version"], EDITS_FORMAT_VERSION);
+    assert_eq!(migrated["edits"][0], json!({ "op": "replace", "pos": format!("1#{}", a_hash), "lines": ["A"] }));
+
+    let edits = parse_edits(&payload.to_string()).unwrap();
+    let outcome = apply_hashline_edits(CONTENT, &edits).unwrap();
+    assert_eq!(outcome.0, "A\nb\nc\nd\ne\n");
+}
+
+#[test]
+fn test_v2_document_with_delete() {
+    let b = anchor(2, "b", Some(&compute_line_hash(1, "a", None)));
+    let payload = json!({ "version": 2, "edits": [{ "op": "delete", "pos": b }] }).to_string();
+
+    let edits = parse_edits(&payload).unwrap();
+    assert_eq!(apply_hashline_edits(CONTENT, &edits).unwrap().0, "a\nc\n");
+
+    let with_lines = json!({ "version": 2, "edits": [{ "op": "delete", "pos": "2#ZZ", "lines": ["x"] }] });
+    let err = edits_from_value(with_lines).unwrap_err();
+    assert_eq!(err.kind(), "invalid_edits");
+    assert!(err.to_string().contains("edits[0]"), "{}", err);
+}
+
+#[test]
+fn test_bare_array_and_unversioned_document_are_v1() {
+    let a = anchor(1, "a", None);
+    let bare = json!([{ "op": "replace", "pos": a, "lines": ["x"] }]);
+    let document = json!({ "edits": bare.clone() });
+    assert_eq!(edits_from_value(bare).unwrap(), edits_from_value(document).unwrap());
+}
+
+#[test]
+fn test_unsupported_version_is_rejected() {
+    let err = parse_edits(r#"{"version": 3, "edits": []}"#).unwrap_err();
+    assert!(matches!(err, HashlineError::InvalidEdits(_)));
+    assert!(err.to_string().contains("unsupported edits version 3"), "{}", err);
+    assert!(matches!(parse_edits("not json"), Err(HashlineError::InvalidEdits(_))));
+}
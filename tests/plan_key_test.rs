@@ -0,0 +1,31 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+
+use hashline_tools::*;
+use tempfile::tempdir;
+
+// Alone in its binary, so no other test sets the key meanwhile
+#[test]
+fn test_plan_and_commit_need_a_key() {
+    std::env::remove_var(PLAN_KEY_ENV_VAR);
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("file.txt");
+    fs::write(&file_path, "one\n").unwrap();
+    let options = EditOptions { format: OutputFormat::Json, ..EditOptions::default() };
+
+    let err = cmd_plan(file_path.to_str().unwrap(), r#"[{"op": "append", "lines": ["two"]}]"#, &options).unwrap_err();
+    let value: serde_json::Value = serde_json::from_str(&err).unwrap();
+    assert_eq!(value["error"], "missing_plan_key");
+
+    // Not even a token signed with the empty key is accepted
+    let plan = PlanToken {
+        file: file_path.to_str().unwrap().to_string(),
+        fingerprint: content_fingerprint("one\n"),
+        edits: Vec::new(),
+        options: ApplyOptions::default(),
+    };
+    let err = cmd_commit(&plan.encode(b""), &options).unwrap_err();
+    let value: serde_json::Value = serde_json::from_str(&err).unwrap();
+    assert_eq!(value["error"], "missing_plan_key");
+}
@@ -0,0 +1,119 @@
+#![cfg(feature = "cli")]
+
+use hashline_tools::*;
+use std::fs;
+use tempfile::tempdir;
+
+// Helper function to compute cumulative hashes for a file and get a specific line's hash
+fn get_line_hash(content: &str, line_num: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut prev_hash: Option<&str> = None;
+    let mut cumulative_hashes: Vec<String> = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let ln = i + 1;
+        let hash = compute_line_hash(ln, line, prev_hash);
+        cumulative_hashes.push(hash);
+        prev_hash = Some(&cumulative_hashes[i]);
+    }
+
+    cumulative_hashes[line_num - 1].clone()
+}
+
+const KEY: &str = "plan-test-key";
+
+fn json_options() -> EditOptions {
+    // Every test in this binary sets the same key
+    std::env::set_var(PLAN_KEY_ENV_VAR, KEY);
+    EditOptions { format: OutputFormat::Json, ..EditOptions::default() }
+}
+
+fn plan(path: &str, content: &str) -> String {
+    let edits = format!(r#"[{{"op": "replace", "pos": "2#{}", "lines": ["TWO"]}}]"#, get_line_hash(content, 2));
+    let output = cmd_plan(path, &edits, &json_options()).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(value["status"], "planned");
+    value["token"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn test_plan_then_commit() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("file.txt");
+    let content = "one\ntwo\nthree\n";
+    fs::write(&file_path, content).unwrap();
+    let path = file_path.to_str().unwrap();
+
+    let token = plan(path, content);
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), content, "Planning must not write");
+
+    let output = cmd_commit(&token, &json_options()).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(value["status"], "applied");
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "one\nTWO\nthree\n");
+}
+
+#[test]
+fn test_commit_rejects_changed_file() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("file.txt");
+    let content = "one\ntwo\nthree\n";
+    fs::write(&file_path, content).unwrap();
+    let token = plan(file_path.to_str().unwrap(), content);
+
+    // A change elsewhere in the file still invalidates the plan
+    fs::write(&file_path, "one\ntwo\nthree\nfour\n").unwrap();
+    let err = cmd_commit(&token, &json_options()).unwrap_err();
+    let value: serde_json::Value = serde_json::from_str(&err).unwrap();
+    assert_eq!(value["error"], "stale_plan");
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "one\ntwo\nthree\nfour\n");
+}
+
+#[test]
+fn test_tampered_token_is_rejected() {
+    let plan = PlanToken {
+        file: "file.txt".to_string(),
+        fingerprint: content_fingerprint("one\n"),
+        edits: Vec::new(),
+        options: ApplyOptions::default(),
+    };
+    let token = plan.encode(b"secret");
+    assert!(PlanToken::decode(&token, b"secret").is_ok());
+    assert!(PlanToken::decode(&token, b"other").is_err());
+
+    let (payload, signature) = token.split_once('.').unwrap();
+    let mut altered = payload.to_string();
+    altered.replace_range(0..2, if &payload[0..2] == "7b" { "5b" } else { "7b" });
+    assert!(PlanToken::decode(&format!("{}.{}", altered, signature), b"secret").is_err());
+}
+
+#[test]
+fn test_token_cannot_turn_on_force() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("file.txt");
+    fs::write(&file_path, "one\ntwo\n").unwrap();
+    let options = json_options();
+
+    // A validly signed token asking to force a stale anchor through
+    let stale = AnchorRef { line: 2, hash: HashedFile::from_content("one\n").anchor(1).unwrap().hash };
+    let plan = PlanToken {
+        file: file_path.to_str().unwrap().to_string(),
+        fingerprint: content_fingerprint("one\ntwo\n"),
+        edits: vec![HashlineEdit::Replace { pos: stale, end: None, lines: vec!["TWO".into()], expected_lines: None }],
+        options: ApplyOptions { force: true, ..ApplyOptions::default() },
+    };
+    let err = cmd_commit(&plan.encode(KEY.as_bytes()), &options).unwrap_err();
+    let value: serde_json::Value = serde_json::from_str(&err).unwrap();
+    assert_eq!(value["error"], "hash_mismatch", "{}", err);
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "one\ntwo\n");
+
+    // A policy that forbids forcing is checked against the committer
+    let options = EditOptions {
+        policy: Policy { forbid_force: true, ..Policy::default() },
+        apply: ApplyOptions { force: true, ..ApplyOptions::default() },
+        ..options
+    };
+    let err = cmd_commit(&plan.encode(KEY.as_bytes()), &options).unwrap_err();
+    let value: serde_json::Value = serde_json::from_str(&err).unwrap();
+    assert_eq!(value["error"], "policy_violation", "{}", err);
+}
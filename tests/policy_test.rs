@@ -0,0 +1,74 @@
+#![cfg(feature = "cli")]
+
+use hashline_tools::*;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn write_policy(dir: &Path, json: &str) -> Policy {
+    let path = dir.join("policy.json");
+    fs::write(&path, json).unwrap();
+    Policy::load(&path).unwrap()
+}
+
+#[test]
+fn test_default_policy_allows_everything() {
+    let policy = Policy::default();
+    assert!(policy.check(Operation::Edit, Path::new("anything.rs"), true).is_ok());
+    assert!(policy.check(Operation::Delete, Path::new("/etc/hosts"), false).is_ok());
+}
+
+#[test]
+fn test_policy_forbids_operations() {
+    let dir = tempdir().unwrap();
+    let policy = write_policy(dir.path(), r#"{"forbid_force": true, "forbid_write": true, "forbid_delete": true}"#);
+    let target = dir.path().join("a.rs");
+
+    assert!(policy.check(Operation::Edit, &target, false).is_ok());
+    assert!(policy.check(Operation::Edit, &target, true).unwrap_err().to_string().contains("--force"));
+    assert!(policy.check(Operation::Write, &target, false).is_err());
+    assert!(policy.check(Operation::Delete, &target, false).is_err());
+}
+
+#[test]
+fn test_policy_restricts_paths_relative_to_policy_file() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/lib.rs"), "x\n").unwrap();
+    fs::write(dir.path().join("secret.txt"), "x\n").unwrap();
+    let policy = write_policy(dir.path(), r#"{"allowed_paths": ["src/**"]}"#);
+
+    assert!(policy.check(Operation::Edit, &dir.path().join("src/lib.rs"), false).is_ok());
+    assert!(policy.check(Operation::Edit, &dir.path().join("secret.txt"), false).is_err());
+    // Parent-directory tricks are resolved before matching
+    assert!(policy.check(Operation::Edit, &dir.path().join("src/../secret.txt"), false).is_err());
+}
+
+#[test]
+fn test_policy_rejects_unknown_keys() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("policy.json");
+    fs::write(&path, r#"{"forbid_everything": true}"#).unwrap();
+    assert!(Policy::load(&path).is_err());
+}
+
+#[test]
+fn test_cmd_edit_reports_policy_violation() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("secret.txt");
+    fs::write(&file_path, "one\n").unwrap();
+    let options = EditOptions {
+        policy: write_policy(dir.path(), r#"{"allowed_paths": ["src/**"]}"#),
+        format: OutputFormat::Json,
+        ..EditOptions::default()
+    };
+
+    let err = cmd_edit_with_options(
+        file_path.to_str().unwrap(),
+        r#"[{"op": "append", "lines": ["two"]}]"#,
+        &options,
+    ).unwrap_err();
+    let value: serde_json::Value = serde_json::from_str(&err).unwrap();
+    assert_eq!(value["error"], "policy_violation");
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "one\n");
+}
@@ -0,0 +1,82 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+fn project() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("app/src")).unwrap();
+    fs::write(dir.path().join("app/src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(
+        dir.path().join(".hashlinerc"),
+        "anchors = \"scoped\"\neditorconfig = false\n\n[policy]\nforbid_force = true\n",
+    ).unwrap();
+    dir
+}
+
+#[test]
+fn test_nearer_files_override_farther_ones() {
+    let dir = project();
+    let main = dir.path().join("app/src/main.rs");
+    let config = ProjectConfig::for_path(&main).unwrap();
+    assert_eq!(config.files, vec![dir.path().join(".hashlinerc")]);
+    assert_eq!((config.anchors, config.editorconfig), (Some(AnchorMode::Scoped), Some(false)));
+
+    fs::write(dir.path().join("app/.hashlinerc"), "anchors = \"chained\"\n").unwrap();
+    let config = ProjectConfig::for_path(&main).unwrap();
+    assert_eq!(config.files.len(), 2);
+    assert_eq!((config.anchors, config.editorconfig), (Some(AnchorMode::Chained), Some(false)));
+
+    fs::write(dir.path().join("app/.hashlinerc"), "root = true\n").unwrap();
+    let config = ProjectConfig::for_path(dir.path().join("app")).unwrap();
+    assert_eq!((config.files.len(), config.anchors), (1, None));
+}
+
+#[test]
+fn test_pinned_anchors() {
+    let dir = project();
+    let config = ProjectConfig::for_path(dir.path().join("app/src/main.rs")).unwrap();
+    assert_eq!(config.pin_anchors(AnchorMode::Chained, false), Ok(AnchorMode::Scoped));
+    assert_eq!(config.pin_anchors(AnchorMode::Scoped, true), Ok(AnchorMode::Scoped));
+    assert_eq!(
+        config.pin_anchors(AnchorMode::Chained, true).unwrap_err(),
+        "--anchors chained conflicts with anchors = \"scoped\" pinned by .hashlinerc"
+    );
+    assert_eq!(ProjectConfig::default().pin_anchors(AnchorMode::Chained, true), Ok(AnchorMode::Chained));
+}
+
+#[test]
+fn test_every_project_policy_applies() {
+    let dir = project();
+    let main = dir.path().join("app/src/main.rs");
+    fs::write(dir.path().join("app/.hashlinerc"), "[policy]\nallowed_paths = [\"src/**\"]\n").unwrap();
+    let policy = Policy::default().and(ProjectConfig::for_path(&main).unwrap().policy);
+    assert!(policy.check(Operation::Edit, &main, false).is_ok());
+    assert!(policy.check(Operation::Edit, &main, true).unwrap_err().0.contains("--force is disabled"));
+    assert!(policy.check(Operation::Edit, &dir.path().join("app/build.rs"), false).unwrap_err().0.contains("outside the allowed paths"));
+}
+
+#[test]
+fn test_invalid_project_file() {
+    let dir = project();
+    fs::write(dir.path().join("app/.hashlinerc"), "anchor = \"chained\"\n").unwrap();
+    let err = ProjectConfig::for_path(dir.path().join("app/src/main.rs")).unwrap_err();
+    assert!(err.starts_with("Invalid ") && err.contains("unknown field `anchor`"), "{}", err);
+
+    fs::write(dir.path().join("app/.hashlinerc"), "[policy]\nallowed_paths = [\"[\"]\n").unwrap();
+    assert!(ProjectConfig::for_path(dir.path().join("app")).unwrap_err().contains("Invalid glob"));
+}
+
+#[test]
+fn test_search_skips_project_ignores() {
+    let dir = project();
+    fs::create_dir(dir.path().join("app/generated")).unwrap();
+    fs::write(dir.path().join("app/generated/out.rs"), "fn main() {}\n").unwrap();
+    fs::write(dir.path().join("app/.hashlinerc"), "ignore = [\"generated/\"]\n").unwrap();
+    let mut out = Vec::new();
+    let options = SearchOptions { recursive: true, ..SearchOptions::default() };
+    assert_eq!(cmd_search(&mut out, dir.path().to_str().unwrap(), "fn main", &options), Ok(1));
+    assert!(String::from_utf8(out).unwrap().contains("main.rs:1#"));
+}
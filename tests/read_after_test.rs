@@ -0,0 +1,56 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+fn read(path: &str, options: &ReadOptions) -> Result<String, String> {
+    let mut out = Vec::new();
+    cmd_read_with_options(&mut out, path, options)?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn test_read_after_a_current_anchor_starts_on_the_next_line() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("f.txt");
+    fs::write(&path, "a\nb\nc\nd\ne\n").unwrap();
+    let path = path.to_str().unwrap();
+    let anchor = HashedFile::from_content("a\nb\nc\nd\ne\n").anchor(2).unwrap();
+
+    for memory_budget in [None, Some(1)] {
+        let options = ReadOptions { after: Some(anchor.clone()), limit: Some(2), memory_budget, ..ReadOptions::default() };
+        assert_eq!(read(path, &options).unwrap(), cmd_read(path, Some(2), Some(2)).unwrap());
+    }
+}
+
+#[test]
+fn test_read_after_a_stale_anchor_lists_candidates() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("f.txt");
+    fs::write(&path, "fn a() {\n    one();\n}\n").unwrap();
+    let path = path.to_str().unwrap();
+    let anchor = HashedFile::from_content_with_mode("fn a() {\n    one();\n}\n", AnchorMode::Scoped).anchor(2).unwrap();
+
+    // A line inserted above moves the anchored line down by one
+    fs::write(path, "// a\nfn a() {\n    one();\n}\n").unwrap();
+    let options = ReadOptions { after: Some(anchor.clone()), anchor_mode: AnchorMode::Scoped, ..ReadOptions::default() };
+    let error = read(path, &options).unwrap_err();
+    assert!(error.starts_with("Hash mismatch error:"), "{}", error);
+    assert!(error.contains(&format!("Did you mean line 3 (3#{})", anchor.hash)), "{}", error);
+
+    let over_budget = ReadOptions { memory_budget: Some(1), ..options };
+    let error = read(path, &over_budget).unwrap_err();
+    assert!(error.contains("line 2 has changed since last read"), "{}", error);
+}
+
+#[test]
+fn test_read_after_a_line_past_the_end_fails() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("f.txt");
+    fs::write(&path, "a\nb\n").unwrap();
+    let path = path.to_str().unwrap();
+    let options = ReadOptions { after: Some("5#AA".parse().unwrap()), ..ReadOptions::default() };
+    assert_eq!(read(path, &options).unwrap_err(), "Line 5 does not exist (file has 2 lines)");
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "cli")]
+
+use hashline_tools::*;
+use std::io::Write;
+
+#[test]
+fn test_cmd_read_to_writes_what_cmd_read_returns() {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    write!(file, "{}", (1..=30).map(|i| format!("line {}\n", i)).collect::<String>()).unwrap();
+    let path = file.path().to_str().unwrap();
+
+    for (offset, limit) in [(None, None), (Some(5), Some(10)), (Some(25), None), (Some(30), None), (Some(99), Some(1))] {
+        let mut out = Vec::new();
+        cmd_read_to(&mut out, path, offset, limit, None).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), cmd_read(path, offset, limit).unwrap(), "{:?} {:?}", offset, limit);
+    }
+}
+
+#[test]
+fn test_cmd_read_to_reports_write_failures() {
+    struct Broken;
+    impl std::io::Write for Broken {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "closed"))
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    writeln!(file, "hello").unwrap();
+
+    let err = cmd_read_to(&mut Broken, file.path().to_str().unwrap(), None, None, None).unwrap_err();
+    assert!(err.starts_with("Failed to write output:"), "{}", err);
+}
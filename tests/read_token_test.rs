@@ -0,0 +1,96 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+fn read(path: &str, options: &ReadOptions) -> Result<String, String> {
+    let mut out = Vec::new();
+    cmd_read_with_options(&mut out, path, options)?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+fn token_in(output: &str) -> ReadToken {
+    let (_, rest) = output.split_once("--continue ").expect("no continuation token");
+    rest.split_whitespace().next().unwrap().parse().unwrap()
+}
+
+#[test]
+fn test_continue_resumes_after_the_last_line_shown() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("f.txt");
+    fs::write(&path, (1..=10).map(|i| format!("line {}\n", i)).collect::<String>()).unwrap();
+    let path = path.to_str().unwrap();
+
+    let first = read(path, &ReadOptions { limit: Some(4), ..ReadOptions::default() }).unwrap();
+    let token = token_in(&first);
+    assert_eq!(token.anchor.line, 4);
+    assert!(first.contains(&format!("{}:line 4", token.anchor)));
+
+    let resumed = ReadOptions { continue_from: Some(token), limit: Some(4), ..ReadOptions::default() };
+    assert_eq!(read(path, &resumed).unwrap(), cmd_read(path, Some(4), Some(4)).unwrap());
+
+    // Lines below the resume point may change
+    fs::write(path, (1..=10).map(|i| format!("line {}\n", if i > 4 { i * 10 } else { i })).collect::<String>()).unwrap();
+    assert!(read(path, &resumed).unwrap().contains(":line 50"));
+}
+
+#[test]
+fn test_continue_fails_when_the_file_changed_above_the_resume_point() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("f.txt");
+    fs::write(&path, "one\ntwo\nthree\nfour\n").unwrap();
+    let path = path.to_str().unwrap();
+    let token = token_in(&read(path, &ReadOptions { limit: Some(2), ..ReadOptions::default() }).unwrap());
+
+    for changed in ["zero\none\ntwo\nthree\nfour\n", "one\ntwo\r\nthree\nfour\n", "one\n"] {
+        fs::write(path, changed).unwrap();
+        let options = ReadOptions { continue_from: Some(token.clone()), ..ReadOptions::default() };
+        let error = read(path, &options).unwrap_err();
+        assert!(error.contains("changed at or above line 2"), "{}", error);
+    }
+}
+
+#[test]
+fn test_continue_requires_the_same_anchor_mode() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("f.txt");
+    fs::write(&path, "one\ntwo\nthree\n").unwrap();
+    let path = path.to_str().unwrap();
+    let scoped = ReadOptions { limit: Some(1), anchor_mode: AnchorMode::Scoped, ..ReadOptions::default() };
+    let token = token_in(&read(path, &scoped).unwrap());
+    assert_eq!(token.anchor_mode, AnchorMode::Scoped);
+
+    let error = read(path, &ReadOptions { continue_from: Some(token.clone()), ..ReadOptions::default() }).unwrap_err();
+    assert!(error.contains("read with --anchors scoped"), "{}", error);
+    let resumed = ReadOptions { continue_from: Some(token), ..scoped };
+    assert!(read(path, &resumed).unwrap().contains(":two"));
+}
+
+#[test]
+fn test_streamed_and_cached_reads_issue_the_same_token() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("f.txt");
+    fs::write(&path, "one\r\n\ntwo\nthree\n").unwrap();
+    let path = path.to_str().unwrap();
+    let token = token_in(&read(path, &ReadOptions { limit: Some(2), ..ReadOptions::default() }).unwrap());
+
+    let streamed = ReadOptions { limit: Some(2), memory_budget: Some(4), ..ReadOptions::default() };
+    assert_eq!(token_in(&read(path, &streamed).unwrap()), token);
+    let cached = ReadOptions { limit: Some(2), hash_cache: Some(HashCache::new(dir.path().join("cache"))), ..ReadOptions::default() };
+    assert_eq!(token_in(&read(path, &cached).unwrap()), token);
+}
+
+#[test]
+fn test_read_token_round_trip() {
+    let token: ReadToken = "12#VK.c.3f9a1b2c4d5e6f70".parse().unwrap();
+    assert_eq!(token.anchor.line, 12);
+    assert_eq!(token.anchor_mode, AnchorMode::Chained);
+    assert_eq!(token.to_string(), "12#VK.c.3f9a1b2c4d5e6f70");
+
+    for malformed in ["", "12#VK", "12#VK.c", "12#VK.x.3f9a1b2c4d5e6f70", "0#VK.c.3f9a1b2c4d5e6f70", "12#VK.c.3f9a", "12#VK.c.3f9a1b2c4d5e6f7g", "12#VK.c.3f9a1b2c4d5e6f70.1"] {
+        let error = malformed.parse::<ReadToken>().unwrap_err();
+        assert!(error.starts_with("Malformed continuation token"), "{}", error);
+    }
+}
@@ -0,0 +1,26 @@
+#![cfg(feature = "schemars")]
+
+use hashline_tools::*;
+
+#[test]
+fn test_edit_schema_lists_json_ops_and_anchor_pattern() {
+    let schema = serde_json::to_value(schema_for_edits()).unwrap();
+    let ops: Vec<&str> = schema["$defs"]["HashlineEdit"]["oneOf"].as_array().unwrap().iter()
+        .map(|variant| variant["properties"]["op"]["const"].as_str().unwrap())
+        .collect();
+    assert_eq!(ops, vec!["replace", "append", "prepend", "delete", "move", "set_path", "set_cell", "set_column"]);
+
+    let pattern = regex::Regex::new(schema["$defs"]["AnchorRef"]["pattern"].as_str().unwrap()).unwrap();
+    let file = HashedFile::from_content("fn main() {}\n");
+    assert!(pattern.is_match(&file.anchor(1).unwrap().to_string()));
+    assert!(!pattern.is_match("1"));
+}
+
+#[test]
+fn test_outcome_schema_describes_statuses() {
+    let schema = serde_json::to_value(schema_for_outcome()).unwrap();
+    let required = schema["required"].as_array().unwrap();
+    assert!(required.contains(&"statuses".into()));
+    assert!(schema["$defs"]["EditStatus"].is_object());
+    assert_eq!(serde_json::to_value(schema_for_options()).unwrap()["properties"]["dedupe"]["default"], true);
+}
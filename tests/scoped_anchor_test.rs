@@ -0,0 +1,183 @@
+#[cfg(feature = "cli")]
+use std::fs;
+
+use hashline_tools::*;
+#[cfg(feature = "cli")]
+use tempfile::TempDir;
+
+const SOURCE: &str = "\
+use std::fmt;
+
+fn first(x: u32) -> u32 {
+    let y = compute(x, 1, 2);
+    y + 1
+}
+
+pub(crate) fn second(x: u32) -> u32 {
+    let y = compute(x, 1, 2);
+    y + 2
+}
+";
+
+/// `SOURCE` with `first` reindented and its call reflowed over three lines.
+const REFORMATTED: &str = "\
+use std::fmt;
+
+fn first(x: u32) -> u32 {
+  let y = compute(
+      x, 1, 2,
+  );
+  y + 1
+}
+
+pub(crate) fn second(x: u32) -> u32 {
+    let y = compute(x, 1, 2);
+    y + 2
+}
+";
+
+fn scoped_options() -> ApplyOptions {
+    ApplyOptions { anchor_mode: AnchorMode::Scoped, ..ApplyOptions::default() }
+}
+
+#[test]
+fn test_scoped_hashes_survive_reformatting_elsewhere() {
+    let before = HashedFile::from_content_with_mode(SOURCE, AnchorMode::Scoped);
+    let after = HashedFile::from_content_with_mode(REFORMATTED, AnchorMode::Scoped);
+    assert_eq!(before.mode(), AnchorMode::Scoped);
+
+    // `second` moved down two lines but kept every hash
+    assert_eq!(before.hashes()[7..], after.hashes()[9..]);
+    // Reindenting alone keeps a line's hash
+    assert_eq!(before.hash_at(5), after.hash_at(7));
+
+    // Chained hashes change from the first reformatted line on
+    let chained_before = HashedFile::from_content(SOURCE);
+    let chained_after = HashedFile::from_content(REFORMATTED);
+    assert_ne!(chained_before.hashes()[7..], chained_after.hashes()[9..]);
+}
+
+#[test]
+fn test_identical_lines_hash_by_their_enclosing_definition() {
+    let file = HashedFile::from_content_with_mode(SOURCE, AnchorMode::Scoped);
+    assert_eq!(file.lines()[3], file.lines()[8]);
+    assert_ne!(file.hash_at(4), file.hash_at(9));
+
+    // Top-level lines hash by content alone
+    let shifted_source = format!("\n\n{}", SOURCE);
+    let shifted = HashedFile::from_content_with_mode(&shifted_source, AnchorMode::Scoped);
+    assert_eq!(file.hash_at(1), shifted.hash_at(3));
+}
+
+#[test]
+fn test_indentation_closes_python_definitions() {
+    let source = "class A:\n    def f(self):\n        return 1\n\n    def g(self):\n        return 1\nreturn 1\n";
+    let file = HashedFile::from_content_with_mode(source, AnchorMode::Scoped);
+    let hashes: Vec<&str> = [3, 6, 7].iter().map(|&line| file.hash_at(line).unwrap()).collect();
+    assert_ne!(hashes[0], hashes[1]);
+    assert_ne!(hashes[1], hashes[2]);
+
+    // A wrapped signature still opens an indented body
+    let wrapped = "def f(\n    a,\n):\n    return 1\nreturn 1\n";
+    let file = HashedFile::from_content_with_mode(wrapped, AnchorMode::Scoped);
+    let top = HashedFile::from_content_with_mode("return 1\n", AnchorMode::Scoped);
+    assert_ne!(file.hash_at(4), top.hash_at(1));
+    assert_eq!(file.hash_at(5), top.hash_at(1));
+}
+
+/// The hashes of `tail` after `head`, and of `tail` on its own. They are
+/// equal when every scope `head` opens is closed again.
+fn tail_hashes(head: &str, tail: &str) -> (Vec<LineHash>, Vec<LineHash>) {
+    let content = format!("{}{}", head, tail);
+    let joined = HashedFile::from_content_with_mode(&content, AnchorMode::Scoped);
+    let alone = HashedFile::from_content_with_mode(tail, AnchorMode::Scoped);
+    (joined.hashes()[joined.len() - alone.len()..].to_vec(), alone.hashes().to_vec())
+}
+
+#[test]
+fn test_braces_in_strings_and_comments_leave_scopes_alone() {
+    let tail = "fn after() {\n    body();\n}\nbody();\n";
+    let heads = [
+        "fn strings() {\n    let open = \"{\";\n    let close = '}';\n    let quoted = \"\\\"{\";\n    let escaped = '\\u{7b}';\n}\n",
+        "fn comments() {\n    x(); // {\n    /* { */ y();\n}\n",
+        "fn spanning() {\n    /* a comment\n       fn hidden() {\n    */\n    let s = \"a string\n{ over lines\";\n}\n",
+        "impl<'a> Wrapper<'a> {\n    fn get(&'a self) -> &'a str {\n        self.0\n    }\n}\n",
+        "class A:\n    \"\"\"Docs with { a brace\n    over } two { lines.\"\"\"\n    def f(self):\n        return '{'  # }\n",
+    ];
+    for head in heads {
+        let (joined, alone) = tail_hashes(head, tail);
+        assert_eq!(joined, alone, "{}", head);
+    }
+
+    // A brace in code does open a scope
+    let (joined, alone) = tail_hashes("fn open() {\n    {\n}\n", tail);
+    assert_ne!(joined, alone);
+}
+
+#[test]
+fn test_semantic_is_an_alias_for_scoped() {
+    assert_eq!(serde_json::from_str::<AnchorMode>("\"semantic\"").unwrap(), AnchorMode::Scoped);
+    assert_eq!(serde_json::to_string(&AnchorMode::Scoped).unwrap(), "\"scoped\"");
+    #[cfg(feature = "cli")]
+    {
+        use clap::Parser;
+        let cli = Cli::try_parse_from(["hashline-tools", "--anchors", "semantic", "read", "lib.rs"]).unwrap();
+        assert_eq!(cli.anchors, AnchorMode::Scoped);
+    }
+}
+
+#[test]
+fn test_anchors_read_before_a_reformat_rebase_onto_the_moved_lines() {
+    let before = HashedFile::from_content_with_mode(SOURCE, AnchorMode::Scoped);
+    let batch = edits().replace(before.anchor(10).unwrap(), ["    y + 3"]).build().unwrap();
+
+    let err = apply_hashline_edits_with_options(REFORMATTED, &batch, &scoped_options()).unwrap_err();
+    assert!(matches!(err, HashlineError::Mismatch(_)));
+
+    let options = ApplyOptions { auto_rebase: true, ..scoped_options() };
+    let outcome = apply_hashline_edits_with_options(REFORMATTED, &batch, &options).unwrap();
+    assert_eq!(outcome.relocations[0].to.line, 12);
+    assert!(outcome.content.contains("    y + 3\n}\n"));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_editor_rehashes_when_the_mode_changes() {
+    let editor = Editor::from_content("lib.rs", SOURCE).with_options(scoped_options());
+    assert_eq!(editor.file(), &HashedFile::from_content_with_mode(SOURCE, AnchorMode::Scoped));
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_read_and_edit_commands_in_scoped_mode() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("lib.rs");
+    fs::write(&path, SOURCE).unwrap();
+    let file_path = path.to_str().unwrap();
+    let file = HashedFile::from_content_with_mode(SOURCE, AnchorMode::Scoped);
+
+    let options = ReadOptions { anchor_mode: AnchorMode::Scoped, ..ReadOptions::default() };
+    let mut out = Vec::new();
+    cmd_read_with_options(&mut out, file_path, &options).unwrap();
+    let output = String::from_utf8(out).unwrap();
+    assert!(output.contains(&format!("9#{}:    let y = compute(x, 1, 2);", file.hash_at(9).unwrap())));
+
+    // Streaming reads hash the same way
+    let streamed = ReadOptions { memory_budget: Some(1), ..options };
+    let mut out = Vec::new();
+    cmd_read_with_options(&mut out, file_path, &streamed).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), output);
+
+    let edits = format!(r#"[{{"op": "replace", "pos": "{}", "lines": ["    y + 3"]}}]"#, file.anchor(10).unwrap());
+    let options = EditOptions { apply: scoped_options(), ..EditOptions::default() };
+    let result = cmd_edit_with_options(file_path, &edits, &options).unwrap();
+    let edited = fs::read_to_string(&path).unwrap();
+    let rehashed = HashedFile::from_content_with_mode(&edited, AnchorMode::Scoped);
+    assert!(result.contains(&format!("+10#{}:    y + 3", rehashed.hash_at(10).unwrap())));
+
+    // Chained anchors do not validate in scoped mode
+    let chained = HashedFile::from_content(&edited);
+    assert_ne!(chained.hash_at(9), rehashed.hash_at(9));
+    let edits = format!(r#"[{{"op": "delete", "pos": "{}"}}]"#, chained.anchor(9).unwrap());
+    assert!(cmd_edit_with_options(file_path, &edits, &options).is_err());
+}
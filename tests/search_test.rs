@@ -0,0 +1,110 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::path::Path;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+fn write(root: &Path, path: &str, content: &str) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+fn search(dir: &Path, pattern: &str, options: &SearchOptions) -> (String, usize) {
+    let mut out = Vec::new();
+    let matches = cmd_search(&mut out, dir.to_str().unwrap(), pattern, options).unwrap();
+    (String::from_utf8(out).unwrap(), matches)
+}
+
+fn recursive() -> SearchOptions {
+    SearchOptions { recursive: true, ..SearchOptions::default() }
+}
+
+#[test]
+fn test_matches_carry_their_anchors() {
+    let dir = TempDir::new().unwrap();
+    let content = "fn main() {\n    helper();\n}\n\nfn helper() {}\n";
+    write(dir.path(), "main.rs", content);
+    write(dir.path(), "nested/lib.rs", "fn helper() {}\n");
+
+    let (out, matches) = search(dir.path(), r"fn \w+", &SearchOptions::default());
+    let file = HashedFile::from_content(content);
+    let shown = dir.path().join("main.rs").display().to_string();
+    assert_eq!(matches, 2);
+    assert_eq!(out, format!(
+        "{shown}:{}:fn main() {{\n{shown}:{}:fn helper() {{}}\n\n(2 matches in 1 file)",
+        file.anchor(1).unwrap(),
+        file.anchor(5).unwrap(),
+    ));
+
+    // Only a recursive search descends into `nested`
+    let (out, matches) = search(dir.path(), r"fn \w+", &recursive());
+    assert_eq!(matches, 3);
+    assert!(out.ends_with("(3 matches in 2 files)"));
+    assert!(out.contains("nested/lib.rs:1#"));
+}
+
+#[test]
+fn test_ignored_files_are_skipped() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path();
+    fs::create_dir(root.join(".git")).unwrap();
+    write(root, ".git/config", "needle\n");
+    write(root, ".gitignore", "*.log\n!keep.log\n/build/\n");
+    write(root, "src/.ignore", "generated.rs\n");
+    write(root, "src/main.rs", "needle\n");
+    write(root, "src/generated.rs", "needle\n");
+    write(root, "src/build/out.rs", "needle\n");
+    write(root, "build/out.rs", "needle\n");
+    write(root, "debug.log", "needle\n");
+    write(root, "keep.log", "needle\n");
+
+    let (out, _) = search(root, "needle", &recursive());
+    let found: Vec<&str> = out.lines().filter_map(|line| line.split(':').next()).filter(|p| p.contains('/')).collect();
+    let shown = |path: &str| root.join(path).display().to_string();
+    assert_eq!(found, [shown("keep.log"), shown("src/build/out.rs"), shown("src/main.rs")]);
+
+    // Rules in the repository above the searched directory still apply
+    write(root, "src/trace.log", "needle\n");
+    let (out, matches) = search(&root.join("src"), "needle", &recursive());
+    assert_eq!(matches, 2);
+    assert!(!out.contains("trace.log"));
+}
+
+#[test]
+fn test_max_results_stops_the_search() {
+    let dir = TempDir::new().unwrap();
+    write(dir.path(), "a.txt", "x\nx\n");
+    write(dir.path(), "b.txt", "x\n");
+    let options = SearchOptions { max_results: Some(2), ..recursive() };
+    let (out, matches) = search(dir.path(), "x", &options);
+    assert_eq!(matches, 2);
+    assert!(!out.contains("b.txt"));
+    assert!(out.ends_with("(Stopped after 2 matches. Raise --max-results to see more)"));
+}
+
+#[test]
+fn test_binary_files_and_bad_patterns() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("image.bin"), b"needle\0\xff").unwrap();
+    let (out, matches) = search(dir.path(), "needle", &recursive());
+    assert_eq!((out.as_str(), matches), ("(0 matches in 0 files)", 0));
+
+    let err = cmd_search(&mut Vec::new(), dir.path().to_str().unwrap(), "(", &recursive()).unwrap_err();
+    assert!(err.starts_with("Invalid pattern"));
+}
+
+#[test]
+fn test_searches_are_audited() {
+    let dir = TempDir::new().unwrap();
+    let log_path = dir.path().join("audit.jsonl");
+    let options = SearchOptions { audit_log: Some(log_path.clone()), ..recursive() };
+    search(dir.path(), "needle", &options);
+
+    let record: serde_json::Value = serde_json::from_str(fs::read_to_string(&log_path).unwrap().trim()).unwrap();
+    assert_eq!(record["event"], "search");
+    assert_eq!(record["files"][0], dir.path().to_str().unwrap());
+    assert_eq!(record["payload_sha256"], content_fingerprint("needle"));
+}
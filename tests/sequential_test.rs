@@ -0,0 +1,74 @@
+use hashline_tools::*;
+
+// Helper function to compute cumulative hashes for a file and get a specific line's hash
+fn get_line_hash(content: &str, line_num: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut prev_hash: Option<&str> = None;
+    let mut cumulative_hashes: Vec<String> = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let ln = i + 1;
+        let hash = compute_line_hash(ln, line, prev_hash);
+        cumulative_hashes.push(hash);
+        prev_hash = Some(&cumulative_hashes[i]);
+    }
+
+    cumulative_hashes[line_num - 1].clone()
+}
+
+fn sequential_options() -> ApplyOptions {
+    ApplyOptions { sequential: true, ..ApplyOptions::default() }
+}
+
+#[test]
+fn test_sequential_edits_use_intermediate_anchors() {
+    let content = "one\ntwo\nthree\n";
+    let after_first = "zero\none\ntwo\nthree\n";
+    let edits = vec![
+        HashlineEdit::Prepend {
+            pos: None,
+            lines: vec!["zero".to_string()],
+            expected_lines: None,
+        },
+        // Anchored against the file as it is after the prepend
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 3, hash: get_line_hash(after_first, 3) },
+            end: None,
+            lines: vec!["TWO".to_string()],
+            expected_lines: None,
+        },
+    ];
+
+    assert!(apply_hashline_edits(content, &edits).is_err(), "Batch mode validates against the original");
+    let outcome = apply_hashline_edits_with_options(content, &edits, &sequential_options()).unwrap();
+    assert_eq!(outcome.content, "zero\none\nTWO\nthree\n");
+    assert_eq!(outcome.first_changed_line, Some(1));
+    assert_eq!(outcome.statuses, vec![EditStatus::Applied, EditStatus::Applied]);
+}
+
+#[test]
+fn test_sequential_stops_at_first_stale_edit() {
+    let content = "one\ntwo\n";
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 1, hash: get_line_hash(content, 1) },
+            end: None,
+            lines: vec!["ONE".to_string()],
+            expected_lines: None,
+        },
+        // Line 1's hash changed with the first edit
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 1, hash: get_line_hash(content, 1) },
+            end: None,
+            lines: vec!["again".to_string()],
+            expected_lines: None,
+        },
+    ];
+
+    let err = apply_hashline_edits_with_options(content, &edits, &sequential_options()).unwrap_err();
+    assert!(matches!(err, HashlineError::Mismatch(_)));
+
+    let statuses = validate_hashline_edits_with_options(content, &edits, &sequential_options());
+    assert_eq!(statuses[0], EditStatus::Valid);
+    assert!(!statuses[1].is_ok());
+}
@@ -0,0 +1,176 @@
+use hashline_tools::*;
+use serde_json::json;
+
+const CARGO_TOML: &str = r#"[package]
+name = "demo"   # the crate name
+version = '0.1.0'
+
+[dependencies]
+serde = { version = "1.0.190", features = ["derive"] }
+clap.version = "4"
+
+[dependencies.tokio]
+version = "1"
+features = [
+    "rt",   # runtime
+    "macros",
+]
+
+[[bin]]
+name = "first"
+
+[[bin]]
+name = "second"
+"#;
+
+const CONFIG_YAML: &str = "\
+# Service settings
+server:
+  host: localhost  # bind address
+  port: 8080
+  tags: [web, 'public']
+  motd: |
+    Hello
+    there
+database:
+  replicas:
+  - name: primary
+    url: \"postgres://a\"
+  - name: replica
+    url: 'postgres://b'
+debug: false
+";
+
+const PACKAGE_JSON: &str = r#"{
+  // build settings
+  "name": "demo",
+  "scripts": { "build": "tsc", "test": "jest" },
+  "files": ["dist", "lib"],
+  "version": "1.0.0",
+}
+"#;
+
+fn apply(content: &str, batch: &[HashlineEdit]) -> Result<ApplyOutcome, HashlineError> {
+    apply_hashline_edits_with_options(content, batch, &ApplyOptions::default())
+}
+
+fn set(content: &str, path: &str, value: serde_json::Value) -> Result<String, HashlineError> {
+    let batch = edits().set_path(path, value).build().unwrap();
+    apply(content, &batch).map(|outcome| outcome.content)
+}
+
+#[test]
+fn test_formats_are_detected_from_the_content() {
+    assert_eq!(StructuredFormat::detect(CARGO_TOML), StructuredFormat::Toml);
+    assert_eq!(StructuredFormat::detect("# x\nkey = 1\n"), StructuredFormat::Toml);
+    assert_eq!(StructuredFormat::detect(CONFIG_YAML), StructuredFormat::Yaml);
+    assert_eq!(StructuredFormat::detect(PACKAGE_JSON), StructuredFormat::Json);
+    assert_eq!(StructuredFormat::detect("[1, 2]"), StructuredFormat::Json);
+}
+
+#[test]
+fn test_toml_values_are_rewritten_in_place() {
+    let edited = set(CARGO_TOML, "dependencies.serde.version", json!("1.0.200")).unwrap();
+    assert_eq!(edited, CARGO_TOML.replace("1.0.190", "1.0.200"));
+
+    // Comments and literal-string quoting are kept
+    assert_eq!(set(CARGO_TOML, "package.name", json!("app")).unwrap(), CARGO_TOML.replace("\"demo\"", "\"app\""));
+    assert_eq!(set(CARGO_TOML, "package.version", json!("0.2.0")).unwrap(), CARGO_TOML.replace("'0.1.0'", "'0.2.0'"));
+
+    // Dotted keys, table headers, array items and arrays of tables
+    assert_eq!(set(CARGO_TOML, "dependencies.clap.version", json!("4.5")).unwrap(), CARGO_TOML.replace("\"4\"", "\"4.5\""));
+    assert_eq!(set(CARGO_TOML, "dependencies.tokio.features[1]", json!("full")).unwrap(), CARGO_TOML.replace("\"macros\"", "\"full\""));
+    assert_eq!(set(CARGO_TOML, "bin.1.name", json!("last")).unwrap(), CARGO_TOML.replace("\"second\"", "\"last\""));
+
+    // A multi-line array collapses onto the key's line
+    let edited = set(CARGO_TOML, "dependencies.tokio.features", json!(["full"])).unwrap();
+    assert!(edited.contains("version = \"1\"\nfeatures = [\"full\"]\n\n[[bin]]"));
+    let edited = set(CARGO_TOML, "dependencies.serde", json!({"version": "1", "default-features": false})).unwrap();
+    assert!(edited.contains("serde = { default-features = false, version = \"1\" }\n"));
+}
+
+#[test]
+fn test_toml_with_crlf_and_quoted_multi_line_strings() {
+    let crlf = CARGO_TOML.replace('\n', "\r\n");
+    let edited = set(&crlf, "dependencies.tokio.features[1]", json!("full")).unwrap();
+    assert_eq!(edited, crlf.replace("\"macros\"", "\"full\""));
+
+    let content = "[package]\ndescription = \"\"\"Says \"hi\" and \"\"bye\"\"\"\"\"\nname = \"demo\"\n";
+    assert_eq!(set(content, "package.name", json!("app")).unwrap(), content.replace("\"demo\"", "\"app\""));
+    let edited = set(content, "package.description", json!("Quiet")).unwrap();
+    assert_eq!(edited, "[package]\ndescription = \"Quiet\"\nname = \"demo\"\n");
+}
+
+#[test]
+fn test_yaml_values_are_rewritten_in_place() {
+    assert_eq!(set(CONFIG_YAML, "server.port", json!(9090)).unwrap(), CONFIG_YAML.replace("8080", "9090"));
+    assert_eq!(
+        set(CONFIG_YAML, "server.host", json!("0.0.0.0")).unwrap(),
+        CONFIG_YAML.replace("localhost  #", "0.0.0.0  #")
+    );
+    assert_eq!(
+        set(CONFIG_YAML, "database.replicas[1].url", json!("postgres://c")).unwrap(),
+        CONFIG_YAML.replace("'postgres://b'", "'postgres://c'")
+    );
+    assert_eq!(
+        set(CONFIG_YAML, "database.replicas.0.url", json!("postgres://z")).unwrap(),
+        CONFIG_YAML.replace("\"postgres://a\"", "\"postgres://z\"")
+    );
+    assert_eq!(set(CONFIG_YAML, "debug", json!(true)).unwrap(), CONFIG_YAML.replace("false", "true"));
+    assert_eq!(set(CONFIG_YAML, "server.tags", json!(["api"])).unwrap(), CONFIG_YAML.replace("[web, 'public']", "[\"api\"]"));
+
+    // Strings that would read back as something else are quoted
+    assert!(set(CONFIG_YAML, "server.host", json!("yes")).unwrap().contains("host: \"yes\"  #"));
+    // A block scalar is replaced with its lines
+    let edited = set(CONFIG_YAML, "server.motd", json!("Bye\n")).unwrap();
+    assert!(edited.contains("  motd: \"Bye\\n\"\ndatabase:"));
+}
+
+#[test]
+fn test_json_values_are_rewritten_in_place() {
+    assert_eq!(set(PACKAGE_JSON, "version", json!("1.1.0")).unwrap(), PACKAGE_JSON.replace("1.0.0", "1.1.0"));
+    assert_eq!(set(PACKAGE_JSON, "scripts.test", json!("vitest")).unwrap(), PACKAGE_JSON.replace("jest", "vitest"));
+    assert_eq!(set(PACKAGE_JSON, "files[0]", json!("build")).unwrap(), PACKAGE_JSON.replace("\"dist\"", "\"build\""));
+    let path_with_dot = set("{\"a.b\": 1}", "\"a.b\"", json!(2)).unwrap();
+    assert_eq!(path_with_dot, "{\"a.b\": 2}");
+}
+
+#[test]
+fn test_missing_paths_and_stale_anchors_are_reported() {
+    let err = set(CARGO_TOML, "dependencies.rand.version", json!("1")).unwrap_err();
+    assert!(err.to_string().contains("no value at `dependencies.rand.version`"));
+    let err = set(CARGO_TOML, "dependencies.tokio", json!("1")).unwrap_err();
+    assert!(err.to_string().contains("`dependencies.tokio` holds nested values"));
+    let err = set(CONFIG_YAML, "server.port", json!(null)).map(|_| ()).and(set(CARGO_TOML, "package.name", json!(null)));
+    assert!(err.unwrap_err().to_string().contains("TOML has no null"));
+    let err = set("{\"a\": }", "a", json!(1)).unwrap_err();
+    assert!(err.to_string().contains("does not parse as JSON: line 1: expected a value"));
+
+    // `pos` must anchor the value's line, with the hash it has now
+    let file = HashedFile::from_content(CONFIG_YAML);
+    let at = |pos: Option<AnchorRef>| vec![HashlineEdit::SetPath {
+        path: "server.port".to_string(),
+        value: json!(1),
+        pos,
+        format: Some(StructuredFormat::Yaml),
+    }];
+    assert!(matches!(apply(CONFIG_YAML, &at(Some("4#ZZ".parse().unwrap()))), Err(HashlineError::Mismatch(_))));
+    let err = apply(CONFIG_YAML, &at(file.anchor(3))).unwrap_err();
+    assert!(err.to_string().contains("`server.port` is on line 4, not line 3"));
+    assert!(apply(CONFIG_YAML, &at(file.anchor(4))).unwrap().content.contains("  port: 1\n"));
+}
+
+#[test]
+fn test_set_path_edits_parse_and_join_a_batch() {
+    let batch = parse_edits(r#"[
+        {"op": "set_path", "path": "server.port", "value": 9090},
+        {"op": "set_path", "path": "debug", "value": true, "format": "yaml"}
+    ]"#).unwrap();
+    let outcome = apply(CONFIG_YAML, &batch).unwrap();
+    assert_eq!(outcome.content, CONFIG_YAML.replace("8080", "9090").replace("false", "true"));
+    assert_eq!(outcome.first_changed_line, Some(4));
+
+    // Two edits of the same value overlap
+    let batch = edits().set_path("debug", false).set_path("debug", true).build().unwrap();
+    assert!(matches!(apply(CONFIG_YAML, &batch), Err(HashlineError::Overlap(_))));
+}
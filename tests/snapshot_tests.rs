@@ -16,11 +16,16 @@ fn get_line_hash(content: &str, line_num: usize) -> String {
     cumulative_hashes[line_num - 1].clone()
 }
 
+#[cfg(feature = "cli")]
 use regex::Regex;
+#[cfg(feature = "cli")]
 use std::fs;
+#[cfg(feature = "cli")]
 use std::io::Write;
+#[cfg(feature = "cli")]
 use tempfile::NamedTempFile;
 
+#[cfg(feature = "cli")]
 fn create_test_file(content: &str) -> (NamedTempFile, String) {
     let mut temp_file = NamedTempFile::new().unwrap();
     write!(temp_file, "{}", content).unwrap();
@@ -28,12 +33,14 @@ fn create_test_file(content: &str) -> (NamedTempFile, String) {
     (temp_file, path)
 }
 
+#[cfg(feature = "cli")]
 fn normalize_edit_output(result: &str) -> String {
     // Replace temp file paths with a placeholder
     let re = Regex::new(r"/tmp/\.tmp\w+").unwrap();
     re.replace_all(result, "<TEMP_FILE>").to_string()
 }
 
+#[cfg(feature = "cli")]
 #[test]
 fn snapshot_cmd_read_simple_file() {
     let (_temp_file, path) = create_test_file("line 1\nline 2\nline 3\n");
@@ -41,6 +48,7 @@ fn snapshot_cmd_read_simple_file() {
     insta::assert_snapshot!(result);
 }
 
+#[cfg(feature = "cli")]
 #[test]
 fn snapshot_cmd_read_empty_file() {
     let (_temp_file, path) = create_test_file("");
@@ -48,6 +56,7 @@ fn snapshot_cmd_read_empty_file() {
     insta::assert_snapshot!(result);
 }
 
+#[cfg(feature = "cli")]
 #[test]
 fn snapshot_cmd_read_with_offset() {
     let mut temp_file = NamedTempFile::new().unwrap();
@@ -55,10 +64,12 @@ fn snapshot_cmd_read_with_offset() {
         writeln!(temp_file, "line {}", i).unwrap();
     }
     let path = temp_file.path().to_str().unwrap().to_string();
-    let result = cmd_read(&path, Some(5), Some(5)).unwrap();
+    // The follow-up commands name the temp file
+    let result = cmd_read(&path, Some(5), Some(5)).unwrap().replace(&path, "FILE");
     insta::assert_snapshot!(result);
 }
 
+#[cfg(feature = "cli")]
 #[test]
 fn snapshot_cmd_read_with_trailing_content() {
     let mut temp_file = NamedTempFile::new().unwrap();
@@ -70,6 +81,7 @@ fn snapshot_cmd_read_with_trailing_content() {
     insta::assert_snapshot!(result);
 }
 
+#[cfg(feature = "cli")]
 #[test]
 fn snapshot_cmd_read_single_line_file() {
     let (_temp_file, path) = create_test_file("only line\n");
@@ -77,6 +89,7 @@ fn snapshot_cmd_read_single_line_file() {
     insta::assert_snapshot!(result);
 }
 
+#[cfg(feature = "cli")]
 #[test]
 fn snapshot_cmd_read_unicode() {
     let (_temp_file, path) = create_test_file("Hello 世界\n🎉 Emoji test\n\n");
@@ -84,6 +97,7 @@ fn snapshot_cmd_read_unicode() {
     insta::assert_snapshot!(result);
 }
 
+#[cfg(feature = "cli")]
 #[test]
 fn snapshot_cmd_read_windows_line_endings() {
     let mut temp_file = NamedTempFile::new().unwrap();
@@ -93,6 +107,7 @@ fn snapshot_cmd_read_windows_line_endings() {
     insta::assert_snapshot!(result);
 }
 
+#[cfg(feature = "cli")]
 #[test]
 fn snapshot_cmd_read_file_with_only_newlines() {
     let (_temp_file, path) = create_test_file("\n\n\n");
@@ -100,6 +115,7 @@ fn snapshot_cmd_read_file_with_only_newlines() {
     insta::assert_snapshot!(result);
 }
 
+#[cfg(feature = "cli")]
 #[test]
 fn snapshot_cmd_read_file_with_only_whitespace_lines() {
     let (_temp_file, path) = create_test_file("   \n  \n\t\n");
@@ -107,6 +123,7 @@ fn snapshot_cmd_read_file_with_only_whitespace_lines() {
     insta::assert_snapshot!(result);
 }
 
+#[cfg(feature = "cli")]
 #[test]
 fn snapshot_cmd_read_offset_beyond_file() {
     let (_temp_file, path) = create_test_file("line 1\nline 2\n");
@@ -164,6 +181,7 @@ fn snapshot_apply_hashline_edits_replace_single() {
             pos: AnchorRef { line: 2, hash: get_line_hash(content, 2) },
             end: None,
             lines: vec!["replaced".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, first_changed) = apply_hashline_edits(content, &edits).unwrap();
@@ -179,6 +197,7 @@ fn snapshot_apply_hashline_edits_replace_range() {
             pos: AnchorRef { line: 1, hash: get_line_hash(content, 1) },
             end: Some(AnchorRef { line: 3, hash: get_line_hash(content, 3) }),
             lines: vec!["replaced".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -192,6 +211,7 @@ fn snapshot_apply_hashline_edits_append() {
         HashlineEdit::Append {
             pos: Some(AnchorRef { line: 1, hash: get_line_hash(content, 1) }),
             lines: vec!["inserted".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -205,6 +225,7 @@ fn snapshot_apply_hashline_edits_append_eof() {
         HashlineEdit::Append {
             pos: None,
             lines: vec!["at eof".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -218,6 +239,7 @@ fn snapshot_apply_hashline_edits_prepend() {
         HashlineEdit::Prepend {
             pos: Some(AnchorRef { line: 2, hash: get_line_hash(content, 2) }),
             lines: vec!["before".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -231,6 +253,7 @@ fn snapshot_apply_hashline_edits_prepend_bof() {
         HashlineEdit::Prepend {
             pos: None,
             lines: vec!["at bof".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -244,6 +267,7 @@ fn snapshot_apply_hashline_edits_empty_content() {
         HashlineEdit::Append {
             pos: None,
             lines: vec!["new line".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -258,6 +282,7 @@ fn snapshot_apply_hashline_edits_empty_new_text() {
             pos: AnchorRef { line: 2, hash: get_line_hash(content, 2) },
             end: None,
             lines: vec![],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -271,6 +296,7 @@ fn snapshot_apply_hashline_edits_to_empty_file() {
         HashlineEdit::Append {
             pos: None,
             lines: vec!["line 1".to_string(), "line 2".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -285,6 +311,7 @@ fn snapshot_apply_hashline_edits_single_line() {
             pos: AnchorRef { line: 1, hash: get_line_hash(content, 1) },
             end: None,
             lines: vec!["modified".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -298,11 +325,13 @@ fn snapshot_apply_hashline_edits_multiple_operations() {
         HashlineEdit::Append {
             pos: Some(AnchorRef { line: 1, hash: get_line_hash(content, 1) }),
             lines: vec!["new line 1.5".to_string()],
+            expected_lines: None,
         },
         HashlineEdit::Replace {
             pos: AnchorRef { line: 5, hash: get_line_hash(content, 5) },
             end: None,
             lines: vec!["modified line 5".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -337,6 +366,7 @@ fn snapshot_hashline_mismatch_error() {
             pos: AnchorRef { line: 2, hash: "ZZ".to_string() }, // Wrong hash
             end: None,
             lines: vec!["replaced".to_string()],
+            expected_lines: None,
         }
     ];
     let result = apply_hashline_edits(content, &edits);
@@ -345,6 +375,34 @@ fn snapshot_hashline_mismatch_error() {
     assert!(err_msg.contains("changed since last read"));
 }
 
+#[test]
+fn snapshot_hashline_mismatch_error_context() {
+    let content = "l1\nl2\nl3\nl4\nl5\nl6\nl7\nl8\nl9\n";
+    let edits = vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 5, hash: "ZZ".to_string() },
+            end: None,
+            lines: vec!["replaced".to_string()],
+            expected_lines: None,
+        }
+    ];
+    let rendered = |context: usize| {
+        let options = ApplyOptions { mismatch_context: context, ..ApplyOptions::default() };
+        apply_hashline_edits_with_options(content, &edits, &options).unwrap_err().to_string()
+    };
+
+    let default = rendered(DEFAULT_MISMATCH_CONTEXT);
+    assert!(default.contains(":l3") && default.contains(":l7"));
+    assert!(!default.contains(":l2") && !default.contains(":l8"));
+
+    let wide = rendered(4);
+    assert!(wide.contains(":l1") && wide.contains(":l9"));
+
+    let narrow = rendered(0);
+    assert!(narrow.contains(":l5"));
+    assert!(!narrow.contains(":l4") && !narrow.contains(":l6"));
+}
+
 #[test]
 fn snapshot_hashline_line_out_of_range() {
     let content = "first\nsecond\n";
@@ -353,6 +411,7 @@ fn snapshot_hashline_line_out_of_range() {
             pos: AnchorRef { line: 10, hash: "AB".to_string() },
             end: None,
             lines: vec!["replaced".to_string()],
+            expected_lines: None,
         }
     ];
     let result = apply_hashline_edits(content, &edits);
@@ -366,6 +425,7 @@ fn snapshot_hashline_append_after_last_line() {
         HashlineEdit::Append {
             pos: Some(AnchorRef { line: 2, hash: get_line_hash(content, 2) }),
             lines: vec!["third".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -380,6 +440,7 @@ fn snapshot_apply_hashline_edits_with_special_characters() {
             pos: AnchorRef { line: 1, hash: get_line_hash(content, 1) },
             end: None,
             lines: vec!["replaced".to_string()],
+            expected_lines: None,
         }
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -404,6 +465,7 @@ fn snapshot_apply_hashline_edits_replace_lines_range_mismatch() {
             pos: AnchorRef { line: 3, hash: get_line_hash(content, 3) },
             end: Some(AnchorRef { line: 1, hash: get_line_hash(content, 1) }),
             lines: vec!["replaced".to_string()],
+            expected_lines: None,
         }
     ];
     let result = apply_hashline_edits(content, &edits);
@@ -417,6 +479,7 @@ fn snapshot_apply_hashline_edits_deduplication() {
         pos: AnchorRef { line: 1, hash: get_line_hash(content, 1) },
         end: None,
         lines: vec!["replaced".to_string()],
+        expected_lines: None,
     };
     // Duplicate edits should be deduplicated
     let edits = vec![edit.clone(), edit];
@@ -434,6 +497,7 @@ fn snapshot_apply_hashline_edits_noop_detection() {
             pos: AnchorRef { line: 1, hash: get_line_hash(content, 1) },
             end: None,
             lines: vec!["first".to_string()], // Same content
+            expected_lines: None,
         }
     ];
     let (result, first_changed) = apply_hashline_edits(content, &edits).unwrap();
@@ -451,17 +515,18 @@ fn test_multiple_edits_applied_bottom_up() {
     let content = "line 1\nline 2\nline 3\n";
     let h1 = get_line_hash(content, 1);
     let h2 = get_line_hash(content, 2);
-    let h3 = get_line_hash(content, 3);
 
     let edits = vec![
         HashlineEdit::Prepend {
             pos: Some(AnchorRef { line: 1, hash: h1.clone() }),
             lines: vec!["prepended".to_string()],
+            expected_lines: None,
         },
         HashlineEdit::Replace {
             pos: AnchorRef { line: 2, hash: h2.clone() },
             end: None,
             lines: vec!["replaced".to_string()],
+            expected_lines: None,
         },
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -485,16 +550,19 @@ fn test_three_edits_bottom_up() {
             pos: AnchorRef { line: 1, hash: h1.clone() },
             end: None,
             lines: vec!["A".to_string()],
+            expected_lines: None,
         },
         HashlineEdit::Replace {
             pos: AnchorRef { line: 4, hash: h4.clone() },
             end: None,
             lines: vec!["D".to_string()],
+            expected_lines: None,
         },
         HashlineEdit::Replace {
             pos: AnchorRef { line: 2, hash: h2.clone() },
             end: None,
             lines: vec!["B".to_string()],
+            expected_lines: None,
         },
     ];
     let (result, _) = apply_hashline_edits(content, &edits).unwrap();
@@ -507,3 +575,42 @@ fn test_three_edits_bottom_up() {
     assert!(result.contains("B"));
     assert!(result.contains("D"));
 }
+
+#[cfg(feature = "cli")]
+#[test]
+fn snapshot_cmd_edit_auto_rebase() {
+    let original = "fn main() {\n    let total = compute(1, 2);\n    println!(\"{}\", total);\n}\n";
+    let stale_hash = get_line_hash(original, 2);
+    let (_temp_file, path) = create_test_file(&format!("// header\n{}", original));
+    let edits_json = format!(
+        r#"[{{"op": "replace", "pos": "2#{}", "lines": ["    let total = compute(1, 3);"]}}]"#,
+        stale_hash
+    );
+    let options = EditOptions {
+        apply: ApplyOptions { auto_rebase: true, ..ApplyOptions::default() },
+        ..EditOptions::default()
+    };
+    let result = cmd_edit_with_options(&path, &edits_json, &options).unwrap();
+    insta::assert_snapshot!(normalize_edit_output(&result));
+    assert!(fs::read_to_string(&path).unwrap().contains("compute(1, 3)"));
+}
+
+#[test]
+fn snapshot_hashline_mismatch_relocation_hint() {
+    let content = "alpha\nbeta\ngamma\ndelta\nepsilon\nzeta\n";
+    // Anchor names line 2 but carries the hash of line 4
+    let edits = |line: &str| vec![
+        HashlineEdit::Replace {
+            pos: AnchorRef { line: 2, hash: get_line_hash(content, 4) },
+            end: None,
+            lines: vec![line.to_string()],
+            expected_lines: None,
+        }
+    ];
+    let err = apply_hashline_edits(content, &edits("delta!")).unwrap_err();
+    insta::assert_snapshot!(err.to_string());
+
+    // Without content resembling line 4, its hash may be there by chance
+    let err = apply_hashline_edits(content, &edits("DELTA")).unwrap_err();
+    assert!(!err.to_string().contains("moved to line"), "{}", err);
+}
@@ -0,0 +1,90 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+fn batch(session: &str, timestamp: u64, edits: usize, applied: usize, mismatches: usize, rebased: usize) -> BatchStats {
+    BatchStats {
+        session: session.to_string(),
+        timestamp,
+        file: "f.txt".to_string(),
+        edits,
+        applied,
+        mismatches,
+        auto_rebase: true,
+        rebased,
+        failed: applied == 0,
+    }
+}
+
+#[test]
+fn test_edits_record_batch_stats() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("f.txt");
+    fs::write(&path, "one\ntwo\n").unwrap();
+    let path = path.to_str().unwrap();
+    let stats_file = dir.path().join("stats/stats.jsonl");
+    let options = EditOptions { stats_file: Some(stats_file.clone()), ..EditOptions::default() };
+
+    let anchor = HashedFile::from_content("one\ntwo\n").anchor(1).unwrap();
+    let edits = format!(r#"[{{"op": "replace", "pos": "{}", "lines": ["uno"]}}]"#, anchor);
+    cmd_edit_with_options(path, &edits, &options).unwrap();
+    // The anchor is stale now
+    assert!(cmd_edit_with_options(path, &edits, &options).is_err());
+    // Dry runs are not recorded
+    cmd_edit_with_options(path, r#"[{"op": "append", "lines": ["three"]}]"#, &EditOptions { dry_run: true, ..options.clone() }).unwrap();
+
+    let batches = load_batch_stats(&stats_file).unwrap();
+    assert_eq!(batches.len(), 2);
+    assert_eq!((batches[0].edits, batches[0].applied, batches[0].mismatches, batches[0].failed), (1, 1, 0, false));
+    assert_eq!((batches[1].applied, batches[1].mismatches, batches[1].failed), (0, 1, true));
+    assert_eq!(batches[0].file, path);
+}
+
+#[test]
+fn test_session_stats_add_up() {
+    let batches = [
+        batch("b", 20, 4, 4, 0, 2),
+        batch("a", 10, 2, 0, 1, 0),
+        batch("b", 30, 3, 3, 0, 0),
+    ];
+    let sessions = session_stats(&batches);
+    let names: Vec<_> = sessions.iter().map(|s| s.session.as_deref().unwrap()).collect();
+    assert_eq!(names, ["a", "b"]);
+    let b = &sessions[1];
+    assert_eq!((b.batches, b.edits, b.applied, b.mismatched_batches), (2, 7, 7, 0));
+    assert_eq!(b.average_batch_size(), 3.5);
+    assert_eq!(b.rebase_success_rate(), Some(1.0));
+    assert_eq!(sessions[0].mismatch_rate(), 1.0);
+    assert_eq!(sessions[0].rebase_success_rate(), Some(0.0));
+    assert_eq!(SessionStats::default().rebase_success_rate(), None);
+}
+
+#[test]
+fn test_stats_command() {
+    let dir = TempDir::new().unwrap();
+    let stats_file = dir.path().join("stats.jsonl");
+    assert!(cmd_stats(&stats_file, false, OutputFormat::Text).unwrap().starts_with("No edit batches recorded"));
+
+    let lines: Vec<String> = [batch("a", 10, 2, 0, 1, 1), batch("b", 20, 2, 2, 0, 0)].iter()
+        .map(|b| serde_json::to_string(b).unwrap())
+        .collect();
+    // A line cut short is skipped
+    fs::write(&stats_file, format!("{}\n{}\n{{\"sess", lines[0], lines[1])).unwrap();
+
+    let text = cmd_stats(&stats_file, false, OutputFormat::Text).unwrap();
+    assert!(text.starts_with("Edit batches: 2 in 2 sessions\nEdits applied: 2 of 4\n"), "{}", text);
+    assert!(text.contains("Mismatch rate: 50% of batches"));
+    assert!(text.ends_with("Auto-rebase success rate: 50% of stale anchors (1 of 2)"));
+
+    let table = cmd_stats(&stats_file, true, OutputFormat::Text).unwrap();
+    assert_eq!(table.lines().count(), 3);
+    assert!(table.starts_with("SESSION  BATCHES"));
+
+    let json: serde_json::Value = serde_json::from_str(&cmd_stats(&stats_file, true, OutputFormat::Json).unwrap()).unwrap();
+    assert_eq!(json["total"]["batches"], 2);
+    assert_eq!(json["sessions"][1]["session"], "b");
+    assert_eq!(json["sessions"][0]["rebase_success_rate"], 0.5);
+}
@@ -0,0 +1,37 @@
+#![cfg(feature = "cli")]
+
+use std::io::Cursor;
+
+use clap::Parser;
+use hashline_tools::*;
+
+#[test]
+fn test_read_payload_enforces_the_limit() {
+    let payload = r#"[{"op":"append","lines":["x"]}]"#;
+    assert_eq!(read_payload(Cursor::new(payload), payload.len() as u64).unwrap(), payload);
+
+    let error = read_payload(Cursor::new(payload), payload.len() as u64 - 1).unwrap_err();
+    assert_eq!(error, format!("Payload on stdin is larger than {} bytes; raise --max-payload to allow it", payload.len() - 1));
+
+    let error = read_payload(Cursor::new(vec![b'[', 0xff, b']']), 1024).unwrap_err();
+    assert!(error.contains("not valid UTF-8"), "{}", error);
+}
+
+#[test]
+fn test_stdin_flags_conflict_with_inline_values() {
+    for args in [
+        &["edit", "f.txt", "--edits", "[]", "--edits-stdin"][..],
+        &["plan", "f.txt", "--edits", "[]", "--edits-stdin"],
+        &["multi-edit", "--edits", "{}", "--edits-stdin"],
+        &["commit", "--token", "t", "--token-stdin"],
+    ] {
+        let result = Cli::try_parse_from(std::iter::once("hashline-tools").chain(args.iter().copied()));
+        let kind = result.err().map(|e| e.kind());
+        assert_eq!(kind, Some(clap::error::ErrorKind::ArgumentConflict), "{:?}", args);
+    }
+
+    let cli = Cli::try_parse_from(["hashline-tools", "edit", "f.txt", "--edits-stdin"]).unwrap();
+    assert_eq!(cli.max_payload, DEFAULT_MAX_PAYLOAD);
+    let cli = Cli::try_parse_from(["hashline-tools", "--max-payload", "1K", "edit", "f.txt", "--edits-stdin"]).unwrap();
+    assert_eq!(cli.max_payload, 1024);
+}
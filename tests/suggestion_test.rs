@@ -0,0 +1,62 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+fn suggestion(start_line: Option<usize>, line: usize, body: &str) -> Suggestion {
+    Suggestion { path: "src/lib.rs".to_string(), start_line, line, side: "RIGHT", body: body.to_string() }
+}
+
+#[test]
+fn test_changed_runs_become_suggestions() {
+    let old = "fn a() {}\n\nfn b() {\n    1\n}\n";
+    let new = "fn a() {}\n\nfn b() {\n    2\n    + 3\n}\n";
+    assert_eq!(github_suggestions("src/lib.rs", old, new), [suggestion(None, 4, "```suggestion\n    2\n    + 3\n```")]);
+
+    // Deleting lines suggests an empty block over them
+    let new = "fn a() {}\n";
+    assert_eq!(github_suggestions("src/lib.rs", old, new), [suggestion(Some(2), 5, "```suggestion\n```")]);
+}
+
+#[test]
+fn test_insertions_take_a_neighboring_line() {
+    let old = "a\nb\n";
+    assert_eq!(github_suggestions("src/lib.rs", old, "a\nb\nc\n"), [suggestion(None, 2, "```suggestion\nb\nc\n```")]);
+    assert_eq!(github_suggestions("src/lib.rs", old, "z\na\nb\n"), [suggestion(None, 1, "```suggestion\nz\na\n```")]);
+    assert!(github_suggestions("src/lib.rs", "", "a\n").is_empty());
+}
+
+#[test]
+fn test_backticks_lengthen_the_fence() {
+    let suggestions = github_suggestions("README.md", "text\n", "```rust\n");
+    assert_eq!(suggestions[0].body, "````suggestion\n```rust\n````");
+}
+
+#[test]
+fn test_edit_suggest_writes_nothing() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("notes.txt");
+    let content = "one\ntwo\nthree\n";
+    fs::write(&path, content).unwrap();
+    let path = path.to_str().unwrap();
+    let file = HashedFile::from_content(content);
+    let edits = format!(
+        r#"[{{"op": "replace", "pos": "{}", "end": "{}", "lines": ["2", "3"]}}]"#,
+        file.anchor(2).unwrap(),
+        file.anchor(3).unwrap(),
+    );
+
+    let options = EditOptions { suggest: true, ..EditOptions::default() };
+    let text = cmd_edit_with_options(path, &edits, &options).unwrap();
+    assert_eq!(text, format!("1 suggestion for {}, nothing written:\n\n{} lines 2-3:\n```suggestion\n2\n3\n```", path, path));
+
+    let json = EditOptions { format: OutputFormat::Json, ..options };
+    let value: serde_json::Value = serde_json::from_str(&cmd_edit_with_options(path, &edits, &json).unwrap()).unwrap();
+    assert_eq!(value["status"], "suggested");
+    assert_eq!(value["comments"][0]["start_line"], 2);
+    assert_eq!(value["comments"][0]["line"], 3);
+    assert_eq!(value["comments"][0]["side"], "RIGHT");
+    assert_eq!(fs::read_to_string(path).unwrap(), content);
+}
@@ -0,0 +1,147 @@
+#![cfg(feature = "tree-sitter")]
+
+#[cfg(feature = "cli")]
+use std::fs;
+
+use hashline_tools::*;
+#[cfg(feature = "cli")]
+use tempfile::TempDir;
+
+const RUST: AnchorMode = AnchorMode::Syntax(Some(Grammar::Rust));
+const PYTHON: AnchorMode = AnchorMode::Syntax(Some(Grammar::Python));
+
+/// `first` returns a raw string ending in a backslash, which the scoped
+/// heuristic takes for an escaped quote, so it never sees `first` close.
+const SOURCE: &str = r#"fn first() -> &'static str {
+    r"C:\"
+}
+
+fn second(x: u32) -> u32 {
+    let y = compute(x, 1, 2);
+    y + 2
+}
+"#;
+
+#[test]
+fn test_syntax_hashes_follow_the_parse_where_the_heuristic_fails() {
+    let renamed_source = SOURCE.replace("fn first", "fn renamed");
+    let before = HashedFile::from_content_with_mode(SOURCE, RUST);
+    let renamed = HashedFile::from_content_with_mode(&renamed_source, RUST);
+    assert_eq!(before.mode(), RUST);
+
+    // Renaming `first` changes its lines' hashes and no others
+    assert_ne!(before.hash_at(2), renamed.hash_at(2));
+    assert_eq!(before.hashes()[3..], renamed.hashes()[3..]);
+
+    // The heuristic leaves `second` inside `first`
+    let scoped = HashedFile::from_content_with_mode(SOURCE, AnchorMode::Scoped);
+    let scoped_renamed = HashedFile::from_content_with_mode(&renamed_source, AnchorMode::Scoped);
+    assert_ne!(scoped.hash_at(6), scoped_renamed.hash_at(6));
+}
+
+#[test]
+fn test_syntax_hashes_survive_reflowing_the_definition() {
+    let reflowed_source = SOURCE.replace("fn second(x: u32) -> u32 {", "fn second(\n  x: u32,\n) -> u32\n{");
+    let before = HashedFile::from_content_with_mode(SOURCE, RUST);
+    let reflowed = HashedFile::from_content_with_mode(&reflowed_source, RUST);
+    assert_eq!(before.hashes()[5..], reflowed.hashes()[8..]);
+
+    // Chained hashes change from the first reflowed line on
+    let chained_before = HashedFile::from_content(SOURCE);
+    let chained_after = HashedFile::from_content(&reflowed_source);
+    assert_ne!(chained_before.hashes()[5..], chained_after.hashes()[8..]);
+}
+
+#[test]
+fn test_python_definitions_seed_their_lines() {
+    let source = "\
+@dataclass
+class A:
+    @property
+    def f(self):
+        return 1
+
+    async def g(self):
+        return 1
+
+
+return 1
+";
+    let file = HashedFile::from_content_with_mode(source, PYTHON);
+    let top = HashedFile::from_content_with_mode("return 1\n", PYTHON);
+    assert_ne!(file.hash_at(5), file.hash_at(8));
+    assert_ne!(file.hash_at(5), top.hash_at(1));
+    assert_eq!(file.hash_at(11), top.hash_at(1));
+
+    // A decorator lies outside the definition it decorates
+    let decorator = HashedFile::from_content_with_mode("@dataclass\n", PYTHON);
+    assert_eq!(file.hash_at(1), decorator.hash_at(1));
+}
+
+#[test]
+fn test_syntax_anchors_take_the_grammar_from_the_extension() {
+    let syntax = AnchorMode::Syntax(None);
+    assert_eq!(syntax.for_path("src/lib.rs"), RUST);
+    assert_eq!(syntax.for_path("stubs/a.pyi"), PYTHON);
+    assert_eq!(syntax.for_path("app.js"), syntax);
+    assert_eq!(RUST.for_path("setup.py"), RUST);
+    assert_eq!(AnchorMode::Scoped.for_path("src/lib.rs"), AnchorMode::Scoped);
+
+    // Files no grammar covers get scoped hashes
+    let uncovered = HashedFile::from_content_with_mode(SOURCE, syntax);
+    let scoped = HashedFile::from_content_with_mode(SOURCE, AnchorMode::Scoped);
+    assert_eq!(uncovered.hashes(), scoped.hashes());
+
+    let editor = Editor::from_content("lib.rs", SOURCE).with_options(ApplyOptions { anchor_mode: syntax, ..ApplyOptions::default() });
+    assert_eq!(editor.file(), &HashedFile::from_content_with_mode(SOURCE, RUST));
+}
+
+#[test]
+fn test_syntax_modes_by_name() {
+    assert_eq!("syntax".parse::<AnchorMode>().unwrap(), AnchorMode::Syntax(None));
+    assert_eq!(serde_json::from_str::<AnchorMode>("\"syntax:python\"").unwrap(), PYTHON);
+    assert_eq!(serde_json::to_string(&RUST).unwrap(), "\"syntax:rust\"");
+    let err = "syntax:go".parse::<AnchorMode>().unwrap_err();
+    assert!(err.contains("syntax:rust"), "{}", err);
+    #[cfg(feature = "cli")]
+    {
+        use clap::Parser;
+        let cli = Cli::try_parse_from(["hashline-tools", "--anchors", "syntax", "read", "lib.rs"]).unwrap();
+        assert_eq!(cli.anchors, AnchorMode::Syntax(None));
+    }
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_read_and_edit_commands_in_syntax_mode() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("lib.rs");
+    fs::write(&path, SOURCE).unwrap();
+    let file_path = path.to_str().unwrap();
+    let file = HashedFile::from_content_with_mode(SOURCE, RUST);
+
+    let options = ReadOptions { anchor_mode: AnchorMode::Syntax(None), ..ReadOptions::default() };
+    let mut out = Vec::new();
+    cmd_read_with_options(&mut out, file_path, &options).unwrap();
+    let output = String::from_utf8(out).unwrap();
+    assert!(output.contains(&format!("6#{}:    let y = compute(x, 1, 2);", file.hash_at(6).unwrap())));
+
+    // The whole file is parsed, so it cannot be streamed
+    let streamed = ReadOptions { memory_budget: Some(1), ..options };
+    let err = cmd_read_with_options(&mut Vec::new(), file_path, &streamed).unwrap_err();
+    assert!(err.contains("over the memory budget"), "{}", err);
+
+    let edits = format!(r#"[{{"op": "replace", "pos": "{}", "lines": ["    y + 3"]}}]"#, file.anchor(7).unwrap());
+    let apply = ApplyOptions { anchor_mode: AnchorMode::Syntax(None), ..ApplyOptions::default() };
+    let options = EditOptions { apply, ..EditOptions::default() };
+    let result = cmd_edit_with_options(file_path, &edits, &options).unwrap();
+    let edited = fs::read_to_string(&path).unwrap();
+    let rehashed = HashedFile::from_content_with_mode(&edited, RUST);
+    assert!(result.contains(&format!("+7#{}:    y + 3", rehashed.hash_at(7).unwrap())));
+
+    // Scoped anchors do not validate in syntax mode
+    let scoped = HashedFile::from_content_with_mode(&edited, AnchorMode::Scoped);
+    assert_ne!(scoped.hash_at(6), rehashed.hash_at(6));
+    let edits = format!(r#"[{{"op": "delete", "pos": "{}"}}]"#, scoped.anchor(6).unwrap());
+    assert!(cmd_edit_with_options(file_path, &edits, &options).is_err());
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "cli")]
+
+use hashline_tools::*;
+use std::fs;
+
+// Helper function to compute cumulative hashes for a file and get a specific line's hash
+fn get_line_hash(content: &str, line_num: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut prev_hash: Option<&str> = None;
+    let mut cumulative_hashes: Vec<String> = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let ln = i + 1;
+        let hash = compute_line_hash(ln, line, prev_hash);
+        cumulative_hashes.push(hash);
+        prev_hash = Some(&cumulative_hashes[i]);
+    }
+
+    cumulative_hashes[line_num - 1].clone()
+}
+
+fn write_file(dir: &tempfile::TempDir, name: &str, content: &str) -> String {
+    let path = dir.path().join(name);
+    fs::write(&path, content).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+fn replace_edit(content: &str, line: usize, text: &str) -> HashlineEdit {
+    HashlineEdit::Replace {
+        pos: AnchorRef { line, hash: get_line_hash(content, line) },
+        end: None,
+        lines: vec![text.to_string()],
+        expected_lines: None,
+    }
+}
+
+#[test]
+fn test_multi_file_edits_apply_together() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = write_file(&dir, "a.txt", "a1\na2\n");
+    let b = write_file(&dir, "b.txt", "b1\nb2\n");
+
+    let mut edits = MultiFileEdits::new();
+    edits.insert(a.clone(), vec![replace_edit("a1\na2\n", 1, "A1")]);
+    edits.insert(b.clone(), vec![replace_edit("b1\nb2\n", 2, "B2")]);
+
+    let changes = apply_multi_file_edits(&edits, &ApplyOptions::default()).unwrap();
+    assert_eq!(changes.len(), 2);
+    assert_eq!(fs::read_to_string(&a).unwrap(), "A1\na2\n");
+    assert_eq!(fs::read_to_string(&b).unwrap(), "b1\nB2\n");
+}
+
+#[test]
+fn test_multi_file_edits_leave_all_files_untouched_on_failure() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = write_file(&dir, "a.txt", "a1\na2\n");
+    let b = write_file(&dir, "b.txt", "b1\nb2\n");
+
+    let mut edits = MultiFileEdits::new();
+    edits.insert(a.clone(), vec![replace_edit("a1\na2\n", 1, "A1")]);
+    edits.insert(b.clone(), vec![HashlineEdit::Replace {
+        pos: AnchorRef { line: 1, hash: "ZZ".to_string() },
+        end: None,
+        lines: vec!["B1".to_string()],
+        expected_lines: None,
+    }]);
+
+    let err = apply_multi_file_edits(&edits, &ApplyOptions::default()).unwrap_err();
+    match &err {
+        TransactionError::Validation(failures) => {
+            assert_eq!(failures.len(), 1);
+            assert_eq!(failures[0].path, b);
+            assert!(failures[0].mismatch.is_some());
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+    assert!(err.to_string().starts_with("Transaction aborted; no files were modified."));
+    assert_eq!(fs::read_to_string(&a).unwrap(), "a1\na2\n");
+    assert_eq!(fs::read_to_string(&b).unwrap(), "b1\nb2\n");
+}
+
+#[test]
+fn test_multi_file_edits_missing_file_aborts() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = write_file(&dir, "a.txt", "a1\n");
+    let missing = dir.path().join("missing.txt").to_str().unwrap().to_string();
+
+    let mut edits = MultiFileEdits::new();
+    edits.insert(a.clone(), vec![replace_edit("a1\n", 1, "A1")]);
+    edits.insert(missing, vec![replace_edit("x\n", 1, "y")]);
+
+    assert!(apply_multi_file_edits(&edits, &ApplyOptions::default()).is_err());
+    assert_eq!(fs::read_to_string(&a).unwrap(), "a1\n");
+    // No staged temp files are left behind
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_cmd_multi_edit_reports_each_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = write_file(&dir, "a.txt", "a1\n");
+    let b = write_file(&dir, "b.txt", "b1\n");
+    let payload = serde_json::json!({
+        a.clone(): [{"op": "replace", "pos": format!("1#{}", get_line_hash("a1\n", 1)), "lines": ["A1"]}],
+        b.clone(): [{"op": "append", "lines": ["b2"]}],
+    });
+
+    let output = cmd_multi_edit(&payload.to_string(), &EditOptions::default()).unwrap();
+    assert!(output.starts_with("Transaction applied to 2 files."), "Got: {}", output);
+    assert!(output.contains(&format!("--- {}", a)));
+    assert!(output.contains(&format!("--- {}", b)));
+    assert_eq!(fs::read_to_string(&b).unwrap(), "b1\nb2\n");
+}
@@ -0,0 +1,142 @@
+#![cfg(feature = "cli")]
+
+use hashline_tools::*;
+use std::fs;
+use std::path::Path;
+
+fn checkpointed(journal_dir: &Path) -> EditOptions {
+    EditOptions {
+        format: OutputFormat::Json,
+        journal_dir: Some(journal_dir.to_path_buf()),
+        checkpoints: true,
+        ..EditOptions::default()
+    }
+}
+
+/// Append `line` to the file and return the checkpoint the edit reports.
+fn append(path: &str, line: &str, options: &EditOptions) -> String {
+    let edits = serde_json::json!([{ "op": "append", "lines": [line] }]).to_string();
+    let result = cmd_edit_with_options(path, &edits, options).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+    json["checkpoint"].as_str().expect("edit reports a checkpoint").to_string()
+}
+
+#[test]
+fn test_undo_reverts_latest_or_back_to_checkpoint() {
+    let dir = tempfile::tempdir().unwrap();
+    let journal_dir = dir.path().join("journal");
+    let options = checkpointed(&journal_dir);
+    let path = dir.path().join("a.txt");
+    fs::write(&path, "one\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let first = append(path, "two", &options);
+    let second = append(path, "three", &options);
+    append(path, "four", &options);
+    assert!(first < second);
+
+    // Without a target only the latest batch is undone
+    let undone = undo_checkpoints(&checkpoint_dir(&journal_dir), None, &Policy::default()).unwrap();
+    assert_eq!(undone.len(), 1);
+    assert_eq!(fs::read_to_string(path).unwrap(), "one\ntwo\nthree\n");
+
+    let undone = undo_checkpoints(&checkpoint_dir(&journal_dir), Some(&first), &Policy::default()).unwrap();
+    let ids: Vec<&str> = undone.iter().map(|c| c.id.as_str()).collect();
+    assert_eq!(ids, vec![second.as_str(), first.as_str()]);
+    assert_eq!(fs::read_to_string(path).unwrap(), "one\n");
+
+    let err = undo_checkpoints(&checkpoint_dir(&journal_dir), Some(&first), &Policy::default()).unwrap_err();
+    assert!(matches!(&err, HashlineError::Undo(m) if m.contains("Unknown checkpoint")), "got: {}", err);
+}
+
+#[test]
+fn test_undo_refuses_when_file_changed_since() {
+    let dir = tempfile::tempdir().unwrap();
+    let journal_dir = dir.path().join("journal");
+    let options = checkpointed(&journal_dir);
+    let path = dir.path().join("a.txt");
+    fs::write(&path, "one\n").unwrap();
+    let path_str = path.to_str().unwrap();
+
+    let checkpoint = append(path_str, "two", &options);
+    fs::write(&path, "one\ntwo\nhand edit\n").unwrap();
+
+    let err = cmd_undo(&journal_dir, Some(&checkpoint), &Policy::default(), OutputFormat::Json).unwrap_err();
+    let json: serde_json::Value = serde_json::from_str(&err).unwrap();
+    assert_eq!(json["error"], "undo_failed");
+    assert!(json["message"].as_str().unwrap().contains("has changed since checkpoint"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\nhand edit\n");
+}
+
+#[test]
+fn test_undo_reverts_multi_file_transaction() {
+    let dir = tempfile::tempdir().unwrap();
+    let journal_dir = dir.path().join("journal");
+    let options = checkpointed(&journal_dir);
+    let a = dir.path().join("a.txt");
+    let b = dir.path().join("b.txt");
+    fs::write(&a, "a\n").unwrap();
+    fs::write(&b, "b\n").unwrap();
+
+    let payload = serde_json::json!({
+        a.to_str().unwrap(): [{ "op": "append", "lines": ["a2"] }],
+        b.to_str().unwrap(): [{ "op": "append", "lines": ["b2"] }],
+    }).to_string();
+    let result = cmd_multi_edit(&payload, &options).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+    let checkpoint = json["checkpoint"].as_str().unwrap();
+
+    let output = cmd_undo(&journal_dir, Some(checkpoint), &Policy::default(), OutputFormat::Text).unwrap();
+    assert!(output.starts_with("Undid 1 checkpoint:"), "got: {}", output);
+    assert_eq!(fs::read_to_string(&a).unwrap(), "a\n");
+    assert_eq!(fs::read_to_string(&b).unwrap(), "b\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_checkpoints_are_private_to_their_owner() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let journal_dir = dir.path().join("journal");
+    let options = checkpointed(&journal_dir);
+    let path = dir.path().join("a.txt");
+    fs::write(&path, "one\n").unwrap();
+    append(path.to_str().unwrap(), "two", &options);
+
+    let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+    let checkpoints = checkpoint_dir(&journal_dir);
+    assert_eq!((mode(&journal_dir), mode(&checkpoints)), (0o700, 0o700));
+    for entry in fs::read_dir(&checkpoints).unwrap() {
+        assert_eq!(mode(&entry.unwrap().path()), 0o600);
+    }
+
+    // Checkpoints others could have planted are neither written nor undone
+    fs::set_permissions(&checkpoints, fs::Permissions::from_mode(0o777)).unwrap();
+    let err = undo_checkpoints(&checkpoints, None, &Policy::default()).unwrap_err();
+    assert!(err.to_string().contains("not be writable by others"), "{}", err);
+    let edits = serde_json::json!([{ "op": "append", "lines": ["three"] }]).to_string();
+    let err = cmd_edit_with_options(path.to_str().unwrap(), &edits, &options).unwrap_err();
+    assert!(err.contains("Failed to record checkpoint"), "{}", err);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+}
+
+#[test]
+fn test_undo_checks_the_policy() {
+    let dir = tempfile::tempdir().unwrap();
+    let journal_dir = dir.path().join("journal");
+    let path = dir.path().join("a.txt");
+    fs::write(&path, "one\n").unwrap();
+    append(path.to_str().unwrap(), "two", &checkpointed(&journal_dir));
+
+    // Undo rewrites whole files, so a policy forbidding that refuses it
+    let policy = Policy { forbid_write: true, ..Policy::default() };
+    let err = cmd_undo(&journal_dir, None, &policy, OutputFormat::Json).unwrap_err();
+    let json: serde_json::Value = serde_json::from_str(&err).unwrap();
+    assert_eq!(json["error"], "policy_violation", "{}", err);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+
+    // The checkpoint is kept for when the policy allows it
+    undo_checkpoints(&checkpoint_dir(&journal_dir), None, &Policy::default()).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "one\n");
+}
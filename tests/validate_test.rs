@@ -0,0 +1,83 @@
+use hashline_tools::*;
+#[cfg(feature = "cli")]
+use std::fs;
+#[cfg(feature = "cli")]
+use std::io::Write;
+#[cfg(feature = "cli")]
+use tempfile::NamedTempFile;
+
+// Helper function to compute cumulative hashes for a file and get a specific line's hash
+fn get_line_hash(content: &str, line_num: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut prev_hash: Option<&str> = None;
+    let mut cumulative_hashes: Vec<String> = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let ln = i + 1;
+        let hash = compute_line_hash(ln, line, prev_hash);
+        cumulative_hashes.push(hash);
+        prev_hash = Some(&cumulative_hashes[i]);
+    }
+
+    cumulative_hashes[line_num - 1].clone()
+}
+
+fn replace(line: usize, hash: String, text: &str) -> HashlineEdit {
+    HashlineEdit::Replace {
+        pos: AnchorRef { line, hash },
+        end: None,
+        lines: vec![text.to_string()],
+        expected_lines: None,
+    }
+}
+
+#[test]
+fn test_validate_reports_each_edit() {
+    let content = "a\nb\nc\n";
+    let edits = vec![
+        replace(1, get_line_hash(content, 1), "A"),
+        replace(2, "ZZ".to_string(), "B"),
+        replace(7, "ZZ".to_string(), "G"),
+    ];
+
+    let statuses = validate_hashline_edits(content, &edits);
+    assert_eq!(statuses.len(), 3);
+    assert_eq!(statuses[0], EditStatus::Valid);
+    assert!(matches!(statuses[1], EditStatus::Skipped { reason: SkipReason::Mismatch, .. }));
+    assert!(matches!(statuses[2], EditStatus::Skipped { reason: SkipReason::Invalid, .. }));
+}
+
+#[test]
+fn test_validate_detects_overlaps() {
+    let content = "a\nb\nc\n";
+    let edits = vec![
+        replace(2, get_line_hash(content, 2), "B"),
+        HashlineEdit::Append {
+            pos: Some(AnchorRef { line: 1, hash: get_line_hash(content, 1) }),
+            lines: vec!["x".to_string()],
+            expected_lines: None,
+        },
+    ];
+
+    let statuses = validate_hashline_edits(content, &edits);
+    assert!(statuses.iter().all(|s| matches!(s, EditStatus::Skipped { reason: SkipReason::Overlap, .. })));
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_cmd_validate_only_does_not_write() {
+    let content = "a\nb\n";
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(temp_file, "{}", content).unwrap();
+    let path = temp_file.path().to_str().unwrap().to_string();
+    let options = EditOptions { validate_only: true, ..EditOptions::default() };
+
+    let edits_json = format!(r#"[{{"op": "replace", "pos": "1#{}", "lines": ["A"]}}]"#, get_line_hash(content, 1));
+    let output = cmd_edit_with_options(&path, &edits_json, &options).unwrap();
+    assert_eq!(output, "Validation passed: 1 edit would apply cleanly.");
+    assert_eq!(fs::read_to_string(&path).unwrap(), content);
+
+    let err = cmd_edit_with_options(&path, r#"[{"op": "replace", "pos": "1#ZZ", "lines": ["A"]}]"#, &options).unwrap_err();
+    assert!(err.starts_with("Validation failed: 1 of 1 edits would be skipped."), "Got: {}", err);
+    assert!(err.contains("edits[0]: skipped (mismatch)"), "Got: {}", err);
+}
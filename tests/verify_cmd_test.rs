@@ -0,0 +1,101 @@
+#![cfg(all(unix, feature = "cli"))]
+
+use std::fs;
+
+use hashline_tools::*;
+use tempfile::TempDir;
+
+fn setup(content: &str) -> (TempDir, String) {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("main.rs");
+    fs::write(&path, content).unwrap();
+    (dir, path.to_str().unwrap().to_string())
+}
+
+fn replace_line(content: &str, line: usize, text: &str) -> String {
+    let anchor = HashedFile::from_content(content).anchor(line).unwrap();
+    format!(r#"[{{"op": "replace", "pos": "{}", "lines": ["{}"]}}]"#, anchor, text)
+}
+
+fn verifying(dir: &TempDir, command: String) -> EditOptions {
+    EditOptions { verify_cmd: Some(command), journal_dir: Some(dir.path().join("journal")), checkpoints: true, ..EditOptions::default() }
+}
+
+#[test]
+fn test_passing_verify_command_keeps_the_edit() {
+    let (dir, file_path) = setup("fn main() {}\n");
+    let options = verifying(&dir, format!("grep -q answer {}", file_path));
+    let result = cmd_edit_with_options(&file_path, &replace_line("fn main() {}\n", 1, "fn answer() {}"), &options).unwrap();
+    assert!(result.starts_with("Edit applied successfully"));
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "fn answer() {}\n");
+}
+
+#[test]
+fn test_failing_verify_command_rolls_the_edit_back() {
+    let original = "fn main() {}\n";
+    let (dir, file_path) = setup(original);
+    let options = verifying(&dir, "echo checking; echo 'error: broken' >&2; exit 3".to_string());
+    let err = cmd_edit_with_options(&file_path, &replace_line(original, 1, "fn main() {"), &options).unwrap_err();
+    assert!(err.starts_with("Verification failed: `echo checking; echo 'error: broken' >&2; exit 3` exited with status 3."));
+    assert!(err.contains(&format!("The edit was rolled back: {}", file_path)));
+    assert!(err.ends_with("<output>\nchecking\nerror: broken\n</output>"));
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), original);
+
+    // The rolled-back write leaves no checkpoint to undo
+    assert!(cmd_undo(&dir.path().join("journal"), None, &Policy::default(), OutputFormat::Text).is_err());
+
+    let json = EditOptions { format: OutputFormat::Json, ..options };
+    let err: serde_json::Value =
+        serde_json::from_str(&cmd_edit_with_options(&file_path, &replace_line(original, 1, "fn main() {"), &json).unwrap_err()).unwrap();
+    assert_eq!(err["error"], "verify_failed");
+    assert_eq!(err["exit_code"], 3);
+    assert_eq!(err["rolled_back"][0], file_path.as_str());
+}
+
+#[test]
+fn test_multi_edit_rolls_back_every_file() {
+    let (dir, first) = setup("a\n");
+    let second = dir.path().join("second.txt");
+    fs::write(&second, "b\n").unwrap();
+    let second = second.to_str().unwrap().to_string();
+    let payload = serde_json::json!({
+        &first: serde_json::from_str::<serde_json::Value>(&replace_line("a\n", 1, "A")).unwrap(),
+        &second: serde_json::from_str::<serde_json::Value>(&replace_line("b\n", 1, "B")).unwrap(),
+    });
+    let err = cmd_multi_edit(&payload.to_string(), &verifying(&dir, "false".to_string())).unwrap_err();
+    assert!(err.contains("Verification failed: `false` exited with status 1"));
+    assert_eq!(fs::read_to_string(&first).unwrap(), "a\n");
+    assert_eq!(fs::read_to_string(&second).unwrap(), "b\n");
+}
+
+#[test]
+fn test_verify_output_keeps_only_the_last_lines() {
+    let failure = run_verify_command("seq 1 100; exit 1").unwrap_err();
+    assert_eq!(failure.exit_code, Some(1));
+    let lines: Vec<&str> = failure.output.lines().collect();
+    assert_eq!(lines.len(), VERIFY_OUTPUT_LINES + 1);
+    assert_eq!(lines[0], format!("({} earlier lines omitted)", 100 - VERIFY_OUTPUT_LINES));
+    assert_eq!(lines.last(), Some(&"100"));
+    assert!(run_verify_command("true").is_ok());
+}
+
+#[test]
+fn test_policy_can_forbid_the_verify_command() {
+    let (dir, file_path) = setup("fn main() {}\n");
+    let marker = dir.path().join("ran");
+    let options = EditOptions {
+        policy: Policy { forbid_verify_cmd: true, ..Policy::default() },
+        format: OutputFormat::Json,
+        ..verifying(&dir, format!("touch {}", marker.display()))
+    };
+    let err = cmd_edit_with_options(&file_path, &replace_line("fn main() {}\n", 1, "fn answer() {}"), &options).unwrap_err();
+    let json: serde_json::Value = serde_json::from_str(&err).unwrap();
+    assert_eq!(json["error"], "policy_violation", "{}", err);
+    assert!(json["message"].as_str().unwrap().contains("--verify-cmd"), "{}", err);
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "fn main() {}\n");
+    assert!(!marker.exists());
+
+    // Without a verify command the same policy lets the edit through
+    let options = EditOptions { verify_cmd: None, ..options };
+    cmd_edit_with_options(&file_path, &replace_line("fn main() {}\n", 1, "fn answer() {}"), &options).unwrap();
+}
@@ -0,0 +1,88 @@
+export function init(input?: RequestInfo | URL | Response | BufferSource | WebAssembly.Module): Promise<void>;
+
+export interface AnchoredLine {
+  line: number;
+  hash: string;
+  /** `LINE#HASH`, ready to use as an edit's `pos` or `end`. */
+  anchor: string;
+  /** The byte offset the line starts at, in UTF-8 bytes. */
+  byteOffset: number;
+  text: string;
+}
+
+export interface Edit {
+  op: "replace" | "append" | "prepend" | "delete" | "move";
+  pos?: string;
+  end?: string;
+  /** Where a "move" puts its lines: just below this line. */
+  after?: string;
+  /** Required by every op except "delete" and "move". */
+  lines?: string[];
+  expectedLines?: string[];
+}
+
+export interface ApplyOptions {
+  autoRebase?: boolean;
+  rebaseWindow?: number;
+  allowPartial?: boolean;
+  fixOffByOne?: boolean;
+  mismatchContext?: number;
+  conflictMarkers?: boolean;
+  force?: boolean;
+  dedupe?: boolean;
+  mergeOverlaps?: boolean;
+  sequential?: boolean;
+  fuzzyThreshold?: number;
+  fuzzyMaxCandidates?: number;
+  limits?: Limits;
+}
+
+/** Caps on one call; each is off when unset. */
+export interface Limits {
+  maxFileBytes?: number;
+  maxEdits?: number;
+  /** Lines carried by all edits together: `lines`, `expectedLines` and `values`. */
+  maxPayloadLines?: number;
+}
+
+export type EditStatus =
+  | { status: "valid" | "applied" }
+  | { status: "conflicted"; message: string }
+  | { status: "duplicate"; of: number }
+  | { status: "merged"; into: number }
+  | { status: "skipped"; reason: "mismatch" | "overlap" | "invalid"; message: string };
+
+export interface Relocation {
+  editIndex: number;
+  field: "pos" | "end" | "after";
+  from: string;
+  to: string;
+  method: { method: "hash" | "expected_lines" | "off_by_one" } | { method: "content"; similarity: number };
+}
+
+export interface BypassedCheck {
+  editIndex: number;
+  field: string;
+  message: string;
+}
+
+export interface ApplyResult {
+  content: string;
+  firstChangedLine: number | null;
+  relocations: Relocation[];
+  statuses: EditStatus[];
+  bypassed: BypassedCheck[];
+  /** The hash-aware diff, empty when nothing changed. */
+  diff: string;
+}
+
+export class HashlineError extends Error {
+  /** Machine-readable cause, e.g. "hash_mismatch" or "overlap". */
+  kind: string;
+  details: { error: string; message: string; [key: string]: unknown };
+}
+
+export function hashLines(content: string): AnchoredLine[];
+export function applyEdits(content: string, edits: Edit[], options?: ApplyOptions): ApplyResult;
+export function validateEdits(content: string, edits: Edit[], options?: ApplyOptions): EditStatus[];
+export function diff(oldContent: string, newContent: string, firstChangedLine?: number): string;
@@ -0,0 +1,69 @@
+// Browser wrapper around the wasm-bindgen exports in src/wasm.rs.
+//
+// The Rust side speaks the CLI's snake_case JSON; this wrapper accepts and
+// returns camelCase objects to match the Node.js bindings.
+
+import initWasm, * as core from "./pkg/hashline_tools.js";
+
+/** Load the WebAssembly module. Call once before anything else. */
+export async function init(input) {
+  await initWasm(input);
+}
+
+/** Thrown when an edit batch fails; `details` holds the full JSON error. */
+export class HashlineError extends Error {
+  constructor(details) {
+    super(details.message);
+    this.name = "HashlineError";
+    this.kind = details.error;
+    this.details = convertKeys(details, toCamel);
+  }
+}
+
+export function hashLines(content) {
+  return convertKeys(JSON.parse(core.hashLines(content)), toCamel);
+}
+
+export function applyEdits(content, edits, options) {
+  return call(core.applyEdits, content, edits, options);
+}
+
+export function validateEdits(content, edits, options) {
+  return call(core.validateEdits, content, edits, options);
+}
+
+export function diff(oldContent, newContent, firstChangedLine = 1) {
+  return core.diff(oldContent, newContent, firstChangedLine);
+}
+
+function call(fn, content, edits, options) {
+  const editsJson = JSON.stringify(convertKeys(edits, toSnake));
+  const optionsJson = options === undefined ? undefined : JSON.stringify(convertKeys(options, toSnake));
+  let result;
+  try {
+    result = fn(content, editsJson, optionsJson);
+  } catch (e) {
+    throw typeof e === "string" ? new HashlineError(JSON.parse(e)) : e;
+  }
+  return convertKeys(JSON.parse(result), toCamel);
+}
+
+function toCamel(key) {
+  return key.replace(/_([a-z])/g, (_, c) => c.toUpperCase());
+}
+
+function toSnake(key) {
+  return key.replace(/[A-Z]/g, (c) => "_" + c.toLowerCase());
+}
+
+function convertKeys(value, convert) {
+  if (Array.isArray(value)) {
+    return value.map((item) => convertKeys(item, convert));
+  }
+  if (value !== null && typeof value === "object") {
+    return Object.fromEntries(
+      Object.entries(value).map(([key, item]) => [convert(key), convertKeys(item, convert)])
+    );
+  }
+  return value;
+}